@@ -1,7 +1,16 @@
 use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 use colored::*;
+use core_lib::at_rest::{self, KeySource};
+use core_lib::audit;
+use core_lib::backup;
+use core_lib::event_relation;
+use core_lib::evidence;
+use core_lib::export::{self, ExportFormat, ImportMode};
+use core_lib::retention;
 use core_lib::storage;
+use core_lib::tombstone;
+use core_lib::trust_propagation::TrustParams;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -24,6 +33,9 @@ use truth_core::p2p::sync::SyncResult;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Разблокировать зашифрованную БД первым ключом из keystore (~/.truthctl/keys.json)
+    #[arg(long, global = true)]
+    db_keystore: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -108,6 +120,150 @@ enum Commands {
         /// Диагностика серверной части (HTTP/API, БД, P2P)
         #[arg(long)]
         server: bool,
+        /// Поиск аномалий доверия в локальной БД (сговор, скачки, циклы валидаций)
+        #[arg(long)]
+        alerts: bool,
+        /// Вместе с --alerts: показать и подтверждённые аномалии
+        #[arg(long)]
+        all: bool,
+    },
+    /// Офлайн-симуляция распространения доверия: базовые параметры против альтернативных
+    Simulate {
+        /// Путь к БД-снимку
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        /// Синтетическая сеть из N узлов вместо снимка БД
+        #[arg(long)]
+        synthetic: Option<usize>,
+        /// Доля нечестных узлов синтетической сети
+        #[arg(long, default_value_t = 0.2)]
+        dishonest: f32,
+        /// Seed генератора синтетической сети
+        #[arg(long, default_value_t = 42)]
+        seed: u64,
+        /// Число раундов обмена оценками
+        #[arg(long, default_value_t = 20)]
+        rounds: usize,
+        /// Пересчитывать рейтинги с нуля каждые N раундов
+        #[arg(long)]
+        recalc_every: Option<usize>,
+        /// JSON-файл с альтернативными параметрами (отсутствующие поля — по умолчанию)
+        #[arg(long)]
+        params: Option<PathBuf>,
+        /// Переопределить параметр: --set blend_remote_weight=0.3 (можно повторять)
+        #[arg(long = "set")]
+        set: Vec<String>,
+        /// Формат вывода: table | json
+        #[arg(long, default_value = "table")]
+        format: String,
+        /// Сколько узлов показать в таблицах
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+    /// Постраничный просмотр событий, утверждений или влияний локальной БД
+    List {
+        #[arg(value_enum)]
+        kind: ListKind,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        /// Контекст события (id)
+        #[arg(long)]
+        context: Option<i64>,
+        /// Публичный ключ автора
+        #[arg(long)]
+        author: Option<String>,
+        /// Флаг detected события
+        #[arg(long)]
+        detected: Option<bool>,
+        /// Не раньше: unix-секунды, YYYY-MM-DD или RFC3339
+        #[arg(long)]
+        since: Option<String>,
+        /// Не позже: unix-секунды, YYYY-MM-DD или RFC3339
+        #[arg(long)]
+        until: Option<String>,
+        /// Нижняя граница оценки (collective_score события, truth_score утверждения)
+        #[arg(long)]
+        min_score: Option<f64>,
+        /// Верхняя граница оценки
+        #[arg(long)]
+        max_score: Option<f64>,
+        /// Сортировка: id | time
+        #[arg(long, default_value = "id")]
+        sort: String,
+        /// По убыванию
+        #[arg(long)]
+        desc: bool,
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Курсор следующей страницы из предыдущего вывода
+        #[arg(long)]
+        cursor: Option<String>,
+        /// Формат вывода: table | json
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    /// Полнотекстовый поиск по событиям, утверждениям и обоснованиям оценок
+    Search {
+        /// Поисковый запрос; слово* — поиск по префиксу
+        query: String,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        /// Только события контекста (id)
+        #[arg(long)]
+        context: Option<i64>,
+        /// Не раньше: unix-секунды, YYYY-MM-DD или RFC3339
+        #[arg(long)]
+        since: Option<String>,
+        /// Не позже: unix-секунды, YYYY-MM-DD или RFC3339
+        #[arg(long)]
+        until: Option<String>,
+        /// Публичный ключ автора
+        #[arg(long)]
+        author: Option<String>,
+        /// Только распознанные (true) или нераспознанные (false) события
+        #[arg(long)]
+        detected: Option<bool>,
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Формат вывода: table | json
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    /// Удалить запись подписанной отметкой (событие — вместе с утверждениями и влияниями);
+    /// отметка уходит пирам при синхронизации и не даёт записи вернуться
+    Delete {
+        #[arg(value_enum)]
+        kind: ListKind,
+        /// id записи: у событий и утверждений — локальный id или uid
+        id: String,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        /// Причина (подписывается вместе с отметкой)
+        #[arg(long)]
+        reason: Option<String>,
+    },
+    /// Полная выгрузка локальной БД в файл (все таблицы, подписи сохраняются)
+    Export {
+        /// Файл выгрузки; .ndjson/.jsonl — построчный NDJSON
+        file: PathBuf,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        /// Формат: json | ndjson (по умолчанию — по расширению файла)
+        #[arg(long)]
+        format: Option<String>,
+    },
+    /// Загрузка выгрузки в локальную БД одной транзакцией
+    Import {
+        /// Файл выгрузки (новый формат или старый ExportData)
+        file: PathBuf,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        /// Формат: json | ndjson (по умолчанию — по расширению файла)
+        #[arg(long)]
+        format: Option<String>,
+        /// Существующие записи: fail | skip | overwrite | merge
+        #[arg(long, default_value = "fail")]
+        mode: String,
     },
     /// Сброс локальных данных узла (БД, журналы, peers)
     ResetData {
@@ -139,11 +295,26 @@ enum Commands {
     Trust { #[command(subcommand)] cmd: TrustCmd },
     /// Визуализация графа сети
     Graph { #[command(subcommand)] cmd: GraphCmd },
+    /// Схема БД: версии и миграции
+    Db { #[command(subcommand)] cmd: DbCmd },
+    /// Резервные копии БД: создание, проверка, восстановление
+    Backup { #[command(subcommand)] cmd: BackupCmd },
+    /// База знаний: контексты, причины, проявления, следствия, формы, категории, типы воздействий
+    Kb { #[command(subcommand)] cmd: KbCmd },
+    /// Журнал аудита изменений: проверка цепочки, просмотр, подпись
+    Audit { #[command(subcommand)] cmd: AuditCmd },
+    /// Доказательства к событиям и утверждениям: файлы и ссылки
+    Evidence { #[command(subcommand)] cmd: EvidenceCmd },
+    /// Связи между событиями: дубликаты, противоречия, поддержка, замена
+    Relation { #[command(subcommand)] cmd: RelationCmd },
 }
 
 #[derive(Copy, Clone, Debug, ValueEnum)]
 enum Mode { Full, Incremental, Push, Pull }
 
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum ListKind { Events, Statements, Impacts }
+
 #[derive(serde::Deserialize)]
 #[allow(dead_code)] // может быть неиспользован без фичи p2p-client-sync
 struct KeyFile { private_key: String, public_key: String }
@@ -195,6 +366,39 @@ enum LogsCmd {
     Show { #[arg(long, default_value_t = 50)] limit: usize, #[arg(long, default_value = "truth.db")] db: PathBuf },
     /// Очистить журнал синхронизации
     Clear { #[arg(long, default_value = "truth.db")] db: PathBuf },
+    /// Удалить устаревшие записи журналов и истории по политикам хранения
+    Prune {
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        /// Только показать, сколько записей будет удалено
+        #[arg(long)]
+        dry_run: bool,
+        /// Сохранить удаляемые строки в DIR/prune-<время>.ndjson.gz
+        #[arg(long)]
+        archive_dir: Option<PathBuf>,
+        /// Очистить только эту таблицу
+        #[arg(long)]
+        table: Option<String>,
+    },
+    /// Показать или изменить политики хранения (0 — без ограничения)
+    Retention {
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        /// Таблица, политику которой изменить
+        table: Option<String>,
+        /// Удалять записи старше N дней
+        #[arg(long)]
+        max_age_days: Option<u32>,
+        /// Хранить не больше N записей
+        #[arg(long)]
+        max_rows: Option<u64>,
+        /// Записи старше N дней прореживать до одной в сутки
+        #[arg(long)]
+        downsample_after_days: Option<u32>,
+        /// Вернуть политику по умолчанию
+        #[arg(long)]
+        reset: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -209,8 +413,336 @@ enum UsersCmd {
 
 #[derive(Subcommand, Debug)]
 enum TrustCmd {
-    /// Делегировать доверие цели (role >= node)
-    Delegate { #[arg(long, default_value = "http://127.0.0.1:8080")] server: String, target_pubkey: String, delta: f32 },
+    /// Делегировать доверие цели (role >= node); запись подписывается локальным ключом
+    Delegate {
+        #[arg(long, default_value = "http://127.0.0.1:8080")] server: String,
+        target_pubkey: String,
+        delta: f32,
+        /// Срок действия в секундах (без флага — бессрочно до отзыва)
+        #[arg(long)] expires_in: Option<i64>,
+    },
+    /// Список делегирований (по умолчанию только действующие)
+    List {
+        #[arg(long, default_value = "http://127.0.0.1:8080")] server: String,
+        /// Включить отозванные и истёкшие
+        #[arg(long)] all: bool,
+        /// Фильтр по получателю
+        #[arg(long)] target: Option<String>,
+    },
+    /// Отозвать собственное делегирование по id
+    Revoke {
+        #[arg(long, default_value = "http://127.0.0.1:8080")] server: String,
+        id: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum DbCmd {
+    /// Применить недостающие миграции или откатить схему до --to
+    Migrate {
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        /// Целевая версия схемы (по умолчанию — последняя)
+        #[arg(long)]
+        to: Option<u32>,
+        /// Показать план, выполнив его в откатываемой транзакции
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Текущая версия схемы, состояние миграций и шифрования
+    Status { #[arg(long, default_value = "truth.db")] db: PathBuf },
+    /// Включить шифрование чувствительных колонок (подписи, обоснования оценок)
+    Encrypt {
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        /// Обернуть ключ БД ключом узла из keystore вместо парольной фразы
+        #[arg(long)]
+        keystore: bool,
+        /// Файл с парольной фразой (иначе TRUTH_DB_PASSPHRASE или запрос в терминале)
+        #[arg(long)]
+        passphrase_file: Option<PathBuf>,
+    },
+    /// Сменить парольную фразу/ключ: перешифровывается только ключ БД
+    Rekey {
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        /// Новый секрет — ключ узла из keystore
+        #[arg(long)]
+        keystore: bool,
+        /// Файл с новой парольной фразой (иначе TRUTH_DB_NEW_PASSPHRASE или запрос в терминале)
+        #[arg(long)]
+        new_passphrase_file: Option<PathBuf>,
+    },
+    /// Проверить секрет локально или разблокировать запущенный сервер (--server)
+    Unlock {
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        /// URL сервера, стартовавшего с заблокированной БД
+        #[arg(long)]
+        server: Option<String>,
+        #[arg(long)]
+        passphrase_file: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum BackupCmd {
+    /// Онлайн-копия БД с манифестом (хеш, подпись первым ключом keystore)
+    Create {
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        #[arg(long, default_value = "backups")]
+        dir: PathBuf,
+        /// Оставить только N последних копий
+        #[arg(long)]
+        keep: Option<usize>,
+    },
+    /// Копии в каталоге
+    List { #[arg(long, default_value = "backups")] dir: PathBuf },
+    /// Проверить хеш, подпись ключом узла из keystore и целостность копии
+    Verify {
+        file: PathBuf,
+        /// Принять копию без подписи
+        #[arg(long)]
+        allow_unsigned: bool,
+    },
+    /// Заменить БД проверенной копией (текущая БД сохраняется снимком в --dir)
+    Restore {
+        file: PathBuf,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        #[arg(long, default_value = "backups")]
+        dir: PathBuf,
+        /// Принять копию без подписи
+        #[arg(long)]
+        allow_unsigned: bool,
+        /// Не спрашивать подтверждение
+        #[arg(long)]
+        confirm: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum AuditCmd {
+    /// Проверить цепочку хешей и подписи отметок; ненулевой код выхода при нарушениях
+    Verify {
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+    },
+    /// Последние записи журнала
+    List {
+        /// Таблица (truth_events, statements, users, ...)
+        #[arg(long)]
+        table: Option<String>,
+        /// Ключ записи (uid, id, публичный ключ)
+        #[arg(long)]
+        record: Option<String>,
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+    },
+    /// Подписать голову журнала первым ключом keystore
+    Sign {
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum RelationCmd {
+    /// Связать события: SOURCE RELATION TARGET; связь подписывается первым ключом keystore
+    Add {
+        /// Событие-источник: локальный id или uid
+        source: String,
+        #[arg(value_parser = clap::builder::PossibleValuesParser::new(core_lib::models::EventRelation::RELATIONS.iter().copied()))]
+        relation: String,
+        /// Целевое событие: локальный id или uid
+        target: String,
+        /// Пояснение (подписывается вместе со связью)
+        #[arg(long)]
+        note: Option<String>,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+    },
+    /// Связи события и его каноническое событие; без события — все связи
+    List {
+        /// id события: локальный id или uid
+        event: Option<String>,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+    },
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum EvidenceTarget { Event, Statement }
+
+#[derive(Subcommand, Debug)]
+enum EvidenceCmd {
+    /// Прикрепить файл или ссылку; доказательство подписывается первым ключом keystore
+    Add {
+        #[arg(value_enum)]
+        target: EvidenceTarget,
+        /// id записи: локальный id или uid
+        id: String,
+        /// Файл: содержимое копируется в хранилище блобов
+        #[arg(long, conflicts_with = "url", required_unless_present = "url")]
+        file: Option<PathBuf>,
+        /// Ссылка http(s)
+        #[arg(long)]
+        url: Option<String>,
+        /// Пояснение (подписывается вместе с доказательством)
+        #[arg(long)]
+        note: Option<String>,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        /// Каталог хранилища блобов
+        #[arg(long, default_value = "blobs")]
+        blobs: PathBuf,
+    },
+    /// Доказательства события вместе с его утверждениями; без события — все
+    List {
+        /// id события: локальный id или uid
+        event: Option<String>,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        #[arg(long, default_value = "blobs")]
+        blobs: PathBuf,
+    },
+    /// Проверить подписи и целостность блобов; ненулевой код выхода при нарушениях
+    Verify {
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        #[arg(long, default_value = "blobs")]
+        blobs: PathBuf,
+    },
+    /// Забрать у пира недостающие блобы (хеш проверяется)
+    Fetch {
+        /// URL пира, например http://127.0.0.1:8080
+        #[arg(long)]
+        peer: String,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+        #[arg(long, default_value = "blobs")]
+        blobs: PathBuf,
+    },
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum KbKind { Category, Cause, Develop, Effect, Forma, Context, ImpactType }
+
+impl KbKind {
+    fn table(self) -> &'static str {
+        match self {
+            KbKind::Category => "category",
+            KbKind::Cause => "cause",
+            KbKind::Develop => "develop",
+            KbKind::Effect => "effect",
+            KbKind::Forma => "forma",
+            KbKind::Context => "context",
+            KbKind::ImpactType => "impact_type",
+        }
+    }
+}
+
+/// Правки подписываются первым ключом keystore; его владелец должен иметь роль admin в локальной БД
+#[derive(Subcommand, Debug)]
+enum KbCmd {
+    /// Записи вида с числом ссылок на них (событий — для контекстов)
+    List {
+        #[arg(value_enum)]
+        kind: KbKind,
+        /// Показать и устаревшие
+        #[arg(long)]
+        all: bool,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+    },
+    /// Добавить запись
+    Add {
+        #[arg(value_enum)]
+        kind: KbKind,
+        name: String,
+        #[arg(long)]
+        description: Option<String>,
+        /// true — положительная (обязательно для cause, develop, effect, forma)
+        #[arg(long)]
+        quality: Option<bool>,
+        /// Ссылки контекста
+        #[arg(long)]
+        category: Option<i64>,
+        #[arg(long)]
+        forma: Option<i64>,
+        #[arg(long)]
+        cause: Option<i64>,
+        #[arg(long)]
+        develop: Option<i64>,
+        #[arg(long)]
+        effect: Option<i64>,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+    },
+    /// Изменить запись (незаданные поля остаются прежними)
+    Edit {
+        #[arg(value_enum)]
+        kind: KbKind,
+        id: i64,
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        description: Option<String>,
+        #[arg(long)]
+        quality: Option<bool>,
+        #[arg(long)]
+        category: Option<i64>,
+        #[arg(long)]
+        forma: Option<i64>,
+        #[arg(long)]
+        cause: Option<i64>,
+        #[arg(long)]
+        develop: Option<i64>,
+        #[arg(long)]
+        effect: Option<i64>,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+    },
+    /// Пометить запись устаревшей: новые события и контексты не смогут на неё ссылаться
+    Deprecate {
+        #[arg(value_enum)]
+        kind: KbKind,
+        id: i64,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+    },
+    /// Загрузить пакет (.toml или .json): новые и изменённые записи — подписанные правки, вопросы — локально
+    Import {
+        file: PathBuf,
+        /// Локаль текстов; по умолчанию default_locale пакета
+        #[arg(long)]
+        locale: Option<String>,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+    },
+    /// Выгрузить базу знаний пакетом; формат — по расширению файла
+    Export {
+        file: PathBuf,
+        /// Локаль, которой помечаются тексты
+        #[arg(long, default_value = "en")]
+        locale: String,
+        /// Имя пакета
+        #[arg(long, default_value = "local")]
+        name: String,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+    },
+    /// Показать, что изменит импорт пакета
+    Diff {
+        file: PathBuf,
+        #[arg(long)]
+        locale: Option<String>,
+        #[arg(long, default_value = "truth.db")]
+        db: PathBuf,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -229,13 +761,26 @@ enum GraphCmd {
         /// Формат вывода: json или ascii
         #[arg(long, default_value = "json")]
         format: String,
+        /// Показать только узлы указанной группы (сообщества)
+        #[arg(long)]
+        group: Option<String>,
     },
 }
 
 #[derive(Subcommand, Debug)]
 enum RatingsCmd {
     /// Показать доверие: локальный уровень, средняя сеть, дельты
-    Trust { #[arg(long)] verbose: bool },
+    Trust {
+        #[arg(long)] verbose: bool,
+        /// Показать историю trust_score и стабильность по узлам
+        #[arg(long)] history: bool,
+        /// Ограничить историю одним узлом
+        #[arg(long)] node: Option<String>,
+        /// Сколько последних точек истории выводить
+        #[arg(long, default_value_t = 10)] limit: usize,
+        /// Окно (число точек) для расчёта стабильности
+        #[arg(long, default_value_t = core_lib::trust_propagation::TRUST_STABILITY_WINDOW)] window: usize,
+    },
 }
 
 // duplicated enums removed
@@ -243,6 +788,9 @@ enum RatingsCmd {
 #[tokio::main(flavor = "multi_thread")] 
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    if cli.db_keystore {
+        core_lib::at_rest::provide_key(keystore_db_key()?);
+    }
     match cli.command {
         Commands::Sync { peer, identity, db, mode } => {
             let peer = peer.unwrap_or_else(|| {
@@ -276,7 +824,10 @@ async fn main() -> anyhow::Result<()> {
         Commands::Logs { cmd } => {
             run_logs(cmd).await
         }
-        Commands::Diagnose { verbose, server } => {
+        Commands::Diagnose { verbose, server, alerts, all } => {
+            if alerts {
+                return run_alerts_diagnose(all);
+            }
             if server {
                 let cfg = load_config().unwrap_or_else(|_| default_config());
                 let base_url = format!("http://127.0.0.1:{}", cfg.port);
@@ -312,13 +863,56 @@ async fn main() -> anyhow::Result<()> {
             }
             Ok(())
         }
+        Commands::Simulate { db, synthetic, dishonest, seed, rounds, recalc_every, params, set, format, top } => {
+            run_simulate(SimulateArgs { db, synthetic, dishonest, seed, rounds, recalc_every, params, set, format, top })
+        }
+        Commands::List { kind, db, context, author, detected, since, until, min_score, max_score, sort, desc, limit, cursor, format } => {
+            let sort = match sort.as_str() {
+                "id" => storage::SortField::Id,
+                "time" => storage::SortField::Time,
+                other => anyhow::bail!("unknown sort field '{}' (id | time)", other),
+            };
+            let q = storage::RecordQuery {
+                context_id: context,
+                author,
+                detected,
+                since: since.as_deref().map(parse_time_arg).transpose()?,
+                until: until.as_deref().map(parse_time_arg).transpose()?,
+                min_score,
+                max_score,
+                sort,
+                descending: desc,
+                limit,
+                cursor,
+            };
+            run_list(kind, &db, &q, &format)
+        }
+        Commands::Search { query, db, context, since, until, author, detected, limit, format } => {
+            let filters = storage::SearchFilters {
+                context_id: context,
+                since: since.as_deref().map(parse_time_arg).transpose()?,
+                until: until.as_deref().map(parse_time_arg).transpose()?,
+                author,
+                detected,
+            };
+            run_search(&db, &query, &filters, limit, &format)
+        }
+        Commands::Delete { kind, id, db, reason } => { as_keystore_actor(|| run_delete(kind, &id, &db, reason)) }
+        Commands::Export { file, db, format } => { run_export(&file, &db, format.as_deref()) }
+        Commands::Import { file, db, format, mode } => { as_keystore_actor(|| run_import(&file, &db, format.as_deref(), &mode)) }
         Commands::ResetData { confirm, reinit } => {
-            reset_local_data(confirm, reinit)?;
+            as_keystore_actor(|| reset_local_data(confirm, reinit))?;
             Ok(())
         }
         Commands::Users { cmd } => { run_users(cmd).await }
         Commands::Trust { cmd } => { run_trust(cmd).await }
         Commands::Graph { cmd } => { run_graph(cmd).await }
+        Commands::Db { cmd } => { run_db(cmd, cli.db_keystore).await }
+        Commands::Backup { cmd } => { run_backup(cmd) }
+        Commands::Kb { cmd } => { as_keystore_actor(|| run_kb(cmd)) }
+        Commands::Audit { cmd } => { run_audit(cmd) }
+        Commands::Evidence { cmd } => { run_evidence(cmd).await }
+        Commands::Relation { cmd } => { run_relation(cmd) }
         Commands::Config { cmd } => {
             run_config(cmd).await
         }
@@ -332,6 +926,400 @@ async fn main() -> anyhow::Result<()> {
     }
 }
 
+/// Прогнать детекторы аномалий по локальной БД и вывести найденное
+fn run_alerts_diagnose(all: bool) -> anyhow::Result<()> {
+    let cfg = load_config().unwrap_or_else(|_| default_config());
+    let conn = storage::open_db(&cfg.db_path)?;
+    let added = storage::detect_anomalies(&conn, chrono::Utc::now().timestamp())?;
+    let alerts = storage::load_alerts(&conn, all, None)?;
+    println!("{}", format!("Trust anomalies: {} ({} new)", alerts.len(), added.len()).blue());
+    if alerts.is_empty() {
+        println!("{}", "✅ No anomalies detected".green());
+        return Ok(());
+    }
+    for a in &alerts {
+        let marker = if a.acknowledged_at.is_some() { "✔".normal() } else if a.severity > 0.7 { "❌".red() } else { "⚠".yellow() };
+        let ts = chrono::DateTime::<chrono::Utc>::from(std::time::UNIX_EPOCH + std::time::Duration::from_secs(a.detected_at as u64)).to_rfc3339();
+        println!("{} [{}] {} severity {:.2} — {}", marker, a.kind, a.subject.get(0..16).unwrap_or(&a.subject), a.severity, a.details);
+        if !a.related.is_empty() {
+            let related: Vec<&str> = a.related.iter().map(|r| r.get(0..8).unwrap_or(r)).collect();
+            println!("    related: {}", related.join(", "));
+        }
+        println!("    id: {} detected: {}", a.id, ts);
+    }
+    Ok(())
+}
+
+/// Время из аргумента: unix-секунды, YYYY-MM-DD (начало суток UTC) или RFC3339
+fn parse_time_arg(s: &str) -> anyhow::Result<i64> {
+    if let Ok(ts) = s.parse::<i64>() {
+        return Ok(ts);
+    }
+    if let Ok(d) = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(d.and_hms_opt(0, 0, 0).map(|dt| dt.and_utc().timestamp()).unwrap_or(0));
+    }
+    chrono::DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.timestamp())
+        .map_err(|_| anyhow::anyhow!("cannot parse time '{}'", s))
+}
+
+fn run_list(kind: ListKind, db: &std::path::Path, q: &storage::RecordQuery, format: &str) -> anyhow::Result<()> {
+    let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+    let fmt_ts = |ts: i64| chrono::DateTime::<chrono::Utc>::from(std::time::UNIX_EPOCH + std::time::Duration::from_secs(ts.max(0) as u64)).to_rfc3339();
+    let short = |pk: &Option<String>| pk.as_deref().map(|k| k.get(0..8).unwrap_or(k).to_string()).unwrap_or_else(|| "-".into());
+    let next_cursor = match kind {
+        ListKind::Events => {
+            let page = storage::query_truth_events(&conn, q)?;
+            if format == "json" {
+                println!("{}", serde_json::to_string_pretty(&page)?);
+                return Ok(());
+            }
+            for e in &page.items {
+                let detected = match e.detected { Some(true) => "✅", Some(false) => "❌", None => "·" };
+                let score = e.collective_score.map(|s| format!("{:.2}", s)).unwrap_or_else(|| "-".into());
+                println!("#{} {} ctx {} {} score {} by {} — {}", e.id, fmt_ts(e.timestamp_start), e.context_id, detected, score, short(&e.public_key), e.description);
+            }
+            page.next_cursor
+        }
+        ListKind::Statements => {
+            let page = storage::query_statements(&conn, q)?;
+            if format == "json" {
+                println!("{}", serde_json::to_string_pretty(&page)?);
+                return Ok(());
+            }
+            for s in &page.items {
+                let score = s.truth_score.map(|v| format!("{:.2}", v)).unwrap_or_else(|| "-".into());
+                println!("#{} {} event {} score {} by {} — {}", s.id, fmt_ts(s.created_at), s.event_id, score, short(&s.public_key), s.text);
+            }
+            page.next_cursor
+        }
+        ListKind::Impacts => {
+            let page = storage::query_impacts(&conn, q)?;
+            if format == "json" {
+                println!("{}", serde_json::to_string_pretty(&page)?);
+                return Ok(());
+            }
+            for i in &page.items {
+                let grade = format!("{:+.2}", i.grade());
+                let grade = if i.grade() >= 0.0 { grade.green() } else { grade.red() };
+                let confidence = i.confidence.map(|c| format!(" (confidence {:.2})", c)).unwrap_or_default();
+                println!("{} {} event {} type {} {}{} by {}", i.id, fmt_ts(i.created_at), i.event_id, i.type_id, grade, confidence, short(&i.public_key));
+            }
+            page.next_cursor
+        }
+    };
+    match next_cursor {
+        Some(c) => println!("{}", format!("more: --cursor {}", c).blue()),
+        None => println!("{}", "(end)".normal()),
+    }
+    Ok(())
+}
+
+fn run_search(db: &std::path::Path, query: &str, filters: &storage::SearchFilters, limit: usize, format: &str) -> anyhow::Result<()> {
+    let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+    let hits = storage::search(&conn, query, filters, limit)?;
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&hits)?);
+        return Ok(());
+    }
+    if hits.is_empty() {
+        println!("{}", "No matches".yellow());
+        return Ok(());
+    }
+    println!("{}", format!("Matches for '{}': {}", query, hits.len()).blue());
+    for h in &hits {
+        let ts = chrono::DateTime::<chrono::Utc>::from(std::time::UNIX_EPOCH + std::time::Duration::from_secs(h.timestamp.max(0) as u64)).to_rfc3339();
+        let kind = match h.kind.as_str() { "event" => "event".green(), "statement" => "statement".cyan(), _ => "judgment".magenta() };
+        println!("{:>6.2} {} #{} {}", h.rank, kind, h.id, ts);
+        println!("       {}", h.snippet);
+    }
+    Ok(())
+}
+
+fn export_format(file: &std::path::Path, format: Option<&str>) -> anyhow::Result<ExportFormat> {
+    Ok(match format {
+        Some(f) => f.parse()?,
+        None => ExportFormat::from_path(&file.to_string_lossy()),
+    })
+}
+
+fn run_delete(kind: ListKind, id: &str, db: &std::path::Path, reason: Option<String>) -> anyhow::Result<()> {
+    let key = keystore_signing_key().ok_or_else(|| anyhow::anyhow!("No signing key in keystore; run `truthctl keys generate --save`"))?;
+    let table = match kind {
+        ListKind::Events => "truth_events",
+        ListKind::Statements => "statements",
+        ListKind::Impacts => "impact",
+    };
+    let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+    // Отметка ссылается на uid: локальный id у пиров указывает на другие записи
+    let record_id = record_uid(&conn, table, id)?;
+    let id = record_id.as_str();
+    let author = hex::encode(key.verifying_key().as_bytes());
+    let mut t = core_lib::models::Tombstone::new(table, id, &author, reason, chrono::Utc::now().timestamp());
+    tombstone::sign(&mut t, &key);
+    match tombstone::apply(&conn, &t)? {
+        tombstone::Applied::Deleted(rows) => {
+            println!("{}", format!("✅ Deleted {} {} ({} rows), tombstone {}", table, id, rows, t.id).green());
+        }
+        tombstone::Applied::Pending => {
+            println!("{}", format!("{} {} not found locally; tombstone {} will suppress it on sync", table, id, t.id).yellow());
+        }
+        tombstone::Applied::Known => println!("{}", "Tombstone already recorded".yellow()),
+        tombstone::Applied::Forbidden => {
+            anyhow::bail!("Only the author of {} {} or an admin may delete it", table, id)
+        }
+    }
+    Ok(())
+}
+
+fn run_kb(cmd: KbCmd) -> anyhow::Result<()> {
+    use core_lib::knowledge::pack::{self, Pack, PackFormat};
+    use core_lib::knowledge::{self, Applied};
+    use core_lib::models::KbEntry;
+    let now = chrono::Utc::now().timestamp();
+    let signing_key = || keystore_signing_key().ok_or_else(|| anyhow::anyhow!("No signing key in keystore; run `truthctl keys generate --save`"));
+    let (conn, mut entry) = match cmd {
+        KbCmd::List { kind, all, db } => {
+            let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+            for e in storage::load_kb_entries(&conn, kind.table(), all)? {
+                let quality = match e.quality { Some(true) => "+", Some(false) => "-", None => " " };
+                let refs: Vec<String> = [("category", e.category_id), ("forma", e.forma_id), ("cause", e.cause_id), ("develop", e.develop_id), ("effect", e.effect_id)]
+                    .iter()
+                    .filter_map(|(k, id)| id.map(|id| format!("{}={}", k, id)))
+                    .collect();
+                let used = storage::kb_usage(&conn, kind.table(), e.id)?;
+                let line = format!("#{:<14} {} {:<28} used {:<4} {}", e.id, quality, e.name, used, refs.join(" "));
+                if e.deprecated_at.is_some() { println!("{} {}", line.dimmed(), "(deprecated)".yellow()); } else { println!("{}", line); }
+            }
+            return Ok(());
+        }
+        KbCmd::Import { file, locale, db } => {
+            let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+            let pack = Pack::load(&file)?;
+            let locale = locale.unwrap_or_else(|| pack.pack.default_locale.clone());
+            let r = pack::import(&conn, &pack, &locale, &signing_key()?, now)?;
+            println!(
+                "{}",
+                format!(
+                    "✅ Imported pack {} v{} ({}): {} added, {} updated, {} unchanged, {} questions",
+                    pack.pack.name, pack.pack.version, locale, r.created, r.updated, r.unchanged, r.questions
+                )
+                .green()
+            );
+            return Ok(());
+        }
+        KbCmd::Export { file, locale, name, db } => {
+            let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+            let pack = pack::export(&conn, &name, &locale)?;
+            std::fs::write(&file, pack.to_string(PackFormat::from_path(&file)?)?)?;
+            let entries: usize = KbEntry::KINDS.iter().map(|k| pack.entries_of(k).len()).sum();
+            println!("{}", format!("✅ Exported {} entries and {} questions to {}", entries, pack.question.len(), file.display()).green());
+            return Ok(());
+        }
+        KbCmd::Diff { file, locale, db } => {
+            let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+            let pack = Pack::load(&file)?;
+            let locale = locale.unwrap_or_else(|| pack.pack.default_locale.clone());
+            let d = pack::diff(&conn, &pack, &locale)?;
+            for e in &d.added {
+                println!("{}", format!("+ {} #{}: {}", e.kind, e.id, e.name).green());
+            }
+            for (local, e) in &d.changed {
+                let mut line = format!("~ {} #{}: {}", e.kind, e.id, local.name);
+                if local.name != e.name {
+                    line.push_str(&format!(" → {}", e.name));
+                }
+                if e.deprecated_at.is_some() && local.deprecated_at.is_none() {
+                    line.push_str(" (deprecate)");
+                }
+                println!("{}", line.yellow());
+            }
+            for q in &d.questions {
+                println!("{}", format!("? question {}", q).yellow());
+            }
+            println!("{} added, {} changed, {} unchanged, {} questions", d.added.len(), d.changed.len(), d.unchanged, d.questions.len());
+            return Ok(());
+        }
+        KbCmd::Add { kind, name, description, quality, category, forma, cause, develop, effect, db } => {
+            let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+            let author = hex::encode(signing_key()?.verifying_key().as_bytes());
+            let entry = KbEntry {
+                kind: kind.table().to_string(),
+                id: KbEntry::new_id(kind.table(), &name, &author, now),
+                name,
+                description,
+                quality,
+                category_id: category,
+                forma_id: forma,
+                cause_id: cause,
+                develop_id: develop,
+                effect_id: effect,
+                updated_at: now,
+                deprecated_at: None,
+                updated_by: None,
+                signature: None,
+            };
+            (conn, entry)
+        }
+        KbCmd::Edit { kind, id, name, description, quality, category, forma, cause, develop, effect, db } => {
+            let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+            let mut entry = storage::get_kb_entry(&conn, kind.table(), id)?
+                .ok_or_else(|| anyhow::anyhow!("{} {} not found", kind.table(), id))?;
+            entry.name = name.unwrap_or(entry.name);
+            entry.description = description.or(entry.description);
+            entry.quality = quality.or(entry.quality);
+            entry.category_id = category.or(entry.category_id);
+            entry.forma_id = forma.or(entry.forma_id);
+            entry.cause_id = cause.or(entry.cause_id);
+            entry.develop_id = develop.or(entry.develop_id);
+            entry.effect_id = effect.or(entry.effect_id);
+            entry.updated_at = knowledge::next_revision(&entry, now);
+            (conn, entry)
+        }
+        KbCmd::Deprecate { kind, id, db } => {
+            let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+            let mut entry = storage::get_kb_entry(&conn, kind.table(), id)?
+                .ok_or_else(|| anyhow::anyhow!("{} {} not found", kind.table(), id))?;
+            entry.updated_at = knowledge::next_revision(&entry, now);
+            entry.deprecated_at = Some(entry.updated_at);
+            (conn, entry)
+        }
+    };
+    knowledge::sign(&mut entry, &signing_key()?);
+    match knowledge::apply(&conn, &entry)? {
+        Applied::Created => println!("{}", format!("✅ Added {} #{}: {}", entry.kind, entry.id, entry.name).green()),
+        Applied::Updated if entry.deprecated_at.is_some() => println!("{}", format!("✅ Deprecated {} #{}", entry.kind, entry.id).green()),
+        Applied::Updated => println!("{}", format!("✅ Updated {} #{}", entry.kind, entry.id).green()),
+        Applied::Stale => anyhow::bail!("{} #{} has a newer revision", entry.kind, entry.id),
+        Applied::Forbidden => anyhow::bail!("Only an admin may edit the knowledge base"),
+    }
+    Ok(())
+}
+
+fn run_export(file: &std::path::Path, db: &std::path::Path, format: Option<&str>) -> anyhow::Result<()> {
+    let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+    let report = export::export_to_file(&conn, &file.to_string_lossy(), export_format(file, format)?)?;
+    for t in report.tables.iter().filter(|t| t.rows > 0) {
+        println!("{:<20} {:>8}", t.table, t.rows);
+    }
+    let total: usize = report.tables.iter().map(|t| t.rows).sum();
+    println!("{}", format!("✅ Exported {} rows (schema v{}) to {}", total, report.header.schema_version, file.display()).green());
+    Ok(())
+}
+
+fn run_import(file: &std::path::Path, db: &std::path::Path, format: Option<&str>, mode: &str) -> anyhow::Result<()> {
+    let mode_name = mode;
+    let mode: ImportMode = mode_name.parse()?;
+    let mut conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+    let report = export::import_from_file(&mut conn, &file.to_string_lossy(), export_format(file, format)?, mode)?;
+    if report.header.is_none() {
+        println!("{}", "Legacy export (truth_events, impacts, metrics only)".yellow());
+    }
+    println!("{:<20} {:>8} {:>8} {:>8}", "table", "inserted", "updated", "skipped");
+    for t in &report.tables {
+        println!("{:<20} {:>8} {:>8} {:>8}", t.table, t.inserted, t.updated, t.skipped);
+    }
+    println!("{}", format!("✅ Imported {} ({} mode)", file.display(), mode_name).green());
+    Ok(())
+}
+
+struct SimulateArgs {
+    db: PathBuf,
+    synthetic: Option<usize>,
+    dishonest: f32,
+    seed: u64,
+    rounds: usize,
+    recalc_every: Option<usize>,
+    params: Option<PathBuf>,
+    set: Vec<String>,
+    format: String,
+    top: usize,
+}
+
+/// Альтернативные параметры: файл JSON, затем переопределения key=value
+fn load_sim_params(path: Option<&PathBuf>, overrides: &[String]) -> anyhow::Result<TrustParams> {
+    let mut value = match path {
+        Some(p) => serde_json::from_str::<serde_json::Value>(&fs::read_to_string(p)?)?,
+        None => serde_json::to_value(TrustParams::default())?,
+    };
+    let known = serde_json::to_value(TrustParams::default())?;
+    for kv in overrides {
+        let (key, raw) = kv.split_once('=').ok_or_else(|| anyhow::anyhow!("expected key=value, got '{}'", kv))?;
+        let key = key.trim();
+        if known.get(key).is_none() {
+            anyhow::bail!("unknown parameter '{}'", key);
+        }
+        let num: f64 = raw.trim().parse().map_err(|_| anyhow::anyhow!("'{}' is not a number", raw))?;
+        let obj = value.as_object_mut().ok_or_else(|| anyhow::anyhow!("params must be a JSON object"))?;
+        obj.insert(key.to_string(), serde_json::json!(num));
+    }
+    let params: TrustParams = serde_json::from_value(value)?;
+    params.validate()?;
+    Ok(params)
+}
+
+fn run_simulate(args: SimulateArgs) -> anyhow::Result<()> {
+    use core_lib::simulator::{compare_ranks, simulate, SimConfig, SimNetwork, SimReport, SyntheticSpec};
+
+    let network = match args.synthetic {
+        Some(nodes) => SimNetwork::synthetic(&SyntheticSpec { nodes, dishonest_share: args.dishonest, seed: args.seed, ..Default::default() }),
+        None => {
+            if !args.db.exists() {
+                anyhow::bail!("Database not found: {}", args.db.display());
+            }
+            let conn = storage::open_db(args.db.to_str().unwrap_or("truth.db"))?;
+            SimNetwork::from_db(&conn)?
+        }
+    };
+    let alt_params = load_sim_params(args.params.as_ref(), &args.set)?;
+    let config = SimConfig { rounds: args.rounds, recalc_every: args.recalc_every, ..Default::default() };
+    let baseline = simulate(&network, &TrustParams::default(), &config)?;
+    let alternative = simulate(&network, &alt_params, &config)?;
+    let changes = compare_ranks(&baseline, &alternative);
+
+    if args.format == "json" {
+        let out = serde_json::json!({ "baseline": baseline, "alternative": alternative, "rank_changes": changes });
+        println!("{}", serde_json::to_string_pretty(&out)?);
+        return Ok(());
+    }
+
+    println!("{}", format!("Simulation: {} nodes, {} peers, {} rounds", network.nodes().len(), network.peers().len(), args.rounds).blue());
+    let summary = |label: &str, r: &SimReport| {
+        let converged = r.converged_at.map(|n| format!("round {}", n)).unwrap_or_else(|| "not converged".to_string());
+        let last = r.rounds.last();
+        println!(
+            "  {:<12} {} | last Δ {:.5} | disagreement {:.3} | trust mean {:.3} σ {:.3} [{:.2}..{:.2}]",
+            label,
+            converged,
+            last.map(|x| x.max_delta).unwrap_or(0.0),
+            last.map(|x| x.disagreement).unwrap_or(0.0),
+            r.distribution.mean,
+            r.distribution.std_dev,
+            r.distribution.min,
+            r.distribution.max,
+        );
+        let hist: Vec<String> = r.distribution.histogram.iter().map(|c| c.to_string()).collect();
+        println!("  {:<12} histogram [-1..1]: {}", "", hist.join(" "));
+    };
+    summary("baseline", &baseline);
+    summary("alternative", &alternative);
+
+    println!("{}", "Top nodes (alternative):".blue());
+    for n in alternative.nodes.iter().take(args.top) {
+        println!("  {:>3}. {:<16} trust {:>6.3} quality {:.3} priority {:.3}", n.rank, n.node_id.get(0..16).unwrap_or(&n.node_id), n.trust, n.quality, n.priority);
+    }
+    if changes.is_empty() {
+        println!("{}", "✅ Ranking unchanged".green());
+    } else {
+        println!("{}", format!("Rank changes: {}", changes.len()).blue());
+        for c in changes.iter().take(args.top) {
+            let arrow = if c.shift() > 0 { format!("▲{}", c.shift()).green() } else { format!("▼{}", -c.shift()).red() };
+            println!("  {:<16} {:>3} → {:<3} {} (trust {:.3} → {:.3})", c.node_id.get(0..16).unwrap_or(&c.node_id), c.baseline_rank, c.alternative_rank, arrow, c.baseline_trust, c.alternative_trust);
+        }
+    }
+    Ok(())
+}
+
 async fn run_status(db_path_flag: PathBuf, identity_path: Option<PathBuf>) -> anyhow::Result<()> {
     // 1) Конфиг узла
     let cfg = load_config().unwrap_or_else(|_| default_config());
@@ -450,20 +1438,23 @@ async fn run_verify(db_path: PathBuf) -> anyhow::Result<()> {
     
     let conn = storage::open_db(db_path.to_str().unwrap())?;
     
-    // Проверка событий с подписями
-    let events = storage::load_truth_events(&conn)?;
+    // Проверка событий с подписями (постранично, без загрузки всей таблицы)
     let mut valid_signatures = 0;
     let mut total_signed = 0;
-    
-    for event in &events {
-        if let (Some(sig), Some(pk)) = (&event.signature, &event.public_key) {
-            total_signed += 1;
-            if truth_core::p2p::encryption::CryptoIdentity::from_public_key_hex(pk)
-                .and_then(|id| id.verify_from_hex(format!("event:{}", event.id).as_bytes(), sig))
-                .is_ok() {
-                valid_signatures += 1;
+    let mut cursor = None;
+    loop {
+        let page = storage::query_truth_events(&conn, &storage::RecordQuery::new().limit(storage::PAGE_MAX_LIMIT).after(cursor))?;
+        for event in &page.items {
+            if let (Some(sig), Some(pk)) = (&event.signature, &event.public_key) {
+                total_signed += 1;
+                if truth_core::p2p::encryption::CryptoIdentity::from_public_key_hex(pk)
+                    .and_then(|id| id.verify_from_hex(format!("event:{}", event.id).as_bytes(), sig))
+                    .is_ok() {
+                    valid_signatures += 1;
+                }
             }
         }
+        match page.next_cursor { Some(c) => cursor = Some(c), None => break }
     }
     
     println!("{}", format!("✅ Verified {}/{} signed events", valid_signatures, total_signed).green());
@@ -962,6 +1953,453 @@ async fn run_peers(cmd: PeersCmd) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Миграции открывают БД напрямую: open_db сам применил бы все миграции
+/// Ключ БД из первого ключа keystore
+fn keystore_db_key() -> anyhow::Result<KeySource> {
+    let store = load_keys()?;
+    let k = store.keys.first().ok_or_else(|| anyhow::anyhow!("No keys found. Use 'truthctl keys generate --save' or 'truthctl keys import'"))?;
+    Ok(KeySource::NodeKey(k.private_key_hex.clone()))
+}
+
+/// Парольная фраза: файл, затем переменная окружения, затем запрос в терминале
+fn read_passphrase(file: Option<&PathBuf>, env: &str, prompt: &str, confirm: bool) -> anyhow::Result<String> {
+    if let Some(path) = file {
+        return Ok(fs::read_to_string(path)?.trim_end_matches(['\r', '\n']).to_string());
+    }
+    if let Ok(p) = std::env::var(env) {
+        return Ok(p);
+    }
+    let mut input = dialoguer::Password::new().with_prompt(prompt);
+    if confirm {
+        input = input.with_confirmation("Repeat passphrase", "Passphrases do not match");
+    }
+    Ok(input.interact()?)
+}
+
+async fn run_db(cmd: DbCmd, db_keystore: bool) -> anyhow::Result<()> {
+    match cmd {
+        DbCmd::Migrate { db, to, dry_run } => {
+            let conn = rusqlite::Connection::open(&db)?;
+            let report = storage::migrate_db(&conn, to, dry_run)?;
+            if !report.legacy_columns.is_empty() {
+                println!("{} {}", "Legacy columns added:".yellow(), report.legacy_columns.join(", "));
+            }
+            if report.steps.is_empty() {
+                println!("{}", format!("✅ Schema is at version {}", report.to).green());
+                return Ok(());
+            }
+            let title = if dry_run { "Migration plan (dry run)" } else { "Applied migrations" };
+            println!("{}", format!("{}: {} -> {}", title, report.from, report.to).blue());
+            for s in &report.steps {
+                let arrow = if s.direction == "up" { "↑".green() } else { "↓".yellow() };
+                println!("{} {:>3} {}", arrow, s.version, s.name);
+            }
+            if !dry_run {
+                println!("{}", format!("✅ Schema is at version {}", report.to).green());
+            }
+        }
+        DbCmd::Status { db } => {
+            let conn = rusqlite::Connection::open(&db)?;
+            let current = core_lib::migrations::current_version(&conn)?;
+            let latest = core_lib::migrations::latest_version();
+            let line = format!("Schema version: {} (latest {})", current, latest);
+            println!("{}", if current == latest { line.green() } else { line.yellow() });
+            for s in core_lib::migrations::status(&conn)? {
+                let state = match s.applied_at {
+                    Some(at) => {
+                        let ts = chrono::DateTime::<chrono::Utc>::from(std::time::UNIX_EPOCH + std::time::Duration::from_secs(at as u64)).to_rfc3339();
+                        if s.checksum_ok { format!("✅ applied {}", ts).normal() } else { format!("❌ applied {}, checksum mismatch", ts).red() }
+                    }
+                    None => "pending".yellow(),
+                };
+                println!("{:>3} {:<20} {}", s.version, s.name, state);
+            }
+            let enc = at_rest::status(&conn)?;
+            match (enc.encrypted, enc.kdf) {
+                (true, Some(kdf)) => println!("Encryption: {} ({})", "on".green(), kdf),
+                _ => println!("Encryption: {}", "off".yellow()),
+            }
+        }
+        DbCmd::Encrypt { db, keystore, passphrase_file } => {
+            let source = if keystore {
+                keystore_db_key()?
+            } else {
+                KeySource::Passphrase(read_passphrase(passphrase_file.as_ref(), at_rest::PASSPHRASE_ENV, "New database passphrase", true)?)
+            };
+            let mut conn = rusqlite::Connection::open(&db)?;
+            storage::init_db(&conn)?;
+            let sealed = at_rest::enable(&mut conn, &source)?;
+            println!("{}", format!("✅ Database encrypted, {} values sealed", sealed).green());
+        }
+        DbCmd::Rekey { db, keystore, new_passphrase_file } => {
+            let old = if db_keystore {
+                keystore_db_key()?
+            } else {
+                KeySource::Passphrase(read_passphrase(None, at_rest::PASSPHRASE_ENV, "Current database passphrase", false)?)
+            };
+            let new = if keystore {
+                keystore_db_key()?
+            } else {
+                KeySource::Passphrase(read_passphrase(new_passphrase_file.as_ref(), "TRUTH_DB_NEW_PASSPHRASE", "New database passphrase", true)?)
+            };
+            let conn = rusqlite::Connection::open(&db)?;
+            storage::init_db(&conn)?;
+            at_rest::rekey(&conn, &old, &new)?;
+            println!("{}", "✅ Database key rewrapped".green());
+        }
+        DbCmd::Unlock { db, server, passphrase_file } => {
+            if let Some(server) = server {
+                let passphrase = read_passphrase(passphrase_file.as_ref(), at_rest::PASSPHRASE_ENV, "Database passphrase", false)?;
+                let resp = reqwest::Client::new()
+                    .post(format!("{}/api/v1/db/unlock", server))
+                    .json(&serde_json::json!({ "passphrase": passphrase }))
+                    .send()
+                    .await?;
+                if !resp.status().is_success() {
+                    anyhow::bail!(format!("Unlock failed: HTTP {} {}", resp.status(), resp.text().await.unwrap_or_default()));
+                }
+                println!("{}", "✅ Server database unlocked".green());
+                return Ok(());
+            }
+            let source = if db_keystore {
+                keystore_db_key()?
+            } else {
+                KeySource::Passphrase(read_passphrase(passphrase_file.as_ref(), at_rest::PASSPHRASE_ENV, "Database passphrase", false)?)
+            };
+            let conn = rusqlite::Connection::open(&db)?;
+            at_rest::unlock(&conn, &source)?;
+            println!("{}", "✅ Key is valid; pass it via TRUTH_DB_PASSPHRASE or --db-keystore to other commands".green());
+        }
+    }
+    Ok(())
+}
+
+/// Ключ подписи манифестов копий: первый ключ keystore
+fn keystore_signing_key() -> Option<ed25519_dalek::SigningKey> {
+    let store = load_keys().ok()?;
+    let bytes: [u8; 32] = hex::decode(&store.keys.first()?.private_key_hex).ok()?.try_into().ok()?;
+    Some(ed25519_dalek::SigningKey::from_bytes(&bytes))
+}
+
+/// Выполнить локальные изменения от имени первого ключа keystore (в журнале аудита);
+/// без ключа — от имени узла
+fn as_keystore_actor<T>(f: impl FnOnce() -> T) -> T {
+    match keystore_signing_key() {
+        Some(key) => audit::with_actor(&hex::encode(key.verifying_key().as_bytes()), f),
+        None => f(),
+    }
+}
+
+fn short_hash(h: &Option<String>) -> &str {
+    h.as_deref().and_then(|h| h.get(0..8)).unwrap_or("-")
+}
+
+fn run_audit(cmd: AuditCmd) -> anyhow::Result<()> {
+    match cmd {
+        AuditCmd::Verify { db } => {
+            let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+            let r = audit::verify(&conn)?;
+            println!("Entries: {} (head #{})", r.entries, r.head_seq);
+            println!("Checkpoints: {} (signed up to #{})", r.checkpoints, r.signed_seq);
+            for k in &r.signers {
+                println!("  signer {}", k.get(0..16).unwrap_or(k));
+            }
+            if r.head_seq > r.signed_seq {
+                println!("{}", format!("{} entries after the last checkpoint are not signed yet", r.head_seq - r.signed_seq).yellow());
+            }
+            if r.is_intact() {
+                println!("{}", "✅ Audit log is intact".green());
+                return Ok(());
+            }
+            for p in &r.problems {
+                println!("{}", format!("❌ {}", p).red());
+            }
+            anyhow::bail!("audit log verification failed: {} problem(s)", r.problems.len())
+        }
+        AuditCmd::List { table, record, limit, db } => {
+            let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+            for e in audit::load_entries(&conn, table.as_deref(), record.as_deref(), limit)? {
+                let ts = chrono::DateTime::<chrono::Utc>::from(std::time::UNIX_EPOCH + std::time::Duration::from_secs(e.ts.max(0) as u64)).to_rfc3339();
+                let actor = if e.actor.len() > 16 { &e.actor[0..16] } else { e.actor.as_str() };
+                println!(
+                    "#{:<6} {} {:<6} {:<18} {:<16} {} {} → {}",
+                    e.seq, ts, e.op, e.table_name, actor, e.record_id, short_hash(&e.before_hash), short_hash(&e.after_hash)
+                );
+            }
+        }
+        AuditCmd::Sign { db } => {
+            let key = keystore_signing_key().ok_or_else(|| anyhow::anyhow!("No signing key in keystore; run `truthctl keys generate --save`"))?;
+            let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+            match audit::checkpoint(&conn, &key, chrono::Utc::now().timestamp())? {
+                Some(c) => println!("{}", format!("✅ Signed audit log up to entry #{}", c.seq).green()),
+                None => println!("{}", "Nothing to sign: the log is empty or its head is already signed".yellow()),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// uid события или утверждения по локальному id; иначе id считается uid
+fn record_uid(conn: &rusqlite::Connection, table: &str, id: &str) -> anyhow::Result<String> {
+    let uid = match (table, id.parse::<i64>()) {
+        ("truth_events", Ok(local)) => storage::get_truth_event(conn, local)?.map(|e| e.uid),
+        ("statements", Ok(local)) => storage::get_statement(conn, local)?.map(|s| s.uid),
+        _ => None,
+    };
+    Ok(uid.unwrap_or_else(|| id.to_string()))
+}
+
+fn print_evidence(e: &core_lib::models::Evidence, blobs: &evidence::BlobStore) {
+    let blob = match e.sha256.as_deref() {
+        Some(sha) if blobs.contains(sha) => format!("sha256:{}", &sha[0..12]),
+        Some(sha) => format!("sha256:{} (not local)", &sha[0..12]),
+        None => "-".to_string(),
+    };
+    let size = e.size.map(|s| format!("{} B", s)).unwrap_or_default();
+    println!(
+        "{} {:<12} {} {:<4} {} {} {} by {}",
+        &e.id[0..12],
+        e.target_table,
+        e.target_uid.get(0..12).unwrap_or(&e.target_uid),
+        e.kind,
+        e.name,
+        size,
+        blob,
+        e.author.get(0..8).unwrap_or(&e.author)
+    );
+    if let Some(note) = &e.note {
+        println!("    {}", note);
+    }
+}
+
+async fn run_evidence(cmd: EvidenceCmd) -> anyhow::Result<()> {
+    match cmd {
+        EvidenceCmd::Add { target, id, file, url, note, db, blobs } => as_keystore_actor(|| {
+            let key = keystore_signing_key().ok_or_else(|| anyhow::anyhow!("No signing key in keystore; run `truthctl keys generate --save`"))?;
+            let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+            let table = match target {
+                EvidenceTarget::Event => "truth_events",
+                EvidenceTarget::Statement => "statements",
+            };
+            let uid = record_uid(&conn, table, &id)?;
+            let author = hex::encode(key.verifying_key().as_bytes());
+            let now = chrono::Utc::now().timestamp();
+            let blobs = evidence::BlobStore::new(blobs);
+            let mut e = match (file, url) {
+                (Some(path), _) => evidence::from_file(&blobs, &path, table, &uid, &author, now)?,
+                (None, Some(url)) => core_lib::models::Evidence::new(table, &uid, "url", None, &url, &author, now),
+                (None, None) => anyhow::bail!("Pass --file or --url"),
+            };
+            e.note = note;
+            evidence::sign(&mut e, &key);
+            match evidence::apply(&conn, &e)? {
+                evidence::Applied::Created => {
+                    println!("{}", format!("✅ Attached evidence {} to {} {}", e.id, table, uid).green());
+                    print_evidence(&e, &blobs);
+                }
+                evidence::Applied::Known => println!("{}", "Evidence already recorded".yellow()),
+                evidence::Applied::MissingTarget | evidence::Applied::Deleted => anyhow::bail!("{} {} not found", table, uid),
+            }
+            Ok(())
+        }),
+        EvidenceCmd::List { event, db, blobs } => {
+            let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+            let blobs = evidence::BlobStore::new(blobs);
+            let list = match event {
+                Some(id) => storage::evidence_for_event(&conn, &record_uid(&conn, "truth_events", &id)?)?,
+                None => storage::load_evidence(&conn, None)?,
+            };
+            if list.is_empty() {
+                println!("{}", "No evidence".yellow());
+            }
+            list.iter().for_each(|e| print_evidence(e, &blobs));
+            Ok(())
+        }
+        EvidenceCmd::Verify { db, blobs } => {
+            let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+            let blobs = evidence::BlobStore::new(blobs);
+            let list = storage::load_evidence(&conn, None)?;
+            let (mut missing, mut problems) = (0usize, 0usize);
+            for e in &list {
+                if let Err(err) = evidence::verify(e) {
+                    problems += 1;
+                    println!("{}", format!("❌ {}", err).red());
+                }
+                let Some(sha) = e.sha256.as_deref() else { continue };
+                match blobs.get(sha) {
+                    Ok(Some(_)) => {}
+                    Ok(None) => missing += 1,
+                    Err(err) => {
+                        problems += 1;
+                        println!("{}", format!("❌ evidence {}: {}", e.id, err).red());
+                    }
+                }
+            }
+            println!("Evidence: {}, blobs not stored locally: {}", list.len(), missing);
+            if problems > 0 {
+                anyhow::bail!("evidence verification failed: {} problem(s)", problems);
+            }
+            println!("{}", "✅ Evidence signatures and local blobs are intact".green());
+            Ok(())
+        }
+        EvidenceCmd::Fetch { peer, db, blobs } => {
+            let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+            let blobs = evidence::BlobStore::new(blobs);
+            let mut wanted: Vec<String> = storage::load_evidence(&conn, None)?
+                .into_iter()
+                .filter_map(|e| e.sha256)
+                .filter(|sha| !blobs.contains(sha))
+                .collect();
+            wanted.sort();
+            wanted.dedup();
+            let client = reqwest::Client::new();
+            let (mut fetched, mut failed) = (0usize, 0usize);
+            for sha in &wanted {
+                let url = format!("{}/api/v1/evidence/blobs/{}", peer.trim_end_matches('/'), sha);
+                let result = async {
+                    let resp = client.get(&url).send().await?.error_for_status()?;
+                    let bytes = resp.bytes().await?;
+                    blobs.put_expected(sha, &bytes)?;
+                    anyhow::Ok(())
+                }
+                .await;
+                match result {
+                    Ok(()) => fetched += 1,
+                    Err(err) => {
+                        failed += 1;
+                        println!("{}", format!("⚠️ {}: {}", &sha[0..12], err).yellow());
+                    }
+                }
+            }
+            println!("{}", format!("✅ Fetched {} of {} missing blobs from {}", fetched, wanted.len(), peer).green());
+            if failed > 0 {
+                anyhow::bail!("{} blob(s) could not be fetched", failed);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn print_relation(r: &core_lib::models::EventRelation) {
+    println!(
+        "{} {} {} {} by {}",
+        &r.id[0..12],
+        r.source_uid.get(0..12).unwrap_or(&r.source_uid),
+        r.relation,
+        r.target_uid.get(0..12).unwrap_or(&r.target_uid),
+        r.author.get(0..8).unwrap_or(&r.author)
+    );
+    if let Some(note) = &r.note {
+        println!("    {}", note);
+    }
+}
+
+fn run_relation(cmd: RelationCmd) -> anyhow::Result<()> {
+    match cmd {
+        RelationCmd::Add { source, relation, target, note, db } => as_keystore_actor(|| {
+            let key = keystore_signing_key().ok_or_else(|| anyhow::anyhow!("No signing key in keystore; run `truthctl keys generate --save`"))?;
+            let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+            let source = record_uid(&conn, "truth_events", &source)?;
+            let target = record_uid(&conn, "truth_events", &target)?;
+            let author = hex::encode(key.verifying_key().as_bytes());
+            let mut rel = core_lib::models::EventRelation::new(&source, &target, &relation, &author, chrono::Utc::now().timestamp());
+            rel.note = note;
+            event_relation::sign(&mut rel, &key);
+            match event_relation::apply(&conn, &rel)? {
+                event_relation::Applied::Created => {
+                    println!("{}", format!("✅ Recorded relation {}", rel.id).green());
+                    print_relation(&rel);
+                    if relation == "duplicate_of" {
+                        println!("Canonical event: {}", event_relation::canonical_uid(&conn, &source)?);
+                    }
+                }
+                event_relation::Applied::Known => println!("{}", "Relation already recorded".yellow()),
+                event_relation::Applied::MissingTarget | event_relation::Applied::Deleted => {
+                    anyhow::bail!("event {} or {} not found", source, target)
+                }
+                event_relation::Applied::Forbidden => {
+                    anyhow::bail!("only the author of event {} or an admin may mark it {}", source, relation)
+                }
+            }
+            Ok(())
+        }),
+        RelationCmd::List { event, db } => {
+            let conn = storage::open_db(db.to_str().unwrap_or("truth.db"))?;
+            let list = match event {
+                Some(id) => {
+                    let uid = record_uid(&conn, "truth_events", &id)?;
+                    let canonical = event_relation::canonical_uid(&conn, &uid)?;
+                    if canonical != uid {
+                        println!("Canonical event: {}", canonical);
+                    }
+                    storage::relations_for_event(&conn, &uid)?
+                }
+                None => storage::load_event_relations(&conn, None)?,
+            };
+            if list.is_empty() {
+                println!("{}", "No relations".yellow());
+            }
+            list.iter().for_each(print_relation);
+            Ok(())
+        }
+    }
+}
+
+fn print_backup(e: &backup::BackupEntry) {
+    let ts = chrono::DateTime::<chrono::Utc>::from(std::time::UNIX_EPOCH + std::time::Duration::from_secs(e.manifest.created_at as u64)).to_rfc3339();
+    let signer = e.manifest.node_public_key.as_deref().and_then(|k| k.get(0..8)).unwrap_or("unsigned");
+    println!("{:<32} {} {:>10} v{:<3} {}", e.manifest.file, ts, e.manifest.size, e.manifest.schema_version, signer);
+}
+
+fn run_backup(cmd: BackupCmd) -> anyhow::Result<()> {
+    match cmd {
+        BackupCmd::Create { db, dir, keep } => {
+            if !db.exists() { anyhow::bail!(format!("Database {} not found", db.display())); }
+            // Без open_db: копируются страницы как есть, ключ зашифрованной БД не нужен
+            let conn = rusqlite::Connection::open(&db)?;
+            let entry = backup::create(&conn, &dir, keystore_signing_key().as_ref())?;
+            println!("{}", format!("✅ Backup created: {}", entry.path.display()).green());
+            if let Some(keep) = keep {
+                for p in backup::prune(&dir, keep.max(1))? {
+                    println!("{} {}", "Removed".yellow(), p.display());
+                }
+            }
+        }
+        BackupCmd::List { dir } => {
+            let entries = backup::list(&dir)?;
+            if entries.is_empty() {
+                println!("{}", "No backups".yellow());
+            } else {
+                println!("{}", "FILE                             CREATED_AT                      SIZE SCHEMA SIGNER".blue());
+                entries.iter().for_each(print_backup);
+            }
+        }
+        BackupCmd::Verify { file, allow_unsigned } => {
+            let node = keystore_signing_key().map(|k| k.verifying_key());
+            let entry = backup::verify(&file, node.as_ref(), allow_unsigned)?;
+            print_backup(&entry);
+            println!("{}", "✅ Backup is intact".green());
+        }
+        BackupCmd::Restore { file, db, dir, allow_unsigned, confirm } => {
+            let node = keystore_signing_key();
+            let entry = backup::verify(&file, node.as_ref().map(|k| k.verifying_key()).as_ref(), allow_unsigned)?;
+            if !confirm {
+                let question = format!("Replace {} with {}?", db.display(), entry.manifest.file);
+                if !dialoguer::Confirm::new().with_prompt(question).default(false).interact()? {
+                    println!("{}", "Restore cancelled".yellow());
+                    return Ok(());
+                }
+            }
+            let report = backup::restore(&file, &db, Some(&dir), node.as_ref(), allow_unsigned)?;
+            if let Some(prev) = report.previous {
+                println!("Previous database saved to {}", prev.path.display());
+            }
+            println!("{}", format!("✅ Restored {} from {}", db.display(), report.restored.manifest.file).green());
+        }
+    }
+    Ok(())
+}
+
 async fn run_logs(cmd: LogsCmd) -> anyhow::Result<()> {
     match cmd {
         LogsCmd::Show { limit, db } => {
@@ -980,9 +2418,50 @@ async fn run_logs(cmd: LogsCmd) -> anyhow::Result<()> {
         }
         LogsCmd::Clear { db } => {
             let conn = storage::open_db(db.to_str().unwrap())?;
-            core_lib::storage::clear_sync_logs(&conn)?;
+            as_keystore_actor(|| core_lib::storage::clear_sync_logs(&conn))?;
             println!("{}", "✅ Logs cleared".green());
         }
+        LogsCmd::Prune { db, dry_run, archive_dir, table } => {
+            let mut conn = storage::open_db(db.to_str().unwrap())?;
+            let report = retention::prune(&mut conn, &retention::PruneOptions { now: None, dry_run, archive_dir, table })?;
+            println!("{}", if dry_run { "Would prune (dry run):" } else { "Pruned:" }.blue());
+            println!("{:<20} {:>8} {:>11} {:>8}", "TABLE", "EXPIRED", "DOWNSAMPLED", "OVERFLOW");
+            for t in &report.tables {
+                println!("{:<20} {:>8} {:>11} {:>8}", t.table, t.expired, t.downsampled, t.overflow);
+            }
+            if let Some(path) = report.archive {
+                println!("Archived to {}", path.display());
+            }
+        }
+        LogsCmd::Retention { db, table, max_age_days, max_rows, downsample_after_days, reset } => {
+            let conn = storage::open_db(db.to_str().unwrap())?;
+            if let Some(table) = table {
+                as_keystore_actor(|| -> anyhow::Result<()> {
+                    if reset {
+                        retention::reset_policy(&conn, &table)?;
+                    } else {
+                        let mut p = retention::policy(&conn, &table)?;
+                        // 0 снимает ограничение
+                        if let Some(v) = max_age_days { p.max_age_days = (v > 0).then_some(v); }
+                        if let Some(v) = max_rows { p.max_rows = (v > 0).then_some(v); }
+                        if let Some(v) = downsample_after_days { p.downsample_after_days = (v > 0).then_some(v); }
+                        retention::set_policy(&conn, &p)?;
+                    }
+                    Ok(())
+                })?;
+            }
+            let limit = |v: Option<String>| v.unwrap_or_else(|| "-".into());
+            println!("{}", format!("{:<20} {:>12} {:>9} {:>16}", "TABLE", "MAX_AGE_DAYS", "MAX_ROWS", "DAILY_AFTER_DAYS").blue());
+            for p in retention::policies(&conn)? {
+                println!(
+                    "{:<20} {:>12} {:>9} {:>16}",
+                    p.table,
+                    limit(p.max_age_days.map(|v| v.to_string())),
+                    limit(p.max_rows.map(|v| v.to_string())),
+                    limit(p.downsample_after_days.map(|v| v.to_string()))
+                );
+            }
+        }
     }
     Ok(())
 }
@@ -1005,21 +2484,23 @@ async fn run_ratings(db_path: PathBuf, recalc: bool, cmd: Option<RatingsCmd>) ->
     let group_ratings = storage::load_group_ratings(&conn)?;
     
     match cmd {
-        Some(RatingsCmd::Trust { verbose }) => {
+        Some(RatingsCmd::Trust { verbose, history, node, limit, window }) => {
             // Локальный доверительный уровень (средний по узлам)
             let avg_local: f64 = if node_ratings.is_empty() {
                 0.0
             } else {
                 node_ratings.iter().map(|n| n.trust_score as f64).sum::<f64>() / node_ratings.len() as f64
             };
-            // Средняя сеть — по глобальной группе
-            let avg_network: f64 = group_ratings
-                .iter()
-                .find(|g| g.group_id == "global")
-                .map(|g| g.avg_score as f64)
-                .unwrap_or_else(|| {
-                    if node_ratings.is_empty() { 0.0 } else { avg_local }
-                });
+            // Средняя сеть — по группам (сообществам), взвешенно по числу участников
+            let group_members: usize = group_ratings.iter().map(|g| g.members.len()).sum();
+            let avg_network: f64 = if group_members == 0 {
+                avg_local
+            } else {
+                group_ratings
+                    .iter()
+                    .map(|g| g.avg_score as f64 * g.members.len() as f64)
+                    .sum::<f64>() / group_members as f64
+            };
 
             // Дельты доверия из последнего события синхронизации, если оно записано
             let recent_logs = core_lib::storage::get_recent_sync_logs(&conn, 1)?;
@@ -1044,6 +2525,22 @@ async fn run_ratings(db_path: PathBuf, recalc: bool, cmd: Option<RatingsCmd>) ->
                     println!("  {} {:<8} {:.3}", sign, &id.get(0..8).unwrap_or(""), sc);
                 }
             }
+            if history {
+                let ids: Vec<String> = match node {
+                    Some(id) => vec![id],
+                    None => node_ratings.iter().map(|n| n.node_id.clone()).collect(),
+                };
+                println!("{}", format!("Trust history (window {}):", window).blue());
+                for id in ids {
+                    let points = storage::load_trust_history(&conn, &id, Some(limit))?;
+                    let stability = storage::trust_score_stability(&conn, &id, window.max(1))?;
+                    println!("  {:<16} stability {:.3} ({} points)", id.get(0..16).unwrap_or(&id), stability, points.len());
+                    for p in points.iter().rev() {
+                        let ts = chrono::DateTime::<chrono::Utc>::from(std::time::UNIX_EPOCH + std::time::Duration::from_secs(p.recorded_at as u64)).to_rfc3339();
+                        println!("    {} {:>7.3} {}", ts, p.trust_score, p.source);
+                    }
+                }
+            }
         }
         None => {
             println!("{}", format!("Node Ratings: {}", node_ratings.len()).blue());
@@ -1052,7 +2549,7 @@ async fn run_ratings(db_path: PathBuf, recalc: bool, cmd: Option<RatingsCmd>) ->
             }
             println!("{}", format!("Group Ratings: {}", group_ratings.len()).blue());
             for rating in &group_ratings {
-                println!("  {}: {:.3} (coherence: {:.3})", rating.group_id, rating.avg_score, rating.coherence);
+                println!("  {}: {:.3} (coherence: {:.3}, members: {})", rating.group_id, rating.avg_score, rating.coherence, rating.members.len());
             }
         }
     }
@@ -1097,7 +2594,19 @@ async fn run_sync(peer: String, identity_path: PathBuf, db_path: PathBuf, mode:
         }
         Mode::Pull => {
             let data = pull_remote_data(&peer, &identity).await?;
-            let res = truth_core::p2p::sync::reconcile(&conn, &data)?;
+            // Рейтинги пира взвешиваются по доверию к его ключу из peers.json; без ключа пир
+            // считается неизвестным узлом (trust 0) и его рейтинги почти не влияют
+            let sender = load_peers()
+                .unwrap_or_default()
+                .peers
+                .into_iter()
+                .find(|p| p.url.trim_end_matches('/') == peer.trim_end_matches('/'))
+                .map(|p| p.public_key)
+                .unwrap_or_else(|| {
+                    eprintln!("{}", format!("Peer {} is not in peers.json; its ratings are weighted as an unknown node", peer).yellow());
+                    peer.clone()
+                });
+            let res = truth_core::p2p::sync::reconcile_from(&conn, &data, Some(&sender))?;
             print_sync_result(res);
         }
     }
@@ -1165,15 +2674,79 @@ async fn run_users(cmd: UsersCmd) -> anyhow::Result<()> {
 
 async fn run_trust(cmd: TrustCmd) -> anyhow::Result<()> {
     use reqwest::Client;
+    use truth_core::p2p::encryption::CryptoIdentity;
     let client = Client::new();
+    // Делегирования и отзывы подписываются первым ключом локального хранилища (тем же, что и при auth)
+    let identity = || -> anyhow::Result<CryptoIdentity> {
+        let ks = load_keys().unwrap_or_default();
+        let k = ks.keys.first().ok_or_else(|| anyhow::anyhow!("No keys found. Use 'truthctl keys generate --save' or 'truthctl keys import'"))?;
+        CryptoIdentity::from_keypair_hex(&k.private_key_hex, &k.public_key_hex).map_err(|e| anyhow::anyhow!(e))
+    };
     match cmd {
-        TrustCmd::Delegate { server, target_pubkey, delta } => {
+        TrustCmd::Delegate { server, target_pubkey, delta, expires_in } => {
             let auth = bearer_or_err()?;
+            let id = identity()?;
+            let created_at = chrono::Utc::now().timestamp();
+            let expires_at = expires_in.map(|s| created_at + s);
+            let d = core_lib::models::TrustDelegation::new(&id.public_key_hex(), &target_pubkey, delta, created_at, expires_at);
+            let signature = hex::encode(id.sign(d.signing_message().as_bytes()).to_bytes());
             let resp = client.post(format!("{}/api/v1/trust/delegate", server))
                 .header("Authorization", auth)
-                .json(&serde_json::json!({"target_pubkey": target_pubkey, "delta": delta}))
+                .json(&serde_json::json!({
+                    "target_pubkey": target_pubkey,
+                    "delta": delta,
+                    "created_at": created_at,
+                    "expires_at": expires_at,
+                    "signature": signature,
+                }))
                 .send().await?;
-            if resp.status().is_success() { println!("{}", "✅ Delegation applied".green()); } else { println!("{} {}", "❌ Denied".red(), resp.status()); }
+            if resp.status().is_success() {
+                println!("{} {}", "✅ Delegation stored:".green(), d.id);
+            } else {
+                println!("{} {}", "❌ Denied".red(), resp.status());
+            }
+        }
+        TrustCmd::List { server, all, target } => {
+            let mut url = format!("{}/api/v1/trust/delegations?all={}", server, all);
+            if let Some(t) = target { url.push_str(&format!("&target={}", t)); }
+            let resp = client.get(url).send().await?;
+            if !resp.status().is_success() {
+                anyhow::bail!("HTTP {}: {}", resp.status(), resp.text().await?);
+            }
+            let list: Vec<core_lib::models::TrustDelegation> = resp.json().await?;
+            println!("{}", format!("Trust delegations: {}", list.len()).blue());
+            let now = chrono::Utc::now().timestamp();
+            for d in list {
+                let state = if d.revoked_at.is_some() {
+                    "revoked".red()
+                } else if d.is_active(now) {
+                    "active".green()
+                } else {
+                    "expired".yellow()
+                };
+                let expires = d.expires_at.map(|e| e.to_string()).unwrap_or_else(|| "never".into());
+                println!(
+                    "  {} {} → {} {:+.3} (expires: {}) [{}]",
+                    d.id.get(0..12).unwrap_or(&d.id),
+                    d.delegator.get(0..8).unwrap_or(&d.delegator),
+                    d.target.get(0..8).unwrap_or(&d.target),
+                    d.delta,
+                    expires,
+                    state
+                );
+            }
+        }
+        TrustCmd::Revoke { server, id: delegation_id } => {
+            let auth = bearer_or_err()?;
+            let id = identity()?;
+            let revoked_at = chrono::Utc::now().timestamp();
+            let msg = core_lib::models::TrustDelegation::revocation_message(&delegation_id, revoked_at);
+            let signature = hex::encode(id.sign(msg.as_bytes()).to_bytes());
+            let resp = client.post(format!("{}/api/v1/trust/revoke", server))
+                .header("Authorization", auth)
+                .json(&serde_json::json!({"id": delegation_id, "revoked_at": revoked_at, "signature": signature}))
+                .send().await?;
+            if resp.status().is_success() { println!("{}", "✅ Delegation revoked".green()); } else { println!("{} {}", "❌ Denied".red(), resp.status()); }
         }
     }
     Ok(())
@@ -1183,8 +2756,11 @@ async fn run_graph(cmd: GraphCmd) -> anyhow::Result<()> {
     use reqwest::Client;
     let client = Client::new();
     match cmd {
-        GraphCmd::Show { server, min_priority, limit, format } => {
-            let url = format!("{}/graph/json?min_priority={}&limit={}", server, min_priority, limit);
+        GraphCmd::Show { server, min_priority, limit, format, group } => {
+            let mut url = format!("{}/graph/json?min_priority={}&limit={}", server, min_priority, limit);
+            if let Some(g) = group {
+                url.push_str(&format!("&group={}", g));
+            }
             let resp = client.get(&url).send().await?;
             
             if !resp.status().is_success() {
@@ -1236,6 +2812,10 @@ fn render_ascii_graph(graph: &serde_json::Value) -> anyhow::Result<()> {
         let priority = node.get("propagation_priority").and_then(|p| p.as_f64()).unwrap_or(0.0);
         let relay_rate = node.get("relay_success_rate").and_then(|r| r.as_f64()).unwrap_or(0.0);
         let quality = node.get("quality_index").and_then(|q| q.as_f64()).unwrap_or(0.0);
+        let group = node.get("group_id").and_then(|g| g.as_str()).unwrap_or("-");
+        let alerts: Vec<&str> = node.get("alerts").and_then(|a| a.as_array())
+            .map(|a| a.iter().filter_map(|k| k.as_str()).collect())
+            .unwrap_or_default();
         
         let short_id = if id.len() > 8 { &id[0..8] } else { id };
         let score_color = if score > 0.5 { "🟢" } else if score > 0.0 { "🟡" } else { "🔴" };
@@ -1243,8 +2823,11 @@ fn render_ascii_graph(graph: &serde_json::Value) -> anyhow::Result<()> {
         let relay_color = if relay_rate > 0.8 { "🟢" } else if relay_rate > 0.5 { "🟡" } else { "🔴" };
         let quality_color = if quality > 0.8 { "🔵" } else if quality > 0.5 { "🟡" } else { "🔴" };
 
-        println!("{}. {} {} {} {} (trust: {:.2}, priority: {:.2}, relay: {:.1}% {}, quality: {:.2} {})", 
-                 i + 1, short_id, score_color, priority_color, relay_color, score, priority, relay_rate * 100.0, relay_color, quality, quality_color);
+        println!("{}. {} {} {} {} (trust: {:.2}, priority: {:.2}, relay: {:.1}% {}, quality: {:.2} {}, group: {})", 
+                 i + 1, short_id, score_color, priority_color, relay_color, score, priority, relay_rate * 100.0, relay_color, quality, quality_color, group);
+        if !alerts.is_empty() {
+            println!("   {} {}", "⚠ anomalies:".yellow(), alerts.join(", "));
+        }
     }
     
     // Показываем связи в ASCII формате
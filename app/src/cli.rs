@@ -2,7 +2,7 @@ use clap::{Parser, Subcommand};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use truth_core::p2p::encryption::CryptoIdentity;
-use truth_core::p2p::sync::{push_local_data, pull_remote_data, reconcile};
+use truth_core::p2p::sync::{push_local_data, pull_remote_data, reconcile_from};
 use core_lib::storage::{open_db, init_db};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -96,7 +96,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 if cli.verbose { println!("Syncing with {}", p.addr); }
                 if pull_only {
                     let remote = pull_remote_data(&p.addr, &identity).await?;
-                    let res = reconcile(&conn, &remote)?;
+                    // Рейтинги пира взвешиваются по доверию к его ключу
+                    let res = reconcile_from(&conn, &remote, Some(&p.public_key_hex))?;
                     total += res.events_added + res.statements_added + res.impacts_added;
                 } else {
                     let res = push_local_data(&p.addr, &identity, &conn).await?;
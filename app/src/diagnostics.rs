@@ -196,10 +196,15 @@ pub fn reset_local_data(confirm: bool, reinit: bool) -> Result<()> {
     // Load config (existing or default)
     let cfg = crate::config_utils::load_config().unwrap_or_else(|_| crate::config_utils::default_config());
 
-    // Clear logs if DB exists, then remove DB file
+    // Snapshot the DB into backups/ next to it, clear logs, then remove DB file
     let db_path = PathBuf::from(&cfg.db_path);
     if db_path.exists() {
         if let Ok(conn) = Connection::open(&db_path) {
+            let backup_dir = db_path.parent().unwrap_or(Path::new("")).join("backups");
+            match core_lib::backup::create(&conn, &backup_dir, None) {
+                Ok(entry) => println!("💾 Database saved to {}", entry.path.display()),
+                Err(e) => eprintln!("{}", format!("⚠️ Backup before reset failed: {}", e).yellow()),
+            }
             let _ = core_lib::storage::clear_sync_logs(&conn);
         }
         let _ = fs::remove_file(&db_path);
@@ -1,8 +1,8 @@
 use chrono::Utc;
 use clap::{Parser, Subcommand};
-use core_lib::expert_simple::{evaluate_answers, questions_for_context};
+use core_lib::expert_simple::{apply_evidence, evaluate_answers, questions_for_context, EVIDENCE_QUESTION};
 use core_lib::{
-    NewTruthEvent, NewStatement, add_impact, add_truth_event, add_statement, get_truth_event, get_statement,
+    NewTruthEvent, NewStatement, add_graded_impact, add_truth_event, add_statement, get_truth_event, get_statement,
     init_db, open_db, recalc_progress_metrics, seed_knowledge_base, set_event_detected,
 };
 use std::collections::HashMap;
@@ -63,6 +63,12 @@ enum Commands {
         type_id: i64,
         #[arg(long)]
         positive: bool,
+        /// Graded value -1..1 (overrides --positive)
+        #[arg(long, allow_hyphen_values = true)]
+        magnitude: Option<f64>,
+        /// Confidence 0..1
+        #[arg(long)]
+        confidence: Option<f64>,
         #[arg(long)]
         notes: Option<String>,
     },
@@ -98,12 +104,24 @@ enum Commands {
         /// Truth score (-1.0 to 1.0)
         #[arg(long)]
         score: Option<f32>,
+        /// Uid of the statement of the same event this one replies to
+        #[arg(long, requires = "relation")]
+        parent: Option<String>,
+        /// Relation to the parent: supports, rebuts or clarifies
+        #[arg(long, requires = "parent")]
+        relation: Option<String>,
     },
     /// Show statement by id
     ShowStatement {
         #[arg(long)]
         id: i64,
     },
+    /// Show the argument tree of an event with propagated scores
+    Arguments {
+        /// Event id
+        #[arg(long)]
+        event: i64,
+    },
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -159,9 +177,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             event,
             type_id,
             positive,
+            magnitude,
+            confidence,
             notes,
         } => {
-            let iid = add_impact(&conn, event, type_id, positive, notes)?;
+            let magnitude = magnitude.unwrap_or(if positive { 1.0 } else { -1.0 });
+            let iid = add_graded_impact(&conn, event, type_id, magnitude, confidence, notes)?;
             println!("Inserted impact id={} for event={}", iid, event);
         }
         Commands::Recalc => {
@@ -187,6 +208,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             text,
             context,
             score,
+            parent,
+            relation,
         } => {
             let id = add_statement(
                 &conn,
@@ -195,10 +218,20 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     text,
                     context,
                     truth_score: score,
+                    parent_uid: parent,
+                    relation,
                 },
             )?;
             println!("Inserted statement id={}", id);
         }
+        Commands::Arguments { event } => {
+            let ev = get_truth_event(&conn, event)?.ok_or_else(|| format!("Event {} not found", event))?;
+            let tree = core_lib::argument::tree_for_event(&conn, &ev.uid)?.ok_or_else(|| format!("Event {} not found", event))?;
+            println!("Event {} score: {}", ev.uid, fmt_score(tree.score));
+            for root in &tree.roots {
+                print_argument(root, 1);
+            }
+        }
         Commands::ShowStatement { id } => {
             let stmt = get_statement(&conn, id)?;
             match stmt {
@@ -214,18 +247,26 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         answers_json: Option<String>,
         apply: bool,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        // 1) Получаем context.name по event_id
-        let (context_name,): (String,) = conn.query_row(
-            "SELECT c.name
+        // 1) Получаем контекст по event_id
+        let (context_id, context_name): (i64, String) = conn.query_row(
+            "SELECT c.id, c.name
              FROM truth_events e
              JOIN context c ON c.id = e.context_id
              WHERE e.id=?1",
             rusqlite::params![event_id],
-            |row| Ok((row.get::<_, String>(0)?,)),
+            |row| Ok((row.get(0)?, row.get(1)?)),
         )?;
 
-        // 2) Формируем вопросы
-        let qs = questions_for_context(&context_name);
+        // 2) Формируем вопросы: из загруженных пакетов, иначе — универсальный набор
+        let mut qs = core_lib::storage::load_questions_for_context(conn, context_id)?;
+        if qs.is_empty() {
+            qs = questions_for_context(&context_name);
+        }
+        // Прикреплённые доказательства отвечают на logs_evidence, если ответа нет
+        let evidence_count = match get_truth_event(conn, event_id)? {
+            Some(ev) => core_lib::storage::evidence_for_event(conn, &ev.uid)?.len(),
+            None => 0,
+        };
 
         // 3) Если answers не переданы — печатаем пример и выходим
         if answers_json.is_none() {
@@ -245,6 +286,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 );
                 println!("  id: {}", q.id);
             }
+            if evidence_count > 0 && qs.iter().any(|q| q.id == EVIDENCE_QUESTION) {
+                println!("\n{} evidence item(s) attached: {} defaults to \"yes\".", evidence_count, EVIDENCE_QUESTION);
+            }
             println!(
                 "\nRun again with --answers '{{\"src_independent\":\"yes\",...}}' to evaluate."
             );
@@ -252,7 +296,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         // 4) Парсим ответы
-        let raw: HashMap<String, String> = serde_json::from_str(&answers_json.unwrap())?;
+        let mut raw: HashMap<String, String> = serde_json::from_str(&answers_json.unwrap())?;
+        if apply_evidence(&qs, &mut raw, evidence_count) {
+            println!("{}: yes ({} evidence item(s) attached)", EVIDENCE_QUESTION, evidence_count);
+        }
         let suggestion = evaluate_answers(&qs, &raw);
         println!(
             "Suggestion: score={:.2}, confidence={:.2}, detected_hint={:?}",
@@ -276,5 +323,26 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         Ok(())
     }
 
+    fn fmt_score(score: Option<f32>) -> String {
+        score.map(|s| format!("{:+.2}", s)).unwrap_or_else(|| "-".into())
+    }
+
+    // Узел дерева с отступом по глубине: [отношение] uid  truth_score → итоговая оценка  текст
+    fn print_argument(node: &core_lib::argument::ArgumentNode, depth: usize) {
+        let s = &node.statement;
+        println!(
+            "{}[{}] {}  {} -> {}  {}",
+            "  ".repeat(depth),
+            s.relation.as_deref().unwrap_or("claim"),
+            s.uid,
+            fmt_score(s.truth_score),
+            fmt_score(node.score),
+            s.text
+        );
+        for reply in &node.replies {
+            print_argument(reply, depth + 1);
+        }
+    }
+
     Ok(())
 }
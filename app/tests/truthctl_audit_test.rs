@@ -0,0 +1,58 @@
+#[test]
+fn audit_list_sign_and_verify() {
+    use std::process::Command;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // isolated HOME and working directory
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let tmp_home = std::env::temp_dir().join(format!("truthctl-test-home-audit-{}", stamp));
+    std::fs::create_dir_all(&tmp_home).unwrap();
+    let tmp_dir = std::env::temp_dir().join(format!("truthctl-test-dir-audit-{}", stamp));
+    std::fs::create_dir_all(&tmp_dir).unwrap();
+
+    let bin = env!("CARGO_BIN_EXE_truthctl");
+    let run = |args: &[&str]| {
+        Command::new(bin)
+            .args(args)
+            .env("HOME", &tmp_home)
+            .current_dir(&tmp_dir)
+            .output()
+            .expect("run truthctl")
+    };
+    let ok = |out: std::process::Output| {
+        assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+
+    ok(run(&["keys", "import", &"55".repeat(32), &"66".repeat(32)]));
+    ok(run(&["db", "migrate"]));
+    let admin = hex::encode(ed25519_dalek::SigningKey::from_bytes(&[0x55; 32]).verifying_key().as_bytes());
+    let conn = rusqlite::Connection::open(tmp_dir.join("truth.db")).unwrap();
+    conn.execute("INSERT INTO users (pubkey, role, trust_score, last_updated) VALUES (?1, 'admin', 0.0, 0)", [&admin]).unwrap();
+    drop(conn);
+
+    ok(run(&["kb", "add", "category", "Audited"]));
+    let stdout = ok(run(&["audit", "list", "--table", "category"]));
+    assert!(stdout.contains("insert") && stdout.contains(&admin[0..16]), "{}", stdout);
+
+    let stdout = ok(run(&["audit", "verify"]));
+    assert!(stdout.contains("not signed yet") && stdout.contains("intact"), "{}", stdout);
+    let stdout = ok(run(&["audit", "sign"]));
+    assert!(stdout.contains("Signed audit log"), "{}", stdout);
+    let stdout = ok(run(&["audit", "sign"]));
+    assert!(stdout.contains("Nothing to sign"), "{}", stdout);
+    let stdout = ok(run(&["audit", "verify"]));
+    assert!(stdout.contains("intact") && !stdout.contains("not signed yet"), "{}", stdout);
+
+    // the log is append-only; tampering requires dropping the triggers
+    let conn = rusqlite::Connection::open(tmp_dir.join("truth.db")).unwrap();
+    let err = conn.execute("UPDATE audit_log SET actor = 'someone'", []).unwrap_err();
+    assert!(err.to_string().contains("append-only"), "{}", err);
+    conn.execute_batch("DROP TRIGGER audit_log_no_update; UPDATE audit_log SET actor = 'someone' WHERE op = 'insert' AND table_name = 'category'").unwrap();
+    drop(conn);
+
+    let out = run(&["audit", "verify"]);
+    assert!(!out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("contents do not match entry_hash"), "{}", stdout);
+}
@@ -0,0 +1,51 @@
+#[test]
+fn backup_create_verify_and_restore() {
+    use std::process::Command;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // isolated HOME and working directory
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let tmp_home = std::env::temp_dir().join(format!("truthctl-test-home-backup-{}", stamp));
+    std::fs::create_dir_all(&tmp_home).unwrap();
+    let tmp_dir = std::env::temp_dir().join(format!("truthctl-test-dir-backup-{}", stamp));
+    std::fs::create_dir_all(&tmp_dir).unwrap();
+
+    let bin = env!("CARGO_BIN_EXE_truthctl");
+    let run = |args: &[&str]| {
+        Command::new(bin)
+            .args(args)
+            .env("HOME", &tmp_home)
+            .current_dir(&tmp_dir)
+            .output()
+            .expect("run truthctl")
+    };
+
+    // key for signing the manifest
+    let out = run(&["keys", "import", &"11".repeat(32), &"22".repeat(32)]);
+    assert!(out.status.success());
+
+    let out = run(&["db", "migrate"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    let out = run(&["backup", "create"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+
+    let out = run(&["backup", "list"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let file = stdout.lines().find_map(|l| l.split_whitespace().next().filter(|f| f.ends_with(".db"))).expect("backup listed").to_string();
+    let path = format!("backups/{}", file);
+
+    let out = run(&["backup", "verify", &path]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("intact"));
+
+    let out = run(&["backup", "restore", &path, "--confirm"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("Previous database saved"));
+
+    // a tampered manifest is rejected before the database is touched
+    let manifest = tmp_dir.join(path.replace(".db", ".json"));
+    let text = std::fs::read_to_string(&manifest).unwrap().replace("\"size\": ", "\"size\": 1");
+    std::fs::write(&manifest, text).unwrap();
+    let out = run(&["backup", "restore", &path, "--confirm"]);
+    assert!(!out.status.success());
+}
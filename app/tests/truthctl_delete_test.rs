@@ -0,0 +1,57 @@
+#[test]
+fn delete_signs_tombstone_and_cascades() {
+    use std::process::Command;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // isolated HOME and working directory
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let tmp_home = std::env::temp_dir().join(format!("truthctl-test-home-delete-{}", stamp));
+    std::fs::create_dir_all(&tmp_home).unwrap();
+    let tmp_dir = std::env::temp_dir().join(format!("truthctl-test-dir-delete-{}", stamp));
+    std::fs::create_dir_all(&tmp_dir).unwrap();
+
+    let bin = env!("CARGO_BIN_EXE_truthctl");
+    let run = |args: &[&str]| {
+        Command::new(bin)
+            .args(args)
+            .env("HOME", &tmp_home)
+            .current_dir(&tmp_dir)
+            .output()
+            .expect("run truthctl")
+    };
+
+    let out = run(&["keys", "import", &"11".repeat(32), &"22".repeat(32)]);
+    assert!(out.status.success());
+    let author = hex::encode(ed25519_dalek::SigningKey::from_bytes(&[0x11; 32]).verifying_key().as_bytes());
+
+    let out = run(&["db", "migrate"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    let conn = rusqlite::Connection::open(tmp_dir.join("truth.db")).unwrap();
+    // the knowledge base is not seeded, so context 1 does not exist
+    conn.execute_batch("PRAGMA foreign_keys = OFF;").unwrap();
+    for (id, key) in [(1, author.as_str()), (2, "someone-else")] {
+        conn.execute(
+            "INSERT INTO truth_events (id, uid, description, context_id, vector, corrected, timestamp_start, code, public_key)
+             VALUES (?1, 'ev-' || ?1, 'event', 1, 1, 0, 1, 1, ?2)",
+            rusqlite::params![id, key],
+        ).unwrap();
+    }
+    conn.execute("INSERT INTO statements (uid, event_id, text, created_at, updated_at) VALUES ('st-1', 1, 'claim', 1, 1)", []).unwrap();
+    drop(conn);
+
+    let out = run(&["delete", "events", "1", "--reason", "duplicate"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("2 rows"));
+
+    // someone else's record stays, whether addressed by local id or uid
+    let out = run(&["delete", "events", "2"]);
+    assert!(!out.status.success());
+    let out = run(&["delete", "events", "ev-2"]);
+    assert!(!out.status.success());
+
+    let conn = rusqlite::Connection::open(tmp_dir.join("truth.db")).unwrap();
+    let count = |sql: &str| conn.query_row(sql, [], |r| r.get::<_, i64>(0)).unwrap();
+    assert_eq!(count("SELECT COUNT(*) FROM truth_events"), 1);
+    assert_eq!(count("SELECT COUNT(*) FROM statements"), 0);
+    assert_eq!(count("SELECT COUNT(*) FROM tombstones WHERE reason = 'duplicate' AND record_id = 'ev-1'"), 1);
+}
@@ -0,0 +1,67 @@
+#[test]
+fn evidence_add_list_verify() {
+    use std::process::Command;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // isolated HOME and working directory
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let tmp_home = std::env::temp_dir().join(format!("truthctl-test-home-evidence-{}", stamp));
+    std::fs::create_dir_all(&tmp_home).unwrap();
+    let tmp_dir = std::env::temp_dir().join(format!("truthctl-test-dir-evidence-{}", stamp));
+    std::fs::create_dir_all(&tmp_dir).unwrap();
+
+    let bin = env!("CARGO_BIN_EXE_truthctl");
+    let run = |args: &[&str]| {
+        Command::new(bin)
+            .args(args)
+            .env("HOME", &tmp_home)
+            .current_dir(&tmp_dir)
+            .output()
+            .expect("run truthctl")
+    };
+    let ok = |out: std::process::Output| {
+        assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+
+    ok(run(&["keys", "import", &"33".repeat(32), &"44".repeat(32)]));
+    ok(run(&["db", "migrate"]));
+    let author = hex::encode(ed25519_dalek::SigningKey::from_bytes(&[0x33; 32]).verifying_key().as_bytes());
+    let conn = rusqlite::Connection::open(tmp_dir.join("truth.db")).unwrap();
+    // the knowledge base is not seeded, so context 1 does not exist
+    conn.execute_batch("PRAGMA foreign_keys = OFF;").unwrap();
+    conn.execute(
+        "INSERT INTO truth_events (id, uid, description, context_id, vector, corrected, timestamp_start, code, public_key)
+         VALUES (1, 'ev-1', 'outage', 1, 1, 0, 1, 1, ?1)",
+        [&author],
+    )
+    .unwrap();
+    conn.execute("INSERT INTO statements (id, uid, event_id, text, created_at, updated_at) VALUES (1, 'st-1', 1, 'it was down', 1, 1)", []).unwrap();
+    drop(conn);
+    std::fs::write(tmp_dir.join("nginx.log"), "12:00 502 Bad Gateway\n").unwrap();
+
+    let stdout = ok(run(&["evidence", "add", "event", "1", "--file", "nginx.log", "--note", "proxy log"]));
+    assert!(stdout.contains("Attached evidence") && stdout.contains("ev-1"), "{}", stdout);
+    ok(run(&["evidence", "add", "statement", "st-1", "--url", "https://status.example/incident"]));
+    assert!(!run(&["evidence", "add", "event", "ev-9", "--url", "https://status.example/incident"]).status.success());
+    assert!(!run(&["evidence", "add", "event", "1"]).status.success());
+
+    let stdout = ok(run(&["evidence", "list", "1"]));
+    assert!(stdout.contains("nginx.log") && stdout.contains("proxy log") && stdout.contains("https://status.example/incident"), "{}", stdout);
+    assert!(!stdout.contains("not local"), "{}", stdout);
+    let stdout = ok(run(&["evidence", "verify"]));
+    assert!(stdout.contains("Evidence: 2, blobs not stored locally: 0"), "{}", stdout);
+
+    // a tampered blob fails verification
+    let blob = std::fs::read_dir(tmp_dir.join("blobs")).unwrap().next().unwrap().unwrap().path();
+    let blob = std::fs::read_dir(blob).unwrap().next().unwrap().unwrap().path();
+    std::fs::write(&blob, "edited").unwrap();
+    let out = run(&["evidence", "verify"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stdout).contains("corrupted"));
+
+    // deleting the event removes its evidence and that of its statements
+    ok(run(&["delete", "events", "1"]));
+    let conn = rusqlite::Connection::open(tmp_dir.join("truth.db")).unwrap();
+    assert_eq!(conn.query_row("SELECT COUNT(*) FROM evidence", [], |r| r.get::<_, i64>(0)).unwrap(), 0);
+}
@@ -0,0 +1,95 @@
+#[test]
+fn kb_pack_import_diff_and_export() {
+    use std::process::Command;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // isolated HOME and working directory
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let tmp_home = std::env::temp_dir().join(format!("truthctl-test-home-kb-pack-{}", stamp));
+    std::fs::create_dir_all(&tmp_home).unwrap();
+    let tmp_dir = std::env::temp_dir().join(format!("truthctl-test-dir-kb-pack-{}", stamp));
+    std::fs::create_dir_all(&tmp_dir).unwrap();
+
+    let bin = env!("CARGO_BIN_EXE_truthctl");
+    let run = |args: &[&str]| {
+        Command::new(bin)
+            .args(args)
+            .env("HOME", &tmp_home)
+            .current_dir(&tmp_dir)
+            .output()
+            .expect("run truthctl")
+    };
+    let ok = |out: std::process::Output| {
+        assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+
+    ok(run(&["keys", "import", &"33".repeat(32), &"44".repeat(32)]));
+    ok(run(&["db", "migrate"]));
+    let admin = hex::encode(ed25519_dalek::SigningKey::from_bytes(&[0x33; 32]).verifying_key().as_bytes());
+    let conn = rusqlite::Connection::open(tmp_dir.join("truth.db")).unwrap();
+    conn.execute("INSERT INTO users (pubkey, role, trust_score, last_updated) VALUES (?1, 'admin', 0.0, 0)", [&admin]).unwrap();
+    drop(conn);
+
+    std::fs::write(
+        tmp_dir.join("medicine.toml"),
+        r#"
+[pack]
+name = "medicine"
+default_locale = "en"
+locales = ["en", "ru"]
+
+[[category]]
+key = "medicine"
+name = { en = "Medicine", ru = "Медицина" }
+
+[[effect]]
+key = "harm"
+quality = false
+name = { en = "Harm to health", ru = "Вред здоровью" }
+
+[[context]]
+key = "miracle_cure"
+category = "medicine"
+effect = "harm"
+name = { en = "Miracle cure", ru = "Чудо-лекарство" }
+
+[[question]]
+key = "trial"
+kind = "YesNo"
+weight = 0.4
+truth_bias = true
+contexts = ["miracle_cure"]
+text = { en = "Was there a controlled trial?", ru = "Было ли контролируемое испытание?" }
+"#,
+    )
+    .unwrap();
+    std::fs::write(tmp_dir.join("broken.json"), r#"{"pack": {"name": "broken", "default_locale": "en"}, "context": [{"key": "x", "forma": "none", "name": {"en": "X"}}]}"#).unwrap();
+
+    let out = run(&["kb", "diff", "broken.json"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("forma none is not in the pack"));
+
+    let stdout = ok(run(&["kb", "diff", "medicine.toml", "--locale", "ru"]));
+    assert!(stdout.contains("+ context") && stdout.contains("Чудо-лекарство"), "{}", stdout);
+    let stdout = ok(run(&["kb", "import", "medicine.toml", "--locale", "ru"]));
+    assert!(stdout.contains("3 added, 0 updated, 0 unchanged, 1 questions"), "{}", stdout);
+    let stdout = ok(run(&["kb", "diff", "medicine.toml", "--locale", "ru"]));
+    assert!(stdout.contains("0 added, 0 changed, 3 unchanged, 0 questions"), "{}", stdout);
+    let stdout = ok(run(&["kb", "import", "medicine.toml"]));
+    assert!(stdout.contains("0 added, 3 updated"), "{}", stdout);
+    let stdout = ok(run(&["kb", "list", "context"]));
+    assert!(stdout.contains("Miracle cure"));
+
+    ok(run(&["kb", "export", "exported.json", "--name", "node"]));
+    let exported: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(tmp_dir.join("exported.json")).unwrap()).unwrap();
+    assert_eq!(exported["pack"]["name"], "node");
+    assert_eq!(exported["question"][0]["key"], "trial");
+    let stdout = ok(run(&["kb", "diff", "exported.json"]));
+    assert!(stdout.contains("0 added, 0 changed"), "{}", stdout);
+
+    let conn = rusqlite::Connection::open(tmp_dir.join("truth.db")).unwrap();
+    let count = |sql: &str| conn.query_row(sql, [], |r| r.get::<_, i64>(0)).unwrap();
+    assert_eq!(count("SELECT COUNT(*) FROM context WHERE name = 'Miracle cure' AND signature IS NOT NULL"), 1);
+    assert_eq!(count("SELECT COUNT(*) FROM expert_questions WHERE pack = 'medicine'"), 1);
+}
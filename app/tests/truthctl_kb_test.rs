@@ -0,0 +1,72 @@
+#[test]
+fn kb_add_edit_deprecate_and_list() {
+    use std::process::Command;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // isolated HOME and working directory
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let tmp_home = std::env::temp_dir().join(format!("truthctl-test-home-kb-{}", stamp));
+    std::fs::create_dir_all(&tmp_home).unwrap();
+    let tmp_dir = std::env::temp_dir().join(format!("truthctl-test-dir-kb-{}", stamp));
+    std::fs::create_dir_all(&tmp_dir).unwrap();
+
+    let bin = env!("CARGO_BIN_EXE_truthctl");
+    let run = |args: &[&str]| {
+        Command::new(bin)
+            .args(args)
+            .env("HOME", &tmp_home)
+            .current_dir(&tmp_dir)
+            .output()
+            .expect("run truthctl")
+    };
+    // "✅ Added cause #<id>: <name>" → id
+    let added_id = |out: &std::process::Output| {
+        assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+        let stdout = String::from_utf8_lossy(&out.stdout).to_string();
+        let rest = &stdout[stdout.find('#').expect("id in output") + 1..];
+        rest[..rest.find(':').unwrap()].to_string()
+    };
+
+    let out = run(&["keys", "import", &"11".repeat(32), &"22".repeat(32)]);
+    assert!(out.status.success());
+    let admin = hex::encode(ed25519_dalek::SigningKey::from_bytes(&[0x11; 32]).verifying_key().as_bytes());
+    let out = run(&["db", "migrate"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+
+    // edits require the admin role
+    let out = run(&["kb", "add", "category", "Science"]);
+    assert!(!out.status.success());
+    let conn = rusqlite::Connection::open(tmp_dir.join("truth.db")).unwrap();
+    conn.execute("INSERT INTO users (pubkey, role, trust_score, last_updated) VALUES (?1, 'admin', 0.0, 0)", [&admin]).unwrap();
+    drop(conn);
+
+    let category = added_id(&run(&["kb", "add", "category", "Science", "--description", "Research and experiments"]));
+    let out = run(&["kb", "add", "cause", "Curiosity"]);
+    assert!(!out.status.success(), "quality is required for causes");
+    let cause = added_id(&run(&["kb", "add", "cause", "Curiosity", "--quality", "true"]));
+    let out = run(&["kb", "add", "context", "Dangling", "--forma", "999"]);
+    assert!(!out.status.success());
+    let context = added_id(&run(&["kb", "add", "context", "Lab report", "--category", &category, "--cause", &cause]));
+
+    // the cause is used by an active context
+    let out = run(&["kb", "deprecate", "cause", &cause]);
+    assert!(!out.status.success());
+    let out = run(&["kb", "edit", "context", &context, "--name", "Lab reports"]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    let out = run(&["kb", "deprecate", "context", &context]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    let out = run(&["kb", "deprecate", "cause", &cause]);
+    assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+
+    let out = run(&["kb", "list", "context"]);
+    assert!(out.status.success());
+    assert!(!String::from_utf8_lossy(&out.stdout).contains("Lab reports"));
+    let out = run(&["kb", "list", "context", "--all"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("Lab reports") && stdout.contains("(deprecated)"));
+
+    let conn = rusqlite::Connection::open(tmp_dir.join("truth.db")).unwrap();
+    let count = |sql: &str| conn.query_row(sql, [], |r| r.get::<_, i64>(0)).unwrap();
+    assert_eq!(count("SELECT COUNT(*) FROM context WHERE deprecated_at IS NOT NULL AND signature IS NOT NULL"), 1);
+    assert_eq!(count("SELECT COUNT(*) FROM sync_log WHERE table_name IN ('category', 'cause', 'context')"), 6);
+}
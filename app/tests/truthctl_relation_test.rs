@@ -0,0 +1,62 @@
+#[test]
+fn relation_add_list_and_fold() {
+    use std::process::Command;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // isolated HOME and working directory
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    let tmp_home = std::env::temp_dir().join(format!("truthctl-test-home-relation-{}", stamp));
+    std::fs::create_dir_all(&tmp_home).unwrap();
+    let tmp_dir = std::env::temp_dir().join(format!("truthctl-test-dir-relation-{}", stamp));
+    std::fs::create_dir_all(&tmp_dir).unwrap();
+
+    let bin = env!("CARGO_BIN_EXE_truthctl");
+    let run = |args: &[&str]| {
+        Command::new(bin)
+            .args(args)
+            .env("HOME", &tmp_home)
+            .current_dir(&tmp_dir)
+            .output()
+            .expect("run truthctl")
+    };
+    let ok = |out: std::process::Output| {
+        assert!(out.status.success(), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+
+    ok(run(&["keys", "import", &"33".repeat(32), &"44".repeat(32)]));
+    ok(run(&["db", "migrate"]));
+    let author = hex::encode(ed25519_dalek::SigningKey::from_bytes(&[0x33; 32]).verifying_key().as_bytes());
+    let conn = rusqlite::Connection::open(tmp_dir.join("truth.db")).unwrap();
+    // the knowledge base is not seeded, so context 1 does not exist
+    conn.execute_batch("PRAGMA foreign_keys = OFF;").unwrap();
+    for (id, uid, key) in [(1, "ev-1", author.as_str()), (2, "ev-2", author.as_str()), (3, "ev-3", "someone-else")] {
+        conn.execute(
+            "INSERT INTO truth_events (id, uid, description, context_id, vector, corrected, timestamp_start, code, public_key)
+             VALUES (?1, ?2, 'bridge closed', 1, 1, 0, 1, 1, ?3)",
+            rusqlite::params![id, uid, key],
+        )
+        .unwrap();
+    }
+    conn.execute("INSERT INTO statements (id, uid, event_id, text, created_at, updated_at) VALUES (1, 'st-1', 2, 'seen it', 1, 1)", []).unwrap();
+    drop(conn);
+
+    // only the source event author may mark it a duplicate; anyone may flag a contradiction
+    assert!(!run(&["relation", "add", "3", "duplicate_of", "1"]).status.success());
+    ok(run(&["relation", "add", "3", "contradicts", "1", "--note", "photos show it open"]));
+    assert!(!run(&["relation", "add", "1", "same_as", "2"]).status.success());
+    assert!(!run(&["relation", "add", "1", "supports", "ev-9"]).status.success());
+
+    let stdout = ok(run(&["relation", "add", "2", "duplicate_of", "ev-1"]));
+    assert!(stdout.contains("Recorded relation") && stdout.contains("Canonical event: ev-1"), "{}", stdout);
+
+    let stdout = ok(run(&["relation", "list", "ev-2"]));
+    assert!(stdout.contains("Canonical event: ev-1") && stdout.contains("duplicate_of"), "{}", stdout);
+    let stdout = ok(run(&["relation", "list", "1"]));
+    assert!(stdout.contains("contradicts") && stdout.contains("photos show it open"), "{}", stdout);
+    assert!(!stdout.contains("Canonical event"), "{}", stdout);
+
+    // the duplicate's statement moved to the canonical event
+    let conn = rusqlite::Connection::open(tmp_dir.join("truth.db")).unwrap();
+    assert_eq!(conn.query_row("SELECT event_id FROM statements WHERE uid = 'st-1'", [], |r| r.get::<_, i64>(0)).unwrap(), 1);
+}
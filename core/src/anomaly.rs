@@ -0,0 +1,225 @@
+use crate::models::{Alert, TrustHistoryEntry};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Ключ считается свежим, если впервые замечен не раньше чем столько секунд назад
+pub const FRESH_KEY_WINDOW_SECS: i64 = 7 * 24 * 3600;
+/// Минимальное число свежих валидаторов одного автора для подозрения на сговор
+pub const COLLUSION_MIN_FRESH_VALIDATORS: usize = 3;
+/// Минимальная доля свежих ключей среди валидаторов автора
+pub const COLLUSION_MIN_FRESH_SHARE: f32 = 0.6;
+/// Рост trust_score за одно слияние, считающийся скачком
+pub const TRUST_SPIKE_THRESHOLD: f32 = 0.3;
+/// Глубина просмотра trust_history при поиске скачков
+pub const ANOMALY_LOOKBACK_SECS: i64 = 7 * 24 * 3600;
+/// Минимум положительных оценок на ребре цикла взаимных валидаций
+pub const RECIPROCAL_MIN_VALIDATIONS: u32 = 2;
+/// Минимальная доля оценок участников, замкнутых внутри цикла
+pub const RECIPROCAL_MIN_INSULARITY: f32 = 0.5;
+
+pub const ALERT_COLLUSION_RING: &str = "collusion_ring";
+pub const ALERT_TRUST_SPIKE: &str = "trust_spike";
+pub const ALERT_RECIPROCAL_LOOP: &str = "reciprocal_loop";
+
+/// Детерминированный id аномалии: повторное обнаружение того же паттерна не создаёт дубликат
+pub fn alert_id(kind: &str, parts: &[&str]) -> String {
+    let hash = blake3::hash(format!("{}:{}", kind, parts.join(",")).as_bytes()).to_hex();
+    format!("alert-{}", &hash[..16])
+}
+
+/// Группа свежих ключей, положительно оценивающих одного автора.
+/// edges — (валидатор, автор, число положительных оценок), first_seen — первое появление ключа.
+pub fn detect_collusion_rings(
+    edges: &[(String, String, u32)],
+    first_seen: &HashMap<String, i64>,
+    now_ts: i64,
+) -> Vec<Alert> {
+    let mut by_author: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for (validator, author, positive) in edges {
+        if *positive > 0 && validator != author {
+            by_author.entry(author.as_str()).or_default().insert(validator.as_str());
+        }
+    }
+
+    let mut out = Vec::new();
+    for (author, validators) in by_author {
+        let fresh: Vec<&str> = validators
+            .iter()
+            .copied()
+            .filter(|v| first_seen.get(*v).map(|t| *t >= now_ts - FRESH_KEY_WINDOW_SECS).unwrap_or(false))
+            .collect();
+        let share = fresh.len() as f32 / validators.len() as f32;
+        if fresh.len() < COLLUSION_MIN_FRESH_VALIDATORS || share < COLLUSION_MIN_FRESH_SHARE {
+            continue;
+        }
+        let mut parts = vec![author];
+        parts.extend(fresh.iter().copied());
+        out.push(Alert {
+            id: alert_id(ALERT_COLLUSION_RING, &parts),
+            kind: ALERT_COLLUSION_RING.to_string(),
+            subject: author.to_string(),
+            related: fresh.iter().map(|v| v.to_string()).collect(),
+            severity: share,
+            details: format!("{} of {} validators are keys first seen within the fresh window", fresh.len(), validators.len()),
+            detected_at: now_ts,
+            acknowledged_at: None,
+        });
+    }
+    out
+}
+
+/// Резкий рост trust_score узла после одного слияния удалённых оценок.
+/// history — записи trust_history любых узлов; внутри узла упорядочиваются по времени.
+pub fn detect_trust_spikes(history: &[TrustHistoryEntry], now_ts: i64) -> Vec<Alert> {
+    let mut by_node: BTreeMap<&str, Vec<&TrustHistoryEntry>> = BTreeMap::new();
+    for h in history {
+        by_node.entry(h.node_id.as_str()).or_default().push(h);
+    }
+
+    let mut out = Vec::new();
+    for (node, mut points) in by_node {
+        points.sort_by_key(|h| (h.recorded_at, h.id));
+        for pair in points.windows(2) {
+            let (prev, cur) = (pair[0], pair[1]);
+            let jump = cur.trust_score - prev.trust_score;
+            if cur.source != "merge" || jump < TRUST_SPIKE_THRESHOLD {
+                continue;
+            }
+            let id_part = cur.id.to_string();
+            out.push(Alert {
+                id: alert_id(ALERT_TRUST_SPIKE, &[node, &id_part]),
+                kind: ALERT_TRUST_SPIKE.to_string(),
+                subject: node.to_string(),
+                related: cur.sender.iter().cloned().collect(),
+                severity: jump.min(1.0),
+                details: format!("trust {:.2} -> {:.2} after a single merge", prev.trust_score, cur.trust_score),
+                detected_at: now_ts,
+                acknowledged_at: None,
+            });
+        }
+    }
+    out
+}
+
+/// Циклы взаимных положительных валидаций длины 2 и 3 (A→B→A, A→B→C→A),
+/// в которых каждый участник отдаёт внутрь цикла не меньше RECIPROCAL_MIN_INSULARITY своих оценок.
+pub fn detect_reciprocal_loops(edges: &[(String, String, u32)], now_ts: i64) -> Vec<Alert> {
+    let mut strong: BTreeMap<&str, BTreeMap<&str, u32>> = BTreeMap::new();
+    let mut totals: HashMap<&str, u32> = HashMap::new();
+    for (validator, author, positive) in edges {
+        if validator == author {
+            continue;
+        }
+        *totals.entry(validator.as_str()).or_insert(0) += *positive;
+        if *positive >= RECIPROCAL_MIN_VALIDATIONS {
+            strong.entry(validator.as_str()).or_default().insert(author.as_str(), *positive);
+        }
+    }
+    let weight = |a: &str, b: &str| strong.get(a).and_then(|m| m.get(b)).copied();
+
+    // Циклы в канонической форме: начинаются с наименьшего узла
+    let mut cycles: BTreeSet<Vec<&str>> = BTreeSet::new();
+    for (&a, outs) in &strong {
+        for &b in outs.keys() {
+            if b <= a {
+                continue;
+            }
+            if weight(b, a).is_some() {
+                cycles.insert(vec![a, b]);
+            }
+            if let Some(b_outs) = strong.get(b) {
+                for &c in b_outs.keys() {
+                    if c > a && c != b && weight(c, a).is_some() {
+                        cycles.insert(vec![a, b, c]);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    for cycle in cycles {
+        // Замкнутость: доля положительных оценок участника, направленных на следующего по циклу
+        let insularity = (0..cycle.len())
+            .map(|i| {
+                let (from, to) = (cycle[i], cycle[(i + 1) % cycle.len()]);
+                let inside = weight(from, to).unwrap_or(0) as f32;
+                inside / totals.get(from).copied().unwrap_or(1).max(1) as f32
+            })
+            .fold(f32::MAX, f32::min);
+        if insularity < RECIPROCAL_MIN_INSULARITY {
+            continue;
+        }
+        out.push(Alert {
+            id: alert_id(ALERT_RECIPROCAL_LOOP, &cycle),
+            kind: ALERT_RECIPROCAL_LOOP.to_string(),
+            subject: cycle[0].to_string(),
+            related: cycle[1..].iter().map(|v| v.to_string()).collect(),
+            severity: insularity.min(1.0),
+            details: format!("validation loop {}", cycle.join(" -> ")),
+            detected_at: now_ts,
+            acknowledged_at: None,
+        });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(v: &str) -> String { v.to_string() }
+
+    #[test]
+    fn collusion_ring_of_fresh_keys() {
+        let now = 1_000_000;
+        let edges = vec![
+            (s("f1"), s("author"), 2),
+            (s("f2"), s("author"), 1),
+            (s("f3"), s("author"), 1),
+            (s("old"), s("author"), 1),
+            (s("old"), s("honest"), 3),
+            (s("f1"), s("honest"), 1),
+        ];
+        let mut first_seen = HashMap::new();
+        for k in ["f1", "f2", "f3"] { first_seen.insert(s(k), now - 3600); }
+        first_seen.insert(s("old"), now - FRESH_KEY_WINDOW_SECS - 1);
+
+        let alerts = detect_collusion_rings(&edges, &first_seen, now);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].subject, "author");
+        assert_eq!(alerts[0].related, vec![s("f1"), s("f2"), s("f3")]);
+        assert!((alerts[0].severity - 0.75).abs() < 1e-6);
+        // Повторное обнаружение даёт тот же id
+        assert_eq!(alerts[0].id, detect_collusion_rings(&edges, &first_seen, now + 10)[0].id);
+    }
+
+    #[test]
+    fn trust_spike_only_after_merge() {
+        let h = |id: i64, trust: f32, source: &str, at: i64| TrustHistoryEntry {
+            id, node_id: s("n"), trust_score: trust, source: s(source), sender: Some(s("peer")), recorded_at: at,
+        };
+        let history = vec![h(1, 0.1, "recalc", 10), h(2, 0.6, "merge", 20), h(3, 0.1, "recalc", 30), h(4, 0.7, "recalc", 40)];
+        let alerts = detect_trust_spikes(&history, 50);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].related, vec![s("peer")]);
+        assert!((alerts[0].severity - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn reciprocal_loops_require_insularity() {
+        let edges = vec![
+            (s("a"), s("b"), 3),
+            (s("b"), s("a"), 2),
+            (s("x"), s("y"), 2),
+            (s("y"), s("z"), 2),
+            (s("z"), s("x"), 2),
+            // c и d взаимно оценивают друг друга, но в основном работают с другими
+            (s("c"), s("d"), 2),
+            (s("d"), s("c"), 2),
+            (s("c"), s("e"), 10),
+        ];
+        let alerts = detect_reciprocal_loops(&edges, 0);
+        let loops: Vec<(String, Vec<String>)> = alerts.into_iter().map(|a| (a.subject, a.related)).collect();
+        assert_eq!(loops, vec![(s("a"), vec![s("b")]), (s("x"), vec![s("y"), s("z")])]);
+    }
+}
@@ -0,0 +1,284 @@
+use crate::models::Statement;
+use crate::storage;
+use crate::CoreError;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Глубже этой вложенности ответы не раскрываются: поддерево становится отдельным корнем
+pub const MAX_DEPTH: usize = 64;
+
+/// Узел дерева аргументов: утверждение, его итоговая оценка и ответы на него (старые первыми)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArgumentNode {
+    #[serde(flatten)]
+    pub statement: Statement,
+    /// Оценка с учётом поддержек и опровержений, -1..1; None — оценок нет ни у утверждения, ни у его ответов
+    pub score: Option<f32>,
+    pub replies: Vec<ArgumentNode>,
+}
+
+/// Дерево аргументов события
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArgumentTree {
+    pub event_uid: String,
+    /// Среднее итоговых оценок корней, у которых она есть; это оценка события по утверждениям
+    pub score: Option<f32>,
+    pub roots: Vec<ArgumentNode>,
+}
+
+/// Проверить пару (родитель, отношение): задаются вместе, отношение — одно из Statement::RELATIONS
+pub fn check_relation(parent_uid: Option<&str>, relation: Option<&str>) -> Result<(), CoreError> {
+    match (parent_uid, relation) {
+        (None, None) => Ok(()),
+        (Some(_), Some(r)) if Statement::RELATIONS.contains(&r) => Ok(()),
+        (Some(_), Some(r)) => Err(CoreError::InvalidArg(format!(
+            "unknown relation {}, expected one of: {}",
+            r,
+            Statement::RELATIONS.join(", ")
+        ))),
+        _ => Err(CoreError::InvalidArg("parent and relation must be given together".into())),
+    }
+}
+
+/// Проверить ответ перед вставкой: отношение допустимо, родитель существует и относится к тому же событию
+pub fn check_reply(conn: &Connection, event_id: i64, parent_uid: Option<&str>, relation: Option<&str>) -> Result<(), CoreError> {
+    check_relation(parent_uid, relation)?;
+    let Some(parent_uid) = parent_uid else { return Ok(()) };
+    let parent_event: i64 = conn
+        .query_row("SELECT event_id FROM statements WHERE uid = ?1", params![parent_uid], |r| r.get(0))
+        .optional()?
+        .ok_or_else(|| CoreError::NotFound(format!("statement {}", parent_uid)))?;
+    if parent_event != event_id {
+        return Err(CoreError::InvalidArg("parent statement belongs to another event".into()));
+    }
+    Ok(())
+}
+
+/// Итоговая оценка утверждения по схеме DF-QuAD. База — truth_score, переведённая в 0..1 (без оценки — 0.5).
+/// Поддержки и опровержения с положительной итоговой оценкой складываются как вероятности
+/// и сдвигают базу к 1 или к 0 на разность сил. Уточнения и недостоверные ответы на родителя не влияют.
+fn strength(truth_score: Option<f32>, replies: &[ArgumentNode]) -> Option<f32> {
+    let mut informed = truth_score.is_some();
+    let (mut support, mut attack) = (0.0f32, 0.0f32);
+    for reply in replies {
+        let Some(score) = reply.score else { continue };
+        let weight = score.max(0.0);
+        match reply.statement.relation.as_deref() {
+            Some("supports") => support = 1.0 - (1.0 - support) * (1.0 - weight),
+            Some("rebuts") => attack = 1.0 - (1.0 - attack) * (1.0 - weight),
+            _ => continue,
+        }
+        informed = true;
+    }
+    if !informed {
+        return None;
+    }
+    let base = (truth_score.unwrap_or(0.0).clamp(-1.0, 1.0) + 1.0) / 2.0;
+    let value = if attack >= support {
+        base - base * (attack - support)
+    } else {
+        base + (1.0 - base) * (support - attack)
+    };
+    Some(value * 2.0 - 1.0)
+}
+
+fn grow(statement: Statement, children: &mut HashMap<String, Vec<Statement>>, depth: usize) -> ArgumentNode {
+    let replies: Vec<ArgumentNode> = if depth < MAX_DEPTH {
+        children
+            .remove(&statement.uid)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|c| grow(c, children, depth + 1))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let score = strength(statement.truth_score, &replies);
+    ArgumentNode { statement, score, replies }
+}
+
+/// Самый старый ещё не размещённый ответ
+fn take_oldest(children: &mut HashMap<String, Vec<Statement>>) -> Option<Statement> {
+    let (key, idx) = children
+        .iter()
+        .flat_map(|(k, list)| list.iter().enumerate().map(move |(i, s)| ((s.created_at, &s.uid), k, i)))
+        .min_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, k, i)| (k.clone(), i))?;
+    let list = children.get_mut(&key)?;
+    let statement = list.remove(idx);
+    if list.is_empty() {
+        children.remove(&key);
+    }
+    Some(statement)
+}
+
+/// Построить дерево из утверждений одного события и посчитать оценки снизу вверх.
+/// Утверждения без родителя или с неизвестным родителем — корни; цикл (возможен, если пир
+/// переписал родителя) и слишком глубокие цепочки разрываются на самом старом оставшемся ответе.
+pub fn build(event_uid: &str, mut statements: Vec<Statement>) -> ArgumentTree {
+    statements.sort_by(|a, b| (a.created_at, &a.uid).cmp(&(b.created_at, &b.uid)));
+    let uids: HashSet<String> = statements.iter().map(|s| s.uid.clone()).collect();
+    let mut children: HashMap<String, Vec<Statement>> = HashMap::new();
+    let mut top = Vec::new();
+    for s in statements {
+        match s.parent_uid.clone().filter(|p| uids.contains(p) && *p != s.uid) {
+            Some(parent) => children.entry(parent).or_default().push(s),
+            None => top.push(s),
+        }
+    }
+    let mut roots: Vec<ArgumentNode> = top.into_iter().map(|s| grow(s, &mut children, 0)).collect();
+    while let Some(s) = take_oldest(&mut children) {
+        roots.push(grow(s, &mut children, 0));
+    }
+    let scored: Vec<f32> = roots.iter().filter_map(|r| r.score).collect();
+    let score = (!scored.is_empty()).then(|| scored.iter().sum::<f32>() / scored.len() as f32);
+    ArgumentTree { event_uid: event_uid.to_string(), score, roots }
+}
+
+/// Дерево аргументов события по uid; None — события нет
+pub fn tree_for_event(conn: &Connection, event_uid: &str) -> Result<Option<ArgumentTree>, CoreError> {
+    let Some(event) = storage::get_truth_event_by_uid(conn, event_uid)? else { return Ok(None) };
+    let statements = storage::get_statements_for_event(conn, event.id)?;
+    Ok(Some(build(&event.uid, statements)))
+}
+
+/// Пересчитать argument_scores для всех событий с утверждениями. Подписи не расшифровываются,
+/// поэтому пересчёт работает и в заблокированной БД. Возвращает число событий с оценкой.
+pub fn refresh_scores(conn: &Connection, ts: i64) -> Result<usize, CoreError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM statements s JOIN truth_events e ON e.id = s.event_id",
+        storage::STATEMENT_COLUMNS
+    ))?;
+    let mut by_event: HashMap<String, Vec<Statement>> = HashMap::new();
+    for s in stmt.query_map([], storage::map_statement)? {
+        let s = s?;
+        by_event.entry(s.event_uid.clone()).or_default().push(s);
+    }
+
+    conn.execute("DELETE FROM argument_scores", [])?;
+    let mut ins = conn.prepare("INSERT INTO argument_scores (event_uid, score, statements, updated_at) VALUES (?1, ?2, ?3, ?4)")?;
+    let mut scored = 0;
+    for (event_uid, statements) in by_event {
+        let count = statements.len() as i64;
+        if let Some(score) = build(&event_uid, statements).score {
+            ins.execute(params![event_uid, score as f64, count, ts])?;
+            scored += 1;
+        }
+    }
+    Ok(scored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NewStatement, NewTruthEvent};
+
+    fn statement(uid: &str, score: Option<f32>, parent: Option<&str>, relation: Option<&str>, at: i64) -> Statement {
+        Statement {
+            id: at,
+            uid: uid.into(),
+            event_id: 1,
+            event_uid: "ev".into(),
+            text: uid.into(),
+            context: None,
+            truth_score: score,
+            created_at: at,
+            updated_at: at,
+            signature: None,
+            public_key: None,
+            parent_uid: parent.map(Into::into),
+            relation: relation.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn flat_statements_average_like_before() {
+        let tree = build("ev", vec![
+            statement("a", Some(0.5), None, None, 1),
+            statement("b", Some(1.0), None, None, 2),
+            statement("c", None, None, None, 3),
+        ]);
+        assert_eq!(tree.roots.len(), 3);
+        assert!((tree.score.unwrap() - 0.75).abs() < 1e-6);
+        assert!(build("ev", vec![statement("a", None, None, None, 1)]).score.is_none());
+    }
+
+    #[test]
+    fn supports_and_rebuts_propagate_to_roots() {
+        // Опровержение, само опровергнутое достоверным ответом, почти не ослабляет корень
+        let tree = build("ev", vec![
+            statement("claim", Some(0.6), None, None, 1),
+            statement("rebut", Some(0.8), Some("claim"), Some("rebuts"), 2),
+            statement("counter", Some(1.0), Some("rebut"), Some("rebuts"), 3),
+            statement("note", Some(-1.0), Some("claim"), Some("clarifies"), 4),
+        ]);
+        let claim = &tree.roots[0];
+        assert_eq!(tree.roots.len(), 1);
+        assert_eq!(claim.replies.len(), 2);
+        assert!(claim.replies[0].score.unwrap() <= -0.99);
+        assert!((claim.score.unwrap() - 0.6).abs() < 1e-6);
+
+        // Достоверное опровержение тянет корень вниз, поддержка — вверх
+        let attacked = build("ev", vec![
+            statement("claim", Some(0.6), None, None, 1),
+            statement("rebut", Some(0.8), Some("claim"), Some("rebuts"), 2),
+        ]);
+        assert!(attacked.score.unwrap() < 0.6);
+        let supported = build("ev", vec![
+            statement("claim", None, None, None, 1),
+            statement("proof", Some(1.0), Some("claim"), Some("supports"), 2),
+        ]);
+        assert!((supported.score.unwrap() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cycles_and_orphans_become_roots() {
+        let tree = build("ev", vec![
+            statement("a", Some(0.2), Some("b"), Some("supports"), 1),
+            statement("b", Some(0.4), Some("a"), Some("supports"), 2),
+            statement("orphan", Some(-0.5), Some("gone"), Some("rebuts"), 3),
+        ]);
+        let roots: Vec<&str> = tree.roots.iter().map(|r| r.statement.uid.as_str()).collect();
+        assert_eq!(roots, vec!["orphan", "a"]);
+        assert_eq!(tree.roots[1].replies[0].statement.uid, "b");
+    }
+
+    #[test]
+    fn replies_are_checked_and_scores_refreshed() {
+        let conn = storage::open_db(":memory:").unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = OFF;").unwrap();
+        let new_event = |d: &str| NewTruthEvent { description: d.into(), context_id: 1, vector: true, timestamp_start: 1, code: 1 };
+        let ev = storage::add_truth_event(&conn, new_event("flood")).unwrap();
+        let other = storage::add_truth_event(&conn, new_event("fire")).unwrap();
+        let new = |event_id, score, parent: Option<&str>, relation: Option<&str>| NewStatement {
+            event_id,
+            text: "claim".into(),
+            context: None,
+            truth_score: score,
+            parent_uid: parent.map(Into::into),
+            relation: relation.map(Into::into),
+        };
+        let root = storage::add_statement(&conn, new(ev, Some(0.6), None, None)).unwrap();
+        let root_uid = storage::get_statement(&conn, root).unwrap().unwrap().uid;
+        storage::add_statement(&conn, new(ev, Some(1.0), Some(&root_uid), Some("rebuts"))).unwrap();
+
+        assert!(matches!(storage::add_statement(&conn, new(ev, None, Some(&root_uid), Some("likes"))), Err(CoreError::InvalidArg(_))));
+        assert!(matches!(storage::add_statement(&conn, new(ev, None, Some(&root_uid), None)), Err(CoreError::InvalidArg(_))));
+        assert!(matches!(storage::add_statement(&conn, new(ev, None, Some("missing"), Some("supports"))), Err(CoreError::NotFound(_))));
+        assert!(matches!(storage::add_statement(&conn, new(other, None, Some(&root_uid), Some("supports"))), Err(CoreError::InvalidArg(_))));
+
+        let event_uid = storage::get_truth_event(&conn, ev).unwrap().unwrap().uid;
+        let tree = tree_for_event(&conn, &event_uid).unwrap().unwrap();
+        assert_eq!(tree.roots.len(), 1);
+        assert_eq!(tree.roots[0].replies[0].statement.relation.as_deref(), Some("rebuts"));
+        assert!(tree_for_event(&conn, "missing").unwrap().is_none());
+
+        assert_eq!(refresh_scores(&conn, 5).unwrap(), 1);
+        let (score, count): (f64, i64) = conn
+            .query_row("SELECT score, statements FROM argument_scores WHERE event_uid = ?1", [&event_uid], |r| Ok((r.get(0)?, r.get(1)?)))
+            .unwrap();
+        assert_eq!(count, 2);
+        assert!((score - tree.score.unwrap() as f64).abs() < 1e-6 && score < -0.5);
+    }
+}
@@ -0,0 +1,528 @@
+use crate::CoreError;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+/// Переменная окружения с парольной фразой, которой open_db разблокирует зашифрованную БД
+pub const PASSPHRASE_ENV: &str = "TRUTH_DB_PASSPHRASE";
+
+/// Колонки, значения которых хранятся зашифрованными (table, column).
+/// Refresh-токены не шифруются, а заменяются ключевым хешем: по ним нужен поиск на равенство.
+pub const SENSITIVE_COLUMNS: &[(&str, &str)] = &[
+    ("truth_events", "signature"),
+    ("statements", "signature"),
+    ("impact", "signature"),
+    ("judgments", "reasoning"),
+    ("judgments", "signature"),
+    ("sync_log", "signature"),
+];
+
+/// Ключ строки таблиц с шифруемыми колонками: (table, column) и значение этой колонки входят в AAD,
+/// поэтому шифртекст нельзя перенести в другую строку или колонку
+const ROW_KEY_COLUMNS: &[(&str, &str)] = &[
+    ("truth_events", "uid"),
+    ("statements", "uid"),
+    ("impact", "id"),
+    ("judgments", "id"),
+    ("sync_log", "id"),
+];
+
+const VALUE_PREFIX: &str = "enc1:";
+const TOKEN_PREFIX: &str = "tok1:";
+const WRAP_AAD: &[u8] = b"truth-training db key v1";
+const NODE_KEY_CONTEXT: &str = "truth-training 2024 db-at-rest node key";
+const TOKEN_KEY_CONTEXT: &str = "truth-training 2024 refresh token digest";
+const NONCE_LEN: usize = 12;
+
+/// Параметры Argon2id для новых ключей (m КиБ, t, p); записываются в БД вместе с солью
+#[cfg(not(test))]
+const ARGON2_PARAMS: (u32, u32, u32) = (19456, 2, 1);
+#[cfg(test)]
+const ARGON2_PARAMS: (u32, u32, u32) = (1024, 1, 1);
+
+/// Секрет, из которого выводится ключ-обёртка
+#[derive(Clone)]
+pub enum KeySource {
+    /// Парольная фраза (Argon2id)
+    Passphrase(String),
+    /// Приватный ключ узла из хранилища ключей, hex
+    NodeKey(String),
+}
+
+impl std::fmt::Debug for KeySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            KeySource::Passphrase(_) => "Passphrase(..)",
+            KeySource::NodeKey(_) => "NodeKey(..)",
+        })
+    }
+}
+
+/// Состояние шифрования БД
+#[derive(Debug, Clone, Serialize)]
+pub struct EncryptionStatus {
+    pub encrypted: bool,
+    pub unlocked: bool,
+    /// "argon2id$m=..,t=..,p=.." | "node-key"
+    pub kdf: Option<String>,
+    pub created_at: Option<i64>,
+    pub rekeyed_at: Option<i64>,
+}
+
+/// Ключ данных: им шифруются значения колонок, сам он хранится только обёрнутым
+#[derive(Clone)]
+struct DataKey([u8; 32]);
+
+/// Разблокированные БД процесса: путь файла → ключ данных
+fn unlocked() -> &'static RwLock<HashMap<String, DataKey>> {
+    static KEYS: OnceLock<RwLock<HashMap<String, DataKey>>> = OnceLock::new();
+    KEYS.get_or_init(Default::default)
+}
+
+/// Ключ, переданный процессом заранее (флаг CLI, файл с паролем сервера)
+fn provided() -> &'static RwLock<Option<KeySource>> {
+    static PROVIDED: OnceLock<RwLock<Option<KeySource>>> = OnceLock::new();
+    PROVIDED.get_or_init(Default::default)
+}
+
+/// Запомнить секрет, которым open_db будет разблокировать зашифрованные БД этого процесса
+pub fn provide_key(source: KeySource) {
+    *provided().write().unwrap_or_else(|e| e.into_inner()) = Some(source);
+}
+
+/// Ключ привязан к файлу: БД в памяти не шифруются
+fn db_path(conn: &Connection) -> Option<&str> {
+    conn.path().filter(|p| !p.is_empty())
+}
+
+fn data_key(conn: &Connection) -> Option<DataKey> {
+    let path = db_path(conn)?;
+    unlocked().read().unwrap_or_else(|e| e.into_inner()).get(path).cloned()
+}
+
+struct KeyRecord {
+    kdf: String,
+    salt: Vec<u8>,
+    wrapped: Vec<u8>,
+    created_at: i64,
+    rekeyed_at: Option<i64>,
+}
+
+fn load_record(conn: &Connection) -> Result<Option<KeyRecord>, CoreError> {
+    let exists = conn
+        .prepare_cached("SELECT 1 FROM sqlite_master WHERE type='table' AND name='db_encryption'")?
+        .exists([])?;
+    if !exists {
+        return Ok(None);
+    }
+    let row = conn
+        .prepare_cached("SELECT kdf, salt, wrapped_key, created_at, rekeyed_at FROM db_encryption WHERE id = 1")?
+        .query_row([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?, r.get(3)?, r.get(4)?)))
+        .optional()?;
+    row.map(|(kdf, salt, wrapped, created_at, rekeyed_at)| {
+        Ok(KeyRecord { kdf, salt: from_hex(&salt)?, wrapped: from_hex(&wrapped)?, created_at, rekeyed_at })
+    })
+    .transpose()
+}
+
+pub fn is_encrypted(conn: &Connection) -> Result<bool, CoreError> {
+    Ok(load_record(conn)?.is_some())
+}
+
+pub fn status(conn: &Connection) -> Result<EncryptionStatus, CoreError> {
+    let rec = load_record(conn)?;
+    Ok(EncryptionStatus {
+        encrypted: rec.is_some(),
+        unlocked: rec.is_some() && data_key(conn).is_some(),
+        kdf: rec.as_ref().map(|r| r.kdf.clone()),
+        created_at: rec.as_ref().map(|r| r.created_at),
+        rekeyed_at: rec.and_then(|r| r.rekeyed_at),
+    })
+}
+
+fn kdf_name(source: &KeySource) -> String {
+    match source {
+        KeySource::Passphrase(_) => {
+            let (m, t, p) = ARGON2_PARAMS;
+            format!("argon2id$m={},t={},p={}", m, t, p)
+        }
+        KeySource::NodeKey(_) => "node-key".to_string(),
+    }
+}
+
+/// Ключ-обёртка из секрета по записанному kdf
+fn derive_kek(source: &KeySource, kdf: &str, salt: &[u8]) -> Result<[u8; 32], CoreError> {
+    let mut out = [0u8; 32];
+    match (source, kdf) {
+        (KeySource::Passphrase(pass), kdf) if kdf.starts_with("argon2id$") => {
+            let mut nums = kdf["argon2id$".len()..].split(',').map(|kv| kv.split_once('=').and_then(|(_, v)| v.parse::<u32>().ok()));
+            let (m, t, p) = match (nums.next().flatten(), nums.next().flatten(), nums.next().flatten()) {
+                (Some(m), Some(t), Some(p)) => (m, t, p),
+                _ => return Err(CoreError::InvalidArg(format!("bad kdf parameters '{}'", kdf))),
+            };
+            let params = argon2::Params::new(m, t, p, Some(32)).map_err(|e| CoreError::InvalidArg(e.to_string()))?;
+            argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+                .hash_password_into(pass.as_bytes(), salt, &mut out)
+                .map_err(|e| CoreError::InvalidArg(e.to_string()))?;
+        }
+        (KeySource::NodeKey(key), "node-key") => {
+            let mut hasher = blake3::Hasher::new_derive_key(NODE_KEY_CONTEXT);
+            hasher.update(salt);
+            hasher.update(key.trim().as_bytes());
+            out = *hasher.finalize().as_bytes();
+        }
+        (_, kdf) => {
+            return Err(CoreError::Locked(format!("database key is derived with '{}', a different secret kind was given", kdf)))
+        }
+    }
+    Ok(out)
+}
+
+fn wrap(kek: &[u8; 32], dek: &DataKey) -> Result<Vec<u8>, CoreError> {
+    encrypt(kek, &dek.0, WRAP_AAD)
+}
+
+fn unwrap_key(kek: &[u8; 32], wrapped: &[u8]) -> Result<DataKey, CoreError> {
+    let plain = decrypt(kek, wrapped, WRAP_AAD).map_err(|_| CoreError::Locked("wrong passphrase or key".into()))?;
+    let key: [u8; 32] = plain.try_into().map_err(|_| CoreError::Locked("corrupted database key".into()))?;
+    Ok(DataKey(key))
+}
+
+fn encrypt(key: &[u8; 32], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>, CoreError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut nonce);
+    let ct = ChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg, aad })
+        .map_err(|_| CoreError::InvalidArg("encryption failed".into()))?;
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&ct);
+    Ok(out)
+}
+
+fn decrypt(key: &[u8; 32], data: &[u8], aad: &[u8]) -> Result<Vec<u8>, CoreError> {
+    if data.len() < NONCE_LEN {
+        return Err(CoreError::InvalidArg("ciphertext is too short".into()));
+    }
+    let (nonce, ct) = data.split_at(NONCE_LEN);
+    ChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ct, aad })
+        .map_err(|_| CoreError::InvalidArg("ciphertext does not match the database key or its cell".into()))
+}
+
+fn register(conn: &Connection, key: DataKey) {
+    if let Some(path) = db_path(conn) {
+        unlocked().write().unwrap_or_else(|e| e.into_inner()).insert(path.to_string(), key);
+    }
+}
+
+/// Разблокировать БД: проверить секрет и запомнить ключ данных для всех соединений к этому файлу
+pub fn unlock(conn: &Connection, source: &KeySource) -> Result<(), CoreError> {
+    let rec = load_record(conn)?.ok_or_else(|| CoreError::InvalidArg("database is not encrypted".into()))?;
+    let kek = derive_kek(source, &rec.kdf, &rec.salt)?;
+    register(conn, unwrap_key(&kek, &rec.wrapped)?);
+    Ok(())
+}
+
+/// Забыть ключ данных этой БД
+pub fn lock(conn: &Connection) {
+    if let Some(path) = db_path(conn) {
+        unlocked().write().unwrap_or_else(|e| e.into_inner()).remove(path);
+    }
+}
+
+/// Вызывается open_db: зашифрованная БД разблокируется переданным процессом ключом или
+/// парольной фразой из TRUTH_DB_PASSPHRASE, иначе — ошибка Locked
+pub fn ensure_unlocked(conn: &Connection) -> Result<(), CoreError> {
+    if data_key(conn).is_some() || !is_encrypted(conn)? {
+        return Ok(());
+    }
+    let source = provided().read().unwrap_or_else(|e| e.into_inner()).clone()
+        .or_else(|| std::env::var(PASSPHRASE_ENV).ok().map(KeySource::Passphrase));
+    match source {
+        Some(source) => unlock(conn, &source),
+        None => Err(CoreError::Locked(format!("database is encrypted; set {} or unlock it", PASSPHRASE_ENV))),
+    }
+}
+
+/// Включить шифрование: создать ключ данных, обернуть его секретом и зашифровать уже записанные значения.
+/// Возвращает число зашифрованных значений.
+pub fn enable(conn: &mut Connection, source: &KeySource) -> Result<usize, CoreError> {
+    if db_path(conn).is_none() {
+        return Err(CoreError::InvalidArg("in-memory databases cannot be encrypted".into()));
+    }
+    if is_encrypted(conn)? {
+        return Err(CoreError::InvalidArg("database is already encrypted".into()));
+    }
+    let mut dek = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut dek);
+    let dek = DataKey(dek);
+    let mut salt = [0u8; 16];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    let kdf = kdf_name(source);
+    let kek = derive_kek(source, &kdf, &salt)?;
+
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO db_encryption (id, kdf, salt, wrapped_key, created_at) VALUES (1, ?1, ?2, ?3, ?4)",
+        params![kdf, to_hex(&salt), to_hex(&wrap(&kek, &dek)?), chrono::Utc::now().timestamp()],
+    )?;
+    let mut sealed = 0usize;
+    for (table, column) in SENSITIVE_COLUMNS {
+        let key = row_key_column(table).ok_or_else(|| CoreError::InvalidArg(format!("{}: no row key column", table)))?;
+        let rows: Vec<(i64, String, String)> = tx
+            .prepare(&format!(
+                "SELECT rowid, CAST({k} AS TEXT), {c} FROM {t} WHERE {c} IS NOT NULL AND {c} NOT LIKE '{p}%'",
+                t = table, k = key, c = column, p = VALUE_PREFIX
+            ))?
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?
+            .collect::<Result<_, _>>()?;
+        let mut update = tx.prepare(&format!("UPDATE {} SET {} = ?1 WHERE rowid = ?2", table, column))?;
+        for (rowid, row, value) in rows {
+            update.execute(params![seal_with(&dek, table, column, &row, &value)?, rowid])?;
+            sealed += 1;
+        }
+    }
+    let tokens: Vec<String> = tx
+        .prepare(&format!("SELECT refresh_token FROM active_tokens WHERE refresh_token NOT LIKE '{}%'", TOKEN_PREFIX))?
+        .query_map([], |r| r.get(0))?
+        .collect::<Result<_, _>>()?;
+    for token in tokens {
+        tx.execute(
+            "UPDATE active_tokens SET refresh_token = ?1 WHERE refresh_token = ?2",
+            params![digest_with(&dek, &token), token],
+        )?;
+    }
+    tx.commit()?;
+    register(conn, dek);
+    // Открытые значения остаются в индексе FTS (по reasoning), в WAL и на освободившихся страницах:
+    // индекс перестраивается по шифртексту, файл переписывается, WAL усекается
+    conn.execute_batch("INSERT INTO judgments_fts(judgments_fts) VALUES ('rebuild'); VACUUM;")?;
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+    Ok(sealed)
+}
+
+/// Сменить секрет: ключ данных переобёртывается, сами данные не перешифровываются
+pub fn rekey(conn: &Connection, old: &KeySource, new: &KeySource) -> Result<(), CoreError> {
+    let rec = load_record(conn)?.ok_or_else(|| CoreError::InvalidArg("database is not encrypted".into()))?;
+    let dek = unwrap_key(&derive_kek(old, &rec.kdf, &rec.salt)?, &rec.wrapped)?;
+    let mut salt = [0u8; 16];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    let kdf = kdf_name(new);
+    let wrapped = wrap(&derive_kek(new, &kdf, &salt)?, &dek)?;
+    conn.execute(
+        "UPDATE db_encryption SET kdf = ?1, salt = ?2, wrapped_key = ?3, rekeyed_at = ?4 WHERE id = 1",
+        params![kdf, to_hex(&salt), to_hex(&wrapped), chrono::Utc::now().timestamp()],
+    )?;
+    register(conn, dek);
+    Ok(())
+}
+
+/// AAD значения: таблица, колонка и ключ строки
+fn cell_aad(table: &str, column: &str, row: &str) -> Vec<u8> {
+    format!("{}\0{}\0{}", table, column, row).into_bytes()
+}
+
+fn seal_with(dek: &DataKey, table: &str, column: &str, row: &str, value: &str) -> Result<String, CoreError> {
+    Ok(format!("{}{}", VALUE_PREFIX, to_hex(&encrypt(&dek.0, value.as_bytes(), &cell_aad(table, column, row))?)))
+}
+
+fn digest_with(dek: &DataKey, token: &str) -> String {
+    let key = blake3::derive_key(TOKEN_KEY_CONTEXT, &dek.0);
+    format!("{}{}", TOKEN_PREFIX, blake3::keyed_hash(&key, token.as_bytes()).to_hex())
+}
+
+/// Значение чувствительной колонки table.column строки с ключом row (см. row_key_column) для записи:
+/// в зашифрованной БД — шифртекст, привязанный к этой ячейке, иначе как есть
+pub fn seal(conn: &Connection, table: &str, column: &str, row: &str, value: Option<&str>) -> Result<Option<String>, CoreError> {
+    let Some(value) = value else { return Ok(None) };
+    match data_key(conn) {
+        Some(dek) => seal_with(&dek, table, column, row, value).map(Some),
+        None if is_encrypted(conn)? => Err(CoreError::Locked("cannot write encrypted columns while the database is locked".into())),
+        None => Ok(Some(value.to_string())),
+    }
+}
+
+/// Прочитанное значение чувствительной колонки table.column строки row; незашифрованные значения возвращаются как есть.
+/// Шифртекст из другой ячейки не расшифровывается
+pub fn open(conn: &Connection, table: &str, column: &str, row: &str, value: Option<String>) -> Result<Option<String>, CoreError> {
+    let Some(value) = value else { return Ok(None) };
+    let Some(hex) = value.strip_prefix(VALUE_PREFIX) else { return Ok(Some(value)) };
+    let dek = data_key(conn).ok_or_else(|| CoreError::Locked(format!("database is encrypted; set {} or unlock it", PASSPHRASE_ENV)))?;
+    let plain = decrypt(&dek.0, &from_hex(hex)?, &cell_aad(table, column, row))?;
+    String::from_utf8(plain).map(Some).map_err(|e| CoreError::InvalidArg(e.to_string()))
+}
+
+/// Как refresh-токен хранится в active_tokens: ключевой хеш в зашифрованной БД, иначе сам токен
+pub fn token_digest(conn: &Connection, token: &str) -> Result<String, CoreError> {
+    match data_key(conn) {
+        Some(dek) => Ok(digest_with(&dek, token)),
+        None if is_encrypted(conn)? => Err(CoreError::Locked("cannot use refresh tokens while the database is locked".into())),
+        None => Ok(token.to_string()),
+    }
+}
+
+/// Колонка хранится зашифрованной
+pub fn is_sensitive(table: &str, column: &str) -> bool {
+    SENSITIVE_COLUMNS.iter().any(|(t, c)| *t == table && *c == column)
+}
+
+/// Колонка, значение которой — ключ строки в AAD шифруемых колонок таблицы
+pub fn row_key_column(table: &str) -> Option<&'static str> {
+    ROW_KEY_COLUMNS.iter().find(|(t, _)| *t == table).map(|(_, c)| *c)
+}
+
+/// Ключ строки (см. row_key_column) из прочитанной строки с колонками names
+pub fn row_key(table: &str, names: &[String], row: &rusqlite::Row<'_>) -> Result<String, CoreError> {
+    let Some(idx) = row_key_column(table).and_then(|k| names.iter().position(|c| c == k)) else { return Ok(String::new()) };
+    Ok(match row.get_ref(idx)? {
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned(),
+        _ => String::new(),
+    })
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub(crate) fn from_hex(s: &str) -> Result<Vec<u8>, CoreError> {
+    if !s.len().is_multiple_of(2) {
+        return Err(CoreError::InvalidArg("odd-length hex".into()));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|e| CoreError::InvalidArg(e.to_string())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::NewTruthEvent;
+    use crate::storage;
+
+    struct TempDb(String);
+
+    impl TempDb {
+        fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("truth-at-rest-{}.db", uuid::Uuid::new_v4())).to_string_lossy().into_owned())
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", self.0, suffix));
+            }
+        }
+    }
+
+    fn raw_signature(conn: &Connection) -> String {
+        conn.query_row("SELECT signature FROM truth_events WHERE id = 1", [], |r| r.get(0)).unwrap()
+    }
+
+    #[test]
+    fn encrypt_unlock_and_rekey() {
+        let db = TempDb::new();
+        let mut conn = storage::open_db(&db.0).unwrap();
+        storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let ev = storage::add_truth_event(&conn, NewTruthEvent {
+            description: "d".into(), context_id: 1, vector: true, timestamp_start: 1, code: 1,
+        }).unwrap();
+        conn.execute("UPDATE truth_events SET signature = 'sig-1' WHERE id = ?1", [ev]).unwrap();
+        storage::register_refresh_token(&conn, "pk", "refresh-1", i64::MAX).unwrap();
+
+        let pass = KeySource::Passphrase("correct horse".into());
+        assert_eq!(enable(&mut conn, &pass).unwrap(), 1);
+        assert!(raw_signature(&conn).starts_with(VALUE_PREFIX));
+        assert_eq!(storage::get_truth_event(&conn, ev).unwrap().unwrap().signature.as_deref(), Some("sig-1"));
+        assert!(storage::find_session_by_refresh(&conn, "refresh-1").unwrap().is_some());
+        let stored: String = conn.query_row("SELECT refresh_token FROM active_tokens", [], |r| r.get(0)).unwrap();
+        assert!(stored.starts_with(TOKEN_PREFIX));
+
+        // Без ключа: open_db отказывает, чтение шифртекста — Locked
+        lock(&conn);
+        assert!(matches!(ensure_unlocked(&conn), Err(CoreError::Locked(_))));
+        assert!(matches!(storage::get_truth_event(&conn, ev), Err(CoreError::Locked(_))));
+        assert!(matches!(unlock(&conn, &KeySource::Passphrase("wrong".into())), Err(CoreError::Locked(_))));
+        assert!(matches!(unlock(&conn, &KeySource::NodeKey("ab".into())), Err(CoreError::Locked(_))));
+        unlock(&conn, &pass).unwrap();
+        assert!(status(&conn).unwrap().unlocked);
+
+        // Смена секрета на ключ узла: данные читаются тем же ключом данных
+        let node = KeySource::NodeKey("11".repeat(32));
+        rekey(&conn, &pass, &node).unwrap();
+        lock(&conn);
+        assert!(unlock(&conn, &pass).is_err());
+        unlock(&conn, &node).unwrap();
+        assert_eq!(storage::get_truth_event(&conn, ev).unwrap().unwrap().signature.as_deref(), Some("sig-1"));
+        let st = status(&conn).unwrap();
+        assert_eq!(st.kdf.as_deref(), Some("node-key"));
+        assert!(st.rekeyed_at.is_some());
+
+        // Экспорт отдаёт открытые значения
+        let mut out = Vec::new();
+        crate::export::export(&conn, &mut out, crate::export::ExportFormat::Json).unwrap();
+        let dump = String::from_utf8(out).unwrap();
+        assert!(dump.contains("sig-1") && !dump.contains(VALUE_PREFIX));
+        lock(&conn);
+    }
+
+    #[test]
+    fn enable_binds_values_to_their_cells_and_scrubs_plaintext() {
+        let db = TempDb::new();
+        let mut conn = storage::open_db(&db.0).unwrap();
+        conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(())).unwrap();
+        storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let new_event = |d: &str, ts: i64| NewTruthEvent { description: d.into(), context_id: 1, vector: true, timestamp_start: ts, code: 1 };
+        let a = storage::add_truth_event(&conn, new_event("a", 1)).unwrap();
+        let b = storage::add_truth_event(&conn, new_event("b", 2)).unwrap();
+        conn.execute("UPDATE truth_events SET signature = 'plain-sig-' || description", []).unwrap();
+        let pid = storage::ci_ensure_participant(&conn, "pk-judge").unwrap();
+        let ci_event = uuid::Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO events_ci (id, title, event_type, created_by, created_at) VALUES (?1, 'outage', 'binary', ?2, 0)",
+            params![ci_event.to_string(), pid.to_string()],
+        ).unwrap();
+        storage::ci_insert_judgment(&conn, &crate::collective_intelligence::models::Judgment {
+            id: uuid::Uuid::new_v4(),
+            participant_id: pid,
+            event_id: ci_event,
+            assessment: "true".into(),
+            confidence_level: 0.5,
+            reasoning: Some("confidentialgauge readings".into()),
+            submitted_at: chrono::Utc::now(),
+            signature: "plain-sig-judge".into(),
+        }).unwrap();
+
+        enable(&mut conn, &KeySource::Passphrase("pass".into())).unwrap();
+
+        // Ни файл БД, ни WAL, ни индекс FTS не хранят открытых значений
+        let mut on_disk = std::fs::read(&db.0).unwrap();
+        on_disk.extend(std::fs::read(format!("{}-wal", db.0)).unwrap_or_default());
+        for plain in ["plain-sig-", "confidentialgauge"] {
+            assert!(!on_disk.windows(plain.len()).any(|w| w == plain.as_bytes()), "{} left on disk", plain);
+        }
+        assert_eq!(storage::ci_get_judgments_by_event(&conn, &ci_event).unwrap()[0].reasoning.as_deref(), Some("confidentialgauge readings"));
+
+        // Шифртекст, перенесённый в другую строку, не расшифровывается
+        conn.execute("UPDATE truth_events SET signature = (SELECT signature FROM truth_events WHERE id = ?1) WHERE id = ?2", [a, b]).unwrap();
+        assert_eq!(storage::get_truth_event(&conn, a).unwrap().unwrap().signature.as_deref(), Some("plain-sig-a"));
+        assert!(matches!(storage::get_truth_event(&conn, b), Err(CoreError::InvalidArg(_))));
+        lock(&conn);
+    }
+
+    #[test]
+    fn plain_databases_pass_through() {
+        let conn = storage::open_db(":memory:").unwrap();
+        assert!(!is_encrypted(&conn).unwrap());
+        assert_eq!(seal(&conn, "impact", "signature", "i1", Some("x")).unwrap().as_deref(), Some("x"));
+        assert_eq!(open(&conn, "impact", "signature", "i1", Some("x".into())).unwrap().as_deref(), Some("x"));
+        assert_eq!(token_digest(&conn, "t").unwrap(), "t");
+    }
+}
@@ -0,0 +1,373 @@
+//! Журнал аудита: каждое изменение отслеживаемых таблиц (локальное или от пира) добавляется
+//! в audit_log с инициатором, хешами строки до и после и хешем предыдущей записи.
+//! Таблица только дополняется (триггеры запрещают UPDATE/DELETE), голову цепочки периодически
+//! подписывает ключ узла (audit_checkpoints); verify находит изменённые, удалённые и переписанные записи.
+
+use crate::at_rest::{self, from_hex, to_hex};
+use crate::models::{AuditCheckpoint, AuditEntry};
+use crate::CoreError;
+use chrono::Utc;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::cell::RefCell;
+
+/// Инициатор изменений, для которых with_actor не задан (фоновые задачи узла)
+pub const LOCAL_ACTOR: &str = "local";
+
+/// Инициатор изменений от пира, ключ которого неизвестен (pull без подписи отправителя)
+pub const PEER_ACTOR: &str = "peer";
+
+/// Отслеживаемые таблицы и ключ записи в журнале; производные данные (рейтинги, метрики, журналы) не отслеживаются
+pub const TRACKED: &[(&str, &str)] = &[
+    ("truth_events", "uid"),
+    ("statements", "uid"),
+    ("impact", "id"),
+    ("users", "pubkey"),
+    ("trust_delegations", "id"),
+    ("tombstones", "id"),
+    ("evidence", "id"),
+    ("event_relations", "id"),
+    ("category", "id"),
+    ("cause", "id"),
+    ("develop", "id"),
+    ("effect", "id"),
+    ("forma", "id"),
+    ("context", "id"),
+    ("impact_type", "id"),
+    ("expert_questions", "key"),
+    ("retention_policies", "table_name"),
+    ("judgments", "id"),
+];
+
+/// Ключевая колонка отслеживаемой таблицы
+pub fn key_column(table: &str) -> Option<&'static str> {
+    TRACKED.iter().find(|(t, _)| *t == table).map(|(_, k)| *k)
+}
+
+thread_local! {
+    static ACTOR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Восстанавливает прежнего инициатора и при панике внутри with_actor
+struct ActorGuard(Option<String>);
+
+impl Drop for ActorGuard {
+    fn drop(&mut self) {
+        ACTOR.with(|a| *a.borrow_mut() = self.0.take());
+    }
+}
+
+/// Выполнить f от имени actor (публичный ключ): изменения внутри попадут в журнал с ним.
+/// Действует в текущем потоке, вложенные вызовы восстанавливают внешнего инициатора
+pub fn with_actor<T>(actor: &str, f: impl FnOnce() -> T) -> T {
+    let _guard = ActorGuard(ACTOR.with(|a| a.replace(Some(actor.to_string()))));
+    f()
+}
+
+/// Текущий инициатор изменений
+pub fn current_actor() -> String {
+    ACTOR.with(|a| a.borrow().clone()).unwrap_or_else(|| LOCAL_ACTOR.to_string())
+}
+
+/// Хеш строки table с key_col = key (все колонки по порядку, чувствительные — расшифрованными,
+/// чтобы перешифрование не выглядело изменением); None — строки нет
+pub fn row_hash(conn: &Connection, table: &str, key_col: &str, key: &str) -> Result<Option<String>, CoreError> {
+    let mut stmt = conn.prepare_cached(&format!("SELECT * FROM \"{}\" WHERE \"{}\" = ?1", table, key_col))?;
+    let names: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+    let mut rows = stmt.query(params![key])?;
+    let Some(row) = rows.next()? else { return Ok(None) };
+    let row_key = at_rest::row_key(table, &names, row)?;
+    let mut h = blake3::Hasher::new();
+    for (i, name) in names.iter().enumerate() {
+        let value = match row.get_ref(i)? {
+            ValueRef::Null => "n".to_string(),
+            ValueRef::Integer(v) => format!("i{}", v),
+            ValueRef::Real(v) => format!("r{}", v),
+            ValueRef::Text(t) => {
+                let t = String::from_utf8_lossy(t).to_string();
+                let t = if at_rest::is_sensitive(table, name) { at_rest::open(conn, table, name, &row_key, Some(t))?.unwrap_or_default() } else { t };
+                format!("t{}:{}", t.len(), t)
+            }
+            ValueRef::Blob(b) => format!("b{}", to_hex(b)),
+        };
+        h.update(format!("{}={};", name, value).as_bytes());
+    }
+    Ok(Some(h.finalize().to_hex().to_string()))
+}
+
+/// Последняя запись журнала: (seq, entry_hash)
+fn head(conn: &Connection) -> Result<Option<(i64, String)>, CoreError> {
+    Ok(conn
+        .query_row("SELECT seq, entry_hash FROM audit_log ORDER BY seq DESC LIMIT 1", [], |r| Ok((r.get(0)?, r.get(1)?)))
+        .optional()?)
+}
+
+/// Добавить в журнал изменение строки от имени current_actor; совпадающие хеши — не изменение
+pub fn record(
+    conn: &Connection,
+    table: &str,
+    record_id: &str,
+    before: Option<String>,
+    after: Option<String>,
+) -> Result<Option<AuditEntry>, CoreError> {
+    if before == after {
+        return Ok(None);
+    }
+    let op = match (&before, &after) {
+        (None, _) => "insert",
+        (_, None) => "delete",
+        _ => "update",
+    };
+    let (seq, prev_hash) = head(conn)?.map(|(s, h)| (s + 1, h)).unwrap_or((1, AuditEntry::GENESIS.to_string()));
+    let mut e = AuditEntry {
+        seq,
+        ts: Utc::now().timestamp(),
+        actor: current_actor(),
+        op: op.to_string(),
+        table_name: table.to_string(),
+        record_id: record_id.to_string(),
+        before_hash: before,
+        after_hash: after,
+        prev_hash,
+        entry_hash: String::new(),
+    };
+    e.entry_hash = e.compute_hash();
+    conn.execute(
+        r#"INSERT INTO audit_log (seq, ts, actor, op, table_name, record_id, before_hash, after_hash, prev_hash, entry_hash)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"#,
+        params![e.seq, e.ts, e.actor, e.op, e.table_name, e.record_id, e.before_hash, e.after_hash, e.prev_hash, e.entry_hash],
+    )?;
+    Ok(Some(e))
+}
+
+/// Выполнить изменение строки table (key_col = record_id) и записать его в журнал.
+/// Изменение и запись журнала атомарны (SAVEPOINT), в том числе внутри внешней транзакции
+pub fn track<T>(
+    conn: &Connection,
+    table: &str,
+    key_col: &str,
+    record_id: &str,
+    f: impl FnOnce() -> Result<T, CoreError>,
+) -> Result<T, CoreError> {
+    conn.execute_batch("SAVEPOINT audit_track")?;
+    let res = (|| {
+        let before = row_hash(conn, table, key_col, record_id)?;
+        let out = f()?;
+        let after = row_hash(conn, table, key_col, record_id)?;
+        record(conn, table, record_id, before, after)?;
+        Ok(out)
+    })();
+    match res {
+        Ok(out) => {
+            conn.execute_batch("RELEASE audit_track")?;
+            Ok(out)
+        }
+        Err(e) => {
+            let _ = conn.execute_batch("ROLLBACK TO audit_track; RELEASE audit_track");
+            Err(e)
+        }
+    }
+}
+
+fn map_entry(r: &rusqlite::Row<'_>) -> rusqlite::Result<AuditEntry> {
+    Ok(AuditEntry {
+        seq: r.get(0)?,
+        ts: r.get(1)?,
+        actor: r.get(2)?,
+        op: r.get(3)?,
+        table_name: r.get(4)?,
+        record_id: r.get(5)?,
+        before_hash: r.get(6)?,
+        after_hash: r.get(7)?,
+        prev_hash: r.get(8)?,
+        entry_hash: r.get(9)?,
+    })
+}
+
+const ENTRY_COLUMNS: &str = "seq, ts, actor, op, table_name, record_id, before_hash, after_hash, prev_hash, entry_hash";
+
+/// Последние записи журнала (новые первыми) с фильтром по таблице и записи
+pub fn load_entries(conn: &Connection, table: Option<&str>, record_id: Option<&str>, limit: usize) -> Result<Vec<AuditEntry>, CoreError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM audit_log WHERE (?1 IS NULL OR table_name = ?1) AND (?2 IS NULL OR record_id = ?2) ORDER BY seq DESC LIMIT ?3",
+        ENTRY_COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![table, record_id, limit as i64], map_entry)?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+fn load_checkpoints(conn: &Connection) -> Result<Vec<AuditCheckpoint>, CoreError> {
+    let mut stmt = conn.prepare("SELECT seq, entry_hash, created_at, public_key, signature FROM audit_checkpoints ORDER BY seq")?;
+    let rows = stmt.query_map([], |r| {
+        Ok(AuditCheckpoint { seq: r.get(0)?, entry_hash: r.get(1)?, created_at: r.get(2)?, public_key: r.get(3)?, signature: r.get(4)? })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// Подписать текущую голову журнала ключом узла; None — журнал пуст или голова уже подписана
+pub fn checkpoint(conn: &Connection, key: &SigningKey, now: i64) -> Result<Option<AuditCheckpoint>, CoreError> {
+    let Some((seq, entry_hash)) = head(conn)? else { return Ok(None) };
+    let signed: Option<i64> = conn.query_row("SELECT MAX(seq) FROM audit_checkpoints", [], |r| r.get(0))?;
+    if signed.is_some_and(|s| s >= seq) {
+        return Ok(None);
+    }
+    let mut c = AuditCheckpoint {
+        seq,
+        entry_hash,
+        created_at: now,
+        public_key: to_hex(key.verifying_key().as_bytes()),
+        signature: String::new(),
+    };
+    c.signature = to_hex(&key.sign(c.signing_message().as_bytes()).to_bytes());
+    conn.execute(
+        "INSERT INTO audit_checkpoints (seq, entry_hash, created_at, public_key, signature) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![c.seq, c.entry_hash, c.created_at, c.public_key, c.signature],
+    )?;
+    Ok(Some(c))
+}
+
+fn verify_checkpoint(c: &AuditCheckpoint) -> Result<(), CoreError> {
+    let pk: [u8; 32] = from_hex(&c.public_key)?
+        .try_into()
+        .map_err(|_| CoreError::InvalidArg(format!("checkpoint {}: bad key", c.seq)))?;
+    let key = VerifyingKey::from_bytes(&pk).map_err(|e| CoreError::InvalidArg(e.to_string()))?;
+    let sig = Signature::from_slice(&from_hex(&c.signature)?).map_err(|e| CoreError::InvalidArg(e.to_string()))?;
+    key.verify(c.signing_message().as_bytes(), &sig)
+        .map_err(|_| CoreError::InvalidArg(format!("checkpoint {}: signature is invalid", c.seq)))
+}
+
+/// Итог проверки журнала
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerifyReport {
+    pub entries: usize,
+    pub head_seq: i64,
+    pub checkpoints: usize,
+    /// seq последней подписанной и совпавшей отметки
+    pub signed_seq: i64,
+    /// Ключи, подписавшие отметки
+    pub signers: Vec<String>,
+    pub problems: Vec<String>,
+}
+
+impl VerifyReport {
+    pub fn is_intact(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Проверить журнал: непрерывность seq, связи prev_hash, entry_hash каждой записи,
+/// подписи отметок и их совпадение с записями (переписанная или усечённая цепочка)
+pub fn verify(conn: &Connection) -> Result<VerifyReport, CoreError> {
+    let mut report = VerifyReport::default();
+    let mut hashes = std::collections::HashMap::new();
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM audit_log ORDER BY seq", ENTRY_COLUMNS))?;
+    let mut prev: Option<AuditEntry> = None;
+    for e in stmt.query_map([], map_entry)? {
+        let e = e?;
+        let (expected_seq, expected_prev) = match &prev {
+            Some(p) => (p.seq + 1, p.entry_hash.as_str()),
+            None => (1, AuditEntry::GENESIS),
+        };
+        if e.seq != expected_seq {
+            report.problems.push(format!("entries {}..{} are missing", expected_seq, e.seq - 1));
+        } else if e.prev_hash != expected_prev {
+            report.problems.push(format!("entry {}: prev_hash does not match entry {}", e.seq, e.seq - 1));
+        }
+        if e.compute_hash() != e.entry_hash {
+            report.problems.push(format!("entry {}: contents do not match entry_hash", e.seq));
+        }
+        report.entries += 1;
+        report.head_seq = e.seq;
+        hashes.insert(e.seq, e.entry_hash.clone());
+        prev = Some(e);
+    }
+
+    for c in load_checkpoints(conn)? {
+        report.checkpoints += 1;
+        if let Err(e) = verify_checkpoint(&c) {
+            report.problems.push(e.to_string());
+            continue;
+        }
+        match hashes.get(&c.seq) {
+            None => report.problems.push(format!("checkpoint {}: signed entry is missing (log truncated)", c.seq)),
+            Some(h) if *h != c.entry_hash => report.problems.push(format!("checkpoint {}: entry differs from the signed one", c.seq)),
+            Some(_) => report.signed_seq = report.signed_seq.max(c.seq),
+        }
+        if !report.signers.contains(&c.public_key) {
+            report.signers.push(c.public_key);
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::NewTruthEvent;
+    use crate::storage;
+
+    fn event(conn: &Connection) -> i64 {
+        storage::add_truth_event(conn, NewTruthEvent { description: "flood".into(), context_id: 1, vector: true, timestamp_start: 10, code: 1 }).unwrap()
+    }
+
+    #[test]
+    fn mutations_are_chained_with_actor() {
+        let mut conn = storage::open_db(":memory:").unwrap();
+        storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let seeded = load_entries(&conn, None, None, 1000).unwrap().len();
+        let id = with_actor("pk-alice", || event(&conn));
+        storage::set_event_detected(&conn, id, true, Some(20), false).unwrap();
+        // Повторная запись того же состояния — не изменение
+        storage::set_event_detected(&conn, id, true, Some(20), false).unwrap();
+        storage::update_user_role(&conn, "pk-bob", "admin").unwrap();
+
+        let uid = storage::get_truth_event(&conn, id).unwrap().unwrap().uid;
+        let log = load_entries(&conn, Some("truth_events"), Some(&uid), 10).unwrap();
+        assert_eq!(log.iter().map(|e| (e.op.as_str(), e.actor.as_str())).collect::<Vec<_>>(), vec![("update", "local"), ("insert", "pk-alice")]);
+        assert_eq!(log[0].before_hash, log[1].after_hash);
+        assert_eq!(log[0].prev_hash, log[1].entry_hash);
+        assert_eq!(current_actor(), LOCAL_ACTOR);
+
+        let report = verify(&conn).unwrap();
+        assert!(report.is_intact(), "{:?}", report.problems);
+        assert_eq!(report.entries, seeded + 3);
+
+        // Неудачное изменение не оставляет записи в журнале
+        let before = verify(&conn).unwrap().entries;
+        assert!(storage::add_impact(&conn, 999, 1, true, None).is_err());
+        assert_eq!(verify(&conn).unwrap().entries, before);
+    }
+
+    #[test]
+    fn verify_detects_tampering() {
+        let conn = storage::open_db(":memory:").unwrap();
+        for i in 0..3 {
+            storage::update_user_role(&conn, &format!("pk-{}", i), "node").unwrap();
+        }
+        let key = SigningKey::from_bytes(&[5; 32]);
+        let c = checkpoint(&conn, &key, 100).unwrap().unwrap();
+        assert_eq!(c.seq, 3);
+        assert!(checkpoint(&conn, &key, 101).unwrap().is_none());
+        storage::update_user_role(&conn, "pk-3", "node").unwrap();
+        let report = verify(&conn).unwrap();
+        assert!(report.is_intact());
+        assert_eq!((report.head_seq, report.signed_seq), (4, 3));
+
+        // Журнал только дополняется
+        assert!(conn.execute("UPDATE audit_log SET actor = 'mallory' WHERE seq = 2", []).is_err());
+        assert!(conn.execute("DELETE FROM audit_log WHERE seq = 4", []).is_err());
+
+        // В обход триггеров: правка записи, удаление хвоста под отметкой
+        conn.execute_batch("DROP TRIGGER audit_log_no_update; DROP TRIGGER audit_log_no_delete;").unwrap();
+        conn.execute("UPDATE audit_log SET actor = 'mallory' WHERE seq = 2", []).unwrap();
+        let problems = verify(&conn).unwrap().problems;
+        assert_eq!(problems, vec!["entry 2: contents do not match entry_hash".to_string()]);
+
+        conn.execute("DELETE FROM audit_log WHERE seq >= 3", []).unwrap();
+        conn.execute("UPDATE audit_log SET actor = 'local' WHERE seq = 2", []).unwrap();
+        let problems = verify(&conn).unwrap().problems;
+        assert_eq!(problems, vec!["checkpoint 3: signed entry is missing (log truncated)".to_string()]);
+    }
+}
@@ -0,0 +1,385 @@
+use crate::at_rest::{from_hex, to_hex};
+use crate::{migrations, CoreError};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rusqlite::backup::Backup;
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Имя формата в манифесте резервной копии
+pub const MANIFEST_FORMAT: &str = "truth-training-backup";
+/// Версия формата манифеста
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Страниц за шаг онлайн-копирования: между шагами писатель успевает занять БД
+const PAGES_PER_STEP: i32 = 1024;
+const STEP_PAUSE: Duration = Duration::from_millis(5);
+
+/// Манифест рядом с файлом копии (<name>.json): хеш содержимого и подпись узла
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format: String,
+    pub version: u32,
+    /// Имя файла копии в том же каталоге
+    pub file: String,
+    pub created_at: i64,
+    pub size: u64,
+    /// blake3 файла копии, hex
+    pub blake3: String,
+    pub schema_version: u32,
+    pub node_public_key: Option<String>,
+    /// Ed25519-подпись signing_message(), hex
+    pub signature: Option<String>,
+}
+
+impl BackupManifest {
+    /// Подписываемое сообщение: все поля, описывающие содержимое
+    pub fn signing_message(&self) -> String {
+        format!(
+            "{}:v{}:{}:{}:{}:{}:{}",
+            self.format, self.version, self.file, self.created_at, self.size, self.blake3, self.schema_version
+        )
+    }
+}
+
+/// Копия на диске: путь к файлу БД и её манифест
+#[derive(Debug, Clone)]
+pub struct BackupEntry {
+    pub path: PathBuf,
+    pub manifest: BackupManifest,
+}
+
+/// Результат восстановления
+#[derive(Debug, Clone)]
+pub struct RestoreReport {
+    pub restored: BackupEntry,
+    /// Снимок БД, сделанный перед заменой
+    pub previous: Option<BackupEntry>,
+}
+
+fn manifest_path(db_file: &Path) -> PathBuf {
+    db_file.with_extension("json")
+}
+
+fn hash_file(path: &Path) -> Result<(u64, String), CoreError> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    let size = std::io::copy(&mut file, &mut hasher)?;
+    Ok((size, hasher.finalize().to_hex().to_string()))
+}
+
+/// Рабочая копия файла резервной копии во временном каталоге: проверка и восстановление идут по ней,
+/// а исходный файл только читается (integrity_check с FTS5 требует записи и мог бы откатить журнал рядом с копией)
+struct WorkCopy(PathBuf);
+
+impl WorkCopy {
+    fn of(src: &Path) -> Result<Self, CoreError> {
+        static SEQ: AtomicU64 = AtomicU64::new(0);
+        let name = format!(
+            "truth-verify-{}-{}-{}.db",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+            SEQ.fetch_add(1, Ordering::Relaxed)
+        );
+        let copy = WorkCopy(std::env::temp_dir().join(name));
+        fs::copy(src, &copy.0)?;
+        Ok(copy)
+    }
+}
+
+impl Drop for WorkCopy {
+    fn drop(&mut self) {
+        for suffix in ["", "-journal", "-wal", "-shm"] {
+            let mut p = self.0.clone().into_os_string();
+            p.push(suffix);
+            let _ = fs::remove_file(p);
+        }
+    }
+}
+
+fn copy_pages(from: &Connection, to: &mut Connection, pages_per_step: i32) -> Result<(), CoreError> {
+    Backup::new(from, to)?.run_to_completion(pages_per_step, STEP_PAUSE, None)?;
+    Ok(())
+}
+
+/// Онлайн-копия БД через SQLite backup API: писатели не блокируются дольше одного шага.
+/// Файл появляется в каталоге только целиком (копия пишется во временный и переименовывается).
+pub fn create(conn: &Connection, dir: &Path, signer: Option<&SigningKey>) -> Result<BackupEntry, CoreError> {
+    fs::create_dir_all(dir)?;
+    let now = chrono::Utc::now();
+    let mut name = format!("truth-{}", now.format("%Y%m%d-%H%M%S-%3f"));
+    while dir.join(format!("{name}.db")).exists() {
+        name.push('x');
+    }
+    let file = format!("{name}.db");
+    let path = dir.join(&file);
+    let partial = dir.join(format!("{name}.db.partial"));
+    {
+        let mut dst = Connection::open(&partial)?;
+        copy_pages(conn, &mut dst, PAGES_PER_STEP)?;
+        // Копия — один самостоятельный файл, без -wal/-shm
+        dst.query_row("PRAGMA journal_mode=DELETE", [], |_| Ok(()))?;
+    }
+    let schema_version = {
+        let dst = Connection::open_with_flags(&partial, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        migrations::current_version(&dst)?
+    };
+    fs::rename(&partial, &path)?;
+
+    let (size, blake3) = hash_file(&path)?;
+    let mut manifest = BackupManifest {
+        format: MANIFEST_FORMAT.into(),
+        version: MANIFEST_VERSION,
+        file,
+        created_at: now.timestamp(),
+        size,
+        blake3,
+        schema_version,
+        node_public_key: None,
+        signature: None,
+    };
+    if let Some(key) = signer {
+        manifest.node_public_key = Some(to_hex(key.verifying_key().as_bytes()));
+        manifest.signature = Some(to_hex(&key.sign(manifest.signing_message().as_bytes()).to_bytes()));
+    }
+    fs::write(manifest_path(&path), serde_json::to_string_pretty(&manifest)?)?;
+    Ok(BackupEntry { path, manifest })
+}
+
+/// Копии каталога от старых к новым; файлы без читаемого манифеста пропускаются
+pub fn list(dir: &Path) -> Result<Vec<BackupEntry>, CoreError> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut entries = Vec::new();
+    for item in fs::read_dir(dir)? {
+        let path = item?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let Ok(text) = fs::read_to_string(&path) else { continue };
+        let Ok(manifest) = serde_json::from_str::<BackupManifest>(&text) else { continue };
+        if manifest.format != MANIFEST_FORMAT {
+            continue;
+        }
+        entries.push(BackupEntry { path: dir.join(&manifest.file), manifest });
+    }
+    entries.sort_by(|a, b| (a.manifest.created_at, &a.manifest.file).cmp(&(b.manifest.created_at, &b.manifest.file)));
+    Ok(entries)
+}
+
+/// Проверить копию (путь к .db или к манифесту): размер и хеш, подпись ключом узла node_key,
+/// PRAGMA integrity_check и совместимость версии схемы с этой сборкой.
+/// Подпись другим ключом отвергается; неподписанная копия принимается только при allow_unsigned
+pub fn verify(path: &Path, node_key: Option<&VerifyingKey>, allow_unsigned: bool) -> Result<BackupEntry, CoreError> {
+    verify_copy(path, node_key, allow_unsigned).map(|(entry, _)| entry)
+}
+
+/// verify по рабочей копии; копия возвращается, чтобы восстанавливать ровно проверенное содержимое
+fn verify_copy(path: &Path, node_key: Option<&VerifyingKey>, allow_unsigned: bool) -> Result<(BackupEntry, WorkCopy), CoreError> {
+    let manifest_file = manifest_path(path);
+    let manifest: BackupManifest = serde_json::from_str(&fs::read_to_string(&manifest_file)?)?;
+    if manifest.format != MANIFEST_FORMAT || manifest.version > MANIFEST_VERSION {
+        return Err(CoreError::InvalidArg(format!("unsupported backup manifest {} v{}", manifest.format, manifest.version)));
+    }
+    let db_file = manifest_file.with_file_name(&manifest.file);
+    let copy = WorkCopy::of(&db_file)?;
+    let (size, blake3) = hash_file(&copy.0)?;
+    if size != manifest.size || blake3 != manifest.blake3 {
+        return Err(CoreError::InvalidArg(format!("{}: content hash mismatch", manifest.file)));
+    }
+    match (&manifest.node_public_key, &manifest.signature) {
+        (Some(pk), Some(sig)) => {
+            // Ключ из манифеста подделывается вместе с ним: подпись проверяется только ожидаемым ключом узла
+            let key = node_key
+                .ok_or_else(|| CoreError::InvalidArg(format!("{}: no node key to check the manifest signature", manifest.file)))?;
+            if !pk.eq_ignore_ascii_case(&to_hex(key.as_bytes())) {
+                return Err(CoreError::InvalidArg(format!("{}: manifest is signed by another key", manifest.file)));
+            }
+            let sig = Signature::from_slice(&from_hex(sig)?).map_err(|e| CoreError::InvalidArg(e.to_string()))?;
+            key.verify(manifest.signing_message().as_bytes(), &sig)
+                .map_err(|_| CoreError::InvalidArg(format!("{}: manifest signature is invalid", manifest.file)))?;
+        }
+        (None, None) if allow_unsigned => {}
+        (None, None) => return Err(CoreError::InvalidArg(format!("{}: backup is unsigned", manifest.file))),
+        _ => return Err(CoreError::InvalidArg(format!("{}: incomplete manifest signature", manifest.file))),
+    }
+    let conn = Connection::open_with_flags(&copy.0, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+    let integrity: String = conn.query_row("PRAGMA integrity_check", [], |r| r.get(0))?;
+    if integrity != "ok" {
+        return Err(CoreError::InvalidArg(format!("{}: integrity check failed: {}", manifest.file, integrity)));
+    }
+    let schema = migrations::current_version(&conn)?;
+    if schema != manifest.schema_version {
+        return Err(CoreError::InvalidArg(format!("{}: schema version {} does not match manifest", manifest.file, schema)));
+    }
+    if schema > migrations::latest_version() {
+        return Err(CoreError::InvalidArg(format!(
+            "{}: schema version {} is newer than supported {}",
+            manifest.file, schema, migrations::latest_version()
+        )));
+    }
+    drop(conn);
+    Ok((BackupEntry { path: db_file, manifest }, copy))
+}
+
+/// Восстановить БД из проверенной копии (см. verify: подпись ключом узла node, неподписанная — только при allow_unsigned).
+/// Текущая БД сначала сохраняется снимком в snapshot_dir, подписанным тем же ключом,
+/// затем её страницы заменяются через backup API (открытые соединения видят новое содержимое).
+pub fn restore(
+    backup: &Path,
+    target: &Path,
+    snapshot_dir: Option<&Path>,
+    node: Option<&SigningKey>,
+    allow_unsigned: bool,
+) -> Result<RestoreReport, CoreError> {
+    let (restored, copy) = verify_copy(backup, node.map(|k| k.verifying_key()).as_ref(), allow_unsigned)?;
+    let mut target_conn = Connection::open(target)?;
+    target_conn.busy_timeout(Duration::from_secs(5))?;
+    let previous = match snapshot_dir {
+        Some(dir) if migrations::current_version(&target_conn)? > 0 => Some(create(&target_conn, dir, node)?),
+        _ => None,
+    };
+    let src = Connection::open_with_flags(&copy.0, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    // Одним шагом: читатели не увидят наполовину восстановленную БД
+    copy_pages(&src, &mut target_conn, i32::MAX)?;
+    crate::at_rest::lock(&target_conn);
+    Ok(RestoreReport { restored, previous })
+}
+
+/// Удалить копии сверх keep самых новых; возвращает удалённые файлы БД
+pub fn prune(dir: &Path, keep: usize) -> Result<Vec<PathBuf>, CoreError> {
+    let entries = list(dir)?;
+    let excess = entries.len().saturating_sub(keep);
+    let mut removed = Vec::new();
+    for e in entries.into_iter().take(excess) {
+        if e.path.exists() {
+            fs::remove_file(&e.path)?;
+        }
+        fs::remove_file(manifest_path(&e.path))?;
+        removed.push(e.path);
+    }
+    Ok(removed)
+}
+
+/// Плановый снимок: копия и очистка по политике хранения
+pub fn snapshot(conn: &Connection, dir: &Path, signer: Option<&SigningKey>, keep: usize) -> Result<BackupEntry, CoreError> {
+    let entry = create(conn, dir, signer)?;
+    prune(dir, keep.max(1))?;
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::NewTruthEvent;
+    use crate::storage;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("truth-backup-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn add_event(conn: &Connection, description: &str) {
+        storage::add_truth_event(conn, NewTruthEvent {
+            description: description.into(), context_id: 1, vector: true, timestamp_start: 1, code: 1,
+        }).unwrap();
+    }
+
+    fn event_count(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM truth_events", [], |r| r.get(0)).unwrap()
+    }
+
+    #[test]
+    fn create_verify_and_restore() {
+        let tmp = TempDir::new();
+        let db = tmp.0.join("live.db");
+        let backups = tmp.0.join("backups");
+        let mut conn = storage::open_db(db.to_str().unwrap()).unwrap();
+        conn.query_row("PRAGMA journal_mode=WAL", [], |_| Ok(())).unwrap();
+        storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        add_event(&conn, "before backup");
+
+        let key = SigningKey::from_bytes(&[7u8; 32]);
+        let entry = create(&conn, &backups, Some(&key)).unwrap();
+        assert_eq!(entry.manifest.schema_version, migrations::latest_version());
+        assert!(entry.manifest.signature.is_some());
+        let node = key.verifying_key();
+        assert_eq!(verify(&entry.path, Some(&node), false).unwrap().manifest.blake3, entry.manifest.blake3);
+
+        add_event(&conn, "after backup");
+        let report = restore(&entry.path, &db, Some(&backups), Some(&key), false).unwrap();
+        assert_eq!(event_count(&conn), 1);
+        // Снимок прежней БД подписан тем же ключом и сам проходит проверку
+        let previous = report.previous.unwrap();
+        assert!(verify(&previous.path, Some(&node), false).is_ok());
+        assert_eq!(list(&backups).unwrap().len(), 2);
+        // Проверка не пишет рядом с копией: ни -wal/-shm/-journal, ни изменений самого файла
+        let side_files = fs::read_dir(&backups).unwrap().filter(|e| {
+            let name = e.as_ref().unwrap().file_name().to_string_lossy().into_owned();
+            name.ends_with("-wal") || name.ends_with("-shm") || name.ends_with("-journal")
+        }).count();
+        assert_eq!(side_files, 0);
+
+        // Подделанный манифест и испорченный файл отвергаются до замены БД
+        let mut forged = entry.manifest.clone();
+        forged.created_at += 1;
+        fs::write(manifest_path(&entry.path), serde_json::to_string(&forged).unwrap()).unwrap();
+        assert!(matches!(verify(&entry.path, Some(&node), false), Err(CoreError::InvalidArg(m)) if m.contains("signature")));
+        fs::write(manifest_path(&entry.path), serde_json::to_string(&entry.manifest).unwrap()).unwrap();
+        let mut bytes = fs::read(&entry.path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        fs::write(&entry.path, bytes).unwrap();
+        assert!(matches!(restore(&entry.path, &db, None, Some(&key), false), Err(CoreError::InvalidArg(m)) if m.contains("hash")));
+        assert_eq!(event_count(&conn), 1);
+    }
+
+    #[test]
+    fn copies_resigned_by_another_key_or_unsigned_are_rejected() {
+        let tmp = TempDir::new();
+        let db = tmp.0.join("live.db");
+        let mut conn = storage::open_db(db.to_str().unwrap()).unwrap();
+        storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        add_event(&conn, "original");
+        let node = SigningKey::from_bytes(&[7u8; 32]);
+
+        // Чужой ключ переписал копию и заново подписал манифест: хеш и подпись сходятся, но ключ не тот
+        let attacker = SigningKey::from_bytes(&[9u8; 32]);
+        let forged = create(&conn, &tmp.0.join("forged"), Some(&attacker)).unwrap();
+        assert!(verify(&forged.path, Some(&attacker.verifying_key()), false).is_ok());
+        assert!(matches!(verify(&forged.path, Some(&node.verifying_key()), false), Err(CoreError::InvalidArg(m)) if m.contains("another key")));
+        assert!(matches!(verify(&forged.path, None, true), Err(CoreError::InvalidArg(m)) if m.contains("no node key")));
+        assert!(restore(&forged.path, &db, None, Some(&node), true).is_err());
+
+        // Неподписанная копия — только по явному разрешению
+        let unsigned = create(&conn, &tmp.0.join("unsigned"), None).unwrap();
+        assert!(matches!(restore(&unsigned.path, &db, None, Some(&node), false), Err(CoreError::InvalidArg(m)) if m.contains("unsigned")));
+        assert!(restore(&unsigned.path, &db, None, Some(&node), true).is_ok());
+        assert_eq!(event_count(&conn), 1);
+    }
+
+    #[test]
+    fn snapshots_keep_the_newest() {
+        let tmp = TempDir::new();
+        let conn = storage::open_db(":memory:").unwrap();
+        let made: Vec<_> = (0..4).map(|_| snapshot(&conn, &tmp.0, None, 2).unwrap().path).collect();
+        let kept: Vec<_> = list(&tmp.0).unwrap().into_iter().map(|e| e.path).collect();
+        assert_eq!(kept, made[2..]);
+        assert!(!made[0].exists() && !manifest_path(&made[0]).exists());
+    }
+}
@@ -0,0 +1,186 @@
+use crate::GroupRating;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Максимальное число итераций label propagation
+pub const LABEL_PROPAGATION_MAX_ITER: usize = 50;
+/// Минимальное сходство (Jaccard) состава, при котором кластер наследует id прежней группы
+pub const GROUP_ID_MIN_OVERLAP: f32 = 0.5;
+/// Срок хранения группы от пира без обновлений (секунд)
+pub const REMOTE_GROUP_TTL_SECS: i64 = 30 * 86_400;
+
+/// Детерминированный label propagation по неориентированному взвешенному графу.
+/// Узлы обходятся в лексикографическом порядке; узел принимает метку с максимальным
+/// суммарным весом среди соседей, при равенстве — текущую метку, иначе наименьшую.
+/// Рёбра с неположительным весом и петли игнорируются. Изолированные узлы образуют
+/// собственные кластеры. Возвращает кластеры (участники отсортированы).
+pub fn label_propagation(
+    nodes: &[String],
+    edges: &[(String, String, f32)],
+    max_iter: usize,
+) -> Vec<Vec<String>> {
+    let mut order: Vec<&String> = nodes.iter().collect();
+    order.sort();
+    order.dedup();
+
+    let known: HashSet<&str> = order.iter().map(|s| s.as_str()).collect();
+    let mut adj: HashMap<&str, Vec<(&str, f32)>> = HashMap::new();
+    for (a, b, w) in edges {
+        if *w <= 0.0 || a == b || !known.contains(a.as_str()) || !known.contains(b.as_str()) {
+            continue;
+        }
+        adj.entry(a.as_str()).or_default().push((b.as_str(), *w));
+        adj.entry(b.as_str()).or_default().push((a.as_str(), *w));
+    }
+
+    let mut labels: HashMap<&str, &str> = order.iter().map(|n| (n.as_str(), n.as_str())).collect();
+    for _ in 0..max_iter {
+        let mut changed = false;
+        for node in &order {
+            let node = node.as_str();
+            let Some(neighbours) = adj.get(node) else { continue };
+            // BTreeMap — детерминированный порядок меток при равенстве весов
+            let mut weights: BTreeMap<&str, f32> = BTreeMap::new();
+            for (nb, w) in neighbours {
+                *weights.entry(labels[nb]).or_insert(0.0) += *w;
+            }
+            let best_w = weights.values().cloned().fold(f32::MIN, f32::max);
+            let current = labels[node];
+            let best = if (weights.get(current).copied().unwrap_or(f32::MIN) - best_w).abs() < 1e-6 {
+                current
+            } else {
+                weights
+                    .iter()
+                    .find(|(_, w)| (**w - best_w).abs() < 1e-6)
+                    .map(|(l, _)| *l)
+                    .unwrap_or(current)
+            };
+            if best != current {
+                labels.insert(node, best);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut clusters: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for node in &order {
+        clusters.entry(labels[node.as_str()]).or_default().push((*node).clone());
+    }
+    clusters.into_values().collect()
+}
+
+/// Назначить кластерам стабильные id групп.
+/// Кластер наследует id прежней группы с наибольшим пересечением состава (Jaccard >= GROUP_ID_MIN_OVERLAP),
+/// каждая прежняя группа используется не более одного раза. Остальные получают id из хэша состава,
+/// одинаковый на всех узлах при одинаковых данных.
+pub fn assign_group_ids(clusters: Vec<Vec<String>>, previous: &[GroupRating]) -> Vec<(String, Vec<String>)> {
+    fn jaccard(a: &HashSet<&str>, b: &HashSet<&str>) -> f32 {
+        let inter = a.intersection(b).count() as f32;
+        let union = a.union(b).count() as f32;
+        if union == 0.0 { 0.0 } else { inter / union }
+    }
+
+    let cluster_sets: Vec<HashSet<&str>> = clusters
+        .iter()
+        .map(|c| c.iter().map(|s| s.as_str()).collect())
+        .collect();
+    let prev_sets: Vec<HashSet<&str>> = previous
+        .iter()
+        .map(|g| g.members.iter().map(|s| s.as_str()).collect())
+        .collect();
+
+    // Кандидаты (кластер, прежняя группа, сходство) — жадно от наибольшего сходства
+    let mut candidates: Vec<(usize, usize, f32)> = Vec::new();
+    for (ci, cs) in cluster_sets.iter().enumerate() {
+        for (pi, ps) in prev_sets.iter().enumerate() {
+            let j = jaccard(cs, ps);
+            if j >= GROUP_ID_MIN_OVERLAP {
+                candidates.push((ci, pi, j));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| {
+        b.2.partial_cmp(&a.2)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| previous[a.1].group_id.cmp(&previous[b.1].group_id))
+            .then_with(|| a.0.cmp(&b.0))
+    });
+
+    let mut ids: Vec<Option<String>> = vec![None; clusters.len()];
+    let mut used: HashSet<String> = HashSet::new();
+    for (ci, pi, _) in candidates {
+        let id = &previous[pi].group_id;
+        if ids[ci].is_some() || used.contains(id) {
+            continue;
+        }
+        ids[ci] = Some(id.clone());
+        used.insert(id.clone());
+    }
+
+    clusters
+        .into_iter()
+        .zip(ids)
+        .map(|(members, id)| {
+            let id = id.unwrap_or_else(|| {
+                let hash = blake3::hash(members.join(",").as_bytes()).to_hex();
+                let mut candidate = format!("grp-{}", &hash[..12]);
+                let mut n = 1;
+                while used.contains(&candidate) {
+                    candidate = format!("grp-{}-{}", &hash[..12], n);
+                    n += 1;
+                }
+                used.insert(candidate.clone());
+                candidate
+            });
+            (id, members)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(v: &str) -> String { v.to_string() }
+
+    #[test]
+    fn label_propagation_splits_disconnected_clusters() {
+        let nodes: Vec<String> = ["a", "b", "c", "x", "y", "lonely"].iter().map(|v| s(v)).collect();
+        let edges = vec![
+            (s("a"), s("b"), 2.0),
+            (s("b"), s("c"), 1.0),
+            (s("c"), s("a"), 1.0),
+            (s("x"), s("y"), 1.0),
+            (s("c"), s("x"), 0.0), // нулевой вес не связывает кластеры
+        ];
+        let clusters = label_propagation(&nodes, &edges, LABEL_PROPAGATION_MAX_ITER);
+        assert_eq!(clusters.len(), 3);
+        assert!(clusters.contains(&vec![s("a"), s("b"), s("c")]));
+        assert!(clusters.contains(&vec![s("x"), s("y")]));
+        assert!(clusters.contains(&vec![s("lonely")]));
+        // Детерминированность
+        assert_eq!(clusters, label_propagation(&nodes, &edges, LABEL_PROPAGATION_MAX_ITER));
+    }
+
+    #[test]
+    fn group_ids_stay_stable_across_recalcs() {
+        let first = assign_group_ids(vec![vec![s("a"), s("b"), s("c")], vec![s("x"), s("y")]], &[]);
+        assert!(first.iter().all(|(id, _)| id.starts_with("grp-")));
+        let previous: Vec<GroupRating> = first
+            .iter()
+            .map(|(id, m)| GroupRating { group_id: id.clone(), members: m.clone(), avg_score: 0.0, coherence: 0.0, last_updated: 0 })
+            .collect();
+
+        // Состав слегка изменился — id наследуются
+        let second = assign_group_ids(vec![vec![s("a"), s("b"), s("c"), s("d")], vec![s("x"), s("y")], vec![s("z")]], &previous);
+        assert_eq!(second[0].0, first[0].0);
+        assert_eq!(second[1].0, first[1].0);
+        assert!(second[2].0 != first[0].0 && second[2].0 != first[1].0);
+
+        // Сильно изменившийся состав получает новый id
+        let third = assign_group_ids(vec![vec![s("a"), s("q"), s("r"), s("t")]], &previous);
+        assert!(third[0].0 != first[0].0);
+    }
+}
@@ -0,0 +1,367 @@
+use crate::at_rest::{from_hex, to_hex};
+use crate::models::{EventRelation, TruthEvent};
+use crate::storage;
+use crate::store::Store;
+use crate::CoreError;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Итог применения связи
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Applied {
+    Created,
+    /// Связь уже известна
+    Known,
+    /// Одного из событий локально нет — связь пропущена до следующей синхронизации
+    MissingTarget,
+    /// Одно из событий удалено отметкой — связь отклонена
+    Deleted,
+    /// duplicate_of или supersedes подписал не автор source и не администратор
+    Forbidden,
+}
+
+/// Подписать связь ключом узла; author должен быть его публичным ключом
+pub fn sign(rel: &mut EventRelation, key: &SigningKey) {
+    rel.signature = to_hex(&key.sign(rel.signing_message().as_bytes()).to_bytes());
+}
+
+/// Проверить вид связи, события, id (выводится из содержимого) и подпись author
+pub fn verify(rel: &EventRelation) -> Result<(), CoreError> {
+    if !EventRelation::RELATIONS.contains(&rel.relation.as_str()) {
+        return Err(CoreError::InvalidArg(format!(
+            "unknown event relation {}, expected one of: {}",
+            rel.relation,
+            EventRelation::RELATIONS.join(", ")
+        )));
+    }
+    if rel.source_uid.is_empty() || rel.target_uid.is_empty() {
+        return Err(CoreError::InvalidArg(format!("event relation {}: event uid is empty", rel.id)));
+    }
+    if rel.source_uid == rel.target_uid {
+        return Err(CoreError::InvalidArg(format!("event relation {}: an event cannot relate to itself", rel.id)));
+    }
+    if EventRelation::new(&rel.source_uid, &rel.target_uid, &rel.relation, &rel.author, rel.created_at).id != rel.id {
+        return Err(CoreError::InvalidArg(format!("event relation {}: id does not match content", rel.id)));
+    }
+    let pk: [u8; 32] = from_hex(&rel.author)?
+        .try_into()
+        .map_err(|_| CoreError::InvalidArg(format!("event relation {}: bad author key", rel.id)))?;
+    let key = VerifyingKey::from_bytes(&pk).map_err(|e| CoreError::InvalidArg(e.to_string()))?;
+    let sig = Signature::from_slice(&from_hex(&rel.signature)?).map_err(|e| CoreError::InvalidArg(e.to_string()))?;
+    key.verify(rel.signing_message().as_bytes(), &sig)
+        .map_err(|_| CoreError::InvalidArg(format!("event relation {}: signature is invalid", rel.id)))
+}
+
+/// Применить подписанную связь (локальную или от пира): подпись, наличие обоих событий, право автора
+/// на duplicate_of и supersedes. Новая связь попадает в sync_log; дубликат сразу сливается
+pub fn apply(conn: &Connection, rel: &EventRelation) -> Result<Applied, CoreError> {
+    verify(rel)?;
+    if storage::get_event_relation(conn, &rel.id)?.is_some() {
+        return Ok(Applied::Known);
+    }
+    let mut source_author = None;
+    for uid in [&rel.source_uid, &rel.target_uid] {
+        match storage::get_truth_event_by_uid(conn, uid)? {
+            Some(ev) if *uid == rel.source_uid => source_author = ev.public_key,
+            Some(_) => {}
+            None if !storage::get_tombstones_for(conn, "truth_events", uid)?.is_empty() => return Ok(Applied::Deleted),
+            None => return Ok(Applied::MissingTarget),
+        }
+    }
+    if EventRelation::OWNER_RELATIONS.contains(&rel.relation.as_str())
+        && source_author.as_deref() != Some(rel.author.as_str())
+        && !conn.is_admin(&rel.author)?
+    {
+        return Ok(Applied::Forbidden);
+    }
+    storage::insert_event_relation(conn, rel)?;
+    storage::log_sync(conn, "insert", "event_relations", &rel.id, Some(rel.signature.clone()), Some(rel.author.clone()))?;
+    if rel.relation == "duplicate_of" {
+        fold_duplicates(conn)?;
+    }
+    Ok(Applied::Created)
+}
+
+/// Связи duplicate_of между локальными событиями: source → target. Если у события их несколько,
+/// действует самая ранняя
+fn duplicates(conn: &Connection) -> Result<HashMap<String, String>, CoreError> {
+    let mut stmt = conn.prepare(
+        "SELECT r.source_uid, r.target_uid FROM event_relations r
+         JOIN truth_events s ON s.uid = r.source_uid
+         JOIN truth_events t ON t.uid = r.target_uid
+         WHERE r.relation = 'duplicate_of' ORDER BY r.created_at, r.id",
+    )?;
+    let rows = stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))?;
+    let mut map = HashMap::new();
+    for row in rows {
+        let (source, target) = row?;
+        map.entry(source).or_insert(target);
+    }
+    Ok(map)
+}
+
+/// Конец цепочки дубликатов; в цикле каноническое — событие цикла с наименьшим uid, одинаково на всех узлах
+fn resolve(map: &HashMap<String, String>, uid: &str) -> String {
+    let mut path = vec![uid.to_string()];
+    let mut current = uid.to_string();
+    while let Some(next) = map.get(&current) {
+        if let Some(pos) = path.iter().position(|p| p == next) {
+            return path[pos..].iter().min().cloned().unwrap_or_default();
+        }
+        path.push(next.clone());
+        current = next.clone();
+    }
+    current
+}
+
+/// Каноническое событие для uid: само событие, если оно не объявлено дубликатом
+pub fn canonical_uid(conn: &Connection, uid: &str) -> Result<String, CoreError> {
+    Ok(resolve(&duplicates(conn)?, uid))
+}
+
+/// Перенести утверждения и влияния всех дубликатов в их канонические события. Идемпотентно:
+/// вызывается после синхронизации, чтобы слить записи, пришедшие к дубликату позже связи.
+/// Возвращает число перенесённых строк.
+pub fn fold_duplicates(conn: &Connection) -> Result<usize, CoreError> {
+    let map = duplicates(conn)?;
+    let targets: BTreeMap<&String, String> = map.keys().map(|uid| (uid, resolve(&map, uid))).collect();
+    let mut moved = 0;
+    for (uid, canonical) in targets {
+        if *uid != canonical {
+            moved += storage::move_event_records(conn, uid, &canonical)?;
+        }
+    }
+    Ok(moved)
+}
+
+/// Связанное событие в карточке события; outgoing — карточное событие является source связи
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelatedEvent {
+    pub uid: String,
+    /// Описание, если событие есть локально
+    pub description: Option<String>,
+    pub outgoing: bool,
+    pub relation: EventRelation,
+}
+
+/// Карточка события: само событие, его каноническое событие и связанные события
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventDetail {
+    pub event: TruthEvent,
+    pub canonical_uid: String,
+    pub related: Vec<RelatedEvent>,
+}
+
+/// Карточка события по uid; None — события нет
+pub fn event_detail(conn: &Connection, uid: &str) -> Result<Option<EventDetail>, CoreError> {
+    let Some(event) = storage::get_truth_event_by_uid(conn, uid)? else { return Ok(None) };
+    let mut related = Vec::new();
+    for relation in storage::relations_for_event(conn, uid)? {
+        let outgoing = relation.source_uid == uid;
+        let other = if outgoing { relation.target_uid.clone() } else { relation.source_uid.clone() };
+        let description = storage::get_truth_event_by_uid(conn, &other)?.map(|e| e.description);
+        related.push(RelatedEvent { uid: other, description, outgoing, relation });
+    }
+    Ok(Some(EventDetail { canonical_uid: canonical_uid(conn, uid)?, event, related }))
+}
+
+/// Вершина графа событий
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventGraphNode {
+    pub uid: String,
+    pub description: String,
+    pub canonical_uid: String,
+    /// Оценка события по дереву аргументов (argument_scores), если посчитана
+    pub score: Option<f32>,
+}
+
+/// Ребро графа событий: связь source → target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventGraphLink {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    pub relation: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventGraph {
+    pub nodes: Vec<EventGraphNode>,
+    pub links: Vec<EventGraphLink>,
+}
+
+/// Граф связей событий: все связи или окрестность события root глубиной depth (в обе стороны)
+pub fn event_graph(conn: &Connection, root: Option<&str>, depth: usize) -> Result<EventGraph, CoreError> {
+    let relations = storage::load_event_relations(conn, None)?;
+    let selected: Vec<&EventRelation> = match root {
+        None => relations.iter().collect(),
+        Some(root) => {
+            let mut seen: BTreeSet<&str> = BTreeSet::from([root]);
+            let mut frontier = vec![root];
+            let mut selected: BTreeMap<&str, &EventRelation> = BTreeMap::new();
+            for _ in 0..depth {
+                let mut next = Vec::new();
+                for rel in &relations {
+                    let (s, t) = (rel.source_uid.as_str(), rel.target_uid.as_str());
+                    if frontier.contains(&s) || frontier.contains(&t) {
+                        selected.insert(rel.id.as_str(), rel);
+                        for uid in [s, t] {
+                            if seen.insert(uid) {
+                                next.push(uid);
+                            }
+                        }
+                    }
+                }
+                frontier = next;
+            }
+            selected.into_values().collect()
+        }
+    };
+
+    let duplicates = duplicates(conn)?;
+    let mut nodes = Vec::new();
+    let mut known = BTreeSet::new();
+    let uids: BTreeSet<&str> = root.into_iter().chain(selected.iter().flat_map(|r| [r.source_uid.as_str(), r.target_uid.as_str()])).collect();
+    for uid in uids {
+        let Some(event) = storage::get_truth_event_by_uid(conn, uid)? else { continue };
+        let score: Option<f64> = conn
+            .query_row("SELECT score FROM argument_scores WHERE event_uid = ?1", params![uid], |r| r.get(0))
+            .optional()?;
+        nodes.push(EventGraphNode {
+            uid: event.uid.clone(),
+            description: event.description,
+            canonical_uid: resolve(&duplicates, uid),
+            score: score.map(|s| s as f32),
+        });
+        known.insert(uid);
+    }
+    let links = selected
+        .into_iter()
+        .filter(|r| known.contains(r.source_uid.as_str()) && known.contains(r.target_uid.as_str()))
+        .map(|r| EventGraphLink { id: r.id.clone(), source: r.source_uid.clone(), target: r.target_uid.clone(), relation: r.relation.clone() })
+        .collect();
+    Ok(EventGraph { nodes, links })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NewStatement, NewTruthEvent, Tombstone};
+    use crate::tombstone;
+
+    fn setup() -> (Connection, SigningKey, String) {
+        let mut conn = storage::open_db(":memory:").unwrap();
+        storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let key = SigningKey::from_bytes(&[6; 32]);
+        let pk = to_hex(key.verifying_key().as_bytes());
+        (conn, key, pk)
+    }
+
+    fn event(conn: &Connection, author: &str) -> (i64, String) {
+        let id = storage::add_truth_event(
+            conn,
+            NewTruthEvent { description: "bridge closed".into(), context_id: 1, vector: true, timestamp_start: 100, code: 1 },
+        )
+        .unwrap();
+        conn.execute("UPDATE truth_events SET public_key = ?1 WHERE id = ?2", rusqlite::params![author, id]).unwrap();
+        (id, storage::get_truth_event(conn, id).unwrap().unwrap().uid)
+    }
+
+    fn signed(key: &SigningKey, pk: &str, source: &str, target: &str, relation: &str, at: i64) -> EventRelation {
+        let mut rel = EventRelation::new(source, target, relation, pk, at);
+        sign(&mut rel, key);
+        rel
+    }
+
+    #[test]
+    fn duplicates_fold_into_the_canonical_event() {
+        let (conn, key, pk) = setup();
+        let (a, a_uid) = event(&conn, &pk);
+        let (b, b_uid) = event(&conn, &pk);
+        let (c, c_uid) = event(&conn, &pk);
+        for ev in [a, b] {
+            storage::add_statement(&conn, NewStatement { event_id: ev, text: "closed".into(), context: None, truth_score: Some(0.5), parent_uid: None, relation: None }).unwrap();
+        }
+        storage::add_impact(&conn, a, 1, true, None).unwrap();
+
+        // a → b → c: всё сливается в c
+        assert_eq!(apply(&conn, &signed(&key, &pk, &a_uid, &b_uid, "duplicate_of", 1)).unwrap(), Applied::Created);
+        assert_eq!(storage::get_statements_for_event(&conn, b).unwrap().len(), 2);
+        assert_eq!(apply(&conn, &signed(&key, &pk, &b_uid, &c_uid, "duplicate_of", 2)).unwrap(), Applied::Created);
+        assert_eq!(storage::get_statements_for_event(&conn, c).unwrap().len(), 2);
+        assert!(storage::get_statements_for_event(&conn, a).unwrap().is_empty());
+        let impacts: i64 = conn.query_row("SELECT COUNT(*) FROM impact WHERE event_id = ?1", [&c_uid], |r| r.get(0)).unwrap();
+        assert_eq!(impacts, 1);
+        assert_eq!(canonical_uid(&conn, &a_uid).unwrap(), c_uid);
+
+        // Цикл c → a сходится к наименьшему uid цикла на любом узле
+        apply(&conn, &signed(&key, &pk, &c_uid, &a_uid, "duplicate_of", 3)).unwrap();
+        let least = [&a_uid, &b_uid, &c_uid].into_iter().min().unwrap().clone();
+        assert_eq!(canonical_uid(&conn, &b_uid).unwrap(), least);
+        assert_eq!(fold_duplicates(&conn).unwrap(), 0);
+        let least_id = storage::get_truth_event_by_uid(&conn, &least).unwrap().unwrap().id;
+        assert_eq!(storage::get_statements_for_event(&conn, least_id).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn detail_and_graph_show_related_events() {
+        let (conn, key, pk) = setup();
+        let (_, a) = event(&conn, &pk);
+        let (_, b) = event(&conn, &pk);
+        let (_, c) = event(&conn, &pk);
+        let (_, far) = event(&conn, &pk);
+        apply(&conn, &signed(&key, &pk, &a, &b, "contradicts", 1)).unwrap();
+        apply(&conn, &signed(&key, &pk, &c, &a, "duplicate_of", 2)).unwrap();
+        apply(&conn, &signed(&key, &pk, &b, &far, "supports", 3)).unwrap();
+
+        let detail = event_detail(&conn, &c).unwrap().unwrap();
+        assert_eq!(detail.canonical_uid, a);
+        assert_eq!(detail.related.len(), 1);
+        assert!(detail.related[0].outgoing && detail.related[0].uid == a);
+        let detail = event_detail(&conn, &a).unwrap().unwrap();
+        let related: Vec<(&str, &str, bool)> =
+            detail.related.iter().map(|r| (r.uid.as_str(), r.relation.relation.as_str(), r.outgoing)).collect();
+        assert_eq!(related, vec![(b.as_str(), "contradicts", true), (c.as_str(), "duplicate_of", false)]);
+        assert!(event_detail(&conn, "missing").unwrap().is_none());
+
+        let near = event_graph(&conn, Some(&a), 1).unwrap();
+        assert_eq!((near.nodes.len(), near.links.len()), (3, 2));
+        assert!(near.nodes.iter().all(|n| n.canonical_uid == a || n.uid == b));
+        let all = event_graph(&conn, None, 0).unwrap();
+        assert_eq!((all.nodes.len(), all.links.len()), (4, 3));
+        assert_eq!(event_graph(&conn, Some(&a), 2).unwrap().links.len(), 3);
+    }
+
+    #[test]
+    fn apply_checks_signature_events_and_rights() {
+        let (conn, key, pk) = setup();
+        let (_, mine) = event(&conn, &pk);
+        let (_, theirs) = event(&conn, "someone-else");
+
+        let rel = signed(&key, &pk, &mine, &theirs, "contradicts", 1);
+        assert_eq!(apply(&conn, &rel).unwrap(), Applied::Created);
+        assert_eq!(apply(&conn, &rel).unwrap(), Applied::Known);
+        assert_eq!(storage::relations_for_event(&conn, &theirs).unwrap(), vec![rel]);
+
+        // Объявить чужое событие дубликатом может только его автор или администратор
+        assert_eq!(apply(&conn, &signed(&key, &pk, &theirs, &mine, "supersedes", 2)).unwrap(), Applied::Forbidden);
+        storage::update_user_role(&conn, &pk, "admin").unwrap();
+        assert_eq!(apply(&conn, &signed(&key, &pk, &theirs, &mine, "supersedes", 2)).unwrap(), Applied::Created);
+
+        assert_eq!(apply(&conn, &signed(&key, &pk, &mine, "later", "supports", 3)).unwrap(), Applied::MissingTarget);
+        assert!(apply(&conn, &signed(&key, &pk, &mine, &mine, "supports", 3)).is_err());
+        assert!(apply(&conn, &signed(&key, &pk, &mine, &theirs, "related", 3)).is_err());
+        let mut forged = signed(&key, &pk, &mine, &theirs, "supports", 4);
+        forged.note = Some("edited".into());
+        assert!(apply(&conn, &forged).is_err());
+
+        // Удаление события убирает его связи; связь с удалённым событием отклоняется
+        let mut t = Tombstone::new("truth_events", &theirs, &pk, None, 10);
+        tombstone::sign(&mut t, &key);
+        tombstone::apply(&conn, &t).unwrap();
+        assert!(storage::relations_for_event(&conn, &mine).unwrap().is_empty());
+        assert_eq!(apply(&conn, &signed(&key, &pk, &mine, &theirs, "supports", 5)).unwrap(), Applied::Deleted);
+    }
+}
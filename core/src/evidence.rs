@@ -0,0 +1,279 @@
+use crate::at_rest::{from_hex, to_hex};
+use crate::models::Evidence;
+use crate::storage;
+use crate::CoreError;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Итог применения доказательства
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Applied {
+    Created,
+    /// Доказательство уже известно
+    Known,
+    /// Записи, к которой оно прикреплено, локально нет — доказательство пропущено до следующей синхронизации
+    MissingTarget,
+    /// Запись удалена отметкой — доказательство отклонено
+    Deleted,
+}
+
+/// SHA-256 содержимого в hex
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+/// Строка — SHA-256 в hex (64 символа в нижнем регистре)
+pub fn is_sha256(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// Тип содержимого по расширению файла
+pub fn media_type_for(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match ext.as_str() {
+        "txt" | "log" => "text/plain",
+        "csv" => "text/csv",
+        "json" => "application/json",
+        "html" | "htm" => "text/html",
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "mp4" => "video/mp4",
+        "zip" => "application/zip",
+        _ => return None,
+    })
+}
+
+/// Локальное хранилище блобов: файл root/ab/abcdef… на каждый SHA-256 содержимого
+#[derive(Debug, Clone)]
+pub struct BlobStore {
+    root: PathBuf,
+}
+
+impl BlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Путь к блобу; sha256 проверяется, чтобы не выйти за пределы root
+    pub fn path(&self, sha256: &str) -> Result<PathBuf, CoreError> {
+        if !is_sha256(sha256) {
+            return Err(CoreError::InvalidArg(format!("not a sha256 hash: {}", sha256)));
+        }
+        Ok(self.root.join(&sha256[..2]).join(sha256))
+    }
+
+    pub fn contains(&self, sha256: &str) -> bool {
+        self.path(sha256).is_ok_and(|p| p.is_file())
+    }
+
+    /// Сохранить содержимое; возвращает его SHA-256. Запись атомарна: временный файл и переименование
+    pub fn put(&self, bytes: &[u8]) -> Result<String, CoreError> {
+        let sha = sha256_hex(bytes);
+        let path = self.path(&sha)?;
+        if !path.is_file() {
+            fs::create_dir_all(path.parent().unwrap_or(&self.root))?;
+            let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+            fs::write(&tmp, bytes)?;
+            fs::rename(&tmp, &path)?;
+        }
+        Ok(sha)
+    }
+
+    /// Сохранить содержимое, полученное по известному хешу (от пира или клиента)
+    pub fn put_expected(&self, sha256: &str, bytes: &[u8]) -> Result<(), CoreError> {
+        let actual = sha256_hex(bytes);
+        if actual != sha256 {
+            return Err(CoreError::InvalidArg(format!("blob hash mismatch: expected {}, got {}", sha256, actual)));
+        }
+        self.put(bytes).map(|_| ())
+    }
+
+    /// Содержимое блоба; None — блоба нет. Повреждённый блоб (хеш не сходится) — ошибка
+    pub fn get(&self, sha256: &str) -> Result<Option<Vec<u8>>, CoreError> {
+        let path = self.path(sha256)?;
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let bytes = fs::read(&path)?;
+        if sha256_hex(&bytes) != sha256 {
+            return Err(CoreError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("blob {} is corrupted", sha256),
+            )));
+        }
+        Ok(Some(bytes))
+    }
+}
+
+/// Неподписанное доказательство-файл: содержимое копируется в хранилище блобов
+pub fn from_file(
+    blobs: &BlobStore,
+    path: &Path,
+    target_table: &str,
+    target_uid: &str,
+    author: &str,
+    created_at: i64,
+) -> Result<Evidence, CoreError> {
+    let bytes = fs::read(path)?;
+    let sha = blobs.put(&bytes)?;
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    let mut e = Evidence::new(target_table, target_uid, "file", Some(sha), name, author, created_at);
+    e.media_type = media_type_for(path).map(String::from);
+    e.size = Some(bytes.len() as i64);
+    Ok(e)
+}
+
+/// Подписать доказательство ключом узла; author должен быть его публичным ключом
+pub fn sign(e: &mut Evidence, key: &SigningKey) {
+    e.signature = to_hex(&key.sign(e.signing_message().as_bytes()).to_bytes());
+}
+
+/// Проверить таблицу, вид, хеш, id (выводится из содержимого) и подпись author
+pub fn verify(e: &Evidence) -> Result<(), CoreError> {
+    if !Evidence::TABLES.contains(&e.target_table.as_str()) {
+        return Err(CoreError::InvalidArg(format!("evidence cannot be attached to {}", e.target_table)));
+    }
+    if !Evidence::KINDS.contains(&e.kind.as_str()) {
+        return Err(CoreError::InvalidArg(format!("unknown evidence kind: {}", e.kind)));
+    }
+    match (e.kind.as_str(), e.sha256.as_deref()) {
+        ("file", None) => return Err(CoreError::InvalidArg(format!("evidence {}: file without sha256", e.id))),
+        (_, Some(sha)) if !is_sha256(sha) => {
+            return Err(CoreError::InvalidArg(format!("evidence {}: not a sha256 hash: {}", e.id, sha)))
+        }
+        _ => {}
+    }
+    if e.kind == "url" && !(e.name.starts_with("http://") || e.name.starts_with("https://")) {
+        return Err(CoreError::InvalidArg(format!("evidence {}: not an http(s) URL: {}", e.id, e.name)));
+    }
+    if e.name.trim().is_empty() {
+        return Err(CoreError::InvalidArg(format!("evidence {}: name is empty", e.id)));
+    }
+    let expected = Evidence::new(&e.target_table, &e.target_uid, &e.kind, e.sha256.clone(), &e.name, &e.author, e.created_at);
+    if expected.id != e.id {
+        return Err(CoreError::InvalidArg(format!("evidence {}: id does not match content", e.id)));
+    }
+    let pk: [u8; 32] = from_hex(&e.author)?
+        .try_into()
+        .map_err(|_| CoreError::InvalidArg(format!("evidence {}: bad author key", e.id)))?;
+    let key = VerifyingKey::from_bytes(&pk).map_err(|err| CoreError::InvalidArg(err.to_string()))?;
+    let sig = Signature::from_slice(&from_hex(&e.signature)?).map_err(|err| CoreError::InvalidArg(err.to_string()))?;
+    key.verify(e.signing_message().as_bytes(), &sig)
+        .map_err(|_| CoreError::InvalidArg(format!("evidence {}: signature is invalid", e.id)))
+}
+
+/// Применить подписанное доказательство (локальное или от пира): подпись, наличие записи,
+/// к которой оно прикреплено; новое доказательство попадает в sync_log
+pub fn apply(conn: &Connection, e: &Evidence) -> Result<Applied, CoreError> {
+    verify(e)?;
+    if storage::get_evidence(conn, &e.id)?.is_some() {
+        return Ok(Applied::Known);
+    }
+    let exists = match e.target_table.as_str() {
+        "truth_events" => storage::get_truth_event_by_uid(conn, &e.target_uid)?.is_some(),
+        _ => storage::get_statement_by_uid(conn, &e.target_uid)?.is_some(),
+    };
+    if !exists {
+        let deleted = !storage::get_tombstones_for(conn, &e.target_table, &e.target_uid)?.is_empty();
+        return Ok(if deleted { Applied::Deleted } else { Applied::MissingTarget });
+    }
+    storage::insert_evidence(conn, e)?;
+    storage::log_sync(conn, "insert", "evidence", &e.id, Some(e.signature.clone()), Some(e.author.clone()))?;
+    Ok(Applied::Created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NewStatement, NewTruthEvent, Tombstone};
+    use crate::tombstone;
+
+    fn setup() -> (Connection, SigningKey, String) {
+        let mut conn = storage::open_db(":memory:").unwrap();
+        storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let key = SigningKey::from_bytes(&[5; 32]);
+        let pk = to_hex(key.verifying_key().as_bytes());
+        (conn, key, pk)
+    }
+
+    fn event(conn: &Connection) -> (i64, String) {
+        let id = storage::add_truth_event(
+            conn,
+            NewTruthEvent {
+                description: "outage".into(),
+                context_id: 1,
+                vector: true,
+                timestamp_start: 100,
+                code: 1,
+            },
+        )
+        .unwrap();
+        (id, storage::get_truth_event(conn, id).unwrap().unwrap().uid)
+    }
+
+    #[test]
+    fn blob_store_round_trip_and_rejects_bad_hashes() {
+        let dir = std::env::temp_dir().join(format!("truth_blobs_{}", std::process::id()));
+        let blobs = BlobStore::new(&dir);
+        let sha = blobs.put(b"server log").unwrap();
+        assert_eq!(sha, sha256_hex(b"server log"));
+        assert!(blobs.contains(&sha));
+        assert_eq!(blobs.get(&sha).unwrap().unwrap(), b"server log");
+        assert!(blobs.get(&sha256_hex(b"missing")).unwrap().is_none());
+
+        assert!(blobs.put_expected(&sha256_hex(b"other"), b"server log").is_err());
+        assert!(blobs.path("../../etc/passwd").is_err());
+
+        fs::write(blobs.path(&sha).unwrap(), b"tampered").unwrap();
+        assert!(blobs.get(&sha).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn signed_evidence_attaches_to_existing_records() {
+        let (conn, key, pk) = setup();
+        let (event_id, uid) = event(&conn);
+        let stmt_id = storage::add_statement(&conn, NewStatement { event_id, text: "it was down".into(), context: None, truth_score: None, parent_uid: None, relation: None }).unwrap();
+        let stmt_uid = storage::get_statement(&conn, stmt_id).unwrap().unwrap().uid;
+
+        let mut file = Evidence::new("truth_events", &uid, "file", Some(sha256_hex(b"log")), "app.log", &pk, 200);
+        sign(&mut file, &key);
+        assert_eq!(apply(&conn, &file).unwrap(), Applied::Created);
+        assert_eq!(apply(&conn, &file).unwrap(), Applied::Known);
+
+        let mut url = Evidence::new("statements", &stmt_uid, "url", None, "https://status.example/incident", &pk, 210);
+        url.note = Some("status page".into());
+        sign(&mut url, &key);
+        assert_eq!(apply(&conn, &url).unwrap(), Applied::Created);
+        assert_eq!(storage::evidence_for_event(&conn, &uid).unwrap(), vec![file.clone(), url.clone()]);
+
+        // Изменённое после подписи содержимое и чужие записи отклоняются
+        let mut forged = url.clone();
+        forged.note = Some("edited".into());
+        assert!(apply(&conn, &forged).is_err());
+        let mut orphan = Evidence::new("truth_events", "nope", "url", None, "https://example.org", &pk, 220);
+        sign(&mut orphan, &key);
+        assert_eq!(apply(&conn, &orphan).unwrap(), Applied::MissingTarget);
+        let mut bad_url = Evidence::new("truth_events", &uid, "url", None, "file:///etc/passwd", &pk, 230);
+        sign(&mut bad_url, &key);
+        assert!(apply(&conn, &bad_url).is_err());
+
+        // Удаление события уносит доказательства его и его утверждений
+        assert_eq!(storage::delete_record(&conn, "truth_events", &uid).unwrap(), 4);
+        assert!(storage::load_evidence(&conn, None).unwrap().is_empty());
+        let mut t = Tombstone::new("truth_events", &uid, &pk, None, 300);
+        tombstone::sign(&mut t, &key);
+        storage::insert_tombstone(&conn, &t).unwrap();
+        assert_eq!(apply(&conn, &file).unwrap(), Applied::Deleted);
+    }
+}
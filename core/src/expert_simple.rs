@@ -2,13 +2,33 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Тип вопроса
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum QuestionKind {
     YesNo,     // "yes" | "no"
     TriState,  // "yes" | "no" | "unknown"
     Scale1to5, // "1".."5"
 }
 
+impl QuestionKind {
+    /// Имя варианта, как в пакетах базы знаний и таблице expert_questions
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QuestionKind::YesNo => "YesNo",
+            QuestionKind::TriState => "TriState",
+            QuestionKind::Scale1to5 => "Scale1to5",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "YesNo" => Some(QuestionKind::YesNo),
+            "TriState" => Some(QuestionKind::TriState),
+            "Scale1to5" => Some(QuestionKind::Scale1to5),
+            _ => None,
+        }
+    }
+}
+
 /// Универсальный вопрос
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Question {
@@ -23,6 +43,16 @@ pub struct Question {
     pub truth_bias: bool,
 }
 
+/// Вопрос из таблицы expert_questions (загружается из пакетов базы знаний)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredQuestion {
+    pub question: Question,
+    /// Контексты, к которым относится вопрос; None — ко всем
+    pub context_ids: Option<Vec<i64>>,
+    /// Пакет, из которого загружен вопрос
+    pub pack: Option<String>,
+}
+
 /// Ответы пользователя: map<question_id, string value>
 pub type Answers = HashMap<String, String>;
 
@@ -91,6 +121,23 @@ pub fn questions_for_context(_context_name: &str) -> Vec<Question> {
     ]
 }
 
+/// Вопрос о технических/документальных подтверждениях
+pub const EVIDENCE_QUESTION: &str = "logs_evidence";
+
+/// Учесть прикреплённые к событию доказательства: если вопрос logs_evidence задан,
+/// а явного ответа нет, прикреплённые доказательства дают ответ "yes".
+/// Возвращает true, если ответ подставлен.
+pub fn apply_evidence(questions: &[Question], answers: &mut Answers, evidence_count: usize) -> bool {
+    if evidence_count == 0
+        || answers.contains_key(EVIDENCE_QUESTION)
+        || !questions.iter().any(|q| q.id == EVIDENCE_QUESTION)
+    {
+        return false;
+    }
+    answers.insert(EVIDENCE_QUESTION.to_string(), "yes".to_string());
+    true
+}
+
 /// Преобразуем ответ в числовое значение с учётом типа
 fn value_to_num(kind: &QuestionKind, raw: &str) -> Option<f32> {
     match kind {
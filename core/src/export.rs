@@ -0,0 +1,717 @@
+use crate::at_rest;
+use crate::audit;
+use crate::CoreError;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::str::FromStr;
+
+/// Имя формата в заголовке выгрузки
+pub const FORMAT_NAME: &str = "truth-training-export";
+/// Версия формата выгрузки; меняется при несовместимом изменении структуры файла
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Как разрешать запись, уже существующую в БД (по первичному ключу), при режиме merge
+#[derive(Debug, Clone, Copy)]
+enum MergeRule {
+    /// Локальная запись остаётся (справочники и журналы только на добавление)
+    Keep,
+    /// Побеждает запись с большим значением колонки
+    Newer(&'static str),
+    /// Запись принимается, если колонка пуста локально и заполнена во входящей (отзыв, подтверждение)
+    Fill(&'static str),
+}
+
+struct TableSpec {
+    name: &'static str,
+    merge: MergeRule,
+}
+
+/// Выгружаемые таблицы: родительские раньше дочерних.
+/// Не выгружаются schema_version (версия пишется в заголовок), active_tokens (секреты сессий),
+/// db_encryption (ключ этой БД; зашифрованные колонки выгружаются открытыми),
+/// audit_log и audit_checkpoints (цепочка аудита своя у каждого узла) и FTS-индексы (заполняются триггерами при импорте). Новая таблица в миграциях должна
+/// появиться здесь или в исключениях теста export_covers_every_table.
+const TABLES: &[TableSpec] = &[
+    // База знаний
+    TableSpec { name: "category", merge: MergeRule::Keep },
+    TableSpec { name: "cause", merge: MergeRule::Keep },
+    TableSpec { name: "develop", merge: MergeRule::Keep },
+    TableSpec { name: "effect", merge: MergeRule::Keep },
+    TableSpec { name: "forma", merge: MergeRule::Keep },
+    TableSpec { name: "context", merge: MergeRule::Keep },
+    TableSpec { name: "impact_type", merge: MergeRule::Keep },
+    TableSpec { name: "expert_questions", merge: MergeRule::Keep },
+    // Данные; правила совпадают с reconcile при синхронизации
+    TableSpec { name: "truth_events", merge: MergeRule::Newer("timestamp_start") },
+    TableSpec { name: "statements", merge: MergeRule::Newer("updated_at") },
+    TableSpec { name: "impact", merge: MergeRule::Keep },
+    TableSpec { name: "tombstones", merge: MergeRule::Keep },
+    TableSpec { name: "evidence", merge: MergeRule::Keep },
+    TableSpec { name: "event_relations", merge: MergeRule::Keep },
+    TableSpec { name: "progress_metrics", merge: MergeRule::Keep },
+    // Рейтинги и метрики
+    TableSpec { name: "node_ratings", merge: MergeRule::Newer("last_updated") },
+    TableSpec { name: "group_ratings", merge: MergeRule::Newer("last_updated") },
+    TableSpec { name: "argument_scores", merge: MergeRule::Newer("updated_at") },
+    TableSpec { name: "node_metrics", merge: MergeRule::Newer("last_seen") },
+    TableSpec { name: "trust_history", merge: MergeRule::Keep },
+    TableSpec { name: "trust_delegations", merge: MergeRule::Fill("revoked_at") },
+    TableSpec { name: "alerts", merge: MergeRule::Fill("acknowledged_at") },
+    // Пользователи
+    TableSpec { name: "roles", merge: MergeRule::Keep },
+    TableSpec { name: "users", merge: MergeRule::Newer("last_updated") },
+    // Коллективный интеллект
+    TableSpec { name: "participants", merge: MergeRule::Newer("last_activity") },
+    TableSpec { name: "events_ci", merge: MergeRule::Keep },
+    TableSpec { name: "judgments", merge: MergeRule::Keep },
+    TableSpec { name: "consensus_ci", merge: MergeRule::Newer("calculated_at") },
+    TableSpec { name: "reputation_history", merge: MergeRule::Keep },
+    // История синхронизации
+    TableSpec { name: "sync_log", merge: MergeRule::Keep },
+    TableSpec { name: "sync_logs", merge: MergeRule::Keep },
+    TableSpec { name: "peer_history", merge: MergeRule::Newer("last_sync") },
+    // Настройки узла
+    TableSpec { name: "retention_policies", merge: MergeRule::Newer("updated_at") },
+];
+
+/// Формат файла: JSON-документ целиком или NDJSON (строка на запись, для больших БД)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    Ndjson,
+}
+
+impl ExportFormat {
+    /// Формат по расширению файла: .ndjson/.jsonl — NDJSON, иначе JSON
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".ndjson") || path.ends_with(".jsonl") { Self::Ndjson } else { Self::Json }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = CoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "ndjson" | "jsonl" => Ok(Self::Ndjson),
+            other => Err(CoreError::InvalidArg(format!("unknown export format '{}' (json | ndjson)", other))),
+        }
+    }
+}
+
+/// Что делать с записью, первичный ключ которой уже есть в БД
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Ошибка и откат всего импорта; полностью совпадающие записи пропускаются
+    Fail,
+    /// Оставить локальную запись
+    Skip,
+    /// Заменить локальную запись входящей
+    Overwrite,
+    /// Разрешить конфликт по правилам синхронизации (новее побеждает, отзыв не теряется)
+    Merge,
+}
+
+impl FromStr for ImportMode {
+    type Err = CoreError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fail" => Ok(Self::Fail),
+            "skip" => Ok(Self::Skip),
+            "overwrite" => Ok(Self::Overwrite),
+            "merge" => Ok(Self::Merge),
+            other => Err(CoreError::InvalidArg(format!("unknown import mode '{}' (fail | skip | overwrite | merge)", other))),
+        }
+    }
+}
+
+/// Заголовок выгрузки: первая строка NDJSON или поле "header" JSON-документа
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportHeader {
+    pub format: String,
+    pub version: u32,
+    pub schema_version: u32,
+    pub exported_at: i64,
+}
+
+/// Число выгруженных строк таблицы
+#[derive(Debug, Clone, Serialize)]
+pub struct TableRows {
+    pub table: String,
+    pub rows: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportReport {
+    pub header: ExportHeader,
+    pub tables: Vec<TableRows>,
+}
+
+/// Итог импорта одной таблицы
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TableImport {
+    pub table: String,
+    pub inserted: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    /// None — файл старого формата ExportData (truth_events, impacts, metrics)
+    pub header: Option<ExportHeader>,
+    pub mode: ImportMode,
+    pub tables: Vec<TableImport>,
+}
+
+/// Выгрузить все таблицы из TABLES; строки пишутся по мере чтения, без загрузки таблицы в память
+pub fn export<W: Write>(conn: &Connection, out: W, format: ExportFormat) -> Result<ExportReport, CoreError> {
+    let mut out = BufWriter::new(out);
+    let header = ExportHeader {
+        format: FORMAT_NAME.to_string(),
+        version: FORMAT_VERSION,
+        schema_version: crate::migrations::current_version(conn)?,
+        exported_at: chrono::Utc::now().timestamp(),
+    };
+    let mut tables = Vec::with_capacity(TABLES.len());
+
+    match format {
+        ExportFormat::Json => write!(out, "{{\"header\":{},\"tables\":{{", serde_json::to_string(&header)?)?,
+        ExportFormat::Ndjson => writeln!(out, "{}", serde_json::json!({ "header": header }))?,
+    }
+    for (i, spec) in TABLES.iter().enumerate() {
+        if format == ExportFormat::Json {
+            write!(out, "{}{}:[", if i > 0 { "," } else { "" }, serde_json::to_string(spec.name)?)?;
+        }
+        let mut stmt = conn.prepare(&format!("SELECT * FROM \"{}\" ORDER BY rowid", spec.name))?;
+        let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+        let mut rows = stmt.query([])?;
+        let mut count = 0usize;
+        while let Some(row) = rows.next()? {
+            let obj = row_to_json(conn, spec.name, &columns, row)?;
+            match format {
+                ExportFormat::Json => write!(out, "{}{}", if count > 0 { "," } else { "" }, Value::Object(obj))?,
+                ExportFormat::Ndjson => writeln!(out, "{}", serde_json::json!({ "table": spec.name, "row": obj }))?,
+            }
+            count += 1;
+        }
+        if format == ExportFormat::Json {
+            write!(out, "]")?;
+        }
+        tables.push(TableRows { table: spec.name.to_string(), rows: count });
+    }
+    if format == ExportFormat::Json {
+        writeln!(out, "}}}}")?;
+    }
+    out.flush()?;
+    Ok(ExportReport { header, tables })
+}
+
+/// Загрузить выгрузку в одной транзакции: при любой ошибке БД остаётся как была.
+/// Внешние ключи проверяются при commit, поэтому порядок строк в файле не важен.
+pub fn import<R: BufRead>(conn: &mut Connection, input: R, format: ExportFormat, mode: ImportMode) -> Result<ImportReport, CoreError> {
+    let tx = conn.transaction()?;
+    tx.execute_batch("PRAGMA defer_foreign_keys = ON;")?;
+    let mut importer = Importer { conn: &tx, mode, schemas: HashMap::new(), counts: Vec::new() };
+
+    let header = match format {
+        ExportFormat::Json => {
+            let doc: Value = serde_json::from_reader(input)?;
+            importer.import_document(doc)?
+        }
+        ExportFormat::Ndjson => {
+            let mut lines = input.lines();
+            let first = loop {
+                match lines.next() {
+                    Some(line) if line.as_ref().map(|l| l.trim().is_empty()).unwrap_or(false) => continue,
+                    Some(line) => break line?,
+                    None => return Err(CoreError::InvalidArg("empty export file".into())),
+                }
+            };
+            let first: Value = serde_json::from_str(&first)?;
+            let header = importer.check_header(first.get("header"))?;
+            for line in lines {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let mut rec: Value = serde_json::from_str(&line)?;
+                let table = rec.get("table").and_then(|t| t.as_str()).map(String::from)
+                    .ok_or_else(|| CoreError::InvalidArg("NDJSON record without 'table'".into()))?;
+                match rec.get_mut("row").map(Value::take) {
+                    Some(Value::Object(row)) => importer.apply_row(&table, row)?,
+                    _ => return Err(CoreError::InvalidArg(format!("{}: NDJSON record without 'row' object", table))),
+                }
+            }
+            Some(header)
+        }
+    };
+
+    let tables = std::mem::take(&mut importer.counts);
+    tx.commit().map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(f, _) if f.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_FOREIGNKEY => {
+            CoreError::InvalidArg("imported rows reference records missing from both the export and the database".into())
+        }
+        e => e.into(),
+    })?;
+    Ok(ImportReport { header, mode, tables })
+}
+
+/// Выгрузить БД в файл
+pub fn export_to_file(conn: &Connection, path: &str, format: ExportFormat) -> Result<ExportReport, CoreError> {
+    export(conn, std::fs::File::create(path)?, format)
+}
+
+/// Загрузить выгрузку из файла
+pub fn import_from_file(conn: &mut Connection, path: &str, format: ExportFormat, mode: ImportMode) -> Result<ImportReport, CoreError> {
+    import(conn, BufReader::new(std::fs::File::open(path)?), format, mode)
+}
+
+struct TableSchema {
+    columns: Vec<String>,
+    primary_key: Vec<String>,
+}
+
+struct Importer<'a> {
+    conn: &'a Connection,
+    mode: ImportMode,
+    schemas: HashMap<String, TableSchema>,
+    counts: Vec<TableImport>,
+}
+
+impl Importer<'_> {
+    fn check_header(&self, header: Option<&Value>) -> Result<ExportHeader, CoreError> {
+        let header: ExportHeader = match header {
+            Some(h) => serde_json::from_value(h.clone())?,
+            None => return Err(CoreError::InvalidArg("export header is missing".into())),
+        };
+        if header.format != FORMAT_NAME {
+            return Err(CoreError::InvalidArg(format!("unknown export format '{}'", header.format)));
+        }
+        if header.version > FORMAT_VERSION {
+            return Err(CoreError::InvalidArg(format!(
+                "export format version {} is newer than supported {}", header.version, FORMAT_VERSION
+            )));
+        }
+        let local = crate::migrations::current_version(self.conn)?;
+        if header.schema_version > local {
+            return Err(CoreError::InvalidArg(format!(
+                "export was made with schema version {}, local database is at {}; run migrations first",
+                header.schema_version, local
+            )));
+        }
+        Ok(header)
+    }
+
+    fn import_document(&mut self, mut doc: Value) -> Result<Option<ExportHeader>, CoreError> {
+        // Старый ExportData: поля моделей совпадают с колонками таблиц
+        if doc.get("header").is_none() && doc.get("truth_events").is_some() {
+            self.assign_legacy_uids(&mut doc)?;
+            for (key, table) in [("truth_events", "truth_events"), ("impacts", "impact"), ("metrics", "progress_metrics")] {
+                self.apply_rows(table, doc.get_mut(key).map(Value::take))?;
+            }
+            return Ok(None);
+        }
+        let header = self.check_header(doc.get("header"))?;
+        let mut tables = match doc.get_mut("tables").map(Value::take) {
+            Some(Value::Object(t)) => t,
+            _ => return Err(CoreError::InvalidArg("export has no 'tables' object".into())),
+        };
+        for spec in TABLES {
+            self.apply_rows(spec.name, tables.remove(spec.name))?;
+        }
+        if let Some(unknown) = tables.keys().next() {
+            return Err(CoreError::InvalidArg(format!("unknown table '{}' in export", unknown)));
+        }
+        Ok(Some(header))
+    }
+
+    /// В старом ExportData у событий нет uid, а влияния ссылаются на целый id события:
+    /// uid выводится из содержимого так же, как в миграции global_ids (TruthEvent::legacy_uid)
+    fn assign_legacy_uids(&self, doc: &mut Value) -> Result<(), CoreError> {
+        let mut uids: HashMap<i64, String> = HashMap::new();
+        if let Some(Value::Array(events)) = doc.get_mut("truth_events") {
+            for ev in events.iter_mut().filter_map(Value::as_object_mut) {
+                let Some(id) = ev.get("id").and_then(Value::as_i64) else { continue };
+                let uid = match ev.get("uid").and_then(Value::as_str) {
+                    Some(uid) if !uid.is_empty() => uid.to_string(),
+                    _ => {
+                        // Числа и строки — текстом, как их передаёт SQL-функция миграции; нет поля — пустая строка
+                        let field = |k: &str| match ev.get(k) {
+                            Some(Value::String(s)) => s.clone(),
+                            Some(Value::Number(n)) => n.to_string(),
+                            _ => String::new(),
+                        };
+                        let fields = ["context_id", "timestamp_start", "code", "public_key"].map(field);
+                        crate::models::legacy_uid(&fields.each_ref().map(String::as_str))
+                    }
+                };
+                ev.insert("uid".into(), Value::String(uid.clone()));
+                uids.insert(id, uid);
+            }
+        }
+        if let Some(Value::Array(impacts)) = doc.get_mut("impacts") {
+            for im in impacts.iter_mut().filter_map(Value::as_object_mut) {
+                let Some(id) = im.get("event_id").and_then(Value::as_str).and_then(|e| e.parse::<i64>().ok()) else { continue };
+                let uid = match uids.get(&id) {
+                    Some(uid) => Some(uid.clone()),
+                    None => self.conn.query_row("SELECT uid FROM truth_events WHERE id = ?1", [id], |r| r.get(0)).optional()?,
+                };
+                if let Some(uid) = uid {
+                    im.insert("event_id".into(), Value::String(uid));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_rows(&mut self, table: &str, rows: Option<Value>) -> Result<(), CoreError> {
+        match rows {
+            None => Ok(()),
+            Some(Value::Array(rows)) => {
+                for row in rows {
+                    match row {
+                        Value::Object(row) => self.apply_row(table, row)?,
+                        _ => return Err(CoreError::InvalidArg(format!("{}: row is not an object", table))),
+                    }
+                }
+                Ok(())
+            }
+            Some(_) => Err(CoreError::InvalidArg(format!("{}: rows must be an array", table))),
+        }
+    }
+
+    fn counts(&mut self, table: &str) -> &mut TableImport {
+        let idx = match self.counts.iter().position(|c| c.table == table) {
+            Some(i) => i,
+            None => {
+                self.counts.push(TableImport { table: table.to_string(), ..Default::default() });
+                self.counts.len() - 1
+            }
+        };
+        &mut self.counts[idx]
+    }
+
+    fn schema(&mut self, table: &str) -> Result<&TableSchema, CoreError> {
+        if !self.schemas.contains_key(table) {
+            let mut stmt = self.conn.prepare(&format!("PRAGMA table_info(\"{}\")", table))?;
+            let mut cols: Vec<(String, i64)> = stmt
+                .query_map([], |r| Ok((r.get::<_, String>(1)?, r.get::<_, i64>(5)?)))?
+                .collect::<Result<_, _>>()?;
+            let columns = cols.iter().map(|(c, _)| c.clone()).collect();
+            cols.retain(|(_, pk)| *pk > 0);
+            cols.sort_by_key(|(_, pk)| *pk);
+            let primary_key = cols.into_iter().map(|(c, _)| c).collect();
+            self.schemas.insert(table.to_string(), TableSchema { columns, primary_key });
+        }
+        Ok(&self.schemas[table])
+    }
+
+    /// Строки отслеживаемых таблиц пишутся через журнал аудита
+    fn apply_row(&mut self, table: &str, row: Map<String, Value>) -> Result<(), CoreError> {
+        let tracked = audit::key_column(table).and_then(|c| {
+            let key = match row.get(c)? {
+                Value::String(s) => s.clone(),
+                v => v.to_string(),
+            };
+            Some((c, key))
+        });
+        match tracked {
+            Some((col, key)) => {
+                let conn = self.conn;
+                audit::track(conn, table, col, &key, || self.write_row(table, row))
+            }
+            None => self.write_row(table, row),
+        }
+    }
+
+    fn write_row(&mut self, table: &str, row: Map<String, Value>) -> Result<(), CoreError> {
+        let spec = TABLES.iter().find(|s| s.name == table)
+            .ok_or_else(|| CoreError::InvalidArg(format!("unknown table '{}' in export", table)))?;
+        let conn = self.conn;
+        let mode = self.mode;
+        let schema = self.schema(table)?;
+        // Ключ строки для AAD зашифрованных колонок (см. at_rest::row_key_column)
+        let row_key = at_rest::row_key_column(table).and_then(|c| match row.get(c)? {
+            Value::Null => None,
+            Value::String(s) => Some(s.clone()),
+            v => Some(v.to_string()),
+        });
+        let mut cols = Vec::with_capacity(row.len());
+        let mut values = Vec::with_capacity(row.len());
+        for (col, v) in row {
+            if !schema.columns.contains(&col) {
+                return Err(CoreError::InvalidArg(format!("{}: unknown column '{}'", table, col)));
+            }
+            let value = match (at_rest::is_sensitive(table, &col), json_to_sql(v)) {
+                (true, SqlValue::Text(t)) => {
+                    let key = row_key.as_deref()
+                        .ok_or_else(|| CoreError::InvalidArg(format!("{}: row without a key cannot hold encrypted '{}'", table, col)))?;
+                    at_rest::seal(conn, table, &col, key, Some(&t))?.map(SqlValue::Text).unwrap_or(SqlValue::Null)
+                }
+                (_, v) => v,
+            };
+            values.push(value);
+            cols.push(col);
+        }
+        let quoted: Vec<String> = cols.iter().map(|c| format!("\"{}\"", c)).collect();
+        let placeholders = vec!["?"; cols.len()].join(", ");
+        let insert = format!(
+            "INSERT INTO \"{}\" ({}) VALUES ({}) ON CONFLICT DO NOTHING",
+            table, quoted.join(", "), placeholders
+        );
+        if conn.prepare_cached(&insert)?.execute(params_from_iter(values.iter()))? > 0 {
+            self.counts(table).inserted += 1;
+            return Ok(());
+        }
+
+        // Запись с таким ключом уже есть
+        let pk: Vec<usize> = schema.primary_key.iter().filter_map(|k| cols.iter().position(|c| c == k)).collect();
+        let updated = match mode {
+            ImportMode::Skip => false,
+            ImportMode::Fail => {
+                // Шифртекст каждый раз новый, поэтому зашифрованные колонки не сравниваются
+                let compared: Vec<usize> = (0..cols.len()).filter(|&i| !at_rest::is_sensitive(table, &cols[i])).collect();
+                let cond: Vec<String> = compared.iter().map(|&i| format!("{} IS ?", quoted[i])).collect();
+                let same = conn
+                    .prepare_cached(&format!("SELECT 1 FROM \"{}\" WHERE {}", table, cond.join(" AND ")))?
+                    .query_row(params_from_iter(compared.iter().map(|&i| &values[i])), |_| Ok(()))
+                    .optional()?
+                    .is_some();
+                if !same {
+                    let key: Vec<String> = pk.iter().map(|&i| format!("{}={}", cols[i], sql_display(&values[i]))).collect();
+                    return Err(CoreError::InvalidArg(format!("{}: conflicting row {} already exists", table, key.join(","))));
+                }
+                false
+            }
+            ImportMode::Overwrite | ImportMode::Merge => {
+                let extra = match (mode, spec.merge) {
+                    (ImportMode::Overwrite, _) => Some(String::new()),
+                    (_, MergeRule::Keep) => None,
+                    (_, MergeRule::Newer(c)) => cols.iter().position(|x| x == c)
+                        .map(|i| format!(" AND (\"{c}\" IS NULL OR \"{c}\" < ?{})", i + 1)),
+                    (_, MergeRule::Fill(c)) => cols.iter().position(|x| x == c)
+                        .map(|i| format!(" AND \"{c}\" IS NULL AND ?{} IS NOT NULL", i + 1)),
+                };
+                match extra {
+                    // Без полного первичного ключа во входящей записи обновлять нечего
+                    Some(extra) if !pk.is_empty() && pk.len() == schema.primary_key.len() => {
+                        let set: Vec<String> = quoted.iter().enumerate().map(|(i, c)| format!("{} = ?{}", c, i + 1)).collect();
+                        let key: Vec<String> = pk.iter().map(|&i| format!("{} = ?{}", quoted[i], i + 1)).collect();
+                        let sql = format!("UPDATE \"{}\" SET {} WHERE {}{}", table, set.join(", "), key.join(" AND "), extra);
+                        conn.prepare_cached(&sql)?.execute(params_from_iter(values.iter()))? > 0
+                    }
+                    _ => false,
+                }
+            }
+        };
+        let counts = self.counts(table);
+        if updated { counts.updated += 1 } else { counts.skipped += 1 }
+        Ok(())
+    }
+}
+
+/// Строка таблицы как JSON-объект колонка → значение.
+/// Зашифрованные колонки выгружаются открытыми, чтобы файл читался в любой БД.
+pub(crate) fn row_to_json(conn: &Connection, table: &str, columns: &[String], row: &rusqlite::Row<'_>) -> Result<Map<String, Value>, CoreError> {
+    let mut obj = Map::with_capacity(columns.len());
+    let row_key = at_rest::row_key(table, columns, row)?;
+    for (idx, col) in columns.iter().enumerate() {
+        let mut value = sql_to_json(table, col, row.get_ref(idx)?)?;
+        if let (true, Value::String(v)) = (at_rest::is_sensitive(table, col), &value) {
+            value = at_rest::open(conn, table, col, &row_key, Some(v.clone()))?.map(Value::String).unwrap_or(Value::Null);
+        }
+        obj.insert(col.clone(), value);
+    }
+    Ok(obj)
+}
+
+fn sql_to_json(table: &str, column: &str, v: ValueRef<'_>) -> Result<Value, CoreError> {
+    Ok(match v {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => serde_json::Number::from_f64(f).map(Value::Number).unwrap_or(Value::Null),
+        ValueRef::Text(t) => Value::String(String::from_utf8_lossy(t).into_owned()),
+        ValueRef::Blob(_) => {
+            return Err(CoreError::InvalidArg(format!("{}.{}: BLOB values are not exportable", table, column)))
+        }
+    })
+}
+
+fn json_to_sql(v: Value) -> SqlValue {
+    match v {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or(0.0)),
+        },
+        Value::String(s) => SqlValue::Text(s),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+fn sql_display(v: &SqlValue) -> String {
+    match v {
+        SqlValue::Null => "NULL".into(),
+        SqlValue::Integer(i) => i.to_string(),
+        SqlValue::Real(f) => f.to_string(),
+        SqlValue::Text(s) => s.clone(),
+        SqlValue::Blob(_) => "<blob>".into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NewStatement, NewTruthEvent, TruthEvent};
+    use crate::storage;
+
+    fn sample_db() -> Connection {
+        let mut conn = storage::open_db(":memory:").unwrap();
+        storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let ev = storage::add_truth_event(&conn, NewTruthEvent {
+            description: "bridge flood".into(), context_id: 1, vector: true, timestamp_start: 100, code: 1,
+        }).unwrap();
+        // Постоянные uid: каждый вызов даёт копию одних и тех же записей
+        conn.execute("UPDATE truth_events SET signature='sig-ev', public_key='pk-a', uid='ev-flood' WHERE id=?1", [ev]).unwrap();
+        let st = storage::add_statement(&conn, NewStatement { event_id: ev, text: "water rose".into(), context: None, truth_score: Some(0.7), parent_uid: None, relation: None }).unwrap();
+        conn.execute("UPDATE statements SET uid='st-water' WHERE id=?1", [st]).unwrap();
+        storage::add_impact(&conn, ev, 1, true, Some("ok".into())).unwrap();
+        storage::recalc_progress_metrics(&conn, 200).unwrap();
+        storage::update_user_role(&conn, "pk-a", "admin").unwrap();
+        conn.execute_batch(
+            "INSERT INTO participants (id, public_key, created_at) VALUES ('p1', 'pk-a', 1);
+             INSERT INTO events_ci (id, title, event_type, created_by, created_at) VALUES ('e1', 'ci', 'claim', 'p1', 1);
+             INSERT INTO judgments (id, participant_id, event_id, assessment, confidence_level, reasoning, submitted_at, signature)
+             VALUES ('j1', 'p1', 'e1', 'true', 0.9, 'seen it', 1, 'sig-j');",
+        ).unwrap();
+        storage::log_sync(&conn, "insert", "truth_events", &ev.to_string(), Some("sig-ev".into()), Some("pk-a".into())).unwrap();
+        storage::log_sync_event(&conn, "http://peer", "full", "success", "ok").unwrap();
+        conn
+    }
+
+    fn export_tables(conn: &Connection) -> Value {
+        let mut buf = Vec::new();
+        export(conn, &mut buf, ExportFormat::Json).unwrap();
+        let mut doc: Value = serde_json::from_slice(&buf).unwrap();
+        doc["tables"].take()
+    }
+
+    #[test]
+    fn export_covers_every_table() {
+        let conn = storage::open_db(":memory:").unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' AND name NOT LIKE '%_fts%' ORDER BY name",
+        ).unwrap();
+        let mut actual: Vec<String> = stmt.query_map([], |r| r.get(0)).unwrap().map(|r| r.unwrap()).collect();
+        actual.retain(|t| !["schema_version", "active_tokens", "db_encryption", "audit_log", "audit_checkpoints"].contains(&t.as_str()));
+        let mut listed: Vec<String> = TABLES.iter().map(|t| t.name.to_string()).collect();
+        listed.sort();
+        assert_eq!(actual, listed);
+    }
+
+    #[test]
+    fn json_and_ndjson_round_trip() {
+        let src = sample_db();
+        let expected = export_tables(&src);
+
+        for format in [ExportFormat::Json, ExportFormat::Ndjson] {
+            let mut buf = Vec::new();
+            let report = export(&src, &mut buf, format).unwrap();
+            assert_eq!(report.tables.iter().find(|t| t.table == "judgments").unwrap().rows, 1);
+
+            // Свежая БД уже содержит roles; совпадающие строки не считаются конфликтом
+            let mut dst = storage::open_db(":memory:").unwrap();
+            let imported = import(&mut dst, &buf[..], format, ImportMode::Fail).unwrap();
+            assert_eq!(imported.header.unwrap().version, FORMAT_VERSION);
+            assert_eq!(export_tables(&dst), expected);
+
+            let ev = storage::get_truth_event(&dst, 1).unwrap().unwrap();
+            assert_eq!(ev.signature.as_deref(), Some("sig-ev"));
+            let hits = storage::search(&dst, "flood", &storage::SearchFilters::default(), 10).unwrap();
+            assert_eq!(hits.len(), 1);
+
+            // Повторный импорт того же файла ничего не меняет
+            let again = import(&mut dst, &buf[..], format, ImportMode::Fail).unwrap();
+            assert!(again.tables.iter().all(|t| t.inserted == 0 && t.updated == 0));
+        }
+    }
+
+    #[test]
+    fn import_modes_resolve_conflicts() {
+        let src = sample_db();
+        src.execute("UPDATE truth_events SET description='newer', timestamp_start=500 WHERE id=1", []).unwrap();
+        src.execute("UPDATE statements SET text='older', updated_at=0 WHERE id=1", []).unwrap();
+        let mut buf = Vec::new();
+        export(&src, &mut buf, ExportFormat::Ndjson).unwrap();
+
+        let local = || {
+            let conn = sample_db();
+            conn.execute("UPDATE truth_events SET description='local' WHERE id=1", []).unwrap();
+            conn.execute("UPDATE statements SET text='local' WHERE id=1", []).unwrap();
+            conn
+        };
+        let texts = |c: &Connection| -> (String, String) {
+            (
+                c.query_row("SELECT description FROM truth_events WHERE id=1", [], |r| r.get(0)).unwrap(),
+                c.query_row("SELECT text FROM statements WHERE id=1", [], |r| r.get(0)).unwrap(),
+            )
+        };
+
+        let mut conn = local();
+        let err = import(&mut conn, &buf[..], ExportFormat::Ndjson, ImportMode::Fail).unwrap_err();
+        assert!(err.to_string().contains("truth_events: conflicting row id=1"), "{}", err);
+        assert_eq!(texts(&conn), ("local".into(), "local".into()));
+
+        let mut conn = local();
+        import(&mut conn, &buf[..], ExportFormat::Ndjson, ImportMode::Skip).unwrap();
+        assert_eq!(texts(&conn), ("local".into(), "local".into()));
+
+        let mut conn = local();
+        import(&mut conn, &buf[..], ExportFormat::Ndjson, ImportMode::Overwrite).unwrap();
+        assert_eq!(texts(&conn), ("newer".into(), "older".into()));
+
+        // Ссылка на участника, которого нет ни в выгрузке, ни в БД
+        let orphan = format!(
+            "{}\n{}\n",
+            buf.split(|b| *b == b'\n').next().map(|l| String::from_utf8_lossy(l).into_owned()).unwrap(),
+            serde_json::json!({"table": "events_ci", "row": {"id": "e2", "title": "x", "event_type": "claim", "created_by": "nobody", "created_at": 1}}),
+        );
+        let err = import(&mut conn, orphan.as_bytes(), ExportFormat::Ndjson, ImportMode::Skip).unwrap_err();
+        assert!(matches!(err, CoreError::InvalidArg(_)), "{}", err);
+
+        // Merge: событие новее по timestamp_start принимается, утверждение старше по updated_at — нет
+        let mut conn = local();
+        let report = import(&mut conn, &buf[..], ExportFormat::Ndjson, ImportMode::Merge).unwrap();
+        assert_eq!(texts(&conn), ("newer".into(), "local".into()));
+        let events = report.tables.iter().find(|t| t.table == "truth_events").unwrap();
+        assert_eq!((events.inserted, events.updated, events.skipped), (0, 1, 0));
+    }
+
+    #[test]
+    fn legacy_export_data_is_accepted() {
+        let ev = TruthEvent {
+            id: 7, uid: String::new(), description: "legacy".into(), context_id: 1, vector: true, detected: Some(false), corrected: false,
+            timestamp_start: 10, timestamp_end: None, code: 1, signature: Some("s".into()), public_key: None, collective_score: None,
+        };
+        // До глобальных id влияние ссылалось на целый id события
+        let im = serde_json::json!({ "id": "imp-7", "event_id": "7", "type_id": 1, "value": true, "notes": null, "created_at": 10 });
+        let doc = serde_json::json!({ "truth_events": [ev], "impacts": [im], "metrics": [] });
+        let mut conn = storage::open_db(":memory:").unwrap();
+        storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let report = import(&mut conn, doc.to_string().as_bytes(), ExportFormat::Json, ImportMode::Fail).unwrap();
+        assert!(report.header.is_none());
+        let got = storage::get_truth_event(&conn, 7).unwrap().unwrap();
+        assert_eq!(got.detected, Some(false));
+        assert_eq!(got.signature.as_deref(), Some("s"));
+        assert_eq!(got.uid, ev.legacy_uid());
+        assert_eq!(storage::get_impact(&conn, "imp-7").unwrap().unwrap().event_id, ev.legacy_uid());
+    }
+}
@@ -1,12 +1,221 @@
-#[derive(Debug)]
-pub struct TruthEvent {
-    pub id: u64,
-    pub description: String,
-    pub context: String,
-    pub detected: bool, // true если ложь распознана
-    pub timestamp: i64, // UNIX time
+use crate::at_rest::{from_hex, to_hex};
+use crate::models::KbEntry;
+use crate::storage;
+use crate::CoreError;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rusqlite::Connection;
+
+pub mod pack;
+
+/// Итог применения правки базы знаний
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Applied {
+    Created,
+    Updated,
+    /// Локальная версия не старше (updated_at, затем подпись) — правка пропущена
+    Stale,
+    /// Подписавший не администратор — правка отклонена
+    Forbidden,
+}
+
+/// Подписать правку ключом администратора; updated_by становится его публичным ключом
+pub fn sign(e: &mut KbEntry, key: &SigningKey) {
+    e.updated_by = Some(to_hex(key.verifying_key().as_bytes()));
+    e.signature = Some(to_hex(&key.sign(e.signing_message().as_bytes()).to_bytes()));
+}
+
+/// Проверить вид записи и подпись updated_by
+pub fn verify(e: &KbEntry) -> Result<(), CoreError> {
+    if !KbEntry::KINDS.contains(&e.kind.as_str()) {
+        return Err(CoreError::InvalidArg(format!("unknown knowledge base kind: {}", e.kind)));
+    }
+    let (Some(author), Some(signature)) = (e.updated_by.as_deref(), e.signature.as_deref()) else {
+        return Err(CoreError::InvalidArg(format!("{} {}: edit is not signed", e.kind, e.id)));
+    };
+    let pk: [u8; 32] = from_hex(author)?
+        .try_into()
+        .map_err(|_| CoreError::InvalidArg(format!("{} {}: bad author key", e.kind, e.id)))?;
+    let key = VerifyingKey::from_bytes(&pk).map_err(|err| CoreError::InvalidArg(err.to_string()))?;
+    let sig = Signature::from_slice(&from_hex(signature)?).map_err(|err| CoreError::InvalidArg(err.to_string()))?;
+    key.verify(e.signing_message().as_bytes(), &sig)
+        .map_err(|_| CoreError::InvalidArg(format!("{} {}: signature is invalid", e.kind, e.id)))
+}
+
+/// Проверить содержимое и ссылки: quality — ровно у cause/develop/effect/forma,
+/// ссылки — только у context и только на существующие действующие записи;
+/// устаревшей нельзя сделать запись, на которую ссылаются действующие контексты
+pub fn validate(conn: &Connection, e: &KbEntry) -> Result<(), CoreError> {
+    if e.name.trim().is_empty() {
+        return Err(CoreError::InvalidArg(format!("{} {}: name is empty", e.kind, e.id)));
+    }
+    if KbEntry::QUALITY_KINDS.contains(&e.kind.as_str()) != e.quality.is_some() {
+        return Err(CoreError::InvalidArg(format!("{} {}: quality is required only for cause, develop, effect and forma", e.kind, e.id)));
+    }
+    let refs = [
+        ("category", e.category_id),
+        ("forma", e.forma_id),
+        ("cause", e.cause_id),
+        ("develop", e.develop_id),
+        ("effect", e.effect_id),
+    ];
+    if e.kind != "context" && refs.iter().any(|(_, id)| id.is_some()) {
+        return Err(CoreError::InvalidArg(format!("{} {}: only contexts reference other entries", e.kind, e.id)));
+    }
+    if e.kind == "context" && e.deprecated_at.is_none() {
+        for (kind, id) in refs {
+            let Some(id) = id else { continue };
+            match storage::get_kb_entry(conn, kind, id)? {
+                None => return Err(CoreError::InvalidArg(format!("context {}: {} {} does not exist", e.id, kind, id))),
+                Some(r) if r.deprecated_at.is_some() => {
+                    return Err(CoreError::InvalidArg(format!("context {}: {} {} is deprecated", e.id, kind, id)))
+                }
+                Some(_) => {}
+            }
+        }
+    }
+    // События остаются на устаревшем контексте, а влияния — на устаревшем типе
+    if e.deprecated_at.is_some() && !matches!(e.kind.as_str(), "context" | "impact_type") {
+        let n = storage::kb_usage(conn, &e.kind, e.id)?;
+        if n > 0 {
+            return Err(CoreError::InvalidArg(format!("{} {} is used by {} active contexts", e.kind, e.id, n)));
+        }
+    }
+    Ok(())
+}
+
+/// Применить подписанную правку (локальную или от пира): подпись, права администратора,
+/// last-writer-wins по updated_at, проверки ссылок; изменение попадает в sync_log
+pub fn apply(conn: &Connection, e: &KbEntry) -> Result<Applied, CoreError> {
+    verify(e)?;
+    let author = e.updated_by.as_deref().unwrap_or_default();
+    let is_admin = storage::get_user_by_pubkey(conn, author)?.is_some_and(|u| u.role == "admin");
+    if !is_admin {
+        return Ok(Applied::Forbidden);
+    }
+    let local = storage::get_kb_entry(conn, &e.kind, e.id)?;
+    if let Some(l) = &local {
+        if (l.updated_at, l.signature.as_deref()) >= (e.updated_at, e.signature.as_deref()) {
+            return Ok(Applied::Stale);
+        }
+    }
+    validate(conn, e)?;
+    storage::upsert_kb_entry(conn, e)?;
+    let op = match (&local, e.deprecated_at) {
+        (_, Some(_)) => "deprecate",
+        (None, None) => "insert",
+        (Some(_), None) => "update",
+    };
+    storage::log_sync(conn, op, &e.kind, &e.id.to_string(), e.signature.clone(), e.updated_by.clone())?;
+    Ok(if local.is_some() { Applied::Updated } else { Applied::Created })
+}
+
+/// Время новой правки записи: не раньше now и строго позже предыдущей
+pub fn next_revision(e: &KbEntry, now: i64) -> i64 {
+    now.max(e.updated_at + 1)
 }
 
-pub fn add_truth_event(event: TruthEvent) {
-    println!("New event added: {:?}", event);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::NewTruthEvent;
+
+    fn setup() -> (Connection, SigningKey) {
+        let mut conn = storage::open_db(":memory:").unwrap();
+        storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let admin = SigningKey::from_bytes(&[7; 32]);
+        storage::update_user_role(&conn, &to_hex(admin.verifying_key().as_bytes()), "admin").unwrap();
+        (conn, admin)
+    }
+
+    fn entry(kind: &str, name: &str, quality: Option<bool>, at: i64) -> KbEntry {
+        KbEntry {
+            kind: kind.into(),
+            id: KbEntry::new_id(kind, name, "test", at),
+            name: name.into(),
+            description: None,
+            quality,
+            category_id: None,
+            forma_id: None,
+            cause_id: None,
+            develop_id: None,
+            effect_id: None,
+            updated_at: at,
+            deprecated_at: None,
+            updated_by: None,
+            signature: None,
+        }
+    }
+
+    #[test]
+    fn admin_creates_edits_and_deprecates() {
+        let (conn, admin) = setup();
+        let mut cause = entry("cause", "Envy", Some(false), 100);
+        sign(&mut cause, &admin);
+        assert_eq!(apply(&conn, &cause).unwrap(), Applied::Created);
+        assert!(cause.id >= 1 << 40);
+
+        let mut ctx = entry("context", "Envious gossip", None, 100);
+        ctx.category_id = Some(1);
+        ctx.cause_id = Some(cause.id);
+        sign(&mut ctx, &admin);
+        assert_eq!(apply(&conn, &ctx).unwrap(), Applied::Created);
+
+        // Повтор и более старая правка не меняют запись
+        assert_eq!(apply(&conn, &ctx).unwrap(), Applied::Stale);
+        let mut renamed = cause.clone();
+        renamed.name = "Jealousy".into();
+        renamed.updated_at = next_revision(&cause, 50);
+        sign(&mut renamed, &admin);
+        assert_eq!(renamed.updated_at, 101);
+        assert_eq!(apply(&conn, &renamed).unwrap(), Applied::Updated);
+        assert_eq!(storage::get_kb_entry(&conn, "cause", cause.id).unwrap().unwrap().name, "Jealousy");
+
+        // Причина используется действующим контекстом — устареть не может
+        let mut dep = renamed.clone();
+        dep.deprecated_at = Some(200);
+        dep.updated_at = 200;
+        sign(&mut dep, &admin);
+        assert!(matches!(apply(&conn, &dep), Err(CoreError::InvalidArg(m)) if m.contains("1 active contexts")));
+
+        let ev = NewTruthEvent { description: "rumour".into(), context_id: ctx.id, vector: false, timestamp_start: 150, code: 1 };
+        storage::add_truth_event(&conn, ev.clone()).unwrap();
+        let mut ctx_dep = ctx.clone();
+        ctx_dep.deprecated_at = Some(200);
+        ctx_dep.updated_at = 200;
+        sign(&mut ctx_dep, &admin);
+        assert_eq!(apply(&conn, &ctx_dep).unwrap(), Applied::Updated);
+        assert_eq!(storage::kb_usage(&conn, "context", ctx.id).unwrap(), 1);
+        assert!(matches!(storage::add_truth_event(&conn, ev), Err(CoreError::InvalidArg(m)) if m.contains("deprecated")));
+        assert_eq!(apply(&conn, &dep).unwrap(), Applied::Updated);
+
+        let active = storage::load_kb_entries(&conn, "context", false).unwrap();
+        assert!(active.iter().all(|c| c.id != ctx.id));
+        assert!(storage::load_kb_entries(&conn, "context", true).unwrap().iter().any(|c| c.id == ctx.id));
+        let changes = storage::load_kb_changes(&conn, Some(150)).unwrap();
+        assert_eq!(changes.iter().map(|c| c.kind.as_str()).collect::<Vec<_>>(), vec!["cause", "context"]);
+    }
+
+    #[test]
+    fn rejects_forged_unauthorized_and_dangling_edits() {
+        let (conn, admin) = setup();
+        let mut e = entry("category", "Science", None, 10);
+        sign(&mut e, &admin);
+        let mut forged = e.clone();
+        forged.name = "Pseudoscience".into();
+        assert!(matches!(apply(&conn, &forged), Err(CoreError::InvalidArg(m)) if m.contains("signature")));
+
+        let mut outsider = e.clone();
+        sign(&mut outsider, &SigningKey::from_bytes(&[8; 32]));
+        assert_eq!(apply(&conn, &outsider).unwrap(), Applied::Forbidden);
+
+        let mut no_quality = entry("effect", "Panic", None, 10);
+        sign(&mut no_quality, &admin);
+        assert!(apply(&conn, &no_quality).is_err());
+
+        let mut dangling = entry("context", "Orphan", None, 10);
+        dangling.forma_id = Some(999);
+        sign(&mut dangling, &admin);
+        assert!(matches!(apply(&conn, &dangling), Err(CoreError::InvalidArg(m)) if m.contains("forma 999 does not exist")));
+        assert!(storage::get_kb_entry(&conn, "context", dangling.id).unwrap().is_none());
+    }
 }
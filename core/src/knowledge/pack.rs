@@ -0,0 +1,631 @@
+//! Пакеты базы знаний: декларативные файлы (TOML или JSON) с категориями, причинами, проявлениями,
+//! следствиями, формами, типами воздействий, контекстами и вопросами экспертной оценки.
+//! Записи ссылаются друг на друга по ключам, тексты задаются для нескольких локалей.
+
+use super::Applied;
+use crate::expert_simple::{Question, QuestionKind, StoredQuestion};
+use crate::models::KbEntry;
+use crate::storage;
+use crate::CoreError;
+use ed25519_dalek::SigningKey;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+/// Встроенный пакет, из которого seed_knowledge_base заполняет новую БД
+pub const BUILTIN: &str = include_str!("../../packs/base.toml");
+
+/// Формат файла пакета
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackFormat {
+    Toml,
+    Json,
+}
+
+impl PackFormat {
+    /// Формат по расширению: .toml или .json
+    pub fn from_path(path: &Path) -> Result<Self, CoreError> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Ok(PackFormat::Toml),
+            Some("json") => Ok(PackFormat::Json),
+            _ => Err(CoreError::InvalidArg(format!("{}: knowledge-base packs are .toml or .json", path.display()))),
+        }
+    }
+}
+
+/// Заголовок пакета
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PackMeta {
+    pub name: String,
+    #[serde(default = "default_version")]
+    pub version: u32,
+    /// Локаль, текст которой обязателен у каждой записи и подставляется, если нужной нет
+    pub default_locale: String,
+    /// Поддерживаемые локали; пусто — только default_locale
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locales: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+fn default_version() -> u32 {
+    1
+}
+
+/// Запись пакета. Без явного id получает KbEntry::pack_id(пакет, вид, key)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PackEntry {
+    pub key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<bool>,
+    // Ссылки контекста — ключи записей этого же пакета
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forma: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cause: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub develop: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effect: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated: bool,
+    /// Локаль → текст
+    pub name: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub description: BTreeMap<String, String>,
+}
+
+impl PackEntry {
+    fn refs(&self) -> [(&'static str, Option<&str>); 5] {
+        [
+            ("category", self.category.as_deref()),
+            ("forma", self.forma.as_deref()),
+            ("cause", self.cause.as_deref()),
+            ("develop", self.develop.as_deref()),
+            ("effect", self.effect.as_deref()),
+        ]
+    }
+}
+
+/// Вопрос экспертной оценки
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PackQuestion {
+    pub key: String,
+    pub kind: QuestionKind,
+    pub weight: f32,
+    pub truth_bias: bool,
+    /// Ключи контекстов; пусто — вопрос для всех контекстов
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contexts: Vec<String>,
+    pub text: BTreeMap<String, String>,
+}
+
+/// Пакет базы знаний; порядок полей — порядок разделов в файле
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Pack {
+    pub pack: PackMeta,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub category: Vec<PackEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cause: Vec<PackEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub develop: Vec<PackEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub effect: Vec<PackEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forma: Vec<PackEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub impact_type: Vec<PackEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context: Vec<PackEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub question: Vec<PackQuestion>,
+}
+
+/// Расхождения пакета с базой знаний узла
+#[derive(Debug, Clone, Default)]
+pub struct PackDiff {
+    /// Записи, которых нет в БД
+    pub added: Vec<KbEntry>,
+    /// (локальная, из пакета) — отличаются имя, описание, quality, ссылки или признак устаревшей
+    pub changed: Vec<(KbEntry, KbEntry)>,
+    pub unchanged: usize,
+    /// Ключи новых и изменённых вопросов
+    pub questions: Vec<String>,
+}
+
+impl PackDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.questions.is_empty()
+    }
+}
+
+/// Итог импорта пакета
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportReport {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub questions: usize,
+}
+
+impl Pack {
+    /// Разобрать и проверить пакет
+    pub fn parse(text: &str, format: PackFormat) -> Result<Self, CoreError> {
+        let pack: Pack = match format {
+            PackFormat::Toml => toml::from_str(text).map_err(|e| CoreError::InvalidArg(format!("pack: {}", e)))?,
+            PackFormat::Json => serde_json::from_str(text).map_err(|e| CoreError::InvalidArg(format!("pack: {}", e)))?,
+        };
+        pack.validate()?;
+        Ok(pack)
+    }
+
+    /// Прочитать и проверить пакет; формат — по расширению
+    pub fn load(path: &Path) -> Result<Self, CoreError> {
+        let format = PackFormat::from_path(path)?;
+        Self::parse(&std::fs::read_to_string(path)?, format)
+    }
+
+    /// Встроенный пакет (его корректность проверяется тестом)
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN, PackFormat::Toml).expect("built-in knowledge-base pack is valid")
+    }
+
+    pub fn to_string(&self, format: PackFormat) -> Result<String, CoreError> {
+        match format {
+            PackFormat::Toml => toml::to_string(self).map_err(|e| CoreError::InvalidArg(format!("pack: {}", e))),
+            PackFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+        }
+    }
+
+    /// Записи вида kind (имена видов — как в KbEntry::KINDS)
+    pub fn entries_of(&self, kind: &str) -> &[PackEntry] {
+        match kind {
+            "category" => &self.category,
+            "cause" => &self.cause,
+            "develop" => &self.develop,
+            "effect" => &self.effect,
+            "forma" => &self.forma,
+            "impact_type" => &self.impact_type,
+            "context" => &self.context,
+            _ => &[],
+        }
+    }
+
+    fn entries_of_mut(&mut self, kind: &str) -> &mut Vec<PackEntry> {
+        match kind {
+            "category" => &mut self.category,
+            "cause" => &mut self.cause,
+            "develop" => &mut self.develop,
+            "effect" => &mut self.effect,
+            "forma" => &mut self.forma,
+            "impact_type" => &mut self.impact_type,
+            _ => &mut self.context,
+        }
+    }
+
+    /// Локали пакета (default_locale — всегда)
+    pub fn locales(&self) -> Vec<&str> {
+        let mut out = vec![self.pack.default_locale.as_str()];
+        out.extend(self.pack.locales.iter().map(|l| l.as_str()).filter(|l| *l != self.pack.default_locale));
+        out
+    }
+
+    /// Проверить пакет целиком; все найденные ошибки возвращаются одной InvalidArg
+    pub fn validate(&self) -> Result<(), CoreError> {
+        let mut problems: Vec<String> = Vec::new();
+        if self.pack.name.trim().is_empty() {
+            problems.push("pack.name is empty".into());
+        }
+        let locales: HashSet<&str> = self.locales().into_iter().collect();
+        let default = self.pack.default_locale.as_str();
+        let check_texts = |what: &str, field: &str, texts: &BTreeMap<String, String>, required: bool, problems: &mut Vec<String>| {
+            if required && texts.get(default).is_none_or(|t| t.trim().is_empty()) {
+                problems.push(format!("{}: {} has no {} text", what, field, default));
+            }
+            for l in texts.keys().filter(|l| !locales.contains(l.as_str())) {
+                problems.push(format!("{}: {} uses locale {} not listed in pack.locales", what, field, l));
+            }
+        };
+
+        let mut keys: HashMap<&str, HashMap<&str, bool>> = HashMap::new(); // вид → key → устаревшая
+        for &kind in KbEntry::KINDS {
+            let mut ids = HashSet::new();
+            let seen = keys.entry(kind).or_default();
+            for e in self.entries_of(kind) {
+                let what = format!("{} {}", kind, e.key);
+                if e.key.trim().is_empty() {
+                    problems.push(format!("{}: key is empty", kind));
+                }
+                if seen.insert(e.key.as_str(), e.deprecated).is_some() {
+                    problems.push(format!("{}: duplicate key", what));
+                }
+                let id = e.id.unwrap_or_else(|| KbEntry::pack_id(&self.pack.name, kind, &e.key));
+                if id <= 0 || !ids.insert(id) {
+                    problems.push(format!("{}: id {} is not positive or not unique", what, id));
+                }
+                if KbEntry::QUALITY_KINDS.contains(&kind) != e.quality.is_some() {
+                    problems.push(format!("{}: quality is required only for cause, develop, effect and forma", what));
+                }
+                if kind != "context" && e.refs().iter().any(|(_, r)| r.is_some()) {
+                    problems.push(format!("{}: only contexts reference other entries", what));
+                }
+                check_texts(&what, "name", &e.name, true, &mut problems);
+                check_texts(&what, "description", &e.description, false, &mut problems);
+            }
+        }
+        for e in &self.context {
+            for (kind, key) in e.refs() {
+                let Some(key) = key else { continue };
+                match keys.get(kind).and_then(|k| k.get(key)) {
+                    None => problems.push(format!("context {}: {} {} is not in the pack", e.key, kind, key)),
+                    Some(true) if !e.deprecated => problems.push(format!("context {}: {} {} is deprecated", e.key, kind, key)),
+                    Some(_) => {}
+                }
+            }
+        }
+
+        let mut question_keys = HashSet::new();
+        for q in &self.question {
+            let what = format!("question {}", q.key);
+            if q.key.trim().is_empty() || !question_keys.insert(q.key.as_str()) {
+                problems.push(format!("{}: key is empty or duplicate", what));
+            }
+            if !(-1.0..=1.0).contains(&q.weight) {
+                problems.push(format!("{}: weight must be within -1..1", what));
+            }
+            for c in q.contexts.iter().filter(|c| !keys["context"].contains_key(c.as_str())) {
+                problems.push(format!("{}: context {} is not in the pack", what, c));
+            }
+            check_texts(&what, "text", &q.text, true, &mut problems);
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(CoreError::InvalidArg(format!("pack {}: {}", self.pack.name, problems.join("; "))))
+        }
+    }
+
+    fn check_locale(&self, locale: &str) -> Result<(), CoreError> {
+        if self.locales().contains(&locale) {
+            Ok(())
+        } else {
+            Err(CoreError::InvalidArg(format!("unsupported locale: {}", locale)))
+        }
+    }
+
+    fn text(&self, texts: &BTreeMap<String, String>, locale: &str) -> Option<String> {
+        texts.get(locale).or_else(|| texts.get(&self.pack.default_locale)).cloned()
+    }
+
+    fn id_of(&self, kind: &str, key: &str) -> Option<i64> {
+        self.entries_of(kind)
+            .iter()
+            .find(|e| e.key == key)
+            .map(|e| e.id.unwrap_or_else(|| KbEntry::pack_id(&self.pack.name, kind, key)))
+    }
+
+    /// Записи пакета на локали locale в порядке KbEntry::KINDS, без подписи и с updated_at = 0
+    pub fn entries(&self, locale: &str) -> Result<Vec<KbEntry>, CoreError> {
+        self.check_locale(locale)?;
+        let mut out = Vec::new();
+        for kind in KbEntry::KINDS {
+            for e in self.entries_of(kind) {
+                let reference = |k: &str, key: &Option<String>| key.as_deref().and_then(|key| self.id_of(k, key));
+                out.push(KbEntry {
+                    kind: kind.to_string(),
+                    id: self.id_of(kind, &e.key).unwrap_or_default(),
+                    name: self.text(&e.name, locale).unwrap_or_default(),
+                    description: self.text(&e.description, locale),
+                    quality: e.quality,
+                    category_id: reference("category", &e.category),
+                    forma_id: reference("forma", &e.forma),
+                    cause_id: reference("cause", &e.cause),
+                    develop_id: reference("develop", &e.develop),
+                    effect_id: reference("effect", &e.effect),
+                    updated_at: 0,
+                    deprecated_at: if e.deprecated { Some(0) } else { None },
+                    updated_by: None,
+                    signature: None,
+                });
+            }
+        }
+        Ok(out)
+    }
+
+    /// Вопросы пакета на локали locale
+    pub fn questions(&self, locale: &str) -> Result<Vec<StoredQuestion>, CoreError> {
+        self.check_locale(locale)?;
+        Ok(self
+            .question
+            .iter()
+            .map(|q| StoredQuestion {
+                question: Question {
+                    id: q.key.clone(),
+                    text: self.text(&q.text, locale).unwrap_or_default(),
+                    kind: q.kind.clone(),
+                    weight: q.weight,
+                    truth_bias: q.truth_bias,
+                },
+                context_ids: (!q.contexts.is_empty())
+                    .then(|| q.contexts.iter().filter_map(|c| self.id_of("context", c)).collect()),
+                pack: Some(self.pack.name.clone()),
+            })
+            .collect())
+    }
+}
+
+/// Совпадает ли содержимое записей (без времени правки и подписи)
+fn same_content(a: &KbEntry, b: &KbEntry) -> bool {
+    a.name == b.name
+        && a.description == b.description
+        && a.quality == b.quality
+        && (a.category_id, a.forma_id, a.cause_id, a.develop_id, a.effect_id)
+            == (b.category_id, b.forma_id, b.cause_id, b.develop_id, b.effect_id)
+        && a.deprecated_at.is_some() == b.deprecated_at.is_some()
+}
+
+fn same_question(a: &StoredQuestion, b: &StoredQuestion) -> bool {
+    (&a.question.text, &a.question.kind, a.question.weight, a.question.truth_bias, &a.context_ids)
+        == (&b.question.text, &b.question.kind, b.question.weight, b.question.truth_bias, &b.context_ids)
+}
+
+/// Заполнить пустую базу знаний пакетом: существующие записи и вопросы не меняются,
+/// новые остаются неподписанными (updated_at = 0) и не синхронизируются
+pub fn seed(conn: &mut Connection, pack: &Pack, locale: &str) -> Result<(), CoreError> {
+    let entries = pack.entries(locale)?;
+    let questions = pack.questions(locale)?;
+    let tx = conn.transaction()?;
+    for e in &entries {
+        if storage::get_kb_entry(&tx, &e.kind, e.id)?.is_none() {
+            storage::upsert_kb_entry(&tx, e)?;
+        }
+    }
+    let known: HashSet<String> = storage::load_expert_questions(&tx)?.into_iter().map(|q| q.question.id).collect();
+    for q in questions.iter().filter(|q| !known.contains(&q.question.id)) {
+        storage::upsert_expert_question(&tx, q)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Сравнить пакет на локали locale с базой знаний узла
+pub fn diff(conn: &Connection, pack: &Pack, locale: &str) -> Result<PackDiff, CoreError> {
+    let mut out = PackDiff::default();
+    for e in pack.entries(locale)? {
+        match storage::get_kb_entry(conn, &e.kind, e.id)? {
+            None => out.added.push(e),
+            Some(local) if same_content(&local, &e) => out.unchanged += 1,
+            Some(local) => out.changed.push((local, e)),
+        }
+    }
+    let local: HashMap<String, StoredQuestion> =
+        storage::load_expert_questions(conn)?.into_iter().map(|q| (q.question.id.clone(), q)).collect();
+    for q in pack.questions(locale)? {
+        if !local.get(&q.question.id).is_some_and(|l| same_question(l, &q)) {
+            out.questions.push(q.question.id);
+        }
+    }
+    Ok(out)
+}
+
+/// Импортировать пакет одной транзакцией: новые и изменённые записи становятся подписанными
+/// правками администратора (как knowledge::apply, распространяются пирам), вопросы сохраняются локально.
+/// Сначала применяются действующие записи (контексты последними), затем устаревшие (контексты первыми).
+pub fn import(conn: &Connection, pack: &Pack, locale: &str, key: &SigningKey, now: i64) -> Result<ImportReport, CoreError> {
+    let d = diff(conn, pack, locale)?;
+    let mut report = ImportReport { unchanged: d.unchanged, ..Default::default() };
+    let mut edits: Vec<KbEntry> = d.added;
+    for (local, mut e) in d.changed {
+        e.updated_at = super::next_revision(&local, now);
+        edits.push(e);
+    }
+    for e in edits.iter_mut() {
+        e.updated_at = e.updated_at.max(now);
+        if e.deprecated_at.is_some() {
+            e.deprecated_at = Some(e.updated_at);
+        }
+        super::sign(e, key);
+    }
+    let rank = |e: &KbEntry| KbEntry::KINDS.iter().position(|k| *k == e.kind).unwrap_or_default();
+    let (mut active, mut deprecated): (Vec<KbEntry>, Vec<KbEntry>) = edits.into_iter().partition(|e| e.deprecated_at.is_none());
+    active.sort_by_key(rank);
+    deprecated.sort_by_key(|e| std::cmp::Reverse(rank(e)));
+
+    let tx = conn.unchecked_transaction()?;
+    for e in active.iter().chain(&deprecated) {
+        match super::apply(&tx, e)? {
+            Applied::Created => report.created += 1,
+            Applied::Updated => report.updated += 1,
+            Applied::Stale => report.unchanged += 1,
+            Applied::Forbidden => return Err(CoreError::InvalidArg("only an admin may import knowledge-base packs".into())),
+        }
+    }
+    for q in pack.questions(locale)?.iter().filter(|q| d.questions.contains(&q.question.id)) {
+        storage::upsert_expert_question(&tx, q)?;
+        report.questions += 1;
+    }
+    tx.commit()?;
+    Ok(report)
+}
+
+/// Выгрузить базу знаний узла пакетом name на локали locale. Id сохраняются явно,
+/// ключи — вид_id; устаревшие записи помечаются deprecated
+pub fn export(conn: &Connection, name: &str, locale: &str) -> Result<Pack, CoreError> {
+    let mut pack = Pack {
+        pack: PackMeta {
+            name: name.to_string(),
+            version: 1,
+            default_locale: locale.to_string(),
+            locales: Vec::new(),
+            description: None,
+        },
+        category: Vec::new(),
+        cause: Vec::new(),
+        develop: Vec::new(),
+        effect: Vec::new(),
+        forma: Vec::new(),
+        impact_type: Vec::new(),
+        context: Vec::new(),
+        question: Vec::new(),
+    };
+    let text = |t: &str| BTreeMap::from([(locale.to_string(), t.to_string())]);
+    let key = |kind: &str, id: Option<i64>| id.map(|id| format!("{}_{}", kind, id));
+    for kind in KbEntry::KINDS {
+        for e in storage::load_kb_entries(conn, kind, true)? {
+            pack.entries_of_mut(kind).push(PackEntry {
+                key: format!("{}_{}", kind, e.id),
+                id: Some(e.id),
+                quality: e.quality,
+                category: key("category", e.category_id),
+                forma: key("forma", e.forma_id),
+                cause: key("cause", e.cause_id),
+                develop: key("develop", e.develop_id),
+                effect: key("effect", e.effect_id),
+                deprecated: e.deprecated_at.is_some(),
+                name: text(&e.name),
+                description: e.description.as_deref().map(text).unwrap_or_default(),
+            });
+        }
+    }
+    for q in storage::load_expert_questions(conn)? {
+        pack.question.push(PackQuestion {
+            key: q.question.id,
+            kind: q.question.kind,
+            weight: q.question.weight,
+            truth_bias: q.question.truth_bias,
+            contexts: q.context_ids.unwrap_or_default().into_iter().map(|id| format!("context_{}", id)).collect(),
+            text: text(&q.question.text),
+        });
+    }
+    Ok(pack)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::at_rest::to_hex;
+
+    const DOMAIN: &str = r#"
+        [pack]
+        name = "science"
+        default_locale = "en"
+        locales = ["en", "ru"]
+
+        [[category]]
+        key = "science"
+        name = { en = "Science", ru = "Наука" }
+
+        [[cause]]
+        key = "curiosity"
+        quality = true
+        name = { en = "Curiosity" }
+
+        [[context]]
+        key = "replication"
+        category = "science"
+        cause = "curiosity"
+        name = { en = "Failed replication", ru = "Невоспроизводимый результат" }
+        description = { en = "A published result does not replicate" }
+
+        [[question]]
+        key = "preregistered"
+        kind = "YesNo"
+        weight = 0.3
+        truth_bias = true
+        contexts = ["replication"]
+        text = { en = "Was the study preregistered?", ru = "Было ли исследование предзарегистрировано?" }
+    "#;
+
+    fn admin_db() -> (Connection, SigningKey) {
+        let conn = storage::open_db(":memory:").unwrap();
+        let admin = SigningKey::from_bytes(&[9; 32]);
+        storage::update_user_role(&conn, &to_hex(admin.verifying_key().as_bytes()), "admin").unwrap();
+        (conn, admin)
+    }
+
+    #[test]
+    fn builtin_pack_matches_both_locales() {
+        let pack = Pack::builtin();
+        let (ru, en) = (pack.entries("ru").unwrap(), pack.entries("en").unwrap());
+        assert_eq!(ru.len(), 50);
+        let ctx = |v: &[KbEntry]| v.iter().find(|e| e.kind == "context" && e.id == 3).cloned().unwrap();
+        assert_eq!((ctx(&ru).name.as_str(), ctx(&en).name.as_str()), ("Финансы: мошенничество", "Finance: fraud"));
+        assert_eq!((ctx(&en).cause_id, ctx(&en).effect_id), (Some(2), Some(5)));
+        assert_eq!(pack.questions("en").unwrap().len(), 7);
+        assert!(matches!(pack.entries("de"), Err(CoreError::InvalidArg(m)) if m == "unsupported locale: de"));
+    }
+
+    #[test]
+    fn validation_reports_every_problem() {
+        let broken = DOMAIN
+            .replace("quality = true\n", "")
+            .replace("cause = \"curiosity\"", "cause = \"greed\"")
+            .replace("key = \"science\"\n        name = { en = \"Science\", ru = \"Наука\" }", "key = \"science\"\n        name = { de = \"Wissenschaft\" }")
+            .replace("weight = 0.3", "weight = 3.0");
+        let Err(CoreError::InvalidArg(m)) = Pack::parse(&broken, PackFormat::Toml) else { panic!("pack must be rejected") };
+        for expected in [
+            "cause curiosity: quality is required",
+            "context replication: cause greed is not in the pack",
+            "category science: name has no en text",
+            "locale de not listed",
+            "question preregistered: weight",
+        ] {
+            assert!(m.contains(expected), "{} not in {}", expected, m);
+        }
+    }
+
+    #[test]
+    fn import_diff_and_export_round_trip() {
+        let (conn, admin) = admin_db();
+        let pack = Pack::parse(DOMAIN, PackFormat::Toml).unwrap();
+        let d = diff(&conn, &pack, "ru").unwrap();
+        assert_eq!((d.added.len(), d.changed.len(), d.questions.len()), (3, 0, 1));
+
+        let report = import(&conn, &pack, "ru", &admin, 1000).unwrap();
+        assert_eq!(report, ImportReport { created: 3, updated: 0, unchanged: 0, questions: 1 });
+        let ctx_id = KbEntry::pack_id("science", "context", "replication");
+        let ctx = storage::get_kb_entry(&conn, "context", ctx_id).unwrap().unwrap();
+        // ru-текст есть у имени, описание — из default_locale
+        assert_eq!(ctx.name, "Невоспроизводимый результат");
+        assert_eq!(ctx.description.as_deref(), Some("A published result does not replicate"));
+        assert_eq!(ctx.cause_id, Some(KbEntry::pack_id("science", "cause", "curiosity")));
+        assert!(super::super::verify(&ctx).is_ok());
+        let qs = storage::load_questions_for_context(&conn, ctx_id).unwrap();
+        assert_eq!(qs[0].text, "Было ли исследование предзарегистрировано?");
+        assert!(storage::load_questions_for_context(&conn, 1).unwrap().is_empty());
+
+        // Повторный импорт ничего не меняет; смена локали и пометка устаревшим — правки
+        assert!(diff(&conn, &pack, "ru").unwrap().is_empty());
+        let mut next = pack.clone();
+        next.context[0].deprecated = true;
+        next.cause[0].deprecated = true;
+        let report = import(&conn, &next, "en", &admin, 1000).unwrap();
+        assert_eq!((report.created, report.updated, report.questions), (0, 3, 1));
+        let ctx = storage::get_kb_entry(&conn, "context", ctx_id).unwrap().unwrap();
+        assert_eq!((ctx.name.as_str(), ctx.updated_at), ("Failed replication", 1001));
+        assert!(ctx.deprecated_at.is_some());
+
+        // Выгрузка читается обратно и совпадает с БД
+        for format in [PackFormat::Toml, PackFormat::Json] {
+            let exported = export(&conn, "node", "en").unwrap();
+            let parsed = Pack::parse(&exported.to_string(format).unwrap(), format).unwrap();
+            assert_eq!(parsed, exported);
+            let d = diff(&conn, &parsed, "en").unwrap();
+            assert!(d.is_empty(), "{:?}", d);
+            assert_eq!(d.unchanged, 3);
+        }
+
+        let outsider = SigningKey::from_bytes(&[10; 32]);
+        let mut renamed = pack.clone();
+        renamed.category[0].name.insert("en".into(), "Natural science".into());
+        assert!(import(&conn, &renamed, "en", &outsider, 2000).is_err());
+        assert_eq!(storage::get_kb_entry(&conn, "category", KbEntry::pack_id("science", "category", "science")).unwrap().unwrap().name, "Science");
+    }
+}
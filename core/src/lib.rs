@@ -1,9 +1,23 @@
+pub mod anomaly;
+pub mod argument;
+pub mod at_rest;
+pub mod audit;
 pub mod auth;
+pub mod backup;
+pub mod community;
+pub mod event_relation;
+pub mod evidence;
 pub mod expert_simple;
+pub mod export;
 pub mod knowledge;
+pub mod migrations;
 pub mod models;
+pub mod retention;
+pub mod simulator;
 pub mod storage;
+pub mod store;
 pub mod sync;
+pub mod tombstone;
 pub mod trust_propagation;
 pub mod collective_intelligence;
 
@@ -0,0 +1,1136 @@
+use crate::CoreError;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+/// Нумерованная миграция схемы: прямой и обратный шаг в виде SQL
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+}
+
+impl Migration {
+    /// Контрольная сумма прямого шага: отличие от записанной означает, что миграция изменилась после применения
+    pub fn checksum(&self) -> String {
+        blake3::hash(self.up.as_bytes()).to_hex()[..16].to_string()
+    }
+}
+
+/// Состояние одной миграции в конкретной БД
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStatus {
+    pub version: u32,
+    pub name: String,
+    pub applied_at: Option<i64>,
+    pub checksum_ok: bool,
+}
+
+/// Шаг плана миграции: direction = "up" | "down"
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStep {
+    pub version: u32,
+    pub name: String,
+    pub direction: String,
+}
+
+/// Итог migrate: откуда и куда перешла схема, какие шаги выполнены
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    pub from: u32,
+    pub to: u32,
+    pub steps: Vec<MigrationStep>,
+    pub dry_run: bool,
+    /// Колонки, добавленные при переводе БД без schema_version (table.column)
+    pub legacy_columns: Vec<String>,
+}
+
+const VERSION_TABLE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS schema_version (
+    version     INTEGER PRIMARY KEY,
+    name        TEXT    NOT NULL,
+    checksum    TEXT    NOT NULL,
+    applied_at  INTEGER NOT NULL
+);
+"#;
+
+/// Базовая схема: всё, что создавалось до появления версионирования
+const BASELINE_UP: &str = r#"
+-- knowledge_base
+CREATE TABLE IF NOT EXISTS category (
+    id          INTEGER PRIMARY KEY,
+    name        TEXT NOT NULL,
+    description TEXT
+);
+
+CREATE TABLE IF NOT EXISTS cause (
+    id          INTEGER PRIMARY KEY,
+    name        TEXT NOT NULL,
+    quality     INTEGER NOT NULL, -- 0/1
+    description TEXT
+);
+
+CREATE TABLE IF NOT EXISTS develop (
+    id          INTEGER PRIMARY KEY,
+    name        TEXT NOT NULL,
+    quality     INTEGER NOT NULL,
+    description TEXT
+);
+
+CREATE TABLE IF NOT EXISTS effect (
+    id          INTEGER PRIMARY KEY,
+    name        TEXT NOT NULL,
+    quality     INTEGER NOT NULL,
+    description TEXT
+);
+
+CREATE TABLE IF NOT EXISTS forma (
+    id          INTEGER PRIMARY KEY,
+    name        TEXT NOT NULL,
+    quality     INTEGER NOT NULL,
+    description TEXT
+);
+
+CREATE TABLE IF NOT EXISTS context (
+    id          INTEGER PRIMARY KEY,
+    name        TEXT NOT NULL,
+category_id INTEGER,
+    forma_id    INTEGER,
+    cause_id    INTEGER,
+    develop_id  INTEGER,
+    effect_id   INTEGER,
+    description TEXT,
+    FOREIGN KEY(category_id) REFERENCES category(id),
+    FOREIGN KEY(forma_id)    REFERENCES forma(id),
+    FOREIGN KEY(cause_id)    REFERENCES cause(id),
+    FOREIGN KEY(develop_id)  REFERENCES develop(id),
+    FOREIGN KEY(effect_id)   REFERENCES effect(id)
+);
+
+CREATE TABLE IF NOT EXISTS impact_type (
+    id          INTEGER PRIMARY KEY,
+    name        TEXT NOT NULL,
+description TEXT
+);
+
+-- base
+CREATE TABLE IF NOT EXISTS truth_events (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    description     TEXT NOT NULL,
+    context_id      INTEGER NOT NULL,
+    vector          INTEGER NOT NULL,       -- 0/1 вместо BOOLEAN
+    detected        INTEGER,                -- NULL/0/1
+    corrected       INTEGER NOT NULL DEFAULT 0,
+timestamp_start INTEGER NOT NULL,
+    timestamp_end   INTEGER,
+    code            INTEGER NOT NULL DEFAULT 1,  -- 8-bit event code
+    collective_score REAL,
+    signature       TEXT,
+    public_key      TEXT,
+FOREIGN KEY(context_id) REFERENCES context(id)
+);
+
+CREATE TABLE IF NOT EXISTS statements (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_id        INTEGER NOT NULL,
+    text            TEXT NOT NULL,
+    context         TEXT,
+    truth_score     REAL,
+    created_at      INTEGER NOT NULL,
+    updated_at      INTEGER NOT NULL,
+    signature       TEXT,
+    public_key      TEXT,
+    FOREIGN KEY(event_id) REFERENCES truth_events(id)
+);
+
+CREATE TABLE IF NOT EXISTS impact (
+            id TEXT PRIMARY KEY,
+            event_id TEXT NOT NULL,
+type_id INTEGER NOT NULL,
+            value INTEGER NOT NULL,      -- SQLite bool (0/1)
+notes TEXT,
+            created_at INTEGER NOT NULL,
+            signature TEXT,
+            public_key TEXT,
+            FOREIGN KEY(event_id) REFERENCES truth_events(id)
+);
+
+CREATE TABLE IF NOT EXISTS progress_metrics (
+    id                           INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp                    INTEGER NOT NULL,
+    total_events                 INTEGER NOT NULL,
+    total_events_group           INTEGER NOT NULL,
+    total_positive_impact        REAL    NOT NULL,
+    total_positive_impact_group  REAL    NOT NULL,
+    total_negative_impact        REAL    NOT NULL,
+    total_negative_impact_group  REAL    NOT NULL,
+    trend                        REAL    NOT NULL,
+    trend_group                  REAL    NOT NULL
+);
+
+-- node and group ratings
+CREATE TABLE IF NOT EXISTS node_ratings (
+    node_id                TEXT PRIMARY KEY,
+    events_true            INTEGER NOT NULL DEFAULT 0,
+    events_false           INTEGER NOT NULL DEFAULT 0,
+    validations            INTEGER NOT NULL DEFAULT 0,
+    reused_events          INTEGER NOT NULL DEFAULT 0,
+    trust_score            REAL    NOT NULL DEFAULT 0.0,
+    propagation_priority   REAL    NOT NULL DEFAULT 0.0,
+    last_updated           INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS group_ratings (
+    group_id      TEXT PRIMARY KEY,
+    members       TEXT    NOT NULL,
+    avg_score     REAL    NOT NULL,
+    coherence     REAL    NOT NULL,
+    last_updated  INTEGER NOT NULL
+);
+
+-- node metrics for network monitoring
+CREATE TABLE IF NOT EXISTS node_metrics (
+    pubkey                  TEXT PRIMARY KEY,
+    last_seen               INTEGER NOT NULL,
+    relay_success_rate      REAL    NOT NULL DEFAULT 0.0,
+    quality_index           REAL    NOT NULL DEFAULT 0.0,
+    propagation_priority    REAL    NOT NULL DEFAULT 0.0
+);
+
+-- sync journal and high-level synchronization logs
+CREATE TABLE IF NOT EXISTS sync_log (
+    id           INTEGER PRIMARY KEY AUTOINCREMENT,
+    op           TEXT NOT NULL,
+    table_name   TEXT NOT NULL,
+    record_id    TEXT NOT NULL,
+    signature    TEXT,
+    public_key   TEXT,
+    created_at   INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS sync_logs (
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp  INTEGER NOT NULL,
+    peer_url   TEXT NOT NULL,
+    mode       TEXT NOT NULL,
+    status     TEXT NOT NULL,
+    details    TEXT NOT NULL
+);
+
+-- local peer sync history for decentralized diagnostics
+CREATE TABLE IF NOT EXISTS peer_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    peer_url TEXT NOT NULL,
+    last_sync INTEGER,
+    success_count INTEGER DEFAULT 0,
+    fail_count INTEGER DEFAULT 0,
+    last_quality_index REAL DEFAULT 0.0,
+    last_trust_score REAL DEFAULT 0.0
+);
+
+-- active JWT refresh tokens (per public key)
+CREATE TABLE IF NOT EXISTS active_tokens (
+    public_key    TEXT    NOT NULL,
+    refresh_token TEXT    NOT NULL UNIQUE,
+    expires_at    INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_active_tokens_pub ON active_tokens(public_key);
+
+-- RBAC: users and roles
+CREATE TABLE IF NOT EXISTS users (
+    pubkey        TEXT PRIMARY KEY,
+    role          TEXT NOT NULL DEFAULT 'observer',
+    trust_score   REAL NOT NULL DEFAULT 0.0,
+    last_updated  INTEGER NOT NULL,
+    display_name  TEXT
+);
+
+-- Optional roles reference for future extension
+CREATE TABLE IF NOT EXISTS roles (
+    role          TEXT PRIMARY KEY,
+    level         INTEGER NOT NULL,
+    description   TEXT
+);
+INSERT OR IGNORE INTO roles(role, level, description) VALUES
+    ('observer', 1, 'Read-only observer'),
+    ('node',     2, 'Authenticated node with delegation rights'),
+    ('admin',    3, 'Administrator');
+
+-- CI tables
+CREATE TABLE IF NOT EXISTS participants (
+    id TEXT PRIMARY KEY,
+    public_key TEXT UNIQUE NOT NULL,
+    reputation_score REAL NOT NULL DEFAULT 0.5,
+    total_judgments INTEGER NOT NULL DEFAULT 0,
+    accurate_judgments INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    last_activity INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS events_ci (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    description TEXT,
+    event_type TEXT NOT NULL,
+    created_by TEXT NOT NULL REFERENCES participants(id),
+    created_at INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'active',
+    resolution_data TEXT
+);
+
+CREATE TABLE IF NOT EXISTS judgments (
+    id TEXT PRIMARY KEY,
+    participant_id TEXT NOT NULL REFERENCES participants(id),
+    event_id TEXT NOT NULL REFERENCES events_ci(id),
+    assessment TEXT NOT NULL,
+    confidence_level REAL NOT NULL,
+    reasoning TEXT,
+    submitted_at INTEGER NOT NULL,
+    signature TEXT NOT NULL,
+    UNIQUE(participant_id, event_id)
+);
+
+CREATE TABLE IF NOT EXISTS consensus_ci (
+    id TEXT PRIMARY KEY,
+    event_id TEXT NOT NULL REFERENCES events_ci(id),
+    consensus_value TEXT NOT NULL,
+    confidence_score REAL NOT NULL,
+    participant_count INTEGER NOT NULL,
+    calculated_at INTEGER NOT NULL,
+    algorithm_version TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS reputation_history (
+    id TEXT PRIMARY KEY,
+    participant_id TEXT NOT NULL REFERENCES participants(id),
+    old_reputation REAL NOT NULL,
+    new_reputation REAL NOT NULL,
+    change_reason TEXT NOT NULL,
+    event_id TEXT,
+    updated_at INTEGER NOT NULL
+);
+"#;
+
+const BASELINE_DOWN: &str = r#"
+DROP TABLE IF EXISTS reputation_history;
+DROP TABLE IF EXISTS consensus_ci;
+DROP TABLE IF EXISTS judgments;
+DROP TABLE IF EXISTS events_ci;
+DROP TABLE IF EXISTS participants;
+DROP TABLE IF EXISTS roles;
+DROP TABLE IF EXISTS users;
+DROP TABLE IF EXISTS active_tokens;
+DROP TABLE IF EXISTS peer_history;
+DROP TABLE IF EXISTS sync_logs;
+DROP TABLE IF EXISTS sync_log;
+DROP TABLE IF EXISTS node_metrics;
+DROP TABLE IF EXISTS group_ratings;
+DROP TABLE IF EXISTS node_ratings;
+DROP TABLE IF EXISTS progress_metrics;
+DROP TABLE IF EXISTS impact;
+DROP TABLE IF EXISTS statements;
+DROP TABLE IF EXISTS truth_events;
+DROP TABLE IF EXISTS impact_type;
+DROP TABLE IF EXISTS context;
+DROP TABLE IF EXISTS effect;
+DROP TABLE IF EXISTS forma;
+DROP TABLE IF EXISTS develop;
+DROP TABLE IF EXISTS cause;
+DROP TABLE IF EXISTS category;
+"#;
+
+/// Все миграции по возрастанию версии; новые добавляются только в конец
+pub const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "baseline", up: BASELINE_UP, down: BASELINE_DOWN },
+    Migration {
+        version: 2,
+        name: "trust_history",
+        up: r#"
+        CREATE TABLE IF NOT EXISTS trust_history (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            node_id      TEXT    NOT NULL,
+            trust_score  REAL    NOT NULL,
+            source       TEXT    NOT NULL,
+            sender       TEXT,
+            recorded_at  INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_trust_history_node ON trust_history(node_id, recorded_at);
+        "#,
+        down: "DROP TABLE IF EXISTS trust_history;",
+    },
+    Migration {
+        version: 3,
+        name: "trust_delegations",
+        up: r#"
+        CREATE TABLE IF NOT EXISTS trust_delegations (
+            id                    TEXT PRIMARY KEY,
+            delegator             TEXT    NOT NULL,
+            target                TEXT    NOT NULL,
+            delta                 REAL    NOT NULL,
+            created_at            INTEGER NOT NULL,
+            expires_at            INTEGER,
+            signature             TEXT    NOT NULL,
+            revoked_at            INTEGER,
+            revocation_signature  TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_trust_delegations_target ON trust_delegations(target);
+        "#,
+        down: "DROP TABLE IF EXISTS trust_delegations;",
+    },
+    Migration {
+        version: 4,
+        name: "alerts",
+        up: r#"
+        CREATE TABLE IF NOT EXISTS alerts (
+            id               TEXT PRIMARY KEY,
+            kind             TEXT    NOT NULL,
+            subject          TEXT    NOT NULL,
+            related          TEXT    NOT NULL DEFAULT '[]',
+            severity         REAL    NOT NULL,
+            details          TEXT    NOT NULL,
+            detected_at      INTEGER NOT NULL,
+            acknowledged_at  INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_alerts_subject ON alerts(subject);
+        "#,
+        down: "DROP TABLE IF EXISTS alerts;",
+    },
+    Migration {
+        version: 5,
+        name: "full_text_search",
+        up: r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS truth_events_fts USING fts5(description, content='truth_events', content_rowid='id');
+        CREATE VIRTUAL TABLE IF NOT EXISTS statements_fts USING fts5(text, context, content='statements', content_rowid='id');
+        CREATE VIRTUAL TABLE IF NOT EXISTS judgments_fts USING fts5(reasoning, content='judgments', content_rowid='rowid');
+
+        CREATE TRIGGER IF NOT EXISTS truth_events_fts_ai AFTER INSERT ON truth_events BEGIN
+            INSERT INTO truth_events_fts(rowid, description) VALUES (new.id, new.description);
+        END;
+        CREATE TRIGGER IF NOT EXISTS truth_events_fts_ad AFTER DELETE ON truth_events BEGIN
+            INSERT INTO truth_events_fts(truth_events_fts, rowid, description) VALUES ('delete', old.id, old.description);
+        END;
+        CREATE TRIGGER IF NOT EXISTS truth_events_fts_au AFTER UPDATE OF description ON truth_events BEGIN
+            INSERT INTO truth_events_fts(truth_events_fts, rowid, description) VALUES ('delete', old.id, old.description);
+            INSERT INTO truth_events_fts(rowid, description) VALUES (new.id, new.description);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS statements_fts_ai AFTER INSERT ON statements BEGIN
+            INSERT INTO statements_fts(rowid, text, context) VALUES (new.id, new.text, new.context);
+        END;
+        CREATE TRIGGER IF NOT EXISTS statements_fts_ad AFTER DELETE ON statements BEGIN
+            INSERT INTO statements_fts(statements_fts, rowid, text, context) VALUES ('delete', old.id, old.text, old.context);
+        END;
+        CREATE TRIGGER IF NOT EXISTS statements_fts_au AFTER UPDATE OF text, context ON statements BEGIN
+            INSERT INTO statements_fts(statements_fts, rowid, text, context) VALUES ('delete', old.id, old.text, old.context);
+            INSERT INTO statements_fts(rowid, text, context) VALUES (new.id, new.text, new.context);
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS judgments_fts_ai AFTER INSERT ON judgments BEGIN
+            INSERT INTO judgments_fts(rowid, reasoning) VALUES (new.rowid, new.reasoning);
+        END;
+        CREATE TRIGGER IF NOT EXISTS judgments_fts_ad AFTER DELETE ON judgments BEGIN
+            INSERT INTO judgments_fts(judgments_fts, rowid, reasoning) VALUES ('delete', old.rowid, old.reasoning);
+        END;
+        CREATE TRIGGER IF NOT EXISTS judgments_fts_au AFTER UPDATE OF reasoning ON judgments BEGIN
+            INSERT INTO judgments_fts(judgments_fts, rowid, reasoning) VALUES ('delete', old.rowid, old.reasoning);
+            INSERT INTO judgments_fts(rowid, reasoning) VALUES (new.rowid, new.reasoning);
+        END;
+
+        -- Проиндексировать уже существующие записи
+        INSERT INTO truth_events_fts(truth_events_fts) VALUES ('rebuild');
+        INSERT INTO statements_fts(statements_fts) VALUES ('rebuild');
+        INSERT INTO judgments_fts(judgments_fts) VALUES ('rebuild');
+        "#,
+        down: r#"
+        DROP TRIGGER IF EXISTS truth_events_fts_ai;
+        DROP TRIGGER IF EXISTS truth_events_fts_ad;
+        DROP TRIGGER IF EXISTS truth_events_fts_au;
+        DROP TRIGGER IF EXISTS statements_fts_ai;
+        DROP TRIGGER IF EXISTS statements_fts_ad;
+        DROP TRIGGER IF EXISTS statements_fts_au;
+        DROP TRIGGER IF EXISTS judgments_fts_ai;
+        DROP TRIGGER IF EXISTS judgments_fts_ad;
+        DROP TRIGGER IF EXISTS judgments_fts_au;
+        DROP TABLE IF EXISTS truth_events_fts;
+        DROP TABLE IF EXISTS statements_fts;
+        DROP TABLE IF EXISTS judgments_fts;
+        "#,
+    },
+    Migration {
+        version: 6,
+        name: "db_encryption",
+        up: r#"
+        -- Ключ данных, обёрнутый ключом из парольной фразы или ключа узла (см. at_rest)
+        CREATE TABLE IF NOT EXISTS db_encryption (
+            id           INTEGER PRIMARY KEY CHECK (id = 1),
+            kdf          TEXT    NOT NULL,
+            salt         TEXT    NOT NULL,
+            wrapped_key  TEXT    NOT NULL,
+            created_at   INTEGER NOT NULL,
+            rekeyed_at   INTEGER
+        );
+        "#,
+        down: "DROP TABLE IF EXISTS db_encryption;",
+    },
+    Migration {
+        version: 7,
+        name: "retention_policies",
+        up: r#"
+        -- Переопределения политик хранения журналов и истории (см. retention::default_policy)
+        CREATE TABLE IF NOT EXISTS retention_policies (
+            table_name             TEXT PRIMARY KEY,
+            max_age_days           INTEGER,
+            max_rows               INTEGER,
+            downsample_after_days  INTEGER,
+            updated_at             INTEGER NOT NULL
+        );
+        "#,
+        down: "DROP TABLE IF EXISTS retention_policies;",
+    },
+    Migration {
+        version: 8,
+        name: "tombstones",
+        up: r#"
+        -- Подписанные отметки удаления (см. models::Tombstone); удаляются по политике хранения
+        CREATE TABLE IF NOT EXISTS tombstones (
+            id          TEXT PRIMARY KEY,
+            table_name  TEXT NOT NULL,
+            record_id   TEXT NOT NULL,
+            author      TEXT NOT NULL,
+            reason      TEXT,
+            deleted_at  INTEGER NOT NULL,
+            signature   TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_tombstones_record ON tombstones(table_name, record_id);
+        CREATE INDEX IF NOT EXISTS idx_tombstones_deleted_at ON tombstones(deleted_at);
+        "#,
+        down: r#"
+        DROP INDEX IF EXISTS idx_tombstones_deleted_at;
+        DROP INDEX IF EXISTS idx_tombstones_record;
+        DROP TABLE IF EXISTS tombstones;
+        "#,
+    },
+    Migration {
+        version: 9,
+        name: "global_ids",
+        up: r#"
+        -- Глобальные id событий и утверждений; целый id остаётся локальным rowid (FTS, statements.event_id).
+        -- Существующим записям — хэш содержимого (см. models::legacy_uid, TruthEvent/Statement::legacy_uid);
+        -- локальные полные копии одной записи различаются суффиксом :<id> у всех, кроме первой
+        ALTER TABLE truth_events ADD COLUMN uid TEXT;
+        ALTER TABLE statements ADD COLUMN uid TEXT;
+        UPDATE truth_events SET uid = legacy_uid(context_id, timestamp_start, code, public_key) WHERE uid IS NULL;
+        UPDATE truth_events SET uid = uid || ':' || id
+            WHERE EXISTS (SELECT 1 FROM truth_events o WHERE o.uid = truth_events.uid AND o.id < truth_events.id);
+        UPDATE statements SET uid = legacy_uid((SELECT uid FROM truth_events WHERE id = statements.event_id), text, context, created_at, public_key)
+            WHERE uid IS NULL;
+        UPDATE statements SET uid = uid || ':' || id
+            WHERE EXISTS (SELECT 1 FROM statements o WHERE o.uid = statements.uid AND o.id < statements.id);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_truth_events_uid ON truth_events(uid);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_statements_uid ON statements(uid);
+
+        -- impact.event_id ссылается на uid события; влияния на отсутствующие события отбрасываются
+        CREATE TABLE impact_new (
+            id TEXT PRIMARY KEY,
+            event_id TEXT NOT NULL,
+            type_id INTEGER NOT NULL,
+            value INTEGER NOT NULL,
+            notes TEXT,
+            created_at INTEGER NOT NULL,
+            signature TEXT,
+            public_key TEXT,
+            FOREIGN KEY(event_id) REFERENCES truth_events(uid)
+        );
+        INSERT INTO impact_new (id, event_id, type_id, value, notes, created_at, signature, public_key)
+            SELECT im.id, te.uid, im.type_id, im.value, im.notes, im.created_at, im.signature, im.public_key
+            FROM impact im JOIN truth_events te ON te.id = CAST(im.event_id AS INTEGER);
+        DROP TABLE impact;
+        ALTER TABLE impact_new RENAME TO impact;
+        CREATE INDEX IF NOT EXISTS idx_impact_event ON impact(event_id);
+
+        UPDATE sync_log SET record_id = (SELECT uid FROM truth_events WHERE id = CAST(sync_log.record_id AS INTEGER))
+            WHERE table_name = 'truth_events'
+              AND EXISTS (SELECT 1 FROM truth_events WHERE id = CAST(sync_log.record_id AS INTEGER));
+        UPDATE sync_log SET record_id = (SELECT uid FROM statements WHERE id = CAST(sync_log.record_id AS INTEGER))
+            WHERE table_name = 'statements'
+              AND EXISTS (SELECT 1 FROM statements WHERE id = CAST(sync_log.record_id AS INTEGER));
+        "#,
+        down: r#"
+        UPDATE sync_log SET record_id = (SELECT CAST(id AS TEXT) FROM truth_events WHERE uid = sync_log.record_id)
+            WHERE table_name = 'truth_events' AND EXISTS (SELECT 1 FROM truth_events WHERE uid = sync_log.record_id);
+        UPDATE sync_log SET record_id = (SELECT CAST(id AS TEXT) FROM statements WHERE uid = sync_log.record_id)
+            WHERE table_name = 'statements' AND EXISTS (SELECT 1 FROM statements WHERE uid = sync_log.record_id);
+
+        CREATE TABLE impact_old (
+            id TEXT PRIMARY KEY,
+            event_id TEXT NOT NULL,
+            type_id INTEGER NOT NULL,
+            value INTEGER NOT NULL,
+            notes TEXT,
+            created_at INTEGER NOT NULL,
+            signature TEXT,
+            public_key TEXT,
+            FOREIGN KEY(event_id) REFERENCES truth_events(id)
+        );
+        INSERT INTO impact_old (id, event_id, type_id, value, notes, created_at, signature, public_key)
+            SELECT im.id, CAST(te.id AS TEXT), im.type_id, im.value, im.notes, im.created_at, im.signature, im.public_key
+            FROM impact im JOIN truth_events te ON te.uid = im.event_id;
+        DROP TABLE impact;
+        ALTER TABLE impact_old RENAME TO impact;
+
+        DROP INDEX IF EXISTS idx_statements_uid;
+        DROP INDEX IF EXISTS idx_truth_events_uid;
+        ALTER TABLE statements DROP COLUMN uid;
+        ALTER TABLE truth_events DROP COLUMN uid;
+        "#,
+    },
+    Migration {
+        version: 10,
+        name: "knowledge_base_edits",
+        up: r#"
+        -- Правки базы знаний подписывает администратор; updated_at = 0 — начальное наполнение (не синхронизируется)
+        ALTER TABLE category ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE category ADD COLUMN deprecated_at INTEGER;
+        ALTER TABLE category ADD COLUMN updated_by TEXT;
+        ALTER TABLE category ADD COLUMN signature TEXT;
+        ALTER TABLE cause ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE cause ADD COLUMN deprecated_at INTEGER;
+        ALTER TABLE cause ADD COLUMN updated_by TEXT;
+        ALTER TABLE cause ADD COLUMN signature TEXT;
+        ALTER TABLE develop ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE develop ADD COLUMN deprecated_at INTEGER;
+        ALTER TABLE develop ADD COLUMN updated_by TEXT;
+        ALTER TABLE develop ADD COLUMN signature TEXT;
+        ALTER TABLE effect ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE effect ADD COLUMN deprecated_at INTEGER;
+        ALTER TABLE effect ADD COLUMN updated_by TEXT;
+        ALTER TABLE effect ADD COLUMN signature TEXT;
+        ALTER TABLE forma ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE forma ADD COLUMN deprecated_at INTEGER;
+        ALTER TABLE forma ADD COLUMN updated_by TEXT;
+        ALTER TABLE forma ADD COLUMN signature TEXT;
+        ALTER TABLE context ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE context ADD COLUMN deprecated_at INTEGER;
+        ALTER TABLE context ADD COLUMN updated_by TEXT;
+        ALTER TABLE context ADD COLUMN signature TEXT;
+        ALTER TABLE impact_type ADD COLUMN updated_at INTEGER NOT NULL DEFAULT 0;
+        ALTER TABLE impact_type ADD COLUMN deprecated_at INTEGER;
+        ALTER TABLE impact_type ADD COLUMN updated_by TEXT;
+        ALTER TABLE impact_type ADD COLUMN signature TEXT;
+        "#,
+        down: r#"
+        ALTER TABLE impact_type DROP COLUMN signature;
+        ALTER TABLE impact_type DROP COLUMN updated_by;
+        ALTER TABLE impact_type DROP COLUMN deprecated_at;
+        ALTER TABLE impact_type DROP COLUMN updated_at;
+        ALTER TABLE context DROP COLUMN signature;
+        ALTER TABLE context DROP COLUMN updated_by;
+        ALTER TABLE context DROP COLUMN deprecated_at;
+        ALTER TABLE context DROP COLUMN updated_at;
+        ALTER TABLE forma DROP COLUMN signature;
+        ALTER TABLE forma DROP COLUMN updated_by;
+        ALTER TABLE forma DROP COLUMN deprecated_at;
+        ALTER TABLE forma DROP COLUMN updated_at;
+        ALTER TABLE effect DROP COLUMN signature;
+        ALTER TABLE effect DROP COLUMN updated_by;
+        ALTER TABLE effect DROP COLUMN deprecated_at;
+        ALTER TABLE effect DROP COLUMN updated_at;
+        ALTER TABLE develop DROP COLUMN signature;
+        ALTER TABLE develop DROP COLUMN updated_by;
+        ALTER TABLE develop DROP COLUMN deprecated_at;
+        ALTER TABLE develop DROP COLUMN updated_at;
+        ALTER TABLE cause DROP COLUMN signature;
+        ALTER TABLE cause DROP COLUMN updated_by;
+        ALTER TABLE cause DROP COLUMN deprecated_at;
+        ALTER TABLE cause DROP COLUMN updated_at;
+        ALTER TABLE category DROP COLUMN signature;
+        ALTER TABLE category DROP COLUMN updated_by;
+        ALTER TABLE category DROP COLUMN deprecated_at;
+        ALTER TABLE category DROP COLUMN updated_at;
+        "#,
+    },
+    Migration {
+        version: 11,
+        name: "expert_questions",
+        up: r#"
+        -- Вопросы экспертной оценки из пакетов базы знаний; context_ids — JSON-массив id, NULL — для всех контекстов
+        CREATE TABLE IF NOT EXISTS expert_questions (
+            key TEXT PRIMARY KEY,
+            text TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            weight REAL NOT NULL,
+            truth_bias INTEGER NOT NULL,
+            context_ids TEXT,
+            pack TEXT
+        );
+        "#,
+        down: r#"
+        DROP TABLE IF EXISTS expert_questions;
+        "#,
+    },
+    Migration {
+        version: 12,
+        name: "audit_log",
+        up: r#"
+        -- Журнал аудита: цепочка хешей изменений, только добавление (см. core_lib::audit)
+        CREATE TABLE IF NOT EXISTS audit_log (
+            seq INTEGER PRIMARY KEY,
+            ts INTEGER NOT NULL,
+            actor TEXT NOT NULL,
+            op TEXT NOT NULL,
+            table_name TEXT NOT NULL,
+            record_id TEXT NOT NULL,
+            before_hash TEXT,
+            after_hash TEXT,
+            prev_hash TEXT NOT NULL,
+            entry_hash TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_audit_log_record ON audit_log(table_name, record_id);
+        -- Подписанные ключом узла отметки головы цепочки
+        CREATE TABLE IF NOT EXISTS audit_checkpoints (
+            seq INTEGER PRIMARY KEY,
+            entry_hash TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            public_key TEXT NOT NULL,
+            signature TEXT NOT NULL
+        );
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+        BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+        BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;
+        CREATE TRIGGER IF NOT EXISTS audit_checkpoints_no_update BEFORE UPDATE ON audit_checkpoints
+        BEGIN SELECT RAISE(ABORT, 'audit_checkpoints is append-only'); END;
+        CREATE TRIGGER IF NOT EXISTS audit_checkpoints_no_delete BEFORE DELETE ON audit_checkpoints
+        BEGIN SELECT RAISE(ABORT, 'audit_checkpoints is append-only'); END;
+        "#,
+        down: r#"
+        DROP TABLE IF EXISTS audit_checkpoints;
+        DROP TABLE IF EXISTS audit_log;
+        "#,
+    },
+    Migration {
+        version: 13,
+        name: "graded_impacts",
+        up: r#"
+        -- Оценка влияния со степенью -1..+1 и уверенностью 0..1; value остаётся знаком для старых пиров
+        ALTER TABLE impact ADD COLUMN magnitude REAL NOT NULL DEFAULT 1.0;
+        ALTER TABLE impact ADD COLUMN confidence REAL;
+        UPDATE impact SET magnitude = CASE WHEN value = 1 THEN 1.0 ELSE -1.0 END;
+        "#,
+        down: r#"
+        ALTER TABLE impact DROP COLUMN confidence;
+        ALTER TABLE impact DROP COLUMN magnitude;
+        "#,
+    },
+    Migration {
+        version: 14,
+        name: "evidence",
+        up: r#"
+        -- Подписанные доказательства к событиям и утверждениям; содержимое — в хранилище блобов по sha256
+        CREATE TABLE IF NOT EXISTS evidence (
+            id TEXT PRIMARY KEY,
+            target_table TEXT NOT NULL,
+            target_uid TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            sha256 TEXT,
+            name TEXT NOT NULL,
+            media_type TEXT,
+            size INTEGER,
+            note TEXT,
+            author TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            signature TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_evidence_target ON evidence(target_table, target_uid);
+        CREATE INDEX IF NOT EXISTS idx_evidence_created ON evidence(created_at);
+        "#,
+        down: r#"
+        DROP TABLE IF EXISTS evidence;
+        "#,
+    },
+    Migration {
+        version: 15,
+        name: "argument_trees",
+        up: r#"
+        -- Утверждение может отвечать на другое утверждение того же события
+        ALTER TABLE statements ADD COLUMN parent_uid TEXT;
+        ALTER TABLE statements ADD COLUMN relation TEXT;
+        CREATE INDEX IF NOT EXISTS idx_statements_parent ON statements(parent_uid);
+        -- Итоговая оценка дерева аргументов события; пересчитывается в recalc_ratings
+        CREATE TABLE IF NOT EXISTS argument_scores (
+            event_uid TEXT PRIMARY KEY,
+            score REAL NOT NULL,
+            statements INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        );
+        "#,
+        down: r#"
+        DROP TABLE IF EXISTS argument_scores;
+        DROP INDEX IF EXISTS idx_statements_parent;
+        ALTER TABLE statements DROP COLUMN relation;
+        ALTER TABLE statements DROP COLUMN parent_uid;
+        "#,
+    },
+    Migration {
+        version: 16,
+        name: "event_relations",
+        up: r#"
+        -- Подписанные связи между событиями: duplicate_of, contradicts, supports, supersedes
+        CREATE TABLE IF NOT EXISTS event_relations (
+            id TEXT PRIMARY KEY,
+            source_uid TEXT NOT NULL,
+            target_uid TEXT NOT NULL,
+            relation TEXT NOT NULL,
+            note TEXT,
+            author TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            signature TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_event_relations_source ON event_relations(source_uid);
+        CREATE INDEX IF NOT EXISTS idx_event_relations_target ON event_relations(target_uid);
+        CREATE INDEX IF NOT EXISTS idx_event_relations_created ON event_relations(created_at);
+        "#,
+        down: r#"
+        DROP TABLE IF EXISTS event_relations;
+        "#,
+    },
+    Migration {
+        version: 17,
+        name: "group_origin",
+        up: r#"
+        -- Происхождение группы: local — из локального пересчёта (заменяется при каждом пересчёте),
+        -- remote — получена от пира и пересчётом не удаляется
+        ALTER TABLE group_ratings ADD COLUMN origin TEXT NOT NULL DEFAULT 'local';
+        "#,
+        down: r#"
+        ALTER TABLE group_ratings DROP COLUMN origin;
+        "#,
+    },
+];
+
+/// Колонки, которые старые run_migrations добавляли через ALTER TABLE.
+/// Нужны только для БД, созданных до schema_version: в них таблицы есть, а колонок может не быть.
+const LEGACY_COLUMNS: &[(&str, &str, &str)] = &[
+    ("truth_events", "signature", "TEXT"),
+    ("truth_events", "public_key", "TEXT"),
+    ("truth_events", "collective_score", "REAL"),
+    ("statements", "signature", "TEXT"),
+    ("statements", "public_key", "TEXT"),
+    ("impact", "signature", "TEXT"),
+    ("impact", "public_key", "TEXT"),
+    ("node_metrics", "quality_index", "REAL NOT NULL DEFAULT 0.0"),
+    ("node_metrics", "propagation_priority", "REAL NOT NULL DEFAULT 0.0"),
+    ("node_ratings", "propagation_priority", "REAL NOT NULL DEFAULT 0.0"),
+];
+
+/// Последняя известная этой сборке версия схемы
+pub fn latest_version() -> u32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+fn table_exists(conn: &Connection, table: &str) -> Result<bool, rusqlite::Error> {
+    conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE type='table' AND name=?1",
+        params![table],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|r| r.is_some())
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info('{}')", table))?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let name: String = row.get(1)?; // 1 = name
+        if name == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Текущая версия схемы; 0 — БД без schema_version (новая или созданная до версионирования)
+pub fn current_version(conn: &Connection) -> Result<u32, CoreError> {
+    if !table_exists(conn, "schema_version")? {
+        return Ok(0);
+    }
+    let v: Option<i64> = conn.query_row("SELECT MAX(version) FROM schema_version", [], |r| r.get(0))?;
+    Ok(v.unwrap_or(0) as u32)
+}
+
+/// Список всех миграций с отметкой о применении. Ничего не меняет в БД.
+pub fn status(conn: &Connection) -> Result<Vec<MigrationStatus>, CoreError> {
+    let mut applied = std::collections::HashMap::new();
+    if table_exists(conn, "schema_version")? {
+        let mut stmt = conn.prepare("SELECT version, checksum, applied_at FROM schema_version")?;
+        let rows = stmt.query_map([], |r| Ok((r.get::<_, i64>(0)? as u32, r.get::<_, String>(1)?, r.get::<_, i64>(2)?)))?;
+        for r in rows {
+            let (version, checksum, applied_at) = r?;
+            applied.insert(version, (checksum, applied_at));
+        }
+    }
+    Ok(MIGRATIONS
+        .iter()
+        .map(|m| {
+            let rec = applied.get(&m.version);
+            MigrationStatus {
+                version: m.version,
+                name: m.name.to_string(),
+                applied_at: rec.map(|(_, at)| *at),
+                checksum_ok: rec.map(|(c, _)| *c == m.checksum()).unwrap_or(true),
+            }
+        })
+        .collect())
+}
+
+/// Довести БД, созданную до schema_version, до формы базовой миграции
+fn adopt_legacy(conn: &Connection) -> Result<Vec<String>, CoreError> {
+    let mut added = Vec::new();
+    if table_exists(conn, "schema_version")? {
+        return Ok(added);
+    }
+    for (table, column, ddl) in LEGACY_COLUMNS {
+        if table_exists(conn, table)? && !has_column(conn, table, column)? {
+            conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, ddl), [])?;
+            added.push(format!("{}.{}", table, column));
+        }
+    }
+    Ok(added)
+}
+
+/// SQL-функции, которые вызывают миграции: legacy_uid(поля...) — models::legacy_uid над текстом аргументов
+fn register_functions(conn: &Connection) -> Result<(), CoreError> {
+    use rusqlite::functions::FunctionFlags;
+    use rusqlite::types::ValueRef;
+    conn.create_scalar_function(
+        "legacy_uid",
+        -1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let fields: Vec<String> = (0..ctx.len())
+                .map(|i| match ctx.get_raw(i) {
+                    ValueRef::Null => String::new(),
+                    ValueRef::Integer(v) => v.to_string(),
+                    ValueRef::Real(v) => v.to_string(),
+                    ValueRef::Text(t) | ValueRef::Blob(t) => String::from_utf8_lossy(t).into_owned(),
+                })
+                .collect();
+            let refs: Vec<&str> = fields.iter().map(String::as_str).collect();
+            Ok(crate::models::legacy_uid(&refs))
+        },
+    )?;
+    Ok(())
+}
+
+fn apply_step(conn: &Connection, m: &Migration, up: bool, now_ts: i64) -> Result<(), CoreError> {
+    if up {
+        conn.execute_batch(m.up)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO schema_version(version, name, checksum, applied_at) VALUES (?1, ?2, ?3, ?4)",
+            params![m.version, m.name, m.checksum(), now_ts],
+        )?;
+    } else {
+        conn.execute_batch(m.down)?;
+        conn.execute("DELETE FROM schema_version WHERE version = ?1", params![m.version])?;
+    }
+    Ok(())
+}
+
+/// Выполнить f в собственной транзакции; при dry_run внешняя транзакция уже открыта
+fn in_tx<T>(conn: &Connection, dry_run: bool, f: impl FnOnce(&Connection) -> Result<T, CoreError>) -> Result<T, CoreError> {
+    if dry_run {
+        return f(conn);
+    }
+    let tx = conn.unchecked_transaction()?;
+    let out = f(&tx)?;
+    tx.commit()?;
+    Ok(out)
+}
+
+/// Перевести схему на версию target (по умолчанию — последнюю): вверх или откатом вниз.
+/// Каждая миграция применяется в своей транзакции; при dry_run всё выполняется
+/// в одной транзакции, которая откатывается, а отчёт показывает, что было бы сделано.
+pub fn migrate(conn: &Connection, target: Option<u32>, dry_run: bool) -> Result<MigrationReport, CoreError> {
+    let latest = latest_version();
+    let target = target.unwrap_or(latest);
+    if target > latest {
+        return Err(CoreError::InvalidArg(format!("unknown schema version {} (latest is {})", target, latest)));
+    }
+    let from = current_version(conn)?;
+    if from > latest {
+        return Err(CoreError::InvalidArg(format!(
+            "database schema version {} is newer than this build supports ({})",
+            from, latest
+        )));
+    }
+
+    register_functions(conn)?;
+    let outer = if dry_run { Some(conn.unchecked_transaction()?) } else { None };
+    let legacy_columns = in_tx(conn, dry_run, |c| {
+        let added = adopt_legacy(c)?;
+        c.execute_batch(VERSION_TABLE_SQL)?;
+        Ok(added)
+    })?;
+
+    let now_ts = chrono::Utc::now().timestamp();
+    let mut steps = Vec::new();
+    let plan: Vec<(&Migration, bool)> = if target >= from {
+        MIGRATIONS.iter().filter(|m| m.version > from && m.version <= target).map(|m| (m, true)).collect()
+    } else {
+        MIGRATIONS.iter().rev().filter(|m| m.version <= from && m.version > target).map(|m| (m, false)).collect()
+    };
+    for (m, up) in plan {
+        in_tx(conn, dry_run, |c| apply_step(c, m, up, now_ts))?;
+        steps.push(MigrationStep {
+            version: m.version,
+            name: m.name.to_string(),
+            direction: if up { "up" } else { "down" }.to_string(),
+        });
+    }
+    // Transaction откатывается при drop
+    drop(outer);
+
+    Ok(MigrationReport { from, to: target, steps, dry_run, legacy_columns })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tables(conn: &Connection) -> Vec<String> {
+        let mut stmt = conn.prepare("SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%' ORDER BY name").unwrap();
+        let rows = stmt.query_map([], |r| r.get(0)).unwrap();
+        rows.map(|r| r.unwrap()).collect()
+    }
+
+    #[test]
+    fn migrate_up_down_and_dry_run() {
+        let conn = Connection::open_in_memory().unwrap();
+
+        let plan = migrate(&conn, None, true).unwrap();
+        assert_eq!(plan.steps.len(), MIGRATIONS.len());
+        assert!(tables(&conn).is_empty(), "dry run must not change the database");
+        assert_eq!(current_version(&conn).unwrap(), 0);
+
+        let report = migrate(&conn, None, false).unwrap();
+        assert_eq!((report.from, report.to), (0, latest_version()));
+        assert!(status(&conn).unwrap().iter().all(|s| s.applied_at.is_some() && s.checksum_ok));
+        assert!(tables(&conn).contains(&"alerts".to_string()));
+        // Повторный запуск ничего не делает
+        assert!(migrate(&conn, None, false).unwrap().steps.is_empty());
+
+        let down = migrate(&conn, Some(1), false).unwrap();
+        assert_eq!(down.steps.iter().map(|s| s.version).collect::<Vec<_>>(), (2..=latest_version()).rev().collect::<Vec<_>>());
+        assert!(down.steps.iter().all(|s| s.direction == "down"));
+        assert_eq!(current_version(&conn).unwrap(), 1);
+        assert!(!tables(&conn).contains(&"alerts".to_string()));
+        assert!(tables(&conn).contains(&"truth_events".to_string()));
+
+        migrate(&conn, Some(0), false).unwrap();
+        assert_eq!(tables(&conn), vec!["schema_version".to_string()]);
+        migrate(&conn, None, false).unwrap();
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+
+        assert!(migrate(&conn, Some(latest_version() + 1), false).is_err());
+        conn.execute("INSERT INTO schema_version VALUES (99, 'future', '', 0)", []).unwrap();
+        assert!(migrate(&conn, None, false).is_err());
+    }
+
+    #[test]
+    fn legacy_database_is_adopted() {
+        let conn = Connection::open_in_memory().unwrap();
+        // Форма БД до версионирования: таблицы есть, поздних колонок нет
+        conn.execute_batch(
+            r#"
+            CREATE TABLE truth_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT, description TEXT NOT NULL, context_id INTEGER NOT NULL,
+                vector INTEGER NOT NULL, detected INTEGER, corrected INTEGER NOT NULL DEFAULT 0,
+                timestamp_start INTEGER NOT NULL, timestamp_end INTEGER, code INTEGER NOT NULL DEFAULT 1
+            );
+            CREATE TABLE node_ratings (node_id TEXT PRIMARY KEY, trust_score REAL NOT NULL DEFAULT 0.0, last_updated INTEGER NOT NULL);
+            INSERT INTO node_ratings(node_id, trust_score, last_updated) VALUES ('n1', 0.4, 1);
+            "#,
+        )
+        .unwrap();
+
+        let report = migrate(&conn, None, false).unwrap();
+        assert_eq!(report.from, 0);
+        assert!(report.legacy_columns.contains(&"truth_events.signature".to_string()));
+        assert!(report.legacy_columns.contains(&"node_ratings.propagation_priority".to_string()));
+        assert!(has_column(&conn, "truth_events", "collective_score").unwrap());
+        let trust: f64 = conn.query_row("SELECT trust_score FROM node_ratings WHERE node_id='n1'", [], |r| r.get(0)).unwrap();
+        assert!((trust - 0.4).abs() < 1e-9);
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn global_ids_rekey_existing_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn, Some(8), false).unwrap();
+        // Контекст не нужен для проверки перекладки id
+        conn.execute_batch(
+            r#"
+            PRAGMA foreign_keys = OFF;
+            INSERT INTO truth_events(id, description, context_id, vector, corrected, timestamp_start, code, public_key)
+                VALUES (5, 'a', 1, 1, 0, 10, 1, 'pk-a'), (6, 'b', 1, 1, 0, 11, 1, NULL), (7, 'b', 1, 1, 0, 11, 1, NULL);
+            INSERT INTO statements(id, event_id, text, created_at, updated_at, public_key) VALUES (3, 5, 's', 10, 10, 'pk-s');
+            INSERT INTO impact(id, event_id, type_id, value, created_at) VALUES ('i1', '5', 1, 1, 10), ('orphan', '42', 1, 1, 10);
+            INSERT INTO sync_log(op, table_name, record_id, created_at) VALUES
+                ('insert', 'truth_events', '6', 1), ('insert', 'statements', '3', 1), ('insert', 'impact', 'i1', 1);
+            PRAGMA foreign_keys = ON;
+            "#,
+        )
+        .unwrap();
+
+        migrate(&conn, Some(9), false).unwrap();
+        let uid = |sql: &str| conn.query_row(sql, [], |r| r.get::<_, String>(0)).unwrap();
+        // uid — хэш содержимого, как у models::legacy_uid; полная копия 6 получает суффикс своего id
+        let a = crate::models::legacy_uid(&["1", "10", "1", "pk-a"]);
+        let b = crate::models::legacy_uid(&["1", "11", "1", ""]);
+        let s3 = crate::models::legacy_uid(&[&a, "s", "", "10", "pk-s"]);
+        assert_eq!(uid("SELECT uid FROM truth_events WHERE id = 5"), a);
+        assert_eq!(uid("SELECT uid FROM truth_events WHERE id = 6"), b);
+        assert_eq!(uid("SELECT uid FROM truth_events WHERE id = 7"), format!("{}:7", b));
+        assert_eq!(uid("SELECT uid FROM statements WHERE id = 3"), s3);
+        assert_eq!(uid("SELECT group_concat(id || '>' || event_id) FROM impact"), format!("i1>{}", a));
+        assert_eq!(uid("SELECT group_concat(record_id, ',') FROM sync_log ORDER BY id"), format!("{},{},i1", b, s3));
+        conn.execute_batch("PRAGMA foreign_keys = OFF").unwrap();
+        let dup = conn.execute(
+            "INSERT INTO truth_events(description, context_id, vector, corrected, timestamp_start, code, uid)
+                 VALUES ('c', 1, 1, 0, 1, 1, ?1)",
+            [&b],
+        );
+        assert!(dup.unwrap_err().to_string().contains("UNIQUE"));
+        conn.execute_batch("PRAGMA foreign_keys = ON").unwrap();
+
+        migrate(&conn, Some(8), false).unwrap();
+        assert!(!has_column(&conn, "truth_events", "uid").unwrap());
+        assert_eq!(uid("SELECT group_concat(id || '>' || event_id) FROM impact"), "i1>5");
+        assert_eq!(uid("SELECT group_concat(record_id, ',') FROM sync_log ORDER BY id"), "6,3,i1");
+    }
+
+    #[test]
+    fn graded_impacts_keep_sign_of_value() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn, Some(12), false).unwrap();
+        conn.execute_batch(
+            r#"
+            PRAGMA foreign_keys = OFF;
+            INSERT INTO impact(id, event_id, type_id, value, created_at) VALUES ('pos', 'e', 1, 1, 10), ('neg', 'e', 1, 0, 10);
+            PRAGMA foreign_keys = ON;
+            "#,
+        )
+        .unwrap();
+
+        migrate(&conn, Some(13), false).unwrap();
+        let grades = conn
+            .query_row("SELECT group_concat(id || '=' || magnitude || '/' || IFNULL(confidence, '-'), ',') FROM (SELECT * FROM impact ORDER BY id)", [], |r| r.get::<_, String>(0))
+            .unwrap();
+        assert_eq!(grades, "neg=-1.0/-,pos=1.0/-");
+
+        migrate(&conn, Some(12), false).unwrap();
+        assert!(!has_column(&conn, "impact", "magnitude").unwrap());
+    }
+}
@@ -13,6 +13,10 @@ InvalidArg(String),
     Io(#[from] std::io::Error),
     #[error("Serde JSON error: {0}")]
     Serde(#[from] serde_json::Error),
+    #[error("Connection pool error: {0}")]
+    Pool(String),
+    #[error("Database is locked: {0}")]
+    Locked(String),
 }
 
 /// Категория (таблица: category)
@@ -83,7 +87,9 @@ pub struct ImpactType {
 /// Событие правды/лжи (таблица: truth_events)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TruthEvent {
-    pub id: i64,                    // INTEGER, PK
+    pub id: i64,                    // INTEGER, PK — локальный rowid, между узлами не совпадает
+    #[serde(default)]
+    pub uid: String,                // TEXT UNIQUE — глобальный id (UUIDv7 или legacy_uid)
     pub description: String,        // TEXT
     pub context_id: i64,            // INTEGER (FK → context.id)
     pub vector: bool,               // BOOLEAN (true = исходящее, false = входящее)
@@ -111,15 +117,49 @@ pub timestamp_start: i64,
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Impact {
     pub id: String,
-    pub event_id: String, // FK → truth_events.id
+    pub event_id: String, // FK → truth_events.uid
     pub type_id: i64,  // FK → impact_type.id
-    pub value: bool,   // true = позитивное, false = негативное
+    pub value: bool,   // знак оценки: true = позитивное (magnitude >= 0); старые пиры знают только его
+    /// Степень оценки: -1 (резко ложно) .. +1 (полностью верно); None — запись старого пира (±1 по value)
+    #[serde(default)]
+    pub magnitude: Option<f64>,
+    /// Уверенность оценивающего 0..1; None — полная
+    #[serde(default)]
+    pub confidence: Option<f64>,
 pub notes: Option<String>,
     pub created_at: i64,
     pub signature: Option<String>,  // Подпись записи влияния
     pub public_key: Option<String>, // Публичный ключ автора
 }
 
+impl Impact {
+    /// Степень оценки; у записей старых пиров — ±1 по value
+    pub fn grade(&self) -> f64 {
+        self.magnitude.unwrap_or(if self.value { 1.0 } else { -1.0 })
+    }
+
+    /// Вклад в агрегаты: степень, умноженная на уверенность
+    pub fn weighted(&self) -> f64 {
+        self.grade() * self.confidence.unwrap_or(1.0)
+    }
+
+    /// value совпадает со знаком magnitude; у записей старых пиров magnitude нет
+    pub fn sign_consistent(&self) -> bool {
+        self.magnitude.is_none_or(|m| (m >= 0.0) == self.value)
+    }
+
+    /// Проверить диапазоны степени (-1..1) и уверенности (0..1)
+    pub fn check_grade(magnitude: f64, confidence: Option<f64>) -> Result<(), CoreError> {
+        if !(-1.0..=1.0).contains(&magnitude) {
+            return Err(CoreError::InvalidArg(format!("impact magnitude {} is outside -1..1", magnitude)));
+        }
+        if let Some(c) = confidence.filter(|c| !(0.0..=1.0).contains(c)) {
+            return Err(CoreError::InvalidArg(format!("impact confidence {} is outside 0..1", c)));
+        }
+        Ok(())
+    }
+}
+
 /// Метрики прогресса (таблица: progress_metrics)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProgressMetrics {
@@ -138,8 +178,12 @@ pub trend_group: f64,
 /// Утверждение (таблица: statements)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Statement {
-    pub id: i64,                    // INTEGER, PK
-    pub event_id: i64,              // INTEGER (FK → truth_events.id)
+    pub id: i64,                    // INTEGER, PK — локальный rowid
+    #[serde(default)]
+    pub uid: String,                // TEXT UNIQUE — глобальный id
+    pub event_id: i64,              // INTEGER (FK → truth_events.id, локальный)
+    #[serde(default)]
+    pub event_uid: String,          // truth_events.uid события — по нему пиры находят событие
     pub text: String,               // TEXT - текст утверждения
     pub context: Option<String>,    // TEXT NULLABLE - дополнительный контекст
     pub truth_score: Option<f32>,   // REAL NULLABLE - оценка правдивости (-1..+1)
@@ -147,6 +191,15 @@ pub struct Statement {
     pub updated_at: i64,            // INTEGER (UNIX secs)
     pub signature: Option<String>,  // Подпись утверждения
     pub public_key: Option<String>, // Публичный ключ автора
+    #[serde(default)]
+    pub parent_uid: Option<String>, // uid родительского утверждения того же события; None — корень
+    #[serde(default)]
+    pub relation: Option<String>,   // отношение к родителю: supports, rebuts, clarifies
+}
+
+impl Statement {
+    /// Отношения ответа к родительскому утверждению
+    pub const RELATIONS: &'static [&'static str] = &["supports", "rebuts", "clarifies"];
 }
 
 /// Вспомогательная структура для вставки утверждения
@@ -156,6 +209,54 @@ pub struct NewStatement {
     pub text: String,
     pub context: Option<String>,
     pub truth_score: Option<f32>,
+    #[serde(default)]
+    pub parent_uid: Option<String>,
+    #[serde(default)]
+    pub relation: Option<String>,
+}
+
+/// Новый глобальный id события или утверждения: UUIDv7 (упорядочен по времени создания)
+pub fn new_uid() -> String {
+    uuid::Uuid::now_v7().to_string()
+}
+
+/// Глобальный id записи, созданной до появления uid: "legacy:" и хэш её содержимого, одинаковый
+/// на всех узлах с копией записи. Целый id в хэш не входит — у разных узлов под одним id лежат разные записи.
+/// Поля хэшируются с длиной, чтобы границы между ними не сдвигались; SQL-функция legacy_uid из миграций
+/// передаёт сюда свои аргументы текстом (NULL — пустая строка)
+pub fn legacy_uid(fields: &[&str]) -> String {
+    let mut hasher = blake3::Hasher::new();
+    for f in fields {
+        hasher.update(&(f.len() as u64).to_le_bytes());
+        hasher.update(f.as_bytes());
+    }
+    format!("legacy:{}", &hasher.finalize().to_hex()[..32])
+}
+
+impl TruthEvent {
+    /// legacy_uid события по контексту, времени начала, коду и ключу автора. Описание в хэш не входит:
+    /// его можно исправить, и исправленная копия должна остаться той же записью
+    pub fn legacy_uid(&self) -> String {
+        legacy_uid(&[
+            &self.context_id.to_string(),
+            &self.timestamp_start.to_string(),
+            &self.code.to_string(),
+            self.public_key.as_deref().unwrap_or(""),
+        ])
+    }
+}
+
+impl Statement {
+    /// legacy_uid утверждения по uid его события, тексту, контексту, времени создания и ключу автора
+    pub fn legacy_uid(&self, event_uid: &str) -> String {
+        legacy_uid(&[
+            event_uid,
+            &self.text,
+            self.context.as_deref().unwrap_or(""),
+            &self.created_at.to_string(),
+            self.public_key.as_deref().unwrap_or(""),
+        ])
+    }
 }
 
 /// Пользователь (для будущего расширения)
@@ -206,6 +307,415 @@ pub struct GroupRating {
     pub last_updated: i64,
 }
 
+/// Подписанное делегирование доверия: delegator сдвигает trust_score target на delta
+/// до expires_at (если задано). Запись синхронизируется между узлами и применяется при пересчёте.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrustDelegation {
+    pub id: String,
+    pub delegator: String, // публичный ключ делегирующего (hex)
+    pub target: String,    // публичный ключ получателя (hex)
+    pub delta: f32,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub signature: String, // подпись delegator над signing_message()
+    pub revoked_at: Option<i64>,
+    pub revocation_signature: Option<String>, // подпись delegator над revocation_message()
+}
+
+impl TrustDelegation {
+    /// Создать неподписанную запись; id выводится из содержимого
+    pub fn new(delegator: &str, target: &str, delta: f32, created_at: i64, expires_at: Option<i64>) -> Self {
+        let id = blake3::hash(
+            format!("{}:{}:{:.6}:{}:{}", delegator, target, delta, created_at, expires_at.unwrap_or(0)).as_bytes(),
+        )
+        .to_hex()
+        .to_string();
+        Self {
+            id,
+            delegator: delegator.to_string(),
+            target: target.to_string(),
+            delta,
+            created_at,
+            expires_at,
+            signature: String::new(),
+            revoked_at: None,
+            revocation_signature: None,
+        }
+    }
+
+    /// Сообщение, которое подписывает delegator при выдаче
+    pub fn signing_message(&self) -> String {
+        format!(
+            "trust_delegate:{}:{}:{}:{:.6}:{}:{}",
+            self.id,
+            self.delegator,
+            self.target,
+            self.delta,
+            self.created_at,
+            self.expires_at.unwrap_or(0)
+        )
+    }
+
+    /// Сообщение, которое подписывает delegator при отзыве
+    pub fn revocation_message(id: &str, revoked_at: i64) -> String {
+        format!("trust_revoke:{}:{}", id, revoked_at)
+    }
+
+    /// Действует ли делегирование на момент ts
+    pub fn is_active(&self, ts: i64) -> bool {
+        self.revoked_at.is_none() && self.expires_at.map(|e| e > ts).unwrap_or(true)
+    }
+}
+
+/// Подписанная отметка удаления записи. Подписывает автор записи или администратор;
+/// отметка распространяется пирам и не даёт удалённой записи вернуться при синхронизации.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Tombstone {
+    pub id: String,
+    pub table_name: String, // truth_events | statements | impact
+    pub record_id: String, // uid события или утверждения, id влияния
+    pub author: String, // публичный ключ подписавшего (hex)
+    pub reason: Option<String>,
+    pub deleted_at: i64,
+    pub signature: String, // подпись author над signing_message()
+}
+
+impl Tombstone {
+    /// Таблицы, записи которых можно удалить отметкой
+    pub const TABLES: &'static [&'static str] = &["truth_events", "statements", "impact"];
+
+    /// Создать неподписанную отметку; id выводится из содержимого
+    pub fn new(table_name: &str, record_id: &str, author: &str, reason: Option<String>, deleted_at: i64) -> Self {
+        let id = blake3::hash(format!("{}:{}:{}:{}", table_name, record_id, author, deleted_at).as_bytes())
+            .to_hex()
+            .to_string();
+        Self {
+            id,
+            table_name: table_name.to_string(),
+            record_id: record_id.to_string(),
+            author: author.to_string(),
+            reason,
+            deleted_at,
+            signature: String::new(),
+        }
+    }
+
+    /// Сообщение, которое подписывает author
+    pub fn signing_message(&self) -> String {
+        format!(
+            "tombstone:{}:{}:{}:{}:{}:{}",
+            self.id,
+            self.table_name,
+            self.record_id,
+            self.author,
+            self.deleted_at,
+            self.reason.as_deref().unwrap_or("")
+        )
+    }
+}
+
+/// Запись базы знаний (category, cause, develop, effect, forma, context, impact_type) в едином виде.
+/// Правку подписывает администратор; записи не удаляются, а помечаются устаревшими.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KbEntry {
+    pub kind: String, // имя таблицы, см. KbEntry::KINDS
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub quality: Option<bool>, // только cause, develop, effect, forma
+    // Ссылки — только у context
+    #[serde(default)]
+    pub category_id: Option<i64>,
+    #[serde(default)]
+    pub forma_id: Option<i64>,
+    #[serde(default)]
+    pub cause_id: Option<i64>,
+    #[serde(default)]
+    pub develop_id: Option<i64>,
+    #[serde(default)]
+    pub effect_id: Option<i64>,
+    #[serde(default)]
+    pub updated_at: i64, // 0 — начальное наполнение
+    #[serde(default)]
+    pub deprecated_at: Option<i64>,
+    #[serde(default)]
+    pub updated_by: Option<String>, // публичный ключ администратора (hex)
+    #[serde(default)]
+    pub signature: Option<String>, // подпись updated_by над signing_message()
+}
+
+impl KbEntry {
+    /// Виды записей; context последним — он ссылается на остальные
+    pub const KINDS: &'static [&'static str] = &["category", "cause", "develop", "effect", "forma", "impact_type", "context"];
+
+    /// Виды с обязательным признаком quality
+    pub const QUALITY_KINDS: &'static [&'static str] = &["cause", "develop", "effect", "forma"];
+
+    /// Id новой записи выводится из содержимого, чтобы правки разных узлов не сталкивались.
+    /// Значения от 2^40 не пересекаются с id начального наполнения.
+    pub fn new_id(kind: &str, name: &str, author: &str, created_at: i64) -> i64 {
+        Self::id_from(&format!("{}:{}:{}:{}", kind, name, author, created_at))
+    }
+
+    /// Стабильный id записи пакета без явного id: один и тот же на всех узлах и при повторном импорте
+    pub fn pack_id(pack: &str, kind: &str, key: &str) -> i64 {
+        Self::id_from(&format!("pack:{}:{}:{}", pack, kind, key))
+    }
+
+    fn id_from(seed: &str) -> i64 {
+        let h = blake3::hash(seed.as_bytes());
+        let mut b = [0u8; 8];
+        b.copy_from_slice(&h.as_bytes()[..8]);
+        ((u64::from_le_bytes(b) >> 24) | (1 << 40)) as i64
+    }
+
+    /// Сообщение, которое подписывает updated_by
+    pub fn signing_message(&self) -> String {
+        let id = |v: Option<i64>| v.map(|x| x.to_string()).unwrap_or_default();
+        format!(
+            "kb:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}",
+            self.kind,
+            self.id,
+            self.name,
+            self.description.as_deref().unwrap_or(""),
+            self.quality.map(|q| if q { "1" } else { "0" }).unwrap_or(""),
+            id(self.category_id),
+            id(self.forma_id),
+            id(self.cause_id),
+            id(self.develop_id),
+            id(self.effect_id),
+            self.updated_at,
+            id(self.deprecated_at),
+            self.updated_by.as_deref().unwrap_or("")
+        )
+    }
+}
+
+/// Подписанное доказательство к событию или утверждению: файл или ссылка.
+/// Содержимое хранится в локальном хранилище блобов по SHA-256, пирам — только метаданные;
+/// сами блобы пиры забирают по запросу.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Evidence {
+    pub id: String,
+    pub target_table: String, // truth_events | statements
+    pub target_uid: String,
+    pub kind: String, // file | url
+    #[serde(default)]
+    pub sha256: Option<String>, // hex SHA-256 содержимого; у ссылки — если содержимое сохранено
+    pub name: String, // имя файла или URL
+    #[serde(default)]
+    pub media_type: Option<String>,
+    #[serde(default)]
+    pub size: Option<i64>,
+    #[serde(default)]
+    pub note: Option<String>,
+    pub author: String, // публичный ключ подписавшего (hex)
+    pub created_at: i64,
+    pub signature: String, // подпись author над signing_message()
+}
+
+impl Evidence {
+    /// Таблицы, к записям которых прикрепляются доказательства
+    pub const TABLES: &'static [&'static str] = &["truth_events", "statements"];
+
+    /// Виды доказательств
+    pub const KINDS: &'static [&'static str] = &["file", "url"];
+
+    /// Создать неподписанное доказательство; id выводится из содержимого
+    pub fn new(
+        target_table: &str,
+        target_uid: &str,
+        kind: &str,
+        sha256: Option<String>,
+        name: &str,
+        author: &str,
+        created_at: i64,
+    ) -> Self {
+        let id = blake3::hash(
+            format!(
+                "{}:{}:{}:{}:{}:{}:{}",
+                target_table,
+                target_uid,
+                kind,
+                sha256.as_deref().unwrap_or(""),
+                name,
+                author,
+                created_at
+            )
+            .as_bytes(),
+        )
+        .to_hex()
+        .to_string();
+        Self {
+            id,
+            target_table: target_table.to_string(),
+            target_uid: target_uid.to_string(),
+            kind: kind.to_string(),
+            sha256,
+            name: name.to_string(),
+            media_type: None,
+            size: None,
+            note: None,
+            author: author.to_string(),
+            created_at,
+            signature: String::new(),
+        }
+    }
+
+    /// Сообщение, которое подписывает author
+    pub fn signing_message(&self) -> String {
+        format!(
+            "evidence:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}:{}",
+            self.id,
+            self.target_table,
+            self.target_uid,
+            self.kind,
+            self.sha256.as_deref().unwrap_or(""),
+            self.name,
+            self.media_type.as_deref().unwrap_or(""),
+            self.size.map(|s| s.to_string()).unwrap_or_default(),
+            self.note.as_deref().unwrap_or(""),
+            self.author,
+            self.created_at
+        )
+    }
+}
+
+/// Подписанная связь между событиями: source — дубликат, опровержение, подтверждение
+/// или новая версия target. Дубликаты сливаются в каноническое событие.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EventRelation {
+    pub id: String,
+    pub source_uid: String,
+    pub target_uid: String,
+    pub relation: String, // duplicate_of | contradicts | supports | supersedes
+    #[serde(default)]
+    pub note: Option<String>,
+    pub author: String, // публичный ключ подписавшего (hex)
+    pub created_at: i64,
+    pub signature: String, // подпись author над signing_message()
+}
+
+impl EventRelation {
+    /// Виды связей
+    pub const RELATIONS: &'static [&'static str] = &["duplicate_of", "contradicts", "supports", "supersedes"];
+
+    /// Связи, которые вправе объявить только автор source или администратор
+    pub const OWNER_RELATIONS: &'static [&'static str] = &["duplicate_of", "supersedes"];
+
+    /// Создать неподписанную связь; id выводится из содержимого
+    pub fn new(source_uid: &str, target_uid: &str, relation: &str, author: &str, created_at: i64) -> Self {
+        let id = blake3::hash(format!("{}:{}:{}:{}:{}", source_uid, target_uid, relation, author, created_at).as_bytes())
+            .to_hex()
+            .to_string();
+        Self {
+            id,
+            source_uid: source_uid.to_string(),
+            target_uid: target_uid.to_string(),
+            relation: relation.to_string(),
+            note: None,
+            author: author.to_string(),
+            created_at,
+            signature: String::new(),
+        }
+    }
+
+    /// Сообщение, которое подписывает author
+    pub fn signing_message(&self) -> String {
+        format!(
+            "event_relation:{}:{}:{}:{}:{}:{}:{}",
+            self.id,
+            self.source_uid,
+            self.target_uid,
+            self.relation,
+            self.note.as_deref().unwrap_or(""),
+            self.author,
+            self.created_at
+        )
+    }
+}
+
+/// Запись журнала аудита: изменение строки таблицы, звено цепочки хешей
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditEntry {
+    pub seq: i64,
+    pub ts: i64,
+    pub actor: String, // публичный ключ инициатора (hex) или "local"
+    pub op: String,    // insert | update | delete
+    pub table_name: String,
+    pub record_id: String,
+    pub before_hash: Option<String>, // хеш строки до изменения; None — строки не было
+    pub after_hash: Option<String>,  // хеш строки после изменения; None — строка удалена
+    pub prev_hash: String,           // entry_hash предыдущей записи (AuditEntry::GENESIS для первой)
+    pub entry_hash: String,
+}
+
+impl AuditEntry {
+    /// prev_hash первой записи
+    pub const GENESIS: &'static str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+    /// Хеш записи вместе с prev_hash — то, что хранится в entry_hash
+    pub fn compute_hash(&self) -> String {
+        let msg = format!(
+            "audit:{}:{}:{}:{}:{}:{}:{}:{}:{}",
+            self.prev_hash,
+            self.seq,
+            self.ts,
+            self.actor,
+            self.op,
+            self.table_name,
+            self.record_id,
+            self.before_hash.as_deref().unwrap_or(""),
+            self.after_hash.as_deref().unwrap_or("")
+        );
+        blake3::hash(msg.as_bytes()).to_hex().to_string()
+    }
+}
+
+/// Подписанная ключом узла отметка головы журнала аудита
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditCheckpoint {
+    pub seq: i64,
+    pub entry_hash: String,
+    pub created_at: i64,
+    pub public_key: String,
+    pub signature: String, // подпись public_key над signing_message()
+}
+
+impl AuditCheckpoint {
+    pub fn signing_message(&self) -> String {
+        format!("audit-checkpoint:{}:{}:{}", self.seq, self.entry_hash, self.created_at)
+    }
+}
+
+/// Найденная аномалия доверия (таблица alerts)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Alert {
+    pub id: String,
+    pub kind: String,         // collusion_ring | trust_spike | reciprocal_loop
+    pub subject: String,      // основной узел (автор, узел со скачком, участник цикла)
+    pub related: Vec<String>, // прочие вовлечённые узлы
+    pub severity: f32,        // 0..1
+    pub details: String,
+    pub detected_at: i64,
+    pub acknowledged_at: Option<i64>,
+}
+
+/// Результат полнотекстового поиска (события, утверждения, обоснования оценок)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SearchHit {
+    pub kind: String,             // event | statement | judgment
+    pub id: String,
+    pub event_id: Option<String>, // событие, к которому относится утверждение/оценка
+    pub snippet: String,          // фрагмент с совпадением, термины в [скобках]
+    pub rank: f64,                // -bm25: больше — релевантнее
+    pub context_id: Option<i64>,
+    pub timestamp: i64,
+    pub author: Option<String>,   // public_key автора
+    pub detected: Option<bool>,
+}
+
 /// Метрики узла для мониторинга сети
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NodeMetrics {
@@ -216,6 +726,17 @@ pub struct NodeMetrics {
     pub propagation_priority: f32, // 0.0..1.0 — адаптивный приоритет распространения
 }
 
+/// Точка временного ряда доверия узла (trust_history)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustHistoryEntry {
+    pub id: i64,
+    pub node_id: String,
+    pub trust_score: f32, // -1.0 .. 1.0
+    pub source: String,   // recalc | merge
+    pub sender: Option<String>, // узел, чьи оценки привели к изменению (для merge)
+    pub recorded_at: i64,
+}
+
 /// Узел графа для визуализации
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphNode {
@@ -225,6 +746,10 @@ pub struct GraphNode {
     pub last_seen: Option<i64>,
     pub relay_success_rate: Option<f32>,
     pub quality_index: f32,
+    #[serde(default)]
+    pub group_id: Option<String>, // сообщество (group_ratings.group_id)
+    #[serde(default)]
+    pub alerts: Vec<String>, // виды неподтверждённых аномалий с участием узла
 }
 
 /// Ребро графа между валидатором (source) и автором события (target)
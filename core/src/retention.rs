@@ -0,0 +1,414 @@
+use crate::audit;
+use crate::export::{row_to_json, ExportHeader, FORMAT_NAME, FORMAT_VERSION};
+use crate::trust_propagation::TRUST_STABILITY_WINDOW;
+use crate::CoreError;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const DAY: i64 = 86_400;
+
+/// Таблица с ограниченным сроком хранения
+struct RetainedTable {
+    name: &'static str,
+    /// Колонка времени (unix, с)
+    time: &'static str,
+    /// Прореживание до одной записи в сутки: None — не поддерживается,
+    /// Some("") — по всей таблице, Some(col) — отдельно для каждого значения col
+    daily_by: Option<&'static str>,
+    /// Столько последних записей (в пределах значения daily_by) прореживание не трогает
+    keep_latest: usize,
+}
+
+const TABLES: &[RetainedTable] = &[
+    RetainedTable { name: "sync_log", time: "created_at", daily_by: None, keep_latest: 0 },
+    RetainedTable { name: "sync_logs", time: "timestamp", daily_by: None, keep_latest: 0 },
+    RetainedTable { name: "peer_history", time: "last_sync", daily_by: None, keep_latest: 0 },
+    RetainedTable { name: "progress_metrics", time: "timestamp", daily_by: Some(""), keep_latest: 0 },
+    RetainedTable { name: "reputation_history", time: "updated_at", daily_by: Some("participant_id"), keep_latest: 0 },
+    RetainedTable { name: "tombstones", time: "deleted_at", daily_by: None, keep_latest: 0 },
+    // Окно стабильности доверия каждого узла остаётся целиком
+    RetainedTable { name: "trust_history", time: "recorded_at", daily_by: Some("node_id"), keep_latest: TRUST_STABILITY_WINDOW },
+    RetainedTable { name: "alerts", time: "detected_at", daily_by: None, keep_latest: 0 },
+];
+
+/// Политика хранения таблицы; None — ограничения нет
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    pub table: String,
+    /// Удалять записи старше N дней
+    pub max_age_days: Option<u32>,
+    /// Оставлять не больше N самых новых записей
+    pub max_rows: Option<u64>,
+    /// Записи старше N дней прореживать до последней за сутки
+    pub downsample_after_days: Option<u32>,
+}
+
+/// Политика по умолчанию; строка в retention_policies заменяет политику таблицы целиком
+pub fn default_policy(table: &str) -> RetentionPolicy {
+    let (max_age_days, max_rows, downsample_after_days) = match table {
+        "sync_log" => (Some(90), None, None),
+        "sync_logs" => (Some(30), Some(10_000), None),
+        "peer_history" => (Some(180), None, None),
+        "progress_metrics" => (None, None, Some(7)),
+        "reputation_history" => (None, None, Some(30)),
+        // После удаления отметки пир, не видевший её, может вернуть запись
+        "tombstones" => (Some(90), None, None),
+        // Сырые точки нужны поиску скачков доверия (anomaly::ANOMALY_LOOKBACK_SECS)
+        "trust_history" => (None, None, Some(7)),
+        "alerts" => (Some(180), None, None),
+        _ => (None, None, None),
+    };
+    RetentionPolicy { table: table.to_string(), max_age_days, max_rows, downsample_after_days }
+}
+
+fn retained(table: &str) -> Result<&'static RetainedTable, CoreError> {
+    TABLES.iter().find(|t| t.name == table).ok_or_else(|| {
+        let names: Vec<_> = TABLES.iter().map(|t| t.name).collect();
+        CoreError::InvalidArg(format!("no retention for table {}; expected one of {}", table, names.join(", ")))
+    })
+}
+
+/// Действующие политики всех таблиц (переопределённые в БД или по умолчанию)
+pub fn policies(conn: &Connection) -> Result<Vec<RetentionPolicy>, CoreError> {
+    TABLES.iter().map(|t| policy(conn, t.name)).collect()
+}
+
+/// Действующая политика таблицы
+pub fn policy(conn: &Connection, table: &str) -> Result<RetentionPolicy, CoreError> {
+    retained(table)?;
+    let row = conn
+        .prepare_cached("SELECT max_age_days, max_rows, downsample_after_days FROM retention_policies WHERE table_name = ?1")?
+        .query_row(params![table], |r| Ok((r.get(0)?, r.get::<_, Option<i64>>(1)?, r.get(2)?)))
+        .optional()?;
+    Ok(match row {
+        Some((max_age_days, max_rows, downsample_after_days)) => RetentionPolicy {
+            table: table.to_string(),
+            max_age_days,
+            max_rows: max_rows.map(|n| n as u64),
+            downsample_after_days,
+        },
+        None => default_policy(table),
+    })
+}
+
+/// Сохранить политику таблицы
+pub fn set_policy(conn: &Connection, policy: &RetentionPolicy) -> Result<(), CoreError> {
+    let spec = retained(&policy.table)?;
+    if policy.downsample_after_days.is_some() && spec.daily_by.is_none() {
+        return Err(CoreError::InvalidArg(format!("{} does not support downsampling", policy.table)));
+    }
+    audit::track(conn, "retention_policies", "table_name", &policy.table, || {
+        conn.execute(
+            "INSERT INTO retention_policies (table_name, max_age_days, max_rows, downsample_after_days, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(table_name) DO UPDATE SET max_age_days = excluded.max_age_days, max_rows = excluded.max_rows,
+                 downsample_after_days = excluded.downsample_after_days, updated_at = excluded.updated_at",
+            params![
+                policy.table,
+                policy.max_age_days,
+                policy.max_rows.map(|n| n as i64),
+                policy.downsample_after_days,
+                chrono::Utc::now().timestamp()
+            ],
+        )?;
+        Ok(())
+    })
+}
+
+/// Вернуть таблице политику по умолчанию
+pub fn reset_policy(conn: &Connection, table: &str) -> Result<(), CoreError> {
+    retained(table)?;
+    audit::track(conn, "retention_policies", "table_name", table, || {
+        conn.execute("DELETE FROM retention_policies WHERE table_name = ?1", params![table])?;
+        Ok(())
+    })
+}
+
+/// Параметры прогона очистки
+#[derive(Debug, Clone, Default)]
+pub struct PruneOptions {
+    /// Момент отсчёта возраста (unix, с); None — текущее время
+    pub now: Option<i64>,
+    /// Только посчитать: удаления выполняются в транзакции, которая откатывается
+    pub dry_run: bool,
+    /// Каталог архива: удалённые строки пишутся в prune-<время>.ndjson.gz (формат NDJSON-выгрузки)
+    pub archive_dir: Option<PathBuf>,
+    /// Очистить только эту таблицу
+    pub table: Option<String>,
+}
+
+/// Удалено строк в таблице по каждому правилу
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TablePrune {
+    pub table: String,
+    pub expired: usize,
+    pub downsampled: usize,
+    pub overflow: usize,
+}
+
+impl TablePrune {
+    pub fn total(&self) -> usize {
+        self.expired + self.downsampled + self.overflow
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PruneReport {
+    pub dry_run: bool,
+    /// Файл архива, если что-то было удалено и задан archive_dir
+    pub archive: Option<PathBuf>,
+    pub tables: Vec<TablePrune>,
+}
+
+/// Архив создаётся при первой удалённой строке
+struct Archive {
+    dir: PathBuf,
+    path: Option<PathBuf>,
+    out: Option<BufWriter<GzEncoder<fs::File>>>,
+}
+
+impl Archive {
+    fn writer(&mut self, conn: &Connection) -> Result<&mut BufWriter<GzEncoder<fs::File>>, CoreError> {
+        if self.out.is_none() {
+            fs::create_dir_all(&self.dir)?;
+            let now = chrono::Utc::now();
+            let path = self.dir.join(format!("prune-{}.ndjson.gz", now.format("%Y%m%d-%H%M%S-%3f")));
+            let mut out = BufWriter::new(GzEncoder::new(fs::File::create(&path)?, Compression::default()));
+            let header = ExportHeader {
+                format: FORMAT_NAME.to_string(),
+                version: FORMAT_VERSION,
+                schema_version: crate::migrations::current_version(conn)?,
+                exported_at: now.timestamp(),
+            };
+            writeln!(out, "{}", serde_json::json!({ "header": header }))?;
+            self.path = Some(path);
+            self.out = Some(out);
+        }
+        Ok(self.out.as_mut().expect("archive writer"))
+    }
+
+    fn finish(self) -> Result<Option<PathBuf>, CoreError> {
+        if let Some(out) = self.out {
+            out.into_inner().map_err(|e| e.into_error())?.finish()?.sync_all()?;
+        }
+        Ok(self.path)
+    }
+
+    fn discard(self) {
+        if let Some(path) = self.path {
+            drop(self.out);
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Выполнить DELETE … RETURNING *, записывая удалённые строки в архив
+fn delete_rows(conn: &Connection, table: &str, sql: &str, arg: i64, archive: &mut Option<Archive>) -> Result<usize, CoreError> {
+    let mut stmt = conn.prepare(sql)?;
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let mut rows = stmt.query(params![arg])?;
+    let mut n = 0;
+    while let Some(row) = rows.next()? {
+        if let Some(archive) = archive.as_mut() {
+            let obj = row_to_json(conn, table, &columns, row)?;
+            writeln!(archive.writer(conn)?, "{}", serde_json::json!({ "table": table, "row": obj }))?;
+        }
+        n += 1;
+    }
+    Ok(n)
+}
+
+fn prune_table(conn: &Connection, spec: &RetainedTable, policy: &RetentionPolicy, now: i64, archive: &mut Option<Archive>) -> Result<TablePrune, CoreError> {
+    let (t, time) = (spec.name, spec.time);
+    let mut report = TablePrune { table: t.to_string(), ..Default::default() };
+    if let Some(days) = policy.max_age_days {
+        let sql = format!("DELETE FROM {t} WHERE {time} < ?1 RETURNING *");
+        report.expired = delete_rows(conn, t, &sql, now - days as i64 * DAY, archive)?;
+    }
+    if let (Some(days), Some(by)) = (policy.downsample_after_days, spec.daily_by) {
+        let partition = if by.is_empty() { format!("{time} / {DAY}") } else { format!("{by}, {time} / {DAY}") };
+        let protect = match spec.keep_latest {
+            0 => String::new(),
+            keep => {
+                let over = if by.is_empty() { String::new() } else { format!("PARTITION BY {by} ") };
+                format!(
+                    " AND rowid NOT IN (SELECT rowid FROM (
+                         SELECT rowid, ROW_NUMBER() OVER ({over}ORDER BY {time} DESC, rowid DESC) AS recent FROM {t}
+                     ) WHERE recent <= {keep})"
+                )
+            }
+        };
+        let sql = format!(
+            "DELETE FROM {t} WHERE rowid IN (
+                 SELECT rowid FROM (
+                     SELECT rowid, ROW_NUMBER() OVER (PARTITION BY {partition} ORDER BY {time} DESC, rowid DESC) AS rn
+                     FROM {t} WHERE {time} < ?1{protect}
+                 ) WHERE rn > 1
+             ) RETURNING *"
+        );
+        report.downsampled = delete_rows(conn, t, &sql, now - days as i64 * DAY, archive)?;
+    }
+    if let Some(max) = policy.max_rows {
+        let sql = format!(
+            "DELETE FROM {t} WHERE rowid IN (SELECT rowid FROM {t} ORDER BY {time} DESC, rowid DESC LIMIT -1 OFFSET ?1) RETURNING *"
+        );
+        report.overflow = delete_rows(conn, t, &sql, max as i64, archive)?;
+    }
+    Ok(report)
+}
+
+/// Применить политики хранения в одной транзакции. Архив дописывается и сбрасывается на диск
+/// до фиксации удаления; при ошибке БД не меняется, а неполный архив удаляется.
+pub fn prune(conn: &mut Connection, opts: &PruneOptions) -> Result<PruneReport, CoreError> {
+    let now = opts.now.unwrap_or_else(|| chrono::Utc::now().timestamp());
+    let specs: Vec<&RetainedTable> = match &opts.table {
+        Some(t) => vec![retained(t)?],
+        None => TABLES.iter().collect(),
+    };
+    let tx = conn.transaction()?;
+    let mut archive = match (&opts.archive_dir, opts.dry_run) {
+        (Some(dir), false) => Some(Archive { dir: dir.clone(), path: None, out: None }),
+        _ => None,
+    };
+    let mut tables = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let res = policy(&tx, spec.name).and_then(|p| prune_table(&tx, spec, &p, now, &mut archive));
+        match res {
+            Ok(r) => tables.push(r),
+            Err(e) => {
+                if let Some(a) = archive {
+                    a.discard();
+                }
+                return Err(e);
+            }
+        }
+    }
+    if opts.dry_run {
+        tx.rollback()?;
+        return Ok(PruneReport { dry_run: true, archive: None, tables });
+    }
+    let archive = archive.map(Archive::finish).transpose()?.flatten();
+    tx.commit()?;
+    Ok(PruneReport { dry_run: false, archive, tables })
+}
+
+/// Прочитать архив очистки (для проверки и восстановления через import)
+pub fn read_archive(path: &Path) -> Result<Vec<serde_json::Value>, CoreError> {
+    use std::io::BufRead;
+    let reader = std::io::BufReader::new(flate2::read::GzDecoder::new(fs::File::open(path)?));
+    reader.lines().map(|l| Ok(serde_json::from_str(&l?)?)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage;
+
+    fn add_log(conn: &Connection, ts: i64) {
+        conn.execute(
+            "INSERT INTO sync_logs (timestamp, peer_url, mode, status, details) VALUES (?1, 'p', 'full', 'success', '')",
+            params![ts],
+        )
+        .unwrap();
+    }
+
+    fn add_metric(conn: &Connection, ts: i64) {
+        conn.execute(
+            "INSERT INTO progress_metrics (timestamp, total_events, total_events_group, total_positive_impact, total_positive_impact_group,
+                 total_negative_impact, total_negative_impact_group, trend, trend_group) VALUES (?1, 0, 0, 0, 0, 0, 0, 0, 0)",
+            params![ts],
+        )
+        .unwrap();
+    }
+
+    fn count(conn: &Connection, table: &str) -> i64 {
+        conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |r| r.get(0)).unwrap()
+    }
+
+    #[test]
+    fn policies_default_and_override() {
+        let conn = storage::open_db(":memory:").unwrap();
+        assert_eq!(policy(&conn, "sync_logs").unwrap().max_rows, Some(10_000));
+        let custom = RetentionPolicy { table: "sync_logs".into(), max_age_days: None, max_rows: Some(5), downsample_after_days: None };
+        set_policy(&conn, &custom).unwrap();
+        assert_eq!(policy(&conn, "sync_logs").unwrap(), custom);
+        reset_policy(&conn, "sync_logs").unwrap();
+        assert_eq!(policy(&conn, "sync_logs").unwrap().max_age_days, Some(30));
+        assert!(set_policy(&conn, &RetentionPolicy { downsample_after_days: Some(1), ..custom }).is_err());
+        assert_eq!(policy(&conn, "tombstones").unwrap().max_age_days, Some(90));
+        assert_eq!(policy(&conn, "alerts").unwrap().max_age_days, Some(180));
+        assert!(policy(&conn, "truth_events").is_err());
+    }
+
+    #[test]
+    fn prune_ages_downsamples_and_archives() {
+        let mut conn = storage::open_db(":memory:").unwrap();
+        let now = 400 * DAY;
+        // Журнал: 2 записи старше 30 дней, 3 свежие
+        for ts in [now - 40 * DAY, now - 31 * DAY, now - DAY, now - 2, now - 1] {
+            add_log(&conn, ts);
+        }
+        // Метрики: по 3 записи в 2 старых сутках и 2 свежие
+        for day in [10, 11] {
+            for h in 0..3 {
+                add_metric(&conn, day * DAY + h * 3600);
+            }
+        }
+        add_metric(&conn, now - 3600);
+        add_metric(&conn, now - 60);
+        set_policy(&conn, &RetentionPolicy { table: "sync_logs".into(), max_age_days: Some(30), max_rows: Some(2), downsample_after_days: None }).unwrap();
+
+        let dry = prune(&mut conn, &PruneOptions { now: Some(now), dry_run: true, ..Default::default() }).unwrap();
+        let logs = dry.tables.iter().find(|t| t.table == "sync_logs").unwrap();
+        assert_eq!((logs.expired, logs.overflow), (2, 1));
+        assert_eq!(dry.tables.iter().find(|t| t.table == "progress_metrics").unwrap().downsampled, 4);
+        assert_eq!(count(&conn, "sync_logs"), 5);
+
+        let dir = std::env::temp_dir().join(format!("truth-prune-{}", uuid::Uuid::new_v4()));
+        let report = prune(&mut conn, &PruneOptions { now: Some(now), archive_dir: Some(dir.clone()), ..Default::default() }).unwrap();
+        assert_eq!(report.tables.iter().map(TablePrune::total).sum::<usize>(), 7);
+        assert_eq!(count(&conn, "sync_logs"), 2);
+        assert_eq!(count(&conn, "progress_metrics"), 4);
+        let kept: Vec<i64> = conn.prepare("SELECT timestamp FROM progress_metrics ORDER BY timestamp").unwrap()
+            .query_map([], |r| r.get(0)).unwrap().map(Result::unwrap).collect();
+        assert_eq!(kept[..2], [10 * DAY + 7200, 11 * DAY + 7200]);
+
+        let lines = read_archive(report.archive.as_ref().unwrap()).unwrap();
+        assert_eq!(lines[0]["header"]["format"], FORMAT_NAME);
+        assert_eq!(lines.len(), 8);
+
+        // Повторный прогон ничего не удаляет и не создаёт пустой архив
+        let again = prune(&mut conn, &PruneOptions { now: Some(now), archive_dir: Some(dir.clone()), ..Default::default() }).unwrap();
+        assert!(again.archive.is_none());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn trust_history_is_downsampled_per_node_outside_the_stability_window() {
+        let mut conn = storage::open_db(":memory:").unwrap();
+        let now = 400 * DAY;
+        // У каждого узла 40 старых точек через 6 часов (10 суток) и пара у другого узла
+        let points: Vec<i64> = (0..40).map(|i| 300 * DAY + i * 6 * 3600).collect();
+        for &at in &points {
+            storage::record_trust_history(&conn, "n1", 0.1, "merge", None, at).unwrap();
+        }
+        for at in [300 * DAY, 300 * DAY + 60] {
+            storage::record_trust_history(&conn, "n2", 0.1, "merge", None, at).unwrap();
+        }
+
+        let report = prune(&mut conn, &PruneOptions { now: Some(now), table: Some("trust_history".into()), ..Default::default() }).unwrap();
+        let older = &points[..points.len() - TRUST_STABILITY_WINDOW];
+        let older_days: std::collections::HashSet<i64> = older.iter().map(|t| t / DAY).collect();
+        assert_eq!(report.tables[0].downsampled, older.len() - older_days.len());
+        let n1 = storage::load_trust_history(&conn, "n1", Some(1000)).unwrap();
+        assert_eq!(n1.len(), TRUST_STABILITY_WINDOW + older_days.len());
+        assert!(n1.iter().take(TRUST_STABILITY_WINDOW).map(|h| h.recorded_at).eq(points.iter().rev().take(TRUST_STABILITY_WINDOW).copied()));
+        // Короткая история другого узла целиком в окне
+        assert_eq!(storage::load_trust_history(&conn, "n2", None).unwrap().len(), 2);
+    }
+}
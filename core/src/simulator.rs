@@ -0,0 +1,479 @@
+use crate::trust_propagation::{
+    blend_trust_weighted, compute_local_trust, compute_propagation_priority_with, compute_quality_index_with,
+    compute_trust_stability, compute_windowed_activity, sender_remote_weight_with, TrustParams,
+};
+use crate::CoreError;
+use rand::{Rng, SeedableRng};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Порог изменения trust за раунд, ниже которого сеть считается сошедшейся
+pub const SIM_CONVERGENCE_EPSILON: f32 = 1e-4;
+/// Число корзин гистограммы trust на отрезке [-1, 1]
+pub const SIM_HISTOGRAM_BINS: usize = 10;
+/// Глубина выборки отметок активности из БД (окно активности задаётся параметрами прогона)
+pub const SIM_ACTIVITY_LOOKBACK_SECS: i64 = 90 * 86_400;
+/// Условный момент «сейчас» синтетической сети
+const SIM_SYNTHETIC_NOW: i64 = 1_700_000_000;
+
+/// Наблюдение валидатора за автором: суммарный вес положительных и отрицательных оценок его событий
+/// (|степень| × уверенность, как в recalc_ratings)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SimObservation {
+    pub validator: String,
+    pub author: String,
+    pub positive: f32,
+    pub negative: f32,
+}
+
+/// Сеть для симуляции: каждый валидатор — узел со своим видом на доверие,
+/// построенным по собственным наблюдениям, и обменивающийся им с остальными.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SimNetwork {
+    pub observations: Vec<SimObservation>,
+    /// relay_success_rate узлов (по умолчанию 0)
+    pub relay_success_rate: BTreeMap<String, f32>,
+    /// Отметки времени действий узлов для оценки активности на момент as_of
+    #[serde(default)]
+    pub activity_timestamps: BTreeMap<String, Vec<i64>>,
+    #[serde(default)]
+    pub as_of: i64,
+}
+
+/// Параметры синтетической сети
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyntheticSpec {
+    pub nodes: usize,
+    /// Доля нечестных узлов: их события чаще ложные, а оценки инвертированы
+    pub dishonest_share: f32,
+    /// Вероятность того, что узел оценивает события данного автора
+    pub density: f32,
+    /// Оценок на пару валидатор→автор
+    pub validations_per_edge: u32,
+    pub seed: u64,
+}
+
+impl Default for SyntheticSpec {
+    fn default() -> Self {
+        Self { nodes: 20, dishonest_share: 0.2, density: 0.3, validations_per_edge: 5, seed: 42 }
+    }
+}
+
+impl SimNetwork {
+    /// Снимок сети из БД: наблюдения по impact × truth_events (веса оценок), relay_success_rate из node_metrics
+    pub fn from_db(conn: &Connection) -> Result<Self, CoreError> {
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT im.public_key, te.public_key,
+                   SUM(MAX(im.magnitude, 0.0) * COALESCE(im.confidence, 1.0)),
+                   SUM(MAX(-im.magnitude, 0.0) * COALESCE(im.confidence, 1.0))
+            FROM impact im
+            JOIN truth_events te ON im.event_id = te.uid
+            WHERE im.public_key IS NOT NULL AND te.public_key IS NOT NULL AND im.public_key <> te.public_key
+            GROUP BY im.public_key, te.public_key
+            ORDER BY im.public_key, te.public_key
+            "#,
+        )?;
+        let rows = stmt.query_map(params![], |r| {
+            Ok(SimObservation {
+                validator: r.get(0)?,
+                author: r.get(1)?,
+                positive: r.get::<_, f64>(2)? as f32,
+                negative: r.get::<_, f64>(3)? as f32,
+            })
+        })?;
+        let mut observations = Vec::new();
+        for r in rows { observations.push(r?); }
+
+        let relay_success_rate = crate::storage::load_all_node_metrics(conn)?
+            .into_iter()
+            .map(|m| (m.pubkey, m.relay_success_rate))
+            .collect();
+        // Отметки активности берутся с запасом: окно задаётся параметрами прогона
+        let as_of = chrono::Utc::now().timestamp();
+        let activity_timestamps = crate::storage::load_activity_timestamps(conn, as_of - SIM_ACTIVITY_LOOKBACK_SECS, None)?
+            .into_iter()
+            .collect();
+        Ok(Self { observations, relay_success_rate, activity_timestamps, as_of })
+    }
+
+    /// Синтетическая сеть с детерминированным генератором (одинаковый seed — одинаковая сеть)
+    pub fn synthetic(spec: &SyntheticSpec) -> Self {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(spec.seed);
+        let mut activity_rng = rand::rngs::StdRng::seed_from_u64(spec.seed.wrapping_add(1));
+        let ids: Vec<String> = (0..spec.nodes).map(|i| format!("sim-{:03}", i)).collect();
+        let dishonest = ((spec.nodes as f32) * spec.dishonest_share.clamp(0.0, 1.0)).round() as usize;
+        // Нечестные узлы — последние в списке
+        let honest = |i: usize| i < spec.nodes - dishonest;
+
+        let mut observations = Vec::new();
+        let mut relay_success_rate = BTreeMap::new();
+        let mut activity_timestamps = BTreeMap::new();
+        let as_of = SIM_SYNTHETIC_NOW;
+        for (v, validator) in ids.iter().enumerate() {
+            relay_success_rate.insert(validator.clone(), if honest(v) { rng.gen_range(0.7..1.0) } else { rng.gen_range(0.2..0.6) });
+            // Действия за последние две недели: часть узлов активна лишь давно.
+            // Отдельный генератор, чтобы активность не меняла граф наблюдений
+            let actions: Vec<i64> = (0..activity_rng.gen_range(0..12))
+                .map(|_| as_of - activity_rng.gen_range(0..14 * 86_400))
+                .collect();
+            activity_timestamps.insert(validator.clone(), actions);
+            for (a, author) in ids.iter().enumerate() {
+                if v == a || rng.gen::<f32>() >= spec.density {
+                    continue;
+                }
+                let truth_rate: f32 = if honest(a) { 0.9 } else { 0.3 };
+                let (mut positive, mut negative) = (0.0, 0.0);
+                for _ in 0..spec.validations_per_edge {
+                    let is_true = rng.gen::<f32>() < truth_rate;
+                    // Нечестный валидатор поддерживает своих и топит остальных
+                    let agree = if honest(v) { is_true } else { !honest(a) };
+                    if agree { positive += 1.0 } else { negative += 1.0 }
+                }
+                observations.push(SimObservation { validator: validator.clone(), author: author.clone(), positive, negative });
+            }
+        }
+        Self { observations, relay_success_rate, activity_timestamps, as_of }
+    }
+
+    /// Узлы, обменивающиеся оценками (валидаторы), в лексикографическом порядке
+    pub fn peers(&self) -> Vec<String> {
+        let mut out: Vec<String> = self.observations.iter().map(|o| o.validator.clone()).collect();
+        out.sort();
+        out.dedup();
+        out
+    }
+
+    /// Все узлы сети (валидаторы и авторы)
+    pub fn nodes(&self) -> Vec<String> {
+        let mut out: Vec<String> = self
+            .observations
+            .iter()
+            .flat_map(|o| [o.validator.clone(), o.author.clone()])
+            .collect();
+        out.sort();
+        out.dedup();
+        out
+    }
+}
+
+/// Настройки прогона
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimConfig {
+    pub rounds: usize,
+    /// Каждые N раундов узлы пересчитывают рейтинги с нуля по своим наблюдениям (как recalc_ratings)
+    pub recalc_every: Option<usize>,
+    pub epsilon: f32,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self { rounds: 20, recalc_every: None, epsilon: SIM_CONVERGENCE_EPSILON }
+    }
+}
+
+/// Метрики одного раунда
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimRound {
+    pub round: usize,
+    /// Максимальное изменение trust в видах узлов за раунд
+    pub max_delta: f32,
+    /// Среднее по узлам стандартное отклонение trust между видами разных узлов
+    pub disagreement: f32,
+}
+
+/// Итоговый рейтинг узла (усреднение видов всех узлов, знающих о нём)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimNodeResult {
+    pub node_id: String,
+    pub trust: f32,
+    pub quality: f32,
+    pub priority: f32,
+    pub rank: usize,
+}
+
+/// Распределение итогового trust
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustDistribution {
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+    pub std_dev: f32,
+    /// Число узлов в каждой из SIM_HISTOGRAM_BINS равных корзин на [-1, 1]
+    pub histogram: Vec<usize>,
+}
+
+/// Результат прогона симуляции
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimReport {
+    pub params: TrustParams,
+    pub rounds: Vec<SimRound>,
+    /// Первый раунд, на котором max_delta < epsilon
+    pub converged_at: Option<usize>,
+    pub nodes: Vec<SimNodeResult>,
+    pub distribution: TrustDistribution,
+}
+
+/// Изменение места узла между двумя прогонами
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RankChange {
+    pub node_id: String,
+    pub baseline_rank: usize,
+    pub alternative_rank: usize,
+    pub baseline_trust: f32,
+    pub alternative_trust: f32,
+}
+
+impl RankChange {
+    /// Положительное значение — узел поднялся
+    pub fn shift(&self) -> i64 {
+        self.baseline_rank as i64 - self.alternative_rank as i64
+    }
+}
+
+/// Прогнать сеть через config.rounds раундов обмена оценками с параметрами params.
+/// Раунд: каждый узел сливает виды всех остальных (по состоянию на начало раунда) с весом
+/// sender_remote_weight_with, затем обновляются quality_index и propagation_priority.
+pub fn simulate(network: &SimNetwork, params: &TrustParams, config: &SimConfig) -> Result<SimReport, CoreError> {
+    params.validate()?;
+    let peers = network.peers();
+    let nodes = network.nodes();
+
+    // Собственные наблюдения узла: локальный trust по формуле пересчёта
+    let mut own: HashMap<&str, BTreeMap<String, f32>> = HashMap::new();
+    let mut author_totals: HashMap<&str, (f32, f32)> = HashMap::new();
+    for o in &network.observations {
+        own.entry(o.validator.as_str())
+            .or_default()
+            .insert(o.author.clone(), compute_local_trust(params, o.positive, o.negative, o.positive));
+        let t = author_totals.entry(o.author.as_str()).or_insert((0.0, 0.0));
+        t.0 += o.positive;
+        t.1 += o.negative;
+    }
+    let relay = |n: &str| network.relay_success_rate.get(n).copied().unwrap_or(0.0);
+    let activity: HashMap<&str, f32> = network
+        .activity_timestamps
+        .iter()
+        .map(|(n, stamps)| (n.as_str(), compute_windowed_activity(params, stamps, network.as_of)))
+        .collect();
+    let conflict_free = |n: &str| match author_totals.get(n) {
+        Some((p, q)) if p + q > 0.0 => p / (p + q),
+        _ => 1.0,
+    };
+
+    let mut views: BTreeMap<String, BTreeMap<String, f32>> = peers
+        .iter()
+        .map(|p| (p.clone(), own.get(p.as_str()).cloned().unwrap_or_default()))
+        .collect();
+    let mut quality: HashMap<String, f32> = HashMap::new();
+    let mut priority: HashMap<String, f32> = HashMap::new();
+    let mut history: HashMap<String, Vec<f32>> = HashMap::new();
+
+    let mut rounds = Vec::new();
+    let mut converged_at = None;
+    for round in 1..=config.rounds {
+        let before = views.clone();
+        if let Some(k) = config.recalc_every {
+            if k > 0 && round % k == 0 {
+                for (p, view) in views.iter_mut() {
+                    *view = own.get(p.as_str()).cloned().unwrap_or_default();
+                }
+            }
+        }
+        for (peer, view) in views.iter_mut() {
+            for (sender, sender_view) in &before {
+                if sender == peer {
+                    continue;
+                }
+                let sender_trust = view.get(sender).copied().unwrap_or(0.0);
+                let sender_quality = quality.get(sender).copied().unwrap_or(0.0);
+                let Some(w) = sender_remote_weight_with(params, sender_trust, sender_quality) else { continue };
+                for (node, remote) in sender_view {
+                    let local = view.get(node).copied().unwrap_or(0.0);
+                    view.insert(node.clone(), blend_trust_weighted(local, *remote, w));
+                }
+            }
+        }
+
+        let consensus = consensus_trust(&views, &nodes);
+        for node in &nodes {
+            let trust = consensus.get(node).copied().unwrap_or(0.0);
+            let h = history.entry(node.clone()).or_default();
+            h.push(trust);
+            let start = h.len().saturating_sub(params.stability_window);
+            let stability = compute_trust_stability(&h[start..]);
+            let q = compute_quality_index_with(params, relay(node), conflict_free(node), stability, quality.get(node).copied());
+            let a = activity.get(node.as_str()).copied().unwrap_or(0.0);
+            let p = compute_propagation_priority_with(params, trust, q, relay(node), a, priority.get(node).copied());
+            quality.insert(node.clone(), q);
+            priority.insert(node.clone(), p);
+        }
+
+        let max_delta = views
+            .iter()
+            .flat_map(|(p, view)| {
+                let prev = &before[p];
+                view.iter().map(move |(n, t)| (t - prev.get(n).copied().unwrap_or(0.0)).abs())
+            })
+            .fold(0.0_f32, f32::max);
+        rounds.push(SimRound { round, max_delta, disagreement: disagreement(&views, &nodes) });
+        if converged_at.is_none() && max_delta < config.epsilon {
+            converged_at = Some(round);
+        }
+    }
+
+    let consensus = consensus_trust(&views, &nodes);
+    let mut results: Vec<SimNodeResult> = nodes
+        .iter()
+        .map(|n| SimNodeResult {
+            node_id: n.clone(),
+            trust: consensus.get(n).copied().unwrap_or(0.0),
+            quality: quality.get(n).copied().unwrap_or(0.0),
+            priority: priority.get(n).copied().unwrap_or(0.0),
+            rank: 0,
+        })
+        .collect();
+    results.sort_by(|a, b| {
+        b.trust
+            .partial_cmp(&a.trust)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.node_id.cmp(&b.node_id))
+    });
+    for (i, r) in results.iter_mut().enumerate() {
+        r.rank = i + 1;
+    }
+    let distribution = trust_distribution(&results);
+
+    Ok(SimReport { params: *params, rounds, converged_at, nodes: results, distribution })
+}
+
+/// Сравнить места узлов между базовым и альтернативным прогоном (только изменившиеся, по убыванию сдвига)
+pub fn compare_ranks(baseline: &SimReport, alternative: &SimReport) -> Vec<RankChange> {
+    let alt: HashMap<&str, &SimNodeResult> = alternative.nodes.iter().map(|n| (n.node_id.as_str(), n)).collect();
+    let mut out: Vec<RankChange> = baseline
+        .nodes
+        .iter()
+        .filter_map(|b| {
+            let a = alt.get(b.node_id.as_str())?;
+            (a.rank != b.rank).then(|| RankChange {
+                node_id: b.node_id.clone(),
+                baseline_rank: b.rank,
+                alternative_rank: a.rank,
+                baseline_trust: b.trust,
+                alternative_trust: a.trust,
+            })
+        })
+        .collect();
+    out.sort_by(|a, b| b.shift().abs().cmp(&a.shift().abs()).then_with(|| a.node_id.cmp(&b.node_id)));
+    out
+}
+
+fn consensus_trust(views: &BTreeMap<String, BTreeMap<String, f32>>, nodes: &[String]) -> HashMap<String, f32> {
+    nodes
+        .iter()
+        .filter_map(|n| {
+            let vals: Vec<f32> = views.values().filter_map(|v| v.get(n).copied()).collect();
+            (!vals.is_empty()).then(|| (n.clone(), vals.iter().sum::<f32>() / vals.len() as f32))
+        })
+        .collect()
+}
+
+fn disagreement(views: &BTreeMap<String, BTreeMap<String, f32>>, nodes: &[String]) -> f32 {
+    let stds: Vec<f32> = nodes
+        .iter()
+        .filter_map(|n| {
+            let vals: Vec<f32> = views.values().filter_map(|v| v.get(n).copied()).collect();
+            if vals.len() < 2 {
+                return None;
+            }
+            let mean = vals.iter().sum::<f32>() / vals.len() as f32;
+            Some((vals.iter().map(|v| (v - mean) * (v - mean)).sum::<f32>() / vals.len() as f32).sqrt())
+        })
+        .collect();
+    if stds.is_empty() { 0.0 } else { stds.iter().sum::<f32>() / stds.len() as f32 }
+}
+
+fn trust_distribution(results: &[SimNodeResult]) -> TrustDistribution {
+    let mut histogram = vec![0; SIM_HISTOGRAM_BINS];
+    if results.is_empty() {
+        return TrustDistribution { min: 0.0, max: 0.0, mean: 0.0, std_dev: 0.0, histogram };
+    }
+    let n = results.len() as f32;
+    let mean = results.iter().map(|r| r.trust).sum::<f32>() / n;
+    let std_dev = (results.iter().map(|r| (r.trust - mean) * (r.trust - mean)).sum::<f32>() / n).sqrt();
+    for r in results {
+        let bin = (((r.trust + 1.0) / 2.0) * SIM_HISTOGRAM_BINS as f32) as usize;
+        histogram[bin.min(SIM_HISTOGRAM_BINS - 1)] += 1;
+    }
+    TrustDistribution {
+        min: results.iter().map(|r| r.trust).fold(f32::MAX, f32::min),
+        max: results.iter().map(|r| r.trust).fold(f32::MIN, f32::max),
+        mean,
+        std_dev,
+        histogram,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synthetic_network_converges_and_separates_dishonest_nodes() {
+        let network = SimNetwork::synthetic(&SyntheticSpec { nodes: 12, dishonest_share: 0.25, density: 0.6, ..Default::default() });
+        // Детерминированная генерация
+        assert_eq!(network.observations, SimNetwork::synthetic(&SyntheticSpec { nodes: 12, dishonest_share: 0.25, density: 0.6, ..Default::default() }).observations);
+
+        let report = simulate(&network, &TrustParams::default(), &SimConfig { rounds: 200, ..Default::default() }).unwrap();
+        assert!(report.converged_at.is_some());
+        assert!(report.rounds.last().unwrap().max_delta < report.rounds[0].max_delta);
+        assert_eq!(report.distribution.histogram.iter().sum::<usize>(), report.nodes.len());
+
+        // Честные узлы (sim-000..sim-008) в среднем выше нечестных (sim-009..sim-011)
+        let avg = |honest: bool| {
+            let v: Vec<f32> = report.nodes.iter().filter(|n| (n.node_id.as_str() < "sim-009") == honest).map(|n| n.trust).collect();
+            v.iter().sum::<f32>() / v.len() as f32
+        };
+        assert!(avg(true) > avg(false));
+    }
+
+    #[test]
+    fn db_observations_weigh_impacts_by_magnitude_and_confidence() {
+        let mut conn = crate::storage::open_db(":memory:").unwrap();
+        crate::storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let ev = crate::storage::add_truth_event(&conn, crate::models::NewTruthEvent {
+            description: "flood".into(), context_id: 1, vector: true, timestamp_start: 1, code: 1,
+        }).unwrap();
+        conn.execute("UPDATE truth_events SET public_key = 'author' WHERE id = ?1", params![ev]).unwrap();
+        for (magnitude, confidence) in [(0.5, Some(0.8)), (1.0, None), (-1.0, Some(0.5)), (-0.2, None)] {
+            let id = crate::storage::add_graded_impact(&conn, ev, 1, magnitude, confidence, None).unwrap();
+            conn.execute("UPDATE impact SET public_key = 'validator' WHERE id = ?1", params![id]).unwrap();
+        }
+        let network = SimNetwork::from_db(&conn).unwrap();
+        assert_eq!(network.observations.len(), 1);
+        let o = &network.observations[0];
+        assert_eq!((o.validator.as_str(), o.author.as_str()), ("validator", "author"));
+        // 0.5×0.8 + 1.0 и 1.0×0.5 + 0.2
+        assert!((o.positive - 1.4).abs() < 1e-6);
+        assert!((o.negative - 0.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn alternative_params_change_ranks_and_are_validated() {
+        let network = SimNetwork::synthetic(&SyntheticSpec::default());
+        let config = SimConfig { rounds: 5, ..Default::default() };
+        let base = simulate(&network, &TrustParams::default(), &config).unwrap();
+        assert!(compare_ranks(&base, &base).is_empty());
+
+        let alt_params = TrustParams { blend_remote_weight: 0.9, remote_trust_floor: -1.0, ..Default::default() };
+        let alt = simulate(&network, &alt_params, &config).unwrap();
+        assert!(alt.rounds[0].max_delta > base.rounds[0].max_delta);
+        for c in compare_ranks(&base, &alt) {
+            assert_ne!(c.baseline_rank, c.alternative_rank);
+        }
+
+        let bad = TrustParams { blend_remote_weight: 2.0, ..Default::default() };
+        assert!(simulate(&network, &bad, &config).is_err());
+    }
+}
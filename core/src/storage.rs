@@ -11,6 +11,8 @@ use crate::{
     GraphData,
     GraphNode,
     GraphLink,
+    Alert,
+    SearchHit,
 };
 use crate::collective_intelligence::models as ci_models;
 use rusqlite::{Connection, OptionalExtension, params};
@@ -18,421 +20,132 @@ use serde::{Deserialize, Serialize};
 // serde_json используется через полные пути
 use uuid::Uuid;
 use chrono::Utc;
-use std::fs;
 use std::collections::{HashMap, HashSet, VecDeque};
 use crate::models::SyncLog;
-use crate::trust_propagation::{compute_quality_index, compute_propagation_priority, propagate_from_remote};
+use crate::trust_propagation::{
+    blend_quality_weighted, blend_trust_weighted, compute_propagation_priority, compute_propagation_priority_with, compute_quality_index,
+    compute_quality_index_with, compute_trust_stability, compute_windowed_activity, load_sender_weight, propagate_from_remote, TrustParams,
+    REMOTE_TRUST_FLOOR, REUSE_BONUS,
+};
 use crate::models::RbacUser;
+use crate::models::KbEntry;
+use crate::models::{EventRelation, Evidence};
+use crate::community::{assign_group_ids, label_propagation, LABEL_PROPAGATION_MAX_ITER, REMOTE_GROUP_TTL_SECS};
+use crate::at_rest;
+use crate::audit;
+use crate::migrations::{self, MigrationReport};
+use crate::anomaly::{detect_collusion_rings, detect_reciprocal_loops, detect_trust_spikes, ANOMALY_LOOKBACK_SECS};
 
 /// Создать соединение с базой данных и инициализировать схему
 pub fn create_db_connection(db_path: &str) -> Result<Connection, CoreError> {
-    let conn = Connection::open(db_path)?;
-    init_db(&conn)?;
-    Ok(conn)
+    open_db(db_path)
 }
 
-/// SQL-схема (knowledge_base + base)
-const SCHEMA_SQL: &str = r#"
-PRAGMA foreign_keys = ON;
-
--- knowledge_base
-CREATE TABLE IF NOT EXISTS category (
-    id          INTEGER PRIMARY KEY,
-    name        TEXT NOT NULL,
-    description TEXT
-);
-
-CREATE TABLE IF NOT EXISTS cause (
-    id          INTEGER PRIMARY KEY,
-    name        TEXT NOT NULL,
-    quality     INTEGER NOT NULL, -- 0/1
-    description TEXT
-);
-
-CREATE TABLE IF NOT EXISTS develop (
-    id          INTEGER PRIMARY KEY,
-    name        TEXT NOT NULL,
-    quality     INTEGER NOT NULL,
-    description TEXT
-);
-
-CREATE TABLE IF NOT EXISTS effect (
-    id          INTEGER PRIMARY KEY,
-    name        TEXT NOT NULL,
-    quality     INTEGER NOT NULL,
-    description TEXT
-);
-
-CREATE TABLE IF NOT EXISTS forma (
-    id          INTEGER PRIMARY KEY,
-    name        TEXT NOT NULL,
-    quality     INTEGER NOT NULL,
-    description TEXT
-);
-
-CREATE TABLE IF NOT EXISTS context (
-    id          INTEGER PRIMARY KEY,
-    name        TEXT NOT NULL,
-category_id INTEGER,
-    forma_id    INTEGER,
-    cause_id    INTEGER,
-    develop_id  INTEGER,
-    effect_id   INTEGER,
-    description TEXT,
-    FOREIGN KEY(category_id) REFERENCES category(id),
-    FOREIGN KEY(forma_id)    REFERENCES forma(id),
-    FOREIGN KEY(cause_id)    REFERENCES cause(id),
-    FOREIGN KEY(develop_id)  REFERENCES develop(id),
-    FOREIGN KEY(effect_id)   REFERENCES effect(id)
-);
-
-CREATE TABLE IF NOT EXISTS impact_type (
-    id          INTEGER PRIMARY KEY,
-    name        TEXT NOT NULL,
-description TEXT
-);
-
--- base
-CREATE TABLE IF NOT EXISTS truth_events (
-    id              INTEGER PRIMARY KEY AUTOINCREMENT,
-    description     TEXT NOT NULL,
-    context_id      INTEGER NOT NULL,
-    vector          INTEGER NOT NULL,       -- 0/1 вместо BOOLEAN
-    detected        INTEGER,                -- NULL/0/1
-    corrected       INTEGER NOT NULL DEFAULT 0,
-timestamp_start INTEGER NOT NULL,
-    timestamp_end   INTEGER,
-    code            INTEGER NOT NULL DEFAULT 1,  -- 8-bit event code
-    collective_score REAL,
-FOREIGN KEY(context_id) REFERENCES context(id)
-);
-
-CREATE TABLE IF NOT EXISTS statements (
-    id              INTEGER PRIMARY KEY AUTOINCREMENT,
-    event_id        INTEGER NOT NULL,
-    text            TEXT NOT NULL,
-    context         TEXT,
-    truth_score     REAL,
-    created_at      INTEGER NOT NULL,
-    updated_at      INTEGER NOT NULL,
-    FOREIGN KEY(event_id) REFERENCES truth_events(id)
-);
-
-CREATE TABLE IF NOT EXISTS impact (
-            id TEXT PRIMARY KEY,
-            event_id TEXT NOT NULL,
-type_id INTEGER NOT NULL,
-            value INTEGER NOT NULL,      -- SQLite bool (0/1)
-notes TEXT,
-            created_at INTEGER NOT NULL,
-            FOREIGN KEY(event_id) REFERENCES truth_events(id)
-);
-
-CREATE TABLE IF NOT EXISTS progress_metrics (
-    id                           INTEGER PRIMARY KEY AUTOINCREMENT,
-    timestamp                    INTEGER NOT NULL,
-    total_events                 INTEGER NOT NULL,
-    total_events_group           INTEGER NOT NULL,
-    total_positive_impact        REAL    NOT NULL,
-    total_positive_impact_group  REAL    NOT NULL,
-    total_negative_impact        REAL    NOT NULL,
-    total_negative_impact_group  REAL    NOT NULL,
-    trend                        REAL    NOT NULL,
-    trend_group                  REAL    NOT NULL
-);
-
--- node and group ratings
-CREATE TABLE IF NOT EXISTS node_ratings (
-    node_id                TEXT PRIMARY KEY,
-    events_true            INTEGER NOT NULL DEFAULT 0,
-    events_false           INTEGER NOT NULL DEFAULT 0,
-    validations            INTEGER NOT NULL DEFAULT 0,
-    reused_events          INTEGER NOT NULL DEFAULT 0,
-    trust_score            REAL    NOT NULL DEFAULT 0.0,
-    propagation_priority   REAL    NOT NULL DEFAULT 0.0,
-    last_updated           INTEGER NOT NULL
-);
-
-CREATE TABLE IF NOT EXISTS group_ratings (
-    group_id      TEXT PRIMARY KEY,
-    members       TEXT    NOT NULL,
-    avg_score     REAL    NOT NULL,
-    coherence     REAL    NOT NULL,
-    last_updated  INTEGER NOT NULL
-);
-
--- RBAC: users and roles
-CREATE TABLE IF NOT EXISTS users (
-    pubkey        TEXT PRIMARY KEY,
-    role          TEXT NOT NULL DEFAULT 'observer',
-    trust_score   REAL NOT NULL DEFAULT 0.0,
-    last_updated  INTEGER NOT NULL,
-    display_name  TEXT
-);
-
--- Optional roles reference for future extension
-CREATE TABLE IF NOT EXISTS roles (
-    role          TEXT PRIMARY KEY,
-    level         INTEGER NOT NULL,
-    description   TEXT
-);
-INSERT OR IGNORE INTO roles(role, level, description) VALUES
-    ('observer', 1, 'Read-only observer'),
-    ('node',     2, 'Authenticated node with delegation rights'),
-    ('admin',    3, 'Administrator');
-
--- CI tables
-CREATE TABLE IF NOT EXISTS participants (
-    id TEXT PRIMARY KEY,
-    public_key TEXT UNIQUE NOT NULL,
-    reputation_score REAL NOT NULL DEFAULT 0.5,
-    total_judgments INTEGER NOT NULL DEFAULT 0,
-    accurate_judgments INTEGER NOT NULL DEFAULT 0,
-    created_at INTEGER NOT NULL,
-    last_activity INTEGER
-);
-
-CREATE TABLE IF NOT EXISTS events_ci (
-    id TEXT PRIMARY KEY,
-    title TEXT NOT NULL,
-    description TEXT,
-    event_type TEXT NOT NULL,
-    created_by TEXT NOT NULL REFERENCES participants(id),
-    created_at INTEGER NOT NULL,
-    status TEXT NOT NULL DEFAULT 'active',
-    resolution_data TEXT
-);
-
-CREATE TABLE IF NOT EXISTS judgments (
-    id TEXT PRIMARY KEY,
-    participant_id TEXT NOT NULL REFERENCES participants(id),
-    event_id TEXT NOT NULL REFERENCES events_ci(id),
-    assessment TEXT NOT NULL,
-    confidence_level REAL NOT NULL,
-    reasoning TEXT,
-    submitted_at INTEGER NOT NULL,
-    signature TEXT NOT NULL,
-    UNIQUE(participant_id, event_id)
-);
-
-CREATE TABLE IF NOT EXISTS consensus_ci (
-    id TEXT PRIMARY KEY,
-    event_id TEXT NOT NULL REFERENCES events_ci(id),
-    consensus_value TEXT NOT NULL,
-    confidence_score REAL NOT NULL,
-    participant_count INTEGER NOT NULL,
-    calculated_at INTEGER NOT NULL,
-    algorithm_version TEXT NOT NULL
-);
-
-CREATE TABLE IF NOT EXISTS reputation_history (
-    id TEXT PRIMARY KEY,
-    participant_id TEXT NOT NULL REFERENCES participants(id),
-    old_reputation REAL NOT NULL,
-    new_reputation REAL NOT NULL,
-    change_reason TEXT NOT NULL,
-    event_id TEXT,
-    updated_at INTEGER NOT NULL
-);
-"#;
-
-/// Открыть/инициализировать БД по пути
+/// Открыть/инициализировать БД по пути; зашифрованная БД должна разблокироваться (at_rest::ensure_unlocked)
 pub fn open_db(path: &str) -> Result<Connection, CoreError> {
     let conn = Connection::open(path)?;
     init_db(&conn)?;
+    at_rest::ensure_unlocked(&conn)?;
     Ok(conn)
 }
 
-/// Инициализация базы: создаёт таблицы, если их нет
+/// Инициализация базы: применяет недостающие миграции схемы
 pub fn init_db(conn: &Connection) -> Result<(), CoreError> {
-    conn.execute_batch(SCHEMA_SQL)?;
-    run_migrations(conn)?;
+    migrate_db(conn, None, false)?;
     Ok(())
 }
 
-/// Выполнить миграции: добавить недостающие колонки и служебные таблицы
-pub fn run_migrations(conn: &Connection) -> Result<(), CoreError> {
-    // Проверка наличия колонки в таблице
-    fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool, rusqlite::Error> {
-        let mut stmt = conn.prepare(&format!("PRAGMA table_info('{}')", table))?;
-        let mut rows = stmt.query([])?;
-        while let Some(row) = rows.next()? {
-            let name: String = row.get(1)?; // 1 = name
-            if name == column {
-                return Ok(true);
-            }
-        }
-        Ok(false)
-    }
-
-    // Добавить подписи/ключи для truth_events
-    if !has_column(conn, "truth_events", "signature")? {
-        conn.execute("ALTER TABLE truth_events ADD COLUMN signature TEXT", [])?;
-    }
-    if !has_column(conn, "truth_events", "public_key")? {
-        conn.execute("ALTER TABLE truth_events ADD COLUMN public_key TEXT", [])?;
-    }
-    // Добавить collective_score для truth_events
-    if !has_column(conn, "truth_events", "collective_score")? {
-        conn.execute("ALTER TABLE truth_events ADD COLUMN collective_score REAL", [])?;
-    }
-
-    // Добавить подписи/ключи для statements
-    if !has_column(conn, "statements", "signature")? {
-        conn.execute("ALTER TABLE statements ADD COLUMN signature TEXT", [])?;
-    }
-    if !has_column(conn, "statements", "public_key")? {
-        conn.execute("ALTER TABLE statements ADD COLUMN public_key TEXT", [])?;
-    }
-
-    // Добавить подписи/ключи для impact
-    if !has_column(conn, "impact", "signature")? {
-        conn.execute("ALTER TABLE impact ADD COLUMN signature TEXT", [])?;
-    }
-    if !has_column(conn, "impact", "public_key")? {
-        conn.execute("ALTER TABLE impact ADD COLUMN public_key TEXT", [])?;
+/// Перевести схему на версию target (None — последняя); см. migrations::migrate
+pub fn migrate_db(conn: &Connection, target: Option<u32>, dry_run: bool) -> Result<MigrationReport, CoreError> {
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    let report = migrations::migrate(conn, target, dry_run)?;
+    // Старые БД получили колонку priority только сейчас — заполнить её
+    if !dry_run && report.legacy_columns.iter().any(|c| c == "node_ratings.propagation_priority") {
+        let _ = refresh_propagation_priority(conn);
     }
+    Ok(report)
+}
 
-    // Таблица журнала синхронизации
-    conn.execute_batch(
-        r#"
-        CREATE TABLE IF NOT EXISTS sync_log (
-            id           INTEGER PRIMARY KEY AUTOINCREMENT,
-            op           TEXT NOT NULL,
-            table_name   TEXT NOT NULL,
-            record_id    TEXT NOT NULL,
-            signature    TEXT,
-            public_key   TEXT,
-            created_at   INTEGER NOT NULL
-        );
-
-        -- high-level synchronization logs
-        CREATE TABLE IF NOT EXISTS sync_logs (
-            id         INTEGER PRIMARY KEY AUTOINCREMENT,
-            timestamp  INTEGER NOT NULL,
-            peer_url   TEXT NOT NULL,
-            mode       TEXT NOT NULL,
-            status     TEXT NOT NULL,
-            details    TEXT NOT NULL
-        );
-        "#,
-    )?;
-
-    // Ensure ratings tables exist (idempotent)
-    conn.execute_batch(
+/// Отметки времени действий узлов начиная с `since`: события и оценки (по public_key),
+/// суждения (по ключу участника), записи журналов синхронизации (по public_key и peer_url).
+/// Если задан `node` — только для него. Ключ — pubkey или URL пира.
+pub fn load_activity_timestamps(
+    conn: &Connection,
+    since: i64,
+    node: Option<&str>,
+) -> Result<HashMap<String, Vec<i64>>, CoreError> {
+    let mut stmt = conn.prepare(
         r#"
-        CREATE TABLE IF NOT EXISTS node_ratings (
-            node_id                TEXT PRIMARY KEY,
-            events_true            INTEGER NOT NULL DEFAULT 0,
-            events_false           INTEGER NOT NULL DEFAULT 0,
-            validations            INTEGER NOT NULL DEFAULT 0,
-            reused_events          INTEGER NOT NULL DEFAULT 0,
-            trust_score            REAL    NOT NULL DEFAULT 0.0,
-            propagation_priority   REAL    NOT NULL DEFAULT 0.0,
-            last_updated           INTEGER NOT NULL
-        );
-
-        CREATE TABLE IF NOT EXISTS group_ratings (
-            group_id      TEXT PRIMARY KEY,
-            members       TEXT    NOT NULL,
-            avg_score     REAL    NOT NULL,
-            coherence     REAL    NOT NULL,
-            last_updated  INTEGER NOT NULL
-        );
-
-        -- node metrics for network monitoring
-        CREATE TABLE IF NOT EXISTS node_metrics (
-            pubkey                  TEXT PRIMARY KEY,
-            last_seen               INTEGER NOT NULL,
-            relay_success_rate      REAL    NOT NULL DEFAULT 0.0,
-            quality_index           REAL    NOT NULL DEFAULT 0.0,
-            propagation_priority    REAL    NOT NULL DEFAULT 0.0
-        );
-
-        -- active JWT refresh tokens (per public key)
-        CREATE TABLE IF NOT EXISTS active_tokens (
-            public_key    TEXT    NOT NULL,
-            refresh_token TEXT    NOT NULL UNIQUE,
-            expires_at    INTEGER NOT NULL
-        );
-        CREATE INDEX IF NOT EXISTS idx_active_tokens_pub ON active_tokens(public_key);
+        SELECT key, ts FROM (
+            SELECT public_key AS key, timestamp_start AS ts FROM truth_events WHERE public_key IS NOT NULL
+            UNION ALL
+            SELECT public_key, created_at FROM impact WHERE public_key IS NOT NULL
+            UNION ALL
+            SELECT p.public_key, j.submitted_at FROM judgments j JOIN participants p ON p.id = j.participant_id
+            UNION ALL
+            SELECT public_key, created_at FROM sync_log WHERE public_key IS NOT NULL
+            UNION ALL
+            SELECT peer_url, timestamp FROM sync_logs WHERE status = 'success'
+            UNION ALL
+            SELECT peer_url, last_sync FROM peer_history WHERE last_sync IS NOT NULL
+        )
+        WHERE ts >= ?1 AND (?2 IS NULL OR key = ?2)
         "#,
     )?;
-
-    // Add quality_index to node_metrics if missing
-    if !has_column(conn, "node_metrics", "quality_index")? {
-        conn.execute(
-            "ALTER TABLE node_metrics ADD COLUMN quality_index REAL NOT NULL DEFAULT 0.0",
-            [],
-        )?;
-    }
-
-    // Add propagation_priority to node_metrics if missing
-    if !has_column(conn, "node_metrics", "propagation_priority")? {
-        conn.execute(
-            "ALTER TABLE node_metrics ADD COLUMN propagation_priority REAL NOT NULL DEFAULT 0.0",
-            [],
-        )?;
+    let rows = stmt.query_map(params![since, node], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)))?;
+    let mut out: HashMap<String, Vec<i64>> = HashMap::new();
+    for r in rows {
+        let (key, ts) = r?;
+        out.entry(key).or_default().push(ts);
     }
+    Ok(out)
+}
 
-    // RBAC migrations: ensure users table has required columns and seed roles
-    conn.execute_batch(
-        r#"
-        CREATE TABLE IF NOT EXISTS users (
-            pubkey        TEXT PRIMARY KEY,
-            role          TEXT NOT NULL DEFAULT 'observer',
-            trust_score   REAL NOT NULL DEFAULT 0.0,
-            last_updated  INTEGER NOT NULL,
-            display_name  TEXT
-        );
-        CREATE TABLE IF NOT EXISTS roles (
-            role          TEXT PRIMARY KEY,
-            level         INTEGER NOT NULL,
-            description   TEXT
-        );
-        INSERT OR IGNORE INTO roles(role, level, description) VALUES
-            ('observer', 1, 'Read-only observer'),
-            ('node',     2, 'Authenticated node with delegation rights'),
-            ('admin',    3, 'Administrator');
-        
-        -- local peer sync history for decentralized diagnostics
-        CREATE TABLE IF NOT EXISTS peer_history (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            peer_url TEXT NOT NULL,
-            last_sync INTEGER,
-            success_count INTEGER DEFAULT 0,
-            fail_count INTEGER DEFAULT 0,
-            last_quality_index REAL DEFAULT 0.0,
-            last_trust_score REAL DEFAULT 0.0
-        );
-        "#,
-    )?;
-
-    // Добавить колонку propagation_priority в node_ratings при обновлении схемы
-    if !has_column(conn, "node_ratings", "propagation_priority")? {
-        conn.execute(
-            "ALTER TABLE node_ratings ADD COLUMN propagation_priority REAL NOT NULL DEFAULT 0.0",
-            [],
-        )?;
-        // Пересчёт значений приоритета после добавления колонки
-        let _ = refresh_propagation_priority(conn);
-    }
+/// Активность узла (0..1) за окно TrustParams::activity_window_days на момент now_ts
+pub fn recent_activity(conn: &Connection, node: &str, now_ts: i64, params: &TrustParams) -> Result<f32, CoreError> {
+    let since = now_ts - (params.activity_window_days * 86_400.0) as i64;
+    let stamps = load_activity_timestamps(conn, since, Some(node))?.remove(node).unwrap_or_default();
+    Ok(compute_windowed_activity(params, &stamps, now_ts))
+}
 
-    Ok(())
+/// Активность всех узлов с действиями в окне (узлы без действий отсутствуют — активность 0)
+pub fn recent_activity_map(conn: &Connection, now_ts: i64, params: &TrustParams) -> Result<HashMap<String, f32>, CoreError> {
+    let since = now_ts - (params.activity_window_days * 86_400.0) as i64;
+    Ok(load_activity_timestamps(conn, since, None)?
+        .into_iter()
+        .map(|(k, stamps)| {
+            let a = compute_windowed_activity(params, &stamps, now_ts);
+            (k, a)
+        })
+        .collect())
 }
 
-/// Вычислить недавнюю активность узла как долю нормализованной активности за последние 7 суток.
-/// На текущем этапе используется прокси: нормализация по сумме событий/валидаций/переиспользований.
-#[allow(dead_code)]
-fn compute_recent_activity(events_true: i64, events_false: i64, validations: i64, reused_events: i64) -> f32 {
-    let total = events_true + events_false + validations + reused_events;
-    if total <= 0 { 0.0 } else { ((total as f32).log10() / 3.0).clamp(0.0, 1.0) }
+/// Текущий приоритет распространения пира (pubkey или URL) без EMA-сглаживания:
+/// trust из node_ratings, quality/relay из node_metrics и активность за окно на момент now_ts.
+/// Пиры, активные лишь давно, теряют вклад активности сразу, не дожидаясь пересчёта.
+pub fn peer_propagation_priority(conn: &Connection, peer: &str, now_ts: i64) -> Result<(f32, f32), CoreError> {
+    let params = TrustParams::default();
+    let trust: f32 = conn
+        .query_row("SELECT trust_score FROM node_ratings WHERE node_id = ?1", params![peer], |r| r.get::<_, f64>(0))
+        .optional()?
+        .unwrap_or(0.0) as f32;
+    let metrics = load_node_metrics(conn, peer)?;
+    let activity = recent_activity(conn, peer, now_ts, &params)?;
+    let p = compute_propagation_priority(
+        trust,
+        metrics.as_ref().map(|m| m.quality_index).unwrap_or(0.0),
+        metrics.as_ref().map(|m| m.relay_success_rate).unwrap_or(0.0),
+        activity,
+        None,
+    );
+    Ok((p, activity))
 }
 
 /// Обновить колонку propagation_priority для всех записей node_ratings
 fn refresh_propagation_priority(conn: &Connection) -> Result<(), CoreError> {
-    // Пересчитываем priority с EMA из trust_score, quality_index и relay_success_rate
+    // Пересчитываем priority с EMA из trust_score, quality_index, relay_success_rate и активности за окно
     let now = chrono::Utc::now().timestamp();
+    let activity = recent_activity_map(conn, now, &TrustParams::default())?;
     let mut sel = conn.prepare(
         r#"
         SELECT nr.node_id, nr.trust_score,
@@ -454,7 +167,8 @@ fn refresh_propagation_priority(conn: &Connection) -> Result<(), CoreError> {
         let relay_success_rate: f32 = r.get::<_, f64>(3)? as f32;
         let prev_p: f32 = r.get::<_, f64>(4)? as f32;
 
-        let p = compute_propagation_priority(trust, quality_index, relay_success_rate, Some(prev_p));
+        let a = activity.get(&node_id).copied().unwrap_or(0.0);
+        let p = compute_propagation_priority(trust, quality_index, relay_success_rate, a, Some(prev_p));
 
         // Обновляем в node_ratings
         upd_ratings.execute(rusqlite::params![node_id, p as f64])?;
@@ -481,6 +195,40 @@ pub fn get_propagation_priority(conn: &Connection, pubkey: &str) -> Result<f32,
     Ok(prio.unwrap_or(0.5) as f32)
 }
 
+/// Запись с подписью, хранимой зашифрованной: таблица и ключ строки для AAD (см. at_rest)
+trait SignedRow {
+    const TABLE: &'static str;
+    fn key_and_signature(&mut self) -> (&str, &mut Option<String>);
+}
+
+impl SignedRow for TruthEvent {
+    const TABLE: &'static str = "truth_events";
+    fn key_and_signature(&mut self) -> (&str, &mut Option<String>) {
+        (&self.uid, &mut self.signature)
+    }
+}
+
+impl SignedRow for Statement {
+    const TABLE: &'static str = "statements";
+    fn key_and_signature(&mut self) -> (&str, &mut Option<String>) {
+        (&self.uid, &mut self.signature)
+    }
+}
+
+impl SignedRow for Impact {
+    const TABLE: &'static str = "impact";
+    fn key_and_signature(&mut self) -> (&str, &mut Option<String>) {
+        (&self.id, &mut self.signature)
+    }
+}
+
+/// Расшифровать подпись, прочитанную из БД (см. at_rest)
+fn open_signature<T: SignedRow>(conn: &Connection, item: &mut T) -> Result<(), CoreError> {
+    let (row, signature) = item.key_and_signature();
+    *signature = at_rest::open(conn, T::TABLE, "signature", row, signature.take())?;
+    Ok(())
+}
+
 /// Register a refresh token for a given public key with expiration timestamp (unix seconds)
 pub fn register_refresh_token(
     conn: &Connection,
@@ -491,7 +239,7 @@ pub fn register_refresh_token(
     conn.execute(
         r#"INSERT INTO active_tokens (public_key, refresh_token, expires_at)
            VALUES (?1, ?2, ?3)"#,
-        params![public_key, refresh_token, expires_at],
+        params![public_key, at_rest::token_digest(conn, refresh_token)?, expires_at],
     )?;
     Ok(())
 }
@@ -505,7 +253,7 @@ pub fn find_session_by_refresh(
         r#"SELECT public_key, expires_at FROM active_tokens WHERE refresh_token = ?1"#,
     )?;
     let row = stmt
-        .query_row(params![refresh_token], |r| Ok((r.get(0)?, r.get(1)?)))
+        .query_row(params![at_rest::token_digest(conn, refresh_token)?], |r| Ok((r.get(0)?, r.get(1)?)))
         .optional()?;
     Ok(row)
 }
@@ -514,7 +262,7 @@ pub fn find_session_by_refresh(
 pub fn delete_refresh_token(conn: &Connection, refresh_token: &str) -> Result<(), CoreError> {
     conn.execute(
         r#"DELETE FROM active_tokens WHERE refresh_token = ?1"#,
-        params![refresh_token],
+        params![at_rest::token_digest(conn, refresh_token)?],
     )?;
     Ok(())
 }
@@ -531,427 +279,10 @@ pub fn cleanup_expired_tokens(conn: &Connection, now_ts: i64) -> Result<usize, C
 /* =========================
 SEED: knowledge_base
 ========================= */
-type ContextRow<'a> = (i64, &'a str, i64, i64, i64, i64, i64, &'a str);
-
+/// Заполнить базу знаний встроенным пакетом (core/packs/base.toml) на локали en или ru;
+/// существующие записи не меняются
 pub fn seed_knowledge_base(conn: &mut Connection, locale: &str) -> Result<(), CoreError> {
-    match locale {
-        "ru" => seed_knowledge_base_ru(conn),
-        "en" => seed_knowledge_base_en(conn),
-        other => Err(CoreError::InvalidArg(format!(
-            "unsupported locale: {}",
-            other
-        ))),
-    }
-}
-
-fn seed_knowledge_base_ru(conn: &mut Connection) -> Result<(), CoreError> {
-    let tx = conn.transaction()?;
-
-    // category
-    let categories: &[(i64, &str, &str)] = &[
-        (1, "Социальный", "Общение, репутация, доверие"),
-        (2, "Финансовый", "Деньги, собственность, договоры"),
-        (
-            3,
-            "Политический",
-            "Государство, договоры, международные отношения",
-        ),
-        (4, "Правовой", "Закон, соблюдение норм, суд"),
-        (5, "Личный", "Самооценка, внутренние решения"),
-        (6, "Организационный", "Команды, компании, процессы"),
-        (7, "Медиа", "Информация, СМИ, платформы"),
-        (8, "Технологический", "ИТ-системы, данные, безопасность"),
-    ];
-    for (id, name, desc) in categories {
-        tx.execute(
-            "INSERT OR IGNORE INTO category (id, name, description) VALUES (?1, ?2, ?3)",
-            params![id, name, desc],
-        )?;
-    }
-
-    // cause
-    let causes: &[(i64, &str, i64, &str)] = &[
-        (1, "Страх", 0, "Избежание наказания, осуждения"),
-        (2, "Выгода", 0, "Материальный/личный интерес"),
-        (3, "Милосердие", 1, "Сострадание, забота о другом"),
-        (4, "Неведение", 0, "Отсутствие знаний, ошибки"),
-        (5, "Долг", 1, "Обязанность, ответственность"),
-        (6, "Любопытство", 1, "Поиск истины, исследование"),
-        (7, "Давление", 0, "Принуждение, конформизм"),
-        (8, "Забота", 1, "Охрана блага другого"),
-    ];
-    for (id, name, q, desc) in causes {
-        tx.execute(
-            "INSERT OR IGNORE INTO cause (id, name, quality, description) VALUES (?1, ?2, ?3, ?4)",
-            params![id, name, q, desc],
-        )?;
-    }
-
-    // develop
-    let develops: &[(i64, &str, i64, &str)] = &[
-        (1, "Сокрытие", 0, "Умышленное недосказание/умолчание"),
-        (
-            2,
-            "Манипуляция",
-            0,
-            "Искажение, давление, подмена контекста",
-        ),
-        (3, "Прозрачность", 1, "Открытость, доступность фактов"),
-        (4, "Проверка", 1, "Верификация, сопоставление источников"),
-        (5, "Преувеличение", 0, "Гипербола, ложная значимость"),
-        (6, "Признание", 1, "Принятие ответственности, исправление"),
-    ];
-    for (id, name, q, desc) in develops {
-        tx.execute(
-            "INSERT OR IGNORE INTO develop (id, name, quality, description) VALUES (?1, ?2, ?3, ?4)",
-            params![id, name, q, desc],
-        )?;
-    }
-
-    // effect
-    let effects: &[(i64, &str, i64, &str)] = &[
-        (1, "Недоверие", 0, "Подрыв доверия, разрыв связей"),
-        (2, "Доверие", 1, "Укрепление отношений, кооперация"),
-        (3, "Конфликт", 0, "Эскалация, противостояние"),
-        (4, "Примирение", 1, "Снижение напряжения, согласие"),
-        (5, "Санкции", 0, "Юридические/репутационные последствия"),
-        (6, "Обучение", 1, "Рост компетентности, выводы"),
-        (7, "Потеря репутации", 0, "Снижение статуса"),
-        (8, "Рост репутации", 1, "Укрепление статуса"),
-    ];
-    for (id, name, q, desc) in effects {
-        tx.execute(
-            "INSERT OR IGNORE INTO effect (id, name, quality, description) VALUES (?1, ?2, ?3, ?4)",
-            params![id, name, q, desc],
-        )?;
-    }
-
-    // forma
-    let formas: &[(i64, &str, i64, &str)] = &[
-        (1, "Обман", 0, "Сознательное искажение реальности"),
-        (2, "Правда", 1, "Соответствие фактам и проверкам"),
-        (3, "Самообман", 0, "Искажение для успокоения себя"),
-        (
-            4,
-            "Полуправда",
-            0,
-            "Частичное искажение с верными фрагментами",
-        ),
-        (5, "Умолчание", 0, "Сокрытие значимой информации"),
-        (6, "Открытость", 1, "Проактивное раскрытие фактов"),
-    ];
-    for (id, name, q, desc) in formas {
-        tx.execute(
-            "INSERT OR IGNORE INTO forma (id, name, quality, description) VALUES (?1, ?2, ?3, ?4)",
-            params![id, name, q, desc],
-        )?;
-    }
-
-    // impact_type
-    let impact_types: &[(i64, &str, &str)] = &[
-        (1, "Репутация", "Социальный капитал, доверие"),
-        (2, "Финансы", "Деньги, активы, обязательства"),
-        (3, "Эмоции", "Стресс, уверенность, мотивация"),
-        (4, "Право", "Юридические риски, санкции"),
-        (5, "Здоровье", "Физическое/психическое состояние"),
-        (6, "Время", "Потери/выигрыш времени"),
-    ];
-    for (id, name, desc) in impact_types {
-        tx.execute(
-            "INSERT OR IGNORE INTO impact_type (id, name, description) VALUES (?1, ?2, ?3)",
-            params![id, name, desc],
-        )?;
-    }
-
-    // context (типовые связки)
-    let contexts: &[ContextRow] = &[
-        // id, name, category, forma, cause, develop, effect, desc
-        (
-            1,
-            "Межличностные отношения: открытость",
-            1,
-            2,
-            5,
-            3,
-            2,
-            "Честный диалог, укрепление доверия",
-        ),
-        (
-            2,
-            "Межличностные отношения: сокрытие",
-            1,
-            1,
-            1,
-            1,
-            1,
-            "Умолчание значимого факта, эрозия доверия",
-        ),
-        (
-            3,
-            "Финансы: мошенничество",
-            2,
-            1,
-            2,
-            2,
-            5,
-            "Обман с целью выгоды, юридические последствия",
-        ),
-        (
-            4,
-            "Финансы: прозрачная отчётность",
-            2,
-            2,
-            5,
-            4,
-            8,
-            "Проверяемость фактов, рост репутации",
-        ),
-        (
-            5,
-            "Политика: нарушение договора",
-            3,
-            1,
-            2,
-            1,
-            1,
-            "Сокрытие нарушений, падение доверия",
-        ),
-        (
-            6,
-            "Политика: соблюдение договора",
-            3,
-            2,
-            5,
-            4,
-            2,
-            "Подтверждённое выполнение обязательств",
-        ),
-        (
-            7,
-            "Организация: признание ошибки",
-            6,
-            2,
-            5,
-            6,
-            6,
-            "Признание и исправление повышают обучаемость",
-        ),
-        (
-            8,
-            "Медиа: дезинформация",
-            7,
-            1,
-            7,
-            2,
-            3,
-            "Манипуляции, приводящие к конфликтам",
-        ),
-    ];
-    for (id, name, cat, forma, cause, develop, effect, desc) in contexts {
-        tx.execute(
-            "INSERT OR IGNORE INTO context
-             (id, name, category_id, forma_id, cause_id, develop_id, effect_id, description)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            params![id, name, cat, forma, cause, develop, effect, desc],
-        )?;
-    }
-
-    tx.commit()?;
-    Ok(())
-}
-
-fn seed_knowledge_base_en(conn: &mut Connection) -> Result<(), CoreError> {
-    let tx = conn.transaction()?;
-
-    let categories: &[(i64, &str, &str)] = &[
-        (1, "Social", "Communication, reputation, trust"),
-        (2, "Financial", "Money, property, contracts"),
-        (3, "Political", "State, treaties, international relations"),
-        (4, "Legal", "Law, compliance, courts"),
-        (5, "Personal", "Self-assessment, inner decisions"),
-        (6, "Organizational", "Teams, companies, processes"),
-        (7, "Media", "Information, press, platforms"),
-        (8, "Technological", "IT systems, data, security"),
-    ];
-    for (id, name, desc) in categories {
-        tx.execute(
-            "INSERT OR IGNORE INTO category (id, name, description) VALUES (?1, ?2, ?3)",
-            params![id, name, desc],
-        )?;
-    }
-
-    let causes: &[(i64, &str, i64, &str)] = &[
-        (1, "Fear", 0, "Avoidance of punishment or blame"),
-        (2, "Benefit", 0, "Material/personal interest"),
-        (3, "Mercy", 1, "Compassion, care for others"),
-        (4, "Ignorance", 0, "Lack of knowledge, mistakes"),
-        (5, "Duty", 1, "Obligation, responsibility"),
-        (6, "Curiosity", 1, "Search for truth, inquiry"),
-        (7, "Pressure", 0, "Coercion, conformism"),
-        (8, "Care", 1, "Protecting another's good"),
-    ];
-    for (id, name, q, desc) in causes {
-        tx.execute(
-            "INSERT OR IGNORE INTO cause (id, name, quality, description) VALUES (?1, ?2, ?3, ?4)",
-            params![id, name, q, desc],
-        )?;
-    }
-
-    let develops: &[(i64, &str, i64, &str)] = &[
-        (1, "Concealment", 0, "Intentional omission/withholding"),
-        (2, "Manipulation", 0, "Distortion, pressure, context switch"),
-        (3, "Transparency", 1, "Openness, factual availability"),
-        (4, "Verification", 1, "Cross-checking sources"),
-        (5, "Exaggeration", 0, "Overstatement, false salience"),
-        (6, "Confession", 1, "Owning mistakes, remediation"),
-    ];
-    for (id, name, q, desc) in develops {
-        tx.execute(
-            "INSERT OR IGNORE INTO develop (id, name, quality, description) VALUES (?1, ?2, ?3, ?4)",
-            params![id, name, q, desc],
-        )?;
-    }
-
-    let effects: &[(i64, &str, i64, &str)] = &[
-        (1, "Distrust", 0, "Erodes trust and ties"),
-        (2, "Trust", 1, "Strengthens cooperation"),
-        (3, "Conflict", 0, "Escalation, confrontation"),
-        (4, "Reconciliation", 1, "Reduced tension, alignment"),
-        (5, "Sanctions", 0, "Legal/reputational penalties"),
-        (6, "Learning", 1, "Competence growth, insights"),
-        (7, "Reputation Loss", 0, "Status decrease"),
-        (8, "Reputation Gain", 1, "Status increase"),
-    ];
-    for (id, name, q, desc) in effects {
-        tx.execute(
-            "INSERT OR IGNORE INTO effect (id, name, quality, description) VALUES (?1, ?2, ?3, ?4)",
-            params![id, name, q, desc],
-        )?;
-    }
-
-    let formas: &[(i64, &str, i64, &str)] = &[
-        (1, "Deception", 0, "Conscious distortion of reality"),
-        (2, "Truth", 1, "Conformance to facts and checks"),
-        (3, "Self-deception", 0, "Distortion to reassure oneself"),
-        (4, "Half-truth", 0, "Partial truth with distortions"),
-        (5, "Silence", 0, "Withholding significant info"),
-        (6, "Openness", 1, "Proactive disclosure of facts"),
-    ];
-    for (id, name, q, desc) in formas {
-        tx.execute(
-            "INSERT OR IGNORE INTO forma (id, name, quality, description) VALUES (?1, ?2, ?3, ?4)",
-            params![id, name, q, desc],
-        )?;
-    }
-
-    let impact_types: &[(i64, &str, &str)] = &[
-        (1, "Reputation", "Social capital, trust"),
-        (2, "Finance", "Money, assets, liabilities"),
-        (3, "Emotions", "Stress, confidence, motivation"),
-        (4, "Law", "Legal risks, sanctions"),
-        (5, "Health", "Physical/mental condition"),
-        (6, "Time", "Time losses/gains"),
-    ];
-    for (id, name, desc) in impact_types {
-        tx.execute(
-            "INSERT OR IGNORE INTO impact_type (id, name, description) VALUES (?1, ?2, ?3)",
-            params![id, name, desc],
-        )?;
-    }
-
-    let contexts: &[ContextRow] = &[
-        (
-            1,
-            "Interpersonal: openness",
-            1,
-            2,
-            5,
-            3,
-            2,
-            "Honest dialogue, strengthening trust",
-        ),
-        (
-            2,
-            "Interpersonal: concealment",
-            1,
-            1,
-            1,
-            1,
-            1,
-            "Withholding a significant fact, trust erosion",
-        ),
-        (
-            3,
-            "Finance: fraud",
-            2,
-            1,
-            2,
-            2,
-            5,
-            "Deception for profit, legal consequences",
-        ),
-        (
-            4,
-            "Finance: transparent reporting",
-            2,
-            2,
-            5,
-            4,
-            8,
-            "Verifiable facts, reputation growth",
-        ),
-        (
-            5,
-            "Politics: treaty breach",
-            3,
-            1,
-            2,
-            1,
-            1,
-            "Hidden violations, loss of trust",
-        ),
-        (
-            6,
-            "Politics: treaty compliance",
-            3,
-            2,
-            5,
-            4,
-            2,
-            "Confirmed execution of obligations",
-        ),
-        (
-            7,
-            "Organization: admitting a mistake",
-            6,
-            2,
-            5,
-            6,
-            6,
-            "Admission and correction improve learning",
-        ),
-        (
-            8,
-            "Media: disinformation",
-            7,
-            1,
-            7,
-            2,
-            3,
-            "Manipulations leading to conflict",
-        ),
-    ];
-    for (id, name, cat, forma, cause, develop, effect, desc) in contexts {
-        tx.execute(
-            "INSERT OR IGNORE INTO context
-             (id, name, category_id, forma_id, cause_id, develop_id, effect_id, description)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            params![id, name, cat, forma, cause, develop, effect, desc],
-        )?;
-    }
-
-    tx.commit()?;
-    Ok(())
+    crate::knowledge::pack::seed(conn, &crate::knowledge::pack::Pack::builtin(), locale)
 }
 
 /* =========================
@@ -963,51 +294,99 @@ pub fn add_truth_event(conn: &Connection, new_ev: NewTruthEvent) -> Result<i64,
     if new_ev.description.trim().is_empty() {
         return Err(CoreError::InvalidArg("description is empty".into()));
     }
+    // Несуществующий контекст отклонит внешний ключ; устаревший — только для новых событий
+    if get_kb_entry(conn, "context", new_ev.context_id)?.is_some_and(|c| c.deprecated_at.is_some()) {
+        return Err(CoreError::InvalidArg(format!("context {} is deprecated", new_ev.context_id)));
+    }
 
-    conn.execute(
-        r#"INSERT INTO truth_events
-            (description, context_id, vector, detected, corrected, timestamp_start, timestamp_end, code, collective_score)
-          VALUES
-            (?1, ?2, ?3, NULL, 0, ?4, NULL, ?5, NULL)"#,
-        params![
-            new_ev.description,
-            new_ev.context_id,
-            if new_ev.vector { 1 } else { 0 },
-            new_ev.timestamp_start,
-            new_ev.code,
-        ],
-    )?;
+    let uid = crate::models::new_uid();
+    audit::track(conn, "truth_events", "uid", &uid, || {
+        conn.execute(
+            r#"INSERT INTO truth_events
+                (uid, description, context_id, vector, detected, corrected, timestamp_start, timestamp_end, code, collective_score)
+              VALUES
+                (?1, ?2, ?3, ?4, NULL, 0, ?5, NULL, ?6, NULL)"#,
+            params![
+                uid,
+                new_ev.description,
+                new_ev.context_id,
+                if new_ev.vector { 1 } else { 0 },
+                new_ev.timestamp_start,
+                new_ev.code,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    })
+}
 
-    Ok(conn.last_insert_rowid())
+/// Колонки события в порядке map_truth_event (таблица под псевдонимом e)
+const EVENT_COLUMNS: &str = "e.id, e.description, e.context_id, e.vector, e.detected, e.corrected, e.timestamp_start, e.timestamp_end, e.code, e.signature, e.public_key, e.collective_score, e.uid";
+
+fn map_truth_event(row: &rusqlite::Row<'_>) -> rusqlite::Result<TruthEvent> {
+    Ok(TruthEvent {
+        id: row.get(0)?,
+        uid: row.get(12)?,
+        description: row.get(1)?,
+        context_id: row.get(2)?,
+        vector: row.get::<_, i64>(3)? != 0,
+        detected: row.get::<_, Option<i64>>(4)?.map(|v| v != 0),
+        corrected: row.get::<_, i64>(5)? != 0,
+        timestamp_start: row.get(6)?,
+        timestamp_end: row.get::<_, Option<i64>>(7)?,
+        code: row.get(8)?,
+        signature: row.get(9)?,
+        public_key: row.get(10)?,
+        collective_score: row.get(11)?,
+    })
 }
 
-/// Получить событие по id
+/// Получить событие по локальному id
 pub fn get_truth_event(conn: &Connection, id: i64) -> Result<Option<TruthEvent>, CoreError> {
-    let mut stmt = conn.prepare(
-        r#"SELECT id, description, context_id, vector, detected, corrected, timestamp_start, timestamp_end, code, signature, public_key, collective_score
-           FROM truth_events WHERE id = ?1"#,
-    )?;
+    let sql = format!("SELECT {} FROM truth_events e WHERE e.id = ?1", EVENT_COLUMNS);
+    let row_opt = conn.query_row(&sql, params![id], map_truth_event).optional()?;
+    row_opt.map(|mut ev| open_signature(conn, &mut ev).map(|_| ev)).transpose()
+}
 
-    let row_opt = stmt
-        .query_row(params![id], |row| {
-        Ok(TruthEvent {
-            id: row.get(0)?,
-            description: row.get(1)?,
-            context_id: row.get(2)?,
-                vector: row.get::<_, i64>(3)? != 0,
-            detected: row.get::<_, Option<i64>>(4)?.map(|v| v != 0),
-            corrected: row.get::<_, i64>(5)? != 0,
-            timestamp_start: row.get(6)?,
-            timestamp_end: row.get::<_, Option<i64>>(7)?,
-                code: row.get(8)?,
-                signature: row.get(9)?,
-                public_key: row.get(10)?,
-                collective_score: row.get(11)?,
-            })
-        })
-        .optional()?;
+/// Получить событие по глобальному id
+pub fn get_truth_event_by_uid(conn: &Connection, uid: &str) -> Result<Option<TruthEvent>, CoreError> {
+    let sql = format!("SELECT {} FROM truth_events e WHERE e.uid = ?1", EVENT_COLUMNS);
+    let row_opt = conn.query_row(&sql, params![uid], map_truth_event).optional()?;
+    row_opt.map(|mut ev| open_signature(conn, &mut ev).map(|_| ev)).transpose()
+}
 
-    Ok(row_opt)
+/// Записать копию события (например, полученную от пира): вставка или замена всех полей по uid.
+/// Локальный id назначается при вставке и не совпадает с id у пира; пустой uid
+/// (пир до глобальных id) заменяется на TruthEvent::legacy_uid. Возвращает локальный id.
+/// collective_score не переносится — он пересчитывается локально.
+pub fn upsert_truth_event(conn: &Connection, ev: &TruthEvent) -> Result<i64, CoreError> {
+    let uid = if ev.uid.is_empty() { ev.legacy_uid() } else { ev.uid.clone() };
+    audit::track(conn, "truth_events", "uid", &uid, || {
+        conn.execute(
+            r#"INSERT INTO truth_events
+                (uid, description, context_id, vector, detected, corrected, timestamp_start, timestamp_end, code, signature, public_key)
+              VALUES
+                (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+              ON CONFLICT(uid) DO UPDATE SET
+                description=excluded.description, context_id=excluded.context_id, vector=excluded.vector,
+                detected=excluded.detected, corrected=excluded.corrected, timestamp_start=excluded.timestamp_start,
+                timestamp_end=excluded.timestamp_end, code=excluded.code, signature=excluded.signature, public_key=excluded.public_key"#,
+            params![
+                uid,
+                ev.description,
+                ev.context_id,
+                if ev.vector { 1 } else { 0 },
+                ev.detected.map(|v| if v { 1 } else { 0 }),
+                if ev.corrected { 1 } else { 0 },
+                ev.timestamp_start,
+                ev.timestamp_end,
+                ev.code as i64,
+                at_rest::seal(conn, "truth_events", "signature", &uid, ev.signature.as_deref())?,
+                ev.public_key,
+            ],
+        )?;
+        Ok(())
+    })?;
+    Ok(conn.query_row("SELECT id FROM truth_events WHERE uid = ?1", params![uid], |r| r.get(0))?)
 }
 
 /// Отметить событие как распознанное (detected = true/false), опционально проставить окончание и corrected
@@ -1018,21 +397,27 @@ pub fn set_event_detected(
     timestamp_end: Option<i64>,
     corrected: bool,
 ) -> Result<(), CoreError> {
-    conn.execute(
-        r#"UPDATE truth_events
-           SET detected = ?2, timestamp_end = COALESCE(?3, timestamp_end), corrected = ?4
-         WHERE id = ?1"#,
-        params![
-            id,
-            if detected { 1 } else { 0 },
-            timestamp_end,
-            if corrected { 1 } else { 0 }
-        ],
-    )?;
-    Ok(())
+    let Some(uid) = conn.query_row("SELECT uid FROM truth_events WHERE id = ?1", params![id], |r| r.get::<_, String>(0)).optional()? else {
+        return Ok(());
+    };
+    audit::track(conn, "truth_events", "uid", &uid, || {
+        conn.execute(
+            r#"UPDATE truth_events
+               SET detected = ?2, timestamp_end = COALESCE(?3, timestamp_end), corrected = ?4
+             WHERE id = ?1"#,
+            params![
+                id,
+                if detected { 1 } else { 0 },
+                timestamp_end,
+                if corrected { 1 } else { 0 }
+            ],
+        )?;
+        Ok(())
+    })
 }
 
-/// Добавить запись impact
+/// Добавить запись impact к событию с локальным id event_id (в impact хранится uid события).
+/// value — оценка без степени: +1 или -1 (см. add_graded_impact)
 pub fn add_impact(
     conn: &Connection,
     event_id: i64,
@@ -1040,30 +425,92 @@ pub fn add_impact(
     value: bool,
     notes: Option<String>,
 ) -> Result<String, CoreError> {
+    add_graded_impact(conn, event_id, type_id, if value { 1.0 } else { -1.0 }, None, notes)
+}
+
+/// Добавить оценку impact со степенью magnitude (-1..1) и уверенностью confidence (0..1)
+pub fn add_graded_impact(
+    conn: &Connection,
+    event_id: i64,
+    type_id: i64,
+    magnitude: f64,
+    confidence: Option<f64>,
+    notes: Option<String>,
+) -> Result<String, CoreError> {
+    Impact::check_grade(magnitude, confidence)?;
     let id = Uuid::new_v4().to_string();
     let created_at = Utc::now().timestamp();
 
-    conn.execute(
-        r#"INSERT INTO impact (id, event_id, type_id, value, notes, created_at)
-          VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
-        params![id, event_id, type_id, if value { 1 } else { 0 }, notes, created_at],
-    )?;
+    audit::track(conn, "impact", "id", &id, || {
+        let n = conn.execute(
+            r#"INSERT INTO impact (id, event_id, type_id, value, notes, created_at, magnitude, confidence)
+              SELECT ?1, uid, ?3, ?4, ?5, ?6, ?7, ?8 FROM truth_events WHERE id = ?2"#,
+            params![id, event_id, type_id, if magnitude >= 0.0 { 1 } else { 0 }, notes, created_at, magnitude, confidence],
+        )?;
+        if n == 0 {
+            return Err(CoreError::NotFound(format!("event {}", event_id)));
+        }
+        Ok(())
+    })?;
     Ok(id)
 }
 
+/// Вставить запись impact целиком (id и подпись из источника); влияния неизменяемы,
+/// поэтому существующий id не перезаписывается. Возвращает true, если запись добавлена.
+pub fn insert_impact_record(conn: &Connection, im: &Impact) -> Result<bool, CoreError> {
+    let magnitude = im.grade();
+    Impact::check_grade(magnitude, im.confidence)?;
+    let n = audit::track(conn, "impact", "id", &im.id, || {
+        Ok(conn.execute(
+            r#"INSERT OR IGNORE INTO impact
+                (id, event_id, type_id, value, notes, created_at, signature, public_key, magnitude, confidence)
+              VALUES
+                (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"#,
+            params![
+                im.id,
+                im.event_id,
+                im.type_id,
+                if magnitude >= 0.0 { 1 } else { 0 },
+                im.notes,
+                im.created_at,
+                at_rest::seal(conn, "impact", "signature", &im.id, im.signature.as_deref())?,
+                im.public_key,
+                magnitude,
+                im.confidence,
+            ],
+        )?)
+    })?;
+    Ok(n > 0)
+}
+
+/// Получить влияние по id
+pub fn get_impact(conn: &Connection, id: &str) -> Result<Option<Impact>, CoreError> {
+    let mut stmt = conn.prepare(
+        "SELECT id, event_id, type_id, value, notes, created_at, signature, public_key, magnitude, confidence FROM impact WHERE id = ?1",
+    )?;
+    let im = stmt.query_row(params![id], map_impact).optional()?;
+    match im {
+        Some(mut im) => {
+            open_signature(conn, &mut im)?;
+            Ok(Some(im))
+        }
+        None => Ok(None),
+    }
+}
+
 /// Пересчёт агрегатов для progress_metrics (MVP-версия)
 pub fn recalc_progress_metrics(conn: &Connection, ts: i64) -> Result<i64, CoreError> {
     let total_events: i64 =
         conn.query_row("SELECT COUNT(*) FROM truth_events", [], |r| r.get(0))?;
 
     let total_positive_impact: f64 = conn.query_row(
-        "SELECT COALESCE(SUM(CASE WHEN value=1 THEN 1.0 ELSE 0.0 END),0.0) FROM impact",
+        "SELECT COALESCE(SUM(MAX(magnitude, 0.0) * COALESCE(confidence, 1.0)),0.0) FROM impact",
         [],
         |r| r.get(0),
     )?;
 
     let total_negative_impact: f64 = conn.query_row(
-        "SELECT COALESCE(SUM(CASE WHEN value=0 THEN 1.0 ELSE 0.0 END),0.0) FROM impact",
+        "SELECT COALESCE(SUM(MAX(-magnitude, 0.0) * COALESCE(confidence, 1.0)),0.0) FROM impact",
         [],
         |r| r.get(0),
     )?;
@@ -1107,6 +554,9 @@ pub fn recalc_progress_metrics(conn: &Connection, ts: i64) -> Result<i64, CoreEr
 
 /// Пересчёт рейтингов узлов и групп на основе текущего состояния БД
 pub fn recalc_ratings(conn: &Connection, ts: i64) -> Result<(), CoreError> {
+    // 0) Оценки событий по деревьям аргументов: поддержки и опровержения распространяются к корням
+    crate::argument::refresh_scores(conn, ts)?;
+
     // 1) Узлы: очищаем и вставляем агрегаты
     conn.execute("DELETE FROM node_ratings", [])?;
 
@@ -1118,10 +568,9 @@ pub fn recalc_ratings(conn: &Connection, ts: i64) -> Result<(), CoreError> {
             SELECT DISTINCT public_key FROM impact WHERE public_key IS NOT NULL
         ),
         stmt_avg AS (
-            SELECT event_id, AVG(truth_score) AS avg_score
-            FROM statements
-            WHERE truth_score IS NOT NULL
-            GROUP BY event_id
+            SELECT te.id AS event_id, a.score AS avg_score
+            FROM argument_scores a
+            JOIN truth_events te ON te.uid = a.event_uid
         ),
         events_true AS (
             SELECT te.public_key AS node_id, COUNT(*) AS cnt
@@ -1143,12 +592,18 @@ pub fn recalc_ratings(conn: &Connection, ts: i64) -> Result<(), CoreError> {
             WHERE im.public_key IS NOT NULL
             GROUP BY im.public_key
         ),
+        -- Событие переиспользовано, если чужая оценка положительна; бонус — по сильнейшей из них
         reused AS (
-            SELECT te.public_key AS node_id, COUNT(DISTINCT te.id) AS cnt
-            FROM truth_events te
-            JOIN impact im ON CAST(im.event_id AS INTEGER) = te.id AND im.value = 1
-            WHERE te.public_key IS NOT NULL AND im.public_key IS NOT NULL AND im.public_key <> te.public_key
-            GROUP BY te.public_key
+            SELECT node_id, COUNT(*) AS cnt, SUM(support) AS weight
+            FROM (
+                SELECT te.public_key AS node_id, MAX(im.magnitude * COALESCE(im.confidence, 1.0)) AS support
+                FROM truth_events te
+                JOIN impact im ON im.event_id = te.uid
+                WHERE te.public_key IS NOT NULL AND im.public_key IS NOT NULL AND im.public_key <> te.public_key
+                GROUP BY te.id
+            )
+            WHERE support > 0
+            GROUP BY node_id
         )
         INSERT INTO node_ratings (node_id, events_true, events_false, validations, reused_events, trust_score, propagation_priority, last_updated)
         SELECT
@@ -1165,7 +620,7 @@ pub fn recalc_ratings(conn: &Connection, ts: i64) -> Result<(), CoreError> {
                             CAST(COALESCE(et.cnt,0) - COALESCE(ef.cnt,0) AS REAL) /
                             CAST(COALESCE(et.cnt,0) + COALESCE(ef.cnt,0) AS REAL)
                         )
-                        + 0.2 * CAST(COALESCE(r.cnt,0) AS REAL) /
+                        + ?2 * COALESCE(r.weight,0.0) /
                           CAST(CASE WHEN (COALESCE(et.cnt,0) + COALESCE(ef.cnt,0)) = 0 THEN 1 ELSE (COALESCE(et.cnt,0) + COALESCE(ef.cnt,0)) END AS REAL)
                 END
             ) AS trust_score_raw,
@@ -1178,9 +633,12 @@ pub fn recalc_ratings(conn: &Connection, ts: i64) -> Result<(), CoreError> {
         LEFT JOIN reused r        ON r.node_id  = n.node_id
         ;
         "#,
-        params![ts],
+        params![ts, REUSE_BONUS as f64],
     )?;
 
+    // 1b) Делегирования доверия: добавляем действующие дельты (последняя запись на пару delegator→target)
+    apply_trust_delegations(conn, ts)?;
+
     // 2) Обрезаем trust_score в диапазон [-1,1]
     conn.execute(
         r#"
@@ -1194,100 +652,807 @@ pub fn recalc_ratings(conn: &Connection, ts: i64) -> Result<(), CoreError> {
         [],
     )?;
 
+    // Зафиксировать точку истории доверия для каждого узла; старая история прореживается
+    conn.execute(
+        r#"
+        INSERT INTO trust_history (node_id, trust_score, source, recorded_at)
+        SELECT node_id, trust_score, 'recalc', ?1 FROM node_ratings
+        "#,
+        params![ts],
+    )?;
+    prune_trust_history(conn, TrustParams::default().stability_window)?;
+
     // Пересчитать propagation_priority после вставки и нормализации trust_score
     refresh_propagation_priority(conn)?;
 
-    // 3) Группы: пока формируем один глобальный кластер
-    // Список участников
-    let mut stmt_nodes = conn.prepare("SELECT node_id FROM node_ratings ORDER BY node_id")?;
-    let rows = stmt_nodes.query_map([], |row| row.get::<_, String>(0))?;
-    let mut members: Vec<String> = Vec::new();
-    for r in rows { members.push(r?); }
-    let members_json = serde_json::to_string(&members)?;
-
-    // Средний скор
-    let avg_score: f64 = conn.query_row(
-        "SELECT COALESCE(AVG(trust_score), 0.0) FROM node_ratings",
-        [],
-        |r| r.get(0),
+    // 3) Группы: сообщества графа валидатор→автор (label propagation) со стабильными id
+    recalc_group_ratings(conn, ts)?;
+
+    // RBAC: зеркалим trust_score узлов в таблицу users для JWT и API
+    let _ = sync_users_with_node_ratings(conn)?;
+
+    // Поиск аномалий по обновлённым данным
+    detect_anomalies(conn, ts)?;
+
+    Ok(())
+}
+
+/// Пересчёт групп: кластеризация графа валидатор→автор по положительным оценкам impact.
+/// Для каждой группы считаются avg_score (средний trust участников) и coherence —
+/// доля оценок валидаторов группы, совпавших по знаку с оценкой события по дереву аргументов (argument_scores).
+/// Id групп наследуются от предыдущего локального пересчёта при схожем составе.
+/// Заменяются только локально посчитанные группы (origin = 'local'): группы от пиров не перезаписываются
+/// и удаляются, когда не обновлялись дольше REMOTE_GROUP_TTL_SECS.
+fn recalc_group_ratings(conn: &Connection, ts: i64) -> Result<(), CoreError> {
+    let trust: HashMap<String, f32> = load_node_ratings(conn)?
+        .into_iter()
+        .map(|n| (n.node_id, n.trust_score))
+        .collect();
+    let nodes: Vec<String> = trust.keys().cloned().collect();
+
+    // Рёбра: сумма положительных оценок валидатора событиям автора (степень × уверенность)
+    let mut stmt_edges = conn.prepare(
+        r#"
+        SELECT im.public_key, te.public_key, SUM(MAX(im.magnitude, 0.0) * COALESCE(im.confidence, 1.0))
+        FROM impact im
+        JOIN truth_events te ON im.event_id = te.uid
+        WHERE im.public_key IS NOT NULL AND te.public_key IS NOT NULL AND im.public_key <> te.public_key
+        GROUP BY im.public_key, te.public_key
+        "#,
     )?;
+    let edge_rows = stmt_edges.query_map([], |r| {
+        Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, f64>(2)? as f32))
+    })?;
+    let mut edges: Vec<(String, String, f32)> = Vec::new();
+    for e in edge_rows { edges.push(e?); }
+
+    let clusters = label_propagation(&nodes, &edges, LABEL_PROPAGATION_MAX_ITER);
+    let previous = load_group_ratings_by_origin(conn, "local")?;
+    let groups = assign_group_ids(clusters, &previous);
 
-    // Коэффициент согласованности (coherence) валидаторов с агрегированным мнением по событиям
-    // total = кол-во оценок impact по событиям, где есть средний балл по statement
-    // agree = совпадение знака impact.value с знаком среднего truth_score по событию
-    let (total_votes, agree_votes): (i64, i64) = conn.query_row(
+    // Голоса валидаторов: (валидатор, вес голоса = |степень| × уверенность,
+    // согласен ли по знаку с агрегированным мнением по событию)
+    let mut stmt_votes = conn.prepare(
         r#"
         WITH stmt_avg AS (
-            SELECT event_id, AVG(truth_score) AS avg_score
-            FROM statements
-            WHERE truth_score IS NOT NULL
-            GROUP BY event_id
+            SELECT te.id AS event_id, a.score AS avg_score
+            FROM argument_scores a
+            JOIN truth_events te ON te.uid = a.event_uid
         )
-        SELECT
-            COUNT(*) AS total_votes,
-            SUM(
-                CASE
-                    WHEN sa.avg_score IS NULL THEN 0
-                    WHEN (sa.avg_score >= 0.0 AND im.value = 1) OR (sa.avg_score < 0.0 AND im.value = 0)
-                        THEN 1 ELSE 0
-                END
-            ) AS agree_votes
+        SELECT im.public_key,
+               ABS(im.magnitude) * COALESCE(im.confidence, 1.0),
+               CASE
+                   WHEN (sa.avg_score >= 0.0 AND im.magnitude >= 0.0) OR (sa.avg_score < 0.0 AND im.magnitude < 0.0)
+                       THEN 1 ELSE 0
+               END
         FROM impact im
-        JOIN stmt_avg sa ON CAST(im.event_id AS INTEGER) = sa.event_id
+        JOIN truth_events te ON te.uid = im.event_id
+        JOIN stmt_avg sa ON sa.event_id = te.id
         WHERE im.public_key IS NOT NULL
         "#,
-        [],
-        |r| Ok((r.get(0)?, r.get(1)?)),
     )?;
+    let vote_rows = stmt_votes.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, f64>(1)?, r.get::<_, i64>(2)? != 0)))?;
+    let mut votes: HashMap<String, (f64, f64)> = HashMap::new(); // validator -> (total, agree)
+    for v in vote_rows {
+        let (validator, weight, agree) = v?;
+        let e = votes.entry(validator).or_insert((0.0, 0.0));
+        e.0 += weight;
+        if agree {
+            e.1 += weight;
+        }
+    }
+
+    conn.execute("DELETE FROM group_ratings WHERE origin = 'local'", [])?;
+    conn.execute(
+        "DELETE FROM group_ratings WHERE origin = 'remote' AND last_updated < ?1",
+        params![ts - REMOTE_GROUP_TTL_SECS],
+    )?;
+    // Группа с тем же id от пира остаётся как есть
+    let mut ins = conn.prepare(
+        r#"
+        INSERT INTO group_ratings (group_id, members, avg_score, coherence, last_updated, origin)
+        VALUES (?1, ?2, ?3, ?4, ?5, 'local')
+        ON CONFLICT(group_id) DO NOTHING
+        "#,
+    )?;
+    for (group_id, members) in groups {
+        let avg_score: f64 = if members.is_empty() {
+            0.0
+        } else {
+            members.iter().map(|m| trust.get(m).copied().unwrap_or(0.0) as f64).sum::<f64>() / members.len() as f64
+        };
+        let (total_votes, agree_votes) = members
+            .iter()
+            .filter_map(|m| votes.get(m))
+            .fold((0.0_f64, 0.0_f64), |acc, v| (acc.0 + v.0, acc.1 + v.1));
+        let coherence: f64 = if total_votes > 0.0 {
+            agree_votes / total_votes
+        } else { 0.0 };
+        let members_json = serde_json::to_string(&members)?;
+        ins.execute(params![group_id, members_json, avg_score, coherence, ts])?;
+    }
+    Ok(())
+}
+
+/* =========================
+Trust delegations
+========================= */
+
+/// Максимальная величина одной дельты делегирования
+pub const MAX_DELEGATION_DELTA: f32 = 0.2;
+
+/// Сохранить делегирование (локальное или полученное от пира).
+/// Новая запись вставляется; для существующей принимается только отзыв.
+/// Возвращает true, если состояние таблицы изменилось. Подпись проверяется вызывающей стороной.
+pub fn upsert_trust_delegation(conn: &Connection, d: &crate::models::TrustDelegation) -> Result<bool, CoreError> {
+    if d.delta.abs() > MAX_DELEGATION_DELTA || d.delegator == d.target {
+        return Err(CoreError::InvalidArg(format!("invalid delegation {}", d.id)));
+    }
+    match get_trust_delegation(conn, &d.id)? {
+        None => audit::track(conn, "trust_delegations", "id", &d.id, || {
+            conn.execute(
+                r#"INSERT INTO trust_delegations
+                    (id, delegator, target, delta, created_at, expires_at, signature, revoked_at, revocation_signature)
+                   VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"#,
+                params![
+                    d.id,
+                    d.delegator,
+                    d.target,
+                    d.delta as f64,
+                    d.created_at,
+                    d.expires_at,
+                    d.signature,
+                    d.revoked_at,
+                    d.revocation_signature,
+                ],
+            )?;
+            Ok(true)
+        }),
+        Some(local) => match (local.revoked_at, d.revoked_at, d.revocation_signature.as_deref()) {
+            (None, Some(revoked_at), Some(sig)) => {
+                revoke_trust_delegation(conn, &d.id, revoked_at, sig)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        },
+    }
+}
+
+/// Получить делегирование по id
+pub fn get_trust_delegation(conn: &Connection, id: &str) -> Result<Option<crate::models::TrustDelegation>, CoreError> {
+    let mut stmt = conn.prepare(
+        r#"SELECT id, delegator, target, delta, created_at, expires_at, signature, revoked_at, revocation_signature
+           FROM trust_delegations WHERE id = ?1"#,
+    )?;
+    let d = stmt.query_row(params![id], map_trust_delegation).optional()?;
+    Ok(d)
+}
+
+/// Загрузить делегирования (новые первыми). active_at: только действующие на момент ts
+pub fn load_trust_delegations(
+    conn: &Connection,
+    active_at: Option<i64>,
+) -> Result<Vec<crate::models::TrustDelegation>, CoreError> {
+    let mut stmt = conn.prepare(
+        r#"SELECT id, delegator, target, delta, created_at, expires_at, signature, revoked_at, revocation_signature
+           FROM trust_delegations
+           ORDER BY created_at DESC, id"#,
+    )?;
+    let rows = stmt.query_map([], map_trust_delegation)?;
+    let mut out = Vec::new();
+    for r in rows {
+        let d = r?;
+        if active_at.map(|ts| d.is_active(ts)).unwrap_or(true) {
+            out.push(d);
+        }
+    }
+    Ok(out)
+}
+
+/// Отозвать делегирование (подпись отзыва сохраняется для распространения)
+pub fn revoke_trust_delegation(conn: &Connection, id: &str, revoked_at: i64, signature: &str) -> Result<(), CoreError> {
+    audit::track(conn, "trust_delegations", "id", id, || {
+        let n = conn.execute(
+            "UPDATE trust_delegations SET revoked_at=?2, revocation_signature=?3 WHERE id=?1 AND revoked_at IS NULL",
+            params![id, revoked_at, signature],
+        )?;
+        if n == 0 {
+            return Err(CoreError::NotFound(format!("active delegation {}", id)));
+        }
+        Ok(())
+    })
+}
+
+fn map_trust_delegation(r: &rusqlite::Row<'_>) -> rusqlite::Result<crate::models::TrustDelegation> {
+    Ok(crate::models::TrustDelegation {
+        id: r.get(0)?,
+        delegator: r.get(1)?,
+        target: r.get(2)?,
+        delta: r.get::<_, f64>(3)? as f32,
+        created_at: r.get(4)?,
+        expires_at: r.get(5)?,
+        signature: r.get(6)?,
+        revoked_at: r.get(7)?,
+        revocation_signature: r.get(8)?,
+    })
+}
+
+/// Применить действующие делегирования к node_ratings (вызывается из recalc_ratings до обрезки).
+/// На каждую пару delegator→target учитывается только последняя действующая запись.
+/// Учитываются только известные узлы с trust >= REMOTE_TRUST_FLOOR; дельта взвешивается trust делегирующего,
+/// а сумма по получателю ограничена ±MAX_DELEGATION_DELTA — множество новых ключей не сдвигает доверие.
+/// Узлы-получатели без собственной активности добавляются с нулевыми счётчиками.
+fn apply_trust_delegations(conn: &Connection, ts: i64) -> Result<(), CoreError> {
+    let mut latest: HashMap<(String, String), crate::models::TrustDelegation> = HashMap::new();
+    for d in load_trust_delegations(conn, Some(ts))? {
+        // load_trust_delegations отдаёт новые первыми
+        latest.entry((d.delegator.clone(), d.target.clone())).or_insert(d);
+    }
+    let mut per_target: HashMap<String, f32> = HashMap::new();
+    for ((delegator, target), d) in latest {
+        let Some(trust) = delegator_trust(conn, &delegator)? else { continue };
+        let delta = d.delta.clamp(-MAX_DELEGATION_DELTA, MAX_DELEGATION_DELTA) * trust.clamp(0.0, 1.0);
+        *per_target.entry(target).or_insert(0.0) += delta;
+    }
+    for (target, delta) in per_target {
+        let delta = delta.clamp(-MAX_DELEGATION_DELTA, MAX_DELEGATION_DELTA);
+        if delta == 0.0 {
+            continue;
+        }
+        conn.execute(
+            r#"INSERT OR IGNORE INTO node_ratings (node_id, events_true, events_false, validations, reused_events, trust_score, propagation_priority, last_updated)
+               VALUES (?1, 0, 0, 0, 0, 0.0, 0.0, ?2)"#,
+            params![target, ts],
+        )?;
+        conn.execute(
+            "UPDATE node_ratings SET trust_score = trust_score + ?2 WHERE node_id = ?1",
+            params![target, delta as f64],
+        )?;
+    }
+    Ok(())
+}
+
+/// trust_score делегирующего узла, если он известен и не ниже REMOTE_TRUST_FLOOR
+pub fn delegator_trust(conn: &Connection, delegator: &str) -> Result<Option<f32>, CoreError> {
+    let trust: Option<f64> = conn
+        .query_row("SELECT trust_score FROM node_ratings WHERE node_id = ?1", [delegator], |r| r.get(0))
+        .optional()?;
+    Ok(trust.map(|t| t as f32).filter(|t| *t >= REMOTE_TRUST_FLOOR))
+}
+
+/* =========================
+Tombstones
+========================= */
+
+/// Сохранить отметку удаления; false — отметка с таким id уже есть.
+/// Подпись и право автора проверяет вызывающая сторона (см. tombstone::apply).
+pub fn insert_tombstone(conn: &Connection, t: &crate::models::Tombstone) -> Result<bool, CoreError> {
+    let n = audit::track(conn, "tombstones", "id", &t.id, || {
+        Ok(conn.execute(
+            r#"INSERT OR IGNORE INTO tombstones (id, table_name, record_id, author, reason, deleted_at, signature)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+            params![t.id, t.table_name, t.record_id, t.author, t.reason, t.deleted_at, t.signature],
+        )?)
+    })?;
+    Ok(n > 0)
+}
+
+/// Отметки удаления записи, старые первыми
+pub fn get_tombstones_for(conn: &Connection, table: &str, record_id: &str) -> Result<Vec<crate::models::Tombstone>, CoreError> {
+    let mut stmt = conn.prepare(
+        r#"SELECT id, table_name, record_id, author, reason, deleted_at, signature
+           FROM tombstones WHERE table_name = ?1 AND record_id = ?2
+           ORDER BY deleted_at, id"#,
+    )?;
+    let rows = stmt.query_map(params![table, record_id], map_tombstone)?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Загрузить отметки удаления (новые первыми); since — только с deleted_at >= since
+pub fn load_tombstones(conn: &Connection, since: Option<i64>) -> Result<Vec<crate::models::Tombstone>, CoreError> {
+    let mut stmt = conn.prepare(
+        r#"SELECT id, table_name, record_id, author, reason, deleted_at, signature
+           FROM tombstones WHERE deleted_at >= ?1
+           ORDER BY deleted_at DESC, id"#,
+    )?;
+    let rows = stmt.query_map(params![since.unwrap_or(i64::MIN)], map_tombstone)?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Удалить запись вместе с зависимыми: у события — его утверждения, влияния, доказательства и связи,
+/// у утверждения — его доказательства (блобы остаются в хранилище).
+/// record_id событий и утверждений — uid, влияний — id. Возвращает общее число удалённых строк.
+pub fn delete_record(conn: &Connection, table: &str, record_id: &str) -> Result<usize, CoreError> {
+    let tx = conn.unchecked_transaction()?;
+    // Удаляемые строки с хешами — для журнала аудита
+    let mut doomed: Vec<(&str, String, Option<String>)> = Vec::new();
+    let mut collect = |t: &'static str, key_col: &str, sql: &str| -> Result<(), CoreError> {
+        let ids: Vec<String> = tx.prepare(sql)?.query_map(params![record_id], |r| r.get(0))?.collect::<Result<_, _>>()?;
+        for id in ids {
+            let hash = audit::row_hash(&tx, t, key_col, &id)?;
+            doomed.push((t, id, hash));
+        }
+        Ok(())
+    };
+    match table {
+        "truth_events" => {
+            collect("evidence", "id", &format!("SELECT id FROM evidence WHERE {}", EVENT_EVIDENCE))?;
+            collect("event_relations", "id", "SELECT id FROM event_relations WHERE source_uid = ?1 OR target_uid = ?1")?;
+            collect("statements", "uid", "SELECT s.uid FROM statements s JOIN truth_events e ON e.id = s.event_id WHERE e.uid = ?1")?;
+            collect("impact", "id", "SELECT id FROM impact WHERE event_id = ?1")?;
+            collect("truth_events", "uid", "SELECT uid FROM truth_events WHERE uid = ?1")?;
+        }
+        "statements" => {
+            collect("evidence", "id", "SELECT id FROM evidence WHERE target_table = 'statements' AND target_uid = ?1")?;
+            collect("statements", "uid", "SELECT uid FROM statements WHERE uid = ?1")?;
+        }
+        "impact" => collect("impact", "id", "SELECT id FROM impact WHERE id = ?1")?,
+        _ => {}
+    }
+    let n = match table {
+        "truth_events" => {
+            tx.execute(&format!("DELETE FROM evidence WHERE {}", EVENT_EVIDENCE), params![record_id])?
+                + tx.execute("DELETE FROM event_relations WHERE source_uid = ?1 OR target_uid = ?1", params![record_id])?
+                + tx.execute("DELETE FROM statements WHERE event_id IN (SELECT id FROM truth_events WHERE uid = ?1)", params![record_id])?
+                + tx.execute("DELETE FROM impact WHERE event_id = ?1", params![record_id])?
+                + tx.execute("DELETE FROM truth_events WHERE uid = ?1", params![record_id])?
+        }
+        "statements" => {
+            tx.execute("DELETE FROM evidence WHERE target_table = 'statements' AND target_uid = ?1", params![record_id])?
+                + tx.execute("DELETE FROM statements WHERE uid = ?1", params![record_id])?
+        }
+        "impact" => tx.execute("DELETE FROM impact WHERE id = ?1", params![record_id])?,
+        other => return Err(CoreError::InvalidArg(format!("records of {} cannot be deleted", other))),
+    };
+    for (t, id, hash) in doomed {
+        audit::record(&tx, t, &id, hash, None)?;
+    }
+    tx.commit()?;
+    Ok(n)
+}
+
+fn map_tombstone(r: &rusqlite::Row<'_>) -> rusqlite::Result<crate::models::Tombstone> {
+    Ok(crate::models::Tombstone {
+        id: r.get(0)?,
+        table_name: r.get(1)?,
+        record_id: r.get(2)?,
+        author: r.get(3)?,
+        reason: r.get(4)?,
+        deleted_at: r.get(5)?,
+        signature: r.get(6)?,
+    })
+}
+
+/* =========================
+Evidence
+========================= */
+
+/// Условие на доказательства события ?1 (uid) и его утверждений
+const EVENT_EVIDENCE: &str = "(target_table = 'truth_events' AND target_uid = ?1)
+    OR (target_table = 'statements' AND target_uid IN
+        (SELECT s.uid FROM statements s JOIN truth_events e ON e.id = s.event_id WHERE e.uid = ?1))";
+
+const EVIDENCE_COLUMNS: &str =
+    "id, target_table, target_uid, kind, sha256, name, media_type, size, note, author, created_at, signature";
+
+fn map_evidence(r: &rusqlite::Row<'_>) -> rusqlite::Result<Evidence> {
+    Ok(Evidence {
+        id: r.get(0)?,
+        target_table: r.get(1)?,
+        target_uid: r.get(2)?,
+        kind: r.get(3)?,
+        sha256: r.get(4)?,
+        name: r.get(5)?,
+        media_type: r.get(6)?,
+        size: r.get(7)?,
+        note: r.get(8)?,
+        author: r.get(9)?,
+        created_at: r.get(10)?,
+        signature: r.get(11)?,
+    })
+}
+
+/// Сохранить доказательство; false — уже известно
+pub fn insert_evidence(conn: &Connection, e: &Evidence) -> Result<bool, CoreError> {
+    let n = audit::track(conn, "evidence", "id", &e.id, || {
+        Ok(conn.execute(
+            &format!("INSERT OR IGNORE INTO evidence ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)", EVIDENCE_COLUMNS),
+            params![
+                e.id,
+                e.target_table,
+                e.target_uid,
+                e.kind,
+                e.sha256,
+                e.name,
+                e.media_type,
+                e.size,
+                e.note,
+                e.author,
+                e.created_at,
+                e.signature
+            ],
+        )?)
+    })?;
+    Ok(n > 0)
+}
+
+pub fn get_evidence(conn: &Connection, id: &str) -> Result<Option<Evidence>, CoreError> {
+    Ok(conn
+        .query_row(&format!("SELECT {} FROM evidence WHERE id = ?1", EVIDENCE_COLUMNS), params![id], map_evidence)
+        .optional()?)
+}
+
+/// Доказательства события (uid) вместе с доказательствами его утверждений, старые первыми
+pub fn evidence_for_event(conn: &Connection, event_uid: &str) -> Result<Vec<Evidence>, CoreError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM evidence WHERE {} ORDER BY created_at, id",
+        EVIDENCE_COLUMNS, EVENT_EVIDENCE
+    ))?;
+    let rows = stmt.query_map(params![event_uid], map_evidence)?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Загрузить доказательства (новые первыми); since — только с created_at >= since
+pub fn load_evidence(conn: &Connection, since: Option<i64>) -> Result<Vec<Evidence>, CoreError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM evidence WHERE created_at >= ?1 ORDER BY created_at DESC, id",
+        EVIDENCE_COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![since.unwrap_or(i64::MIN)], map_evidence)?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/* =========================
+Event relations
+========================= */
+
+const EVENT_RELATION_COLUMNS: &str = "id, source_uid, target_uid, relation, note, author, created_at, signature";
+
+fn map_event_relation(r: &rusqlite::Row<'_>) -> rusqlite::Result<EventRelation> {
+    Ok(EventRelation {
+        id: r.get(0)?,
+        source_uid: r.get(1)?,
+        target_uid: r.get(2)?,
+        relation: r.get(3)?,
+        note: r.get(4)?,
+        author: r.get(5)?,
+        created_at: r.get(6)?,
+        signature: r.get(7)?,
+    })
+}
+
+/// Сохранить связь между событиями; false — уже известна
+pub fn insert_event_relation(conn: &Connection, rel: &EventRelation) -> Result<bool, CoreError> {
+    let n = audit::track(conn, "event_relations", "id", &rel.id, || {
+        Ok(conn.execute(
+            &format!("INSERT OR IGNORE INTO event_relations ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)", EVENT_RELATION_COLUMNS),
+            params![rel.id, rel.source_uid, rel.target_uid, rel.relation, rel.note, rel.author, rel.created_at, rel.signature],
+        )?)
+    })?;
+    Ok(n > 0)
+}
+
+pub fn get_event_relation(conn: &Connection, id: &str) -> Result<Option<EventRelation>, CoreError> {
+    Ok(conn
+        .query_row(&format!("SELECT {} FROM event_relations WHERE id = ?1", EVENT_RELATION_COLUMNS), params![id], map_event_relation)
+        .optional()?)
+}
+
+/// Связи, в которых событие (uid) — источник или цель, старые первыми
+pub fn relations_for_event(conn: &Connection, event_uid: &str) -> Result<Vec<EventRelation>, CoreError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM event_relations WHERE source_uid = ?1 OR target_uid = ?1 ORDER BY created_at, id",
+        EVENT_RELATION_COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![event_uid], map_event_relation)?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Загрузить связи (новые первыми); since — только с created_at >= since
+pub fn load_event_relations(conn: &Connection, since: Option<i64>) -> Result<Vec<EventRelation>, CoreError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM event_relations WHERE created_at >= ?1 ORDER BY created_at DESC, id",
+        EVENT_RELATION_COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![since.unwrap_or(i64::MIN)], map_event_relation)?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Перенести утверждения и влияния события from_uid в событие to_uid (слияние дубликатов).
+/// updated_at не меняется: пиры сливают те же записи сами. Возвращает число перенесённых строк.
+pub fn move_event_records(conn: &Connection, from_uid: &str, to_uid: &str) -> Result<usize, CoreError> {
+    let to_id: i64 = conn
+        .query_row("SELECT id FROM truth_events WHERE uid = ?1", params![to_uid], |r| r.get(0))
+        .optional()?
+        .ok_or_else(|| CoreError::NotFound(format!("event {}", to_uid)))?;
+    let statements: Vec<String> = conn
+        .prepare("SELECT s.uid FROM statements s JOIN truth_events e ON e.id = s.event_id WHERE e.uid = ?1")?
+        .query_map(params![from_uid], |r| r.get(0))?
+        .collect::<Result<_, _>>()?;
+    for uid in &statements {
+        audit::track(conn, "statements", "uid", uid, || {
+            Ok(conn.execute("UPDATE statements SET event_id = ?2 WHERE uid = ?1", params![uid, to_id])?)
+        })?;
+    }
+    let impacts: Vec<String> = conn
+        .prepare("SELECT id FROM impact WHERE event_id = ?1")?
+        .query_map(params![from_uid], |r| r.get(0))?
+        .collect::<Result<_, _>>()?;
+    for id in &impacts {
+        audit::track(conn, "impact", "id", id, || {
+            Ok(conn.execute("UPDATE impact SET event_id = ?2 WHERE id = ?1", params![id, to_uid])?)
+        })?;
+    }
+    Ok(statements.len() + impacts.len())
+}
+
+/* =========================
+Knowledge base
+========================= */
+
+/// Колонки записи базы знаний в порядке map_kb_entry; отсутствующие у вида поля — NULL
+fn kb_columns(kind: &str) -> Result<&'static str, CoreError> {
+    Ok(match kind {
+        "category" | "impact_type" => "id, name, description, NULL, NULL, NULL, NULL, NULL, NULL, updated_at, deprecated_at, updated_by, signature",
+        "cause" | "develop" | "effect" | "forma" => {
+            "id, name, description, quality, NULL, NULL, NULL, NULL, NULL, updated_at, deprecated_at, updated_by, signature"
+        }
+        "context" => {
+            "id, name, description, NULL, category_id, forma_id, cause_id, develop_id, effect_id, updated_at, deprecated_at, updated_by, signature"
+        }
+        other => return Err(CoreError::InvalidArg(format!("unknown knowledge base kind: {}", other))),
+    })
+}
+
+fn map_kb_entry(kind: &str, r: &rusqlite::Row<'_>) -> rusqlite::Result<KbEntry> {
+    Ok(KbEntry {
+        kind: kind.to_string(),
+        id: r.get(0)?,
+        name: r.get(1)?,
+        description: r.get(2)?,
+        quality: r.get::<_, Option<i64>>(3)?.map(|q| q != 0),
+        category_id: r.get(4)?,
+        forma_id: r.get(5)?,
+        cause_id: r.get(6)?,
+        develop_id: r.get(7)?,
+        effect_id: r.get(8)?,
+        updated_at: r.get(9)?,
+        deprecated_at: r.get(10)?,
+        updated_by: r.get(11)?,
+        signature: r.get(12)?,
+    })
+}
+
+/// Записи базы знаний вида kind по id; устаревшие — только с include_deprecated
+pub fn load_kb_entries(conn: &Connection, kind: &str, include_deprecated: bool) -> Result<Vec<KbEntry>, CoreError> {
+    let sql = format!(
+        "SELECT {} FROM {} WHERE ?1 OR deprecated_at IS NULL ORDER BY id",
+        kb_columns(kind)?,
+        kind
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params![include_deprecated], |r| map_kb_entry(kind, r))?;
+    Ok(rows.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Получить запись базы знаний
+pub fn get_kb_entry(conn: &Connection, kind: &str, id: i64) -> Result<Option<KbEntry>, CoreError> {
+    let sql = format!("SELECT {} FROM {} WHERE id = ?1", kb_columns(kind)?, kind);
+    let mut stmt = conn.prepare(&sql)?;
+    Ok(stmt.query_row(params![id], |r| map_kb_entry(kind, r)).optional()?)
+}
+
+/// Вставить или заменить запись базы знаний. Проверки и подпись — в knowledge::apply
+pub fn upsert_kb_entry(conn: &Connection, e: &KbEntry) -> Result<(), CoreError> {
+    kb_columns(&e.kind)?;
+    audit::track(conn, &e.kind, "id", &e.id.to_string(), || write_kb_entry(conn, e))
+}
+
+fn write_kb_entry(conn: &Connection, e: &KbEntry) -> Result<(), CoreError> {
+    let meta = params![e.id, e.name, e.description, e.updated_at, e.deprecated_at, e.updated_by, e.signature];
+    let common = "updated_at=excluded.updated_at, deprecated_at=excluded.deprecated_at, updated_by=excluded.updated_by, signature=excluded.signature";
+    match e.kind.as_str() {
+        "category" | "impact_type" => conn.execute(
+            &format!(
+                "INSERT INTO {} (id, name, description, updated_at, deprecated_at, updated_by, signature) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                 ON CONFLICT(id) DO UPDATE SET name=excluded.name, description=excluded.description, {}",
+                e.kind, common
+            ),
+            meta,
+        )?,
+        "cause" | "develop" | "effect" | "forma" => conn.execute(
+            &format!(
+                "INSERT INTO {} (id, name, description, updated_at, deprecated_at, updated_by, signature, quality) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                 ON CONFLICT(id) DO UPDATE SET name=excluded.name, description=excluded.description, quality=excluded.quality, {}",
+                e.kind, common
+            ),
+            params![e.id, e.name, e.description, e.updated_at, e.deprecated_at, e.updated_by, e.signature, e.quality.unwrap_or(false)],
+        )?,
+        "context" => conn.execute(
+            &format!(
+                "INSERT INTO context (id, name, description, updated_at, deprecated_at, updated_by, signature,
+                     category_id, forma_id, cause_id, develop_id, effect_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+                 ON CONFLICT(id) DO UPDATE SET name=excluded.name, description=excluded.description,
+                     category_id=excluded.category_id, forma_id=excluded.forma_id, cause_id=excluded.cause_id,
+                     develop_id=excluded.develop_id, effect_id=excluded.effect_id, {}",
+                common
+            ),
+            params![
+                e.id, e.name, e.description, e.updated_at, e.deprecated_at, e.updated_by, e.signature,
+                e.category_id, e.forma_id, e.cause_id, e.develop_id, e.effect_id
+            ],
+        )?,
+        other => return Err(CoreError::InvalidArg(format!("unknown knowledge base kind: {}", other))),
+    };
+    Ok(())
+}
+
+/// Подписанные правки базы знаний всех видов (updated_at > 0, не раньше since) в порядке KbEntry::KINDS
+pub fn load_kb_changes(conn: &Connection, since: Option<i64>) -> Result<Vec<KbEntry>, CoreError> {
+    let mut out = Vec::new();
+    for kind in KbEntry::KINDS {
+        let sql = format!(
+            "SELECT {} FROM {} WHERE updated_at > 0 AND updated_at >= ?1 ORDER BY updated_at, id",
+            kb_columns(kind)?,
+            kind
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params![since.unwrap_or(0)], |r| map_kb_entry(kind, r))?;
+        for r in rows {
+            out.push(r?);
+        }
+    }
+    Ok(out)
+}
+
+/// Сколько записей ссылается на запись базы знаний: событий — на context,
+/// действующих контекстов — на category/cause/develop/effect/forma, влияний — на impact_type
+pub fn kb_usage(conn: &Connection, kind: &str, id: i64) -> Result<i64, CoreError> {
+    let sql = match kind {
+        "context" => "SELECT COUNT(*) FROM truth_events WHERE context_id = ?1".to_string(),
+        "impact_type" => "SELECT COUNT(*) FROM impact WHERE type_id = ?1".to_string(),
+        "category" | "cause" | "develop" | "effect" | "forma" => {
+            format!("SELECT COUNT(*) FROM context WHERE {}_id = ?1 AND deprecated_at IS NULL", kind)
+        }
+        other => return Err(CoreError::InvalidArg(format!("unknown knowledge base kind: {}", other))),
+    };
+    Ok(conn.query_row(&sql, params![id], |r| r.get(0))?)
+}
 
-    let coherence: f64 = if total_votes > 0 {
-        (agree_votes as f64) / (total_votes as f64)
-    } else { 0.0 };
+/// Сохранить вопрос экспертной оценки (ключ — key)
+pub fn upsert_expert_question(conn: &Connection, q: &crate::expert_simple::StoredQuestion) -> Result<(), CoreError> {
+    let context_ids = q.context_ids.as_ref().map(serde_json::to_string).transpose()?;
+    // Вес хранится кратчайшей десятичной записью (0.12, а не 0.11999999731779099), чтобы переживать выгрузку в JSON
+    let weight: f64 = q.question.weight.to_string().parse().unwrap_or_default();
+    audit::track(conn, "expert_questions", "key", &q.question.id, || {
+        conn.execute(
+            r#"INSERT INTO expert_questions (key, text, kind, weight, truth_bias, context_ids, pack)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+               ON CONFLICT(key) DO UPDATE SET text=excluded.text, kind=excluded.kind, weight=excluded.weight,
+                   truth_bias=excluded.truth_bias, context_ids=excluded.context_ids, pack=excluded.pack"#,
+            params![
+                q.question.id,
+                q.question.text,
+                q.question.kind.as_str(),
+                weight,
+                q.question.truth_bias,
+                context_ids,
+                q.pack
+            ],
+        )?;
+        Ok(())
+    })
+}
 
-    // UPSERT глобальной группы
-    conn.execute(
-        r#"
-        INSERT INTO group_ratings (group_id, members, avg_score, coherence, last_updated)
-        VALUES ('global', ?1, ?2, ?3, ?4)
-        ON CONFLICT(group_id) DO UPDATE SET
-            members = excluded.members,
-            avg_score = excluded.avg_score,
-            coherence = excluded.coherence,
-            last_updated = excluded.last_updated
-        "#,
-        params![members_json, avg_score, coherence, ts],
+/// Все вопросы экспертной оценки по key
+pub fn load_expert_questions(conn: &Connection) -> Result<Vec<crate::expert_simple::StoredQuestion>, CoreError> {
+    use crate::expert_simple::{Question, QuestionKind, StoredQuestion};
+    let mut stmt = conn.prepare(
+        "SELECT key, text, kind, weight, truth_bias, context_ids, pack FROM expert_questions ORDER BY rowid",
     )?;
+    let rows = stmt.query_map([], |r| {
+        Ok((
+            r.get::<_, String>(0)?,
+            r.get::<_, String>(1)?,
+            r.get::<_, String>(2)?,
+            r.get::<_, f64>(3)?,
+            r.get::<_, bool>(4)?,
+            r.get::<_, Option<String>>(5)?,
+            r.get::<_, Option<String>>(6)?,
+        ))
+    })?;
+    let mut out = Vec::new();
+    for row in rows {
+        let (id, text, kind, weight, truth_bias, context_ids, pack) = row?;
+        let kind = QuestionKind::parse(&kind)
+            .ok_or_else(|| CoreError::InvalidArg(format!("question {}: unknown kind {}", id, kind)))?;
+        out.push(StoredQuestion {
+            question: Question { id, text, kind, weight: weight as f32, truth_bias },
+            context_ids: context_ids.as_deref().map(serde_json::from_str).transpose()?,
+            pack,
+        });
+    }
+    Ok(out)
+}
 
-    // RBAC: зеркалим trust_score узлов в таблицу users для JWT и API
-    let _ = sync_users_with_node_ratings(conn)?;
+/// Вопросы для контекста: общие и привязанные к нему. Пусто, если пакеты с вопросами не загружались
+pub fn load_questions_for_context(conn: &Connection, context_id: i64) -> Result<Vec<crate::expert_simple::Question>, CoreError> {
+    Ok(load_expert_questions(conn)?
+        .into_iter()
+        .filter(|q| q.context_ids.as_ref().is_none_or(|ids| ids.contains(&context_id)))
+        .map(|q| q.question)
+        .collect())
+}
 
-    Ok(())
+/// Получить группу по id
+pub fn get_group_rating(conn: &Connection, group_id: &str) -> Result<Option<GroupRating>, CoreError> {
+    Ok(load_group_ratings(conn)?.into_iter().find(|g| g.group_id == group_id))
+}
+
+/// Соответствие node_id → group_id по текущим group_ratings
+pub fn load_node_groups(conn: &Connection) -> Result<HashMap<String, String>, CoreError> {
+    let mut out = HashMap::new();
+    for g in load_group_ratings(conn)? {
+        for m in g.members {
+            out.insert(m, g.group_id.clone());
+        }
+    }
+    Ok(out)
 }
 
-/// Пересчёт коллективной оценки истинности событий на основе оценок impact
+/// Пересчёт коллективной оценки истинности событий на основе оценок impact:
+/// среднее степеней, приведённых к 0..1, с весами-уверенностями
 pub fn recalc_collective_truth(conn: &Connection) -> Result<(), CoreError> {
     let mut stmt = conn.prepare(
         r#"
-        SELECT CAST(event_id AS INTEGER) AS event_id, AVG(CASE WHEN value=1 THEN 1.0 ELSE 0.0 END) as avg_score
+        SELECT event_id,
+               SUM((magnitude + 1.0) / 2.0 * COALESCE(confidence, 1.0)) / SUM(COALESCE(confidence, 1.0)) as avg_score
         FROM impact
         GROUP BY event_id
+        HAVING SUM(COALESCE(confidence, 1.0)) > 0
         "#,
     )?;
     let rows = stmt.query_map([], |row| {
-        let event_id: i64 = row.get(0)?;
+        let event_id: String = row.get(0)?;
         let avg_score: f64 = row.get(1)?;
         Ok((event_id, avg_score))
     })?;
     for r in rows {
         let (event_id, avg_score) = r?;
         conn.execute(
-            "UPDATE truth_events SET collective_score = ?1 WHERE id = ?2",
+            "UPDATE truth_events SET collective_score = ?1 WHERE uid = ?2",
             params![avg_score, event_id],
         )?;
     }
     Ok(())
 }
 
+/// Записать рейтинг узла как есть (без смешивания; для смешивания — merge_ratings_from)
+pub fn upsert_node_rating(conn: &Connection, r: &NodeRating) -> Result<(), CoreError> {
+    conn.execute(
+        r#"INSERT INTO node_ratings (node_id, events_true, events_false, validations, reused_events, trust_score, propagation_priority, last_updated)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+           ON CONFLICT(node_id) DO UPDATE SET
+             events_true=excluded.events_true, events_false=excluded.events_false, validations=excluded.validations,
+             reused_events=excluded.reused_events, trust_score=excluded.trust_score,
+             propagation_priority=excluded.propagation_priority, last_updated=excluded.last_updated"#,
+        params![r.node_id, r.events_true, r.events_false, r.validations, r.reused_events, r.trust_score, r.propagation_priority, r.last_updated],
+    )?;
+    Ok(())
+}
+
+/// Записать рейтинг группы как есть
+pub fn upsert_group_rating(conn: &Connection, g: &GroupRating) -> Result<(), CoreError> {
+    conn.execute(
+        r#"INSERT INTO group_ratings (group_id, members, avg_score, coherence, last_updated)
+           VALUES (?1, ?2, ?3, ?4, ?5)
+           ON CONFLICT(group_id) DO UPDATE SET
+             members=excluded.members, avg_score=excluded.avg_score, coherence=excluded.coherence, last_updated=excluded.last_updated"#,
+        params![g.group_id, serde_json::to_string(&g.members)?, g.avg_score, g.coherence, g.last_updated],
+    )?;
+    Ok(())
+}
+
 /// Загрузить рейтинги узлов
 pub fn load_node_ratings(conn: &Connection) -> Result<Vec<NodeRating>, CoreError> {
     let mut stmt = conn.prepare(
@@ -1312,10 +1477,20 @@ pub fn load_node_ratings(conn: &Connection) -> Result<Vec<NodeRating>, CoreError
 
 /// Загрузить рейтинги групп
 pub fn load_group_ratings(conn: &Connection) -> Result<Vec<GroupRating>, CoreError> {
-    let mut stmt = conn.prepare(
-        "SELECT group_id, members, avg_score, coherence, last_updated FROM group_ratings ORDER BY group_id",
-    )?;
-    let rows = stmt.query_map([], |row| {
+    load_group_ratings_where(conn, "1", [])
+}
+
+/// Группы одного происхождения: 'local' — посчитанные этим узлом, 'remote' — полученные от пиров
+pub fn load_group_ratings_by_origin(conn: &Connection, origin: &str) -> Result<Vec<GroupRating>, CoreError> {
+    load_group_ratings_where(conn, "origin = ?1", [origin])
+}
+
+fn load_group_ratings_where(conn: &Connection, filter: &str, args: impl rusqlite::Params) -> Result<Vec<GroupRating>, CoreError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT group_id, members, avg_score, coherence, last_updated FROM group_ratings WHERE {} ORDER BY group_id",
+        filter
+    ))?;
+    let rows = stmt.query_map(args, |row| {
         let members_json: String = row.get(1)?;
         let members: Vec<String> = serde_json::from_str(&members_json).unwrap_or_default();
         Ok(GroupRating {
@@ -1331,6 +1506,207 @@ pub fn load_group_ratings(conn: &Connection) -> Result<Vec<GroupRating>, CoreErr
     Ok(out)
 }
 
+/// Записать точку истории доверия узла (sender — отправитель удалённых оценок, если есть)
+pub fn record_trust_history(
+    conn: &Connection,
+    node_id: &str,
+    trust_score: f32,
+    source: &str,
+    sender: Option<&str>,
+    recorded_at: i64,
+) -> Result<(), CoreError> {
+    conn.execute(
+        "INSERT INTO trust_history (node_id, trust_score, source, sender, recorded_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![node_id, trust_score as f64, source, sender, recorded_at],
+    )?;
+    Ok(())
+}
+
+/// Загрузить историю доверия узла (новые записи первыми)
+pub fn load_trust_history(
+    conn: &Connection,
+    node_id: &str,
+    limit: Option<usize>,
+) -> Result<Vec<crate::models::TrustHistoryEntry>, CoreError> {
+    let lim = limit.unwrap_or(100) as i64;
+    let mut stmt = conn.prepare(
+        r#"SELECT id, node_id, trust_score, source, sender, recorded_at
+           FROM trust_history
+           WHERE node_id = ?1
+           ORDER BY recorded_at DESC, id DESC
+           LIMIT ?2"#,
+    )?;
+    let rows = stmt.query_map(params![node_id, lim], |r| {
+        Ok(crate::models::TrustHistoryEntry {
+            id: r.get(0)?,
+            node_id: r.get(1)?,
+            trust_score: r.get::<_, f64>(2)? as f32,
+            source: r.get(3)?,
+            sender: r.get(4)?,
+            recorded_at: r.get(5)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for r in rows { out.push(r?); }
+    Ok(out)
+}
+
+/// Проредить trust_history: у каждого узла последние `window` точек (окно стабильности) остаются,
+/// из более старых — только последняя за сутки. Возвращает число удалённых строк
+pub fn prune_trust_history(conn: &Connection, window: usize) -> Result<usize, CoreError> {
+    Ok(conn.execute(
+        r#"
+        DELETE FROM trust_history WHERE id IN (
+            SELECT id FROM (
+                SELECT id,
+                    ROW_NUMBER() OVER (PARTITION BY node_id ORDER BY recorded_at DESC, id DESC) AS rn,
+                    ROW_NUMBER() OVER (PARTITION BY node_id, recorded_at / 86400 ORDER BY recorded_at DESC, id DESC) AS day_rn
+                FROM trust_history
+            ) WHERE rn > ?1 AND day_rn > 1
+        )
+        "#,
+        params![window as i64],
+    )?)
+}
+
+/// Стабильность доверия узла по последним `window` точкам trust_history (0..1)
+pub fn trust_score_stability(conn: &Connection, node_id: &str, window: usize) -> Result<f32, CoreError> {
+    let scores: Vec<f32> = load_trust_history(conn, node_id, Some(window))?
+        .into_iter()
+        .map(|h| h.trust_score)
+        .collect();
+    Ok(compute_trust_stability(&scores))
+}
+
+/* =========================
+Anomaly detection
+========================= */
+
+/// Прогнать детекторы аномалий по impact, trust_history и sync_log и сохранить находки в alerts.
+/// Повторно найденные паттерны не дублируются (id детерминирован). Возвращает только новые записи.
+pub fn detect_anomalies(conn: &Connection, now_ts: i64) -> Result<Vec<Alert>, CoreError> {
+    // Положительные оценки валидатор → автор
+    let mut stmt_edges = conn.prepare(
+        r#"
+        SELECT im.public_key, te.public_key, SUM(CASE WHEN im.magnitude > 0 THEN 1 ELSE 0 END)
+        FROM impact im
+        JOIN truth_events te ON im.event_id = te.uid
+        WHERE im.public_key IS NOT NULL AND te.public_key IS NOT NULL AND im.public_key <> te.public_key
+        GROUP BY im.public_key, te.public_key
+        "#,
+    )?;
+    let edge_rows = stmt_edges.query_map([], |r| {
+        Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, i64>(2)?.max(0) as u32))
+    })?;
+    let mut edges: Vec<(String, String, u32)> = Vec::new();
+    for e in edge_rows { edges.push(e?); }
+
+    // Первое появление ключа: оценки, события и записи журнала синхронизации
+    let mut stmt_seen = conn.prepare(
+        r#"
+        SELECT key, MIN(ts) FROM (
+            SELECT public_key AS key, created_at AS ts FROM impact WHERE public_key IS NOT NULL
+            UNION ALL
+            SELECT public_key, timestamp_start FROM truth_events WHERE public_key IS NOT NULL
+            UNION ALL
+            SELECT public_key, created_at FROM sync_log WHERE public_key IS NOT NULL
+        )
+        GROUP BY key
+        "#,
+    )?;
+    let seen_rows = stmt_seen.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?)))?;
+    let mut first_seen: HashMap<String, i64> = HashMap::new();
+    for r in seen_rows {
+        let (k, t) = r?;
+        first_seen.insert(k, t);
+    }
+
+    let mut stmt_hist = conn.prepare(
+        "SELECT id, node_id, trust_score, source, sender, recorded_at FROM trust_history WHERE recorded_at >= ?1",
+    )?;
+    let hist_rows = stmt_hist.query_map(params![now_ts - ANOMALY_LOOKBACK_SECS], |r| {
+        Ok(crate::models::TrustHistoryEntry {
+            id: r.get(0)?,
+            node_id: r.get(1)?,
+            trust_score: r.get::<_, f64>(2)? as f32,
+            source: r.get(3)?,
+            sender: r.get(4)?,
+            recorded_at: r.get(5)?,
+        })
+    })?;
+    let mut history = Vec::new();
+    for h in hist_rows { history.push(h?); }
+
+    let mut found = detect_collusion_rings(&edges, &first_seen, now_ts);
+    found.extend(detect_trust_spikes(&history, now_ts));
+    found.extend(detect_reciprocal_loops(&edges, now_ts));
+
+    let mut ins = conn.prepare(
+        r#"INSERT OR IGNORE INTO alerts (id, kind, subject, related, severity, details, detected_at)
+           VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"#,
+    )?;
+    let mut added = Vec::new();
+    for a in found {
+        let related_json = serde_json::to_string(&a.related)?;
+        if ins.execute(params![a.id, a.kind, a.subject, related_json, a.severity as f64, a.details, a.detected_at])? > 0 {
+            added.push(a);
+        }
+    }
+    Ok(added)
+}
+
+/// Загрузить аномалии (новые первыми); по умолчанию только неподтверждённые
+pub fn load_alerts(conn: &Connection, include_acknowledged: bool, kind: Option<&str>) -> Result<Vec<Alert>, CoreError> {
+    let mut stmt = conn.prepare(
+        r#"SELECT id, kind, subject, related, severity, details, detected_at, acknowledged_at
+           FROM alerts
+           WHERE (?1 = 1 OR acknowledged_at IS NULL) AND (?2 IS NULL OR kind = ?2)
+           ORDER BY detected_at DESC, id"#,
+    )?;
+    let rows = stmt.query_map(params![include_acknowledged as i64, kind], |r| {
+        let related_json: String = r.get(3)?;
+        Ok(Alert {
+            id: r.get(0)?,
+            kind: r.get(1)?,
+            subject: r.get(2)?,
+            related: serde_json::from_str(&related_json).unwrap_or_default(),
+            severity: r.get::<_, f64>(4)? as f32,
+            details: r.get(5)?,
+            detected_at: r.get(6)?,
+            acknowledged_at: r.get(7)?,
+        })
+    })?;
+    let mut out = Vec::new();
+    for r in rows { out.push(r?); }
+    Ok(out)
+}
+
+/// Отметить аномалию как просмотренную
+pub fn acknowledge_alert(conn: &Connection, id: &str, ts: i64) -> Result<(), CoreError> {
+    let n = conn.execute(
+        "UPDATE alerts SET acknowledged_at = ?2 WHERE id = ?1 AND acknowledged_at IS NULL",
+        params![id, ts],
+    )?;
+    if n == 0 {
+        return Err(CoreError::NotFound(format!("alert {}", id)));
+    }
+    Ok(())
+}
+
+/// Соответствие node_id → виды неподтверждённых аномалий с его участием
+pub fn load_alerted_nodes(conn: &Connection) -> Result<HashMap<String, Vec<String>>, CoreError> {
+    let mut out: HashMap<String, Vec<String>> = HashMap::new();
+    for a in load_alerts(conn, false, None)? {
+        for node in std::iter::once(&a.subject).chain(a.related.iter()) {
+            let kinds = out.entry(node.clone()).or_default();
+            if !kinds.contains(&a.kind) {
+                kinds.push(a.kind.clone());
+            }
+        }
+    }
+    Ok(out)
+}
+
 /* =========================
 RBAC: Users and Roles helpers
 ========================= */
@@ -1357,15 +1733,17 @@ pub fn get_user_by_pubkey(conn: &Connection, pubkey: &str) -> Result<Option<Rbac
 /// Обновить роль пользователя (создаст при отсутствии)
 pub fn update_user_role(conn: &Connection, pubkey: &str, role: &str) -> Result<(), CoreError> {
     let now = chrono::Utc::now().timestamp();
-    conn.execute(
-        r#"
-        INSERT INTO users(pubkey, role, trust_score, last_updated)
-        VALUES (?1, ?2, 0.0, ?3)
-        ON CONFLICT(pubkey) DO UPDATE SET role=excluded.role, last_updated=excluded.last_updated
-        "#,
-        params![pubkey, role, now],
-    )?;
-    Ok(())
+    audit::track(conn, "users", "pubkey", pubkey, || {
+        conn.execute(
+            r#"
+            INSERT INTO users(pubkey, role, trust_score, last_updated)
+            VALUES (?1, ?2, 0.0, ?3)
+            ON CONFLICT(pubkey) DO UPDATE SET role=excluded.role, last_updated=excluded.last_updated
+            "#,
+            params![pubkey, role, now],
+        )?;
+        Ok(())
+    })
 }
 
 /// Список пользователей (сортировка по роли уровню и trust_score)
@@ -1394,6 +1772,10 @@ pub fn list_users(conn: &Connection) -> Result<Vec<RbacUser>, CoreError> {
 
 /// Подкорректировать trust_score пользователя, с ограничением [-1,1]
 pub fn adjust_trust_score(conn: &Connection, pubkey: &str, delta: f32) -> Result<f32, CoreError> {
+    audit::track(conn, "users", "pubkey", pubkey, || write_trust_score(conn, pubkey, delta))
+}
+
+fn write_trust_score(conn: &Connection, pubkey: &str, delta: f32) -> Result<f32, CoreError> {
     let now = chrono::Utc::now().timestamp();
     // ensure record exists
     conn.execute(
@@ -1463,22 +1845,29 @@ pub fn ci_ensure_participant(conn: &Connection, public_key: &str) -> Result<uuid
     Ok(id)
 }
 
+/// Записать суждение в таблицу judgments (reasoning индексируется триггерами judgments_fts)
+/// и увеличить счётчик суждений участника
 pub fn ci_insert_judgment(
     conn: &Connection,
     judgment: &ci_models::Judgment,
 ) -> Result<(), CoreError> {
+    audit::track(conn, "judgments", "id", &judgment.id.to_string(), || write_judgment(conn, judgment))
+}
+
+fn write_judgment(conn: &Connection, judgment: &ci_models::Judgment) -> Result<(), CoreError> {
+    let id = judgment.id.to_string();
     conn.execute(
-        r#"INSERT INTO judgments_ci (id, participant_id, event_id, assessment, confidence_level, reasoning, submitted_at, signature)
+        r#"INSERT INTO judgments (id, participant_id, event_id, assessment, confidence_level, reasoning, submitted_at, signature)
            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"#,
         rusqlite::params![
-            judgment.id.to_string(),
+            id,
             judgment.participant_id.to_string(),
             judgment.event_id.to_string(),
             judgment.assessment,
             judgment.confidence_level as f64,
-            judgment.reasoning,
+            at_rest::seal(conn, "judgments", "reasoning", &id, judgment.reasoning.as_deref())?,
             judgment.submitted_at.timestamp(),
-            judgment.signature,
+            at_rest::seal(conn, "judgments", "signature", &id, Some(&judgment.signature))?,
         ],
     )?;
     // increment participant counters
@@ -1491,10 +1880,11 @@ pub fn ci_insert_judgment(
     Ok(())
 }
 
+/// Суждения по событию из таблицы judgments, по времени подачи
 pub fn ci_get_judgments_by_event(conn: &Connection, event_id: &uuid::Uuid) -> Result<Vec<ci_models::Judgment>, CoreError> {
     let mut stmt = conn.prepare(
         r#"SELECT id, participant_id, event_id, assessment, confidence_level, reasoning, submitted_at, signature
-           FROM judgments_ci WHERE event_id = ?1 ORDER BY submitted_at ASC"#,
+           FROM judgments WHERE event_id = ?1 ORDER BY submitted_at ASC"#,
     )?;
     let rows = stmt.query_map(rusqlite::params![event_id.to_string()], |row| {
         Ok(ci_models::Judgment {
@@ -1509,7 +1899,13 @@ pub fn ci_get_judgments_by_event(conn: &Connection, event_id: &uuid::Uuid) -> Re
         })
     })?;
     let mut out = Vec::new();
-    for r in rows { out.push(r?); }
+    for r in rows {
+        let mut j = r?;
+        let id = j.id.to_string();
+        j.reasoning = at_rest::open(conn, "judgments", "reasoning", &id, j.reasoning)?;
+        j.signature = at_rest::open(conn, "judgments", "signature", &id, Some(j.signature))?.unwrap_or_default();
+        out.push(j);
+    }
     Ok(out)
 }
 
@@ -1601,46 +1997,86 @@ pub fn merge_ratings(
     conn: &Connection,
     incoming_nodes: &[NodeRating],
     incoming_groups: &[GroupRating],
+) -> Result<Vec<(String, f32)>, CoreError> {
+    merge_ratings_from(conn, None, incoming_nodes, incoming_groups)
+}
+
+/// Слияние входящих рейтингов с учётом репутации отправителя.
+/// Вес удалённых оценок масштабируется trust/quality отправителя; если его trust ниже
+/// REMOTE_TRUST_FLOOR — входящие рейтинги узлов и групп игнорируются целиком.
+pub fn merge_ratings_from(
+    conn: &Connection,
+    sender: Option<&str>,
+    incoming_nodes: &[NodeRating],
+    incoming_groups: &[GroupRating],
+) -> Result<Vec<(String, f32)>, CoreError> {
+    merge_ratings_from_with(conn, &TrustParams::default(), sender, incoming_nodes, incoming_groups)
+}
+
+/// merge_ratings_from с заданными параметрами (окно стабильности, веса quality_index и приоритета)
+pub fn merge_ratings_from_with(
+    conn: &Connection,
+    params: &TrustParams,
+    sender: Option<&str>,
+    incoming_nodes: &[NodeRating],
+    incoming_groups: &[GroupRating],
 ) -> Result<Vec<(String, f32)>, CoreError> {
     // 0) Больше не используем временной спад — сохраняем справедливость для оффлайн-нод
     let now_ts = chrono::Utc::now().timestamp();
 
+    // Отправитель ниже порога доверия — не принимаем его оценки
+    let Some(remote_weight) = load_sender_weight(conn, sender)? else {
+        return Ok(Vec::new());
+    };
+
     // 1) Узлы: распространяем доверие через взвешенное смешивание
-    let trust_diffs = propagate_from_remote(conn, incoming_nodes, now_ts)?;
+    let trust_diffs = propagate_from_remote(conn, incoming_nodes, now_ts, sender)?;
 
     // После обновления trust_score — пересчитать propagation_priority
     refresh_propagation_priority(conn)?;
 
-    // 2) Группы: резолвим по last_updated, как и раньше
-    let mut stmt_groups = conn.prepare(
-        r#"
-        INSERT INTO group_ratings (group_id, members, avg_score, coherence, last_updated)
-        VALUES (?1, ?2, ?3, ?4, ?5)
-        ON CONFLICT(group_id) DO UPDATE SET
-            members      = excluded.members,
-            avg_score    = excluded.avg_score,
-            coherence    = excluded.coherence,
-            last_updated = excluded.last_updated
-        WHERE excluded.last_updated > group_ratings.last_updated
-        "#,
-    )?;
+    // 2) Группы: avg_score и coherence смешиваются с тем же весом отправителя, что и trust узлов;
+    //    состав берётся из более свежей записи
     for gr in incoming_groups {
-        let members_json = serde_json::to_string(&gr.members)?;
-        stmt_groups.execute(rusqlite::params![
-            gr.group_id,
-            members_json,
-            gr.avg_score,
-            gr.coherence,
-            gr.last_updated,
-        ])?;
+        let local = conn
+            .query_row(
+                "SELECT members, avg_score, coherence, last_updated FROM group_ratings WHERE group_id = ?1",
+                [gr.group_id.as_str()],
+                |r| Ok((r.get::<_, String>(0)?, r.get::<_, f64>(1)? as f32, r.get::<_, f64>(2)? as f32, r.get::<_, i64>(3)?)),
+            )
+            .optional()?;
+        let remote_members = serde_json::to_string(&gr.members)?;
+        let (members, avg_score, coherence, last_updated) = match local {
+            Some((members, avg, coherence, updated)) => (
+                if gr.last_updated > updated { remote_members } else { members },
+                blend_trust_weighted(avg, gr.avg_score, remote_weight),
+                blend_quality_weighted(coherence, gr.coherence, remote_weight),
+                updated.max(gr.last_updated),
+            ),
+            None => (
+                remote_members,
+                blend_trust_weighted(0.0, gr.avg_score, remote_weight),
+                blend_quality_weighted(0.0, gr.coherence, remote_weight),
+                gr.last_updated,
+            ),
+        };
+        conn.execute(
+            r#"
+            INSERT INTO group_ratings (group_id, members, avg_score, coherence, last_updated, origin)
+            VALUES (?1, ?2, ?3, ?4, ?5, 'remote')
+            ON CONFLICT(group_id) DO UPDATE SET
+                members      = excluded.members,
+                avg_score    = excluded.avg_score,
+                coherence    = excluded.coherence,
+                last_updated = excluded.last_updated
+            "#,
+            rusqlite::params![gr.group_id, members, avg_score as f64, coherence as f64, last_updated],
+        )?;
     }
 
     // Зеркалим обновлённые trust_score в таблицу users для консистентности JWT/RBAC
     let _ = sync_users_with_node_ratings(conn);
 
-    // Зеркалим обновлённые trust_score в таблицу users для согласованности JWT
-    let _ = sync_users_with_node_ratings(conn);
-
     // Обновляем метрики узлов после синхронизации (relay_success_rate уже рассчитывался выше)
     for node in incoming_nodes {
         let relay_success_rate = if node.validations > 0 {
@@ -1654,12 +2090,13 @@ pub fn merge_ratings(
         let conflict_free_ratio = if total_events > 0.0 {
             (node.events_true as f32) / total_events
         } else { 1.0 };
-        // Стабильность trust: 1.0 как базовое значение (улучшим в будущем с хранением истории)
-        let trust_score_stability: f32 = 1.0;
+        // Стабильность trust по истории значений (1 - σ за последние params.stability_window точек)
+        let trust_score_stability = trust_score_stability(conn, &node.node_id, params.stability_window)?;
 
         // Загрузить предыдущее качество для EMA
         let prev_q = load_node_metrics(conn, &node.node_id)?.map(|m| m.quality_index);
-        let q = compute_quality_index(
+        let q = compute_quality_index_with(
+            params,
             relay_success_rate.clamp(0.0, 1.0),
             conflict_free_ratio.clamp(0.0, 1.0),
             trust_score_stability.clamp(0.0, 1.0),
@@ -1667,10 +2104,14 @@ pub fn merge_ratings(
         );
         // Обновить/вставить метрики с пересчётом приоритета
         let prev_p = load_node_metrics(conn, &node.node_id)?.map(|m| m.propagation_priority);
-        let p = compute_propagation_priority(node.trust_score, q, relay_success_rate, prev_p);
+        let a = recent_activity(conn, &node.node_id, now_ts, params)?;
+        let p = compute_propagation_priority_with(params, node.trust_score, q, relay_success_rate, a, prev_p);
         let _ = upsert_node_metrics_with_quality_and_priority(conn, &node.node_id, node.last_updated, relay_success_rate, q, p);
     }
 
+    // Скачки доверия после слияния фиксируются сразу
+    detect_anomalies(conn, now_ts)?;
+
     Ok(trust_diffs)
 }
 
@@ -1693,23 +2134,31 @@ pub fn load_graph(conn: &Connection) -> Result<GraphData, CoreError> {
             last_seen: row.get(3)?,
             relay_success_rate: row.get(4)?,
             quality_index: row.get::<_, Option<f64>>(5)?.unwrap_or(0.0) as f32,
+            group_id: None,
+            alerts: Vec::new(),
         })
     })?;
     let mut nodes: Vec<GraphNode> = Vec::new();
     for r in node_rows { nodes.push(r?); }
+    let node_groups = load_node_groups(conn)?;
+    let node_alerts = load_alerted_nodes(conn)?;
+    for n in nodes.iter_mut() {
+        n.group_id = node_groups.get(&n.id).cloned();
+        n.alerts = node_alerts.get(&n.id).cloned().unwrap_or_default();
+    }
 
     // Рёбра между валидаторами и авторами
     let mut stmt_links = conn.prepare(
         r#"
         SELECT im.public_key AS source,
                te.public_key AS target,
-               SUM(CASE WHEN im.value = 1 THEN 1 ELSE 0 END) AS pos,
-               SUM(CASE WHEN im.value = 0 THEN 1 ELSE 0 END) AS neg,
+               SUM(im.magnitude * COALESCE(im.confidence, 1.0)) AS graded,
+               SUM(COALESCE(im.confidence, 1.0)) AS total,
                AVG(CASE WHEN im.created_at > te.timestamp_start 
                         THEN (im.created_at - te.timestamp_start) * 1000 
                         ELSE NULL END) AS avg_latency_ms
         FROM impact im
-        JOIN truth_events te ON CAST(im.event_id AS INTEGER) = te.id
+        JOIN truth_events te ON im.event_id = te.uid
         WHERE im.public_key IS NOT NULL AND te.public_key IS NOT NULL AND im.public_key <> te.public_key
         GROUP BY source, target
         "#,
@@ -1717,11 +2166,11 @@ pub fn load_graph(conn: &Connection) -> Result<GraphData, CoreError> {
     let link_rows = stmt_links.query_map([], |row| {
         let source: String = row.get(0)?;
         let target: String = row.get(1)?;
-        let pos: i64 = row.get(2)?;
-        let neg: i64 = row.get(3)?;
+        let graded: f64 = row.get(2)?;
+        let total: f64 = row.get(3)?;
         let latency_ms: Option<f64> = row.get(4)?;
-        let total = (pos + neg).max(1) as f32;
-        let signed = (pos as f32 - neg as f32) / total; // -1..1
+        // Средняя степень с весами-уверенностями: -1..1
+        let signed = if total > 0.0 { (graded / total) as f32 } else { 0.0 };
         let weight = (signed + 1.0) / 2.0; // 0..1
         Ok(GraphLink { 
             source, 
@@ -1766,10 +2215,18 @@ pub fn load_graph_filtered(
             last_seen: row.get(3)?,
             relay_success_rate: row.get(4)?,
             quality_index: row.get::<_, Option<f64>>(5)?.unwrap_or(0.0) as f32,
+            group_id: None,
+            alerts: Vec::new(),
         })
     })?;
     let mut nodes: Vec<GraphNode> = Vec::new();
     for r in node_rows { nodes.push(r?); }
+    let node_groups = load_node_groups(conn)?;
+    let node_alerts = load_alerted_nodes(conn)?;
+    for n in nodes.iter_mut() {
+        n.group_id = node_groups.get(&n.id).cloned();
+        n.alerts = node_alerts.get(&n.id).cloned().unwrap_or_default();
+    }
 
     // Быстрый выход, если узлов нет
     if nodes.is_empty() {
@@ -1784,13 +2241,13 @@ pub fn load_graph_filtered(
         r#"
         SELECT im.public_key AS source,
                te.public_key AS target,
-               SUM(CASE WHEN im.value = 1 THEN 1 ELSE 0 END) AS pos,
-               SUM(CASE WHEN im.value = 0 THEN 1 ELSE 0 END) AS neg,
+               SUM(im.magnitude * COALESCE(im.confidence, 1.0)) AS graded,
+               SUM(COALESCE(im.confidence, 1.0)) AS total,
                AVG(CASE WHEN im.created_at > te.timestamp_start 
                         THEN (im.created_at - te.timestamp_start) * 1000 
                         ELSE NULL END) AS avg_latency_ms
         FROM impact im
-        JOIN truth_events te ON CAST(im.event_id AS INTEGER) = te.id
+        JOIN truth_events te ON im.event_id = te.uid
         WHERE im.public_key IS NOT NULL AND te.public_key IS NOT NULL AND im.public_key <> te.public_key
         GROUP BY source, target
         "#,
@@ -1798,11 +2255,11 @@ pub fn load_graph_filtered(
     let link_rows = stmt_links.query_map([], |row| {
         let source: String = row.get(0)?;
         let target: String = row.get(1)?;
-        let pos: i64 = row.get(2)?;
-        let neg: i64 = row.get(3)?;
+        let graded: f64 = row.get(2)?;
+        let total: f64 = row.get(3)?;
         let latency_ms: Option<f64> = row.get(4)?;
-        let total = (pos + neg).max(1) as f32;
-        let signed = (pos as f32 - neg as f32) / total; // -1..1
+        // Средняя степень с весами-уверенностями: -1..1
+        let signed = if total > 0.0 { (graded / total) as f32 } else { 0.0 };
         let weight = (signed + 1.0) / 2.0; // 0..1
         Ok(GraphLink { 
             source, 
@@ -1869,269 +2326,610 @@ pub fn load_graph_filtered(
         }
     }
 
-    // Финальная сортировка рёбер по весу убыв.
-    filtered_links.sort_by(|a, b| b.weight.partial_cmp(&a.weight).unwrap_or(std::cmp::Ordering::Equal));
-
-    Ok(GraphData { nodes, links: filtered_links })
+    // Финальная сортировка рёбер по весу убыв.
+    filtered_links.sort_by(|a, b| b.weight.partial_cmp(&a.weight).unwrap_or(std::cmp::Ordering::Equal));
+
+    Ok(GraphData { nodes, links: filtered_links })
+}
+
+/// Полная выгрузка БД в JSON-файл; формат и NDJSON — см. export
+pub fn export_to_json(conn: &Connection, file_path: &str) -> Result<crate::export::ExportReport, CoreError> {
+    crate::export::export_to_file(conn, file_path, crate::export::ExportFormat::Json)
+}
+
+/// Загрузить JSON-выгрузку (в том числе старого формата ExportData) в одной транзакции
+pub fn import_from_json(
+    conn: &mut Connection,
+    file_path: &str,
+    mode: crate::export::ImportMode,
+) -> Result<crate::export::ImportReport, CoreError> {
+    crate::export::import_from_file(conn, file_path, crate::export::ExportFormat::Json, mode)
+}
+
+/// Добавить утверждение
+pub fn add_statement(conn: &Connection, new_stmt: NewStatement) -> Result<i64, CoreError> {
+    if new_stmt.text.trim().is_empty() {
+        return Err(CoreError::InvalidArg("statement text is empty".into()));
+    }
+    crate::argument::check_reply(conn, new_stmt.event_id, new_stmt.parent_uid.as_deref(), new_stmt.relation.as_deref())?;
+
+    let now = chrono::Utc::now().timestamp();
+    let uid = crate::models::new_uid();
+    audit::track(conn, "statements", "uid", &uid, || {
+        conn.execute(
+            r#"INSERT INTO statements (uid, event_id, text, context, truth_score, created_at, updated_at, parent_uid, relation)
+              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"#,
+            params![
+                uid,
+                new_stmt.event_id,
+                new_stmt.text,
+                new_stmt.context,
+                new_stmt.truth_score,
+                now,
+                now,
+                new_stmt.parent_uid,
+                new_stmt.relation,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    })
+}
+
+/// Колонки утверждения в порядке map_statement: утверждение под псевдонимом s,
+/// его событие — e (LEFT JOIN truth_events e ON e.id = s.event_id)
+pub(crate) const STATEMENT_COLUMNS: &str =
+    "s.id, s.event_id, s.text, s.context, s.truth_score, s.created_at, s.updated_at, s.signature, s.public_key, s.uid, COALESCE(e.uid, ''), s.parent_uid, s.relation";
+
+pub(crate) fn map_statement(row: &rusqlite::Row<'_>) -> rusqlite::Result<Statement> {
+    Ok(Statement {
+        id: row.get(0)?,
+        uid: row.get(9)?,
+        event_id: row.get(1)?,
+        event_uid: row.get(10)?,
+        text: row.get(2)?,
+        context: row.get(3)?,
+        truth_score: row.get(4)?,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+        signature: row.get(7)?,
+        public_key: row.get(8)?,
+        parent_uid: row.get(11)?,
+        relation: row.get(12)?,
+    })
+}
+
+/// Получить утверждение по локальному id
+pub fn get_statement(conn: &Connection, id: i64) -> Result<Option<Statement>, CoreError> {
+    let sql = format!("SELECT {} FROM statements s LEFT JOIN truth_events e ON e.id = s.event_id WHERE s.id = ?1", STATEMENT_COLUMNS);
+    let row_opt = conn.query_row(&sql, params![id], map_statement).optional()?;
+    row_opt.map(|mut st| open_signature(conn, &mut st).map(|_| st)).transpose()
+}
+
+/// Получить утверждение по глобальному id
+pub fn get_statement_by_uid(conn: &Connection, uid: &str) -> Result<Option<Statement>, CoreError> {
+    let sql = format!("SELECT {} FROM statements s LEFT JOIN truth_events e ON e.id = s.event_id WHERE s.uid = ?1", STATEMENT_COLUMNS);
+    let row_opt = conn.query_row(&sql, params![uid], map_statement).optional()?;
+    row_opt.map(|mut st| open_signature(conn, &mut st).map(|_| st)).transpose()
+}
+
+/// Получить все утверждения для события
+pub fn get_statements_for_event(conn: &Connection, event_id: i64) -> Result<Vec<Statement>, CoreError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM statements s LEFT JOIN truth_events e ON e.id = s.event_id WHERE s.event_id = ?1 ORDER BY s.created_at DESC",
+        STATEMENT_COLUMNS
+    ))?;
+
+    let rows = stmt.query_map(params![event_id], map_statement)?;
+
+    let mut statements = Vec::new();
+    for s in rows {
+        let mut s = s?;
+        open_signature(conn, &mut s)?;
+        statements.push(s);
+    }
+    Ok(statements)
+}
+
+/// Получить все утверждения
+pub fn load_statements(conn: &Connection) -> Result<Vec<Statement>, CoreError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM statements s LEFT JOIN truth_events e ON e.id = s.event_id ORDER BY s.created_at DESC",
+        STATEMENT_COLUMNS
+    ))?;
+
+    let rows = stmt.query_map([], map_statement)?;
+
+    let mut statements = Vec::new();
+    for s in rows {
+        let mut s = s?;
+        open_signature(conn, &mut s)?;
+        statements.push(s);
+    }
+    Ok(statements)
+}
+
+/// Записать копию утверждения: вставка или замена всех полей по uid. Событие ищется по event_uid
+/// (у пира до глобальных id он пуст — тогда event_id считается локальным id). Возвращает локальный id.
+pub fn upsert_statement(conn: &Connection, st: &Statement) -> Result<i64, CoreError> {
+    let (event_id, event_uid): (i64, String) = if st.event_uid.is_empty() {
+        conn.query_row("SELECT id, uid FROM truth_events WHERE id = ?1", params![st.event_id], |r| Ok((r.get(0)?, r.get(1)?)))
+            .optional()?
+            .ok_or_else(|| CoreError::NotFound(format!("event {}", st.event_id)))?
+    } else {
+        conn.query_row("SELECT id FROM truth_events WHERE uid = ?1", params![st.event_uid], |r| r.get(0))
+            .optional()?
+            .map(|id| (id, st.event_uid.clone()))
+            .ok_or_else(|| CoreError::NotFound(format!("event {}", st.event_uid)))?
+    };
+    let uid = if st.uid.is_empty() { st.legacy_uid(&event_uid) } else { st.uid.clone() };
+    // Родитель может прийти позже ответа, поэтому здесь проверяется только само отношение
+    crate::argument::check_relation(st.parent_uid.as_deref(), st.relation.as_deref())?;
+    audit::track(conn, "statements", "uid", &uid, || {
+        conn.execute(
+            r#"INSERT INTO statements
+                (uid, event_id, text, context, truth_score, created_at, updated_at, signature, public_key, parent_uid, relation)
+              VALUES
+                (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+              ON CONFLICT(uid) DO UPDATE SET
+                event_id=excluded.event_id, text=excluded.text, context=excluded.context, truth_score=excluded.truth_score,
+                created_at=excluded.created_at, updated_at=excluded.updated_at, signature=excluded.signature, public_key=excluded.public_key,
+                parent_uid=excluded.parent_uid, relation=excluded.relation"#,
+            params![
+                uid,
+                event_id,
+                st.text,
+                st.context,
+                st.truth_score,
+                st.created_at,
+                st.updated_at,
+                at_rest::seal(conn, "statements", "signature", &uid, st.signature.as_deref())?,
+                st.public_key,
+                st.parent_uid,
+                st.relation,
+            ],
+        )?;
+        Ok(())
+    })?;
+    Ok(conn.query_row("SELECT id FROM statements WHERE uid = ?1", params![uid], |r| r.get(0))?)
+}
+
+/// Обновить оценку правдивости утверждения
+pub fn update_statement_score(conn: &Connection, id: i64, truth_score: f32) -> Result<(), CoreError> {
+    let now = chrono::Utc::now().timestamp();
+    let Some(uid) = conn.query_row("SELECT uid FROM statements WHERE id = ?1", params![id], |r| r.get::<_, String>(0)).optional()? else {
+        return Ok(());
+    };
+    audit::track(conn, "statements", "uid", &uid, || {
+        conn.execute(
+            r#"UPDATE statements SET truth_score = ?2, updated_at = ?3 WHERE id = ?1"#,
+            params![id, truth_score, now],
+        )?;
+        Ok(())
+    })
+}
+
+/// Фильтры полнотекстового поиска; None — без ограничения
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchFilters {
+    pub context_id: Option<i64>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub author: Option<String>,
+    pub detected: Option<bool>,
+}
+
+/// Пользовательский запрос → выражение FTS5: каждое слово в кавычках (без операторов),
+/// хвостовая `*` сохраняется как поиск по префиксу
+fn fts_query(q: &str) -> Result<String, CoreError> {
+    let terms: Vec<String> = q
+        .split_whitespace()
+        .filter_map(|t| {
+            let prefix = t.ends_with('*');
+            let word: String = t.chars().filter(|c| *c != '"' && *c != '*').collect();
+            if word.is_empty() {
+                None
+            } else if prefix {
+                Some(format!("\"{}\"*", word))
+            } else {
+                Some(format!("\"{}\"", word))
+            }
+        })
+        .collect();
+    if terms.is_empty() {
+        return Err(CoreError::InvalidArg("empty search query".into()));
+    }
+    Ok(terms.join(" "))
+}
+
+/// Полнотекстовый поиск по описаниям событий, утверждениям и обоснованиям оценок.
+/// Результаты всех видов упорядочены по релевантности (bm25). Оценки CI не привязаны
+/// к контексту и флагу detected, поэтому при этих фильтрах не возвращаются.
+pub fn search(conn: &Connection, q: &str, filters: &SearchFilters, limit: usize) -> Result<Vec<SearchHit>, CoreError> {
+    let query = fts_query(q)?;
+    let detected = filters.detected.map(|d| d as i64);
+    let limit = limit as i64;
+    let map_hit = |r: &rusqlite::Row<'_>| -> rusqlite::Result<SearchHit> {
+        Ok(SearchHit {
+            kind: r.get(0)?,
+            id: r.get(1)?,
+            event_id: r.get(2)?,
+            snippet: r.get(3)?,
+            rank: r.get(4)?,
+            context_id: r.get(5)?,
+            timestamp: r.get(6)?,
+            author: r.get(7)?,
+            detected: r.get::<_, Option<i64>>(8)?.map(|v| v != 0),
+        })
+    };
+
+    let mut hits = Vec::new();
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT 'event', CAST(e.id AS TEXT), CAST(e.id AS TEXT),
+               snippet(truth_events_fts, 0, '[', ']', '…', 12), -bm25(truth_events_fts),
+               e.context_id, e.timestamp_start, e.public_key, e.detected
+        FROM truth_events_fts JOIN truth_events e ON e.id = truth_events_fts.rowid
+        WHERE truth_events_fts MATCH ?1
+          AND (?2 IS NULL OR e.context_id = ?2)
+          AND (?3 IS NULL OR e.timestamp_start >= ?3)
+          AND (?4 IS NULL OR e.timestamp_start <= ?4)
+          AND (?5 IS NULL OR e.public_key = ?5)
+          AND (?6 IS NULL OR e.detected = ?6)
+        ORDER BY bm25(truth_events_fts) LIMIT ?7
+        "#,
+    )?;
+    let rows = stmt.query_map(
+        params![query, filters.context_id, filters.since, filters.until, filters.author, detected, limit],
+        map_hit,
+    )?;
+    for r in rows { hits.push(r?); }
+
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT 'statement', CAST(s.id AS TEXT), CAST(s.event_id AS TEXT),
+               snippet(statements_fts, -1, '[', ']', '…', 12), -bm25(statements_fts),
+               e.context_id, s.created_at, s.public_key, e.detected
+        FROM statements_fts JOIN statements s ON s.id = statements_fts.rowid
+        LEFT JOIN truth_events e ON e.id = s.event_id
+        WHERE statements_fts MATCH ?1
+          AND (?2 IS NULL OR e.context_id = ?2)
+          AND (?3 IS NULL OR s.created_at >= ?3)
+          AND (?4 IS NULL OR s.created_at <= ?4)
+          AND (?5 IS NULL OR s.public_key = ?5)
+          AND (?6 IS NULL OR e.detected = ?6)
+        ORDER BY bm25(statements_fts) LIMIT ?7
+        "#,
+    )?;
+    let rows = stmt.query_map(
+        params![query, filters.context_id, filters.since, filters.until, filters.author, detected, limit],
+        map_hit,
+    )?;
+    for r in rows { hits.push(r?); }
+
+    if filters.context_id.is_none() && filters.detected.is_none() {
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT 'judgment', j.id, j.event_id,
+                   snippet(judgments_fts, 0, '[', ']', '…', 12), -bm25(judgments_fts),
+                   NULL, j.submitted_at, p.public_key, NULL
+            FROM judgments_fts JOIN judgments j ON j.rowid = judgments_fts.rowid
+            LEFT JOIN participants p ON p.id = j.participant_id
+            WHERE judgments_fts MATCH ?1
+              AND j.reasoning NOT LIKE 'enc1:%' -- зашифрованные обоснования (at_rest) не ищутся
+              AND (?2 IS NULL OR j.submitted_at >= ?2)
+              AND (?3 IS NULL OR j.submitted_at <= ?3)
+              AND (?4 IS NULL OR p.public_key = ?4)
+            ORDER BY bm25(judgments_fts) LIMIT ?5
+            "#,
+        )?;
+        let rows = stmt.query_map(params![query, filters.since, filters.until, filters.author, limit], map_hit)?;
+        for r in rows { hits.push(r?); }
+    }
+
+    hits.sort_by(|a, b| b.rank.partial_cmp(&a.rank).unwrap_or(std::cmp::Ordering::Equal));
+    hits.truncate(limit as usize);
+    Ok(hits)
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct ExportData {
-    pub truth_events: Vec<TruthEvent>,
-    pub impacts: Vec<Impact>,
-    pub metrics: Vec<ProgressMetrics>,
+/// Размер страницы по умолчанию и верхняя граница для выборок RecordQuery
+pub const PAGE_DEFAULT_LIMIT: usize = 100;
+pub const PAGE_MAX_LIMIT: usize = 1000;
+/// Курсор «после последней записи»: всегда даёт пустую страницу
+pub const CURSOR_END: &str = "end";
+
+/// Поле сортировки выборок; id всегда добавляется вторым ключом для стабильного курсора
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    #[default]
+    Id,
+    /// timestamp_start для событий, created_at для утверждений и влияний
+    Time,
 }
 
-pub fn export_to_json(conn: &Connection, file_path: &str) -> Result<(), CoreError> {
-    let truth_events = load_truth_events(conn)?;
-    let impacts = load_impacts(conn)?;
-    let metrics = load_metrics(conn)?;
+/// Страница выборки; next_cursor есть, только если дальше остались записи
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
 
-    let data = ExportData {
-        truth_events,
-        impacts,
-        metrics,
-    };
+impl<T> Page<T> {
+    fn open_signatures(mut self, conn: &Connection) -> Result<Self, CoreError>
+    where
+        T: SignedRow,
+    {
+        for item in &mut self.items {
+            open_signature(conn, item)?;
+        }
+        Ok(self)
+    }
+}
 
-    let json_data = serde_json::to_string_pretty(&data)?;
-    fs::write(file_path, json_data)?;
-    Ok(())
+/// Фильтры, сортировка и курсор для событий, утверждений и влияний.
+/// context_id, detected и диапазон оценки для утверждений и влияний берутся у их события,
+/// кроме оценки утверждения — это его собственный truth_score.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordQuery {
+    pub context_id: Option<i64>,
+    pub author: Option<String>,
+    pub detected: Option<bool>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub min_score: Option<f64>,
+    pub max_score: Option<f64>,
+    #[serde(default)]
+    pub sort: SortField,
+    #[serde(default)]
+    pub descending: bool,
+    /// 0 — PAGE_DEFAULT_LIMIT; больше PAGE_MAX_LIMIT обрезается
+    #[serde(default)]
+    pub limit: usize,
+    pub cursor: Option<String>,
 }
 
-pub fn import_from_json(conn: &mut Connection, file_path: &str) -> Result<(), CoreError> {
-    let json_str = fs::read_to_string(file_path)?;
-    let data: ExportData = serde_json::from_str(&json_str)?;
-
-    let tx = conn.transaction()?;
-
-    for event in data.truth_events {
-        tx.execute(
-            "INSERT INTO truth_events (id, description, context_id, vector, detected, corrected, timestamp_start, timestamp_end, code, signature, public_key)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
-            rusqlite::params![
-                event.id,
-                event.description,
-                event.context_id,
-                if event.vector { 1 } else { 0 },
-                event.detected.map(|v| if v { 1 } else { 0 }),
-                if event.corrected { 1 } else { 0 },
-                event.timestamp_start,
-                event.timestamp_end,
-                event.code,
-                event.signature,
-                event.public_key
-            ],
-        )?;
+impl RecordQuery {
+    pub fn new() -> Self {
+        Self::default()
     }
-
-    for impact in data.impacts {
-        tx.execute(
-            "INSERT INTO impact (id, event_id, type_id, value, notes, created_at, signature, public_key)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            rusqlite::params![
-                impact.id,
-                impact.event_id,
-                impact.type_id,
-                if impact.value { 1 } else { 0 },
-                impact.notes,
-                impact.created_at,
-                impact.signature,
-                impact.public_key,
-            ],
-        )?;
+    pub fn context(mut self, context_id: i64) -> Self {
+        self.context_id = Some(context_id);
+        self
     }
-
-    for metric in data.metrics {
-        tx.execute(
-            "INSERT INTO progress_metrics (id, timestamp, total_events, total_events_group, total_positive_impact, total_positive_impact_group, total_negative_impact, total_negative_impact_group, trend, trend_group)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-            rusqlite::params![
-                metric.id,
-                metric.timestamp,
-                metric.total_events,
-                metric.total_events_group,
-                metric.total_positive_impact,
-                metric.total_positive_impact_group,
-                metric.total_negative_impact,
-                metric.total_negative_impact_group,
-                metric.trend,
-                metric.trend_group
-            ],
-        )?;
+    pub fn author(mut self, public_key: impl Into<String>) -> Self {
+        self.author = Some(public_key.into());
+        self
     }
-
-    tx.commit()?;
-    Ok(())
-}
-
-/// Добавить утверждение
-pub fn add_statement(conn: &Connection, new_stmt: NewStatement) -> Result<i64, CoreError> {
-    if new_stmt.text.trim().is_empty() {
-        return Err(CoreError::InvalidArg("statement text is empty".into()));
+    pub fn detected(mut self, detected: bool) -> Self {
+        self.detected = Some(detected);
+        self
+    }
+    pub fn time_range(mut self, since: Option<i64>, until: Option<i64>) -> Self {
+        self.since = since;
+        self.until = until;
+        self
+    }
+    pub fn score_range(mut self, min: Option<f64>, max: Option<f64>) -> Self {
+        self.min_score = min;
+        self.max_score = max;
+        self
+    }
+    pub fn sort_by(mut self, field: SortField, descending: bool) -> Self {
+        self.sort = field;
+        self.descending = descending;
+        self
+    }
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+    pub fn after(mut self, cursor: Option<String>) -> Self {
+        self.cursor = cursor;
+        self
     }
 
-    let now = chrono::Utc::now().timestamp();
-    conn.execute(
-        r#"INSERT INTO statements (event_id, text, context, truth_score, created_at, updated_at)
-          VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
-        params![
-            new_stmt.event_id,
-            new_stmt.text,
-            new_stmt.context,
-            new_stmt.truth_score,
-            now,
-            now,
-        ],
-    )?;
+    pub(crate) fn page_size(&self) -> usize {
+        if self.limit == 0 { PAGE_DEFAULT_LIMIT } else { self.limit.min(PAGE_MAX_LIMIT) }
+    }
+}
 
-    Ok(conn.last_insert_rowid())
+/// Колонки таблицы, к которым привязываются фильтры RecordQuery
+struct QueryColumns {
+    select: &'static str,
+    id: &'static str,
+    int_id: bool,
+    time: &'static str,
+    author: &'static str,
+    context: &'static str,
+    detected: &'static str,
+    score: &'static str,
 }
 
-/// Получить утверждение по id
-pub fn get_statement(conn: &Connection, id: i64) -> Result<Option<Statement>, CoreError> {
-    let mut stmt = conn.prepare(
-        r#"SELECT id, event_id, text, context, truth_score, created_at, updated_at, signature, public_key
-           FROM statements WHERE id = ?1"#,
-    )?;
-
-    let row_opt = stmt
-        .query_row(params![id], |row| {
-            Ok(Statement {
-                id: row.get(0)?,
-                event_id: row.get(1)?,
-                text: row.get(2)?,
-                context: row.get(3)?,
-                truth_score: row.get(4)?,
-                created_at: row.get(5)?,
-                updated_at: row.get(6)?,
-                signature: row.get(7)?,
-                public_key: row.get(8)?,
-            })
-        })
-        .optional()?;
+/// Курсор: "<время>:<id>" последней выданной записи
+pub(crate) fn encode_cursor(time: i64, id: &str) -> String {
+    format!("{}:{}", time, id)
+}
 
-    Ok(row_opt)
+pub(crate) fn decode_cursor(cursor: &str) -> Result<(i64, String), CoreError> {
+    cursor
+        .split_once(':')
+        .and_then(|(t, id)| Some((t.parse::<i64>().ok()?, id.to_string())))
+        .filter(|(_, id)| !id.is_empty())
+        .ok_or_else(|| CoreError::InvalidArg(format!("bad cursor '{}'", cursor)))
 }
 
-/// Получить все утверждения для события
-pub fn get_statements_for_event(conn: &Connection, event_id: i64) -> Result<Vec<Statement>, CoreError> {
-    let mut stmt = conn.prepare(
-        r#"SELECT id, event_id, text, context, truth_score, created_at, updated_at, signature, public_key
-           FROM statements WHERE event_id = ?1 ORDER BY created_at DESC"#,
-    )?;
+/// Выполнить выборку по RecordQuery; row_key возвращает (время, id) записи для курсора
+fn query_page<T>(
+    conn: &Connection,
+    cols: &QueryColumns,
+    q: &RecordQuery,
+    map: impl Fn(&rusqlite::Row<'_>) -> rusqlite::Result<T>,
+    row_key: impl Fn(&T) -> (i64, String),
+) -> Result<Page<T>, CoreError> {
+    use rusqlite::types::Value;
+    if q.cursor.as_deref() == Some(CURSOR_END) {
+        return Ok(Page { items: Vec::new(), next_cursor: None });
+    }
+    let mut clauses: Vec<String> = Vec::new();
+    let mut args: Vec<Value> = Vec::new();
+    let mut push = |clause: String, v: Value| {
+        args.push(v);
+        clauses.push(clause.replace('?', &format!("?{}", args.len())));
+    };
+    if let Some(c) = q.context_id { push(format!("{} = ?", cols.context), Value::Integer(c)); }
+    if let Some(a) = &q.author { push(format!("{} = ?", cols.author), Value::Text(a.clone())); }
+    if let Some(d) = q.detected { push(format!("{} = ?", cols.detected), Value::Integer(d as i64)); }
+    if let Some(t) = q.since { push(format!("{} >= ?", cols.time), Value::Integer(t)); }
+    if let Some(t) = q.until { push(format!("{} <= ?", cols.time), Value::Integer(t)); }
+    if let Some(v) = q.min_score { push(format!("{} >= ?", cols.score), Value::Real(v)); }
+    if let Some(v) = q.max_score { push(format!("{} <= ?", cols.score), Value::Real(v)); }
+
+    let op = if q.descending { "<" } else { ">" };
+    if let Some(cursor) = q.cursor.as_deref() {
+        let (time, id) = decode_cursor(cursor)?;
+        let id_value = if cols.int_id {
+            Value::Integer(id.parse().map_err(|_| CoreError::InvalidArg(format!("bad cursor '{}'", cursor)))?)
+        } else {
+            Value::Text(id)
+        };
+        match q.sort {
+            SortField::Id => push(format!("{} {} ?", cols.id, op), id_value),
+            SortField::Time => {
+                args.push(Value::Integer(time));
+                let t = args.len();
+                args.push(id_value);
+                let i = args.len();
+                clauses.push(format!(
+                    "({time} {op} ?{t} OR ({time} = ?{t} AND {id} {op} ?{i}))",
+                    time = cols.time, id = cols.id, op = op, t = t, i = i
+                ));
+            }
+        }
+    }
 
-    let rows = stmt.query_map(params![event_id], |row| {
-        Ok(Statement {
-            id: row.get(0)?,
-            event_id: row.get(1)?,
-            text: row.get(2)?,
-            context: row.get(3)?,
-            truth_score: row.get(4)?,
-            created_at: row.get(5)?,
-            updated_at: row.get(6)?,
-            signature: row.get(7)?,
-            public_key: row.get(8)?,
+    let dir = if q.descending { "DESC" } else { "ASC" };
+    let order = match q.sort {
+        SortField::Id => format!("{} {}", cols.id, dir),
+        SortField::Time => format!("{} {}, {} {}", cols.time, dir, cols.id, dir),
+    };
+    let size = q.page_size();
+    let sql = format!(
+        "{} {} ORDER BY {} LIMIT {}",
+        cols.select,
+        if clauses.is_empty() { String::new() } else { format!("WHERE {}", clauses.join(" AND ")) },
+        order,
+        size + 1
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(rusqlite::params_from_iter(args), map)?;
+    let mut items = Vec::new();
+    for r in rows { items.push(r?); }
+
+    let next_cursor = if items.len() > size {
+        items.truncate(size);
+        items.last().map(|last| {
+            let (time, id) = row_key(last);
+            encode_cursor(time, &id)
         })
-    })?;
-
-    let mut statements = Vec::new();
-    for s in rows {
-        statements.push(s?);
-    }
-    Ok(statements)
+    } else {
+        None
+    };
+    Ok(Page { items, next_cursor })
 }
 
-/// Получить все утверждения
-pub fn load_statements(conn: &Connection) -> Result<Vec<Statement>, CoreError> {
-    let mut stmt = conn.prepare(
-        "SELECT id, event_id, text, context, truth_score, created_at, updated_at, signature, public_key FROM statements ORDER BY created_at DESC",
-    )?;
-
-    let rows = stmt.query_map([], |row| {
-        Ok(Statement {
-            id: row.get(0)?,
-            event_id: row.get(1)?,
-            text: row.get(2)?,
-            context: row.get(3)?,
-            truth_score: row.get(4)?,
-            created_at: row.get(5)?,
-            updated_at: row.get(6)?,
-            signature: row.get(7)?,
-            public_key: row.get(8)?,
-        })
-    })?;
+/// Страница событий по фильтрам RecordQuery
+pub fn query_truth_events(conn: &Connection, q: &RecordQuery) -> Result<Page<TruthEvent>, CoreError> {
+    let cols = QueryColumns {
+        select: "SELECT e.id, e.description, e.context_id, e.vector, e.detected, e.corrected, e.timestamp_start, e.timestamp_end, e.code, e.signature, e.public_key, e.collective_score, e.uid FROM truth_events e",
+        id: "e.id",
+        int_id: true,
+        time: "e.timestamp_start",
+        author: "e.public_key",
+        context: "e.context_id",
+        detected: "e.detected",
+        score: "e.collective_score",
+    };
+    query_page(conn, &cols, q, map_truth_event, |e| (e.timestamp_start, e.id.to_string()))?
+    .open_signatures(conn)
+}
 
-    let mut statements = Vec::new();
-    for s in rows {
-        statements.push(s?);
-    }
-    Ok(statements)
+/// Страница утверждений по фильтрам RecordQuery
+pub fn query_statements(conn: &Connection, q: &RecordQuery) -> Result<Page<Statement>, CoreError> {
+    let cols = QueryColumns {
+        select: "SELECT s.id, s.event_id, s.text, s.context, s.truth_score, s.created_at, s.updated_at, s.signature, s.public_key, s.uid, COALESCE(e.uid, ''), s.parent_uid, s.relation FROM statements s LEFT JOIN truth_events e ON e.id = s.event_id",
+        id: "s.id",
+        int_id: true,
+        time: "s.created_at",
+        author: "s.public_key",
+        context: "e.context_id",
+        detected: "e.detected",
+        score: "s.truth_score",
+    };
+    query_page(conn, &cols, q, map_statement, |s| (s.created_at, s.id.to_string()))?
+    .open_signatures(conn)
 }
 
-/// Обновить оценку правдивости утверждения
-pub fn update_statement_score(conn: &Connection, id: i64, truth_score: f32) -> Result<(), CoreError> {
-    let now = chrono::Utc::now().timestamp();
-    conn.execute(
-        r#"UPDATE statements SET truth_score = ?2, updated_at = ?3 WHERE id = ?1"#,
-        params![id, truth_score, now],
-    )?;
-    Ok(())
+/// Страница влияний по фильтрам RecordQuery
+pub fn query_impacts(conn: &Connection, q: &RecordQuery) -> Result<Page<Impact>, CoreError> {
+    let cols = QueryColumns {
+        select: "SELECT i.id, i.event_id, i.type_id, i.value, i.notes, i.created_at, i.signature, i.public_key, i.magnitude, i.confidence FROM impact i LEFT JOIN truth_events e ON e.uid = i.event_id",
+        id: "i.id",
+        int_id: false,
+        time: "i.created_at",
+        author: "i.public_key",
+        context: "e.context_id",
+        detected: "e.detected",
+        score: "e.collective_score",
+    };
+    query_page(conn, &cols, q, map_impact, |i| (i.created_at, i.id.clone()))?
+    .open_signatures(conn)
 }
 
 /// Загружаем все события
 pub fn load_truth_events(conn: &Connection) -> Result<Vec<TruthEvent>, CoreError> {
-    let mut stmt = conn.prepare(
-        "SELECT id, description, context_id, vector, detected, corrected, timestamp_start, timestamp_end, code, signature, public_key, collective_score FROM truth_events",
-    )?;
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM truth_events e", EVENT_COLUMNS))?;
 
-    let rows = stmt.query_map([], |row| {
-        Ok(TruthEvent {
-            id: row.get(0)?,
-            description: row.get(1)?,
-            context_id: row.get(2)?,
-            vector: row.get::<_, i64>(3)? != 0,
-            detected: row.get::<_, Option<i64>>(4)?.map(|v| v != 0),
-            corrected: row.get::<_, i64>(5)? != 0,
-            timestamp_start: row.get(6)?,
-            timestamp_end: row.get(7)?,
-            code: row.get(8)?,
-            signature: row.get(9)?,
-            public_key: row.get(10)?,
-            collective_score: row.get(11)?,
-        })
-    })?;
+    let rows = stmt.query_map([], map_truth_event)?;
 
     let mut events = Vec::new();
     for e in rows {
-        events.push(e?);
+        let mut e = e?;
+        open_signature(conn, &mut e)?;
+        events.push(e);
     }
     Ok(events)
 }
 
 /// Загружаем все записи влияния
 pub fn load_impacts(conn: &Connection) -> Result<Vec<Impact>, CoreError> {
-    let mut stmt = conn.prepare("SELECT id, event_id, type_id, value, notes, created_at, signature, public_key FROM impact")?;
+    let mut stmt = conn.prepare("SELECT id, event_id, type_id, value, notes, created_at, signature, public_key, magnitude, confidence FROM impact")?;
 
-    let rows = stmt.query_map([], |row| {
-        Ok(Impact {
-            id: row.get(0)?,
-            event_id: row.get(1)?,
-            type_id: row.get(2)?,
-            value: row.get::<_, i64>(3)? != 0,
-            notes: row.get(4)?,
-            created_at: row.get(5)?,
-            signature: row.get(6)?,
-            public_key: row.get(7)?,
-        })
-    })?;
+    let rows = stmt.query_map([], map_impact)?;
 
     let mut impacts = Vec::new();
     for i in rows {
-        impacts.push(i?);
+        let mut i = i?;
+        open_signature(conn, &mut i)?;
+        impacts.push(i);
     }
     Ok(impacts)
 }
 
+fn map_impact(row: &rusqlite::Row<'_>) -> rusqlite::Result<Impact> {
+    Ok(Impact {
+        id: row.get(0)?,
+        event_id: row.get(1)?,
+        type_id: row.get(2)?,
+        value: row.get::<_, i64>(3)? != 0,
+        magnitude: row.get(8)?,
+        confidence: row.get(9)?,
+        notes: row.get(4)?,
+        created_at: row.get(5)?,
+        signature: row.get(6)?,
+        public_key: row.get(7)?,
+    })
+}
+
 /// Загружаем все метрики прогресса
 pub fn load_metrics(conn: &Connection) -> Result<Vec<ProgressMetrics>, CoreError> {
     let mut stmt = conn.prepare(
@@ -2171,11 +2969,19 @@ pub fn log_sync(
 ) -> Result<i64, CoreError> {
     let created_at = Utc::now().timestamp();
     conn.execute(
-        r#"INSERT INTO sync_log (op, table_name, record_id, signature, public_key, created_at)
-           VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
-        params![op, table_name, record_id, signature, public_key, created_at],
+        r#"INSERT INTO sync_log (op, table_name, record_id, public_key, created_at)
+           VALUES (?1, ?2, ?3, ?4, ?5)"#,
+        params![op, table_name, record_id, public_key, created_at],
     )?;
-    Ok(conn.last_insert_rowid())
+    let id = conn.last_insert_rowid();
+    // Ключ строки в AAD подписи — id, известный только после вставки
+    if signature.is_some() {
+        conn.execute(
+            "UPDATE sync_log SET signature = ?1 WHERE id = ?2",
+            params![at_rest::seal(conn, "sync_log", "signature", &id.to_string(), signature.as_deref())?, id],
+        )?;
+    }
+    Ok(id)
 }
 
 /// Добавить запись высокого уровня о попытке синхронизации
@@ -2215,16 +3021,30 @@ pub fn get_recent_sync_logs(conn: &Connection, limit: usize) -> Result<Vec<SyncL
     Ok(out)
 }
 
-/// Очистить журнал sync_logs
+/// Очистить журнал sync_logs. До удаления в журнал аудита пишется одна запись от имени current_actor:
+/// record_id "all:<число строк>", before_hash — хеш всех удаляемых строк
 pub fn clear_sync_logs(conn: &Connection) -> Result<(), CoreError> {
-    conn.execute("DELETE FROM sync_logs", [])?;
+    let tx = conn.unchecked_transaction()?;
+    let ids: Vec<String> = tx
+        .prepare("SELECT CAST(id AS TEXT) FROM sync_logs ORDER BY id")?
+        .query_map([], |r| r.get(0))?
+        .collect::<Result<_, _>>()?;
+    if !ids.is_empty() {
+        let mut h = blake3::Hasher::new();
+        for id in &ids {
+            h.update(audit::row_hash(&tx, "sync_logs", "id", id)?.unwrap_or_default().as_bytes());
+        }
+        audit::record(&tx, "sync_logs", &format!("all:{}", ids.len()), Some(h.finalize().to_hex().to_string()), None)?;
+    }
+    tx.execute("DELETE FROM sync_logs", [])?;
+    tx.commit()?;
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::trust_propagation::blend_trust;
+    use crate::trust_propagation::{blend_trust, TRUST_STABILITY_WINDOW};
 
     #[test]
     fn inserts_and_sync_log_work() {
@@ -2275,6 +3095,181 @@ mod tests {
         assert!(imp.public_key.is_none());
     }
 
+    #[test]
+    fn clear_sync_logs_is_audited_with_row_count_and_actor() {
+        let conn = open_db(":memory:").expect("open db");
+        clear_sync_logs(&conn).unwrap();
+        assert!(audit::load_entries(&conn, Some("sync_logs"), None, 10).unwrap().is_empty());
+
+        for status in ["success", "error", "success"] {
+            log_sync_event(&conn, "http://peer", "full", status, "").unwrap();
+        }
+        audit::with_actor("pk-operator", || clear_sync_logs(&conn)).unwrap();
+        assert!(get_recent_sync_logs(&conn, 10).unwrap().is_empty());
+        let entries = audit::load_entries(&conn, Some("sync_logs"), None, 10).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!((entries[0].op.as_str(), entries[0].record_id.as_str(), entries[0].actor.as_str()), ("delete", "all:3", "pk-operator"));
+        assert!(entries[0].before_hash.is_some() && entries[0].after_hash.is_none());
+        assert!(audit::verify(&conn).unwrap().is_intact());
+    }
+
+    #[test]
+    fn edited_legacy_event_keeps_its_uid() {
+        let mut conn = open_db(":memory:").expect("open db");
+        seed_knowledge_base(&mut conn, "en").expect("seed kb");
+        let ev_id = add_truth_event(&conn, NewTruthEvent {
+            description: "River flood".to_string(),
+            context_id: 1,
+            vector: true,
+            timestamp_start: 1_700_000_000,
+            code: 1,
+        })
+        .unwrap();
+        // Копия от пира без uid, затем та же запись с исправленным описанием
+        let legacy = TruthEvent { uid: String::new(), public_key: Some("pk-old-peer".into()), ..get_truth_event(&conn, ev_id).unwrap().unwrap() };
+        let id = upsert_truth_event(&conn, &legacy).unwrap();
+        let edited = TruthEvent { description: "River flood near the bridge".into(), ..legacy.clone() };
+        assert_eq!(edited.legacy_uid(), legacy.legacy_uid());
+        assert_eq!(upsert_truth_event(&conn, &edited).unwrap(), id);
+        let stored = get_truth_event_by_uid(&conn, &legacy.legacy_uid()).unwrap().unwrap();
+        assert_eq!((stored.id, stored.description.as_str()), (id, "River flood near the bridge"));
+    }
+
+    #[test]
+    fn ci_judgments_round_trip_through_judgments_table() {
+        // Оценки пишутся в таблицу judgments из схемы (раньше запрос шёл в несуществующую judgments_ci)
+        let conn = open_db(":memory:").expect("open db");
+        let pid = ci_ensure_participant(&conn, "pk-judge").unwrap();
+        let ci_event = uuid::Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO events_ci (id, title, event_type, created_by, created_at) VALUES (?1, 'outage', 'binary', ?2, 0)",
+            params![ci_event.to_string(), pid.to_string()],
+        ).unwrap();
+        let judgment = ci_models::Judgment {
+            id: uuid::Uuid::new_v4(),
+            participant_id: pid,
+            event_id: ci_event,
+            assessment: "true".into(),
+            confidence_level: 0.75,
+            reasoning: Some("status page confirmed".into()),
+            submitted_at: chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            signature: "sig".into(),
+        };
+        ci_insert_judgment(&conn, &judgment).unwrap();
+
+        let stored = ci_get_judgments_by_event(&conn, &ci_event).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].id, judgment.id);
+        assert_eq!(stored[0].reasoning.as_deref(), Some("status page confirmed"));
+        assert_eq!(stored[0].submitted_at, judgment.submitted_at);
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM judgments", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 1);
+        let total: i64 = conn
+            .query_row("SELECT total_judgments FROM participants WHERE id = ?1", [pid.to_string()], |r| r.get(0))
+            .unwrap();
+        assert_eq!(total, 1);
+    }
+
+    #[test]
+    fn full_text_search_over_events_statements_and_judgments() {
+        let mut conn = open_db(":memory:").expect("open db");
+        seed_knowledge_base(&mut conn, "en").expect("seed kb");
+        let ev = |d: &str, ctx: i64, ts: i64| NewTruthEvent { description: d.to_string(), context_id: ctx, vector: true, timestamp_start: ts, code: 1 };
+        let flood = add_truth_event(&conn, ev("River flood reported near the old bridge", 1, 100)).unwrap();
+        let fire = add_truth_event(&conn, ev("Forest fire rumour", 2, 200)).unwrap();
+        conn.execute("UPDATE truth_events SET public_key = 'pk-a', detected = 1 WHERE id = ?1", params![flood]).unwrap();
+        add_statement(&conn, NewStatement { event_id: fire, text: "Satellite images show no flood".into(), context: None, truth_score: None, parent_uid: None, relation: None }).unwrap();
+
+        let pid = ci_ensure_participant(&conn, "pk-judge").unwrap();
+        let ci_event = uuid::Uuid::new_v4();
+        conn.execute(
+            "INSERT INTO events_ci (id, title, event_type, created_by, created_at) VALUES (?1, 'flood', 'binary', ?2, 0)",
+            params![ci_event.to_string(), pid.to_string()],
+        ).unwrap();
+        ci_insert_judgment(&conn, &ci_models::Judgment {
+            id: uuid::Uuid::new_v4(),
+            participant_id: pid,
+            event_id: ci_event,
+            assessment: "false".into(),
+            confidence_level: 0.9,
+            reasoning: Some("water level gauges did not rise, no flood".into()),
+            submitted_at: chrono::Utc::now(),
+            signature: "sig".into(),
+        }).unwrap();
+
+        let hits = search(&conn, "flood", &SearchFilters::default(), 10).unwrap();
+        let kinds: HashSet<&str> = hits.iter().map(|h| h.kind.as_str()).collect();
+        assert_eq!(kinds, HashSet::from(["event", "statement", "judgment"]));
+        assert!(hits.windows(2).all(|w| w[0].rank >= w[1].rank));
+        let event_hit = hits.iter().find(|h| h.kind == "event").unwrap();
+        assert_eq!(event_hit.id, flood.to_string());
+        assert!(event_hit.snippet.contains("[flood]"));
+
+        // Фильтры: контекст и detected отсекают оценки, автор — чужие записи
+        let f = SearchFilters { context_id: Some(1), detected: Some(true), ..Default::default() };
+        let hits = search(&conn, "flood", &f, 10).unwrap();
+        assert_eq!(hits.iter().map(|h| h.kind.as_str()).collect::<Vec<_>>(), vec!["event"]);
+        let f = SearchFilters { author: Some("pk-judge".into()), ..Default::default() };
+        assert_eq!(search(&conn, "flood", &f, 10).unwrap().len(), 1);
+        let f = SearchFilters { since: Some(150), until: Some(250), ..Default::default() };
+        assert!(search(&conn, "flood", &f, 10).unwrap().iter().all(|h| h.kind != "event"));
+
+        // Индекс следит за изменениями; префиксы и кавычки в запросе безопасны
+        conn.execute("UPDATE truth_events SET description = 'Forest fire confirmed' WHERE id = ?1", params![fire]).unwrap();
+        assert_eq!(search(&conn, "confirm*", &SearchFilters::default(), 10).unwrap().len(), 1);
+        assert!(search(&conn, "rumour", &SearchFilters::default(), 10).unwrap().is_empty());
+        assert!(search(&conn, "\"bridge OR", &SearchFilters::default(), 10).unwrap().is_empty());
+        assert!(search(&conn, "  ", &SearchFilters::default(), 10).is_err());
+    }
+
+    #[test]
+    fn record_query_paginates_with_filters_and_sorting() {
+        let mut conn = open_db(":memory:").expect("open db");
+        seed_knowledge_base(&mut conn, "en").expect("seed kb");
+        let mut ids = Vec::new();
+        for i in 0..7i64 {
+            let id = add_truth_event(&conn, NewTruthEvent {
+                description: format!("event {}", i), context_id: 1 + i % 2, vector: true,
+                // Два события с одинаковым временем проверяют стабильность курсора
+                timestamp_start: 1000 + (i / 2) * 10, code: 1,
+            }).unwrap();
+            conn.execute("UPDATE truth_events SET collective_score = ?2, public_key = ?3 WHERE id = ?1",
+                params![id, i as f64 / 10.0, if i < 3 { "pk-a" } else { "pk-b" }]).unwrap();
+            add_impact(&conn, id, 1, i % 2 == 0, None).unwrap();
+            ids.push(id);
+        }
+
+        // Полный обход по времени в обратном порядке страницами по 3
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = query_truth_events(&conn, &RecordQuery::new().sort_by(SortField::Time, true).limit(3).after(cursor)).unwrap();
+            seen.extend(page.items.iter().map(|e| (e.timestamp_start, e.id)));
+            match page.next_cursor { Some(c) => cursor = Some(c), None => break }
+        }
+        let mut expected: Vec<(i64, i64)> = seen.clone();
+        expected.sort_by(|a, b| b.cmp(a));
+        assert_eq!(seen.len(), 7);
+        assert_eq!(seen, expected);
+
+        let q = RecordQuery::new().context(1).author("pk-b").score_range(Some(0.3), None);
+        let page = query_truth_events(&conn, &q).unwrap();
+        assert_eq!(page.items.iter().map(|e| e.id).collect::<Vec<_>>(), vec![ids[4], ids[6]]);
+        assert!(page.next_cursor.is_none());
+        let q = RecordQuery::new().time_range(Some(1010), Some(1020));
+        assert_eq!(query_truth_events(&conn, &q).unwrap().items.len(), 4);
+
+        // Влияния фильтруются по своему событию; текстовые id тоже листаются курсором
+        let first = query_impacts(&conn, &RecordQuery::new().context(2).limit(2)).unwrap();
+        assert_eq!(first.items.len(), 2);
+        let rest = query_impacts(&conn, &RecordQuery::new().context(2).limit(2).after(first.next_cursor)).unwrap();
+        assert_eq!(rest.items.len(), 1);
+        assert!(rest.next_cursor.is_none());
+
+        assert!(query_truth_events(&conn, &RecordQuery::new().after(Some(CURSOR_END.into()))).unwrap().items.is_empty());
+        assert!(query_statements(&conn, &RecordQuery::new().after(Some("garbage".into()))).is_err());
+    }
+
     #[test]
     fn recalc_ratings_basic() {
         let mut conn = open_db(":memory:").expect("open db");
@@ -2301,6 +3296,8 @@ mod tests {
             text: "Looks true".to_string(),
             context: None,
             truth_score: Some(0.9),
+            parent_uid: None,
+            relation: None,
         }).expect("add statement");
         assert!(stmt_id > 0);
 
@@ -2325,6 +3322,12 @@ mod tests {
         let rb = ratings.iter().find(|r| r.node_id == "nodeB").unwrap();
         assert!(rb.validations >= 1);
 
+        // Пересчёт пишет точку истории доверия для каждого узла
+        let hist = load_trust_history(&conn, "nodeA", None).expect("load trust history");
+        assert_eq!(hist.len(), 1);
+        assert_eq!(hist[0].source, "recalc");
+        assert_eq!(hist[0].recorded_at, 1_700_000_200);
+
         // Group rating exists and includes members
         let groups = load_group_ratings(&conn).expect("load group ratings");
         assert!(!groups.is_empty());
@@ -2338,6 +3341,47 @@ mod tests {
         assert!(graph.links.iter().any(|l| l.source == "nodeB" && l.target == "nodeA"));
     }
 
+    #[test]
+    fn graded_impacts_weight_aggregates() {
+        let mut conn = open_db(":memory:").expect("open db");
+        seed_knowledge_base(&mut conn, "en").expect("seed kb");
+        let ev_id = add_truth_event(&conn, NewTruthEvent {
+            description: "Graded event".to_string(),
+            context_id: 1,
+            vector: true,
+            timestamp_start: 1_700_000_100,
+            code: 1,
+        }).expect("insert event");
+        conn.execute("UPDATE truth_events SET public_key = 'nodeA' WHERE id = ?1", params![ev_id]).unwrap();
+
+        // "Скорее правда" с полной уверенностью и "резко ложно" с половинной
+        let mostly = add_graded_impact(&conn, ev_id, 1, 0.5, None, None).expect("graded impact");
+        let strongly = add_graded_impact(&conn, ev_id, 1, -1.0, Some(0.5), None).expect("graded impact");
+        conn.execute("UPDATE impact SET public_key = 'nodeB' WHERE id = ?1", params![mostly]).unwrap();
+        conn.execute("UPDATE impact SET public_key = 'nodeC' WHERE id = ?1", params![strongly]).unwrap();
+        assert!(matches!(add_graded_impact(&conn, ev_id, 1, 1.5, None, None), Err(CoreError::InvalidArg(_))));
+        assert!(matches!(add_graded_impact(&conn, ev_id, 1, 0.2, Some(2.0), None), Err(CoreError::InvalidArg(_))));
+
+        let im = get_impact(&conn, &strongly).unwrap().unwrap();
+        assert_eq!((im.value, im.magnitude, im.confidence), (false, Some(-1.0), Some(0.5)));
+
+        // (0.75 * 1.0 + 0.0 * 0.5) / 1.5
+        recalc_collective_truth(&conn).unwrap();
+        let ev = get_truth_event(&conn, ev_id).unwrap().unwrap();
+        assert!((ev.collective_score.unwrap() - 0.5).abs() < 1e-9);
+
+        recalc_progress_metrics(&conn, 1_700_000_200).unwrap();
+        let m = load_metrics(&conn).unwrap().pop().unwrap();
+        assert!((m.total_positive_impact - 0.5).abs() < 1e-9);
+        assert!((m.total_negative_impact - 0.5).abs() < 1e-9);
+
+        recalc_ratings(&conn, 1_700_000_200).unwrap();
+        let graph = load_graph(&conn).unwrap();
+        let weight = |source: &str| graph.links.iter().find(|l| l.source == source && l.target == "nodeA").unwrap().weight;
+        assert!((weight("nodeB") - 0.75).abs() < 1e-6);
+        assert!(weight("nodeC").abs() < 1e-6);
+    }
+
     #[test]
     fn merge_ratings_conflict_resolution() {
         let conn = open_db(":memory:").expect("open db");
@@ -2386,7 +3430,7 @@ mod tests {
         // last_updated обновляется до now_ts при merge_ratings
         assert!(nx.last_updated >= 150);
 
-        // Groups: берем более новый по last_updated
+        // Groups: оценки смешиваются с базовым весом 0.2, состав и время — из более новой записи
         conn.execute(
             "INSERT INTO group_ratings (group_id, members, avg_score, coherence, last_updated) VALUES ('g', '[]', 0.1, 0.5, 100)",
             [],
@@ -2401,8 +3445,9 @@ mod tests {
         let _ = merge_ratings(&conn, &[], &incoming_groups).unwrap();
         let groups = load_group_ratings(&conn).unwrap();
         let g = groups.iter().find(|g| g.group_id == "g").unwrap();
-        assert!((g.avg_score - 0.2).abs() < 1e-6);
-        assert!((g.coherence - 0.6).abs() < 1e-6);
+        assert!((g.avg_score - 0.12).abs() < 1e-6);
+        assert!((g.coherence - 0.52).abs() < 1e-6);
+        assert_eq!(g.members, vec!["a".to_string()]);
         assert_eq!(g.last_updated, 200);
     }
 
@@ -2437,6 +3482,412 @@ mod tests {
         assert!((blend_trust(-1.0, -1.0) - (-1.0)).abs() < 1e-6);
     }
 
+    #[test]
+    fn trust_history_recorded_and_stability() {
+        let conn = open_db(":memory:").expect("open db");
+        conn.execute(
+            "INSERT INTO node_ratings (node_id, events_true, events_false, validations, reused_events, trust_score, last_updated) VALUES ('nodeH',1,0,0,0,0.5,100)",
+            [],
+        ).unwrap();
+        // Без истории — базовая стабильность
+        assert!((trust_score_stability(&conn, "nodeH", TRUST_STABILITY_WINDOW).unwrap() - 1.0).abs() < 1e-6);
+
+        // Каждое слияние пишет точку истории
+        for remote in [0.9_f32, -0.9, 0.9] {
+            let incoming = vec![NodeRating {
+                node_id: "nodeH".into(),
+                events_true: 1,
+                events_false: 0,
+                validations: 0,
+                reused_events: 0,
+                trust_score: remote,
+                propagation_priority: 0.0,
+                last_updated: 200,
+            }];
+            merge_ratings(&conn, &incoming, &[]).unwrap();
+        }
+        let hist = load_trust_history(&conn, "nodeH", None).unwrap();
+        assert_eq!(hist.len(), 3);
+        assert!(hist.iter().all(|h| h.source == "merge"));
+        let last = load_node_ratings(&conn).unwrap().into_iter().find(|r| r.node_id == "nodeH").unwrap();
+        assert!((hist[0].trust_score - last.trust_score).abs() < 1e-6);
+
+        // Колебания снижают стабильность, окно ограничивает выборку
+        let s_all = trust_score_stability(&conn, "nodeH", TRUST_STABILITY_WINDOW).unwrap();
+        assert!(s_all < 1.0);
+        let s_one = trust_score_stability(&conn, "nodeH", 1).unwrap();
+        assert!((s_one - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn quality_index_uses_the_stability_window_from_params() {
+        // Размах в старых точках истории виден только в широком окне
+        let quality = |window: usize| {
+            let conn = open_db(":memory:").expect("open db");
+            for (score, at) in [(0.9_f32, 10), (-0.9, 20), (0.5, 30), (0.5, 40)] {
+                record_trust_history(&conn, "nodeW", score, "recalc", None, at).unwrap();
+            }
+            let incoming = vec![NodeRating {
+                node_id: "nodeW".into(),
+                events_true: 1,
+                events_false: 0,
+                validations: 1,
+                reused_events: 0,
+                trust_score: 0.5,
+                propagation_priority: 0.0,
+                last_updated: 200,
+            }];
+            let params = TrustParams { stability_window: window, ..TrustParams::default() };
+            merge_ratings_from_with(&conn, &params, None, &incoming, &[]).unwrap();
+            load_node_metrics(&conn, "nodeW").unwrap().unwrap().quality_index
+        };
+        assert!(quality(2) > quality(TRUST_STABILITY_WINDOW));
+    }
+
+    #[test]
+    fn recalc_prunes_trust_history_outside_the_window() {
+        let mut conn = open_db(":memory:").expect("open db");
+        seed_knowledge_base(&mut conn, "en").expect("seed kb");
+        // Точка каждые 6 часов в течение 25 суток
+        let points: Vec<i64> = (0..100).map(|i| 1_000_000 + i * 6 * 3600).collect();
+        for &at in &points {
+            record_trust_history(&conn, "nodeP", 0.1, "recalc", None, at).unwrap();
+        }
+        recalc_ratings(&conn, points[points.len() - 1] + 3600).unwrap();
+
+        let window = TrustParams::default().stability_window;
+        let kept = load_trust_history(&conn, "nodeP", Some(1000)).unwrap();
+        let recent: HashSet<i64> = points[points.len() - window..].iter().map(|t| t / 86_400).collect();
+        let older_days: HashSet<i64> = points[..points.len() - window].iter().map(|t| t / 86_400).filter(|d| !recent.contains(d)).collect();
+        assert_eq!(kept.len(), window + older_days.len());
+        // Окно стабильности не тронуто, за каждые старые сутки осталась последняя точка
+        assert!(kept.iter().take(window).map(|h| h.recorded_at).eq(points.iter().rev().take(window).copied()));
+        assert!(kept.iter().skip(window).all(|h| (h.recorded_at + 6 * 3600) / 86_400 != h.recorded_at / 86_400));
+    }
+
+    #[test]
+    fn merge_ratings_weighted_by_sender_reputation() {
+        let conn = open_db(":memory:").expect("open db");
+        conn.execute_batch(
+            r#"
+            INSERT INTO node_ratings (node_id, events_true, events_false, validations, reused_events, trust_score, last_updated) VALUES
+                ('target', 1, 0, 0, 0, 0.5, 100),
+                ('trusted', 5, 0, 0, 0, 1.0, 100),
+                ('shady', 0, 5, 0, 0, -0.6, 100);
+            INSERT INTO node_metrics (pubkey, last_seen, relay_success_rate, quality_index, propagation_priority) VALUES
+                ('trusted', 100, 1.0, 1.0, 0.5);
+            "#,
+        ).unwrap();
+        let incoming = |score: f32| vec![NodeRating {
+            node_id: "target".into(),
+            events_true: 1,
+            events_false: 0,
+            validations: 0,
+            reused_events: 0,
+            trust_score: score,
+            propagation_priority: 0.0,
+            last_updated: 200,
+        }];
+        let trust_of = |conn: &Connection| load_node_ratings(conn).unwrap()
+            .into_iter().find(|r| r.node_id == "target").unwrap().trust_score;
+
+        // Отправитель ниже порога — оценки игнорируются, истории нет
+        let diffs = merge_ratings_from(&conn, Some("shady"), &incoming(-1.0), &[]).unwrap();
+        assert!(diffs.is_empty());
+        assert!((trust_of(&conn) - 0.5).abs() < 1e-6);
+        assert!(load_trust_history(&conn, "target", None).unwrap().is_empty());
+
+        // Доверенный отправитель с полным качеством — базовый вес 0.2: 0.5*0.8 + 0.0*0.2 = 0.4
+        merge_ratings_from(&conn, Some("trusted"), &incoming(0.0), &[]).unwrap();
+        assert!((trust_of(&conn) - 0.4).abs() < 1e-6);
+
+        // Неизвестный отправитель — вес 0.07: 0.4*0.93 + 1.0*0.07 = 0.442
+        merge_ratings_from(&conn, Some("stranger"), &incoming(1.0), &[]).unwrap();
+        assert!((trust_of(&conn) - 0.442).abs() < 1e-5);
+
+        // Отправитель записан в истории для аудита
+        let hist = load_trust_history(&conn, "target", None).unwrap();
+        assert_eq!(hist[0].sender.as_deref(), Some("stranger"));
+        assert_eq!(hist[1].sender.as_deref(), Some("trusted"));
+    }
+
+    #[test]
+    fn merge_group_ratings_weighted_by_sender_reputation() {
+        let conn = open_db(":memory:").expect("open db");
+        conn.execute_batch(
+            r#"
+            INSERT INTO node_ratings (node_id, events_true, events_false, validations, reused_events, trust_score, last_updated) VALUES
+                ('trusted', 5, 0, 0, 0, 1.0, 100),
+                ('shady', 0, 5, 0, 0, -0.6, 100);
+            INSERT INTO node_metrics (pubkey, last_seen, relay_success_rate, quality_index, propagation_priority) VALUES
+                ('trusted', 100, 1.0, 1.0, 0.5);
+            INSERT INTO group_ratings (group_id, members, avg_score, coherence, last_updated) VALUES
+                ('grp-a', '["a","b"]', 0.5, 0.5, 100);
+            "#,
+        ).unwrap();
+        let incoming = |group_id: &str, score: f32| vec![GroupRating {
+            group_id: group_id.into(),
+            members: vec!["a".into(), "b".into(), "c".into()],
+            avg_score: score,
+            coherence: 1.0,
+            last_updated: 200,
+        }];
+
+        // Отправитель ниже порога — группа не меняется
+        merge_ratings_from(&conn, Some("shady"), &[], &incoming("grp-a", -1.0)).unwrap();
+        let g = get_group_rating(&conn, "grp-a").unwrap().unwrap();
+        assert_eq!((g.avg_score, g.coherence, g.members.len()), (0.5, 0.5, 2));
+
+        // Вес 0.2: avg 0.5*0.8 + 0.0*0.2 = 0.4, coherence 0.5*0.8 + 1.0*0.2 = 0.6; состав из более свежей записи
+        merge_ratings_from(&conn, Some("trusted"), &[], &incoming("grp-a", 0.0)).unwrap();
+        let g = get_group_rating(&conn, "grp-a").unwrap().unwrap();
+        assert!((g.avg_score - 0.4).abs() < 1e-6 && (g.coherence - 0.6).abs() < 1e-6);
+        assert_eq!((g.members.len(), g.last_updated), (3, 200));
+
+        // Новая группа от неизвестного отправителя (вес 0.07) не принимается по номиналу
+        merge_ratings_from(&conn, Some("stranger"), &[], &incoming("grp-b", 1.0)).unwrap();
+        let g = get_group_rating(&conn, "grp-b").unwrap().unwrap();
+        assert!((g.avg_score - 0.07).abs() < 1e-5 && (g.coherence - 0.07).abs() < 1e-5);
+    }
+
+    #[test]
+    fn recalc_keeps_groups_merged_from_peers() {
+        let conn = open_db(":memory:").expect("open db");
+        // Группа прошлого локального пересчёта, которой больше нет в графе
+        conn.execute(
+            "INSERT INTO group_ratings (group_id, members, avg_score, coherence, last_updated) VALUES ('grp-stale', '[\"x\"]', 0.1, 0.5, 100)",
+            [],
+        ).unwrap();
+        let remote = GroupRating {
+            group_id: "grp-remote".into(),
+            members: vec!["p".into(), "q".into()],
+            avg_score: 0.5,
+            coherence: 1.0,
+            last_updated: 200,
+        };
+        merge_ratings(&conn, &[], &[remote]).unwrap();
+
+        recalc_ratings(&conn, 300).unwrap();
+        assert!(get_group_rating(&conn, "grp-stale").unwrap().is_none());
+        let g = get_group_rating(&conn, "grp-remote").unwrap().expect("remote group survives recalc");
+        assert_eq!(g.members, vec!["p".to_string(), "q".to_string()]);
+        recalc_ratings(&conn, 400).unwrap();
+        assert!(get_group_rating(&conn, "grp-remote").unwrap().is_some());
+    }
+
+    #[test]
+    fn recalc_does_not_take_over_or_keep_stale_remote_groups() {
+        let mut conn = open_db(":memory:").expect("open db");
+        seed_knowledge_base(&mut conn, "en").expect("seed kb");
+        // Локальный кластер {a, b, c}: b и c подтверждают событие a
+        author_events(&conn, "a", 1, 0);
+        let a_event: String = conn.query_row("SELECT uid FROM truth_events WHERE public_key = 'a'", [], |r| r.get(0)).unwrap();
+        for validator in ["b", "c"] {
+            conn.execute(
+                "INSERT INTO impact (id, event_id, type_id, value, created_at, public_key, magnitude, confidence) VALUES (?1, ?2, 1, 1, 1, ?3, 1.0, 1.0)",
+                params![format!("imp-{}", validator), a_event, validator],
+            ).unwrap();
+        }
+        // Группа пира пересекается с кластером на 2/3, вторая не обновлялась дольше срока хранения
+        let now = 1_700_000_000;
+        let remote = GroupRating {
+            group_id: "grp-remote".into(),
+            members: vec!["a".into(), "b".into()],
+            avg_score: 0.5,
+            coherence: 1.0,
+            last_updated: now - 10,
+        };
+        let stale = GroupRating { group_id: "grp-old".into(), last_updated: now - REMOTE_GROUP_TTL_SECS - 1, ..remote.clone() };
+        merge_ratings(&conn, &[], &[remote, stale]).unwrap();
+        let merged = get_group_rating(&conn, "grp-remote").unwrap().unwrap();
+
+        recalc_ratings(&conn, now).unwrap();
+        let kept = get_group_rating(&conn, "grp-remote").unwrap().expect("remote group kept");
+        assert_eq!((kept.members, kept.avg_score, kept.last_updated), (merged.members, merged.avg_score, merged.last_updated));
+        let origin: String = conn.query_row("SELECT origin FROM group_ratings WHERE group_id = 'grp-remote'", [], |r| r.get(0)).unwrap();
+        assert_eq!(origin, "remote");
+        let local = load_group_ratings_by_origin(&conn, "local").unwrap();
+        assert_eq!(local.len(), 1);
+        assert_eq!(local[0].members, vec!["a".to_string(), "b".to_string(), "c".to_string()]);
+        assert!(get_group_rating(&conn, "grp-old").unwrap().is_none());
+    }
+
+    /// Автор с событиями, признанными истинными (true_n) и ложными (false_n) по дереву аргументов
+    fn author_events(conn: &Connection, author: &str, true_n: usize, false_n: usize) {
+        for score in std::iter::repeat_n(1.0, true_n).chain(std::iter::repeat_n(-1.0, false_n)) {
+            let ev = add_truth_event(conn, NewTruthEvent {
+                description: format!("{} event", author), context_id: 1, vector: true, timestamp_start: 1, code: 1,
+            }).unwrap();
+            conn.execute("UPDATE truth_events SET public_key = ?1 WHERE id = ?2", params![author, ev]).unwrap();
+            conn.execute(
+                "INSERT INTO statements (uid, event_id, text, truth_score, created_at, updated_at) VALUES (?1, ?2, 'claim', ?3, 1, 1)",
+                params![format!("{}-{}", author, ev), ev, score],
+            ).unwrap();
+        }
+    }
+
+    #[test]
+    fn trust_delegations_applied_on_recalc_and_revoked() {
+        let mut conn = open_db(":memory:").expect("open db");
+        seed_knowledge_base(&mut conn, "en").expect("seed kb");
+        // alice — известный узел с полным доверием: её дельты применяются без ослабления
+        author_events(&conn, "alice", 1, 0);
+        let now = 1_700_000_000;
+        let mut d = crate::models::TrustDelegation::new("alice", "bob", 0.15, now, Some(now + 3600));
+        d.signature = "sig".into();
+        assert!(upsert_trust_delegation(&conn, &d).unwrap());
+        // Повторная вставка не меняет состояние
+        assert!(!upsert_trust_delegation(&conn, &d).unwrap());
+
+        // Более ранняя запись той же пары перекрывается последней
+        let mut older = crate::models::TrustDelegation::new("alice", "bob", 0.05, now - 10, None);
+        older.signature = "sig".into();
+        upsert_trust_delegation(&conn, &older).unwrap();
+
+        recalc_ratings(&conn, now).unwrap();
+        let bob = load_node_ratings(&conn).unwrap().into_iter().find(|r| r.node_id == "bob").unwrap();
+        assert!((bob.trust_score - 0.15).abs() < 1e-6);
+
+        // Истёкшее делегирование не применяется: остаётся более раннее бессрочное
+        recalc_ratings(&conn, now + 7200).unwrap();
+        let bob = load_node_ratings(&conn).unwrap().into_iter().find(|r| r.node_id == "bob").unwrap();
+        assert!((bob.trust_score - 0.05).abs() < 1e-6);
+
+        // Отзыв, пришедший от пира, применяется к существующей записи
+        let mut revoked = older.clone();
+        revoked.revoked_at = Some(now + 7300);
+        revoked.revocation_signature = Some("rsig".into());
+        assert!(upsert_trust_delegation(&conn, &revoked).unwrap());
+        recalc_ratings(&conn, now + 7400).unwrap();
+        assert!(load_node_ratings(&conn).unwrap().iter().all(|r| r.node_id != "bob"));
+        assert_eq!(load_trust_delegations(&conn, Some(now + 7400)).unwrap().len(), 0);
+        assert_eq!(load_trust_delegations(&conn, None).unwrap().len(), 2);
+
+        // Слишком большая дельта и делегирование самому себе отклоняются
+        let big = crate::models::TrustDelegation::new("alice", "carol", 0.5, now, None);
+        assert!(upsert_trust_delegation(&conn, &big).is_err());
+        let own = crate::models::TrustDelegation::new("alice", "alice", 0.1, now, None);
+        assert!(upsert_trust_delegation(&conn, &own).is_err());
+    }
+
+    #[test]
+    fn delegations_from_unknown_keys_do_not_move_trust() {
+        let mut conn = open_db(":memory:").expect("open db");
+        seed_knowledge_base(&mut conn, "en").expect("seed kb");
+        let now = 1_700_000_000;
+        let delegate = |conn: &Connection, delegator: &str, target: &str, delta: f32| {
+            let mut d = crate::models::TrustDelegation::new(delegator, target, delta, now, None);
+            d.signature = "sig".into();
+            upsert_trust_delegation(conn, &d).unwrap();
+        };
+        let trust_of = |conn: &Connection, node: &str| {
+            load_node_ratings(conn).unwrap().into_iter().find(|r| r.node_id == node).map(|r| r.trust_score)
+        };
+
+        // Множество свежих ключей без активности — доверие получателя не меняется
+        for i in 0..50 {
+            delegate(&conn, &format!("sybil-{}", i), "target", MAX_DELEGATION_DELTA);
+        }
+        recalc_ratings(&conn, now).unwrap();
+        assert_eq!(trust_of(&conn, "target"), None);
+
+        // Узел с отрицательным доверием тоже не учитывается
+        author_events(&conn, "liar", 0, 1);
+        delegate(&conn, "liar", "target", -MAX_DELEGATION_DELTA);
+        recalc_ratings(&conn, now).unwrap();
+        assert_eq!(trust_of(&conn, "target"), None);
+
+        // Дельта взвешивается доверием делегирующего: trust 0.5 → 0.2 * 0.5
+        author_events(&conn, "half", 3, 1);
+        delegate(&conn, "half", "target", MAX_DELEGATION_DELTA);
+        recalc_ratings(&conn, now).unwrap();
+        assert!((trust_of(&conn, "target").unwrap() - 0.1).abs() < 1e-6);
+
+        // Сумма по получателю ограничена MAX_DELEGATION_DELTA
+        for key in ["trusted-1", "trusted-2"] {
+            author_events(&conn, key, 1, 0);
+            delegate(&conn, key, "target", MAX_DELEGATION_DELTA);
+        }
+        recalc_ratings(&conn, now).unwrap();
+        assert!((trust_of(&conn, "target").unwrap() - MAX_DELEGATION_DELTA).abs() < 1e-6);
+    }
+
+    #[test]
+    fn anomalies_detected_on_recalc_and_acknowledged() {
+        let mut conn = open_db(":memory:").expect("open db");
+        seed_knowledge_base(&mut conn, "en").expect("seed kb");
+        let now = Utc::now().timestamp();
+
+        let ev_id = add_truth_event(&conn, NewTruthEvent {
+            description: "Boosted event".to_string(),
+            context_id: 1,
+            vector: true,
+            timestamp_start: now - 100,
+            code: 1,
+        }).expect("insert event");
+        conn.execute("UPDATE truth_events SET public_key='author' WHERE id=?1", params![ev_id]).unwrap();
+        // Три свежих ключа подтверждают событие одного автора
+        for key in ["fresh1", "fresh2", "fresh3"] {
+            let id = add_impact(&conn, ev_id, 1, true, None).expect("add impact");
+            conn.execute("UPDATE impact SET public_key=?1 WHERE id=?2", params![key, id]).unwrap();
+        }
+
+        recalc_ratings(&conn, now).expect("recalc");
+        let alerts = load_alerts(&conn, false, Some(crate::anomaly::ALERT_COLLUSION_RING)).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].subject, "author");
+        assert_eq!(alerts[0].related.len(), 3);
+
+        let graph = load_graph(&conn).unwrap();
+        let author = graph.nodes.iter().find(|n| n.id == "author").unwrap();
+        assert_eq!(author.alerts, vec![crate::anomaly::ALERT_COLLUSION_RING.to_string()]);
+
+        // Подтверждённая аномалия не возвращается повторным пересчётом
+        acknowledge_alert(&conn, &alerts[0].id, now + 1).unwrap();
+        recalc_ratings(&conn, now + 2).expect("recalc");
+        assert!(load_alerts(&conn, false, None).unwrap().is_empty());
+        assert_eq!(load_alerts(&conn, true, None).unwrap().len(), 1);
+        assert!(acknowledge_alert(&conn, &alerts[0].id, now + 3).is_err());
+    }
+
+    #[test]
+    fn propagation_priority_uses_windowed_activity() {
+        let mut conn = open_db(":memory:").expect("open db");
+        seed_knowledge_base(&mut conn, "en").expect("seed kb");
+        let now = Utc::now().timestamp();
+        let day = 86_400;
+
+        // Два автора с одинаковой историей: busy активен сейчас, stale — месяц назад
+        for (key, at) in [("busy", now - 3600), ("stale", now - 30 * day)] {
+            let ev = add_truth_event(&conn, NewTruthEvent {
+                description: format!("event by {}", key),
+                context_id: 1,
+                vector: true,
+                timestamp_start: at,
+                code: 1,
+            }).expect("insert event");
+            conn.execute("UPDATE truth_events SET public_key=?1 WHERE id=?2", params![key, ev]).unwrap();
+        }
+        let params = TrustParams::default();
+        assert!(recent_activity(&conn, "busy", now, &params).unwrap() > 0.0);
+        assert_eq!(recent_activity(&conn, "stale", now, &params).unwrap(), 0.0);
+        // Широкое окно снова учитывает давние действия
+        let wide = TrustParams { activity_window_days: 60.0, activity_half_life_days: 30.0, ..params };
+        assert!(recent_activity(&conn, "stale", now, &wide).unwrap() > 0.0);
+
+        recalc_ratings(&conn, now).expect("recalc");
+        let busy = get_propagation_priority(&conn, "busy").unwrap();
+        let stale = get_propagation_priority(&conn, "stale").unwrap();
+        assert!(busy > stale);
+        let (live_busy, a_busy) = peer_propagation_priority(&conn, "busy", now).unwrap();
+        let (live_stale, a_stale) = peer_propagation_priority(&conn, "stale", now).unwrap();
+        assert!(live_busy > live_stale && a_busy > 0.0 && a_stale == 0.0);
+
+        // Успешная синхронизация с пиром по URL тоже считается активностью
+        log_sync_event(&conn, "http://peer:8080", "full", "success", "ok").unwrap();
+        assert!(recent_activity(&conn, "http://peer:8080", now + 1, &params).unwrap() > 0.0);
+    }
+
     #[test]
     fn rbac_users_basic_crud_and_sync() {
         let conn = open_db(":memory:").expect("open db");
@@ -2484,7 +3935,8 @@ pub fn upsert_node_metrics(
             |r| r.get::<_, f64>(0),
         )
         .unwrap_or(0.0) as f32;
-    let p = compute_propagation_priority(trust_score, prev_q, relay_success_rate.clamp(0.0, 1.0), prev_p);
+    let activity = recent_activity(conn, pubkey, chrono::Utc::now().timestamp(), &TrustParams::default())?;
+    let p = compute_propagation_priority(trust_score, prev_q, relay_success_rate.clamp(0.0, 1.0), activity, prev_p);
 
     conn.execute(
         "INSERT INTO node_metrics (pubkey, last_seen, relay_success_rate, quality_index, propagation_priority) VALUES (?1, ?2, ?3, COALESCE((SELECT quality_index FROM node_metrics WHERE pubkey=?1), 0.0), ?4)
@@ -2547,7 +3999,7 @@ pub fn flush_relay_metrics(conn: &Connection, relay_stats: &[(String, f32)]) ->
         let prev_q = load_node_metrics(conn, pubkey)?.map(|m| m.quality_index);
         let prev_p = load_node_metrics(conn, pubkey)?.map(|m| m.propagation_priority);
         let conflict_free_ratio = 1.0; // не знаем конфликтность в этом пути → считаем отсутствие конфликтов
-        let trust_score_stability = 1.0; // без истории оставим базовое значение
+        let trust_score_stability = trust_score_stability(conn, pubkey, TrustParams::default().stability_window)?;
         let q = compute_quality_index((*success_rate).clamp(0.0, 1.0), conflict_free_ratio, trust_score_stability, prev_q);
         // Для EMA приоритета требуется текущий trust_score
         let trust_score: f32 = conn
@@ -2557,7 +4009,8 @@ pub fn flush_relay_metrics(conn: &Connection, relay_stats: &[(String, f32)]) ->
                 |r| r.get::<_, f64>(0),
             )
             .unwrap_or(0.0) as f32;
-        let p = compute_propagation_priority(trust_score, q, (*success_rate).clamp(0.0, 1.0), prev_p);
+        let activity = recent_activity(conn, pubkey, chrono::Utc::now().timestamp(), &TrustParams::default())?;
+        let p = compute_propagation_priority(trust_score, q, (*success_rate).clamp(0.0, 1.0), activity, prev_p);
         upsert_node_metrics_with_quality_and_priority(conn, pubkey, chrono::Utc::now().timestamp(), *success_rate, q, p)?;
     }
     Ok(())
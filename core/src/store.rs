@@ -0,0 +1,407 @@
+use crate::collective_intelligence::models::{Consensus, Judgment};
+use crate::models::{
+    CoreError, GroupRating, Impact, NewStatement, NewTruthEvent, NodeMetrics, NodeRating, ProgressMetrics, Statement,
+    SyncLog, Tombstone, TruthEvent,
+};
+use crate::storage::{self, Page, RecordQuery};
+use rusqlite::Connection;
+
+mod memory;
+pub use memory::MemoryStore;
+
+/// Хранилище данных узла: события, утверждения, влияния, рейтинги, метрики, CI и журналы синхронизации.
+/// Реализации: `rusqlite::Connection` (основная, SQLite) и `MemoryStore` (тесты, встраивание).
+/// Методы принимают `&self`: SQLite сериализует запись сам, MemoryStore держит данные под RwLock.
+pub trait Store {
+    // События
+    fn add_event(&self, ev: NewTruthEvent) -> Result<i64, CoreError>;
+    fn get_event(&self, id: i64) -> Result<Option<TruthEvent>, CoreError>;
+    fn get_event_by_uid(&self, uid: &str) -> Result<Option<TruthEvent>, CoreError>;
+    /// Вставить или заменить копию события по uid (collective_score не переносится); локальный id
+    fn put_event(&self, ev: &TruthEvent) -> Result<i64, CoreError>;
+    fn set_event_detected(&self, id: i64, detected: bool, timestamp_end: Option<i64>, corrected: bool) -> Result<(), CoreError>;
+    fn query_events(&self, q: &RecordQuery) -> Result<Page<TruthEvent>, CoreError>;
+
+    // Утверждения
+    fn add_statement(&self, st: NewStatement) -> Result<i64, CoreError>;
+    fn get_statement(&self, id: i64) -> Result<Option<Statement>, CoreError>;
+    fn get_statement_by_uid(&self, uid: &str) -> Result<Option<Statement>, CoreError>;
+    /// Вставить или заменить копию утверждения по uid; событие ищется по event_uid. Локальный id
+    fn put_statement(&self, st: &Statement) -> Result<i64, CoreError>;
+    fn update_statement_score(&self, id: i64, truth_score: f32) -> Result<(), CoreError>;
+    /// Утверждения события, новые первыми
+    fn statements_for_event(&self, event_id: i64) -> Result<Vec<Statement>, CoreError>;
+    fn query_statements(&self, q: &RecordQuery) -> Result<Page<Statement>, CoreError>;
+
+    // Влияния (неизменяемые)
+    /// Оценка без степени: +1 или -1
+    fn add_impact(&self, event_id: i64, type_id: i64, value: bool, notes: Option<String>) -> Result<String, CoreError> {
+        self.add_graded_impact(event_id, type_id, if value { 1.0 } else { -1.0 }, None, notes)
+    }
+    /// Оценка со степенью -1..1 и уверенностью 0..1
+    fn add_graded_impact(&self, event_id: i64, type_id: i64, magnitude: f64, confidence: Option<f64>, notes: Option<String>) -> Result<String, CoreError>;
+    /// Вставить готовую запись; false — id уже есть
+    fn insert_impact(&self, im: &Impact) -> Result<bool, CoreError>;
+    fn get_impact(&self, id: &str) -> Result<Option<Impact>, CoreError>;
+    fn query_impacts(&self, q: &RecordQuery) -> Result<Page<Impact>, CoreError>;
+
+    // Удаления
+    /// Отметки удаления записи, старые первыми
+    fn tombstones_for(&self, table: &str, record_id: &str) -> Result<Vec<Tombstone>, CoreError>;
+    /// Сохранить отметку; false — id уже есть
+    fn put_tombstone(&self, t: &Tombstone) -> Result<bool, CoreError>;
+    /// Удалить запись с зависимыми (утверждения и влияния события); число удалённых строк
+    fn delete_record(&self, table: &str, record_id: &str) -> Result<usize, CoreError>;
+    /// Есть ли у ключа роль admin
+    fn is_admin(&self, pubkey: &str) -> Result<bool, CoreError>;
+
+    // Рейтинги
+    /// Рейтинги узлов по убыванию trust_score
+    fn node_ratings(&self) -> Result<Vec<NodeRating>, CoreError>;
+    fn put_node_rating(&self, r: &NodeRating) -> Result<(), CoreError>;
+    /// Рейтинги групп по group_id
+    fn group_ratings(&self) -> Result<Vec<GroupRating>, CoreError>;
+    fn put_group_rating(&self, g: &GroupRating) -> Result<(), CoreError>;
+
+    // Метрики
+    /// Пересчитать агрегаты progress_metrics на момент ts; возвращает id записи
+    fn recalc_progress_metrics(&self, ts: i64) -> Result<i64, CoreError>;
+    fn progress_metrics(&self) -> Result<Vec<ProgressMetrics>, CoreError>;
+    fn node_metrics(&self, pubkey: &str) -> Result<Option<NodeMetrics>, CoreError>;
+    fn all_node_metrics(&self) -> Result<Vec<NodeMetrics>, CoreError>;
+    fn put_node_metrics(&self, m: &NodeMetrics) -> Result<(), CoreError>;
+
+    // Коллективный интеллект
+    /// id участника по публичному ключу; создаёт участника при первом обращении
+    fn ensure_participant(&self, public_key: &str) -> Result<uuid::Uuid, CoreError>;
+    /// Повторная оценка того же события участником — ошибка
+    fn insert_judgment(&self, j: &Judgment) -> Result<(), CoreError>;
+    /// Оценки события по времени подачи
+    fn judgments_by_event(&self, event_id: &uuid::Uuid) -> Result<Vec<Judgment>, CoreError>;
+    /// Консенсус события
+    fn consensus(&self, event_id: &uuid::Uuid) -> Result<Option<Consensus>, CoreError>;
+    fn put_consensus(&self, c: &Consensus) -> Result<(), CoreError>;
+
+    // Журналы синхронизации
+    /// Запись sync_log по отдельной операции над таблицей
+    fn log_sync(&self, op: &str, table_name: &str, record_id: &str, signature: Option<String>, public_key: Option<String>) -> Result<i64, CoreError>;
+    /// Запись sync_logs высокого уровня (сеанс с пиром)
+    fn log_sync_event(&self, peer_url: &str, mode: &str, status: &str, details: &str) -> Result<i64, CoreError>;
+    /// Последние записи sync_logs, новые первыми
+    fn recent_sync_logs(&self, limit: usize) -> Result<Vec<SyncLog>, CoreError>;
+    fn clear_sync_logs(&self) -> Result<(), CoreError>;
+}
+
+/// SQLite: делегирует функциям core_lib::storage
+impl Store for Connection {
+    fn add_event(&self, ev: NewTruthEvent) -> Result<i64, CoreError> {
+        storage::add_truth_event(self, ev)
+    }
+    fn get_event(&self, id: i64) -> Result<Option<TruthEvent>, CoreError> {
+        storage::get_truth_event(self, id)
+    }
+    fn get_event_by_uid(&self, uid: &str) -> Result<Option<TruthEvent>, CoreError> {
+        storage::get_truth_event_by_uid(self, uid)
+    }
+    fn put_event(&self, ev: &TruthEvent) -> Result<i64, CoreError> {
+        storage::upsert_truth_event(self, ev)
+    }
+    fn set_event_detected(&self, id: i64, detected: bool, timestamp_end: Option<i64>, corrected: bool) -> Result<(), CoreError> {
+        storage::set_event_detected(self, id, detected, timestamp_end, corrected)
+    }
+    fn query_events(&self, q: &RecordQuery) -> Result<Page<TruthEvent>, CoreError> {
+        storage::query_truth_events(self, q)
+    }
+
+    fn add_statement(&self, st: NewStatement) -> Result<i64, CoreError> {
+        storage::add_statement(self, st)
+    }
+    fn get_statement(&self, id: i64) -> Result<Option<Statement>, CoreError> {
+        storage::get_statement(self, id)
+    }
+    fn get_statement_by_uid(&self, uid: &str) -> Result<Option<Statement>, CoreError> {
+        storage::get_statement_by_uid(self, uid)
+    }
+    fn put_statement(&self, st: &Statement) -> Result<i64, CoreError> {
+        storage::upsert_statement(self, st)
+    }
+    fn update_statement_score(&self, id: i64, truth_score: f32) -> Result<(), CoreError> {
+        storage::update_statement_score(self, id, truth_score)
+    }
+    fn statements_for_event(&self, event_id: i64) -> Result<Vec<Statement>, CoreError> {
+        storage::get_statements_for_event(self, event_id)
+    }
+    fn query_statements(&self, q: &RecordQuery) -> Result<Page<Statement>, CoreError> {
+        storage::query_statements(self, q)
+    }
+
+    fn add_graded_impact(&self, event_id: i64, type_id: i64, magnitude: f64, confidence: Option<f64>, notes: Option<String>) -> Result<String, CoreError> {
+        storage::add_graded_impact(self, event_id, type_id, magnitude, confidence, notes)
+    }
+    fn insert_impact(&self, im: &Impact) -> Result<bool, CoreError> {
+        storage::insert_impact_record(self, im)
+    }
+    fn get_impact(&self, id: &str) -> Result<Option<Impact>, CoreError> {
+        storage::get_impact(self, id)
+    }
+    fn query_impacts(&self, q: &RecordQuery) -> Result<Page<Impact>, CoreError> {
+        storage::query_impacts(self, q)
+    }
+
+    fn tombstones_for(&self, table: &str, record_id: &str) -> Result<Vec<Tombstone>, CoreError> {
+        storage::get_tombstones_for(self, table, record_id)
+    }
+    fn put_tombstone(&self, t: &Tombstone) -> Result<bool, CoreError> {
+        storage::insert_tombstone(self, t)
+    }
+    fn delete_record(&self, table: &str, record_id: &str) -> Result<usize, CoreError> {
+        storage::delete_record(self, table, record_id)
+    }
+    fn is_admin(&self, pubkey: &str) -> Result<bool, CoreError> {
+        Ok(storage::get_user_by_pubkey(self, pubkey)?.is_some_and(|u| u.role == "admin"))
+    }
+
+    fn node_ratings(&self) -> Result<Vec<NodeRating>, CoreError> {
+        storage::load_node_ratings(self)
+    }
+    fn put_node_rating(&self, r: &NodeRating) -> Result<(), CoreError> {
+        storage::upsert_node_rating(self, r)
+    }
+    fn group_ratings(&self) -> Result<Vec<GroupRating>, CoreError> {
+        storage::load_group_ratings(self)
+    }
+    fn put_group_rating(&self, g: &GroupRating) -> Result<(), CoreError> {
+        storage::upsert_group_rating(self, g)
+    }
+
+    fn recalc_progress_metrics(&self, ts: i64) -> Result<i64, CoreError> {
+        storage::recalc_progress_metrics(self, ts)
+    }
+    fn progress_metrics(&self) -> Result<Vec<ProgressMetrics>, CoreError> {
+        storage::load_metrics(self)
+    }
+    fn node_metrics(&self, pubkey: &str) -> Result<Option<NodeMetrics>, CoreError> {
+        storage::load_node_metrics(self, pubkey)
+    }
+    fn all_node_metrics(&self) -> Result<Vec<NodeMetrics>, CoreError> {
+        storage::load_all_node_metrics(self)
+    }
+    fn put_node_metrics(&self, m: &NodeMetrics) -> Result<(), CoreError> {
+        storage::upsert_node_metrics_with_quality_and_priority(
+            self,
+            &m.pubkey,
+            m.last_seen,
+            m.relay_success_rate,
+            m.quality_index,
+            m.propagation_priority,
+        )
+    }
+
+    fn ensure_participant(&self, public_key: &str) -> Result<uuid::Uuid, CoreError> {
+        storage::ci_ensure_participant(self, public_key)
+    }
+    fn insert_judgment(&self, j: &Judgment) -> Result<(), CoreError> {
+        storage::ci_insert_judgment(self, j)
+    }
+    fn judgments_by_event(&self, event_id: &uuid::Uuid) -> Result<Vec<Judgment>, CoreError> {
+        storage::ci_get_judgments_by_event(self, event_id)
+    }
+    fn consensus(&self, event_id: &uuid::Uuid) -> Result<Option<Consensus>, CoreError> {
+        storage::ci_get_consensus_by_event(self, event_id)
+    }
+    fn put_consensus(&self, c: &Consensus) -> Result<(), CoreError> {
+        storage::ci_upsert_consensus(self, c)
+    }
+
+    fn log_sync(&self, op: &str, table_name: &str, record_id: &str, signature: Option<String>, public_key: Option<String>) -> Result<i64, CoreError> {
+        storage::log_sync(self, op, table_name, record_id, signature, public_key)
+    }
+    fn log_sync_event(&self, peer_url: &str, mode: &str, status: &str, details: &str) -> Result<i64, CoreError> {
+        storage::log_sync_event(self, peer_url, mode, status, details)
+    }
+    fn recent_sync_logs(&self, limit: usize) -> Result<Vec<SyncLog>, CoreError> {
+        storage::get_recent_sync_logs(self, limit)
+    }
+    fn clear_sync_logs(&self) -> Result<(), CoreError> {
+        storage::clear_sync_logs(self)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::SortField;
+
+    fn ev(description: &str, context_id: i64, ts: i64) -> NewTruthEvent {
+        NewTruthEvent { description: description.into(), context_id, vector: true, timestamp_start: ts, code: 1 }
+    }
+
+    fn judgment(participant_id: uuid::Uuid, event_id: uuid::Uuid, assessment: &str, at: i64) -> Judgment {
+        Judgment {
+            id: uuid::Uuid::new_v4(),
+            participant_id,
+            event_id,
+            assessment: assessment.into(),
+            confidence_level: 0.8,
+            reasoning: None,
+            submitted_at: chrono::DateTime::<chrono::Utc>::from_timestamp(at, 0).unwrap(),
+            signature: "sig".into(),
+        }
+    }
+
+    /// Один сценарий для обеих реализаций; возвращает сводку, которая должна совпасть
+    fn exercise(store: &dyn Store, ci_event: uuid::Uuid) -> serde_json::Value {
+        let a = store.add_event(ev("first", 1, 100)).unwrap();
+        let b = store.add_event(ev("second", 2, 200)).unwrap();
+        let c = store.add_event(ev("third", 1, 200)).unwrap();
+        assert!(store.add_event(ev("  ", 1, 1)).is_err());
+        store.set_event_detected(b, true, Some(250), false).unwrap();
+
+        let mut remote = store.get_event(a).unwrap().unwrap();
+        remote.description = "first (edited)".into();
+        remote.public_key = Some("pk-remote".into());
+        assert_eq!(store.put_event(&remote).unwrap(), a);
+        store.set_event_detected(a, false, None, true).unwrap();
+
+        let s1 = store.add_statement(NewStatement { event_id: a, text: "claim".into(), context: None, truth_score: Some(0.4), parent_uid: None, relation: None }).unwrap();
+        store.update_statement_score(s1, 0.9).unwrap();
+        let mut copy = store.get_statement(s1).unwrap().unwrap();
+        copy.id = 100;
+        copy.uid = "remote-statement".into();
+        copy.text = "copied claim".into();
+        copy.created_at -= 10;
+        let copy_id = store.put_statement(&copy).unwrap();
+        assert_ne!(copy_id, 100, "remote id is not a local id");
+        assert_eq!(store.get_statement_by_uid("remote-statement").unwrap().map(|s| s.event_id), Some(a));
+        let s2 = store.add_statement(NewStatement { event_id: b, text: "other".into(), context: None, truth_score: None, parent_uid: None, relation: None }).unwrap();
+
+        store.add_impact(b, 1, true, None).unwrap();
+        assert!(store.add_impact(999, 1, true, None).is_err());
+        let c_uid = store.get_event(c).unwrap().unwrap().uid;
+        let im = Impact {
+            id: "imp-1".into(),
+            event_id: c_uid.clone(),
+            type_id: 1,
+            value: false,
+            magnitude: None,
+            confidence: None,
+            notes: None,
+            created_at: 300,
+            signature: None,
+            public_key: Some("pk-remote".into()),
+        };
+        assert!(store.insert_impact(&im).unwrap());
+        assert!(!store.insert_impact(&im).unwrap());
+        store.recalc_progress_metrics(1_000).unwrap();
+
+        for (node, trust) in [("n1", 0.5), ("n2", 0.9)] {
+            store.put_node_rating(&NodeRating {
+                node_id: node.into(),
+                events_true: 1,
+                events_false: 0,
+                validations: 2,
+                reused_events: 0,
+                trust_score: trust,
+                propagation_priority: 0.2,
+                last_updated: 10,
+            }).unwrap();
+        }
+        store.put_group_rating(&GroupRating { group_id: "g".into(), members: vec!["n1".into(), "n2".into()], avg_score: 0.7, coherence: 1.0, last_updated: 10 }).unwrap();
+        store.put_node_metrics(&NodeMetrics { pubkey: "n1".into(), last_seen: 10, relay_success_rate: 0.5, quality_index: 0.7, propagation_priority: 0.3 }).unwrap();
+
+        let pid = store.ensure_participant("pk-judge").unwrap();
+        assert_eq!(store.ensure_participant("pk-judge").unwrap(), pid);
+        let other = store.ensure_participant("pk-other").unwrap();
+        store.insert_judgment(&judgment(pid, ci_event, "true", 50)).unwrap();
+        store.insert_judgment(&judgment(other, ci_event, "false", 40)).unwrap();
+        assert!(store.insert_judgment(&judgment(pid, ci_event, "false", 60)).is_err());
+        let consensus = Consensus {
+            id: uuid::Uuid::new_v4(),
+            event_id: ci_event,
+            consensus_value: "true".into(),
+            confidence_score: 0.6,
+            participant_count: 2,
+            calculated_at: chrono::Utc::now(),
+            algorithm_version: "1.0.0".into(),
+        };
+        store.put_consensus(&consensus).unwrap();
+        store.put_consensus(&Consensus { consensus_value: "false".into(), ..consensus.clone() }).unwrap();
+
+        store.log_sync("insert", "truth_events", &a.to_string(), None, None).unwrap();
+        store.log_sync_event("http://peer", "full", "success", "ok").unwrap();
+        store.log_sync_event("http://peer", "full", "error", "timeout").unwrap();
+        let logs: Vec<String> = store.recent_sync_logs(10).unwrap().into_iter().map(|l| l.status).collect();
+        store.clear_sync_logs().unwrap();
+
+        let events = store.query_events(&RecordQuery::new().sort_by(SortField::Time, true)).unwrap();
+        let ctx1 = store.query_events(&RecordQuery::new().context(1).limit(1)).unwrap();
+        let ctx1_rest = store.query_events(&RecordQuery::new().context(1).limit(1).after(ctx1.next_cursor.clone())).unwrap();
+        let metrics = store.progress_metrics().unwrap();
+
+        let mut summary = serde_json::json!({
+            "events": events.items.iter().map(|e| (e.id, e.description.clone(), e.detected, e.corrected, e.timestamp_end, e.public_key.clone())).collect::<Vec<_>>(),
+            "ctx1": [ctx1.items.iter().map(|e| e.id).collect::<Vec<_>>(), ctx1_rest.items.iter().map(|e| e.id).collect::<Vec<_>>()],
+            "ctx1_more": [ctx1.next_cursor.is_some(), ctx1_rest.next_cursor.is_some()],
+            "statements": store.statements_for_event(a).unwrap().iter().map(|s| (s.id, s.text.clone(), s.truth_score)).collect::<Vec<_>>(),
+            "next_statement": s2,
+            "undetected_statements": store.query_statements(&RecordQuery::new().detected(false)).unwrap().items.len(),
+            "scored_statements": store.query_statements(&RecordQuery::new().score_range(Some(0.5), None)).unwrap().items.len(),
+            "impacts_by_author": store.query_impacts(&RecordQuery::new().author("pk-remote")).unwrap().items.iter().map(|i| i.id.clone()).collect::<Vec<_>>(),
+            "impacts_ctx2": store.query_impacts(&RecordQuery::new().context(2)).unwrap().items.len(),
+            "progress": metrics.iter().map(|m| (m.total_events, m.total_positive_impact, m.total_negative_impact, m.trend)).collect::<Vec<_>>(),
+            "ratings": store.node_ratings().unwrap().iter().map(|r| r.node_id.clone()).collect::<Vec<_>>(),
+            "groups": store.group_ratings().unwrap().iter().map(|g| g.members.clone()).collect::<Vec<_>>(),
+            "metrics": store.node_metrics("n1").unwrap().map(|m| m.quality_index),
+            "all_metrics": store.all_node_metrics().unwrap().len(),
+            "judgments": store.judgments_by_event(&ci_event).unwrap().iter().map(|j| j.assessment.clone()).collect::<Vec<_>>(),
+            "consensus": store.consensus(&ci_event).unwrap().map(|c| c.consensus_value),
+            "logs": logs,
+            "logs_after_clear": store.recent_sync_logs(10).unwrap().len(),
+        });
+
+        // Событие пира с тем же целым id, но другим uid — новая запись, а не замена
+        let foreign = TruthEvent { uid: "peer-event".into(), description: "peer event".into(), ..remote.clone() };
+        let foreign_id = store.put_event(&foreign).unwrap();
+        assert_ne!(foreign_id, a);
+        assert_eq!(store.get_event(a).unwrap().unwrap().description, "first (edited)");
+        assert_eq!(store.get_event_by_uid("peer-event").unwrap().map(|e| e.id), Some(foreign_id));
+
+        // Удаление события с зависимыми — после сводки, чтобы не менять её остальные поля
+        assert_eq!(store.get_impact("imp-1").unwrap().map(|i| i.public_key), Some(Some("pk-remote".into())));
+        let tomb = Tombstone::new("truth_events", &c_uid, "pk-remote", None, 400);
+        assert!(store.put_tombstone(&tomb).unwrap());
+        assert!(!store.put_tombstone(&tomb).unwrap());
+        let deleted = store.delete_record("truth_events", &c_uid).unwrap();
+        assert!(store.get_impact("imp-1").unwrap().is_none());
+        assert!(store.delete_record("users", "x").is_err());
+        summary["deleted"] = deleted.into();
+        summary["tombstones"] = store.tombstones_for("truth_events", &c_uid).unwrap().len().into();
+        summary["admin"] = store.is_admin("pk-remote").unwrap().into();
+        summary
+    }
+
+    #[test]
+    fn sqlite_and_memory_backends_agree() {
+        let mut conn = storage::open_db(":memory:").unwrap();
+        storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        // CI-события создаются вне Store; для SQLite нужна строка events_ci под внешний ключ
+        let ci_event = uuid::Uuid::new_v4();
+        let creator = conn.ensure_participant("pk-judge").unwrap();
+        conn.execute(
+            "INSERT INTO events_ci (id, title, event_type, created_by, created_at) VALUES (?1, 'ci', 'binary', ?2, 0)",
+            rusqlite::params![ci_event.to_string(), creator.to_string()],
+        ).unwrap();
+
+        let sqlite = exercise(&conn, ci_event);
+        let memory = exercise(&MemoryStore::new(), ci_event);
+        assert_eq!(sqlite, memory);
+        assert_eq!(sqlite["ctx1_more"], serde_json::json!([true, false]));
+        assert_eq!(sqlite["statements"][0][0], serde_json::json!(1));
+        assert_eq!(sqlite["next_statement"], serde_json::json!(3));
+        assert_eq!(sqlite["judgments"], serde_json::json!(["false", "true"]));
+        assert_eq!(sqlite["logs"], serde_json::json!(["error", "success"]));
+        // событие и его влияние
+        assert_eq!(sqlite["deleted"], serde_json::json!(2));
+    }
+}
@@ -0,0 +1,559 @@
+use super::Store;
+use crate::collective_intelligence::models::{Consensus, Judgment};
+use crate::models::{
+    CoreError, GroupRating, Impact, NewStatement, NewTruthEvent, NodeMetrics, NodeRating, ProgressMetrics, Statement,
+    SyncLog, Tombstone, TruthEvent,
+};
+use crate::storage::{decode_cursor, encode_cursor, Page, RecordQuery, SortField, CURSOR_END};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Хранилище в памяти с той же семантикой, что и SQLite-схема (без внешних ключей).
+/// Подходит для тестов и встраивания без файла БД.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    state: RwLock<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    events: BTreeMap<i64, TruthEvent>,
+    events_seq: i64,
+    statements: BTreeMap<i64, Statement>,
+    statements_seq: i64,
+    impacts: BTreeMap<String, Impact>,
+    node_ratings: BTreeMap<String, NodeRating>,
+    group_ratings: BTreeMap<String, GroupRating>,
+    progress: Vec<ProgressMetrics>,
+    node_metrics: BTreeMap<String, NodeMetrics>,
+    participants: BTreeMap<String, uuid::Uuid>,
+    judgments: Vec<Judgment>,
+    consensus: Vec<Consensus>,
+    sync_log: Vec<SyncLogRecord>,
+    sync_logs: Vec<SyncLog>,
+    sync_logs_seq: i64,
+    tombstones: Vec<Tombstone>,
+    admins: BTreeSet<String>,
+}
+
+/// Строка sync_log (журнал операций над таблицами)
+#[derive(Debug)]
+#[allow(dead_code)]
+struct SyncLogRecord {
+    op: String,
+    table_name: String,
+    record_id: String,
+    signature: Option<String>,
+    public_key: Option<String>,
+    created_at: i64,
+}
+
+/// Ключ записи для сортировки и курсора: целый id событий/утверждений, текстовый — влияний
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum RowId {
+    Int(i64),
+    Text(String),
+}
+
+/// Поля записи, к которым применяются фильтры RecordQuery (None ведёт себя как NULL в SQL)
+struct RowAttrs<'a> {
+    time: i64,
+    id: RowId,
+    author: Option<&'a str>,
+    context_id: Option<i64>,
+    detected: Option<bool>,
+    score: Option<f64>,
+}
+
+/// SQLite хранит время в секундах
+fn whole_seconds(t: DateTime<Utc>) -> DateTime<Utc> {
+    DateTime::<Utc>::from_timestamp(t.timestamp(), 0).unwrap_or(t)
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, State> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, State> {
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Выдать ключу роль admin (в SQLite — таблица users)
+    pub fn grant_admin(&self, pubkey: &str) {
+        self.write().admins.insert(pubkey.to_string());
+    }
+}
+
+impl State {
+    fn event_for(&self, event_id: i64) -> Option<&TruthEvent> {
+        self.events.get(&event_id)
+    }
+
+    fn event_by_uid(&self, uid: &str) -> Option<&TruthEvent> {
+        self.events.values().find(|e| e.uid == uid)
+    }
+}
+
+/// Выборка страницы по RecordQuery с тем же порядком и курсорами, что и storage::query_page
+fn query_page<'a, T: Clone + 'a>(
+    rows: impl Iterator<Item = &'a T>,
+    q: &RecordQuery,
+    int_id: bool,
+    attrs: impl Fn(&'a T) -> RowAttrs<'a>,
+) -> Result<Page<T>, CoreError> {
+    if q.cursor.as_deref() == Some(CURSOR_END) {
+        return Ok(Page { items: Vec::new(), next_cursor: None });
+    }
+    let after = match q.cursor.as_deref() {
+        Some(cursor) => {
+            let (time, id) = decode_cursor(cursor)?;
+            let id = if int_id {
+                RowId::Int(id.parse().map_err(|_| CoreError::InvalidArg(format!("bad cursor '{}'", cursor)))?)
+            } else {
+                RowId::Text(id)
+            };
+            Some((time, id))
+        }
+        None => None,
+    };
+    let sort_key = |a: &RowAttrs<'_>| match q.sort {
+        SortField::Id => (0, a.id.clone()),
+        SortField::Time => (a.time, a.id.clone()),
+    };
+    let beyond = |key: &(i64, RowId), cursor: &(i64, RowId)| {
+        let cursor = match q.sort {
+            SortField::Id => (0, cursor.1.clone()),
+            SortField::Time => cursor.clone(),
+        };
+        if q.descending { *key < cursor } else { *key > cursor }
+    };
+
+    let mut matched: Vec<((i64, RowId), &T)> = rows
+        .filter_map(|row| {
+            let a = attrs(row);
+            let ok = q.context_id.is_none_or(|c| a.context_id == Some(c))
+                && q.author.as_deref().is_none_or(|p| a.author == Some(p))
+                && q.detected.is_none_or(|d| a.detected == Some(d))
+                && q.since.is_none_or(|t| a.time >= t)
+                && q.until.is_none_or(|t| a.time <= t)
+                && q.min_score.is_none_or(|v| a.score.is_some_and(|s| s >= v))
+                && q.max_score.is_none_or(|v| a.score.is_some_and(|s| s <= v));
+            let key = sort_key(&a);
+            (ok && after.as_ref().is_none_or(|c| beyond(&key, c))).then_some((key, row))
+        })
+        .collect();
+    matched.sort_by(|x, y| if q.descending { y.0.cmp(&x.0) } else { x.0.cmp(&y.0) });
+
+    let size = q.page_size();
+    let next_cursor = if matched.len() > size {
+        matched.truncate(size);
+        matched.last().map(|(_, row)| {
+            let a = attrs(row);
+            let id = match a.id {
+                RowId::Int(i) => i.to_string(),
+                RowId::Text(s) => s,
+            };
+            encode_cursor(a.time, &id)
+        })
+    } else {
+        None
+    };
+    Ok(Page { items: matched.into_iter().map(|(_, row)| row.clone()).collect(), next_cursor })
+}
+
+impl Store for MemoryStore {
+    fn add_event(&self, ev: NewTruthEvent) -> Result<i64, CoreError> {
+        if ev.description.trim().is_empty() {
+            return Err(CoreError::InvalidArg("description is empty".into()));
+        }
+        let mut st = self.write();
+        st.events_seq += 1;
+        let id = st.events_seq;
+        st.events.insert(id, TruthEvent {
+            id,
+            uid: crate::models::new_uid(),
+            description: ev.description,
+            context_id: ev.context_id,
+            vector: ev.vector,
+            detected: None,
+            corrected: false,
+            timestamp_start: ev.timestamp_start,
+            timestamp_end: None,
+            code: ev.code,
+            signature: None,
+            public_key: None,
+            collective_score: None,
+        });
+        Ok(id)
+    }
+
+    fn get_event(&self, id: i64) -> Result<Option<TruthEvent>, CoreError> {
+        Ok(self.read().events.get(&id).cloned())
+    }
+
+    fn get_event_by_uid(&self, uid: &str) -> Result<Option<TruthEvent>, CoreError> {
+        Ok(self.read().event_by_uid(uid).cloned())
+    }
+
+    fn put_event(&self, ev: &TruthEvent) -> Result<i64, CoreError> {
+        let mut st = self.write();
+        let uid = if ev.uid.is_empty() { ev.legacy_uid() } else { ev.uid.clone() };
+        let (id, collective_score) = match st.event_by_uid(&uid) {
+            Some(e) => (e.id, e.collective_score),
+            None => {
+                st.events_seq += 1;
+                (st.events_seq, None)
+            }
+        };
+        st.events.insert(id, TruthEvent { id, uid, collective_score, ..ev.clone() });
+        Ok(id)
+    }
+
+    fn set_event_detected(&self, id: i64, detected: bool, timestamp_end: Option<i64>, corrected: bool) -> Result<(), CoreError> {
+        if let Some(e) = self.write().events.get_mut(&id) {
+            e.detected = Some(detected);
+            e.timestamp_end = timestamp_end.or(e.timestamp_end);
+            e.corrected = corrected;
+        }
+        Ok(())
+    }
+
+    fn query_events(&self, q: &RecordQuery) -> Result<Page<TruthEvent>, CoreError> {
+        let st = self.read();
+        query_page(st.events.values(), q, true, |e| RowAttrs {
+            time: e.timestamp_start,
+            id: RowId::Int(e.id),
+            author: e.public_key.as_deref(),
+            context_id: Some(e.context_id),
+            detected: e.detected,
+            score: e.collective_score,
+        })
+    }
+
+    fn add_statement(&self, new_stmt: NewStatement) -> Result<i64, CoreError> {
+        if new_stmt.text.trim().is_empty() {
+            return Err(CoreError::InvalidArg("statement text is empty".into()));
+        }
+        crate::argument::check_relation(new_stmt.parent_uid.as_deref(), new_stmt.relation.as_deref())?;
+        let now = Utc::now().timestamp();
+        let mut st = self.write();
+        if let Some(parent_uid) = &new_stmt.parent_uid {
+            let parent = st.statements.values().find(|s| &s.uid == parent_uid).ok_or_else(|| CoreError::NotFound(format!("statement {}", parent_uid)))?;
+            if parent.event_id != new_stmt.event_id {
+                return Err(CoreError::InvalidArg("parent statement belongs to another event".into()));
+            }
+        }
+        st.statements_seq += 1;
+        let id = st.statements_seq;
+        let event_uid = st.event_for(new_stmt.event_id).map(|e| e.uid.clone()).unwrap_or_default();
+        st.statements.insert(id, Statement {
+            id,
+            uid: crate::models::new_uid(),
+            event_id: new_stmt.event_id,
+            event_uid,
+            text: new_stmt.text,
+            context: new_stmt.context,
+            truth_score: new_stmt.truth_score,
+            created_at: now,
+            updated_at: now,
+            signature: None,
+            public_key: None,
+            parent_uid: new_stmt.parent_uid,
+            relation: new_stmt.relation,
+        });
+        Ok(id)
+    }
+
+    fn get_statement(&self, id: i64) -> Result<Option<Statement>, CoreError> {
+        Ok(self.read().statements.get(&id).cloned())
+    }
+
+    fn get_statement_by_uid(&self, uid: &str) -> Result<Option<Statement>, CoreError> {
+        Ok(self.read().statements.values().find(|s| s.uid == uid).cloned())
+    }
+
+    fn put_statement(&self, s: &Statement) -> Result<i64, CoreError> {
+        let mut st = self.write();
+        let event = if s.event_uid.is_empty() {
+            st.event_for(s.event_id)
+        } else {
+            Some(st.event_by_uid(&s.event_uid).ok_or_else(|| CoreError::NotFound(format!("event {}", s.event_uid)))?)
+        };
+        let (event_id, event_uid) = event.map(|e| (e.id, e.uid.clone())).unwrap_or((s.event_id, String::new()));
+        let uid = if s.uid.is_empty() { s.legacy_uid(&event_uid) } else { s.uid.clone() };
+        let id = match st.statements.values().find(|x| x.uid == uid) {
+            Some(x) => x.id,
+            None => {
+                st.statements_seq += 1;
+                st.statements_seq
+            }
+        };
+        st.statements.insert(id, Statement { id, uid, event_id, event_uid, ..s.clone() });
+        Ok(id)
+    }
+
+    fn update_statement_score(&self, id: i64, truth_score: f32) -> Result<(), CoreError> {
+        if let Some(s) = self.write().statements.get_mut(&id) {
+            s.truth_score = Some(truth_score);
+            s.updated_at = Utc::now().timestamp();
+        }
+        Ok(())
+    }
+
+    fn statements_for_event(&self, event_id: i64) -> Result<Vec<Statement>, CoreError> {
+        let mut out: Vec<Statement> = self.read().statements.values().filter(|s| s.event_id == event_id).cloned().collect();
+        out.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+        Ok(out)
+    }
+
+    fn query_statements(&self, q: &RecordQuery) -> Result<Page<Statement>, CoreError> {
+        let st = self.read();
+        query_page(st.statements.values(), q, true, |s| {
+            let ev = st.event_for(s.event_id);
+            RowAttrs {
+                time: s.created_at,
+                id: RowId::Int(s.id),
+                author: s.public_key.as_deref(),
+                context_id: ev.map(|e| e.context_id),
+                detected: ev.and_then(|e| e.detected),
+                score: s.truth_score.map(f64::from),
+            }
+        })
+    }
+
+    fn add_graded_impact(&self, event_id: i64, type_id: i64, magnitude: f64, confidence: Option<f64>, notes: Option<String>) -> Result<String, CoreError> {
+        Impact::check_grade(magnitude, confidence)?;
+        let id = uuid::Uuid::new_v4().to_string();
+        let mut st = self.write();
+        let event_uid = st.event_for(event_id).ok_or_else(|| CoreError::NotFound(format!("event {}", event_id)))?.uid.clone();
+        st.impacts.insert(id.clone(), Impact {
+            id: id.clone(),
+            event_id: event_uid,
+            type_id,
+            value: magnitude >= 0.0,
+            magnitude: Some(magnitude),
+            confidence,
+            notes,
+            created_at: Utc::now().timestamp(),
+            signature: None,
+            public_key: None,
+        });
+        Ok(id)
+    }
+
+    fn insert_impact(&self, im: &Impact) -> Result<bool, CoreError> {
+        let mut st = self.write();
+        if st.impacts.contains_key(&im.id) {
+            return Ok(false);
+        }
+        let magnitude = im.grade();
+        Impact::check_grade(magnitude, im.confidence)?;
+        st.impacts.insert(im.id.clone(), Impact { value: magnitude >= 0.0, magnitude: Some(magnitude), ..im.clone() });
+        Ok(true)
+    }
+
+    fn get_impact(&self, id: &str) -> Result<Option<Impact>, CoreError> {
+        Ok(self.read().impacts.get(id).cloned())
+    }
+
+    fn query_impacts(&self, q: &RecordQuery) -> Result<Page<Impact>, CoreError> {
+        let st = self.read();
+        query_page(st.impacts.values(), q, false, |i| {
+            let ev = st.event_by_uid(&i.event_id);
+            RowAttrs {
+                time: i.created_at,
+                id: RowId::Text(i.id.clone()),
+                author: i.public_key.as_deref(),
+                context_id: ev.map(|e| e.context_id),
+                detected: ev.and_then(|e| e.detected),
+                score: ev.and_then(|e| e.collective_score),
+            }
+        })
+    }
+
+    fn tombstones_for(&self, table: &str, record_id: &str) -> Result<Vec<Tombstone>, CoreError> {
+        let mut out: Vec<Tombstone> = self
+            .read()
+            .tombstones
+            .iter()
+            .filter(|t| t.table_name == table && t.record_id == record_id)
+            .cloned()
+            .collect();
+        out.sort_by(|a, b| (a.deleted_at, &a.id).cmp(&(b.deleted_at, &b.id)));
+        Ok(out)
+    }
+
+    fn put_tombstone(&self, t: &Tombstone) -> Result<bool, CoreError> {
+        let mut st = self.write();
+        if st.tombstones.iter().any(|x| x.id == t.id) {
+            return Ok(false);
+        }
+        st.tombstones.push(t.clone());
+        Ok(true)
+    }
+
+    fn delete_record(&self, table: &str, record_id: &str) -> Result<usize, CoreError> {
+        let mut st = self.write();
+        let n = match table {
+            "truth_events" => {
+                let Some(id) = st.event_by_uid(record_id).map(|e| e.id) else { return Ok(0) };
+                let before = st.statements.len() + st.impacts.len();
+                st.statements.retain(|_, s| s.event_id != id);
+                st.impacts.retain(|_, i| i.event_id != record_id);
+                let children = before - st.statements.len() - st.impacts.len();
+                children + usize::from(st.events.remove(&id).is_some())
+            }
+            "statements" => {
+                let before = st.statements.len();
+                st.statements.retain(|_, s| s.uid != record_id);
+                before - st.statements.len()
+            }
+            "impact" => usize::from(st.impacts.remove(record_id).is_some()),
+            other => return Err(CoreError::InvalidArg(format!("records of {} cannot be deleted", other))),
+        };
+        Ok(n)
+    }
+
+    fn is_admin(&self, pubkey: &str) -> Result<bool, CoreError> {
+        Ok(self.read().admins.contains(pubkey))
+    }
+
+    fn node_ratings(&self) -> Result<Vec<NodeRating>, CoreError> {
+        let mut out: Vec<NodeRating> = self.read().node_ratings.values().cloned().collect();
+        out.sort_by(|a, b| b.trust_score.total_cmp(&a.trust_score));
+        Ok(out)
+    }
+
+    fn put_node_rating(&self, r: &NodeRating) -> Result<(), CoreError> {
+        self.write().node_ratings.insert(r.node_id.clone(), r.clone());
+        Ok(())
+    }
+
+    fn group_ratings(&self) -> Result<Vec<GroupRating>, CoreError> {
+        Ok(self.read().group_ratings.values().cloned().collect())
+    }
+
+    fn put_group_rating(&self, g: &GroupRating) -> Result<(), CoreError> {
+        self.write().group_ratings.insert(g.group_id.clone(), g.clone());
+        Ok(())
+    }
+
+    fn recalc_progress_metrics(&self, ts: i64) -> Result<i64, CoreError> {
+        let mut st = self.write();
+        let total_events = st.events.len() as i64;
+        let positive: f64 = st.impacts.values().map(|i| i.weighted().max(0.0)).sum();
+        let negative: f64 = st.impacts.values().map(|i| (-i.weighted()).max(0.0)).sum();
+        let trend = (positive - negative) / (total_events.max(1) as f64);
+        let id = st.progress.last().map(|m| m.id).unwrap_or(0) + 1;
+        // Для MVP group = общие значения (как в storage::recalc_progress_metrics)
+        st.progress.push(ProgressMetrics {
+            id,
+            timestamp: ts,
+            total_events,
+            total_events_group: total_events,
+            total_positive_impact: positive,
+            total_positive_impact_group: positive,
+            total_negative_impact: negative,
+            total_negative_impact_group: negative,
+            trend,
+            trend_group: trend,
+        });
+        Ok(id)
+    }
+
+    fn progress_metrics(&self) -> Result<Vec<ProgressMetrics>, CoreError> {
+        Ok(self.read().progress.clone())
+    }
+
+    fn node_metrics(&self, pubkey: &str) -> Result<Option<NodeMetrics>, CoreError> {
+        Ok(self.read().node_metrics.get(pubkey).cloned())
+    }
+
+    fn all_node_metrics(&self) -> Result<Vec<NodeMetrics>, CoreError> {
+        let mut out: Vec<NodeMetrics> = self.read().node_metrics.values().cloned().collect();
+        out.sort_by_key(|m| std::cmp::Reverse(m.last_seen));
+        Ok(out)
+    }
+
+    fn put_node_metrics(&self, m: &NodeMetrics) -> Result<(), CoreError> {
+        self.write().node_metrics.insert(m.pubkey.clone(), m.clone());
+        Ok(())
+    }
+
+    fn ensure_participant(&self, public_key: &str) -> Result<uuid::Uuid, CoreError> {
+        Ok(*self.write().participants.entry(public_key.to_string()).or_insert_with(uuid::Uuid::new_v4))
+    }
+
+    fn insert_judgment(&self, j: &Judgment) -> Result<(), CoreError> {
+        let mut st = self.write();
+        if st.judgments.iter().any(|x| x.id == j.id || (x.participant_id == j.participant_id && x.event_id == j.event_id)) {
+            return Err(CoreError::InvalidArg("judgment already submitted".into()));
+        }
+        st.judgments.push(Judgment { submitted_at: whole_seconds(j.submitted_at), ..j.clone() });
+        Ok(())
+    }
+
+    fn judgments_by_event(&self, event_id: &uuid::Uuid) -> Result<Vec<Judgment>, CoreError> {
+        let mut out: Vec<Judgment> = self.read().judgments.iter().filter(|j| j.event_id == *event_id).cloned().collect();
+        out.sort_by_key(|j| j.submitted_at);
+        Ok(out)
+    }
+
+    fn consensus(&self, event_id: &uuid::Uuid) -> Result<Option<Consensus>, CoreError> {
+        Ok(self.read().consensus.iter().find(|c| c.event_id == *event_id).cloned())
+    }
+
+    fn put_consensus(&self, c: &Consensus) -> Result<(), CoreError> {
+        let mut st = self.write();
+        let c = Consensus { calculated_at: whole_seconds(c.calculated_at), ..c.clone() };
+        match st.consensus.iter_mut().find(|x| x.id == c.id) {
+            // event_id при обновлении не меняется, как и в ON CONFLICT(id) DO UPDATE
+            Some(existing) => *existing = Consensus { event_id: existing.event_id, ..c },
+            None => st.consensus.push(c),
+        }
+        Ok(())
+    }
+
+    fn log_sync(&self, op: &str, table_name: &str, record_id: &str, signature: Option<String>, public_key: Option<String>) -> Result<i64, CoreError> {
+        let mut st = self.write();
+        st.sync_log.push(SyncLogRecord {
+            op: op.to_string(),
+            table_name: table_name.to_string(),
+            record_id: record_id.to_string(),
+            signature,
+            public_key,
+            created_at: Utc::now().timestamp(),
+        });
+        Ok(st.sync_log.len() as i64)
+    }
+
+    fn log_sync_event(&self, peer_url: &str, mode: &str, status: &str, details: &str) -> Result<i64, CoreError> {
+        let mut st = self.write();
+        st.sync_logs_seq += 1;
+        let id = st.sync_logs_seq;
+        st.sync_logs.push(SyncLog {
+            id,
+            timestamp: Utc::now().timestamp(),
+            peer_url: peer_url.to_string(),
+            mode: mode.to_string(),
+            status: status.to_string(),
+            details: details.to_string(),
+        });
+        Ok(id)
+    }
+
+    fn recent_sync_logs(&self, limit: usize) -> Result<Vec<SyncLog>, CoreError> {
+        Ok(self.read().sync_logs.iter().rev().take(limit).cloned().collect())
+    }
+
+    fn clear_sync_logs(&self) -> Result<(), CoreError> {
+        self.write().sync_logs.clear();
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+use crate::at_rest::{from_hex, to_hex};
+use crate::models::Tombstone;
+use crate::store::Store;
+use crate::CoreError;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+/// Итог применения отметки удаления
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Applied {
+    /// Запись удалена вместе с зависимыми (число строк)
+    Deleted(usize),
+    /// Записи локально нет: отметка сохранена и не даст ей появиться при синхронизации
+    Pending,
+    /// Отметка уже известна
+    Known,
+    /// Подписавший не автор записи и не администратор — отметка отклонена
+    Forbidden,
+}
+
+/// Подписать отметку ключом узла; author должен быть его публичным ключом
+pub fn sign(t: &mut Tombstone, key: &SigningKey) {
+    t.signature = to_hex(&key.sign(t.signing_message().as_bytes()).to_bytes());
+}
+
+/// Проверить таблицу, id (выводится из содержимого) и подпись author
+pub fn verify(t: &Tombstone) -> Result<(), CoreError> {
+    if !Tombstone::TABLES.contains(&t.table_name.as_str()) {
+        return Err(CoreError::InvalidArg(format!("records of {} cannot be deleted", t.table_name)));
+    }
+    if Tombstone::new(&t.table_name, &t.record_id, &t.author, None, t.deleted_at).id != t.id {
+        return Err(CoreError::InvalidArg(format!("tombstone {}: id does not match content", t.id)));
+    }
+    let pk: [u8; 32] = from_hex(&t.author)?
+        .try_into()
+        .map_err(|_| CoreError::InvalidArg(format!("tombstone {}: bad author key", t.id)))?;
+    let key = VerifyingKey::from_bytes(&pk).map_err(|e| CoreError::InvalidArg(e.to_string()))?;
+    let sig = Signature::from_slice(&from_hex(&t.signature)?).map_err(|e| CoreError::InvalidArg(e.to_string()))?;
+    key.verify(t.signing_message().as_bytes(), &sig)
+        .map_err(|_| CoreError::InvalidArg(format!("tombstone {}: signature is invalid", t.id)))
+}
+
+/// Применить отметку: проверить подпись, удалить запись с зависимыми (если подписавший вправе)
+/// и сохранить отметку для распространения пирам
+pub fn apply<S: Store + ?Sized>(store: &S, t: &Tombstone) -> Result<Applied, CoreError> {
+    verify(t)?;
+    match record_author(store, &t.table_name, &t.record_id)? {
+        Some(author) => {
+            if !authorized(store, t, author.as_deref())? {
+                return Ok(Applied::Forbidden);
+            }
+            store.put_tombstone(t)?;
+            let n = store.delete_record(&t.table_name, &t.record_id)?;
+            store.log_sync("delete", &t.table_name, &t.record_id, Some(t.signature.clone()), Some(t.author.clone()))?;
+            Ok(Applied::Deleted(n))
+        }
+        None if store.put_tombstone(t)? => Ok(Applied::Pending),
+        None => Ok(Applied::Known),
+    }
+}
+
+/// Удалена ли запись автора author отметкой, которую подписавший вправе был поставить.
+/// Проверяется для каждой записи, пришедшей от пира, до её вставки.
+pub fn suppresses<S: Store + ?Sized>(store: &S, table: &str, record_id: &str, author: Option<&str>) -> Result<bool, CoreError> {
+    for t in store.tombstones_for(table, record_id)? {
+        if authorized(store, &t, author)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Удалено ли событие (по uid), к которому относится утверждение или влияние: его нет локально, но есть отметка
+pub fn parent_deleted<S: Store + ?Sized>(store: &S, event_uid: &str) -> Result<bool, CoreError> {
+    Ok(store.get_event_by_uid(event_uid)?.is_none() && !store.tombstones_for("truth_events", event_uid)?.is_empty())
+}
+
+/// Удалить запись может её автор или администратор; записи без автора — только администратор
+fn authorized<S: Store + ?Sized>(store: &S, t: &Tombstone, record_author: Option<&str>) -> Result<bool, CoreError> {
+    Ok(record_author == Some(t.author.as_str()) || store.is_admin(&t.author)?)
+}
+
+/// Some(public_key автора), если запись есть локально; события и утверждения ищутся по uid
+fn record_author<S: Store + ?Sized>(store: &S, table: &str, record_id: &str) -> Result<Option<Option<String>>, CoreError> {
+    Ok(match table {
+        "truth_events" => store.get_event_by_uid(record_id)?.map(|e| e.public_key),
+        "statements" => store.get_statement_by_uid(record_id)?.map(|s| s.public_key),
+        "impact" => store.get_impact(record_id)?.map(|i| i.public_key),
+        other => return Err(CoreError::InvalidArg(format!("records of {} cannot be deleted", other))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Impact, NewStatement, TruthEvent};
+    use crate::store::MemoryStore;
+
+    fn key(seed: u8) -> (SigningKey, String) {
+        let k = SigningKey::from_bytes(&[seed; 32]);
+        let pk = to_hex(k.verifying_key().as_bytes());
+        (k, pk)
+    }
+
+    fn tombstone(table: &str, record_id: &str, signer: &(SigningKey, String), at: i64) -> Tombstone {
+        let mut t = Tombstone::new(table, record_id, &signer.1, Some("spam".into()), at);
+        sign(&mut t, &signer.0);
+        t
+    }
+
+    fn event(id: i64, author: &str) -> TruthEvent {
+        TruthEvent {
+            id,
+            uid: format!("ev-{}", id),
+            description: format!("event {}", id),
+            context_id: 1,
+            vector: true,
+            detected: None,
+            corrected: false,
+            timestamp_start: 100,
+            timestamp_end: None,
+            code: 1,
+            signature: None,
+            public_key: Some(author.into()),
+            collective_score: None,
+        }
+    }
+
+    #[test]
+    fn author_or_admin_deletes_with_cascade() {
+        let (alice, bob, admin) = (key(1), key(2), key(3));
+        let store = MemoryStore::new();
+        store.grant_admin(&admin.1);
+        let first = store.put_event(&event(1, &alice.1)).unwrap();
+        store.put_event(&event(2, &alice.1)).unwrap();
+        store.add_statement(NewStatement { event_id: first, text: "claim".into(), context: None, truth_score: None, parent_uid: None, relation: None }).unwrap();
+        store.insert_impact(&Impact {
+            id: "imp".into(),
+            event_id: "ev-1".into(),
+            type_id: 1,
+            value: true,
+            magnitude: None,
+            confidence: None,
+            notes: None,
+            created_at: 10,
+            signature: None,
+            public_key: Some(bob.1.clone()),
+        }).unwrap();
+
+        // Чужая отметка отклоняется и не сохраняется
+        assert_eq!(apply(&store, &tombstone("truth_events", "ev-1", &bob, 200)).unwrap(), Applied::Forbidden);
+        assert!(store.tombstones_for("truth_events", "ev-1").unwrap().is_empty());
+
+        // Автор удаляет событие вместе с утверждением и влиянием
+        let own = tombstone("truth_events", "ev-1", &alice, 200);
+        assert_eq!(apply(&store, &own).unwrap(), Applied::Deleted(3));
+        assert!(store.get_impact("imp").unwrap().is_none());
+        assert!(store.statements_for_event(first).unwrap().is_empty());
+        assert_eq!(apply(&store, &own).unwrap(), Applied::Known);
+
+        // Администратор удаляет чужое
+        assert_eq!(apply(&store, &tombstone("truth_events", "ev-2", &admin, 200)).unwrap(), Applied::Deleted(1));
+
+        // Повторная запись от пира подавляется, чужая — нет
+        assert!(suppresses(&store, "truth_events", "ev-1", Some(&alice.1)).unwrap());
+        assert!(!suppresses(&store, "truth_events", "ev-1", Some(&bob.1)).unwrap());
+        assert!(suppresses(&store, "truth_events", "ev-2", None).unwrap());
+        assert!(parent_deleted(&store, "ev-1").unwrap());
+        assert!(!parent_deleted(&store, "ev-3").unwrap());
+
+        // Отметка для записи, которой ещё нет
+        assert_eq!(apply(&store, &tombstone("statements", "42", &bob, 300)).unwrap(), Applied::Pending);
+        assert!(suppresses(&store, "statements", "42", Some(&bob.1)).unwrap());
+    }
+
+    #[test]
+    fn forged_tombstones_are_rejected() {
+        let (alice, bob) = (key(1), key(2));
+        let mut t = tombstone("truth_events", "1", &alice, 200);
+        assert!(verify(&t).is_ok());
+
+        // Подпись другим ключом
+        t.signature = tombstone("truth_events", "1", &bob, 200).signature;
+        assert!(verify(&t).is_err());
+
+        // Подмена записи при сохранённой подписи
+        let mut moved = tombstone("truth_events", "1", &alice, 200);
+        moved.record_id = "2".into();
+        assert!(verify(&moved).is_err());
+
+        let mut other_table = Tombstone::new("users", "x", &alice.1, None, 1);
+        sign(&mut other_table, &alice.0);
+        assert!(verify(&other_table).is_err());
+    }
+}
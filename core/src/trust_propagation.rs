@@ -1,17 +1,204 @@
 use crate::{CoreError, NodeRating};
 use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
 
 // Константы распространения доверия и качества/приоритета
 const BLEND_LOCAL_WEIGHT: f32 = 0.8;
 const BLEND_REMOTE_WEIGHT: f32 = 0.2;
 const QUALITY_EMA_ALPHA: f32 = 0.3; // сглаживание экспоненциальным средним
 const PRIORITY_EMA_ALPHA: f32 = 0.3; // EMA для propagation_priority
+/// Окно (число последних точек trust_history) для оценки стабильности доверия по умолчанию
+pub const TRUST_STABILITY_WINDOW: usize = 20;
+/// Порог доверия отправителя: рейтинги от узлов с trust_score ниже игнорируются
+pub const REMOTE_TRUST_FLOOR: f32 = 0.0;
+/// Бонус за переиспользование событий автора в формуле пересчёта trust_score
+pub const REUSE_BONUS: f32 = 0.2;
+/// Окно учёта активности узла (суток)
+pub const ACTIVITY_WINDOW_DAYS: f32 = 7.0;
+/// Период полураспада веса действия внутри окна активности (суток)
+pub const ACTIVITY_HALF_LIFE_DAYS: f32 = 2.0;
+/// Взвешенное число действий, при котором активность равна 0.5
+pub const ACTIVITY_HALF_SATURATION: f32 = 5.0;
+/// Доля активности в propagation_priority
+pub const PRIORITY_ACTIVITY_WEIGHT: f32 = 0.2;
+
+/// Параметры распространения доверия и формул пересчёта.
+/// Значения по умолчанию совпадают с константами модуля; узел работает на них,
+/// альтернативные наборы используются симулятором (`crate::simulator`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TrustParams {
+    /// Базовый вес удалённого значения при смешивании (локальный = 1 - вес)
+    pub blend_remote_weight: f32,
+    pub quality_ema_alpha: f32,
+    pub priority_ema_alpha: f32,
+    /// Веса quality_index: relay_success_rate, conflict_free_ratio, trust_score_stability
+    pub quality_relay_weight: f32,
+    pub quality_conflict_weight: f32,
+    pub quality_stability_weight: f32,
+    /// Веса propagation_priority: trust_norm, quality_index, relay_success_rate
+    pub priority_trust_weight: f32,
+    pub priority_quality_weight: f32,
+    pub priority_relay_weight: f32,
+    /// Доля trust в репутации отправителя (остальное — quality_index)
+    pub sender_trust_weight: f32,
+    pub remote_trust_floor: f32,
+    pub reuse_bonus: f32,
+    /// Окно и полураспад активности (суток), доля активности в propagation_priority
+    pub activity_window_days: f32,
+    pub activity_half_life_days: f32,
+    pub priority_activity_weight: f32,
+    /// Окно (число последних точек trust_history) для стабильности доверия в quality_index
+    pub stability_window: usize,
+}
+
+impl Default for TrustParams {
+    fn default() -> Self {
+        Self {
+            blend_remote_weight: BLEND_REMOTE_WEIGHT,
+            quality_ema_alpha: QUALITY_EMA_ALPHA,
+            priority_ema_alpha: PRIORITY_EMA_ALPHA,
+            quality_relay_weight: 0.5,
+            quality_conflict_weight: 0.3,
+            quality_stability_weight: 0.2,
+            priority_trust_weight: 0.4,
+            priority_quality_weight: 0.3,
+            priority_relay_weight: 0.3,
+            sender_trust_weight: 0.7,
+            remote_trust_floor: REMOTE_TRUST_FLOOR,
+            reuse_bonus: REUSE_BONUS,
+            activity_window_days: ACTIVITY_WINDOW_DAYS,
+            activity_half_life_days: ACTIVITY_HALF_LIFE_DAYS,
+            priority_activity_weight: PRIORITY_ACTIVITY_WEIGHT,
+            stability_window: TRUST_STABILITY_WINDOW,
+        }
+    }
+}
+
+impl TrustParams {
+    /// Проверить диапазоны: веса и коэффициенты EMA в [0, 1], порог доверия в [-1, 1]
+    pub fn validate(&self) -> Result<(), CoreError> {
+        let unit = [
+            ("blend_remote_weight", self.blend_remote_weight),
+            ("quality_ema_alpha", self.quality_ema_alpha),
+            ("priority_ema_alpha", self.priority_ema_alpha),
+            ("quality_relay_weight", self.quality_relay_weight),
+            ("quality_conflict_weight", self.quality_conflict_weight),
+            ("quality_stability_weight", self.quality_stability_weight),
+            ("priority_trust_weight", self.priority_trust_weight),
+            ("priority_quality_weight", self.priority_quality_weight),
+            ("priority_relay_weight", self.priority_relay_weight),
+            ("sender_trust_weight", self.sender_trust_weight),
+            ("reuse_bonus", self.reuse_bonus),
+            ("priority_activity_weight", self.priority_activity_weight),
+        ];
+        for (name, v) in unit {
+            if !(0.0..=1.0).contains(&v) {
+                return Err(CoreError::InvalidArg(format!("{} must be within [0, 1], got {}", name, v)));
+            }
+        }
+        if !(-1.0..=1.0).contains(&self.remote_trust_floor) {
+            return Err(CoreError::InvalidArg(format!(
+                "remote_trust_floor must be within [-1, 1], got {}",
+                self.remote_trust_floor
+            )));
+        }
+        if self.activity_window_days <= 0.0 || self.activity_half_life_days <= 0.0 {
+            return Err(CoreError::InvalidArg(
+                "activity_window_days and activity_half_life_days must be positive".to_string(),
+            ));
+        }
+        if self.stability_window == 0 {
+            return Err(CoreError::InvalidArg("stability_window must be positive".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Активность узла по отметкам времени его действий (события, оценки, суждения, синхронизации).
+/// Учитываются действия за последние activity_window_days; вес действия убывает вдвое каждые
+/// activity_half_life_days. Итог насыщается: a = s / (s + ACTIVITY_HALF_SATURATION), 0..1.
+pub fn compute_windowed_activity(params: &TrustParams, timestamps: &[i64], now_ts: i64) -> f32 {
+    let window = (params.activity_window_days * 86_400.0) as i64;
+    let half_life = params.activity_half_life_days * 86_400.0;
+    let score: f32 = timestamps
+        .iter()
+        .filter(|t| now_ts - **t < window)
+        .map(|t| 0.5_f32.powf((now_ts - t).max(0) as f32 / half_life))
+        .sum();
+    score / (score + ACTIVITY_HALF_SATURATION)
+}
+
+/// Локальный trust_score по весам узла (формула recalc_ratings):
+/// (true - false)/(true + false) + reuse_bonus * reused/(true + false), с обрезкой в [-1, 1].
+/// Веса — счётчики или суммы оценок «степень × уверенность»
+pub fn compute_local_trust(params: &TrustParams, events_true: f32, events_false: f32, reused: f32) -> f32 {
+    let total = events_true + events_false;
+    if total <= 0.0 {
+        return 0.0;
+    }
+    let base = (events_true - events_false) / total;
+    (base + params.reuse_bonus * reused / total).clamp(-1.0, 1.0)
+}
 
 /// Смешивает локальный и удалённый скор по формуле:
 /// new = local*0.8 + remote*0.2, с обрезкой в [-1, 1]
 pub fn blend_trust(local_score: f32, remote_score: f32) -> f32 {
-    let blended = local_score * BLEND_LOCAL_WEIGHT + remote_score * BLEND_REMOTE_WEIGHT;
-    blended.clamp(-1.0, 1.0)
+    blend_trust_weighted(local_score, remote_score, BLEND_REMOTE_WEIGHT)
+}
+
+/// Смешивание trust с явным весом удалённого значения:
+/// new = local*(1-w) + remote*w, с обрезкой в [-1, 1]
+pub fn blend_trust_weighted(local_score: f32, remote_score: f32, remote_weight: f32) -> f32 {
+    let w = remote_weight.clamp(0.0, 1.0);
+    (local_score * (1.0 - w) + remote_score * w).clamp(-1.0, 1.0)
+}
+
+/// Вес удалённых оценок с учётом репутации отправителя.
+/// w = 0.2 * (0.7*trust_norm + 0.3*quality_index), где trust_norm = (trust+1)/2.
+/// Узел с максимальным доверием и качеством получает прежний вес 0.2.
+/// Если trust отправителя ниже REMOTE_TRUST_FLOOR — None (его оценки игнорируются).
+pub fn sender_remote_weight(sender_trust: f32, sender_quality: f32) -> Option<f32> {
+    sender_remote_weight_with(&TrustParams::default(), sender_trust, sender_quality)
+}
+
+/// sender_remote_weight с явными параметрами
+pub fn sender_remote_weight_with(params: &TrustParams, sender_trust: f32, sender_quality: f32) -> Option<f32> {
+    if sender_trust < params.remote_trust_floor {
+        return None;
+    }
+    let trust_norm = ((sender_trust + 1.0) / 2.0).clamp(0.0, 1.0);
+    let reputation = params.sender_trust_weight * trust_norm
+        + (1.0 - params.sender_trust_weight) * sender_quality.clamp(0.0, 1.0);
+    Some(params.blend_remote_weight * reputation)
+}
+
+/// Вес удалённых оценок для отправителя по локальным node_ratings/node_metrics.
+/// Без указания отправителя (локальный импорт, тесты) используется базовый вес 0.2.
+/// Неизвестный отправитель рассматривается как узел с trust 0.0 и quality 0.0.
+pub fn load_sender_weight(conn: &Connection, sender: Option<&str>) -> Result<Option<f32>, CoreError> {
+    let sender = match sender {
+        Some(s) => s,
+        None => return Ok(Some(BLEND_REMOTE_WEIGHT)),
+    };
+    let trust: Option<f64> = conn
+        .query_row(
+            "SELECT trust_score FROM node_ratings WHERE node_id = ?1",
+            [sender],
+            |r| r.get(0),
+        )
+        .optional()?;
+    let quality: Option<f64> = conn
+        .query_row(
+            "SELECT quality_index FROM node_metrics WHERE pubkey = ?1",
+            [sender],
+            |r| r.get(0),
+        )
+        .optional()?;
+    Ok(sender_remote_weight(
+        trust.unwrap_or(0.0) as f32,
+        quality.unwrap_or(0.0) as f32,
+    ))
 }
 
 #[cfg(test)]
@@ -19,18 +206,76 @@ mod tests {
 
     #[test]
     fn propagation_priority_blend_from_trust_and_activity() {
-        // Прокси-расчёт: trust 0.5 (нормализованный 0.75), activity 0.0 → priority = 0.75*0.8 + 0.0*0.2 = 0.6
-        // Эта проверка валидирует веса смешивания (0.8/0.2) на уровне формулы.
-        let trust = 0.5f32;
-        let trust_norm = ((trust + 1.0) / 2.0).clamp(0.0, 1.0);
-        let recent_activity = 0.0f32;
-        let priority = trust_norm * 0.8 + recent_activity * 0.2;
-        assert!((priority - 0.6).abs() < 1e-6);
-
+        use super::{compute_propagation_priority, compute_windowed_activity, TrustParams};
+        // trust 0.5 (нормализованный 0.75), quality/relay 0: base = 0.4*0.75 = 0.3, priority = 0.8*0.3 + 0.2*activity
+        let idle = compute_propagation_priority(0.5, 0.0, 0.0, 0.0, None);
+        assert!((idle - 0.24).abs() < 1e-6);
         // При высокой активности priority растёт на 20% вклада
-        let recent_activity = 1.0f32;
-        let priority2 = trust_norm * 0.8 + recent_activity * 0.2;
-        assert!(priority2 > priority);
+        let busy = compute_propagation_priority(0.5, 0.0, 0.0, 1.0, None);
+        assert!((busy - 0.44).abs() < 1e-6);
+
+        // Активность: только действия внутри окна, свежие весят больше давних
+        let p = TrustParams::default();
+        let now = 1_700_000_000;
+        let day = 86_400;
+        assert_eq!(compute_windowed_activity(&p, &[], now), 0.0);
+        let fresh = compute_windowed_activity(&p, &[now - 60; 5], now);
+        let older = compute_windowed_activity(&p, &[now - 4 * day; 5], now);
+        let expired = compute_windowed_activity(&p, &[now - 8 * day; 50], now);
+        assert!(fresh > older && older > 0.0);
+        assert!((fresh - 0.5).abs() < 1e-3);
+        assert_eq!(expired, 0.0);
+        // Узкое окно отсекает давние действия
+        let narrow = TrustParams { activity_window_days: 3.0, ..p };
+        assert_eq!(compute_windowed_activity(&narrow, &[now - 4 * day; 5], now), 0.0);
+    }
+
+    #[test]
+    fn trust_stability_from_variance() {
+        use super::compute_trust_stability;
+        // Недостаточно истории — считаем стабильным
+        assert!((compute_trust_stability(&[]) - 1.0).abs() < 1e-6);
+        assert!((compute_trust_stability(&[0.3]) - 1.0).abs() < 1e-6);
+        // Постоянный ряд — максимальная стабильность
+        assert!((compute_trust_stability(&[0.5, 0.5, 0.5]) - 1.0).abs() < 1e-6);
+        // Колебания между -1 и 1: σ = 1 → стабильность 0
+        assert!(compute_trust_stability(&[-1.0, 1.0, -1.0, 1.0]).abs() < 1e-6);
+        // Небольшой дрейф стабильнее резких скачков
+        let calm = compute_trust_stability(&[0.40, 0.42, 0.41, 0.43]);
+        let jumpy = compute_trust_stability(&[0.40, -0.20, 0.70, 0.10]);
+        assert!(calm > jumpy);
+    }
+
+    #[test]
+    fn sender_weight_scales_with_reputation() {
+        use super::{blend_trust, blend_trust_weighted, sender_remote_weight, BLEND_REMOTE_WEIGHT};
+        // Ниже порога — игнорируем
+        assert!(sender_remote_weight(-0.5, 1.0).is_none());
+        // Максимальная репутация — прежний вес 0.2
+        let w_max = sender_remote_weight(1.0, 1.0).unwrap();
+        assert!((w_max - BLEND_REMOTE_WEIGHT).abs() < 1e-6);
+        assert!((blend_trust_weighted(0.5, 0.4, w_max) - blend_trust(0.5, 0.4)).abs() < 1e-6);
+        // Нейтральный отправитель без качества: 0.2 * 0.7 * 0.5 = 0.07
+        let w_neutral = sender_remote_weight(0.0, 0.0).unwrap();
+        assert!((w_neutral - 0.07).abs() < 1e-6);
+        assert!(w_neutral < w_max);
+    }
+
+    #[test]
+    fn default_params_match_constant_formulas() {
+        use super::*;
+        let p = TrustParams::default();
+        assert!(p.validate().is_ok());
+        assert_eq!(compute_quality_index_with(&p, 0.9, 0.5, 0.7, Some(0.4)), compute_quality_index(0.9, 0.5, 0.7, Some(0.4)));
+        assert_eq!(compute_propagation_priority_with(&p, 0.3, 0.6, 0.8, 0.5, None), compute_propagation_priority(0.3, 0.6, 0.8, 0.5, None));
+        // 2 истинных, 1 ложное, 2 переиспользования: 1/3 + 0.2*2/3
+        assert!((compute_local_trust(&p, 2.0, 1.0, 2.0) - 0.466_666_7).abs() < 1e-5);
+
+        // Частичный JSON дополняется значениями по умолчанию
+        let alt: TrustParams = serde_json::from_str(r#"{"blend_remote_weight": 0.5}"#).unwrap();
+        assert_eq!(alt.quality_ema_alpha, p.quality_ema_alpha);
+        assert!(sender_remote_weight_with(&alt, 1.0, 1.0).unwrap() > sender_remote_weight(1.0, 1.0).unwrap());
+        assert!(TrustParams { quality_ema_alpha: 1.5, ..p }.validate().is_err());
     }
 }
 
@@ -40,6 +285,12 @@ pub fn blend_quality(local_quality: f32, remote_quality: f32) -> f32 {
     blended.clamp(0.0, 1.0)
 }
 
+/// Смешивание качества с явным весом удалённого значения (0..1)
+pub fn blend_quality_weighted(local_quality: f32, remote_quality: f32, remote_weight: f32) -> f32 {
+    let w = remote_weight.clamp(0.0, 1.0);
+    (local_quality * (1.0 - w) + remote_quality * w).clamp(0.0, 1.0)
+}
+
 /// Рассчитать адаптивный quality_index по взвешенной формуле с EMA-сглаживанием
 /// q_raw = 0.5*relay_success_rate + 0.3*conflict_free_ratio + 0.2*trust_score_stability
 /// q = alpha*q_raw + (1-alpha)*prev, alpha = QUALITY_EMA_ALPHA
@@ -49,49 +300,106 @@ pub fn compute_quality_index(
     trust_score_stability: f32,
     prev_quality: Option<f32>,
 ) -> f32 {
-    let q_raw = (0.5 * relay_success_rate)
-        + (0.3 * conflict_free_ratio)
-        + (0.2 * trust_score_stability);
+    compute_quality_index_with(&TrustParams::default(), relay_success_rate, conflict_free_ratio, trust_score_stability, prev_quality)
+}
+
+/// compute_quality_index с явными параметрами
+pub fn compute_quality_index_with(
+    params: &TrustParams,
+    relay_success_rate: f32,
+    conflict_free_ratio: f32,
+    trust_score_stability: f32,
+    prev_quality: Option<f32>,
+) -> f32 {
+    let q_raw = (params.quality_relay_weight * relay_success_rate)
+        + (params.quality_conflict_weight * conflict_free_ratio)
+        + (params.quality_stability_weight * trust_score_stability);
     let q_raw = q_raw.clamp(0.0, 1.0);
+    let alpha = params.quality_ema_alpha;
     match prev_quality {
-        Some(prev) => (QUALITY_EMA_ALPHA * q_raw + (1.0 - QUALITY_EMA_ALPHA) * prev).clamp(0.0, 1.0),
+        Some(prev) => (alpha * q_raw + (1.0 - alpha) * prev).clamp(0.0, 1.0),
         None => q_raw,
     }
 }
 
+/// Стабильность доверия по временному ряду trust_score (-1..1):
+/// stability = 1 - σ, где σ — стандартное отклонение (корень дисперсии) ряда.
+/// Максимально возможная σ для значений в [-1, 1] равна 1, поэтому результат лежит в [0, 1].
+/// Для ряда короче двух точек история не позволяет судить о колебаниях — возвращаем 1.0.
+pub fn compute_trust_stability(scores: &[f32]) -> f32 {
+    if scores.len() < 2 {
+        return 1.0;
+    }
+    let n = scores.len() as f32;
+    let mean = scores.iter().sum::<f32>() / n;
+    let variance = scores.iter().map(|s| (s - mean) * (s - mean)).sum::<f32>() / n;
+    (1.0 - variance.sqrt()).clamp(0.0, 1.0)
+}
+
 /// Смешивание приоритета (0..1) между локальным и удалённым значениями
 pub fn blend_priority(local_priority: f32, remote_priority: f32) -> f32 {
     let blended = local_priority * BLEND_LOCAL_WEIGHT + remote_priority * BLEND_REMOTE_WEIGHT;
     blended.clamp(0.0, 1.0)
 }
 
+/// Смешивание приоритета с явным весом удалённого значения (0..1)
+pub fn blend_priority_weighted(local_priority: f32, remote_priority: f32, remote_weight: f32) -> f32 {
+    let w = remote_weight.clamp(0.0, 1.0);
+    (local_priority * (1.0 - w) + remote_priority * w).clamp(0.0, 1.0)
+}
+
 /// Рассчитать адаптивный propagation_priority по формуле с EMA-сглаживанием.
-/// p_raw = 0.4*trust_norm + 0.3*quality_index + 0.3*relay_success_rate,
+/// base = 0.4*trust_norm + 0.3*quality_index + 0.3*relay_success_rate,
 /// где trust_norm = clamp((trust_score+1)/2, 0..1)
+/// p_raw = 0.8*base + 0.2*recent_activity (активность за окно, см. compute_windowed_activity)
 /// p = alpha*p_raw + (1-alpha)*prev, alpha = PRIORITY_EMA_ALPHA
 pub fn compute_propagation_priority(
     trust_score: f32,          // -1..1
     quality_index: f32,        // 0..1
     relay_success_rate: f32,   // 0..1
+    recent_activity: f32,      // 0..1
+    prev_priority: Option<f32>,
+) -> f32 {
+    compute_propagation_priority_with(&TrustParams::default(), trust_score, quality_index, relay_success_rate, recent_activity, prev_priority)
+}
+
+/// compute_propagation_priority с явными параметрами
+pub fn compute_propagation_priority_with(
+    params: &TrustParams,
+    trust_score: f32,
+    quality_index: f32,
+    relay_success_rate: f32,
+    recent_activity: f32,
     prev_priority: Option<f32>,
 ) -> f32 {
     let trust_norm = ((trust_score + 1.0) / 2.0).clamp(0.0, 1.0);
-    let p_raw = (0.4 * trust_norm) + (0.3 * quality_index.clamp(0.0, 1.0)) + (0.3 * relay_success_rate.clamp(0.0, 1.0));
-    let p_raw = p_raw.clamp(0.0, 1.0);
+    let base = (params.priority_trust_weight * trust_norm)
+        + (params.priority_quality_weight * quality_index.clamp(0.0, 1.0))
+        + (params.priority_relay_weight * relay_success_rate.clamp(0.0, 1.0));
+    let aw = params.priority_activity_weight;
+    let p_raw = ((1.0 - aw) * base + aw * recent_activity.clamp(0.0, 1.0)).clamp(0.0, 1.0);
+    let alpha = params.priority_ema_alpha;
     match prev_priority {
-        Some(prev) => (PRIORITY_EMA_ALPHA * p_raw + (1.0 - PRIORITY_EMA_ALPHA) * prev).clamp(0.0, 1.0),
+        Some(prev) => (alpha * p_raw + (1.0 - alpha) * prev).clamp(0.0, 1.0),
         None => p_raw,
     }
 }
 
 /// Выполнить распространение доверия по входящим оценкам: обновить/вставить blended trust.
-/// Возвращает список изменений доверия (node_id, delta)
+/// Вес удалённых оценок масштабируется репутацией отправителя (`sender`), отправитель
+/// ниже REMOTE_TRUST_FLOOR игнорируется. Каждое изменение записывается в trust_history
+/// вместе с отправителем. Возвращает список изменений доверия (node_id, delta)
 pub fn propagate_from_remote(
     conn: &Connection,
     incoming_nodes: &[NodeRating],
     now_ts: i64,
+    sender: Option<&str>,
 ) -> Result<Vec<(String, f32)>, CoreError> {
     let mut diffs: Vec<(String, f32)> = Vec::new();
+    let remote_weight = match load_sender_weight(conn, sender)? {
+        Some(w) => w,
+        None => return Ok(diffs),
+    };
 
     // Подготовим SELECT и UPSERT/UPDATE
     let mut sel = conn.prepare(
@@ -129,7 +437,7 @@ pub fn propagate_from_remote(
 
         match row {
             Some((et, ef, val, re, local_trust, local_updated)) => {
-                let new_trust = blend_trust(local_trust, nr.trust_score);
+                let new_trust = blend_trust_weighted(local_trust, nr.trust_score, remote_weight);
                 let delta = new_trust - local_trust;
                 if delta.abs() > 1e-6 {
                     diffs.push((nr.node_id.clone(), delta));
@@ -149,10 +457,11 @@ pub fn propagate_from_remote(
                     new_trust as f64,
                     last_updated,
                 ])?;
+                crate::storage::record_trust_history(conn, &nr.node_id, new_trust, "merge", sender, now_ts)?;
             }
             None => {
                 // Нет локальной записи: blended от 0.0 и удалённого (без штрафов за давность)
-                let new_trust = blend_trust(0.0, nr.trust_score);
+                let new_trust = blend_trust_weighted(0.0, nr.trust_score, remote_weight);
                 let delta = new_trust; // от 0.0
                 if delta.abs() > 1e-6 {
                     diffs.push((nr.node_id.clone(), delta));
@@ -167,6 +476,7 @@ pub fn propagate_from_remote(
                     new_trust as f64,
                     last_updated,
                 ])?;
+                crate::storage::record_trust_history(conn, &nr.node_id, new_trust, "merge", sender, now_ts)?;
             }
         }
     }
@@ -1,13 +1,12 @@
-use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::Mutex;
 
 use core_lib::models::{Impact, NewTruthEvent, NewStatement, GraphData, GraphSummary, RbacUser};
 use core_lib::storage;
 use crate::p2p::encryption::CryptoIdentity;
 use crate::p2p::sync::SyncData;
 use crate::p2p::node::Node;
+use crate::db::DbPool;
 use chrono::Utc;
 use std::fmt;
 use jsonwebtoken::{encode, decode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
@@ -18,8 +17,6 @@ use core_lib::models::{PeerHistoryEntry, PeerSummary};
 use base64::{engine::general_purpose, Engine as _};
 // rusqlite::OptionalExtension is used in storage functions // for query_row(...).optional()
 
-type DbPool = Arc<Mutex<rusqlite::Connection>>;
-
 /// Параметры HTTP-сервера, необходимые для служебных эндпоинтов
 #[derive(Clone)]
 pub struct AppInfo {
@@ -88,27 +85,22 @@ async fn api_v1_info(node: web::Data<Node>, meta: web::Data<AppInfo>) -> impl Re
 async fn api_v1_stats(pool: web::Data<DbPool>) -> impl Responder {
     let pool = pool.clone();
     let result = web::block(move || {
-        let conn = pool.blocking_lock();
-        let events = core_lib::storage::load_truth_events(&conn)?.len() as i32;
-        let statements = core_lib::storage::load_statements(&conn)?.len() as i32;
-        let impacts = core_lib::storage::load_impacts(&conn)?.len() as i32;
-        let node_rs = core_lib::storage::load_node_ratings(&conn)?;
-        let group_rs = core_lib::storage::load_group_ratings(&conn)?;
-        let node_ratings = node_rs.len() as i32;
-        let group_ratings = group_rs.len() as i32;
-        let avg_trust_score: f32 = if node_rs.is_empty() {
-            0.0
-        } else {
-            let sum: f64 = node_rs.iter().map(|r| r.trust_score as f64).sum();
-            (sum / (node_rs.len() as f64)) as f32
-        };
-        
-        let avg_propagation_priority: f32 = if node_rs.is_empty() {
-            0.0
-        } else {
-            let sum: f64 = node_rs.iter().map(|r| r.propagation_priority as f64).sum();
-            (sum / (node_rs.len() as f64)) as f32
+        let conn = pool.read()?;
+        // Считаем в SQL, не загружая таблицы
+        let count = |table: &str| -> Result<i32, core_lib::models::CoreError> {
+            Ok(conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |r| r.get::<_, i64>(0))? as i32)
         };
+        let events = count("truth_events")?;
+        let statements = count("statements")?;
+        let impacts = count("impact")?;
+        let group_ratings = count("group_ratings")?;
+        let (node_ratings, avg_trust_score, avg_propagation_priority): (i64, f64, f64) = conn.query_row(
+            "SELECT COUNT(*), COALESCE(AVG(trust_score), 0.0), COALESCE(AVG(propagation_priority), 0.0) FROM node_ratings",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )?;
+        let (node_ratings, avg_trust_score, avg_propagation_priority) =
+            (node_ratings as i32, avg_trust_score as f32, avg_propagation_priority as f32);
         
         // Загружаем метрики узлов
         let node_metrics = core_lib::storage::load_all_node_metrics(&conn)?;
@@ -287,18 +279,63 @@ async fn health() -> impl Responder {
     HttpResponse::Ok().body("OK")
 }
 
-/// GET /statements
+/// Параметры постраничных выборок (/events, /statements)
+#[derive(Deserialize)]
+struct RecordQueryParams {
+    context: Option<i64>,
+    author: Option<String>,
+    detected: Option<bool>,
+    since: Option<i64>,
+    until: Option<i64>,
+    min_score: Option<f64>,
+    max_score: Option<f64>,
+    sort: Option<storage::SortField>,
+    order: Option<String>,
+    limit: Option<usize>,
+    cursor: Option<String>,
+}
+
+impl RecordQueryParams {
+    fn into_query(self) -> storage::RecordQuery {
+        storage::RecordQuery {
+            context_id: self.context,
+            author: self.author,
+            detected: self.detected,
+            since: self.since,
+            until: self.until,
+            min_score: self.min_score,
+            max_score: self.max_score,
+            sort: self.sort.unwrap_or_default(),
+            descending: self.order.as_deref() == Some("desc"),
+            limit: self.limit.unwrap_or(0),
+            cursor: self.cursor,
+        }
+    }
+}
+
+/// Страница как JSON-массив; курсор следующей страницы — в заголовке X-Next-Cursor
+fn page_response<T: Serialize>(page: storage::Page<T>) -> HttpResponse {
+    let mut resp = HttpResponse::Ok();
+    if let Some(c) = &page.next_cursor {
+        resp.insert_header(("X-Next-Cursor", c.as_str()));
+    }
+    resp.json(page.items)
+}
+
+/// GET /statements — постранично, с фильтрами RecordQueryParams
 #[get("/statements")]
-async fn get_statements(pool: web::Data<DbPool>) -> impl Responder {
+async fn get_statements(pool: web::Data<DbPool>, query: web::Query<RecordQueryParams>) -> impl Responder {
     let pool = pool.clone();
+    let q = query.into_inner().into_query();
     let result = web::block(move || {
-        let _conn = pool.blocking_lock();
-        storage::load_statements(&_conn)
+        let _conn = pool.read()?;
+        storage::query_statements(&_conn, &q)
     })
     .await;
 
     match result {
-        Ok(Ok(statements)) => HttpResponse::Ok().json(statements),
+        Ok(Ok(page)) => page_response(page),
+        Ok(Err(core_lib::models::CoreError::InvalidArg(m))) => HttpResponse::BadRequest().json(serde_json::json!({"error": m})),
         Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
         Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
     }
@@ -310,6 +347,10 @@ struct AddStatementRequest {
     text: String,
     context: Option<String>,
     truth_score: Option<f32>,
+    /// uid утверждения того же события, на которое это отвечает
+    parent_uid: Option<String>,
+    /// supports, rebuts или clarifies; обязательно вместе с parent_uid
+    relation: Option<String>,
 }
 
 /// POST /statements
@@ -322,12 +363,14 @@ async fn add_statement(
     let req = payload.into_inner();
 
     let result = web::block(move || {
-        let _conn = pool.blocking_lock();
+        let _conn = pool.write()?;
         let new_statement = NewStatement {
             event_id: req.event_id,
             text: req.text,
             context: req.context,
             truth_score: req.truth_score,
+            parent_uid: req.parent_uid,
+            relation: req.relation,
         };
         storage::add_statement(&_conn, new_statement)
     })
@@ -335,14 +378,50 @@ async fn add_statement(
 
     match result {
         Ok(Ok(id)) => HttpResponse::Ok().json(serde_json::json!({"id": id})),
+        Ok(Err(core_lib::models::CoreError::InvalidArg(m))) => HttpResponse::BadRequest().json(serde_json::json!({"error": m})),
+        Ok(Err(core_lib::models::CoreError::NotFound(m))) => HttpResponse::NotFound().json(serde_json::json!({"error": m})),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// GET /api/v1/events/{uid}/arguments — дерево утверждений события с итоговыми оценками узлов и события
+#[get("/api/v1/events/{uid}/arguments")]
+async fn api_v1_event_arguments(pool: web::Data<DbPool>, path: web::Path<String>) -> impl Responder {
+    let uid = path.into_inner();
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.read()?;
+        core_lib::argument::tree_for_event(&conn, &uid)
+    }).await;
+    match res {
+        Ok(Ok(Some(tree))) => HttpResponse::Ok().json(tree),
+        Ok(Ok(None)) => HttpResponse::NotFound().json(serde_json::json!({"error":"event not found"})),
         Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
         Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
     }
 }
 
-/// GET /events
+/// GET /api/v1/events/{uid} — карточка события: каноническое событие (для дубликатов) и связанные события
+#[get("/api/v1/events/{uid}")]
+async fn api_v1_event_detail(pool: web::Data<DbPool>, path: web::Path<String>) -> impl Responder {
+    let uid = path.into_inner();
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.read()?;
+        core_lib::event_relation::event_detail(&conn, &uid)
+    }).await;
+    match res {
+        Ok(Ok(Some(detail))) => HttpResponse::Ok().json(detail),
+        Ok(Ok(None)) => HttpResponse::NotFound().json(serde_json::json!({"error":"event not found"})),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// GET /events — постранично, с фильтрами RecordQueryParams
 #[get("/events")]
-async fn get_events(req: HttpRequest, pool: web::Data<DbPool>) -> impl Responder {
+async fn get_events(req: HttpRequest, pool: web::Data<DbPool>, query: web::Query<RecordQueryParams>) -> impl Responder {
     let public_key = req
         .headers()
         .get("X-Public-Key")
@@ -365,14 +444,16 @@ async fn get_events(req: HttpRequest, pool: web::Data<DbPool>) -> impl Responder
         Ok(()) => {
             // подпись валидна — возвращаем события из БД
             let pool = pool.clone();
+            let q = query.into_inner().into_query();
             let result = web::block(move || {
-                let _conn = pool.blocking_lock();
-                storage::load_truth_events(&_conn)
+                let _conn = pool.read()?;
+                storage::query_truth_events(&_conn, &q)
             })
             .await;
 
             match result {
-                Ok(Ok(list)) => HttpResponse::Ok().json(list),
+                Ok(Ok(page)) => page_response(page),
+                Ok(Err(core_lib::models::CoreError::InvalidArg(m))) => HttpResponse::BadRequest().json(serde_json::json!({"error": m})),
                 Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
                 Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
             }
@@ -400,7 +481,7 @@ async fn add_event(pool: web::Data<DbPool>, payload: web::Json<AddEventRequest>)
     let req = payload.into_inner();
 
     let result = web::block(move || {
-        let _conn = pool.blocking_lock();
+        let _conn = pool.write()?;
         let new_event = NewTruthEvent {
             description: req.description,
             context_id: req.context_id,
@@ -408,12 +489,14 @@ async fn add_event(pool: web::Data<DbPool>, payload: web::Json<AddEventRequest>)
             timestamp_start: chrono::Utc::now().timestamp(),
             code: 1, // Default code for new events
         };
-        storage::add_truth_event(&_conn, new_event)
+        let id = storage::add_truth_event(&_conn, new_event)?;
+        let uid = storage::get_truth_event(&_conn, id)?.map(|e| e.uid).unwrap_or_default();
+        Ok::<_, core_lib::models::CoreError>((id, uid))
     })
     .await;
 
     match result {
-        Ok(Ok(id)) => HttpResponse::Ok().json(serde_json::json!({"id": id})),
+        Ok(Ok((id, uid))) => HttpResponse::Ok().json(serde_json::json!({"id": id, "uid": uid})),
         Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
         Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
     }
@@ -427,13 +510,26 @@ async fn add_impact(pool: web::Data<DbPool>, payload: web::Json<Impact>) -> impl
     let im_copy = im.clone();
 
     let result = web::block(move || {
-        let _conn = pool.blocking_lock();
-        storage::add_impact(&_conn, im_copy.event_id.parse().unwrap_or(0), im_copy.type_id, im_copy.value, im_copy.notes)
+        // value должен совпадать со знаком magnitude, как и у записей от пиров
+        if !im_copy.sign_consistent() {
+            return Err(core_lib::models::CoreError::InvalidArg("impact value disagrees with the sign of magnitude".into()));
+        }
+        let _conn = pool.write()?;
+        // event_id — локальный id события или его uid
+        let event_id = match im_copy.event_id.parse::<i64>() {
+            Ok(id) => id,
+            Err(_) => storage::get_truth_event_by_uid(&_conn, &im_copy.event_id)?
+                .map(|e| e.id)
+                .ok_or_else(|| core_lib::models::CoreError::NotFound(format!("event {}", im_copy.event_id)))?,
+        };
+        // Без magnitude (старые клиенты) степень ±1 по value
+        storage::add_graded_impact(&_conn, event_id, im_copy.type_id, im_copy.grade(), im_copy.confidence, im_copy.notes)
     })
     .await;
 
     match result {
         Ok(Ok(id)) => HttpResponse::Ok().json(serde_json::json!({"id": id})),
+        Ok(Err(core_lib::models::CoreError::InvalidArg(m))) => HttpResponse::BadRequest().json(serde_json::json!({"error": m})),
         Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
         Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
     }
@@ -444,7 +540,7 @@ async fn add_impact(pool: web::Data<DbPool>, payload: web::Json<Impact>) -> impl
 async fn init_db(pool: web::Data<DbPool>) -> impl Responder {
     let pool = pool.clone();
     let result = web::block(move || {
-        let _conn = pool.blocking_lock();
+        let _conn = pool.write()?;
         storage::init_db(&_conn)
     })
     .await;
@@ -469,7 +565,7 @@ async fn seed_db(pool: web::Data<DbPool>, payload: web::Json<SeedRequest>) -> im
     let locale_for_response = locale.clone();
     
     let result = web::block(move || {
-        let mut _conn = pool.blocking_lock();
+        let mut _conn = pool.write()?;
         storage::seed_knowledge_base(&mut _conn, &locale)
     })
     .await;
@@ -496,7 +592,7 @@ async fn detect_event(pool: web::Data<DbPool>, payload: web::Json<serde_json::Va
         .unwrap_or(false);
     
     let result = web::block(move || {
-        let _conn = pool.blocking_lock();
+        let _conn = pool.write()?;
         storage::set_event_detected(&_conn, event_id, detected, None, corrected)
             .and_then(|_| core_lib::storage::recalc_ratings(&_conn, chrono::Utc::now().timestamp()))
     })
@@ -515,7 +611,7 @@ async fn recalc_metrics(req: HttpRequest, pool: web::Data<DbPool>) -> impl Respo
     if let Err(resp) = require_jwt(req).await.map(|_| ()) { return resp; }
     let pool = pool.clone();
     let result = web::block(move || {
-        let _conn = pool.blocking_lock();
+        let _conn = pool.write()?;
         let ts = chrono::Utc::now().timestamp();
         let metric_id = storage::recalc_progress_metrics(&_conn, ts)?;
         core_lib::storage::recalc_ratings(&_conn, ts)?;
@@ -530,27 +626,49 @@ async fn recalc_metrics(req: HttpRequest, pool: web::Data<DbPool>) -> impl Respo
     }
 }
 
-/// GET /get_data - Получение всех данных
+#[derive(Deserialize)]
+struct GetDataQuery {
+    limit: Option<usize>,
+    events_cursor: Option<String>,
+    impacts_cursor: Option<String>,
+}
+
+/// GET /get_data - Данные для синхронизации; события и влияния постранично (курсор `end` — пропустить)
 #[get("/get_data")]
-async fn get_all_data(pool: web::Data<DbPool>) -> impl Responder {
+async fn get_all_data(pool: web::Data<DbPool>, query: web::Query<GetDataQuery>) -> impl Responder {
     let pool = pool.clone();
+    let GetDataQuery { limit, events_cursor, impacts_cursor } = query.into_inner();
     let result = web::block(move || {
-        let _conn = pool.blocking_lock();
-        let events = storage::load_truth_events(&_conn)?;
-        let impacts = storage::load_impacts(&_conn)?;
+        let _conn = pool.read()?;
+        let base = storage::RecordQuery::new().limit(limit.unwrap_or(storage::PAGE_MAX_LIMIT));
+        let events = storage::query_truth_events(&_conn, &base.clone().after(events_cursor))?;
+        let impacts = storage::query_impacts(&_conn, &base.after(impacts_cursor))?;
         let metrics = storage::load_metrics(&_conn)?;
-        Ok::<(Vec<core_lib::models::TruthEvent>, Vec<core_lib::models::Impact>, Vec<core_lib::models::ProgressMetrics>), core_lib::models::CoreError>((events, impacts, metrics))
+        let delegations = storage::load_trust_delegations(&_conn, None)?;
+        let tombstones = storage::load_tombstones(&_conn, None)?;
+        let knowledge_base = storage::load_kb_changes(&_conn, None)?;
+        let evidence = storage::load_evidence(&_conn, None)?;
+        let event_relations = storage::load_event_relations(&_conn, None)?;
+        Ok::<_, core_lib::models::CoreError>((events, impacts, metrics, delegations, tombstones, knowledge_base, evidence, event_relations))
     })
     .await;
 
     match result {
-        Ok(Ok((events, impacts, metrics))) => {
+        Ok(Ok((events, impacts, metrics, delegations, tombstones, knowledge_base, evidence, event_relations))) => {
             HttpResponse::Ok().json(serde_json::json!({
-                "events": events,
-                "impacts": impacts,
-                "metrics": metrics
+                "events": events.items,
+                "impacts": impacts.items,
+                "metrics": metrics,
+                "trust_delegations": delegations,
+                "tombstones": tombstones,
+                "knowledge_base": knowledge_base,
+                "evidence": evidence,
+                "event_relations": event_relations,
+                "next_events_cursor": events.next_cursor,
+                "next_impacts_cursor": impacts.next_cursor
             }))
         },
+        Ok(Err(core_lib::models::CoreError::InvalidArg(m))) => HttpResponse::BadRequest().json(serde_json::json!({"error": m})),
         Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
         Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
     }
@@ -561,7 +679,7 @@ async fn get_all_data(pool: web::Data<DbPool>) -> impl Responder {
 async fn get_progress(pool: web::Data<DbPool>) -> impl Responder {
     let pool = pool.clone();
     let result = web::block(move || {
-        let _conn = pool.blocking_lock();
+        let _conn = pool.read()?;
         storage::load_metrics(&_conn)
     })
     .await;
@@ -603,11 +721,12 @@ async fn sync_data(req: HttpRequest, pool: web::Data<DbPool>, payload: web::Json
         Ok(()) => {
             let pool = pool.clone();
             let received_data = payload.into_inner();
+            let sender = public_key.to_string();
             
             let result = web::block(move || {
-                let _conn = pool.blocking_lock();
-                // Reconcile into local DB and log
-                crate::p2p::sync::reconcile(&_conn, &received_data)
+                let _conn = pool.write()?;
+                // Reconcile into local DB and log (вес рейтингов — по репутации отправителя)
+                crate::p2p::sync::reconcile_from(&_conn, &received_data, Some(&sender))
                     .map_err(|e| core_lib::models::CoreError::InvalidArg(e.to_string()))
             })
             .await;
@@ -654,10 +773,11 @@ async fn incremental_sync(req: HttpRequest, pool: web::Data<DbPool>, payload: we
         Ok(()) => {
             let pool = pool.clone();
             let received_data = payload.into_inner();
+            let sender = public_key.to_string();
             
             let result = web::block(move || {
-                let _conn = pool.blocking_lock();
-                crate::p2p::sync::reconcile(&_conn, &received_data)
+                let _conn = pool.write()?;
+                crate::p2p::sync::reconcile_from(&_conn, &received_data, Some(&sender))
                     .map_err(|e| core_lib::models::CoreError::InvalidArg(e.to_string()))
             })
             .await;
@@ -691,7 +811,7 @@ async fn api_v1_recalc(req: HttpRequest, pool: web::Data<DbPool>) -> impl Respon
     if let Err(resp) = require_jwt(req).await.map(|_| ()) { return resp; }
     let pool = pool.clone();
     let result = web::block(move || {
-        let _conn = pool.blocking_lock();
+        let _conn = pool.write()?;
         let ts = chrono::Utc::now().timestamp();
         let metric_id = storage::recalc_progress_metrics(&_conn, ts)?;
         core_lib::storage::recalc_ratings(&_conn, ts)?;
@@ -708,7 +828,7 @@ async fn api_v1_recalc(req: HttpRequest, pool: web::Data<DbPool>) -> impl Respon
 async fn recalc_collective(pool: web::Data<DbPool>) -> impl Responder {
     let pool = pool.clone();
     let result = web::block(move || {
-        let conn = pool.blocking_lock();
+        let conn = pool.write()?;
         core_lib::recalc_collective_truth(&conn)
     }).await;
     match result {
@@ -739,6 +859,43 @@ async fn api_v1_reinit(req: HttpRequest) -> impl Responder {
     HttpResponse::Ok().json(serde_json::json!({"status":"ok"}))
 }
 
+/// GET /api/v1/db/encryption — состояние шифрования БД
+#[get("/api/v1/db/encryption")]
+async fn api_v1_db_encryption(pool: web::Data<DbPool>) -> impl Responder {
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.read()?;
+        core_lib::at_rest::status(&conn)
+    }).await;
+    match res {
+        Ok(Ok(status)) => HttpResponse::Ok().json(status),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct UnlockRequest { passphrase: String }
+
+/// POST /api/v1/db/unlock — разблокировать зашифрованную БД парольной фразой.
+/// Без JWT: пока БД заблокирована, сессии недоступны, а паролем служит сама фраза.
+#[post("/api/v1/db/unlock")]
+async fn api_v1_db_unlock(pool: web::Data<DbPool>, body: web::Json<UnlockRequest>) -> impl Responder {
+    let source = core_lib::at_rest::KeySource::Passphrase(body.into_inner().passphrase);
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.write()?;
+        core_lib::at_rest::unlock(&conn, &source)
+    }).await;
+    match res {
+        Ok(Ok(())) => HttpResponse::Ok().json(serde_json::json!({"status":"unlocked"})),
+        Ok(Err(core_lib::models::CoreError::Locked(m))) => HttpResponse::Unauthorized().json(serde_json::json!({"error": m})),
+        Ok(Err(core_lib::models::CoreError::InvalidArg(m))) => HttpResponse::BadRequest().json(serde_json::json!({"error": m})),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
 /// helper: зарегистрировать все маршруты
 pub fn routes(cfg: &mut web::ServiceConfig) {
     cfg.service(health)
@@ -753,6 +910,8 @@ pub fn routes(cfg: &mut web::ServiceConfig) {
         .service(api_v1_ratings_sync)
         .service(api_v1_reset)
         .service(api_v1_reinit)
+        .service(api_v1_db_encryption)
+        .service(api_v1_db_unlock)
         .service(init_db)
         .service(seed_db)
         .service(detect_event)
@@ -762,12 +921,17 @@ pub fn routes(cfg: &mut web::ServiceConfig) {
         .service(get_progress)
         .service(api_v1_peers_priorities)
         .service(get_node_ratings)
+        .service(get_node_trust_history)
         .service(get_group_ratings)
+        .service(get_group_rating)
         .service(get_graph)
         .service(get_graph_json)
         .service(get_graph_summary)
+        .service(get_graph_events)
         .service(get_statements)
         .service(add_statement)
+        .service(api_v1_event_arguments)
+        .service(api_v1_event_detail)
         .service(get_events)
         .service(add_event)
         .service(add_impact)
@@ -777,6 +941,22 @@ pub fn routes(cfg: &mut web::ServiceConfig) {
         .service(api_v1_users_list)
         .service(api_v1_users_role)
         .service(api_v1_trust_delegate)
+        .service(api_v1_trust_delegations)
+        .service(api_v1_trust_revoke)
+        .service(api_v1_tombstones_post)
+        .service(api_v1_tombstones_get)
+        .service(api_v1_evidence_post)
+        .service(api_v1_evidence_get)
+        .service(api_v1_evidence_blob_put)
+        .service(api_v1_evidence_blob_get)
+        .service(api_v1_event_relations_post)
+        .service(api_v1_event_relations_get)
+        .service(api_v1_kb_list)
+        .service(api_v1_kb_post)
+        .service(api_v1_alerts)
+        .service(api_v1_alerts_scan)
+        .service(api_v1_alerts_ack)
+        .service(api_v1_search)
         .service(api_v1_judgments_post)
         .service(api_v1_judgments_get)
         .service(api_v1_consensus_get)
@@ -792,6 +972,8 @@ pub struct PeerPriorityItem {
     pub propagation_priority: f32,
     pub relay_rate: f32,
     pub quality_index: f32,
+    /// Активность пира за окно (0..1)
+    pub recent_activity: f32,
 }
 
 /// GET /api/v1/network/local — локальная статистика по пирам и сводка
@@ -804,7 +986,7 @@ pub struct PeerPriorityItem {
 async fn api_v1_network_local(pool: web::Data<DbPool>) -> impl Responder {
     let pool = pool.clone();
     let result = web::block(move || {
-        let conn = pool.blocking_lock();
+        let conn = pool.read()?;
         // Грузим историю и сводку
         let peers = core_lib::storage::load_peer_history(&conn, Some(200))?;
         let summary = core_lib::storage::get_peer_summary(&conn)?;
@@ -851,17 +1033,19 @@ async fn api_v1_peers_priorities(node: web::Data<Node>, pool: web::Data<DbPool>)
     let peers = node.peers.clone();
     let pool_for_block = pool.clone();
     let priorities = web::block(move || {
-        let conn = pool_for_block.blocking_lock();
-        let mut items: Vec<PeerPriorityItem> = Vec::new();
+        let conn = pool_for_block.read()?;
+        let now = chrono::Utc::now().timestamp();
         let ratings = core_lib::storage::load_node_ratings(&conn).unwrap_or_default();
         let map: std::collections::HashMap<String, core_lib::models::NodeRating> = ratings.into_iter().map(|r| (r.node_id.clone(), r)).collect();
+        let mut items: Vec<PeerPriorityItem> = Vec::new();
         for url in peers.iter() {
-            // public_key hex в URL не хранится — в MVP ищем по совпадению начала ключа в URL или пропускаем
-            // Для корректности ожидается, что Node.peers содержит URLы пиров, а соответствие ключ→URL хранится в приложении CLI/конфиге.
-            // Здесь заполним trust_priority как среднее по сети, если ключ не найден.
-            let (trust, prio) = if let Some((_id, r)) = map.iter().next() { (r.trust_score, r.propagation_priority) } else { (0.0f32, 0.5f32) };
-            items.push(PeerPriorityItem { peer_url: url.clone(), trust_score: trust, propagation_priority: prio, relay_rate: 0.0, quality_index: 0.0 });
+            // Пир идентифицируется URL (или pubkey, если он хранится вместо URL); приоритет считается
+            // на текущий момент, поэтому давно неактивные пиры опускаются без ожидания пересчёта
+            let trust = map.get(url).map(|r| r.trust_score).unwrap_or(0.0);
+            let (prio, activity) = core_lib::storage::peer_propagation_priority(&conn, url, now)?;
+            items.push(PeerPriorityItem { peer_url: url.clone(), trust_score: trust, propagation_priority: prio, relay_rate: 0.0, quality_index: 0.0, recent_activity: activity });
         }
+        items.sort_by(|a, b| b.propagation_priority.partial_cmp(&a.propagation_priority).unwrap_or(std::cmp::Ordering::Equal));
         Ok::<Vec<PeerPriorityItem>, core_lib::models::CoreError>(items)
     }).await;
 
@@ -877,7 +1061,7 @@ async fn api_v1_peers_priorities(node: web::Data<Node>, pool: web::Data<DbPool>)
         if let Ok(Ok(Some(mm))) = web::block({
             let pool2 = pool.clone();
             move || {
-                let conn = pool2.blocking_lock();
+                let conn = pool2.read()?;
                 core_lib::storage::load_node_metrics(&conn, &key)
             }
         }).await {
@@ -907,7 +1091,7 @@ async fn api_v1_auth(req: HttpRequest, pool: web::Data<DbPool>) -> impl Responde
         let pool = pool.clone();
         let pk_owned = pk.to_string();
         move || {
-            let conn = pool.blocking_lock();
+            let conn = pool.read()?;
             issue_jwt_pair_with(&conn, &pk_owned)
         }
     }).await {
@@ -919,7 +1103,7 @@ async fn api_v1_auth(req: HttpRequest, pool: web::Data<DbPool>) -> impl Responde
     let pk_owned = pk.to_string();
     let refresh_owned = refresh.clone();
     let res = web::block(move || {
-        let conn = pool.blocking_lock();
+        let conn = pool.write()?;
         core_lib::storage::register_refresh_token(&conn, &pk_owned, &refresh_owned, exp_refresh)
     }).await;
     match res {
@@ -945,7 +1129,7 @@ async fn api_v1_users_list(req: HttpRequest, pool: web::Data<DbPool>) -> impl Re
     if let Err(resp) = require_role(req, "admin").await.map(|_| ()) { return resp; }
     let pool = pool.clone();
     let result = web::block(move || {
-        let conn = pool.blocking_lock();
+        let conn = pool.read()?;
         core_lib::storage::list_users(&conn)
     }).await;
     match result {
@@ -964,42 +1148,525 @@ struct RoleRequest { pubkey: String, role: String }
 )]
 #[post("/api/v1/users/role")]
 async fn api_v1_users_role(req: HttpRequest, pool: web::Data<DbPool>, body: web::Json<RoleRequest>) -> impl Responder {
-    if let Err(resp) = require_role(req, "admin").await.map(|_| ()) { return resp; }
+    let claims = match require_role(req, "admin").await { Ok(c) => c, Err(resp) => return resp };
     let RoleRequest { pubkey, role } = body.into_inner();
     let poolc = pool.clone();
     let res = web::block(move || {
-        let conn = poolc.blocking_lock();
-        core_lib::storage::update_user_role(&conn, &pubkey, &role)
+        let conn = poolc.write()?;
+        core_lib::audit::with_actor(&claims.sub, || core_lib::storage::update_user_role(&conn, &pubkey, &role))
     }).await;
     match res { Ok(Ok(())) => HttpResponse::Ok().finish(), _ => HttpResponse::InternalServerError().finish() }
 }
 
 #[derive(Deserialize)]
-struct TrustDelegateRequest { target_pubkey: String, delta: f32 }
+struct TrustDelegateRequest {
+    target_pubkey: String,
+    delta: f32,
+    created_at: i64,
+    expires_at: Option<i64>,
+    /// Подпись делегирующего узла над TrustDelegation::signing_message()
+    signature: String,
+}
 
 #[utoipa::path(
     post,
     path = "/api/v1/trust/delegate",
-    responses((status=200, description="Делегирование доверия применено"))
+    responses((status=200, description="Делегирование доверия сохранено"))
 )]
 #[post("/api/v1/trust/delegate")]
 async fn api_v1_trust_delegate(req: HttpRequest, pool: web::Data<DbPool>, body: web::Json<TrustDelegateRequest>) -> impl Responder {
     // Требуется роль не ниже node
     let claims = match require_role(req, "node").await { Ok(c) => c, Err(resp) => return resp };
-    let TrustDelegateRequest { target_pubkey, delta } = body.into_inner();
-    // ограничиваем делегирование по FidoNet-like: разрешено только положительное малое смещение и не на себя
-    if target_pubkey == claims.sub || delta.abs() > 0.2 { // лимит шага делегирования
+    let TrustDelegateRequest { target_pubkey, delta, created_at, expires_at, signature } = body.into_inner();
+    // ограничиваем делегирование по FidoNet-like: разрешено только малое смещение и не на себя
+    if target_pubkey == claims.sub || delta.abs() > core_lib::storage::MAX_DELEGATION_DELTA { // лимит шага делегирования
         return HttpResponse::BadRequest().body("invalid delegation request");
     }
+    // Запись подписывается делегирующим узлом (субъект JWT) и распространяется пирам как есть
+    let mut delegation = core_lib::models::TrustDelegation::new(&claims.sub, &target_pubkey, delta, created_at, expires_at);
+    delegation.signature = signature;
+    if let Err(e) = crate::p2p::sync::verify_delegation(&delegation) {
+        return HttpResponse::Unauthorized().body(format!("Invalid signature: {}", e));
+    }
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.write()?;
+        core_lib::audit::with_actor(&delegation.delegator, || core_lib::storage::upsert_trust_delegation(&conn, &delegation))?;
+        core_lib::storage::log_sync(&conn, "insert", "trust_delegations", &delegation.id, Some(delegation.signature.clone()), Some(delegation.delegator.clone()))?;
+        // применяем к рейтингам сразу, не дожидаясь следующего пересчёта
+        core_lib::storage::recalc_ratings(&conn, chrono::Utc::now().timestamp())?;
+        Ok::<String, core_lib::models::CoreError>(delegation.id)
+    }).await;
+    match res {
+        Ok(Ok(id)) => HttpResponse::Ok().json(serde_json::json!({"status":"ok","id":id})),
+        _ => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[derive(Deserialize)]
+struct TrustDelegationsQuery {
+    target: Option<String>,
+    delegator: Option<String>,
+    all: Option<bool>,
+}
+
+/// GET /api/v1/trust/delegations — список делегирований (по умолчанию только действующие)
+#[get("/api/v1/trust/delegations")]
+async fn api_v1_trust_delegations(pool: web::Data<DbPool>, query: web::Query<TrustDelegationsQuery>) -> impl Responder {
+    let TrustDelegationsQuery { target, delegator, all } = query.into_inner();
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.read()?;
+        let active_at = if all.unwrap_or(false) { None } else { Some(chrono::Utc::now().timestamp()) };
+        let mut list = core_lib::storage::load_trust_delegations(&conn, active_at)?;
+        if let Some(t) = target.as_deref() { list.retain(|d| d.target == t); }
+        if let Some(dg) = delegator.as_deref() { list.retain(|d| d.delegator == dg); }
+        Ok::<_, core_lib::models::CoreError>(list)
+    }).await;
+    match res {
+        Ok(Ok(list)) => HttpResponse::Ok().json(list),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct TrustRevokeRequest {
+    id: String,
+    revoked_at: i64,
+    /// Подпись делегирующего узла над TrustDelegation::revocation_message()
+    signature: String,
+}
+
+/// POST /api/v1/trust/revoke — отзыв делегирования его автором
+#[post("/api/v1/trust/revoke")]
+async fn api_v1_trust_revoke(req: HttpRequest, pool: web::Data<DbPool>, body: web::Json<TrustRevokeRequest>) -> impl Responder {
+    let claims = match require_role(req, "node").await { Ok(c) => c, Err(resp) => return resp };
+    let TrustRevokeRequest { id, revoked_at, signature } = body.into_inner();
+    enum Outcome { Revoked, NotFound, Forbidden, BadSignature(String) }
+    let poolc = pool.clone();
+    let idc = id.clone();
+    let res = web::block(move || {
+        let conn = poolc.write()?;
+        let mut d = match core_lib::storage::get_trust_delegation(&conn, &idc)? {
+            Some(d) => d,
+            None => return Ok(Outcome::NotFound),
+        };
+        if d.delegator != claims.sub {
+            return Ok(Outcome::Forbidden);
+        }
+        d.revoked_at = Some(revoked_at);
+        d.revocation_signature = Some(signature.clone());
+        if let Err(e) = crate::p2p::sync::verify_delegation(&d) {
+            return Ok(Outcome::BadSignature(e.to_string()));
+        }
+        core_lib::audit::with_actor(&d.delegator, || core_lib::storage::revoke_trust_delegation(&conn, &idc, revoked_at, &signature))?;
+        core_lib::storage::log_sync(&conn, "revoke", "trust_delegations", &idc, Some(signature), Some(d.delegator))?;
+        core_lib::storage::recalc_ratings(&conn, chrono::Utc::now().timestamp())?;
+        Ok::<_, core_lib::models::CoreError>(Outcome::Revoked)
+    }).await;
+    match res {
+        Ok(Ok(Outcome::Revoked)) => HttpResponse::Ok().json(serde_json::json!({"status":"revoked","id":id})),
+        Ok(Ok(Outcome::NotFound)) => HttpResponse::NotFound().json(serde_json::json!({"error":"delegation not found"})),
+        Ok(Ok(Outcome::Forbidden)) => HttpResponse::Forbidden().json(serde_json::json!({"error":"forbidden","code":403})),
+        Ok(Ok(Outcome::BadSignature(e))) => HttpResponse::Unauthorized().body(format!("Invalid signature: {}", e)),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct TombstoneRequest {
+    table: String,
+    record_id: String,
+    reason: Option<String>,
+    deleted_at: i64,
+    /// Подпись автора записи или администратора над Tombstone::signing_message()
+    signature: String,
+}
+
+/// POST /api/v1/tombstones — удалить событие, утверждение или влияние подписанной отметкой.
+/// Удалять может автор записи или администратор; отметка распространяется пирам при синхронизации.
+#[post("/api/v1/tombstones")]
+async fn api_v1_tombstones_post(req: HttpRequest, pool: web::Data<DbPool>, body: web::Json<TombstoneRequest>) -> impl Responder {
+    use core_lib::tombstone::Applied;
+    let claims = match require_role(req, "node").await { Ok(c) => c, Err(resp) => return resp };
+    let TombstoneRequest { table, record_id, reason, deleted_at, signature } = body.into_inner();
+    if !core_lib::models::Tombstone::TABLES.contains(&table.as_str()) {
+        return HttpResponse::BadRequest().json(serde_json::json!({"error": format!("records of {} cannot be deleted", table)}));
+    }
+    let mut tombstone = core_lib::models::Tombstone::new(&table, &record_id, &claims.sub, reason, deleted_at);
+    tombstone.signature = signature;
+    if let Err(e) = core_lib::tombstone::verify(&tombstone) {
+        return HttpResponse::Unauthorized().body(format!("Invalid signature: {}", e));
+    }
+    let id = tombstone.id.clone();
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.write()?;
+        core_lib::audit::with_actor(&tombstone.author, || core_lib::tombstone::apply(&*conn, &tombstone))
+    }).await;
+    match res {
+        Ok(Ok(Applied::Deleted(rows))) => HttpResponse::Ok().json(serde_json::json!({"status":"deleted","id":id,"rows":rows})),
+        Ok(Ok(Applied::Pending)) => HttpResponse::Accepted().json(serde_json::json!({"status":"pending","id":id})),
+        Ok(Ok(Applied::Known)) => HttpResponse::Ok().json(serde_json::json!({"status":"known","id":id})),
+        Ok(Ok(Applied::Forbidden)) => HttpResponse::Forbidden().json(serde_json::json!({"error":"forbidden","code":403})),
+        Ok(Err(core_lib::models::CoreError::InvalidArg(m))) => HttpResponse::BadRequest().json(serde_json::json!({"error": m})),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct TombstonesQuery {
+    since: Option<i64>,
+    table: Option<String>,
+}
+
+/// GET /api/v1/tombstones — отметки удаления (новые первыми)
+#[get("/api/v1/tombstones")]
+async fn api_v1_tombstones_get(pool: web::Data<DbPool>, query: web::Query<TombstonesQuery>) -> impl Responder {
+    let TombstonesQuery { since, table } = query.into_inner();
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.read()?;
+        let mut list = core_lib::storage::load_tombstones(&conn, since)?;
+        if let Some(t) = table.as_deref() { list.retain(|x| x.table_name == t); }
+        Ok::<_, core_lib::models::CoreError>(list)
+    }).await;
+    match res {
+        Ok(Ok(list)) => HttpResponse::Ok().json(list),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct EvidenceRequest {
+    target_table: String,
+    target_uid: String,
+    kind: String,
+    sha256: Option<String>,
+    name: String,
+    media_type: Option<String>,
+    size: Option<i64>,
+    note: Option<String>,
+    created_at: i64,
+    /// Подпись автора над Evidence::signing_message()
+    signature: String,
+}
+
+/// POST /api/v1/evidence — прикрепить к событию или утверждению подписанное доказательство (файл или ссылку).
+/// Содержимое файла загружается отдельно: PUT /api/v1/evidence/blobs/{sha256}.
+#[post("/api/v1/evidence")]
+async fn api_v1_evidence_post(req: HttpRequest, pool: web::Data<DbPool>, body: web::Json<EvidenceRequest>) -> impl Responder {
+    use core_lib::evidence::Applied;
+    let claims = match require_role(req, "node").await { Ok(c) => c, Err(resp) => return resp };
+    let r = body.into_inner();
+    let mut evidence = core_lib::models::Evidence::new(&r.target_table, &r.target_uid, &r.kind, r.sha256, &r.name, &claims.sub, r.created_at);
+    evidence.media_type = r.media_type;
+    evidence.size = r.size;
+    evidence.note = r.note;
+    evidence.signature = r.signature;
+    if let Err(e) = core_lib::evidence::verify(&evidence) {
+        return HttpResponse::BadRequest().json(serde_json::json!({"error": e.to_string()}));
+    }
+    let id = evidence.id.clone();
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.write()?;
+        core_lib::audit::with_actor(&evidence.author, || core_lib::evidence::apply(&conn, &evidence))
+    }).await;
+    match res {
+        Ok(Ok(Applied::Created)) => HttpResponse::Created().json(serde_json::json!({"status":"created","id":id})),
+        Ok(Ok(Applied::Known)) => HttpResponse::Ok().json(serde_json::json!({"status":"known","id":id})),
+        Ok(Ok(Applied::MissingTarget | Applied::Deleted)) => HttpResponse::NotFound().json(serde_json::json!({"error":"target record not found"})),
+        Ok(Err(core_lib::models::CoreError::InvalidArg(m))) => HttpResponse::BadRequest().json(serde_json::json!({"error": m})),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct EvidenceQuery {
+    /// uid события: его доказательства и доказательства его утверждений
+    event: Option<String>,
+    since: Option<i64>,
+}
+
+/// GET /api/v1/evidence — доказательства события (?event=uid, старые первыми) или все (новые первыми)
+#[get("/api/v1/evidence")]
+async fn api_v1_evidence_get(pool: web::Data<DbPool>, query: web::Query<EvidenceQuery>) -> impl Responder {
+    let EvidenceQuery { event, since } = query.into_inner();
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.read()?;
+        match event {
+            Some(uid) => core_lib::storage::evidence_for_event(&conn, &uid),
+            None => core_lib::storage::load_evidence(&conn, since),
+        }
+    }).await;
+    match res {
+        Ok(Ok(list)) => HttpResponse::Ok().json(list),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct EventRelationRequest {
+    source_uid: String,
+    target_uid: String,
+    relation: String,
+    note: Option<String>,
+    created_at: i64,
+    /// Подпись автора над EventRelation::signing_message()
+    signature: String,
+}
+
+/// POST /api/v1/event_relations — подписанная связь между событиями.
+/// duplicate_of и supersedes может ставить только автор события-источника или администратор.
+#[post("/api/v1/event_relations")]
+async fn api_v1_event_relations_post(req: HttpRequest, pool: web::Data<DbPool>, body: web::Json<EventRelationRequest>) -> impl Responder {
+    use core_lib::event_relation::Applied;
+    let claims = match require_role(req, "node").await { Ok(c) => c, Err(resp) => return resp };
+    let r = body.into_inner();
+    let mut rel = core_lib::models::EventRelation::new(&r.source_uid, &r.target_uid, &r.relation, &claims.sub, r.created_at);
+    rel.note = r.note;
+    rel.signature = r.signature;
+    if let Err(e) = core_lib::event_relation::verify(&rel) {
+        return HttpResponse::BadRequest().json(serde_json::json!({"error": e.to_string()}));
+    }
+    let id = rel.id.clone();
     let poolc = pool.clone();
     let res = web::block(move || {
-        let conn = poolc.blocking_lock();
-        // применяем дельту
-        let _new = core_lib::storage::adjust_trust_score(&conn, &target_pubkey, delta)?;
-        // синхронизация с node_ratings произойдет при следующем пересчете; можно инициировать мягкий апдейт users уже сейчас
-        Ok::<(), core_lib::models::CoreError>(())
+        let conn = poolc.write()?;
+        core_lib::audit::with_actor(&rel.author, || core_lib::event_relation::apply(&conn, &rel))
     }).await;
-    match res { Ok(Ok(())) => HttpResponse::Ok().json(serde_json::json!({"status":"ok"})), _ => HttpResponse::InternalServerError().finish() }
+    match res {
+        Ok(Ok(Applied::Created)) => HttpResponse::Created().json(serde_json::json!({"status":"created","id":id})),
+        Ok(Ok(Applied::Known)) => HttpResponse::Ok().json(serde_json::json!({"status":"known","id":id})),
+        Ok(Ok(Applied::MissingTarget | Applied::Deleted)) => HttpResponse::NotFound().json(serde_json::json!({"error":"event not found"})),
+        Ok(Ok(Applied::Forbidden)) => HttpResponse::Forbidden().json(serde_json::json!({"error":"only the source event author or an admin may set this relation"})),
+        Ok(Err(core_lib::models::CoreError::InvalidArg(m))) => HttpResponse::BadRequest().json(serde_json::json!({"error": m})),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct EventRelationQuery {
+    /// uid события: связи, где оно source или target
+    event: Option<String>,
+    since: Option<i64>,
+}
+
+/// GET /api/v1/event_relations — связи события (?event=uid, старые первыми) или все (новые первыми)
+#[get("/api/v1/event_relations")]
+async fn api_v1_event_relations_get(pool: web::Data<DbPool>, query: web::Query<EventRelationQuery>) -> impl Responder {
+    let EventRelationQuery { event, since } = query.into_inner();
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.read()?;
+        match event {
+            Some(uid) => core_lib::storage::relations_for_event(&conn, &uid),
+            None => core_lib::storage::load_event_relations(&conn, since),
+        }
+    }).await;
+    match res {
+        Ok(Ok(list)) => HttpResponse::Ok().json(list),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// Наибольший размер блоба, принимаемого через API
+const MAX_BLOB_BYTES: usize = 32 * 1024 * 1024;
+
+/// PUT /api/v1/evidence/blobs/{sha256} — загрузить содержимое доказательства; хеш тела должен совпасть с путём
+#[put("/api/v1/evidence/blobs/{sha256}")]
+async fn api_v1_evidence_blob_put(
+    req: HttpRequest,
+    blobs: web::Data<core_lib::evidence::BlobStore>,
+    path: web::Path<String>,
+    payload: web::Payload,
+) -> impl Responder {
+    if let Err(resp) = require_role(req, "node").await {
+        return resp;
+    }
+    let sha = path.into_inner();
+    let bytes = match payload.to_bytes_limited(MAX_BLOB_BYTES).await {
+        Ok(Ok(b)) => b,
+        Ok(Err(e)) => return HttpResponse::BadRequest().json(serde_json::json!({"error": e.to_string()})),
+        Err(_) => return HttpResponse::PayloadTooLarge().json(serde_json::json!({"error": format!("blob exceeds {} bytes", MAX_BLOB_BYTES)})),
+    };
+    let res = web::block(move || blobs.put_expected(&sha, &bytes).map(|_| sha)).await;
+    match res {
+        Ok(Ok(sha)) => HttpResponse::Created().json(serde_json::json!({"status":"stored","sha256":sha})),
+        Ok(Err(core_lib::models::CoreError::InvalidArg(m))) => HttpResponse::BadRequest().json(serde_json::json!({"error": m})),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// GET /api/v1/evidence/blobs/{sha256} — содержимое доказательства; пиры забирают блобы по запросу
+#[get("/api/v1/evidence/blobs/{sha256}")]
+async fn api_v1_evidence_blob_get(blobs: web::Data<core_lib::evidence::BlobStore>, path: web::Path<String>) -> impl Responder {
+    let sha = path.into_inner();
+    let res = web::block(move || blobs.get(&sha)).await;
+    match res {
+        Ok(Ok(Some(bytes))) => HttpResponse::Ok().content_type("application/octet-stream").body(bytes),
+        Ok(Ok(None)) => HttpResponse::NotFound().json(serde_json::json!({"error":"blob not found"})),
+        Ok(Err(core_lib::models::CoreError::InvalidArg(m))) => HttpResponse::BadRequest().json(serde_json::json!({"error": m})),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct KbQuery {
+    include_deprecated: Option<bool>,
+}
+
+/// GET /api/v1/kb/{kind} — записи базы знаний вида kind (category, cause, develop, effect, forma, context, impact_type)
+#[get("/api/v1/kb/{kind}")]
+async fn api_v1_kb_list(pool: web::Data<DbPool>, path: web::Path<String>, query: web::Query<KbQuery>) -> impl Responder {
+    let kind = path.into_inner();
+    let include_deprecated = query.include_deprecated.unwrap_or(false);
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.read()?;
+        core_lib::storage::load_kb_entries(&conn, &kind, include_deprecated)
+    }).await;
+    match res {
+        Ok(Ok(list)) => HttpResponse::Ok().json(list),
+        Ok(Err(core_lib::models::CoreError::InvalidArg(m))) => HttpResponse::BadRequest().json(serde_json::json!({"error": m})),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// POST /api/v1/kb — создать, изменить или пометить устаревшей запись базы знаний.
+/// Тело — KbEntry, подписанная администратором (updated_by берётся из токена); правка распространяется пирам.
+#[post("/api/v1/kb")]
+async fn api_v1_kb_post(req: HttpRequest, pool: web::Data<DbPool>, body: web::Json<core_lib::models::KbEntry>) -> impl Responder {
+    use core_lib::knowledge::Applied;
+    let claims = match require_role(req, "admin").await { Ok(c) => c, Err(resp) => return resp };
+    let mut entry = body.into_inner();
+    entry.updated_by = Some(claims.sub.clone());
+    if let Err(e) = core_lib::knowledge::verify(&entry) {
+        return HttpResponse::Unauthorized().body(format!("Invalid signature: {}", e));
+    }
+    let (kind, id) = (entry.kind.clone(), entry.id);
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.write()?;
+        core_lib::audit::with_actor(&claims.sub, || core_lib::knowledge::apply(&conn, &entry))
+    }).await;
+    match res {
+        Ok(Ok(Applied::Created)) => HttpResponse::Created().json(serde_json::json!({"status":"created","kind":kind,"id":id})),
+        Ok(Ok(Applied::Updated)) => HttpResponse::Ok().json(serde_json::json!({"status":"updated","kind":kind,"id":id})),
+        Ok(Ok(Applied::Stale)) => HttpResponse::Conflict().json(serde_json::json!({"error":"a newer revision exists","code":409})),
+        Ok(Ok(Applied::Forbidden)) => HttpResponse::Forbidden().json(serde_json::json!({"error":"forbidden","code":403})),
+        Ok(Err(core_lib::models::CoreError::InvalidArg(m))) => HttpResponse::BadRequest().json(serde_json::json!({"error": m})),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct AlertsQuery {
+    kind: Option<String>,
+    node: Option<String>,
+    all: Option<bool>,
+}
+
+/// GET /api/v1/alerts — найденные аномалии доверия (по умолчанию только неподтверждённые)
+#[get("/api/v1/alerts")]
+async fn api_v1_alerts(pool: web::Data<DbPool>, query: web::Query<AlertsQuery>) -> impl Responder {
+    let AlertsQuery { kind, node, all } = query.into_inner();
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.read()?;
+        let mut list = core_lib::storage::load_alerts(&conn, all.unwrap_or(false), kind.as_deref())?;
+        if let Some(n) = node.as_deref() {
+            list.retain(|a| a.subject == n || a.related.iter().any(|r| r == n));
+        }
+        Ok::<_, core_lib::models::CoreError>(list)
+    }).await;
+    match res {
+        Ok(Ok(list)) => HttpResponse::Ok().json(list),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// POST /api/v1/alerts/scan — внеочередной прогон детекторов, возвращает новые аномалии
+#[post("/api/v1/alerts/scan")]
+async fn api_v1_alerts_scan(req: HttpRequest, pool: web::Data<DbPool>) -> impl Responder {
+    if let Err(resp) = require_role(req, "node").await { return resp; }
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.write()?;
+        core_lib::storage::detect_anomalies(&conn, chrono::Utc::now().timestamp())
+    }).await;
+    match res {
+        Ok(Ok(added)) => HttpResponse::Ok().json(added),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// POST /api/v1/alerts/{id}/ack — пометить аномалию как просмотренную (admin)
+#[post("/api/v1/alerts/{id}/ack")]
+async fn api_v1_alerts_ack(req: HttpRequest, pool: web::Data<DbPool>, path: web::Path<String>) -> impl Responder {
+    if let Err(resp) = require_role(req, "admin").await { return resp; }
+    let id = path.into_inner();
+    let poolc = pool.clone();
+    let idc = id.clone();
+    let res = web::block(move || {
+        let conn = poolc.write()?;
+        core_lib::storage::acknowledge_alert(&conn, &idc, chrono::Utc::now().timestamp())
+    }).await;
+    match res {
+        Ok(Ok(())) => HttpResponse::Ok().json(serde_json::json!({"status":"acknowledged","id":id})),
+        Ok(Err(core_lib::models::CoreError::NotFound(_))) => HttpResponse::NotFound().json(serde_json::json!({"error":"alert not found"})),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// Максимум результатов поиска за один запрос
+const SEARCH_MAX_LIMIT: usize = 200;
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+    context: Option<i64>,
+    since: Option<i64>,
+    until: Option<i64>,
+    author: Option<String>,
+    detected: Option<bool>,
+    limit: Option<usize>,
+}
+
+/// GET /api/v1/search?q= — полнотекстовый поиск по событиям, утверждениям и обоснованиям оценок
+#[get("/api/v1/search")]
+async fn api_v1_search(pool: web::Data<DbPool>, query: web::Query<SearchQuery>) -> impl Responder {
+    let SearchQuery { q, context, since, until, author, detected, limit } = query.into_inner();
+    let filters = core_lib::storage::SearchFilters { context_id: context, since, until, author, detected };
+    let limit = limit.unwrap_or(20).clamp(1, SEARCH_MAX_LIMIT);
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.read()?;
+        core_lib::storage::search(&conn, &q, &filters, limit)
+    }).await;
+    match res {
+        Ok(Ok(hits)) => HttpResponse::Ok().json(hits),
+        Ok(Err(core_lib::models::CoreError::InvalidArg(m))) => HttpResponse::BadRequest().json(serde_json::json!({"error": m})),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
 }
 
 #[derive(Deserialize)]
@@ -1011,7 +1678,7 @@ async fn api_v1_refresh(pool: web::Data<DbPool>, body: web::Json<RefreshRequest>
     let refresh_lookup = refresh.clone();
     let pool1 = pool.clone();
     let res = web::block(move || {
-        let conn = pool1.blocking_lock();
+        let conn = pool1.read()?;
         core_lib::storage::find_session_by_refresh(&conn, &refresh_lookup)
     }).await;
     let Ok(Ok(opt)) = res else { return HttpResponse::InternalServerError().finish(); };
@@ -1022,7 +1689,7 @@ async fn api_v1_refresh(pool: web::Data<DbPool>, body: web::Json<RefreshRequest>
         // expired
         let pool_del = pool.clone();
         let _ = web::block(move || {
-            let conn = pool_del.blocking_lock();
+            let conn = pool_del.write()?;
             core_lib::storage::delete_refresh_token(&conn, &refresh)
         }).await;
         return HttpResponse::Unauthorized().json(serde_json::json!({"error":"unauthorized","code":401}));
@@ -1032,7 +1699,7 @@ async fn api_v1_refresh(pool: web::Data<DbPool>, body: web::Json<RefreshRequest>
         let pool = pool.clone();
         let public_key = public_key.clone();
         move || {
-            let conn = pool.blocking_lock();
+            let conn = pool.read()?;
             issue_jwt_pair_with(&conn, &public_key)
         }
     }).await {
@@ -1044,7 +1711,7 @@ async fn api_v1_refresh(pool: web::Data<DbPool>, body: web::Json<RefreshRequest>
     let refresh_old = refresh.clone();
     let public_key2 = public_key.clone();
     let res2 = web::block(move || {
-        let conn = pool2.blocking_lock();
+        let conn = pool2.write()?;
         core_lib::storage::delete_refresh_token(&conn, &refresh_old)?;
         core_lib::storage::register_refresh_token(&conn, &public_key2, &new_refresh_for_closure, exp_refresh)
     }).await;
@@ -1130,7 +1797,7 @@ async fn recalc_ratings(req: HttpRequest, pool: web::Data<DbPool>) -> impl Respo
     if let Err(resp) = require_jwt(req).await.map(|_| ()) { return resp; }
     let pool = pool.clone();
     let result = web::block(move || {
-        let _conn = pool.blocking_lock();
+        let _conn = pool.write()?;
         core_lib::storage::recalc_ratings(&_conn, chrono::Utc::now().timestamp())
     })
     .await;
@@ -1147,10 +1814,10 @@ async fn recalc_ratings(req: HttpRequest, pool: web::Data<DbPool>) -> impl Respo
 async fn get_node_ratings(pool: web::Data<DbPool>) -> impl Responder {
     let pool = pool.clone();
     let result = web::block(move || {
-        let conn = pool.blocking_lock();
-        let mut ratings = core_lib::storage::load_node_ratings(&conn)?;
+        let mut ratings = core_lib::storage::load_node_ratings(&*pool.read()?)?;
         if ratings.is_empty() {
             // lazy recalc if empty
+            let conn = pool.write()?;
             let _ = core_lib::storage::recalc_ratings(&conn, chrono::Utc::now().timestamp());
             ratings = core_lib::storage::load_node_ratings(&conn)?;
         }
@@ -1165,14 +1832,51 @@ async fn get_node_ratings(pool: web::Data<DbPool>) -> impl Responder {
     }
 }
 
+#[derive(Deserialize)]
+struct TrustHistoryQuery {
+    limit: Option<usize>,
+    window: Option<usize>,
+}
+
+/// GET /ratings/nodes/{id}/history — временной ряд trust_score узла и его стабильность
+#[get("/ratings/nodes/{id}/history")]
+async fn get_node_trust_history(
+    pool: web::Data<DbPool>,
+    path: web::Path<String>,
+    query: web::Query<TrustHistoryQuery>,
+) -> impl Responder {
+    let pool = pool.clone();
+    let node_id = path.into_inner();
+    let TrustHistoryQuery { limit, window } = query.into_inner();
+    let window = window.unwrap_or(core_lib::trust_propagation::TRUST_STABILITY_WINDOW).max(1);
+    let result = web::block(move || {
+        let conn = pool.read()?;
+        let history = core_lib::storage::load_trust_history(&conn, &node_id, limit)?;
+        let stability = core_lib::storage::trust_score_stability(&conn, &node_id, window)?;
+        Ok::<_, core_lib::models::CoreError>(serde_json::json!({
+            "node_id": node_id,
+            "window": window,
+            "stability": stability,
+            "history": history,
+        }))
+    })
+    .await;
+
+    match result {
+        Ok(Ok(v)) => HttpResponse::Ok().json(v),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
 /// GET /ratings/groups
 #[get("/ratings/groups")]
 async fn get_group_ratings(pool: web::Data<DbPool>) -> impl Responder {
     let pool = pool.clone();
     let result = web::block(move || {
-        let conn = pool.blocking_lock();
-        let mut ratings = core_lib::storage::load_group_ratings(&conn)?;
+        let mut ratings = core_lib::storage::load_group_ratings(&*pool.read()?)?;
         if ratings.is_empty() {
+            let conn = pool.write()?;
             let _ = core_lib::storage::recalc_ratings(&conn, chrono::Utc::now().timestamp());
             ratings = core_lib::storage::load_group_ratings(&conn)?;
         }
@@ -1187,12 +1891,42 @@ async fn get_group_ratings(pool: web::Data<DbPool>) -> impl Responder {
     }
 }
 
+/// GET /ratings/groups/{id} — группа и рейтинги её участников
+#[get("/ratings/groups/{id}")]
+async fn get_group_rating(pool: web::Data<DbPool>, path: web::Path<String>) -> impl Responder {
+    let pool = pool.clone();
+    let group_id = path.into_inner();
+    let result = web::block(move || {
+        let conn = pool.read()?;
+        let group = match core_lib::storage::get_group_rating(&conn, &group_id)? {
+            Some(g) => g,
+            None => return Ok(None),
+        };
+        let members: Vec<core_lib::models::NodeRating> = core_lib::storage::load_node_ratings(&conn)?
+            .into_iter()
+            .filter(|n| group.members.contains(&n.node_id))
+            .collect();
+        Ok::<_, core_lib::models::CoreError>(Some(serde_json::json!({
+            "group": group,
+            "members": members,
+        })))
+    })
+    .await;
+
+    match result {
+        Ok(Ok(Some(v))) => HttpResponse::Ok().json(v),
+        Ok(Ok(None)) => HttpResponse::NotFound().json(serde_json::json!({"error": "group not found"})),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
 /// GET /graph - данные графа доверия
 #[get("/graph")]
 async fn get_graph(pool: web::Data<DbPool>) -> impl Responder {
     let pool = pool.clone();
     let result = web::block(move || {
-        let _conn = pool.blocking_lock();
+        let _conn = pool.read()?;
         core_lib::storage::load_graph(&_conn)
     })
     .await;
@@ -1211,13 +1945,14 @@ struct GraphQuery {
     depth: Option<usize>,
     min_priority: Option<f32>,
     limit: Option<usize>,
+    group: Option<String>,
 }
 
 /// GET /graph/json — полный граф в JSON с параметрами фильтра
 #[get("/graph/json")]
 async fn get_graph_json(pool: web::Data<DbPool>, query: web::Query<GraphQuery>) -> impl Responder {
     let pool = pool.clone();
-    let GraphQuery { min_score, max_links, depth, min_priority, limit } = query.into_inner();
+    let GraphQuery { min_score, max_links, depth, min_priority, limit, group } = query.into_inner();
 
     // Значения по умолчанию
     let min_score = min_score.unwrap_or(-1.0);
@@ -1226,11 +1961,16 @@ async fn get_graph_json(pool: web::Data<DbPool>, query: web::Query<GraphQuery>)
     let limit = limit.unwrap_or(50);
 
     let result = web::block(move || {
-        let _conn = pool.blocking_lock();
+        let _conn = pool.write()?;
         // ensure ratings up-to-date for graph queries
         let _ = core_lib::storage::recalc_ratings(&_conn, chrono::Utc::now().timestamp());
         let mut graph = core_lib::storage::load_graph_filtered(&_conn, min_score, max_links, depth)?;
         
+        // Фильтр по группе (сообществу)
+        if let Some(g) = group.as_deref() {
+            retain_group(&mut graph, g);
+        }
+        
         // Фильтр по propagation_priority
         if min_priority > 0.0 {
             graph.nodes.retain(|node| node.propagation_priority >= min_priority);
@@ -1255,18 +1995,51 @@ async fn get_graph_json(pool: web::Data<DbPool>, query: web::Query<GraphQuery>)
     }
 }
 
+#[derive(Deserialize)]
+struct EventGraphQuery {
+    /// uid события: только его окрестность глубиной depth
+    event: Option<String>,
+    depth: Option<usize>,
+}
+
+/// GET /graph/events — граф событий по их связям (duplicate_of, contradicts, supports, supersedes)
+#[get("/graph/events")]
+async fn get_graph_events(pool: web::Data<DbPool>, query: web::Query<EventGraphQuery>) -> impl Responder {
+    let EventGraphQuery { event, depth } = query.into_inner();
+    let poolc = pool.clone();
+    let res = web::block(move || {
+        let conn = poolc.read()?;
+        core_lib::event_relation::event_graph(&conn, event.as_deref(), depth.unwrap_or(2))
+    }).await;
+    match res {
+        Ok(Ok(graph)) => HttpResponse::Ok().json(graph),
+        Ok(Err(e)) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// Оставить в графе только узлы группы и рёбра между ними
+fn retain_group(graph: &mut GraphData, group_id: &str) {
+    graph.nodes.retain(|n| n.group_id.as_deref() == Some(group_id));
+    let node_ids: std::collections::HashSet<String> = graph.nodes.iter().map(|n| n.id.clone()).collect();
+    graph.links.retain(|link| node_ids.contains(&link.source) && node_ids.contains(&link.target));
+}
+
 /// GET /graph/summary — агрегированные метрики по графу
 #[get("/graph/summary")]
 async fn get_graph_summary(pool: web::Data<DbPool>, query: web::Query<GraphQuery>) -> impl Responder {
     let pool = pool.clone();
-    let GraphQuery { min_score, max_links, depth, .. } = query.into_inner();
+    let GraphQuery { min_score, max_links, depth, group, .. } = query.into_inner();
 
     let min_score = min_score.unwrap_or(-1.0);
     let max_links = max_links.unwrap_or(10).max(0);
 
     let result = web::block(move || {
-        let _conn = pool.blocking_lock();
-        let graph: GraphData = core_lib::storage::load_graph_filtered(&_conn, min_score, max_links, depth)?;
+        let _conn = pool.read()?;
+        let mut graph: GraphData = core_lib::storage::load_graph_filtered(&_conn, min_score, max_links, depth)?;
+        if let Some(g) = group.as_deref() {
+            retain_group(&mut graph, g);
+        }
         let summary: GraphSummary = core_lib::models::summarize_graph(&graph);
         Ok::<GraphSummary, core_lib::models::CoreError>(summary)
     })
@@ -1285,8 +2058,6 @@ mod tests {
     use crate::p2p::encryption::CryptoIdentity;
     // use hex; // not needed here
     use actix_web::{test, App};
-    use std::sync::Arc;
-    use tokio::sync::Mutex;
 
     #[actix_web::test]
     async fn test_signature_verification_refactor() {
@@ -1318,16 +2089,15 @@ mod tests {
     #[actix_web::test]
     async fn ratings_endpoints_work() {
         // Prepare in-memory DB and app
-        let conn = core_lib::storage::open_db(":memory:").unwrap();
-        let conn_data = Arc::new(Mutex::new(conn));
+        let pool = crate::db::DbPool::memory().unwrap();
         {
-            let mut c = conn_data.lock().await;
+            let mut c = pool.write().unwrap();
             core_lib::storage::seed_knowledge_base(&mut c, "en").unwrap();
         }
 
         let app = test::init_service(
             App::new()
-                .app_data(actix_web::web::Data::new(conn_data.clone()))
+                .app_data(actix_web::web::Data::new(pool.clone()))
                 .configure(crate::api::routes)
         ).await;
 
@@ -1343,7 +2113,7 @@ mod tests {
 
         // set author public key on event
         {
-            let c = conn_data.lock().await;
+            let c = pool.write().unwrap();
             c.execute("UPDATE truth_events SET public_key='nodeA' WHERE id=?1", rusqlite::params![ev_id]).unwrap();
         }
 
@@ -1358,7 +2128,7 @@ mod tests {
 
         // direct DB insert impact to set validator pubkey
         {
-            let c = conn_data.lock().await;
+            let c = pool.write().unwrap();
             let impact_id = core_lib::storage::add_impact(&c, ev_id, 1, true, Some("ok".into())).unwrap();
             c.execute("UPDATE impact SET public_key='nodeB' WHERE id=?1", rusqlite::params![impact_id]).unwrap();
         }
@@ -1376,24 +2146,48 @@ mod tests {
         let groups: serde_json::Value = test::call_and_read_body_json(&app, req).await;
         assert!(!groups.as_array().unwrap().is_empty());
 
+        let group_id = groups[0].get("group_id").and_then(|v| v.as_str()).unwrap().to_string();
+        let req = test::TestRequest::get().uri(&format!("/ratings/groups/{}", group_id)).to_request();
+        let group: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert!(group.get("members").unwrap().as_array().unwrap().iter().any(|m| m.get("node_id").and_then(|v| v.as_str()) == Some("nodeA")));
+
+        let req = test::TestRequest::get().uri("/ratings/nodes/nodeA/history?window=5").to_request();
+        let hist: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(hist.get("window").and_then(|v| v.as_u64()), Some(5));
+        assert!(!hist.get("history").unwrap().as_array().unwrap().is_empty());
+
         let req = test::TestRequest::get().uri("/graph").to_request();
         let graph: serde_json::Value = test::call_and_read_body_json(&app, req).await;
         assert!(!graph.get("nodes").unwrap().as_array().unwrap().is_empty());
+
+        // Статистика считается в SQL и совпадает с полными выборками
+        let req = test::TestRequest::get().uri("/api/v1/stats").to_request();
+        let stats: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        {
+            let c = pool.read().unwrap();
+            let nodes = core_lib::storage::load_node_ratings(&c).unwrap();
+            assert_eq!(stats["events"], 1);
+            assert_eq!(stats["statements"], 1);
+            assert_eq!(stats["impacts"], 1);
+            assert_eq!(stats["node_ratings"].as_u64(), Some(nodes.len() as u64));
+            assert_eq!(stats["group_ratings"].as_u64(), Some(core_lib::storage::load_group_ratings(&c).unwrap().len() as u64));
+            let avg = nodes.iter().map(|r| r.trust_score as f64).sum::<f64>() / nodes.len() as f64;
+            assert!((stats["avg_trust_score"].as_f64().unwrap() - avg).abs() < 1e-5);
+        }
     }
 
     #[actix_web::test]
     async fn graph_json_filters_work() {
         // Prepare in-memory DB and app
-        let conn = core_lib::storage::open_db(":memory:").unwrap();
-        let conn_data = Arc::new(Mutex::new(conn));
+        let pool = crate::db::DbPool::memory().unwrap();
         {
-            let mut c = conn_data.lock().await;
+            let mut c = pool.write().unwrap();
             core_lib::storage::seed_knowledge_base(&mut c, "en").unwrap();
         }
 
         let app = test::init_service(
             App::new()
-                .app_data(actix_web::web::Data::new(conn_data.clone()))
+                .app_data(actix_web::web::Data::new(pool.clone()))
                 .configure(crate::api::routes)
         ).await;
 
@@ -1407,7 +2201,7 @@ mod tests {
         let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
         let ev1 = resp.get("id").and_then(|v| v.as_i64()).unwrap();
         {
-            let c = conn_data.lock().await;
+            let c = pool.write().unwrap();
             c.execute("UPDATE truth_events SET public_key='nodeA' WHERE id=?1", rusqlite::params![ev1]).unwrap();
         }
         let add_stmt_req = serde_json::json!({
@@ -1419,7 +2213,7 @@ mod tests {
         let req = test::TestRequest::post().uri("/statements").set_json(&add_stmt_req).to_request();
         let _resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
         {
-            let c = conn_data.lock().await;
+            let c = pool.write().unwrap();
             let impact_id = core_lib::storage::add_impact(&c, ev1, 1, true, Some("ok".into())).unwrap();
             c.execute("UPDATE impact SET public_key='nodeB' WHERE id=?1", rusqlite::params![impact_id]).unwrap();
         }
@@ -1434,7 +2228,7 @@ mod tests {
         let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
         let ev2 = resp.get("id").and_then(|v| v.as_i64()).unwrap();
         {
-            let c = conn_data.lock().await;
+            let c = pool.write().unwrap();
             c.execute("UPDATE truth_events SET public_key='nodeC' WHERE id=?1", rusqlite::params![ev2]).unwrap();
         }
         let add_stmt_req = serde_json::json!({
@@ -1473,16 +2267,15 @@ mod tests {
     #[actix_web::test]
     async fn graph_summary_consistent() {
         // Prepare in-memory DB and app
-        let conn = core_lib::storage::open_db(":memory:").unwrap();
-        let conn_data = Arc::new(Mutex::new(conn));
+        let pool = crate::db::DbPool::memory().unwrap();
         {
-            let mut c = conn_data.lock().await;
+            let mut c = pool.write().unwrap();
             core_lib::storage::seed_knowledge_base(&mut c, "en").unwrap();
         }
 
         let app = test::init_service(
             App::new()
-                .app_data(actix_web::web::Data::new(conn_data.clone()))
+                .app_data(actix_web::web::Data::new(pool.clone()))
                 .configure(crate::api::routes)
         ).await;
 
@@ -1492,14 +2285,14 @@ mod tests {
         let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
         let ev1 = resp.get("id").and_then(|v| v.as_i64()).unwrap();
         {
-            let c = conn_data.lock().await;
+            let c = pool.write().unwrap();
             c.execute("UPDATE truth_events SET public_key='nodeA' WHERE id=?1", rusqlite::params![ev1]).unwrap();
         }
         let add_stmt_req = serde_json::json!({"event_id":ev1,"text":"t","context":null,"truth_score":0.7});
         let req = test::TestRequest::post().uri("/statements").set_json(&add_stmt_req).to_request();
         let _resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
         {
-            let c = conn_data.lock().await;
+            let c = pool.write().unwrap();
             let impact_id = core_lib::storage::add_impact(&c, ev1, 1, true, None).unwrap();
             c.execute("UPDATE impact SET public_key='nodeB' WHERE id=?1", rusqlite::params![impact_id]).unwrap();
         }
@@ -1522,19 +2315,540 @@ mod tests {
         assert_eq!(summary.top_nodes.len(), std::cmp::min(10, graph.nodes.len()));
     }
 
+    #[actix_web::test]
+    async fn trust_delegation_signed_list_and_revoke() {
+        let pool = crate::db::DbPool::memory().unwrap();
+        let mut conn = pool.write().unwrap();
+        core_lib::storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let identity = CryptoIdentity::new();
+        let delegator = identity.public_key_hex();
+        core_lib::storage::update_user_role(&conn, &delegator, "node").unwrap();
+        // Делегирования учитываются только от узлов с доверием: событие делегирующего подтверждено
+        let ev = core_lib::storage::add_truth_event(&conn, core_lib::models::NewTruthEvent {
+            description: "verified".into(), context_id: 1, vector: true, timestamp_start: 1, code: 1,
+        }).unwrap();
+        conn.execute("UPDATE truth_events SET public_key = ?1 WHERE id = ?2", rusqlite::params![delegator, ev]).unwrap();
+        conn.execute("INSERT INTO statements (uid, event_id, text, truth_score, created_at, updated_at) VALUES ('st-verified', ?1, 'confirmed', 1.0, 1, 1)", [ev]).unwrap();
+        let (access, _refresh, _exp) = issue_jwt_pair_with(&conn, &delegator).unwrap();
+        drop(conn);
+        let app = test::init_service(
+            App::new()
+                .app_data(actix_web::web::Data::new(pool.clone()))
+                .configure(crate::api::routes)
+        ).await;
+        let auth = format!("Bearer {}", access);
+        let created_at = chrono::Utc::now().timestamp();
+        let d = core_lib::models::TrustDelegation::new(&delegator, "nodeT", 0.1, created_at, None);
+
+        // Неверная подпись отклоняется
+        let req = test::TestRequest::post().uri("/api/v1/trust/delegate")
+            .insert_header(("Authorization", auth.clone()))
+            .set_json(serde_json::json!({"target_pubkey":"nodeT","delta":0.1,"created_at":created_at,"expires_at":null,"signature":"00"}))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+
+        let sig = hex::encode(identity.sign(d.signing_message().as_bytes()).to_bytes());
+        let req = test::TestRequest::post().uri("/api/v1/trust/delegate")
+            .insert_header(("Authorization", auth.clone()))
+            .set_json(serde_json::json!({"target_pubkey":"nodeT","delta":0.1,"created_at":created_at,"expires_at":null,"signature":sig}))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.get("id").and_then(|v| v.as_str()), Some(d.id.as_str()));
+
+        // Делегирование применено при пересчёте
+        {
+            let c = pool.write().unwrap();
+            let t = core_lib::storage::load_node_ratings(&c).unwrap().into_iter().find(|r| r.node_id == "nodeT").unwrap();
+            assert!((t.trust_score - 0.1).abs() < 1e-6);
+        }
+
+        let req = test::TestRequest::get().uri("/api/v1/trust/delegations?target=nodeT").to_request();
+        let list: Vec<core_lib::models::TrustDelegation> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(list.len(), 1);
+
+        // Отзыв с подписью автора
+        let revoked_at = created_at + 1;
+        let rsig = hex::encode(identity.sign(core_lib::models::TrustDelegation::revocation_message(&d.id, revoked_at).as_bytes()).to_bytes());
+        let req = test::TestRequest::post().uri("/api/v1/trust/revoke")
+            .insert_header(("Authorization", auth.clone()))
+            .set_json(serde_json::json!({"id": d.id, "revoked_at": revoked_at, "signature": rsig}))
+            .to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+
+        let req = test::TestRequest::get().uri("/api/v1/trust/delegations").to_request();
+        let list: Vec<core_lib::models::TrustDelegation> = test::call_and_read_body_json(&app, req).await;
+        assert!(list.is_empty());
+        let req = test::TestRequest::get().uri("/api/v1/trust/delegations?all=true").to_request();
+        let list: Vec<core_lib::models::TrustDelegation> = test::call_and_read_body_json(&app, req).await;
+        assert!(list[0].revoked_at.is_some());
+        assert!(crate::p2p::sync::verify_delegation(&list[0]).is_ok());
+    }
+
+    #[actix_web::test]
+    async fn tombstones_delete_own_records_only() {
+        let pool = crate::db::DbPool::memory().unwrap();
+        let mut conn = pool.write().unwrap();
+        core_lib::storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let (author, other) = (CryptoIdentity::new(), CryptoIdentity::new());
+        let mut tokens = Vec::new();
+        for id in [&author, &other] {
+            core_lib::storage::update_user_role(&conn, &id.public_key_hex(), "node").unwrap();
+            tokens.push(format!("Bearer {}", issue_jwt_pair_with(&conn, &id.public_key_hex()).unwrap().0));
+        }
+        let ev = core_lib::storage::add_truth_event(&conn, core_lib::models::NewTruthEvent {
+            description: "to delete".into(), context_id: 1, vector: true, timestamp_start: 1, code: 1,
+        }).unwrap();
+        conn.execute("UPDATE truth_events SET public_key=?2 WHERE id=?1", rusqlite::params![ev, author.public_key_hex()]).unwrap();
+        let uid = core_lib::storage::get_truth_event(&conn, ev).unwrap().unwrap().uid;
+        drop(conn);
+        let app = test::init_service(
+            App::new()
+                .app_data(actix_web::web::Data::new(pool.clone()))
+                .configure(crate::api::routes)
+        ).await;
+        let deleted_at = chrono::Utc::now().timestamp();
+        let body = |id: &CryptoIdentity| {
+            let t = core_lib::models::Tombstone::new("truth_events", &uid, &id.public_key_hex(), None, deleted_at);
+            let sig = hex::encode(id.sign(t.signing_message().as_bytes()).to_bytes());
+            serde_json::json!({"table":"truth_events","record_id":uid,"reason":null,"deleted_at":deleted_at,"signature":sig})
+        };
+
+        // Чужую запись удалить нельзя
+        let req = test::TestRequest::post().uri("/api/v1/tombstones")
+            .insert_header(("Authorization", tokens[1].clone()))
+            .set_json(body(&other))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::FORBIDDEN);
+
+        // Подпись другим ключом
+        let req = test::TestRequest::post().uri("/api/v1/tombstones")
+            .insert_header(("Authorization", tokens[0].clone()))
+            .set_json(body(&other))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::post().uri("/api/v1/tombstones")
+            .insert_header(("Authorization", tokens[0].clone()))
+            .set_json(body(&author))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["status"], "deleted");
+        assert!(core_lib::storage::get_truth_event(&pool.read().unwrap(), ev).unwrap().is_none());
+
+        let req = test::TestRequest::get().uri("/api/v1/tombstones?table=truth_events").to_request();
+        let list: Vec<core_lib::models::Tombstone> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(list.len(), 1);
+        assert!(core_lib::tombstone::verify(&list[0]).is_ok());
+    }
+
+    #[actix_web::test]
+    async fn statement_replies_form_argument_tree() {
+        let pool = crate::db::DbPool::memory().unwrap();
+        let mut conn = pool.write().unwrap();
+        core_lib::storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let ev = core_lib::storage::add_truth_event(&conn, core_lib::models::NewTruthEvent {
+            description: "flood".into(), context_id: 1, vector: true, timestamp_start: 1, code: 1,
+        }).unwrap();
+        let uid = core_lib::storage::get_truth_event(&conn, ev).unwrap().unwrap().uid;
+        drop(conn);
+        let app = test::init_service(App::new().app_data(actix_web::web::Data::new(pool.clone())).configure(crate::api::routes)).await;
+
+        let req = test::TestRequest::post().uri("/statements")
+            .set_json(serde_json::json!({"event_id": ev, "text": "the river rose", "truth_score": 0.6}))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let root = core_lib::storage::get_statement(&pool.read().unwrap(), body["id"].as_i64().unwrap()).unwrap().unwrap().uid;
+        let reply = |parent: &str, relation: &str| test::TestRequest::post().uri("/statements")
+            .set_json(serde_json::json!({"event_id": ev, "text": "gauge data", "truth_score": 1.0, "parent_uid": parent, "relation": relation}))
+            .to_request();
+        assert_eq!(test::call_service(&app, reply(&root, "rebuts")).await.status(), actix_web::http::StatusCode::OK);
+        assert_eq!(test::call_service(&app, reply(&root, "likes")).await.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(test::call_service(&app, reply("missing", "supports")).await.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get().uri(&format!("/api/v1/events/{}/arguments", uid)).to_request();
+        let tree: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(tree["roots"].as_array().unwrap().len(), 1);
+        assert_eq!(tree["roots"][0]["uid"], root.as_str());
+        assert_eq!(tree["roots"][0]["replies"][0]["relation"], "rebuts");
+        assert!(tree["score"].as_f64().unwrap() < -0.5);
+        let req = test::TestRequest::get().uri("/api/v1/events/missing/arguments").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn impacts_reject_value_disagreeing_with_magnitude() {
+        let pool = crate::db::DbPool::memory().unwrap();
+        let mut conn = pool.write().unwrap();
+        core_lib::storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let ev = core_lib::storage::add_truth_event(&conn, core_lib::models::NewTruthEvent {
+            description: "flood".into(), context_id: 1, vector: true, timestamp_start: 1, code: 1,
+        }).unwrap();
+        drop(conn);
+        let app = test::init_service(App::new().app_data(actix_web::web::Data::new(pool.clone())).configure(crate::api::routes)).await;
+
+        let impact = |value: bool, magnitude: f64| test::TestRequest::post().uri("/impacts")
+            .set_json(serde_json::json!({
+                "id": "", "event_id": ev.to_string(), "type_id": 1, "value": value, "notes": null,
+                "created_at": 0, "magnitude": magnitude, "confidence": 0.5
+            }))
+            .to_request();
+        assert_eq!(test::call_service(&app, impact(true, -0.8)).await.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(test::call_service(&app, impact(false, 0.3)).await.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        assert_eq!(test::call_service(&app, impact(false, -0.8)).await.status(), actix_web::http::StatusCode::OK);
+        let stored = core_lib::storage::load_impacts(&pool.read().unwrap()).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!((stored[0].value, stored[0].magnitude), (false, Some(-0.8)));
+    }
+
+    #[actix_web::test]
+    async fn evidence_metadata_and_blobs() {
+        let pool = crate::db::DbPool::memory().unwrap();
+        let mut conn = pool.write().unwrap();
+        core_lib::storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let author = CryptoIdentity::new();
+        core_lib::storage::update_user_role(&conn, &author.public_key_hex(), "node").unwrap();
+        let token = format!("Bearer {}", issue_jwt_pair_with(&conn, &author.public_key_hex()).unwrap().0);
+        let ev = core_lib::storage::add_truth_event(&conn, core_lib::models::NewTruthEvent {
+            description: "outage".into(), context_id: 1, vector: true, timestamp_start: 1, code: 1,
+        }).unwrap();
+        let uid = core_lib::storage::get_truth_event(&conn, ev).unwrap().unwrap().uid;
+        drop(conn);
+        let dir = std::env::temp_dir().join(format!("truth_api_blobs_{}", std::process::id()));
+        let app = test::init_service(
+            App::new()
+                .app_data(actix_web::web::Data::new(pool.clone()))
+                .app_data(actix_web::web::Data::new(core_lib::evidence::BlobStore::new(&dir)))
+                .configure(crate::api::routes)
+        ).await;
+        let content = b"12:00 502 Bad Gateway".to_vec();
+        let sha = core_lib::evidence::sha256_hex(&content);
+
+        // Тело с другим хешем не принимается
+        let req = test::TestRequest::put().uri(&format!("/api/v1/evidence/blobs/{}", core_lib::evidence::sha256_hex(b"x")))
+            .insert_header(("Authorization", token.clone()))
+            .set_payload(content.clone())
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let req = test::TestRequest::put().uri(&format!("/api/v1/evidence/blobs/{}", sha))
+            .insert_header(("Authorization", token.clone()))
+            .set_payload(content.clone())
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::CREATED);
+
+        let created_at = chrono::Utc::now().timestamp();
+        let mut e = core_lib::models::Evidence::new("truth_events", &uid, "file", Some(sha.clone()), "nginx.log", &author.public_key_hex(), created_at);
+        e.size = Some(content.len() as i64);
+        let sig = hex::encode(author.sign(e.signing_message().as_bytes()).to_bytes());
+        let req = test::TestRequest::post().uri("/api/v1/evidence")
+            .insert_header(("Authorization", token.clone()))
+            .set_json(serde_json::json!({
+                "target_table": "truth_events", "target_uid": uid, "kind": "file", "sha256": sha,
+                "name": "nginx.log", "size": content.len(), "created_at": created_at, "signature": sig
+            }))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["status"], "created");
+        assert_eq!(resp["id"], e.id.as_str());
+
+        let req = test::TestRequest::get().uri(&format!("/api/v1/evidence?event={}", uid)).to_request();
+        let list: Vec<core_lib::models::Evidence> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(list.len(), 1);
+        assert!(core_lib::evidence::verify(&list[0]).is_ok());
+        let req = test::TestRequest::get().uri(&format!("/api/v1/evidence/blobs/{}", sha)).to_request();
+        assert_eq!(test::call_and_read_body(&app, req).await.to_vec(), content);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn event_relations_link_and_fold_events() {
+        let pool = crate::db::DbPool::memory().unwrap();
+        let mut conn = pool.write().unwrap();
+        core_lib::storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let author = CryptoIdentity::new();
+        let other = CryptoIdentity::new();
+        let mut tokens = Vec::new();
+        for id in [&author, &other] {
+            core_lib::storage::update_user_role(&conn, &id.public_key_hex(), "node").unwrap();
+            tokens.push(format!("Bearer {}", issue_jwt_pair_with(&conn, &id.public_key_hex()).unwrap().0));
+        }
+        let mut uids = Vec::new();
+        for description in ["bridge closed", "bridge shut", "bridge open"] {
+            let ev = core_lib::storage::add_truth_event(&conn, core_lib::models::NewTruthEvent {
+                description: description.into(), context_id: 1, vector: true, timestamp_start: 1, code: 1,
+            }).unwrap();
+            conn.execute("UPDATE truth_events SET public_key = ?1 WHERE id = ?2", rusqlite::params![author.public_key_hex(), ev]).unwrap();
+            uids.push(core_lib::storage::get_truth_event(&conn, ev).unwrap().unwrap().uid);
+        }
+        drop(conn);
+        let app = test::init_service(App::new().app_data(actix_web::web::Data::new(pool.clone())).configure(crate::api::routes)).await;
+        let post = |who: &CryptoIdentity, token: &str, source: &str, relation: &str, target: &str| {
+            let created_at = chrono::Utc::now().timestamp();
+            let rel = core_lib::models::EventRelation::new(source, target, relation, &who.public_key_hex(), created_at);
+            let sig = hex::encode(who.sign(rel.signing_message().as_bytes()).to_bytes());
+            test::TestRequest::post().uri("/api/v1/event_relations")
+                .insert_header(("Authorization", token.to_string()))
+                .set_json(serde_json::json!({
+                    "source_uid": source, "target_uid": target, "relation": relation, "created_at": created_at, "signature": sig
+                }))
+                .to_request()
+        };
+
+        // Чужое событие нельзя объявить дубликатом, а противоречие может отметить любой узел
+        let req = post(&other, &tokens[1], &uids[1], "duplicate_of", &uids[0]);
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::FORBIDDEN);
+        let req = post(&other, &tokens[1], &uids[2], "contradicts", &uids[0]);
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::CREATED);
+        let req = post(&author, &tokens[0], &uids[1], "duplicate_of", "missing");
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::NOT_FOUND);
+        let req = post(&author, &tokens[0], &uids[1], "same_as", &uids[0]);
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let req = post(&author, &tokens[0], &uids[1], "duplicate_of", &uids[0]);
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::CREATED);
+
+        let req = test::TestRequest::get().uri(&format!("/api/v1/events/{}", uids[1])).to_request();
+        let detail: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(detail["canonical_uid"], uids[0].as_str());
+        assert_eq!(detail["related"][0]["uid"], uids[0].as_str());
+        let req = test::TestRequest::get().uri(&format!("/api/v1/events/{}", uids[0])).to_request();
+        let detail: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(detail["event"]["description"], "bridge closed");
+        assert_eq!(detail["related"].as_array().unwrap().len(), 2);
+        let req = test::TestRequest::get().uri("/api/v1/events/missing").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get().uri(&format!("/api/v1/event_relations?event={}", uids[0])).to_request();
+        let list: Vec<core_lib::models::EventRelation> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(list.len(), 2);
+        assert!(list.iter().all(|r| core_lib::event_relation::verify(r).is_ok()));
+        let req = test::TestRequest::get().uri("/graph/events").to_request();
+        let graph: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(graph["nodes"].as_array().unwrap().len(), 3);
+        assert_eq!(graph["links"].as_array().unwrap().len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn kb_edits_admin_only_and_checked() {
+        use core_lib::models::KbEntry;
+        let pool = crate::db::DbPool::memory().unwrap();
+        let mut conn = pool.write().unwrap();
+        core_lib::storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let (admin, node) = (CryptoIdentity::new(), CryptoIdentity::new());
+        core_lib::storage::update_user_role(&conn, &admin.public_key_hex(), "admin").unwrap();
+        core_lib::storage::update_user_role(&conn, &node.public_key_hex(), "node").unwrap();
+        let admin_auth = format!("Bearer {}", issue_jwt_pair_with(&conn, &admin.public_key_hex()).unwrap().0);
+        let node_auth = format!("Bearer {}", issue_jwt_pair_with(&conn, &node.public_key_hex()).unwrap().0);
+        drop(conn);
+        let app = test::init_service(
+            App::new()
+                .app_data(actix_web::web::Data::new(pool.clone()))
+                .configure(crate::api::routes)
+        ).await;
+        let now = chrono::Utc::now().timestamp();
+        let signed = |id: &CryptoIdentity, mut e: KbEntry| {
+            e.updated_by = Some(id.public_key_hex());
+            e.signature = Some(hex::encode(id.sign(e.signing_message().as_bytes()).to_bytes()));
+            e
+        };
+        let ctx = KbEntry {
+            kind: "context".into(),
+            id: KbEntry::new_id("context", "Ad fraud", &admin.public_key_hex(), now),
+            name: "Ad fraud".into(),
+            description: Some("Misleading advertising".into()),
+            quality: None,
+            category_id: Some(2),
+            forma_id: Some(1),
+            cause_id: Some(2),
+            develop_id: Some(2),
+            effect_id: Some(1),
+            updated_at: now,
+            deprecated_at: None,
+            updated_by: None,
+            signature: None,
+        };
+
+        let req = test::TestRequest::post().uri("/api/v1/kb")
+            .insert_header(("Authorization", node_auth))
+            .set_json(signed(&node, ctx.clone()))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::FORBIDDEN);
+
+        let req = test::TestRequest::post().uri("/api/v1/kb")
+            .insert_header(("Authorization", admin_auth.clone()))
+            .set_json(signed(&admin, ctx.clone()))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::CREATED);
+
+        // Ссылка на несуществующую форму
+        let mut bad = ctx.clone();
+        bad.forma_id = Some(404);
+        bad.updated_at = now + 1;
+        let req = test::TestRequest::post().uri("/api/v1/kb")
+            .insert_header(("Authorization", admin_auth.clone()))
+            .set_json(signed(&admin, bad))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        let mut dep = ctx.clone();
+        dep.deprecated_at = Some(now + 2);
+        dep.updated_at = now + 2;
+        let req = test::TestRequest::post().uri("/api/v1/kb")
+            .insert_header(("Authorization", admin_auth))
+            .set_json(signed(&admin, dep))
+            .to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["status"], "updated");
+
+        let req = test::TestRequest::get().uri("/api/v1/kb/context").to_request();
+        let list: Vec<KbEntry> = test::call_and_read_body_json(&app, req).await;
+        assert!(list.iter().all(|c| c.id != ctx.id));
+        let req = test::TestRequest::get().uri("/api/v1/kb/context?include_deprecated=true").to_request();
+        let list: Vec<KbEntry> = test::call_and_read_body_json(&app, req).await;
+        let stored = list.iter().find(|c| c.id == ctx.id).unwrap();
+        assert!(stored.deprecated_at.is_some());
+        assert!(core_lib::knowledge::verify(stored).is_ok());
+        let req = test::TestRequest::get().uri("/api/v1/kb/users").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn alerts_endpoints_list_scan_and_ack() {
+        let pool = crate::db::DbPool::memory().unwrap();
+        let conn = pool.write().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        // Узел n резко вырос в доверии после одного слияния
+        core_lib::storage::record_trust_history(&conn, "n", 0.0, "recalc", None, now - 10).unwrap();
+        core_lib::storage::record_trust_history(&conn, "n", 0.8, "merge", Some("peer"), now - 5).unwrap();
+        let identity = CryptoIdentity::new();
+        let admin = identity.public_key_hex();
+        core_lib::storage::update_user_role(&conn, &admin, "admin").unwrap();
+        let (access, _refresh, _exp) = issue_jwt_pair_with(&conn, &admin).unwrap();
+        drop(conn);
+        let app = test::init_service(
+            App::new()
+                .app_data(actix_web::web::Data::new(pool.clone()))
+                .configure(crate::api::routes)
+        ).await;
+        let auth = format!("Bearer {}", access);
+
+        let req = test::TestRequest::post().uri("/api/v1/alerts/scan").to_request();
+        assert!(test::call_service(&app, req).await.status().is_client_error());
+
+        let req = test::TestRequest::post().uri("/api/v1/alerts/scan")
+            .insert_header(("Authorization", auth.clone()))
+            .to_request();
+        let added: Vec<core_lib::models::Alert> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].kind, "trust_spike");
+
+        let req = test::TestRequest::get().uri("/api/v1/alerts?node=peer").to_request();
+        let list: Vec<core_lib::models::Alert> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(list.len(), 1);
+
+        let req = test::TestRequest::post().uri(&format!("/api/v1/alerts/{}/ack", added[0].id))
+            .insert_header(("Authorization", auth.clone()))
+            .to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+        let req = test::TestRequest::get().uri("/api/v1/alerts").to_request();
+        let list: Vec<core_lib::models::Alert> = test::call_and_read_body_json(&app, req).await;
+        assert!(list.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn statements_and_get_data_are_paginated() {
+        let pool = crate::db::DbPool::memory().unwrap();
+        let mut conn = pool.write().unwrap();
+        core_lib::storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let ev = core_lib::storage::add_truth_event(&conn, core_lib::models::NewTruthEvent {
+            description: "paged".into(), context_id: 1, vector: true, timestamp_start: 1_700_000_000, code: 1,
+        }).unwrap();
+        for i in 0..5 {
+            core_lib::storage::add_statement(&conn, core_lib::models::NewStatement {
+                event_id: ev, text: format!("statement {}", i), context: None, truth_score: None, parent_uid: None, relation: None,
+            }).unwrap();
+            core_lib::storage::add_impact(&conn, ev, 1, true, None).unwrap();
+        }
+        drop(conn);
+        let app = test::init_service(
+            App::new()
+                .app_data(actix_web::web::Data::new(pool.clone()))
+                .configure(crate::api::routes)
+        ).await;
+
+        let mut texts = Vec::new();
+        let mut uri = "/statements?limit=2&order=desc".to_string();
+        loop {
+            let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+            assert!(resp.status().is_success());
+            let next = resp.headers().get("X-Next-Cursor").map(|v| v.to_str().unwrap().to_string());
+            let page: Vec<core_lib::models::Statement> = test::read_body_json(resp).await;
+            assert!(page.len() <= 2);
+            texts.extend(page.into_iter().map(|s| s.text));
+            match next {
+                Some(c) => uri = format!("/statements?limit=2&order=desc&cursor={}", c),
+                None => break,
+            }
+        }
+        assert_eq!(texts, (0..5).rev().map(|i| format!("statement {}", i)).collect::<Vec<_>>());
+
+        let req = test::TestRequest::get().uri("/get_data?limit=3").to_request();
+        let v: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(v["events"].as_array().unwrap().len(), 1);
+        assert!(v["next_events_cursor"].is_null());
+        assert_eq!(v["impacts"].as_array().unwrap().len(), 3);
+        let cursor = v["next_impacts_cursor"].as_str().unwrap().to_string();
+        let req = test::TestRequest::get().uri(&format!("/get_data?limit=3&events_cursor=end&impacts_cursor={}", cursor)).to_request();
+        let v: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert!(v["events"].as_array().unwrap().is_empty());
+        assert_eq!(v["impacts"].as_array().unwrap().len(), 2);
+        assert!(v["next_impacts_cursor"].is_null());
+
+        let req = test::TestRequest::get().uri("/statements?cursor=bogus").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn search_endpoint_ranks_and_filters() {
+        let pool = crate::db::DbPool::memory().unwrap();
+        let mut conn = pool.write().unwrap();
+        core_lib::storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        for (d, ctx) in [("Bridge closed after flood", 1), ("Flood warning lifted", 2), ("Power outage downtown", 1)] {
+            core_lib::storage::add_truth_event(&conn, core_lib::models::NewTruthEvent {
+                description: d.into(), context_id: ctx, vector: true, timestamp_start: 1_700_000_000, code: 1,
+            }).unwrap();
+        }
+        drop(conn);
+        let app = test::init_service(
+            App::new()
+                .app_data(actix_web::web::Data::new(pool.clone()))
+                .configure(crate::api::routes)
+        ).await;
+
+        let req = test::TestRequest::get().uri("/api/v1/search?q=flood").to_request();
+        let hits: Vec<core_lib::models::SearchHit> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().all(|h| h.kind == "event" && h.snippet.contains("[")));
+
+        let req = test::TestRequest::get().uri("/api/v1/search?q=flood&context=2").to_request();
+        let hits: Vec<core_lib::models::SearchHit> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].context_id, Some(2));
+
+        let req = test::TestRequest::get().uri("/api/v1/search?q=%20").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), actix_web::http::StatusCode::BAD_REQUEST);
+    }
+
     #[actix_web::test]
     async fn recalc_collective_endpoint_works() {
         // Prepare in-memory DB and app
-        let conn = core_lib::storage::open_db(":memory:").unwrap();
-        let conn_data = Arc::new(Mutex::new(conn));
+        let pool = crate::db::DbPool::memory().unwrap();
         {
-            let mut c = conn_data.lock().await;
+            let mut c = pool.write().unwrap();
             core_lib::storage::seed_knowledge_base(&mut c, "en").unwrap();
         }
 
         let app = test::init_service(
             App::new()
-                .app_data(actix_web::web::Data::new(conn_data.clone()))
+                .app_data(actix_web::web::Data::new(pool.clone()))
                 .configure(crate::api::routes)
         ).await;
 
@@ -1550,7 +2864,7 @@ mod tests {
 
         // Insert a positive impact for this event
         {
-            let c = conn_data.lock().await;
+            let c = pool.write().unwrap();
             let _iid = core_lib::storage::add_impact(&c, ev_id, 1, true, None).unwrap();
         }
 
@@ -1561,7 +2875,7 @@ mod tests {
 
         // Verify value persisted in DB
         {
-            let c = conn_data.lock().await;
+            let c = pool.write().unwrap();
             let ev = core_lib::storage::get_truth_event(&c, ev_id).unwrap().unwrap();
             assert!(ev.collective_score.is_some());
             assert!(ev.collective_score.unwrap() > 0.0);
@@ -1598,7 +2912,7 @@ async fn api_v1_judgments_post(req: HttpRequest, pool: web::Data<DbPool>, payloa
     }
     let poolc = pool.clone();
     let res = web::block(move || {
-        let conn = poolc.blocking_lock();
+        let conn = poolc.write()?;
         // Ensure participant using provided public key
         let participant_id = core_lib::storage::ci_ensure_participant(&conn, &pk_hex)?;
         let j = core_lib::collective_intelligence::models::Judgment {
@@ -1611,7 +2925,7 @@ async fn api_v1_judgments_post(req: HttpRequest, pool: web::Data<DbPool>, payloa
             submitted_at: chrono::Utc::now(),
             signature: sig_hex,
         };
-        core_lib::storage::ci_insert_judgment(&conn, &j)?;
+        core_lib::audit::with_actor(&pk_hex, || core_lib::storage::ci_insert_judgment(&conn, &j))?;
         // Recalculate consensus
         let _c = core_lib::storage::ci_calculate_and_upsert_consensus(&conn, &j.event_id)?;
         Ok::<uuid::Uuid, core_lib::models::CoreError>(j.id)
@@ -1638,7 +2952,7 @@ async fn api_v1_judgments_get(req: HttpRequest, pool: web::Data<DbPool>, q: web:
     let event_id = match q.get("event_id").cloned() { Some(v) => v, None => return HttpResponse::BadRequest().body("event_id required") };
     let poolc = pool.clone();
     let res = web::block(move || {
-        let conn = poolc.blocking_lock();
+        let conn = poolc.read()?;
         let ev = uuid::Uuid::parse_str(&event_id).map_err(|_| core_lib::models::CoreError::InvalidArg("bad event_id".into()))?;
         let js = core_lib::storage::ci_get_judgments_by_event(&conn, &ev)?;
         Ok::<Vec<core_lib::collective_intelligence::models::Judgment>, core_lib::models::CoreError>(js)
@@ -1656,7 +2970,7 @@ async fn api_v1_consensus_get(req: HttpRequest, pool: web::Data<DbPool>, path: w
     let event_id = match uuid::Uuid::parse_str(&path.into_inner()) { Ok(v) => v, Err(_) => return HttpResponse::BadRequest().finish() };
     let poolc = pool.clone();
     let res = web::block(move || {
-        let conn = poolc.blocking_lock();
+        let conn = poolc.read()?;
         core_lib::storage::ci_get_consensus_by_event(&conn, &event_id)
     }).await;
     match res {
@@ -1673,7 +2987,7 @@ async fn api_v1_consensus_calculate(req: HttpRequest, pool: web::Data<DbPool>, p
     let event_id = match uuid::Uuid::parse_str(&path.into_inner()) { Ok(v) => v, Err(_) => return HttpResponse::BadRequest().finish() };
     let poolc = pool.clone();
     let res = web::block(move || {
-        let conn = poolc.blocking_lock();
+        let conn = poolc.write()?;
         core_lib::storage::ci_calculate_and_upsert_consensus(&conn, &event_id)
     }).await;
     match res {
@@ -1690,7 +3004,7 @@ async fn api_v1_reputation_get(req: HttpRequest, pool: web::Data<DbPool>, path:
     let poolc = pool.clone();
     let pid_out = pid.clone();
     let res = web::block(move || {
-        let conn = poolc.blocking_lock();
+        let conn = poolc.read()?;
         core_lib::storage::ci_get_reputation_by_participant(&conn, &pid)
     }).await;
     match res {
@@ -1708,7 +3022,7 @@ async fn api_v1_reputation_leaderboard(req: HttpRequest, pool: web::Data<DbPool>
     let min_j: i64 = q.get("min_judgments").and_then(|v| v.parse().ok()).unwrap_or(0);
     let poolc = pool.clone();
     let res = web::block(move || {
-        let conn = poolc.blocking_lock();
+        let conn = poolc.read()?;
         core_lib::storage::ci_get_reputation_leaderboard(&conn, min_j, limit)
     }).await;
     match res {
@@ -0,0 +1,220 @@
+use core_lib::models::CoreError;
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{Connection, OpenFlags};
+use std::time::Duration;
+
+/// Соединение, взятое из пула; возвращается в пул при drop
+pub type PooledConn = PooledConnection<SqliteConnectionManager>;
+
+/// Параметры пула соединений
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    /// Число соединений только для чтения
+    pub readers: u32,
+    /// Сколько SQLite ждёт снятия блокировки, прежде чем вернуть SQLITE_BUSY
+    pub busy_timeout: Duration,
+    /// Сколько ждать свободного соединения из пула
+    pub checkout_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            readers: 4,
+            busy_timeout: Duration::from_secs(5),
+            checkout_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Пул соединений SQLite сервера и узла.
+/// Писатель один (SQLite сериализует запись), читателей несколько; в режиме WAL
+/// чтения не блокируются записью, поэтому долгая синхронизация не останавливает HTTP-запросы.
+#[derive(Clone)]
+pub struct DbPool {
+    read: Pool<SqliteConnectionManager>,
+    write: Pool<SqliteConnectionManager>,
+}
+
+impl DbPool {
+    /// Открыть файл БД: применить миграции, включить WAL и поднять пулы
+    pub fn open(path: &str, cfg: &PoolConfig) -> Result<Self, CoreError> {
+        {
+            let conn = Connection::open(path)?;
+            conn.busy_timeout(cfg.busy_timeout)?;
+            conn.pragma_update(None, "journal_mode", "WAL")?;
+            core_lib::storage::init_db(&conn)?;
+        }
+        Self::build(path, OpenFlags::default(), cfg)
+    }
+
+    /// Отдельная БД в памяти (тесты): общий кэш вместо файла, схема уже применена
+    #[allow(dead_code)] // Используется в тестах библиотеки
+    pub fn memory() -> Result<Self, CoreError> {
+        let uri = format!("file:truth-{}?mode=memory&cache=shared", uuid::Uuid::new_v4());
+        let pool = Self::build(&uri, OpenFlags::default() | OpenFlags::SQLITE_OPEN_URI, &PoolConfig::default())?;
+        core_lib::storage::init_db(&*pool.write()?)?;
+        Ok(pool)
+    }
+
+    fn build(path: &str, flags: OpenFlags, cfg: &PoolConfig) -> Result<Self, CoreError> {
+        let busy = cfg.busy_timeout;
+        let write_manager = SqliteConnectionManager::file(path)
+            .with_flags(flags)
+            .with_init(move |c| {
+                c.busy_timeout(busy)?;
+                c.execute_batch("PRAGMA foreign_keys = ON; PRAGMA synchronous = NORMAL;")
+            });
+        let read_flags = (flags - OpenFlags::SQLITE_OPEN_READ_WRITE - OpenFlags::SQLITE_OPEN_CREATE)
+            | OpenFlags::SQLITE_OPEN_READ_ONLY;
+        let read_manager = SqliteConnectionManager::file(path)
+            .with_flags(read_flags)
+            .with_init(move |c| {
+                c.busy_timeout(busy)?;
+                c.execute_batch("PRAGMA query_only = ON;")
+            });
+
+        // Соединения не пересоздаются по таймеру: для БД в памяти это потеряло бы данные
+        let write = Pool::builder()
+            .max_size(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connection_timeout(cfg.checkout_timeout)
+            .build(write_manager)
+            .map_err(pool_error)?;
+        let read = Pool::builder()
+            .max_size(cfg.readers.max(1))
+            .min_idle(Some(1))
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connection_timeout(cfg.checkout_timeout)
+            .build(read_manager)
+            .map_err(pool_error)?;
+        Ok(Self { read, write })
+    }
+
+    /// Соединение только для чтения
+    pub fn read(&self) -> Result<PooledConn, CoreError> {
+        self.read.get().map_err(pool_error)
+    }
+
+    /// Единственное пишущее соединение; держать его только на время записи
+    pub fn write(&self) -> Result<PooledConn, CoreError> {
+        self.write.get().map_err(pool_error)
+    }
+}
+
+fn pool_error(e: r2d2::Error) -> CoreError {
+    CoreError::Pool(e.to_string())
+}
+
+/// Плановые снимки БД: онлайн-копия с читающего соединения, подписанная ключом узла,
+/// и очистка каталога до keep последних копий
+pub async fn run_backup_scheduler(
+    pool: DbPool,
+    signer: ed25519_dalek::SigningKey,
+    dir: std::path::PathBuf,
+    interval: Duration,
+    keep: usize,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        let (pool, signer, dir) = (pool.clone(), signer.clone(), dir.clone());
+        let res = tokio::task::spawn_blocking(move || {
+            let conn = pool.read()?;
+            core_lib::backup::snapshot(&conn, &dir, Some(&signer), keep)
+        })
+        .await;
+        match res {
+            Ok(Ok(entry)) => log::info!("backup snapshot {}", entry.path.display()),
+            Ok(Err(e)) => log::warn!("backup snapshot failed: {}", e),
+            Err(e) => log::warn!("backup task failed: {}", e),
+        }
+    }
+}
+
+/// Фоновая очистка журналов и истории по политикам хранения (core_lib::retention)
+pub async fn run_pruner(pool: DbPool, interval: Duration, archive_dir: Option<std::path::PathBuf>) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        let (pool, archive_dir) = (pool.clone(), archive_dir.clone());
+        let res = tokio::task::spawn_blocking(move || {
+            let mut conn = pool.write()?;
+            let opts = core_lib::retention::PruneOptions { archive_dir, ..Default::default() };
+            core_lib::retention::prune(&mut conn, &opts)
+        })
+        .await;
+        match res {
+            Ok(Ok(report)) => {
+                let total: usize = report.tables.iter().map(|t| t.total()).sum();
+                if total > 0 {
+                    log::info!("pruned {} rows, archive {:?}", total, report.archive);
+                }
+            }
+            Ok(Err(e)) => log::warn!("prune failed: {}", e),
+            Err(e) => log::warn!("prune task failed: {}", e),
+        }
+    }
+}
+
+/// Периодическая подпись головы журнала аудита ключом узла (core_lib::audit::checkpoint)
+pub async fn run_audit_signer(pool: DbPool, signer: ed25519_dalek::SigningKey, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        let (pool, signer) = (pool.clone(), signer.clone());
+        let res = tokio::task::spawn_blocking(move || {
+            let conn = pool.write()?;
+            core_lib::audit::checkpoint(&conn, &signer, chrono::Utc::now().timestamp())
+        })
+        .await;
+        match res {
+            Ok(Ok(Some(cp))) => log::info!("audit checkpoint at entry {}", cp.seq),
+            Ok(Ok(None)) => {}
+            Ok(Err(e)) => log::warn!("audit checkpoint failed: {}", e),
+            Err(e) => log::warn!("audit signer task failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn readers_see_writes_and_cannot_write() {
+        let pool = DbPool::memory().unwrap();
+        let writer = pool.write().unwrap();
+        writer.execute("INSERT INTO sync_logs (timestamp, peer_url, mode, status, details) VALUES (1, 'p', 'm', 's', 'd')", []).unwrap();
+        drop(writer);
+
+        let reader = pool.read().unwrap();
+        let n: i64 = reader.query_row("SELECT COUNT(*) FROM sync_logs", [], |r| r.get(0)).unwrap();
+        assert_eq!(n, 1);
+        assert!(reader.execute("DELETE FROM sync_logs", []).is_err());
+    }
+
+    #[test]
+    fn file_database_uses_wal() {
+        let path = std::env::temp_dir().join(format!("truth-pool-{}.db", uuid::Uuid::new_v4()));
+        let path_str = path.to_string_lossy().to_string();
+        {
+            let pool = DbPool::open(&path_str, &PoolConfig::default()).unwrap();
+            let mode: String = pool.read().unwrap().query_row("PRAGMA journal_mode", [], |r| r.get(0)).unwrap();
+            assert_eq!(mode, "wal");
+
+            // Чтение не ждёт открытой транзакции записи
+            let writer = pool.write().unwrap();
+            writer.execute_batch("BEGIN IMMEDIATE; INSERT INTO sync_logs (timestamp, peer_url, mode, status, details) VALUES (1, 'p', 'm', 's', 'd');").unwrap();
+            let n: i64 = pool.read().unwrap().query_row("SELECT COUNT(*) FROM sync_logs", [], |r| r.get(0)).unwrap();
+            assert_eq!(n, 0);
+            writer.execute_batch("COMMIT;").unwrap();
+        }
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path_str, suffix));
+        }
+    }
+}
@@ -1,6 +1,8 @@
 #[cfg(feature = "desktop")]
 pub mod api;
 #[cfg(feature = "desktop")]
+pub mod db;
+#[cfg(feature = "desktop")]
 pub mod net;
 #[cfg(feature = "desktop")]
 pub mod p2p;
@@ -11,11 +11,13 @@ use clap::Parser;
 #[cfg(feature = "desktop")]
 use std::sync::Arc;
 #[cfg(feature = "desktop")]
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::RwLock;
 
 #[cfg(feature = "desktop")]
 mod api;
 #[cfg(feature = "desktop")]
+mod db;
+#[cfg(feature = "desktop")]
 mod net;
 #[cfg(feature = "desktop")]
 mod p2p;
@@ -39,6 +41,46 @@ struct Args {
 
     #[arg(long, default_value = "truth_training.db")]
     db: String,
+
+    /// Число соединений БД только для чтения
+    #[arg(long, default_value = "4")]
+    db_readers: u32,
+
+    /// Ожидание блокировки SQLite, мс
+    #[arg(long, default_value = "5000")]
+    busy_timeout_ms: u64,
+
+    /// Файл с парольной фразой зашифрованной БД (иначе TRUTH_DB_PASSPHRASE или POST /api/v1/db/unlock)
+    #[arg(long)]
+    db_passphrase_file: Option<std::path::PathBuf>,
+
+    /// Каталог плановых снимков БД (без него снимки не делаются)
+    #[arg(long)]
+    backup_dir: Option<std::path::PathBuf>,
+
+    /// Интервал снимков, с
+    #[arg(long, default_value = "86400")]
+    backup_interval_secs: u64,
+
+    /// Сколько последних снимков хранить
+    #[arg(long, default_value = "7")]
+    backup_keep: usize,
+
+    /// Интервал очистки журналов по политикам хранения, с (0 — выключено)
+    #[arg(long, default_value = "3600")]
+    prune_interval_secs: u64,
+
+    /// Каталог архива удаляемых при очистке строк (без него строки не сохраняются)
+    #[arg(long)]
+    archive_dir: Option<std::path::PathBuf>,
+
+    /// Интервал подписи головы журнала аудита ключом узла, с (0 — выключено)
+    #[arg(long, default_value = "600")]
+    audit_sign_interval_secs: u64,
+
+    /// Каталог хранилища блобов доказательств
+    #[arg(long, default_value = "blobs")]
+    blob_dir: std::path::PathBuf,
 }
 
 #[cfg(feature = "desktop")]
@@ -77,12 +119,31 @@ async fn main() -> std::io::Result<()> {
     tokio::spawn(run_beacon_listener(peers.clone()));
     tokio::spawn(run_peer_logger(peers.clone()));
 
-    // Инициализация БД
-    let mut conn = core_lib::storage::create_db_connection(&args.db)
-        .map_err(std::io::Error::other)?;
-    core_lib::storage::seed_knowledge_base(&mut conn, "ru")
-        .map_err(std::io::Error::other)?;
-    let conn_data = Arc::new(Mutex::new(conn));
+    // Инициализация БД: WAL, один писатель и пул читателей
+    let pool_cfg = db::PoolConfig {
+        readers: args.db_readers,
+        busy_timeout: std::time::Duration::from_millis(args.busy_timeout_ms),
+        ..Default::default()
+    };
+    let db_pool = db::DbPool::open(&args.db, &pool_cfg).map_err(std::io::Error::other)?;
+    {
+        let mut conn = db_pool.write().map_err(std::io::Error::other)?;
+        core_lib::storage::seed_knowledge_base(&mut conn, "ru")
+            .map_err(std::io::Error::other)?;
+        if let Some(file) = &args.db_passphrase_file {
+            let passphrase = std::fs::read_to_string(file)?;
+            core_lib::at_rest::provide_key(core_lib::at_rest::KeySource::Passphrase(
+                passphrase.trim_end_matches(['\r', '\n']).to_string(),
+            ));
+        }
+        match core_lib::at_rest::ensure_unlocked(&conn) {
+            Ok(()) => {}
+            Err(core_lib::models::CoreError::Locked(m)) => {
+                log::warn!("{}; starting locked until POST /api/v1/db/unlock", m)
+            }
+            Err(e) => return Err(std::io::Error::other(e)),
+        }
+    }
 
     // 🔒 Генерация крипто-идентичности узла
     let crypto_identity = CryptoIdentity::new();
@@ -93,7 +154,30 @@ async fn main() -> std::io::Result<()> {
 
     //Теперь при создании Node передаём CryptoIdentity и пул БД
     let crypto_identity = Arc::new(CryptoIdentity::new());
-    let node = Arc::new(Node::new(peers_list, conn_data.clone(), crypto_identity.clone()));
+    let node = Arc::new(Node::new(peers_list, db_pool.clone(), crypto_identity.clone()));
+    if let Some(dir) = args.backup_dir.clone() {
+        tokio::spawn(db::run_backup_scheduler(
+            db_pool.clone(),
+            crypto_identity.signing_key.clone(),
+            dir,
+            std::time::Duration::from_secs(args.backup_interval_secs.max(60)),
+            args.backup_keep,
+        ));
+    }
+    if args.prune_interval_secs > 0 {
+        tokio::spawn(db::run_pruner(
+            db_pool.clone(),
+            std::time::Duration::from_secs(args.prune_interval_secs),
+            args.archive_dir.clone(),
+        ));
+    }
+    if args.audit_sign_interval_secs > 0 {
+        tokio::spawn(db::run_audit_signer(
+            db_pool.clone(),
+            crypto_identity.signing_key.clone(),
+            std::time::Duration::from_secs(args.audit_sign_interval_secs),
+        ));
+    }
     let node_for_task = node.clone();
     tokio::spawn(async move {
         node_for_task.start().await;
@@ -104,8 +188,9 @@ async fn main() -> std::io::Result<()> {
         App::new()
             // Wide-open CORS for Android debugging; lock down in production
             .wrap(Cors::permissive())
-            .app_data(actix_web::web::Data::new(conn_data.clone()))
+            .app_data(actix_web::web::Data::new(db_pool.clone()))
             .app_data(actix_web::web::Data::new(node.clone()))
+            .app_data(actix_web::web::Data::new(core_lib::evidence::BlobStore::new(args.blob_dir.clone())))
             .app_data(actix_web::web::Data::new(crate::api::AppInfo {
                 db_path: args.db.clone(),
                 p2p_enabled: true,
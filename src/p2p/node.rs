@@ -1,11 +1,10 @@
 use std::sync::Arc;
 use tokio::time::{self, Duration};
-use tokio::sync::Mutex;
 use crate::p2p::encryption::CryptoIdentity;
 use crate::p2p::sync::{SyncError, SyncData, compute_ratings_hash};
 // already imported above
 use log::{info, error};
-use rusqlite::Connection;
+use crate::db::DbPool;
 use reqwest::Client;
 use std::time::Duration as StdDuration;
 use chrono::Utc;
@@ -13,14 +12,14 @@ use chrono::Utc;
 #[derive(Clone)]
 pub struct Node {
     pub peers: Vec<String>,
-    pub conn_data: Arc<Mutex<Connection>>, // shared DB connection
+    pub db: DbPool, // пул соединений БД
     pub crypto: Arc<CryptoIdentity>,
 }
 
 impl Node {
     /// Теперь принимает готовую CryptoIdentity и пул БД
-    pub fn new(peers: Vec<String>, conn_data: Arc<Mutex<Connection>>, crypto: Arc<CryptoIdentity>) -> Self {
-        Self { peers, conn_data, crypto }
+    pub fn new(peers: Vec<String>, db: DbPool, crypto: Arc<CryptoIdentity>) -> Self {
+        Self { peers, db, crypto }
     }
 
     /// Запуск узла — периодическая синхронизация с другими
@@ -34,11 +33,14 @@ impl Node {
                 let peer = peer.clone();
                 let identity = self.crypto.clone();
 
-                let conn_data = self.conn_data.clone();
+                let db = self.db.clone();
                 tokio::spawn(async move {
-                    // 1) Считаем данные из БД синхронно и освободим соединение до await
+                    // 1) Считаем данные через соединение для чтения и вернём его в пул до await
                     let (sync_data, sig_hex, pub_hex, ts, rhash) = {
-                        let conn = conn_data.lock().await;
+                        let conn = match db.read() {
+                            Ok(c) => c,
+                            Err(e) => { error!("❌ DB read failed: {e}"); return; }
+                        };
                         let events = core_lib::storage::load_truth_events(&conn)
                             .map_err(|e| SyncError::Other(e.to_string()));
                         let statements = core_lib::storage::load_statements(&conn)
@@ -66,6 +68,26 @@ impl Node {
                                 return;
                             }
                         };
+                        let trust_delegations = match core_lib::storage::load_trust_delegations(&conn, None) {
+                            Ok(d) => d,
+                            Err(e) => { error!("❌ DB read failed: {e}"); return; }
+                        };
+                        let tombstones = match core_lib::storage::load_tombstones(&conn, None) {
+                            Ok(t) => t,
+                            Err(e) => { error!("❌ DB read failed: {e}"); return; }
+                        };
+                        let knowledge_base = match core_lib::storage::load_kb_changes(&conn, None) {
+                            Ok(k) => k,
+                            Err(e) => { error!("❌ DB read failed: {e}"); return; }
+                        };
+                        let evidence = match core_lib::storage::load_evidence(&conn, None) {
+                            Ok(e) => e,
+                            Err(e) => { error!("❌ DB read failed: {e}"); return; }
+                        };
+                        let event_relations = match core_lib::storage::load_event_relations(&conn, None) {
+                            Ok(r) => r,
+                            Err(e) => { error!("❌ DB read failed: {e}"); return; }
+                        };
                         let sync_data = SyncData {
                             events,
                             statements,
@@ -74,6 +96,11 @@ impl Node {
                             node_ratings: node_ratings.clone(),
                             group_ratings: group_ratings.clone(),
                             node_metrics,
+                            trust_delegations,
+                            tombstones,
+                            knowledge_base,
+                            evidence,
+                            event_relations,
                             last_sync: Utc::now().timestamp(),
                         };
                         let ts = Utc::now().timestamp();
@@ -108,13 +135,15 @@ impl Node {
 
     /// Широковещательная отправка локальных рейтингов всем известным пирам
     pub async fn broadcast_ratings(&self) -> Result<(), SyncError> {
-        let conn = self.conn_data.lock().await;
+        let conn = self.db.read().map_err(|e| SyncError::Other(e.to_string()))?;
         let node_ratings = core_lib::storage::load_node_ratings(&conn)
             .map_err(|e| SyncError::Other(e.to_string()))?;
         let group_ratings = core_lib::storage::load_group_ratings(&conn)
             .map_err(|e| SyncError::Other(e.to_string()))?;
         let node_metrics = core_lib::storage::load_all_node_metrics(&conn)
             .map_err(|e| SyncError::Other(e.to_string()))?;
+        let trust_delegations = core_lib::storage::load_trust_delegations(&conn, None)
+            .map_err(|e| SyncError::Other(e.to_string()))?;
         drop(conn);
         let payload = SyncData {
             events: Vec::new(),
@@ -124,6 +153,11 @@ impl Node {
             node_ratings: node_ratings.clone(),
             group_ratings: group_ratings.clone(),
             node_metrics,
+            trust_delegations,
+            tombstones: Vec::new(),
+            knowledge_base: Vec::new(),
+            evidence: Vec::new(),
+            event_relations: Vec::new(),
             last_sync: Utc::now().timestamp(),
         };
 
@@ -137,14 +171,17 @@ impl Node {
         let public_key_hex = self.crypto.public_key_hex();
 
         // Отправка последовательно (избегаем зависимости от futures)
-        // Приоритетная очередь: сортируем по propagation_priority (DESC); низким приоритетам даём задержку
+        // Приоритетная очередь: сортируем пиров по их текущему propagation_priority (DESC)
+        // с учётом активности за окно; низким приоритетам даём задержку
         let mut peers_with_prio: Vec<(String, f32)> = Vec::new();
-        for peer in &self.peers {
-            let prio = {
-                let conn = self.conn_data.lock().await;
-                core_lib::storage::get_propagation_priority(&conn, &self.crypto.public_key_hex()).unwrap_or(0.5)
-            };
-            peers_with_prio.push((peer.clone(), prio));
+        {
+            let conn = self.db.read().map_err(|e| SyncError::Other(e.to_string()))?;
+            for peer in &self.peers {
+                let prio = core_lib::storage::peer_propagation_priority(&conn, peer, ts)
+                    .map(|(p, _)| p)
+                    .unwrap_or(0.5);
+                peers_with_prio.push((peer.clone(), prio));
+            }
         }
         peers_with_prio.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
 
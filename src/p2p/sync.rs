@@ -4,11 +4,19 @@ use reqwest::Client;
 use std::time::Duration;
 #[cfg(any(test, feature = "p2p-client-sync"))]
 use crate::p2p::encryption::CryptoIdentity;
-use core_lib::models::{TruthEvent, Statement, Impact, ProgressMetrics, NodeRating, GroupRating, NodeMetrics as NodeMetricsModel};
-use core_lib::trust_propagation::{blend_quality, blend_priority};
+use core_lib::models::{TruthEvent, Statement, Impact, ProgressMetrics, NodeRating, GroupRating, NodeMetrics as NodeMetricsModel, TrustDelegation, Tombstone, KbEntry, Evidence, EventRelation};
+use core_lib::trust_propagation::{blend_quality_weighted, blend_priority_weighted, load_sender_weight};
 use core_lib::storage;
+use core_lib::store::Store;
+use core_lib::tombstone;
+use core_lib::knowledge;
+use core_lib::evidence;
+use core_lib::event_relation;
+use core_lib::models::CoreError;
 // trust_propagation используется внутри core/storage::merge_ratings
-use rusqlite::{Connection, params, OptionalExtension};
+use rusqlite::Connection;
+#[cfg(any(test, feature = "p2p-client-sync"))]
+use rusqlite::params;
 use serde::{Deserialize, Serialize};
 #[cfg(any(test, feature = "p2p-client-sync"))]
 use chrono::Utc;
@@ -27,6 +35,21 @@ pub struct SyncData {
     pub node_ratings: Vec<NodeRating>,
     pub group_ratings: Vec<GroupRating>,
     pub node_metrics: Vec<NodeMetricsModel>,
+    /// Подписанные делегирования доверия (старые пиры поле не присылают)
+    #[serde(default)]
+    pub trust_delegations: Vec<TrustDelegation>,
+    /// Подписанные отметки удаления (старые пиры поле не присылают)
+    #[serde(default)]
+    pub tombstones: Vec<Tombstone>,
+    /// Подписанные правки базы знаний (старые пиры поле не присылают)
+    #[serde(default)]
+    pub knowledge_base: Vec<KbEntry>,
+    /// Подписанные метаданные доказательств; блобы пиры забирают по запросу (старые пиры поле не присылают)
+    #[serde(default)]
+    pub evidence: Vec<Evidence>,
+    /// Подписанные связи между событиями (старые пиры поле не присылают)
+    #[serde(default)]
+    pub event_relations: Vec<EventRelation>,
     pub last_sync: i64,
 }
 
@@ -42,12 +65,45 @@ pub struct SyncResult {
     pub nodes_trust_changed: u32,
     pub trust_diff: Vec<TrustDelta>,
     pub avg_quality_index: f32,
+    #[serde(default)]
+    pub delegations_added: u32,
+    #[serde(default)]
+    pub tombstones_applied: u32,
+    #[serde(default)]
+    pub kb_entries_applied: u32,
+    #[serde(default)]
+    pub evidence_added: u32,
+    #[serde(default)]
+    pub event_relations_added: u32,
+}
+
+#[cfg(any(test, feature = "p2p-client-sync"))]
+impl SyncResult {
+    /// Добавить итоги следующей партии той же синхронизации
+    pub fn absorb(&mut self, other: SyncResult) {
+        self.conflicts_resolved += other.conflicts_resolved;
+        self.events_added += other.events_added;
+        self.statements_added += other.statements_added;
+        self.impacts_added += other.impacts_added;
+        self.errors.extend(other.errors);
+        self.nodes_trust_changed += other.nodes_trust_changed;
+        self.trust_diff.extend(other.trust_diff);
+        self.avg_quality_index = other.avg_quality_index;
+        self.delegations_added += other.delegations_added;
+        self.tombstones_applied += other.tombstones_applied;
+        self.kb_entries_applied += other.kb_entries_applied;
+        self.evidence_added += other.evidence_added;
+        self.event_relations_added += other.event_relations_added;
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrustDelta {
     pub node_id: String,
     pub delta: f32,
+    /// Узел-отправитель, чьи оценки вызвали изменение (для аудита)
+    #[serde(default)]
+    pub sender: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -173,6 +229,11 @@ pub async fn sync_with_peer(peer_url: &str, identity: &CryptoIdentity) -> anyhow
         nodes_trust_changed: 0,
         trust_diff: Vec::new(),
         avg_quality_index: 0.0,
+        delegations_added: 0,
+        tombstones_applied: 0,
+        kb_entries_applied: 0,
+        evidence_added: 0,
+        event_relations_added: 0,
     })
 }
 
@@ -185,58 +246,11 @@ pub async fn bidirectional_sync_with_peer(
     conn: &Connection,
 ) -> anyhow::Result<SyncResult> {
     let result: anyhow::Result<SyncResult> = async {
-        // Получаем локальные данные для отправки
-        let local_events = storage::load_truth_events(conn)?;
-        let local_statements = storage::load_statements(conn)?;
-        let local_impacts = storage::load_impacts(conn)?;
-        let local_metrics = storage::load_metrics(conn)?;
-
-        let local_node_ratings = core_lib::storage::load_node_ratings(conn)?;
-        let local_group_ratings = core_lib::storage::load_group_ratings(conn)?;
-        let local_node_metrics = core_lib::storage::load_all_node_metrics(conn)?;
-    let sync_data = SyncData {
-            events: local_events,
-            statements: local_statements,
-            impacts: local_impacts,
-            metrics: local_metrics,
-        node_ratings: local_node_ratings.clone(),
-        group_ratings: local_group_ratings.clone(),
-        node_metrics: local_node_metrics,
-            last_sync: chrono::Utc::now().timestamp(),
-        };
-
-        // Создаём асинхронный HTTP клиент
+        // Локальные данные уходят постранично, без загрузки таблиц целиком
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
             .build()?;
-
-        // Формируем сообщение для подписи
-        let ts = chrono::Utc::now().timestamp();
-        let ratings_hash = compute_ratings_hash(&sync_data.node_ratings, &sync_data.group_ratings)?;
-        let message = format!("sync_push:{}:{}", ts, ratings_hash);
-
-        // Подписываем сообщение приватным ключом
-        let signature = identity.sign(message.as_bytes());
-        let public_key_hex = identity.public_key_hex();
-        let signature_hex = hex::encode(signature.to_bytes());
-
-        // Отправляем данные
-        let response = client
-            .post(format!("{peer_url}/sync"))
-            .header("X-Public-Key", public_key_hex)
-            .header("X-Signature", signature_hex)
-            .header("X-Timestamp", ts.to_string())
-            .header("X-Ratings-Hash", ratings_hash)
-            .json(&sync_data)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            anyhow::bail!("Peer sync push failed: {}", response.status());
-        }
-
-        // Получаем ответ с результатами синхронизации
-        let sync_result: SyncResult = response.json().await?;
+        let sync_result = push_batches(&client, peer_url, identity, conn).await?;
 
         log::info!(
             "Bidirectional sync with {peer_url} completed: conflicts {}, events {}, trust changes {}",
@@ -293,44 +307,128 @@ pub async fn push_local_data(
     identity: &CryptoIdentity,
     conn: &Connection,
 ) -> anyhow::Result<SyncResult> {
-    let node_ratings = core_lib::storage::load_node_ratings(conn)?;
-    let group_ratings = core_lib::storage::load_group_ratings(conn)?;
-    let node_metrics = core_lib::storage::load_all_node_metrics(conn)?;
-    let sync_data = SyncData {
-        events: storage::load_truth_events(conn)?,
-        statements: storage::load_statements(conn)?,
-        impacts: storage::load_impacts(conn)?,
-        metrics: storage::load_metrics(conn)?,
-        node_ratings: node_ratings.clone(),
-        group_ratings: group_ratings.clone(),
-        node_metrics,
-        last_sync: Utc::now().timestamp(),
-    };
-
     let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
-    let ts = Utc::now().timestamp();
-    let ratings_hash = compute_ratings_hash(&sync_data.node_ratings, &sync_data.group_ratings)?;
-    let message = format!("sync_push:{}:{}", ts, ratings_hash);
-    let sig = identity.sign(message.as_bytes());
-    let signature_hex = hex::encode(sig.to_bytes());
-    let public_key_hex = identity.public_key_hex();
+    let result = push_batches(&client, peer_url, identity, conn).await;
+    record_relay_result(peer_url, result.is_ok()).await;
+    result
+}
 
-    let resp = client
-        .post(format!("{peer_url}/sync"))
-        .header("X-Public-Key", public_key_hex)
-        .header("X-Signature", signature_hex)
-        .header("X-Timestamp", ts.to_string())
-        .header("X-Ratings-Hash", ratings_hash)
-        .json(&sync_data)
-        .send()
-        .await?;
+/// Этап постраничной отправки локальных записей
+#[cfg(any(test, feature = "p2p-client-sync"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PushStage {
+    Events,
+    Statements,
+    Impacts,
+    Done,
+}
+
+/// Курсоры постраничной отправки: сначала все страницы событий, затем утверждений, затем влияний
+#[cfg(any(test, feature = "p2p-client-sync"))]
+struct PushPages {
+    stage: PushStage,
+    cursor: Option<String>,
+}
 
-    let ok = resp.status().is_success();
-    record_relay_result(peer_url, ok).await;
-    if !ok {
-        anyhow::bail!("Peer sync push failed: {}", resp.status());
+#[cfg(any(test, feature = "p2p-client-sync"))]
+impl PushPages {
+    fn new() -> Self {
+        Self { stage: PushStage::Events, cursor: None }
+    }
+
+    /// Следующая партия для /sync или None, если всё отправлено. Остальные таблицы едут в последней
+    /// партии: доказательства, связи и отметки удаления ссылаются на уже отправленные записи
+    fn next_batch(&mut self, conn: &Connection) -> anyhow::Result<Option<SyncData>> {
+        let mut batch = SyncData {
+            events: Vec::new(),
+            statements: Vec::new(),
+            impacts: Vec::new(),
+            metrics: Vec::new(),
+            node_ratings: Vec::new(),
+            group_ratings: Vec::new(),
+            node_metrics: Vec::new(),
+            trust_delegations: Vec::new(),
+            tombstones: Vec::new(),
+            knowledge_base: Vec::new(),
+            evidence: Vec::new(),
+            event_relations: Vec::new(),
+            last_sync: Utc::now().timestamp(),
+        };
+        let q = storage::RecordQuery::new().limit(storage::PAGE_MAX_LIMIT).after(self.cursor.take());
+        let (next_cursor, next_stage) = match self.stage {
+            PushStage::Events => {
+                let page = storage::query_truth_events(conn, &q)?;
+                batch.events = page.items;
+                (page.next_cursor, PushStage::Statements)
+            }
+            PushStage::Statements => {
+                let page = storage::query_statements(conn, &q)?;
+                batch.statements = page.items;
+                (page.next_cursor, PushStage::Impacts)
+            }
+            PushStage::Impacts => {
+                let page = storage::query_impacts(conn, &q)?;
+                batch.impacts = page.items;
+                (page.next_cursor, PushStage::Done)
+            }
+            PushStage::Done => return Ok(None),
+        };
+        match next_cursor {
+            Some(c) => self.cursor = Some(c),
+            None => self.stage = next_stage,
+        }
+        if self.stage == PushStage::Done {
+            batch.metrics = storage::load_metrics(conn)?;
+            batch.node_ratings = storage::load_node_ratings(conn)?;
+            batch.group_ratings = storage::load_group_ratings(conn)?;
+            batch.node_metrics = storage::load_all_node_metrics(conn)?;
+            batch.trust_delegations = storage::load_trust_delegations(conn, None)?;
+            batch.tombstones = storage::load_tombstones(conn, None)?;
+            batch.knowledge_base = storage::load_kb_changes(conn, None)?;
+            batch.evidence = storage::load_evidence(conn, None)?;
+            batch.event_relations = storage::load_event_relations(conn, None)?;
+        }
+        Ok(Some(batch))
     }
-    Ok(resp.json().await?)
+}
+
+/// Отправить локальные данные в /sync пира партиями, каждая подписана; итоги партий суммируются
+#[cfg(any(test, feature = "p2p-client-sync"))]
+async fn push_batches(
+    client: &Client,
+    peer_url: &str,
+    identity: &CryptoIdentity,
+    conn: &Connection,
+) -> anyhow::Result<SyncResult> {
+    let mut pages = PushPages::new();
+    let mut total: Option<SyncResult> = None;
+    while let Some(sync_data) = pages.next_batch(conn)? {
+        let ts = Utc::now().timestamp();
+        let ratings_hash = compute_ratings_hash(&sync_data.node_ratings, &sync_data.group_ratings)?;
+        let message = format!("sync_push:{}:{}", ts, ratings_hash);
+        let sig = identity.sign(message.as_bytes());
+        let signature_hex = hex::encode(sig.to_bytes());
+        let public_key_hex = identity.public_key_hex();
+
+        let resp = client
+            .post(format!("{peer_url}/sync"))
+            .header("X-Public-Key", public_key_hex)
+            .header("X-Signature", signature_hex)
+            .header("X-Timestamp", ts.to_string())
+            .header("X-Ratings-Hash", ratings_hash)
+            .json(&sync_data)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            anyhow::bail!("Peer sync push failed: {}", resp.status());
+        }
+        let part: SyncResult = resp.json().await?;
+        match total.as_mut() {
+            Some(t) => t.absorb(part),
+            None => total = Some(part),
+        }
+    }
+    total.ok_or_else(|| anyhow::anyhow!("nothing to push"))
 }
 
 /// Pull remote data from peer by combining /get_data and /statements
@@ -344,34 +442,109 @@ pub async fn pull_remote_data(peer_url: &str, identity: &CryptoIdentity) -> anyh
     let signature_hex = hex::encode(sig.to_bytes());
     let public_key_hex = identity.public_key_hex();
 
-    // get events, impacts, metrics
-    let resp_main = client
-        .get(format!("{peer_url}/get_data"))
-        .header("X-Public-Key", public_key_hex.clone())
-        .header("X-Signature", signature_hex.clone())
-        .header("X-Timestamp", ts.to_string())
-        .send()
-        .await?;
-    if !resp_main.status().is_success() {
-        anyhow::bail!("Peer returned non-success status: {}", resp_main.status());
+    // get events, impacts, metrics: события и влияния листаются курсорами,
+    // исчерпанная коллекция запрашивается с курсором `end`
+    let mut events: Vec<TruthEvent> = Vec::new();
+    let mut impacts: Vec<Impact> = Vec::new();
+    let mut metrics: Vec<ProgressMetrics> = Vec::new();
+    let mut trust_delegations: Vec<TrustDelegation> = Vec::new();
+    let mut tombstones: Vec<Tombstone> = Vec::new();
+    let mut knowledge_base: Vec<KbEntry> = Vec::new();
+    let mut evidence: Vec<Evidence> = Vec::new();
+    let mut event_relations: Vec<EventRelation> = Vec::new();
+    let mut events_cursor: Option<String> = None;
+    let mut impacts_cursor: Option<String> = None;
+    let mut first_page = true;
+    loop {
+        let mut query: Vec<(&str, String)> = Vec::new();
+        if let Some(c) = &events_cursor { query.push(("events_cursor", c.clone())); }
+        if let Some(c) = &impacts_cursor { query.push(("impacts_cursor", c.clone())); }
+        let resp_main = client
+            .get(format!("{peer_url}/get_data"))
+            .query(&query)
+            .header("X-Public-Key", public_key_hex.clone())
+            .header("X-Signature", signature_hex.clone())
+            .header("X-Timestamp", ts.to_string())
+            .send()
+            .await?;
+        if !resp_main.status().is_success() {
+            anyhow::bail!("Peer returned non-success status: {}", resp_main.status());
+        }
+        let v = resp_main.json::<serde_json::Value>().await?;
+        events.extend(serde_json::from_value::<Vec<TruthEvent>>(v.get("events").cloned().unwrap_or_default())?);
+        impacts.extend(serde_json::from_value::<Vec<Impact>>(v.get("impacts").cloned().unwrap_or_default())?);
+        if first_page {
+            metrics = serde_json::from_value(v.get("metrics").cloned().unwrap_or_default())?;
+            trust_delegations = v
+                .get("trust_delegations")
+                .cloned()
+                .map(serde_json::from_value)
+                .transpose()?
+                .unwrap_or_default();
+            tombstones = v
+                .get("tombstones")
+                .cloned()
+                .map(serde_json::from_value)
+                .transpose()?
+                .unwrap_or_default();
+            knowledge_base = v
+                .get("knowledge_base")
+                .cloned()
+                .map(serde_json::from_value)
+                .transpose()?
+                .unwrap_or_default();
+            evidence = v
+                .get("evidence")
+                .cloned()
+                .map(serde_json::from_value)
+                .transpose()?
+                .unwrap_or_default();
+            event_relations = v
+                .get("event_relations")
+                .cloned()
+                .map(serde_json::from_value)
+                .transpose()?
+                .unwrap_or_default();
+            first_page = false;
+        }
+        let next = |key: &str| v.get(key).and_then(|c| c.as_str()).map(|c| c.to_string());
+        let (next_events, next_impacts) = (next("next_events_cursor"), next("next_impacts_cursor"));
+        if next_events.is_none() && next_impacts.is_none() {
+            break;
+        }
+        events_cursor = Some(next_events.unwrap_or_else(|| storage::CURSOR_END.to_string()));
+        impacts_cursor = Some(next_impacts.unwrap_or_else(|| storage::CURSOR_END.to_string()));
     }
-    let v = resp_main.json::<serde_json::Value>().await?;
-    let events: Vec<TruthEvent> = serde_json::from_value(v.get("events").cloned().unwrap_or_default())?;
-    let impacts: Vec<Impact> = serde_json::from_value(v.get("impacts").cloned().unwrap_or_default())?;
-    let metrics: Vec<ProgressMetrics> = serde_json::from_value(v.get("metrics").cloned().unwrap_or_default())?;
 
-    // get statements
-    let resp_stmts = client
-        .get(format!("{peer_url}/statements"))
-        .header("X-Public-Key", public_key_hex)
-        .header("X-Signature", signature_hex)
-        .header("X-Timestamp", ts.to_string())
-        .send()
-        .await?;
-    if !resp_stmts.status().is_success() {
-        anyhow::bail!("Peer returned non-success status: {}", resp_stmts.status());
+    // get statements (курсор следующей страницы — в заголовке X-Next-Cursor)
+    let mut statements: Vec<Statement> = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut req = client
+            .get(format!("{peer_url}/statements"))
+            .query(&[("limit", storage::PAGE_MAX_LIMIT.to_string())]);
+        if let Some(c) = &cursor {
+            req = req.query(&[("cursor", c.as_str())]);
+        }
+        let resp_stmts = req
+            .header("X-Public-Key", public_key_hex.clone())
+            .header("X-Signature", signature_hex.clone())
+            .header("X-Timestamp", ts.to_string())
+            .send()
+            .await?;
+        if !resp_stmts.status().is_success() {
+            anyhow::bail!("Peer returned non-success status: {}", resp_stmts.status());
+        }
+        cursor = resp_stmts
+            .headers()
+            .get("X-Next-Cursor")
+            .and_then(|v| v.to_str().ok())
+            .map(|c| c.to_string());
+        statements.extend(resp_stmts.json::<Vec<Statement>>().await?);
+        if cursor.is_none() {
+            break;
+        }
     }
-    let statements: Vec<Statement> = resp_stmts.json().await?;
 
     Ok(SyncData {
         events,
@@ -381,191 +554,224 @@ pub async fn pull_remote_data(peer_url: &str, identity: &CryptoIdentity) -> anyh
         node_ratings: Vec::new(),
         group_ratings: Vec::new(),
         node_metrics: Vec::new(),
+        trust_delegations,
+        tombstones,
+        knowledge_base,
+        evidence,
+        event_relations,
         last_sync: ts,
     })
 }
 
-/// Reconcile remote data into local DB using timestamp-based conflict resolution and log via sync_log
-pub fn reconcile(conn: &Connection, remote: &SyncData) -> anyhow::Result<SyncResult> {
-    let mut conflicts_resolved = 0u32;
-    let mut events_added = 0u32;
-    let mut statements_added = 0u32;
-    let mut impacts_added = 0u32;
-    // trust_changes будет заполнен после merge
+/// Проверить подписи делегирования (выдачи и, если есть, отзыва) ключом delegator
+pub fn verify_delegation(d: &TrustDelegation) -> anyhow::Result<()> {
+    let identity = crate::p2p::encryption::CryptoIdentity::from_public_key_hex(&d.delegator)
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    identity
+        .verify_from_hex(d.signing_message().as_bytes(), &d.signature)
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    if let Some(revoked_at) = d.revoked_at {
+        let sig = d
+            .revocation_signature
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("revocation without signature"))?;
+        identity
+            .verify_from_hex(TrustDelegation::revocation_message(&d.id, revoked_at).as_bytes(), sig)
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    }
+    Ok(())
+}
 
-    // Events
+/// Итог слияния событий, утверждений и влияний
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecordsMerged {
+    pub conflicts_resolved: u32,
+    pub events_added: u32,
+    pub statements_added: u32,
+    pub impacts_added: u32,
+    /// Записи, удалённые по отметкам пира
+    pub tombstones_applied: u32,
+}
+
+/// Слияние событий, утверждений и влияний пира через Store (last-write-wins; влияния только добавляются).
+/// Работает с любым хранилищем: SQLite-соединением узла или MemoryStore в тестах.
+pub fn reconcile_records<S: Store + ?Sized>(store: &S, remote: &SyncData) -> Result<RecordsMerged, CoreError> {
+    let mut out = RecordsMerged::default();
+
+    // Отметки удаления — до записей, чтобы удалённое пиром не вставлялось из того же пакета
+    for t in &remote.tombstones {
+        match tombstone::apply(store, t) {
+            Ok(tombstone::Applied::Deleted(_)) => out.tombstones_applied += 1,
+            Ok(tombstone::Applied::Forbidden) => log::warn!("tombstone {}: {} may not delete {}/{}", t.id, t.author, t.table_name, t.record_id),
+            Ok(_) => {}
+            Err(CoreError::InvalidArg(e)) => log::warn!("tombstone {}: {}", t.id, e),
+            Err(e) => return Err(e),
+        }
+    }
+
+    // Events: записи сопоставляются по uid; побеждает более поздний timestamp_start
     for ev in &remote.events {
-        let existing = storage::get_truth_event(conn, ev.id).map_err(|e| anyhow::anyhow!(e.to_string()))?;
-        match existing {
-            Some(local) => {
-                if ev.timestamp_start > local.timestamp_start {
-                    // remote wins -> update
-                    conn.execute(
-                        r#"UPDATE truth_events SET
-                            description=?2, context_id=?3, vector=?4, detected=?5, corrected=?6,
-                            timestamp_start=?7, timestamp_end=?8, code=?9, signature=?10, public_key=?11
-                          WHERE id=?1"#,
-                        params![
-                            ev.id,
-                            ev.description,
-                            ev.context_id,
-                            if ev.vector { 1 } else { 0 },
-                            ev.detected.map(|v| if v { 1 } else { 0 }),
-                            if ev.corrected { 1 } else { 0 },
-                            ev.timestamp_start,
-                            ev.timestamp_end,
-                            ev.code as i64,
-                            ev.signature,
-                            ev.public_key,
-                        ],
-                    )?;
-                    conflicts_resolved += 1;
-                    events_added += 1; // count updates as added for simplicity
-                    storage::log_sync(
-                        conn,
-                        "update",
-                        "truth_events",
-                        &ev.id.to_string(),
-                        ev.signature.clone(),
-                        ev.public_key.clone(),
-                    )?;
-                }
+        let uid = event_uid(ev);
+        if tombstone::suppresses(store, "truth_events", &uid, ev.public_key.as_deref())? {
+            continue;
+        }
+        let op = match store.get_event_by_uid(&uid)? {
+            Some(local) if ev.timestamp_start > local.timestamp_start => {
+                out.conflicts_resolved += 1;
+                "update"
             }
-            None => {
-                conn.execute(
-                    r#"INSERT OR IGNORE INTO truth_events
-                        (id, description, context_id, vector, detected, corrected, timestamp_start, timestamp_end, code, signature, public_key)
-                      VALUES
-                        (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)"#,
-                    params![
-                        ev.id,
-                        ev.description,
-                        ev.context_id,
-                        if ev.vector { 1 } else { 0 },
-                        ev.detected.map(|v| if v { 1 } else { 0 }),
-                        if ev.corrected { 1 } else { 0 },
-                        ev.timestamp_start,
-                        ev.timestamp_end,
-                        ev.code as i64,
-                        ev.signature,
-                        ev.public_key,
-                    ],
-                )?;
-                events_added += 1;
-                storage::log_sync(
-                    conn,
-                    "insert",
-                    "truth_events",
-                    &ev.id.to_string(),
-                    ev.signature.clone(),
-                    ev.public_key.clone(),
-                )?;
+            Some(_) => continue,
+            None => "insert",
+        };
+        store.put_event(&TruthEvent { uid: uid.clone(), ..ev.clone() })?;
+        out.events_added += 1; // обновления считаются добавленными для простоты
+        store.log_sync(op, "truth_events", &uid, ev.signature.clone(), ev.public_key.clone())?;
+    }
+
+    // Statements: побеждает более поздний updated_at; без события локально и с неизвестным отношением к родителю не вставляются
+    for st in &remote.statements {
+        let parent = if st.event_uid.is_empty() { legacy_event_uid(store, remote, st.event_id)? } else { Some(st.event_uid.clone()) };
+        let Some(parent) = parent else { continue };
+        let uid = if st.uid.is_empty() { st.legacy_uid(&parent) } else { st.uid.clone() };
+        if tombstone::suppresses(store, "statements", &uid, st.public_key.as_deref())?
+            || tombstone::parent_deleted(store, &parent)?
+            || store.get_event_by_uid(&parent)?.is_none()
+            || core_lib::argument::check_relation(st.parent_uid.as_deref(), st.relation.as_deref()).is_err()
+        {
+            continue;
+        }
+        let op = match store.get_statement_by_uid(&uid)? {
+            Some(local) if st.updated_at > local.updated_at => {
+                out.conflicts_resolved += 1;
+                "update"
             }
+            Some(_) => continue,
+            None => "insert",
+        };
+        store.put_statement(&Statement { uid: uid.clone(), event_uid: parent, ..st.clone() })?;
+        out.statements_added += 1;
+        store.log_sync(op, "statements", &uid, st.signature.clone(), st.public_key.clone())?;
+    }
+
+    // Impacts (append-only); event_id — uid события, у пира до глобальных id — его целый id
+    for im in &remote.impacts {
+        let parent = match im.event_id.parse::<i64>() {
+            Ok(id) => legacy_event_uid(store, remote, id)?,
+            Err(_) => Some(im.event_id.clone()),
+        };
+        let Some(parent) = parent else { continue };
+        // Степень вне диапазона или value не совпадает с её знаком — запись не принимается:
+        // хранилище пересчитало бы value, и строка разошлась бы с подписанной
+        if Impact::check_grade(im.grade(), im.confidence).is_err()
+            || !im.sign_consistent()
+            || tombstone::suppresses(store, "impact", &im.id, im.public_key.as_deref())?
+            || tombstone::parent_deleted(store, &parent)?
+            || store.get_event_by_uid(&parent)?.is_none()
+        {
+            continue;
+        }
+        if store.insert_impact(&Impact { event_id: parent, ..im.clone() })? {
+            out.impacts_added += 1;
+            store.log_sync("insert", "impact", &im.id, im.signature.clone(), im.public_key.clone())?;
         }
     }
+    Ok(out)
+}
 
-    // Statements
-    for st in &remote.statements {
-        let mut stmt = conn.prepare("SELECT updated_at FROM statements WHERE id=?1")?;
-        let upd: Option<i64> = stmt.query_row(params![st.id], |r| r.get(0)).optional()?;
-        match upd {
-            Some(local_updated_at) => {
-                if st.updated_at > local_updated_at {
-                    conn.execute(
-                        r#"UPDATE statements SET
-                            event_id=?2, text=?3, context=?4, truth_score=?5, created_at=?6, updated_at=?7, signature=?8, public_key=?9
-                          WHERE id=?1"#,
-                        params![
-                            st.id,
-                            st.event_id,
-                            st.text,
-                            st.context,
-                            st.truth_score,
-                            st.created_at,
-                            st.updated_at,
-                            st.signature,
-                            st.public_key,
-                        ],
-                    )?;
-                    conflicts_resolved += 1;
-                    statements_added += 1;
-                    storage::log_sync(
-                        conn,
-                        "update",
-                        "statements",
-                        &st.id.to_string(),
-                        st.signature.clone(),
-                        st.public_key.clone(),
-                    )?;
+/// uid события пира; у пира до глобальных id он пуст и выводится из содержимого события
+fn event_uid(ev: &TruthEvent) -> String {
+    if ev.uid.is_empty() { ev.legacy_uid() } else { ev.uid.clone() }
+}
+
+/// uid события, на которое запись пира до глобальных id ссылается целым id: сначала среди событий
+/// того же пакета, затем локально (так такие ссылки разрешались раньше)
+fn legacy_event_uid<S: Store + ?Sized>(store: &S, remote: &SyncData, id: i64) -> Result<Option<String>, CoreError> {
+    if let Some(ev) = remote.events.iter().find(|e| e.id == id) {
+        return Ok(Some(event_uid(ev)));
+    }
+    Ok(store.get_event(id)?.map(|e| e.uid))
+}
+
+/// Reconcile с учётом отправителя: вес его рейтингов и метрик масштабируется его trust/quality,
+/// рейтинги отправителя ниже порога доверия не принимаются
+pub fn reconcile_from(conn: &Connection, remote: &SyncData, sender: Option<&str>) -> anyhow::Result<SyncResult> {
+    // В журнал аудита изменения попадают от имени отправителя
+    core_lib::audit::with_actor(sender.unwrap_or(core_lib::audit::PEER_ACTOR), || merge_remote(conn, remote, sender))
+}
+
+fn merge_remote(conn: &Connection, remote: &SyncData, sender: Option<&str>) -> anyhow::Result<SyncResult> {
+    let mut errors: Vec<String> = Vec::new();
+    // База знаний — до событий: они могут ссылаться на новые контексты
+    let kb_entries_applied = reconcile_knowledge_base(conn, &remote.knowledge_base, &mut errors);
+
+    // trust_changes будет заполнен после merge
+    let RecordsMerged { conflicts_resolved, events_added, statements_added, impacts_added, tombstones_applied } =
+        reconcile_records(conn, remote).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    // Доказательства — после событий и утверждений, к которым прикреплены
+    let evidence_added = reconcile_evidence(conn, &remote.evidence, &mut errors);
+    // Связи событий — тоже после записей; затем в канонические события сливаются и записи,
+    // пришедшие к дубликатам в этой синхронизации
+    let event_relations_added = reconcile_event_relations(conn, &remote.event_relations, &mut errors);
+    if let Err(e) = event_relation::fold_duplicates(conn) {
+        errors.push(format!("duplicates: {}", e));
+    }
+
+    // Делегирования доверия: принимаем только с валидной подписью от известного узла с trust >= REMOTE_TRUST_FLOOR
+    // (как и через API, где нужна роль node); свежий ключ делегировать не может.
+    // Отзыв уже принятого делегирования достаточно подписи: доверие к делегатору могло с тех пор упасть
+    let mut delegations_added = 0u32;
+    for d in &remote.trust_delegations {
+        if let Err(e) = verify_delegation(d) {
+            errors.push(format!("delegation {}: {}", d.id, e));
+            continue;
+        }
+        let revokes_known = match d.revoked_at {
+            Some(_) => match storage::get_trust_delegation(conn, &d.id) {
+                Ok(local) => local.is_some(),
+                Err(e) => {
+                    errors.push(format!("delegation {}: {}", d.id, e));
+                    continue;
+                }
+            },
+            None => false,
+        };
+        if !revokes_known {
+            match storage::delegator_trust(conn, &d.delegator) {
+                Ok(Some(_)) => {}
+                Ok(None) => {
+                    errors.push(format!("delegation {}: delegator is not a trusted node", d.id));
+                    continue;
+                }
+                Err(e) => {
+                    errors.push(format!("delegation {}: {}", d.id, e));
+                    continue;
                 }
             }
-            None => {
-                conn.execute(
-                    r#"INSERT OR IGNORE INTO statements
-                        (id, event_id, text, context, truth_score, created_at, updated_at, signature, public_key)
-                      VALUES
-                        (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"#,
-                    params![
-                        st.id,
-                        st.event_id,
-                        st.text,
-                        st.context,
-                        st.truth_score,
-                        st.created_at,
-                        st.updated_at,
-                        st.signature,
-                        st.public_key,
-                    ],
-                )?;
-                statements_added += 1;
+        }
+        match core_lib::storage::upsert_trust_delegation(conn, d) {
+            Ok(true) => {
+                delegations_added += 1;
                 storage::log_sync(
                     conn,
-                    "insert",
-                    "statements",
-                    &st.id.to_string(),
-                    st.signature.clone(),
-                    st.public_key.clone(),
+                    if d.revoked_at.is_some() { "revoke" } else { "insert" },
+                    "trust_delegations",
+                    &d.id,
+                    Some(d.signature.clone()),
+                    Some(d.delegator.clone()),
                 )?;
             }
+            Ok(false) => {}
+            Err(e) => errors.push(format!("delegation {}: {}", d.id, e)),
         }
     }
 
-    // Impacts (append-only)
-    for im in &remote.impacts {
-        let mut stmt = conn.prepare("SELECT 1 FROM impact WHERE id=?1")?;
-        let exists: Option<i64> = stmt.query_row(params![im.id.clone()], |r| r.get(0)).optional()?;
-        if exists.is_none() {
-            conn.execute(
-                r#"INSERT OR IGNORE INTO impact
-                    (id, event_id, type_id, value, notes, created_at, signature, public_key)
-                  VALUES
-                    (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"#,
-                params![
-                    im.id,
-                    im.event_id,
-                    im.type_id,
-                    if im.value { 1 } else { 0 },
-                    im.notes,
-                    im.created_at,
-                    im.signature,
-                    im.public_key,
-                ],
-            )?;
-            impacts_added += 1;
-            storage::log_sync(
-                conn,
-                "insert",
-                "impact",
-                &im.id,
-                im.signature.clone(),
-                im.public_key.clone(),
-            )?;
-        }
-    }
-
-    // Ratings merge: принимаем все входящие записи без фильтра доверия
-    let diffs = core_lib::storage::merge_ratings(
+    // Вес отправителя фиксируем до слияния, чтобы входящие рейтинги не влияли на собственный вес
+    let remote_weight = load_sender_weight(conn, sender).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+    // Ratings merge: вес входящих записей зависит от доверия к отправителю
+    let diffs = core_lib::storage::merge_ratings_from(
         conn,
+        sender,
         &remote.node_ratings,
         &remote.group_ratings,
     ).map_err(|e| anyhow::anyhow!(e.to_string()))?;
@@ -576,21 +782,24 @@ pub fn reconcile(conn: &Connection, remote: &SyncData) -> anyhow::Result<SyncRes
 
     let trust_changes: Vec<TrustDelta> = diffs
         .into_iter()
-        .map(|(node_id, delta)| TrustDelta { node_id, delta })
+        .map(|(node_id, delta)| TrustDelta { node_id, delta, sender: sender.map(|s| s.to_string()) })
         .collect();
 
     // После слияния доверия — распространяем качество и приоритет: для каждого метрика из remote применяем правило смешивания
-    for m in &remote.node_metrics {
-        let prev = core_lib::storage::load_node_metrics(conn, &m.pubkey)?;
-        let local_q = prev.as_ref().map(|pm| pm.quality_index).unwrap_or(0.0);
-        let blended_q = blend_quality(local_q, m.quality_index);
-        core_lib::storage::update_node_quality(conn, &m.pubkey, blended_q)?;
-
-        // Приоритет распространения: смешиваем локальный и удалённый
-        let local_p = prev.as_ref().map(|pm| pm.propagation_priority).unwrap_or(0.0);
-        let remote_p = m.propagation_priority;
-        let blended_p = blend_priority(local_p, remote_p);
-        let _ = core_lib::storage::update_node_priority(conn, &m.pubkey, blended_p);
+    // (метрики от отправителя ниже порога доверия пропускаем)
+    if let Some(w) = remote_weight {
+        for m in &remote.node_metrics {
+            let prev = core_lib::storage::load_node_metrics(conn, &m.pubkey)?;
+            let local_q = prev.as_ref().map(|pm| pm.quality_index).unwrap_or(0.0);
+            let blended_q = blend_quality_weighted(local_q, m.quality_index, w);
+            core_lib::storage::update_node_quality(conn, &m.pubkey, blended_q)?;
+
+            // Приоритет распространения: смешиваем локальный и удалённый
+            let local_p = prev.as_ref().map(|pm| pm.propagation_priority).unwrap_or(0.0);
+            let remote_p = m.propagation_priority;
+            let blended_p = blend_priority_weighted(local_p, remote_p, w);
+            let _ = core_lib::storage::update_node_priority(conn, &m.pubkey, blended_p);
+        }
     }
 
     // Лог высокого уровня о доверии
@@ -601,7 +810,9 @@ pub fn reconcile(conn: &Connection, remote: &SyncData) -> anyhow::Result<SyncRes
     let losses = trust_changes.iter().filter(|d| d.delta < 0.0).count();
     let equals = trust_changes.len().saturating_sub(gains + losses);
     let details = format!(
-        "trust propagation: avg={:.3}, changes={} (gains {}, losses {}, equal {}); sample={}",
+        "trust propagation: sender={}, weight={}, avg={:.3}, changes={} (gains {}, losses {}, equal {}); sample={}",
+        sender.map(|s| s.get(0..8).unwrap_or(s)).unwrap_or("-"),
+        remote_weight.map(|w| format!("{:.3}", w)).unwrap_or_else(|| "ignored".into()),
         avg_trust,
         trust_changes.len(),
         gains,
@@ -633,13 +844,68 @@ pub fn reconcile(conn: &Connection, remote: &SyncData) -> anyhow::Result<SyncRes
         events_added,
         statements_added,
         impacts_added,
-        errors: vec![],
+        errors,
         nodes_trust_changed: trust_changes.len() as u32,
         trust_diff: trust_changes,
         avg_quality_index,
+        delegations_added,
+        tombstones_applied,
+        kb_entries_applied,
+        evidence_added,
+        event_relations_added,
     })
 }
 
+/// Применить правки базы знаний пира: подпись и права администратора проверяет knowledge::apply,
+/// записи идут в порядке KbEntry::KINDS (контексты — после записей, на которые ссылаются)
+fn reconcile_knowledge_base(conn: &Connection, entries: &[KbEntry], errors: &mut Vec<String>) -> u32 {
+    let mut sorted: Vec<&KbEntry> = entries.iter().collect();
+    sorted.sort_by_key(|e| (KbEntry::KINDS.iter().position(|k| *k == e.kind).unwrap_or(usize::MAX), e.updated_at));
+    let mut applied = 0u32;
+    for e in sorted {
+        match knowledge::apply(conn, e) {
+            Ok(knowledge::Applied::Created | knowledge::Applied::Updated) => applied += 1,
+            Ok(knowledge::Applied::Stale) => {}
+            Ok(knowledge::Applied::Forbidden) => errors.push(format!("{} {}: signer is not an admin", e.kind, e.id)),
+            Err(err) => errors.push(format!("{} {}: {}", e.kind, e.id, err)),
+        }
+    }
+    applied
+}
+
+/// Применить доказательства пира: подпись проверяет evidence::apply; доказательства к записям,
+/// которых локально ещё нет, пропускаются до следующей синхронизации, к удалённым — отклоняются
+fn reconcile_evidence(conn: &Connection, items: &[Evidence], errors: &mut Vec<String>) -> u32 {
+    let mut added = 0u32;
+    for e in items {
+        match evidence::apply(conn, e) {
+            Ok(evidence::Applied::Created) => added += 1,
+            Ok(evidence::Applied::Known | evidence::Applied::MissingTarget) => {}
+            Ok(evidence::Applied::Deleted) => log::info!("evidence {}: {}/{} is deleted", e.id, e.target_table, e.target_uid),
+            Err(err) => errors.push(format!("evidence {}: {}", e.id, err)),
+        }
+    }
+    added
+}
+
+/// Применить связи событий пира: подпись и право на duplicate_of/supersedes проверяет event_relation::apply;
+/// связи с событиями, которых локально ещё нет, пропускаются до следующей синхронизации
+fn reconcile_event_relations(conn: &Connection, items: &[EventRelation], errors: &mut Vec<String>) -> u32 {
+    let mut added = 0u32;
+    for rel in items {
+        match event_relation::apply(conn, rel) {
+            Ok(event_relation::Applied::Created) => added += 1,
+            Ok(event_relation::Applied::Known | event_relation::Applied::MissingTarget) => {}
+            Ok(event_relation::Applied::Deleted) => log::info!("event relation {}: an event is deleted", rel.id),
+            Ok(event_relation::Applied::Forbidden) => {
+                errors.push(format!("event relation {}: signer may not mark {} as {}", rel.id, rel.source_uid, rel.relation))
+            }
+            Err(err) => errors.push(format!("event relation {}: {}", rel.id, err)),
+        }
+    }
+    added
+}
+
 /// Инкрементальная синхронизация - только изменения с последней синхронизации
 #[cfg(any(test, feature = "p2p-client-sync"))]
 #[allow(dead_code)]
@@ -666,6 +932,11 @@ pub async fn incremental_sync_with_peer(
             node_ratings: node_ratings.clone(),
             group_ratings: group_ratings.clone(),
             node_metrics,
+            trust_delegations: core_lib::storage::load_trust_delegations(conn, None)?,
+            tombstones: core_lib::storage::load_tombstones(conn, Some(last_sync_timestamp))?,
+            knowledge_base: core_lib::storage::load_kb_changes(conn, Some(last_sync_timestamp))?,
+            evidence: core_lib::storage::load_evidence(conn, Some(last_sync_timestamp))?,
+            event_relations: core_lib::storage::load_event_relations(conn, Some(last_sync_timestamp))?,
             last_sync: last_sync_timestamp,
         };
 
@@ -741,13 +1012,14 @@ pub async fn incremental_sync_with_peer(
 #[allow(dead_code)]
 fn get_events_since(conn: &Connection, timestamp: i64) -> anyhow::Result<Vec<TruthEvent>> {
     let mut stmt = conn.prepare(
-        "SELECT id, description, context_id, vector, detected, corrected, timestamp_start, timestamp_end, code, signature, public_key, collective_score \
+        "SELECT id, description, context_id, vector, detected, corrected, timestamp_start, timestamp_end, code, signature, public_key, collective_score, uid \
          FROM truth_events WHERE timestamp_start > ?1 ORDER BY timestamp_start"
     )?;
 
     let rows = stmt.query_map(params![timestamp], |row| {
         Ok(TruthEvent {
             id: row.get(0)?,
+            uid: row.get(12)?,
             description: row.get(1)?,
             context_id: row.get(2)?,
             vector: row.get::<_, i64>(3)? != 0,
@@ -764,7 +1036,9 @@ fn get_events_since(conn: &Connection, timestamp: i64) -> anyhow::Result<Vec<Tru
 
     let mut events = Vec::new();
     for e in rows {
-        events.push(e?);
+        let mut e = e?;
+        e.signature = core_lib::at_rest::open(conn, "truth_events", "signature", &e.uid, e.signature)?;
+        events.push(e);
     }
     Ok(events)
 }
@@ -774,14 +1048,16 @@ fn get_events_since(conn: &Connection, timestamp: i64) -> anyhow::Result<Vec<Tru
 #[allow(dead_code)]
 fn get_statements_since(conn: &Connection, timestamp: i64) -> anyhow::Result<Vec<Statement>> {
     let mut stmt = conn.prepare(
-        "SELECT id, event_id, text, context, truth_score, created_at, updated_at, signature, public_key \
-         FROM statements WHERE created_at > ?1 ORDER BY created_at"
+        "SELECT s.id, s.event_id, s.text, s.context, s.truth_score, s.created_at, s.updated_at, s.signature, s.public_key, s.uid, COALESCE(e.uid, ''), \
+         s.parent_uid, s.relation FROM statements s LEFT JOIN truth_events e ON e.id = s.event_id WHERE s.created_at > ?1 ORDER BY s.created_at"
     )?;
 
     let rows = stmt.query_map(params![timestamp], |row| {
         Ok(Statement {
             id: row.get(0)?,
+            uid: row.get(9)?,
             event_id: row.get(1)?,
+            event_uid: row.get(10)?,
             text: row.get(2)?,
             context: row.get(3)?,
             truth_score: row.get(4)?,
@@ -789,12 +1065,16 @@ fn get_statements_since(conn: &Connection, timestamp: i64) -> anyhow::Result<Vec
             updated_at: row.get(6)?,
             signature: row.get(7)?,
             public_key: row.get(8)?,
+            parent_uid: row.get(11)?,
+            relation: row.get(12)?,
         })
     })?;
 
     let mut statements = Vec::new();
     for s in rows {
-        statements.push(s?);
+        let mut s = s?;
+        s.signature = core_lib::at_rest::open(conn, "statements", "signature", &s.uid, s.signature)?;
+        statements.push(s);
     }
     Ok(statements)
 }
@@ -804,7 +1084,7 @@ fn get_statements_since(conn: &Connection, timestamp: i64) -> anyhow::Result<Vec
 #[allow(dead_code)]
 fn get_impacts_since(conn: &Connection, timestamp: i64) -> anyhow::Result<Vec<Impact>> {
     let mut stmt = conn.prepare(
-        "SELECT id, event_id, type_id, value, notes, created_at, signature, public_key \
+        "SELECT id, event_id, type_id, value, notes, created_at, signature, public_key, magnitude, confidence \
          FROM impact WHERE created_at > ?1 ORDER BY created_at"
     )?;
 
@@ -814,6 +1094,8 @@ fn get_impacts_since(conn: &Connection, timestamp: i64) -> anyhow::Result<Vec<Im
             event_id: row.get(1)?,
             type_id: row.get(2)?,
             value: row.get::<_, i64>(3)? != 0,
+            magnitude: row.get(8)?,
+            confidence: row.get(9)?,
             notes: row.get(4)?,
             created_at: row.get(5)?,
             signature: row.get(6)?,
@@ -823,7 +1105,9 @@ fn get_impacts_since(conn: &Connection, timestamp: i64) -> anyhow::Result<Vec<Im
 
     let mut impacts = Vec::new();
     for i in rows {
-        impacts.push(i?);
+        let mut i = i?;
+        i.signature = core_lib::at_rest::open(conn, "impact", "signature", &i.id, i.signature)?;
+        impacts.push(i);
     }
     Ok(impacts)
 }
@@ -862,3 +1146,586 @@ pub fn resolve_event_conflicts(
 
     resolved_events
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core_lib::store::MemoryStore;
+
+    fn event(id: i64, description: &str, ts: i64) -> TruthEvent {
+        TruthEvent {
+            id,
+            uid: String::new(),
+            description: description.into(),
+            context_id: 1,
+            vector: true,
+            detected: None,
+            corrected: false,
+            timestamp_start: ts,
+            timestamp_end: None,
+            code: 1,
+            signature: None,
+            public_key: None,
+            collective_score: None,
+        }
+    }
+
+    #[test]
+    fn reconcile_records_merges_into_memory_store() {
+        let store = MemoryStore::new();
+        let with_uid = |id: i64, description: &str, ts: i64| TruthEvent { uid: format!("ev-{}", id), ..event(id, description, ts) };
+        store.put_event(&with_uid(1, "local", 100)).unwrap();
+        store.put_event(&with_uid(2, "local newer", 300)).unwrap();
+
+        let impact = Impact {
+            id: "imp-1".into(),
+            event_id: "1".into(),
+            type_id: 1,
+            value: true,
+            magnitude: None,
+            confidence: None,
+            notes: None,
+            created_at: 10,
+            signature: None,
+            public_key: None,
+        };
+        let remote = SyncData {
+            events: vec![with_uid(1, "remote", 200), with_uid(2, "remote older", 200), with_uid(3, "new", 50)],
+            statements: vec![],
+            impacts: vec![impact.clone(), impact],
+            metrics: vec![],
+            node_ratings: vec![],
+            group_ratings: vec![],
+            node_metrics: vec![],
+            trust_delegations: vec![],
+            tombstones: vec![],
+            knowledge_base: vec![],
+            evidence: vec![],
+            event_relations: vec![],
+            last_sync: 0,
+        };
+        let merged = reconcile_records(&store, &remote).unwrap();
+        assert_eq!(merged, RecordsMerged { conflicts_resolved: 1, events_added: 2, statements_added: 0, impacts_added: 1, tombstones_applied: 0 });
+        assert_eq!(store.get_event(1).unwrap().unwrap().description, "remote");
+        assert_eq!(store.get_event(2).unwrap().unwrap().description, "local newer");
+        assert!(store.get_event(3).unwrap().is_some());
+
+        // Повторное слияние ничего не меняет
+        assert_eq!(reconcile_records(&store, &remote).unwrap(), RecordsMerged::default());
+    }
+
+    #[test]
+    fn legacy_events_with_same_id_from_different_nodes_do_not_collide() {
+        let store = MemoryStore::new();
+        // Без uid и ключа автора: у обоих узлов это событие 5, но записи разные
+        let ours = event(5, "ours", 100);
+        store.put_event(&ours).unwrap();
+        let theirs = event(5, "theirs", 200);
+        let statement = Statement {
+            id: 5,
+            uid: String::new(),
+            event_id: 5,
+            event_uid: String::new(),
+            text: "confirmed".into(),
+            context: None,
+            truth_score: Some(1.0),
+            created_at: 100,
+            updated_at: 100,
+            signature: None,
+            public_key: None,
+            parent_uid: None,
+            relation: None,
+        };
+        let remote = SyncData {
+            // У пира наша копия хранится под его id 6
+            events: vec![theirs.clone(), TruthEvent { id: 6, ..ours.clone() }],
+            statements: vec![statement.clone()],
+            impacts: vec![],
+            metrics: vec![],
+            node_ratings: vec![],
+            group_ratings: vec![],
+            node_metrics: vec![],
+            trust_delegations: vec![],
+            tombstones: vec![],
+            knowledge_base: vec![],
+            evidence: vec![],
+            event_relations: vec![],
+            last_sync: 0,
+        };
+        // Новое только чужое событие и утверждение к нему; копия нашего узнаётся по контексту, времени и коду
+        let merged = reconcile_records(&store, &remote).unwrap();
+        assert_eq!((merged.events_added, merged.statements_added), (1, 1));
+        assert_ne!(ours.legacy_uid(), theirs.legacy_uid());
+        assert_eq!(store.get_event_by_uid(&ours.legacy_uid()).unwrap().unwrap().description, "ours");
+        assert_eq!(store.get_event_by_uid(&theirs.legacy_uid()).unwrap().unwrap().description, "theirs");
+        let stored = store.get_statement_by_uid(&statement.legacy_uid(&theirs.legacy_uid())).unwrap().unwrap();
+        assert_eq!(stored.event_uid, theirs.legacy_uid());
+    }
+
+    #[test]
+    fn impacts_from_old_peers_are_graded_by_value() {
+        let store = MemoryStore::new();
+        store.put_event(&event(1, "local", 100)).unwrap();
+        // Старый пир не знает magnitude и confidence
+        let old: Impact = serde_json::from_value(serde_json::json!({
+            "id": "old", "event_id": "1", "type_id": 1, "value": false, "notes": null,
+            "created_at": 10, "signature": null, "public_key": null
+        }))
+        .unwrap();
+        let graded = Impact { id: "graded".into(), value: true, magnitude: Some(0.4), confidence: Some(0.8), ..old.clone() };
+        let invalid = Impact { id: "invalid".into(), magnitude: Some(-3.0), ..old.clone() };
+        let mismatched = Impact { id: "mismatched".into(), value: true, magnitude: Some(-0.8), ..old.clone() };
+        let remote = SyncData {
+            events: vec![],
+            statements: vec![],
+            impacts: vec![old, graded, invalid, mismatched],
+            metrics: vec![],
+            node_ratings: vec![],
+            group_ratings: vec![],
+            node_metrics: vec![],
+            trust_delegations: vec![],
+            tombstones: vec![],
+            knowledge_base: vec![],
+            evidence: vec![],
+            event_relations: vec![],
+            last_sync: 0,
+        };
+        assert_eq!(reconcile_records(&store, &remote).unwrap().impacts_added, 2);
+        let old = store.get_impact("old").unwrap().unwrap();
+        assert_eq!((old.magnitude, old.confidence), (Some(-1.0), None));
+        let graded = store.get_impact("graded").unwrap().unwrap();
+        assert!((graded.weighted() - 0.32).abs() < 1e-9);
+        assert!(store.get_impact("invalid").unwrap().is_none());
+        assert!(store.get_impact("mismatched").unwrap().is_none());
+
+        // Новые поля не мешают старым пирам: value остаётся знаком оценки
+        let wire = serde_json::to_value(&graded).unwrap();
+        assert_eq!(wire["value"], true);
+        assert_eq!(wire["magnitude"], 0.4);
+    }
+
+    #[test]
+    fn reconcile_records_applies_tombstones() {
+        let author = CryptoIdentity::new();
+        let pk = author.public_key_hex();
+        let signed = |table: &str, id: &str| {
+            let mut t = Tombstone::new(table, id, &pk, None, 500);
+            t.signature = hex::encode(author.sign(t.signing_message().as_bytes()).to_bytes());
+            t
+        };
+        let own = |id: i64| TruthEvent { public_key: Some(pk.clone()), ..event(id, "own", 100 + id) };
+        let own_uid = |id: i64| own(id).legacy_uid();
+        let store = MemoryStore::new();
+        store.put_event(&own(1)).unwrap();
+        store.put_event(&event(2, "foreign", 100)).unwrap();
+
+        let mut forged = signed("truth_events", &own_uid(3));
+        forged.signature = signed("truth_events", &own_uid(4)).signature;
+        let remote = SyncData {
+            // Пир ещё хранит удалённые записи
+            events: vec![own(1), event(2, "foreign", 100), own(3), own(4)],
+            statements: vec![],
+            impacts: vec![Impact {
+                id: "imp-1".into(),
+                event_id: "1".into(),
+                type_id: 1,
+                value: true,
+                magnitude: None,
+                confidence: None,
+                notes: None,
+                created_at: 10,
+                signature: None,
+                public_key: None,
+            }],
+            metrics: vec![],
+            node_ratings: vec![],
+            group_ratings: vec![],
+            node_metrics: vec![],
+            trust_delegations: vec![],
+            tombstones: vec![
+                signed("truth_events", &own_uid(1)),
+                signed("truth_events", &event(2, "foreign", 100).legacy_uid()),
+                signed("truth_events", &own_uid(4)),
+                forged,
+            ],
+            knowledge_base: vec![],
+            evidence: vec![],
+            event_relations: vec![],
+            last_sync: 0,
+        };
+        let merged = reconcile_records(&store, &remote).unwrap();
+        // 1 удалено автором, 2 — чужое и остаётся, 3 — поддельная отметка, 4 — не воскресает
+        assert_eq!(merged, RecordsMerged { conflicts_resolved: 0, events_added: 1, statements_added: 0, impacts_added: 0, tombstones_applied: 1 });
+        assert!(store.get_event(1).unwrap().is_none());
+        assert!(store.get_event(2).unwrap().is_some());
+        assert!(store.get_event(3).unwrap().is_some());
+        assert!(store.get_event_by_uid(&own_uid(4)).unwrap().is_none());
+        assert_eq!(reconcile_records(&store, &remote).unwrap(), RecordsMerged::default());
+    }
+
+    #[test]
+    fn reconcile_records_matches_by_uid() {
+        let store = MemoryStore::new();
+        let mine = store
+            .add_event(core_lib::models::NewTruthEvent { description: "mine".into(), context_id: 1, vector: true, timestamp_start: 100, code: 1 })
+            .unwrap();
+
+        // Пир создал своё событие с тем же целым id, но позже
+        let theirs = TruthEvent { uid: "peer-event".into(), ..event(mine, "theirs", 200) };
+        let statement = |uid: &str, event_uid: &str| Statement {
+            id: 1,
+            uid: uid.into(),
+            event_id: mine,
+            event_uid: event_uid.into(),
+            text: "claim".into(),
+            context: None,
+            truth_score: None,
+            created_at: 10,
+            updated_at: 10,
+            signature: None,
+            public_key: None,
+            parent_uid: None,
+            relation: None,
+        };
+        let remote = SyncData {
+            events: vec![theirs],
+            statements: vec![statement("peer-statement", "peer-event"), statement("orphan", "unknown-event")],
+            impacts: vec![Impact {
+                id: "imp-1".into(),
+                event_id: "peer-event".into(),
+                type_id: 1,
+                value: true,
+                magnitude: None,
+                confidence: None,
+                notes: None,
+                created_at: 10,
+                signature: None,
+                public_key: None,
+            }],
+            metrics: vec![],
+            node_ratings: vec![],
+            group_ratings: vec![],
+            node_metrics: vec![],
+            trust_delegations: vec![],
+            tombstones: vec![],
+            knowledge_base: vec![],
+            evidence: vec![],
+            event_relations: vec![],
+            last_sync: 0,
+        };
+        let merged = reconcile_records(&store, &remote).unwrap();
+        assert_eq!(merged, RecordsMerged { conflicts_resolved: 0, events_added: 1, statements_added: 1, impacts_added: 1, tombstones_applied: 0 });
+        assert_eq!(store.get_event(mine).unwrap().unwrap().description, "mine");
+        let copy = store.get_event_by_uid("peer-event").unwrap().unwrap();
+        assert_ne!(copy.id, mine);
+        assert_eq!(store.get_statement_by_uid("peer-statement").unwrap().unwrap().event_id, copy.id);
+        assert!(store.get_statement_by_uid("orphan").unwrap().is_none());
+        assert_eq!(store.get_impact("imp-1").unwrap().unwrap().event_id, "peer-event");
+    }
+
+    #[test]
+    fn reconcile_from_applies_knowledge_base_before_events() {
+        let mut conn = storage::open_db(":memory:").unwrap();
+        storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let admin = ed25519_dalek::SigningKey::from_bytes(&[5; 32]);
+        storage::update_user_role(&conn, &hex::encode(admin.verifying_key().as_bytes()), "admin").unwrap();
+        let entry = |kind: &str, name: &str| KbEntry {
+            kind: kind.into(),
+            id: KbEntry::new_id(kind, name, "peer", 100),
+            name: name.into(),
+            description: None,
+            quality: None,
+            category_id: None,
+            forma_id: None,
+            cause_id: None,
+            develop_id: None,
+            effect_id: None,
+            updated_at: 100,
+            deprecated_at: None,
+            updated_by: None,
+            signature: None,
+        };
+        let category = entry("category", "Sports");
+        // Контекст пришёл раньше категории, на которую ссылается
+        let mut context = KbEntry { category_id: Some(category.id), ..entry("context", "Doping") };
+        knowledge::sign(&mut context, &admin);
+        let mut category = category;
+        knowledge::sign(&mut category, &admin);
+        let mut forged = entry("impact_type", "Fame");
+        knowledge::sign(&mut forged, &ed25519_dalek::SigningKey::from_bytes(&[6; 32]));
+
+        let remote = SyncData {
+            events: vec![TruthEvent { uid: "doping-case".into(), context_id: context.id, ..event(1, "doping case", 150) }],
+            statements: vec![],
+            impacts: vec![],
+            metrics: vec![],
+            node_ratings: vec![],
+            group_ratings: vec![],
+            node_metrics: vec![],
+            trust_delegations: vec![],
+            tombstones: vec![],
+            knowledge_base: vec![context.clone(), category, forged],
+            evidence: vec![],
+            event_relations: vec![],
+            last_sync: 0,
+        };
+        let result = reconcile_from(&conn, &remote, None).unwrap();
+        assert_eq!((result.kb_entries_applied, result.events_added), (2, 1));
+        assert_eq!(result.errors.len(), 1, "{:?}", result.errors);
+        assert_eq!(storage::get_kb_entry(&conn, "context", context.id).unwrap(), Some(context));
+        assert_eq!(storage::kb_usage(&conn, "impact_type", 0).unwrap(), 0);
+        assert_eq!(storage::load_kb_changes(&conn, None).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn push_pages_split_records_and_send_other_tables_last() {
+        let mut conn = storage::open_db(":memory:").unwrap();
+        storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let tx = conn.transaction().unwrap();
+        for i in 0..storage::PAGE_MAX_LIMIT + 5 {
+            tx.execute(
+                "INSERT INTO truth_events (uid, description, context_id, vector, corrected, timestamp_start, code) VALUES (?1, 'bulk', 1, 1, 0, ?2, 1)",
+                params![format!("bulk-{}", i), i as i64],
+            ).unwrap();
+        }
+        tx.commit().unwrap();
+        conn.execute(
+            "INSERT INTO node_ratings (node_id, events_true, events_false, validations, reused_events, trust_score, last_updated) VALUES ('node', 1, 0, 0, 0, 1.0, 100)",
+            [],
+        ).unwrap();
+
+        let mut pages = PushPages::new();
+        let mut batches = Vec::new();
+        while let Some(batch) = pages.next_batch(&conn).unwrap() {
+            batches.push(batch);
+        }
+        // Две страницы событий, пустые утверждения и влияния; рейтинги — только в последней партии
+        let sizes: Vec<_> = batches.iter().map(|b| (b.events.len(), b.statements.len(), b.impacts.len(), b.node_ratings.len())).collect();
+        assert_eq!(sizes, vec![(storage::PAGE_MAX_LIMIT, 0, 0, 0), (5, 0, 0, 0), (0, 0, 0, 0), (0, 0, 0, 1)]);
+        let mut uids: Vec<_> = batches.iter().flat_map(|b| b.events.iter().map(|e| e.uid.clone())).collect();
+        uids.dedup();
+        assert_eq!(uids.len(), storage::PAGE_MAX_LIMIT + 5);
+    }
+
+    #[test]
+    fn reconcile_from_rejects_delegations_from_unknown_keys() {
+        let conn = storage::open_db(":memory:").unwrap();
+        let signed = |key: &CryptoIdentity| {
+            let mut d = TrustDelegation::new(&key.public_key_hex(), "target", storage::MAX_DELEGATION_DELTA, 100, None);
+            d.signature = hex::encode(key.sign(d.signing_message().as_bytes()).to_bytes());
+            d
+        };
+        let trusted = CryptoIdentity::new();
+        conn.execute(
+            "INSERT INTO node_ratings (node_id, events_true, events_false, validations, reused_events, trust_score, last_updated) VALUES (?1, 1, 0, 0, 0, 1.0, 100)",
+            [trusted.public_key_hex()],
+        ).unwrap();
+        // Подписи валидны, но ключи свежие
+        let mut delegations: Vec<TrustDelegation> = (0..20).map(|_| signed(&CryptoIdentity::new())).collect();
+        delegations.push(signed(&trusted));
+
+        let remote = SyncData {
+            events: vec![],
+            statements: vec![],
+            impacts: vec![],
+            metrics: vec![],
+            node_ratings: vec![],
+            group_ratings: vec![],
+            node_metrics: vec![],
+            trust_delegations: delegations,
+            tombstones: vec![],
+            knowledge_base: vec![],
+            evidence: vec![],
+            event_relations: vec![],
+            last_sync: 0,
+        };
+        let result = reconcile_from(&conn, &remote, None).unwrap();
+        assert_eq!(result.delegations_added, 1);
+        assert_eq!(result.errors.len(), 20, "{:?}", result.errors);
+        let stored = storage::load_trust_delegations(&conn, None).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].delegator, trusted.public_key_hex());
+    }
+
+    #[test]
+    fn reconcile_from_accepts_revocation_after_delegator_trust_drops() {
+        let conn = storage::open_db(":memory:").unwrap();
+        let key = CryptoIdentity::new();
+        let pk = key.public_key_hex();
+        conn.execute(
+            "INSERT INTO node_ratings (node_id, events_true, events_false, validations, reused_events, trust_score, last_updated) VALUES (?1, 1, 0, 0, 0, 1.0, 100)",
+            [&pk],
+        ).unwrap();
+        let mut d = TrustDelegation::new(&pk, "target", storage::MAX_DELEGATION_DELTA, 100, None);
+        d.signature = hex::encode(key.sign(d.signing_message().as_bytes()).to_bytes());
+        let batch = |d: &TrustDelegation| SyncData {
+            events: vec![],
+            statements: vec![],
+            impacts: vec![],
+            metrics: vec![],
+            node_ratings: vec![],
+            group_ratings: vec![],
+            node_metrics: vec![],
+            trust_delegations: vec![d.clone()],
+            tombstones: vec![],
+            knowledge_base: vec![],
+            evidence: vec![],
+            event_relations: vec![],
+            last_sync: 0,
+        };
+        assert_eq!(reconcile_from(&conn, &batch(&d), None).unwrap().delegations_added, 1);
+
+        // Доверие к делегатору упало ниже порога; его отзыв всё равно применяется
+        conn.execute("UPDATE node_ratings SET trust_score = -0.5 WHERE node_id = ?1", [&pk]).unwrap();
+        let mut revoked = d.clone();
+        revoked.revoked_at = Some(200);
+        revoked.revocation_signature =
+            Some(hex::encode(key.sign(TrustDelegation::revocation_message(&d.id, 200).as_bytes()).to_bytes()));
+        let result = reconcile_from(&conn, &batch(&revoked), None).unwrap();
+        assert_eq!((result.delegations_added, result.errors.len()), (1, 0), "{:?}", result.errors);
+        assert_eq!(storage::get_trust_delegation(&conn, &d.id).unwrap().unwrap().revoked_at, Some(200));
+
+        // Неподписанный отзыв и новое делегирование от того же ключа не принимаются
+        let mut forged = revoked.clone();
+        forged.revocation_signature = Some(d.signature.clone());
+        assert_eq!(reconcile_from(&conn, &batch(&forged), None).unwrap().errors.len(), 1);
+        let mut fresh = TrustDelegation::new(&pk, "other", 0.1, 300, None);
+        fresh.signature = hex::encode(key.sign(fresh.signing_message().as_bytes()).to_bytes());
+        let result = reconcile_from(&conn, &batch(&fresh), None).unwrap();
+        assert_eq!(result.delegations_added, 0);
+        assert!(result.errors[0].contains("not a trusted node"), "{:?}", result.errors);
+    }
+
+    #[test]
+    fn reconcile_from_attaches_evidence_after_events() {
+        let mut conn = storage::open_db(":memory:").unwrap();
+        storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let key = ed25519_dalek::SigningKey::from_bytes(&[8; 32]);
+        let pk = hex::encode(key.verifying_key().as_bytes());
+        let signed = |uid: &str, url: &str| {
+            let mut e = Evidence::new("truth_events", uid, "url", None, url, &pk, 200);
+            evidence::sign(&mut e, &key);
+            e
+        };
+        let attached = signed("outage", "https://status.example/1");
+        let mut forged = signed("outage", "https://status.example/2");
+        forged.name = "https://status.example/3".into();
+
+        let remote = SyncData {
+            events: vec![TruthEvent { uid: "outage".into(), ..event(1, "outage", 150) }],
+            statements: vec![],
+            impacts: vec![],
+            metrics: vec![],
+            node_ratings: vec![],
+            group_ratings: vec![],
+            node_metrics: vec![],
+            trust_delegations: vec![],
+            tombstones: vec![],
+            knowledge_base: vec![],
+            evidence: vec![attached.clone(), signed("unknown", "https://status.example/4"), forged],
+            event_relations: vec![],
+            last_sync: 0,
+        };
+        let result = reconcile_from(&conn, &remote, None).unwrap();
+        assert_eq!((result.events_added, result.evidence_added), (1, 1));
+        assert_eq!(result.errors.len(), 1, "{:?}", result.errors);
+        assert_eq!(storage::evidence_for_event(&conn, "outage").unwrap(), vec![attached]);
+    }
+
+    #[test]
+    fn reconcile_from_keeps_statement_threads() {
+        let mut conn = storage::open_db(":memory:").unwrap();
+        storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let statement = |uid: &str, score: f32, parent: Option<&str>, relation: Option<&str>| Statement {
+            id: 1,
+            uid: uid.into(),
+            event_id: 1,
+            event_uid: "flood".into(),
+            text: "claim".into(),
+            context: None,
+            truth_score: Some(score),
+            created_at: 10,
+            updated_at: 10,
+            signature: None,
+            public_key: None,
+            parent_uid: parent.map(Into::into),
+            relation: relation.map(Into::into),
+        };
+        // Ответ приходит раньше родителя — порядок в пакете не важен
+        let remote = SyncData {
+            events: vec![TruthEvent { uid: "flood".into(), ..event(1, "flood", 150) }],
+            statements: vec![
+                statement("rebuttal", 1.0, Some("claim"), Some("rebuts")),
+                statement("claim", 0.6, None, None),
+                statement("bogus", 1.0, Some("claim"), Some("likes")),
+            ],
+            impacts: vec![],
+            metrics: vec![],
+            node_ratings: vec![],
+            group_ratings: vec![],
+            node_metrics: vec![],
+            trust_delegations: vec![],
+            tombstones: vec![],
+            knowledge_base: vec![],
+            evidence: vec![],
+            event_relations: vec![],
+            last_sync: 0,
+        };
+        let result = reconcile_from(&conn, &remote, None).unwrap();
+        assert_eq!(result.statements_added, 2);
+        assert!(storage::get_statement_by_uid(&conn, "bogus").unwrap().is_none());
+        let tree = core_lib::argument::tree_for_event(&conn, "flood").unwrap().unwrap();
+        assert_eq!(tree.roots.len(), 1);
+        assert_eq!(tree.roots[0].replies[0].statement.uid, "rebuttal");
+        assert!(tree.score.unwrap() < -0.5);
+    }
+
+    #[test]
+    fn reconcile_from_folds_remote_duplicates() {
+        let mut conn = storage::open_db(":memory:").unwrap();
+        storage::seed_knowledge_base(&mut conn, "en").unwrap();
+        let key = ed25519_dalek::SigningKey::from_bytes(&[9; 32]);
+        let pk = hex::encode(key.verifying_key().as_bytes());
+        let owned = |id: i64, uid: &str| TruthEvent { uid: uid.into(), public_key: Some(pk.clone()), ..event(id, "bridge closed", 150) };
+        let signed = |source: &str, relation: &str, target: &str| {
+            let mut rel = EventRelation::new(source, target, relation, &pk, 200);
+            event_relation::sign(&mut rel, &key);
+            rel
+        };
+        let remote = SyncData {
+            events: vec![owned(1, "bridge"), owned(2, "bridge-dup")],
+            statements: vec![Statement {
+                id: 1,
+                uid: "witness".into(),
+                event_id: 2,
+                event_uid: "bridge-dup".into(),
+                text: "saw the barrier".into(),
+                context: None,
+                truth_score: Some(0.9),
+                created_at: 10,
+                updated_at: 10,
+                signature: None,
+                public_key: None,
+                parent_uid: None,
+                relation: None,
+            }],
+            impacts: vec![],
+            metrics: vec![],
+            node_ratings: vec![],
+            group_ratings: vec![],
+            node_metrics: vec![],
+            trust_delegations: vec![],
+            tombstones: vec![],
+            knowledge_base: vec![],
+            evidence: vec![],
+            event_relations: vec![signed("bridge-dup", "duplicate_of", "bridge"), signed("bridge", "supports", "unknown")],
+            last_sync: 0,
+        };
+        let result = reconcile_from(&conn, &remote, None).unwrap();
+        assert_eq!((result.events_added, result.event_relations_added), (2, 1));
+        assert_eq!(event_relation::canonical_uid(&conn, "bridge-dup").unwrap(), "bridge");
+        let canonical = storage::get_truth_event_by_uid(&conn, "bridge").unwrap().unwrap();
+        let statement = storage::get_statement_by_uid(&conn, "witness").unwrap().unwrap();
+        assert_eq!(statement.event_id, canonical.id);
+    }
+}
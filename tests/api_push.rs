@@ -11,12 +11,11 @@ use truth_core::p2p::encryption::CryptoIdentity;
 #[actix_web::test]
 async fn push_valid_and_invalid() {
     // Init in-memory DB & app
-    let conn = core_lib::storage::open_db(":memory:").unwrap();
-    let conn_data = std::sync::Arc::new(tokio::sync::Mutex::new(conn));
+    let pool = truth_core::db::DbPool::memory().unwrap();
 
     let app = test::init_service(
         App::new()
-            .app_data(actix_web::web::Data::new(conn_data.clone()))
+            .app_data(actix_web::web::Data::new(pool.clone()))
             .configure(crate::api::routes)
     ).await;
 
@@ -33,7 +32,7 @@ async fn push_valid_and_invalid() {
     // Note: /api/v1/auth expects headers with pk/sig/timestamp; we shortcut by issuing directly with helper when available.
     // For test simplicity, just build a Bearer from api::issue_jwt_pair_with through DB.
     let token = {
-        let conn = conn_data.lock().await;
+        let conn = pool.read().unwrap();
         let (access, _refresh, _exp) = api::issue_jwt_pair_with(&conn, &id.public_key_hex()).unwrap();
         access
     };